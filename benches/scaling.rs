@@ -0,0 +1,50 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use certificate_stark::{verify_batch, ProofProfile, TransactionMetadata, TransactionProver};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::time::Duration;
+
+const SIZES: [usize; 4] = [1, 4, 16, 64];
+const TREE_DEPTHS: [usize; 3] = [8, 15, 32];
+
+fn scaling_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scaling");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+
+    let prover = TransactionProver::new(ProofProfile::default().to_proof_options());
+    for &tree_depth in TREE_DEPTHS.iter() {
+        for &size in SIZES.iter() {
+            let parameter = format!("{}tx/depth{}", size, tree_depth);
+            let tx_metadata = TransactionMetadata::build_random_with_tree_depth(size, tree_depth);
+
+            group.bench_function(BenchmarkId::new("build_trace", &parameter), |bench| {
+                bench.iter(|| prover.build_trace(&tx_metadata));
+            });
+
+            group.bench_function(BenchmarkId::new("prove", &parameter), |bench| {
+                bench.iter(|| prover.prove(&tx_metadata).unwrap());
+            });
+            let proofs = vec![prover.prove(&tx_metadata).unwrap()];
+            let pub_inputs = vec![prover.public_inputs(&tx_metadata)];
+
+            group.bench_function(BenchmarkId::new("verify", &parameter), |bench| {
+                bench.iter_batched(
+                    || (proofs.clone(), pub_inputs.clone()),
+                    |(proofs, pub_inputs)| verify_batch(proofs, pub_inputs),
+                    BatchSize::SmallInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(scaling_group, scaling_bench);
+criterion_main!(scaling_group);
@@ -20,9 +20,9 @@ fn state_transition_bench(c: &mut Criterion) {
     for &size in SIZES.iter() {
         let state_transition = get_example(size);
         group.bench_function(BenchmarkId::new("prove", size), |bench| {
-            bench.iter(|| state_transition.prove());
+            bench.iter(|| state_transition.prove().unwrap());
         });
-        let proof = state_transition.prove();
+        let proof = state_transition.prove().unwrap();
 
         group.bench_function(BenchmarkId::new("verify", size), |bench| {
             bench.iter(|| state_transition.verify(proof.clone()));
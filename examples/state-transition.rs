@@ -6,7 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use certificate_stark::TransactionExample;
+use certificate_stark::{TransactionExample, TransactionProver};
 use winterfell::{FieldExtension, HashFunction, ProofOptions};
 
 use gumdrop::Options;
@@ -81,21 +81,22 @@ fn main() {
         256,
     );
 
-    let state_transition_example = TransactionExample::new(proof_options, num_tx);
+    let state_transition_example = TransactionExample::new(proof_options.clone(), num_tx);
 
     debug!("============================================================");
 
     // generate proof
-    let now = Instant::now();
-    let proof = state_transition_example.prove();
+    let prover = TransactionProver::new(proof_options);
+    let (proof, report) = prover
+        .prove_with_report(state_transition_example.tx_metadata())
+        .unwrap();
     debug!(
         "---------------------\nProof generated in {} ms",
-        now.elapsed().as_millis()
+        (report.trace_time + report.proving_time).as_millis()
     );
 
-    let proof_bytes = proof.to_bytes();
-    debug!("Proof size: {:.1} KB", proof_bytes.len() as f64 / 1024f64);
-    debug!("Proof security: {} bits", proof.security_level(true));
+    debug!("Proof size: {:.1} KB", report.proof_size as f64 / 1024f64);
+    debug!("Proof security: {} bits", report.security_level);
 
     let now = Instant::now();
     match state_transition_example.verify(proof) {
@@ -0,0 +1,274 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::constants::schnorr_const::{AFFINE_POINT_WIDTH, POINT_COORDINATE_WIDTH};
+use crate::constants::{ACCOUNT_INDEX_DOMAIN, NULLIFIER_DOMAIN};
+use crate::merkle::{constants::MAX_MERKLE_TREE_DEPTH, hash_leaf_with_hasher, TreeHasher};
+use crate::schnorr::musig::{self, NUM_SIGNERS};
+use crate::schnorr::threshold::KeyTree;
+use crate::utils::rescue::{self, Hash, STATE_WIDTH};
+use winterfell::{
+    math::{curves::curve_f63::AffinePoint, fields::f63::BaseElement, FieldElement, StarkField},
+    ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable,
+};
+
+// ACCOUNT KIND
+// ================================================================================================
+
+/// Flag set in the nonce of accounts controlled by a key aggregated from the keys of several
+/// signers.
+// The key, balance and nonce of an account fill the whole hash state of its leaf, leaving no
+// element for the flag, which is thus carried by a high bit of the nonce. As the AIR programs
+// only ever increment the nonce by one, the flag is preserved by all transactions, including
+// the rekeyings of multisig accounts to a single key. The nonces of single-key accounts are
+// kept below the flag, both when creating them and when incrementing them, so that they are
+// never reported as multisig.
+pub const MULTISIG_NONCE_FLAG: u64 = 1 << 62;
+
+/// The kind of an account, as flagged in its leaf.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountKind {
+    /// An account controlled by a single key
+    Single,
+    /// An account controlled by a key aggregated from the keys of `NUM_SIGNERS` signers,
+    /// which must all take part in signing its transactions with `schnorr::musig`
+    Multisig,
+}
+
+// ACCOUNT STATE
+// ================================================================================================
+
+/// The state of an account, stored in a leaf of the Merkle tree of accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountState {
+    /// The public key of the account owner
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::point"))]
+    pub pub_key: AffinePoint,
+    /// The account balance
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub balance: BaseElement,
+    /// The account nonce, incremented by each transaction sent from the account and signed
+    /// as part of its message, thus preventing signatures from being replayed. Its bit
+    /// `MULTISIG_NONCE_FLAG` flags accounts controlled by an aggregated key
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub nonce: BaseElement,
+}
+
+impl AccountState {
+    /// Returns a new account state from the provided public key, balance and nonce.
+    ///
+    /// # Panics
+    /// Panics if `nonce` is not below `MULTISIG_NONCE_FLAG`, which only
+    /// `AccountState::new_multisig` sets.
+    pub fn new(pub_key: AffinePoint, balance: BaseElement, nonce: BaseElement) -> Self {
+        assert!(
+            nonce.to_repr() < MULTISIG_NONCE_FLAG,
+            "nonce must be below the multisig flag"
+        );
+        AccountState {
+            pub_key,
+            balance,
+            nonce,
+        }
+    }
+
+    /// Returns a new state of an account controlled together by the owners of `keys`, holding
+    /// their aggregated key and flagged as `AccountKind::Multisig`, with a zero nonce
+    /// otherwise.
+    pub fn new_multisig(keys: &[AffinePoint; NUM_SIGNERS], balance: BaseElement) -> Self {
+        AccountState {
+            pub_key: musig::aggregate_keys(keys),
+            balance,
+            nonce: BaseElement::new(MULTISIG_NONCE_FLAG),
+        }
+    }
+
+    /// Returns a new state of an account controlled by a committee, i.e. by any `threshold`
+    /// members out of the ones whose keys are committed in `tree`, with a zero nonce.
+    ///
+    /// The account holds a pseudo-key rather than a key, made of the root of `tree` followed
+    /// by `threshold` and zeros, which signs no transaction. Its balance is only debited by
+    /// committee transfers, signed by `threshold` distinct members.
+    ///
+    /// # Panics
+    /// Panics if `threshold` is zero or greater than the number of keys in `tree`.
+    pub fn new_committee(tree: &KeyTree, threshold: usize, balance: BaseElement) -> Self {
+        assert!(
+            threshold > 0 && threshold <= tree.keys().len(),
+            "threshold must be between 1 and the number of members"
+        );
+        let mut pkey_coords = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        pkey_coords[..rescue::RATE_WIDTH].copy_from_slice(&tree.root().to_elements());
+        pkey_coords[rescue::RATE_WIDTH] = BaseElement::new(threshold as u64);
+
+        AccountState {
+            pub_key: AffinePoint::from_raw_coordinates(pkey_coords),
+            balance,
+            nonce: BaseElement::ZERO,
+        }
+    }
+
+    /// Returns the root of the key tree and the threshold of the committee controlling this
+    /// account, if its key is the pseudo-key of a committee as set by
+    /// `AccountState::new_committee`.
+    pub fn committee(&self) -> Option<(Hash, usize)> {
+        let elements = self.pub_key_elements();
+        if elements[rescue::RATE_WIDTH + 1..]
+            .iter()
+            .any(|&element| element != BaseElement::ZERO)
+        {
+            return None;
+        }
+
+        Some((
+            Hash::new(
+                elements[0],
+                elements[1],
+                elements[2],
+                elements[3],
+                elements[4],
+                elements[5],
+                elements[6],
+            ),
+            elements[rescue::RATE_WIDTH].to_repr() as usize,
+        ))
+    }
+
+    /// Returns the kind of this account, as flagged in its nonce.
+    ///
+    /// The kind is advisory only: it is set once when creating the account, and never
+    /// enforced by the AIR programs, which verify signatures of whichever key is stored in the
+    /// leaf. In particular, an account rekeyed with `TransactionKind::Rekey` keeps its kind,
+    /// even though its new key may be held by a single signer.
+    pub fn kind(&self) -> AccountKind {
+        if self.nonce.to_repr() & MULTISIG_NONCE_FLAG != 0 {
+            AccountKind::Multisig
+        } else {
+            AccountKind::Single
+        }
+    }
+
+    /// Returns whether the nonce of this account can be incremented by one more transaction,
+    /// i.e. without reaching `MULTISIG_NONCE_FLAG` for single-key accounts, nor wrapping
+    /// around the field modulus for multisig ones.
+    pub fn can_increment_nonce(&self) -> bool {
+        let limit = match self.kind() {
+            AccountKind::Single => MULTISIG_NONCE_FLAG,
+            AccountKind::Multisig => BaseElement::MODULUS,
+        };
+        self.nonce.to_repr() + 1 < limit
+    }
+
+    /// Returns the affine coordinates of the account public key, x coordinate first.
+    pub fn pub_key_elements(&self) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        elements[0..POINT_COORDINATE_WIDTH].copy_from_slice(&self.pub_key.get_x());
+        elements[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&self.pub_key.get_y());
+
+        elements
+    }
+
+    /// Returns the elements representing this account within the AIR programs, i.e.
+    /// the affine coordinates of its public key followed by its balance and nonce.
+    pub fn to_elements(&self) -> [BaseElement; AFFINE_POINT_WIDTH + 2] {
+        let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
+        elements[0..AFFINE_POINT_WIDTH].copy_from_slice(&self.pub_key_elements());
+        elements[AFFINE_POINT_WIDTH] = self.balance;
+        elements[AFFINE_POINT_WIDTH + 1] = self.nonce;
+
+        elements
+    }
+
+    /// Returns the account state represented by the provided elements, as output by
+    /// `AccountState::to_elements`.
+    pub fn from_elements(elements: &[BaseElement; AFFINE_POINT_WIDTH + 2]) -> Self {
+        let mut pkey_coords = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        pkey_coords.copy_from_slice(&elements[0..AFFINE_POINT_WIDTH]);
+
+        AccountState {
+            pub_key: AffinePoint::from_raw_coordinates(pkey_coords),
+            balance: elements[AFFINE_POINT_WIDTH],
+            nonce: elements[AFFINE_POINT_WIDTH + 1],
+        }
+    }
+
+    /// Returns the nullifier of the next transaction sent from this account, i.e. the hash of
+    /// its public key and nonce. As this nonce is consumed by the transaction, the nullifier
+    /// identifies it once and for all, and exposes any double-submission of it.
+    pub fn nullifier(&self) -> Hash {
+        let mut state = [BaseElement::ZERO; STATE_WIDTH];
+        state[..AFFINE_POINT_WIDTH].copy_from_slice(&self.pub_key_elements());
+        state[AFFINE_POINT_WIDTH] = self.nonce;
+        state[STATE_WIDTH - 1] = NULLIFIER_DOMAIN;
+        rescue::apply_permutation(&mut state);
+
+        Hash::new(
+            state[0], state[1], state[2], state[3], state[4], state[5], state[6],
+        )
+    }
+
+    /// Returns the Merkle tree leaf of this account, hashed with the provided tree hasher.
+    pub fn to_leaf<H: TreeHasher>(&self) -> Hash {
+        hash_leaf_with_hasher::<H>(&self.to_elements())
+    }
+
+    /// Returns the index of the leaf of this account in a tree of the provided depth, as
+    /// derived from its public key by `account_index`.
+    pub fn derived_index(&self, depth: usize) -> usize {
+        account_index(&self.pub_key, depth)
+    }
+}
+
+// ACCOUNT ADDRESSING
+// ================================================================================================
+
+/// Returns the index of the leaf of the account owned by `pub_key` in a tree of the provided
+/// depth, i.e. the `depth` low bits of the hash of its coordinates.
+///
+/// Deriving indices from public keys lets anyone locate an account from its owner's key alone,
+/// and prevents an operator from placing a key at an arbitrary leaf. Distinct keys may still
+/// collide on the same leaf, with a probability growing with the number of accounts relative
+/// to the `2^depth` leaves of the tree.
+///
+/// # Panics
+/// Panics if `depth` is greater than `MAX_MERKLE_TREE_DEPTH`.
+pub fn account_index(pub_key: &AffinePoint, depth: usize) -> usize {
+    assert!(
+        depth <= MAX_MERKLE_TREE_DEPTH,
+        "tree depth must be at most {}",
+        MAX_MERKLE_TREE_DEPTH
+    );
+    let mut state = [BaseElement::ZERO; STATE_WIDTH];
+    state[..POINT_COORDINATE_WIDTH].copy_from_slice(&pub_key.get_x());
+    state[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&pub_key.get_y());
+    state[STATE_WIDTH - 1] = ACCOUNT_INDEX_DOMAIN;
+    rescue::apply_permutation(&mut state);
+
+    (state[0].to_repr() & ((1 << depth) - 1)) as usize
+}
+
+impl Serializable for AccountState {
+    /// Serializes `self` and writes the resulting bytes into the `target` writer.
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write(&self.to_elements()[..]);
+    }
+}
+
+impl Deserializable for AccountState {
+    /// Reads an `AccountState` object from the specified `source` reader.
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
+        elements.copy_from_slice(&BaseElement::read_batch_from(
+            source,
+            AFFINE_POINT_WIDTH + 2,
+        )?);
+
+        Ok(AccountState::from_elements(&elements))
+    }
+}
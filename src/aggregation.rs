@@ -0,0 +1,431 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::constants::merkle_const::HASH_RATE_WIDTH;
+use super::range::RANGE_LOG;
+use super::utils::rescue::{Hash, Rescue63};
+use super::{
+    verify_with_hasher, BatchStatistics, Deposit, Memo, PublicInputs, TransactionKind,
+    TransactionMetadata, Withdrawal,
+};
+use core::{fmt, ops::Range};
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    StarkProof, VerifierError,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// AGGREGATION ERROR
+// ================================================================================================
+
+/// Errors raised when building or verifying an `AggregatedProof`
+#[derive(Debug, PartialEq)]
+pub enum AggregationError {
+    /// The initial root of the proof at the given position does not match
+    /// the final root of the previous one
+    RootMismatch(usize),
+    /// The tree depth of the proof at the given position does not match
+    /// the one of the aggregated proof
+    TreeDepthMismatch(usize),
+    /// The fee collector of the proof at the given position does not match
+    /// the one of the aggregated proof
+    FeeCollectorMismatch(usize),
+    /// The range log of the proof at the given position does not match
+    /// the one of the aggregated proof
+    RangeLogMismatch(usize),
+    /// The proof at the given position does not support refunds of negative amounts if and
+    /// only if the aggregated proof does
+    SignedDeltasMismatch(usize),
+    /// The subnet identifier of the proof at the given position does not match
+    /// the one of the aggregated proof
+    SubnetMismatch(usize),
+    /// The epoch of the proof at the given position is prior to the one of the
+    /// previous proof
+    EpochRegression(usize),
+    /// The aggregated proof does not move the tree from the expected initial root
+    /// to the expected final root
+    UnexpectedRoots,
+    /// The proof at the given position failed to verify
+    InvalidProof(usize, VerifierError),
+}
+
+impl fmt::Display for AggregationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AggregationError::RootMismatch(index) => write!(
+                f,
+                "initial root of proof {} does not match the final root of proof {}",
+                index,
+                index - 1
+            ),
+            AggregationError::TreeDepthMismatch(index) => {
+                write!(f, "tree depth of proof {} does not match", index)
+            }
+            AggregationError::FeeCollectorMismatch(index) => {
+                write!(f, "fee collector of proof {} does not match", index)
+            }
+            AggregationError::RangeLogMismatch(index) => {
+                write!(f, "range log of proof {} does not match", index)
+            }
+            AggregationError::SignedDeltasMismatch(index) => {
+                write!(f, "signed deltas of proof {} do not match", index)
+            }
+            AggregationError::SubnetMismatch(index) => {
+                write!(f, "subnet identifier of proof {} does not match", index)
+            }
+            AggregationError::EpochRegression(index) => write!(
+                f,
+                "epoch of proof {} is prior to the epoch of proof {}",
+                index,
+                index - 1
+            ),
+            AggregationError::UnexpectedRoots => {
+                write!(f, "aggregated proof does not match the expected roots")
+            }
+            AggregationError::InvalidProof(index, err) => {
+                write!(f, "proof {} failed to verify: {}", index, err)
+            }
+        }
+    }
+}
+
+// AGGREGATED PROOF
+// ================================================================================================
+
+/// A chain of state-transition proofs, where the final root of each proof
+/// is the initial root of the next one.
+///
+/// As a whole, it attests to a valid state-transition from the initial root
+/// of the first proof to the final root of the last one.
+#[derive(Clone, Debug)]
+pub struct AggregatedProof {
+    tree_depth: usize,
+    fee_collector: usize,
+    range_log: usize,
+    signed_deltas: bool,
+    subnet_id: BaseElement,
+    roots: Vec<Hash>,
+    withdrawals: Vec<Vec<Withdrawal>>,
+    deposits: Vec<Vec<Deposit>>,
+    memos: Vec<Vec<Memo>>,
+    epochs: Vec<u32>,
+    intermediate_roots: Vec<Option<Vec<[BaseElement; HASH_RATE_WIDTH]>>>,
+    transactions_digests: Vec<Option<[BaseElement; HASH_RATE_WIDTH]>>,
+    nullifiers_commitments: Vec<Option<[BaseElement; HASH_RATE_WIDTH]>>,
+    batch_statistics: Vec<Option<BatchStatistics>>,
+    proofs: Vec<StarkProof>,
+}
+
+impl AggregatedProof {
+    /// Returns a new empty `AggregatedProof`, starting from `initial_root` in
+    /// a Merkle tree of depth `tree_depth`, with fees credited to the leaf at
+    /// index `fee_collector`.
+    pub fn new(initial_root: Hash, tree_depth: usize, fee_collector: usize) -> Self {
+        AggregatedProof {
+            tree_depth,
+            fee_collector,
+            range_log: RANGE_LOG,
+            signed_deltas: false,
+            subnet_id: BaseElement::ZERO,
+            roots: vec![initial_root],
+            withdrawals: Vec::new(),
+            deposits: Vec::new(),
+            memos: Vec::new(),
+            epochs: Vec::new(),
+            intermediate_roots: Vec::new(),
+            transactions_digests: Vec::new(),
+            nullifiers_commitments: Vec::new(),
+            batch_statistics: Vec::new(),
+            proofs: Vec::new(),
+        }
+    }
+
+    /// Returns this aggregated proof, expecting proofs with range checks over `range_log`
+    /// bits instead of the default `RANGE_LOG`.
+    pub fn with_range_log(mut self, range_log: usize) -> Self {
+        self.range_log = range_log;
+        self
+    }
+
+    /// Returns this aggregated proof, expecting proofs supporting refunds of negative amounts.
+    pub fn with_signed_deltas(mut self) -> Self {
+        self.signed_deltas = true;
+        self
+    }
+
+    /// Returns this aggregated proof, expecting proofs of transactions of the subnet
+    /// identified by `subnet_id` instead of the default subnet zero.
+    pub fn with_subnet_id(mut self, subnet_id: BaseElement) -> Self {
+        self.subnet_id = subnet_id;
+        self
+    }
+
+    /// Appends a proof to this aggregated proof.
+    ///
+    /// Returns an error if the proof does not start from the current final root
+    /// of the chain, if it was generated for a different tree depth, fee collector,
+    /// range log, signedness of deltas or subnet, or if its epoch is prior to the one of the
+    /// previous proof.
+    pub fn push(
+        &mut self,
+        proof: StarkProof,
+        pub_inputs: &PublicInputs,
+    ) -> Result<(), AggregationError> {
+        let index = self.proofs.len();
+        if pub_inputs.tree_depth != self.tree_depth {
+            return Err(AggregationError::TreeDepthMismatch(index));
+        }
+        if pub_inputs.fee_collector != self.fee_collector {
+            return Err(AggregationError::FeeCollectorMismatch(index));
+        }
+        if pub_inputs.range_log != self.range_log {
+            return Err(AggregationError::RangeLogMismatch(index));
+        }
+        if pub_inputs.signed_deltas != self.signed_deltas {
+            return Err(AggregationError::SignedDeltasMismatch(index));
+        }
+        if pub_inputs.subnet_id != self.subnet_id {
+            return Err(AggregationError::SubnetMismatch(index));
+        }
+        if matches!(self.epochs.last(), Some(&epoch) if pub_inputs.epoch < epoch) {
+            return Err(AggregationError::EpochRegression(index));
+        }
+        if pub_inputs.initial_root != self.final_root().to_elements() {
+            return Err(AggregationError::RootMismatch(index));
+        }
+
+        let root = pub_inputs.final_root;
+        self.roots.push(Hash::new(
+            root[0], root[1], root[2], root[3], root[4], root[5], root[6],
+        ));
+        self.withdrawals.push(pub_inputs.withdrawals.clone());
+        self.deposits.push(pub_inputs.deposits.clone());
+        self.memos.push(pub_inputs.memos.clone());
+        self.epochs.push(pub_inputs.epoch);
+        self.intermediate_roots
+            .push(pub_inputs.intermediate_roots.clone());
+        self.transactions_digests
+            .push(pub_inputs.transactions_digest);
+        self.nullifiers_commitments
+            .push(pub_inputs.nullifiers_commitment);
+        self.batch_statistics.push(pub_inputs.batch_statistics);
+        self.proofs.push(proof);
+
+        Ok(())
+    }
+
+    /// Returns the number of proofs in this aggregated proof.
+    pub fn num_proofs(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Returns the depth of the Merkle tree.
+    pub fn tree_depth(&self) -> usize {
+        self.tree_depth
+    }
+
+    /// Returns the index of the fee collector leaf.
+    pub fn fee_collector(&self) -> usize {
+        self.fee_collector
+    }
+
+    /// Returns the bit length of the range proofs of the underlying proofs.
+    pub fn range_log(&self) -> usize {
+        self.range_log
+    }
+
+    /// Returns whether the underlying proofs support refunds of negative amounts.
+    pub fn has_signed_deltas(&self) -> bool {
+        self.signed_deltas
+    }
+
+    /// Returns the identifier of the subnet of the underlying proofs.
+    pub fn subnet_id(&self) -> BaseElement {
+        self.subnet_id
+    }
+
+    /// Returns the epoch of the transactions of the proof at position `index`.
+    pub fn epoch(&self, index: usize) -> u32 {
+        self.epochs[index]
+    }
+
+    /// Returns the withdrawals exposed by the proof at position `index`.
+    pub fn withdrawals(&self, index: usize) -> &[Withdrawal] {
+        &self.withdrawals[index]
+    }
+
+    /// Returns the deposits exposed by the proof at position `index`.
+    pub fn deposits(&self, index: usize) -> &[Deposit] {
+        &self.deposits[index]
+    }
+
+    /// Returns the memos exposed by the proof at position `index`.
+    pub fn memos(&self, index: usize) -> &[Memo] {
+        &self.memos[index]
+    }
+
+    /// Returns the Merkle tree roots in between the transactions of the proof at position
+    /// `index`, if exposed by this proof.
+    pub fn intermediate_roots(&self, index: usize) -> Option<&[[BaseElement; HASH_RATE_WIDTH]]> {
+        self.intermediate_roots[index].as_deref()
+    }
+
+    /// Returns the running digest of the transactions of the proof at position `index`, if
+    /// exposed by this proof.
+    pub fn transactions_digest(&self, index: usize) -> Option<[BaseElement; HASH_RATE_WIDTH]> {
+        self.transactions_digests[index]
+    }
+
+    /// Returns the commitment to the nullifiers of the transactions of the proof at position
+    /// `index`, if exposed by this proof.
+    pub fn nullifiers_commitment(&self, index: usize) -> Option<[BaseElement; HASH_RATE_WIDTH]> {
+        self.nullifiers_commitments[index]
+    }
+
+    /// Returns the number and volume of the transactions of the proof at position `index`, if
+    /// exposed by this proof.
+    pub fn batch_statistics(&self, index: usize) -> Option<BatchStatistics> {
+        self.batch_statistics[index]
+    }
+
+    /// Returns the initial root of the aggregated state-transition.
+    pub fn initial_root(&self) -> Hash {
+        self.roots[0]
+    }
+
+    /// Returns the final root of the aggregated state-transition.
+    pub fn final_root(&self) -> Hash {
+        self.roots[self.roots.len() - 1]
+    }
+
+    /// Verifies that this aggregated proof attests to a valid state-transition
+    /// from `initial_root` to `final_root`, by checking every underlying proof
+    /// against its linked roots.
+    pub fn verify(&self, initial_root: Hash, final_root: Hash) -> Result<(), AggregationError> {
+        if self.initial_root() != initial_root || self.final_root() != final_root {
+            return Err(AggregationError::UnexpectedRoots);
+        }
+
+        for (index, proof) in self.proofs.iter().enumerate() {
+            let pub_inputs = PublicInputs {
+                intermediate_roots: self.intermediate_roots[index].clone(),
+                transactions_digest: self.transactions_digests[index],
+                nullifiers_commitment: self.nullifiers_commitments[index],
+                batch_statistics: self.batch_statistics[index],
+                signed_deltas: self.signed_deltas,
+                ..PublicInputs::new(
+                    self.roots[index],
+                    self.roots[index + 1],
+                    self.tree_depth,
+                    self.fee_collector,
+                    self.withdrawals[index].clone(),
+                    self.deposits[index].clone(),
+                )
+                .with_memos(self.memos[index].clone())
+                .with_range_log(self.range_log)
+                .with_subnet_id(self.subnet_id)
+                .with_epoch(self.epochs[index])
+            };
+            verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs)
+                .map_err(|err| AggregationError::InvalidProof(index, err))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A chain of proofs of consecutive sub-batches of a batch of transactions, as generated by
+/// `TransactionProver::prove_chain`. Sub-batches obtained with
+/// `TransactionMetadata::sub_batches` can also be proven independently, possibly on different
+/// machines, and their proofs pushed in order to a chain starting from the initial root of the
+/// batch.
+pub type ProofChain = AggregatedProof;
+
+// SUB-BATCHES
+// ================================================================================================
+
+impl TransactionMetadata {
+    /// Splits these transactions into consecutive sub-batches of at most `max_batch_size`
+    /// transactions, each of which can be proven on its own, the final root of each
+    /// sub-batch being the initial root of the next one.
+    ///
+    /// Outputs of a split transfer are never separated: a sub-batch ends before the first
+    /// output of a split transfer it cannot hold entirely, or after its last output if it
+    /// would otherwise be empty. Likewise, committee transfers are never separated from their
+    /// approvals.
+    ///
+    /// # Panics
+    /// Panics if `max_batch_size` is zero.
+    pub fn sub_batches(&self, max_batch_size: usize) -> Vec<TransactionMetadata> {
+        assert!(max_batch_size > 0, "sub-batches must hold transactions");
+        let num_transactions = self.num_transactions();
+        let continues_transfer = |index: usize| {
+            index < num_transactions
+                && match self.kinds[index] {
+                    TransactionKind::Split { position, .. } => position > 0,
+                    kind => kind.is_approval(),
+                }
+        };
+
+        let mut sub_batches = Vec::new();
+        let mut start = 0;
+        while start < num_transactions {
+            let mut end = core::cmp::min(start + max_batch_size, num_transactions);
+            while end > start && continues_transfer(end) {
+                end -= 1;
+            }
+            if end == start {
+                end = start + 1;
+                while continues_transfer(end) {
+                    end += 1;
+                }
+            }
+            sub_batches.push(self.sub_batch(start..end));
+            start = end;
+        }
+
+        sub_batches
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the transactions within `range`, starting from the tree root prior to the
+    /// first of them.
+    fn sub_batch(&self, range: Range<usize>) -> TransactionMetadata {
+        let final_root = match self.initial_roots.get(range.end) {
+            Some(root) => *root,
+            None => self.final_root,
+        };
+
+        TransactionMetadata {
+            tree_depth: self.tree_depth,
+            initial_roots: self.initial_roots[range.clone()].to_vec(),
+            final_root,
+            s_old_values: self.s_old_values[range.clone()].to_vec(),
+            r_old_values: self.r_old_values[range.clone()].to_vec(),
+            c_old_values: self.c_old_values[range.clone()].to_vec(),
+            s_indices: self.s_indices[range.clone()].to_vec(),
+            r_indices: self.r_indices[range.clone()].to_vec(),
+            fee_collector: self.fee_collector,
+            s_paths: self.s_paths[range.clone()].to_vec(),
+            r_paths: self.r_paths[range.clone()].to_vec(),
+            c_paths: self.c_paths[range.clone()].to_vec(),
+            deltas: self.deltas[range.clone()].to_vec(),
+            fees: self.fees[range.clone()].to_vec(),
+            kinds: self.kinds[range.clone()].to_vec(),
+            signatures: self.signatures[range.clone()].to_vec(),
+            memos: self.memos[range.clone()].to_vec(),
+            shares: self.shares[range].to_vec(),
+            subnet_id: self.subnet_id,
+            epoch: self.epoch,
+        }
+    }
+}
@@ -6,104 +6,1037 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use super::constants::merkle_const::transaction_hash_length;
 use super::constants::merkle_const::{
-    BALANCE_CONSTRAINT_RES, HASH_RATE_WIDTH, HASH_STATE_WIDTH, INT_ROOT_EQUALITY_RES,
+    BALANCE_CONSTRAINT_RES, COLLECTOR_BIT_POS, COLLECTOR_INITIAL_POS, COLLECTOR_UPDATED_POS,
+    HASH_RATE_WIDTH, HASH_STATE_WIDTH, LEAF_DOMAIN, MAX_MERKLE_TREE_DEPTH,
     NONCE_UPDATE_CONSTRAINT_RES, PREV_TREE_ROOT_POS, RECEIVER_BIT_POS, RECEIVER_INITIAL_POS,
-    RECEIVER_UPDATED_POS, SENDER_INITIAL_POS, SENDER_UPDATED_POS,
-    TRANSACTION_CYCLE_LENGTH as MERKLE_UPDATE_LENGTH, TRANSACTION_HASH_LENGTH,
-    VALUE_CONSTRAINT_RES,
+    RECEIVER_UPDATED_POS, SENDER_BIT_POS, SENDER_INITIAL_POS, SENDER_UPDATED_POS,
+    TRANSACTION_CYCLE_LENGTH as MERKLE_UPDATE_LENGTH, VALUE_CONSTRAINT_RES,
 };
-use super::constants::range_const::RANGE_LOG;
+use super::constants::range_const::{MAX_RANGE_LOG, MAX_SIGNED_RANGE_LOG, RANGE_LOG};
 use super::constants::rescue_const::HASH_CYCLE_LENGTH;
 use super::constants::schnorr_const::{
-    AFFINE_POINT_WIDTH, PROJECTIVE_POINT_WIDTH, SIG_CYCLE_LENGTH,
+    ADDEND_POS, AFFINE_POINT_WIDTH, CHALLENGE_DOMAIN, HASH_STATE_POS as SCHNORR_HASH_STATE_POS,
+    POINT_COORDINATE_WIDTH, PROJECTIVE_POINT_WIDTH, SIG_CYCLE_LENGTH, TOTAL_HASH_LENGTH,
 };
 use super::constants::{
-    ARK_INDEX, DELTA_ACCUMULATE_POS, DELTA_BIT_POS, DELTA_COPY_POS, DELTA_COPY_RES,
-    DELTA_RANGE_RES, DOUBLING_MASK_INDEX, FINISH_MASK_INDEX, HASH_INPUT_MASK_INDEX,
-    HASH_INTERNAL_INPUT_MASKS_INDEX, HASH_MASK_INDEX, MERKLE_MASK_INDEX, NONCE_COPY_POS,
-    NONCE_COPY_RES, RANGE_PROOF_FINISH_MASK_INDEX, RANGE_PROOF_STEP_MASK_INDEX,
-    RECEIVER_KEY_POINT_POS, RECEIVER_KEY_POINT_RES, SCALAR_MULT_MASK_INDEX,
-    SCHNORR_DIGEST_MASK_INDEX, SCHNORR_HASH_MASK_INDEX, SCHNORR_MASK_INDEX, SCHNORR_REGISTER_WIDTH,
-    SENDER_KEY_POINT_POS, SENDER_KEY_POINT_RES, SETUP_MASK_INDEX, SIGMA_ACCUMULATE_POS,
-    SIGMA_BIT_POS, SIGMA_COPY_POS, SIGMA_COPY_RES, SIGMA_RANGE_RES, TRACE_WIDTH,
-    TRANSACTION_CYCLE_LENGTH, VALUE_COPY_MASK_INDEX,
+    ACCOUNT_INDEX_DOMAIN, APPROVALS_LEFT_POS, APPROVALS_LEFT_RES, APPROVAL_COPY_RES,
+    APPROVAL_FLAG_POS, ARK_INDEX, COMMITTEE_FLAG_POS, COMMITTEE_RES, COMMITTEE_ROOT_POS,
+    COMMITTEE_ROOT_RES, DELTA_ACCUMULATE_POS, DELTA_BIT_POS, DELTA_COPY_POS, DELTA_COPY_RES,
+    DELTA_RANGE_RES, DELTA_SIGN_POS, DELTA_SIGN_RES, DEPOSIT_FLAG_POS, DEPOSIT_FLAG_RES,
+    DOUBLING_MASK_INDEX, EPOCH_MESSAGE_INDEX, FEE_ACCUMULATE_POS, FEE_BIT_POS, FEE_COPY_POS,
+    FEE_COPY_RES, FEE_RANGE_RES, FINISH_MASK_INDEX, HASH_INPUT_MASK_INDEX,
+    HASH_INTERNAL_INPUT_MASKS_INDEX, HASH_MASK_INDEX, INDEX_WEIGHTS_INDEX, KEY_COPY_MASK_INDEX,
+    KEY_INDEX_BOUND_INDEX, KEY_INDEX_CHECK_MASK_INDEX, KEY_INDEX_FINISH_MASK_INDEX,
+    KEY_INDEX_HASH_POS, KEY_INDEX_HASH_RES, KEY_INDEX_NUM_BITS, KEY_INDEX_SETUP_MASK_INDEX,
+    KEY_INDEX_STEP_MASK_INDEX, KEY_INDEX_WEIGHTS_INDEX, KEY_PATH_POS, KEY_PATH_RES,
+    KEY_TREE_ROOT_RES, MAX_SPLIT_OUTPUTS, MEMBER_GAP_ACCUMULATE_POS, MEMBER_GAP_BIT_POS,
+    MEMBER_GAP_RES, MEMBER_INDEX_POS, MEMBER_INDEX_RES, MEMO_COPY_RES, MEMO_POS, MEMO_WIDTH,
+    MERKLE_MASK_INDEX, NONCE_COPY_POS, NONCE_COPY_RES, NOOP_FLAG_POS, NOOP_RES,
+    NULLIFIERS_FINISH_MASK_INDEX, NULLIFIERS_POS, NULLIFIERS_RES, NULLIFIER_DOMAIN,
+    NUM_DELTA_SIGN_CONSTRAINTS, NUM_DERIVED_INDEX_CONSTRAINTS, NUM_KEY_INDEX_CONSTRAINTS,
+    NUM_NOOP_CONSTRAINTS, NUM_REKEY_CONSTRAINTS, NUM_SHARED_PATH_CONSTRAINTS,
+    NUM_SPLIT_CHAIN_CONSTRAINTS, PREVIOUS_MEMBER_INDEX_POS, RANGE_PROOF_FINISH_MASK_INDEX,
+    RANGE_PROOF_STEP_MASK_INDEX, RECEIVER_BALANCE_ACCUMULATE_POS, RECEIVER_BALANCE_BIT_POS,
+    RECEIVER_BALANCE_COPY_POS, RECEIVER_BALANCE_COPY_RES, RECEIVER_BALANCE_RANGE_RES,
+    RECEIVER_INDEX_MESSAGE_INDEX, RECEIVER_INDEX_POS, RECEIVER_INDEX_RES,
+    RECEIVER_KEY_INDEX_ACCUMULATE_POS, RECEIVER_KEY_INDEX_BIT_POS, RECEIVER_KEY_INDEX_BOUND_POS,
+    RECEIVER_KEY_INDEX_RES, RECEIVER_KEY_POINT_POS, RECEIVER_KEY_POINT_RES, REKEY_FLAG_POS,
+    REKEY_RES, SCALAR_MULT_MASK_INDEX, SCHNORR_DIGEST_MASK_INDEX, SCHNORR_HASH_HOLD_MASK_INDEX,
+    SCHNORR_HASH_MASK_INDEX, SCHNORR_MASK_INDEX, SCHNORR_REGISTER_WIDTH,
+    SENDER_INDEX_MESSAGE_INDEX, SENDER_INDEX_POS, SENDER_INDEX_RES,
+    SENDER_KEY_INDEX_ACCUMULATE_POS, SENDER_KEY_INDEX_BIT_POS, SENDER_KEY_INDEX_BOUND_POS,
+    SENDER_KEY_INDEX_RES, SENDER_KEY_POINT_POS, SENDER_KEY_POINT_RES, SETUP_MASK_INDEX,
+    SHARED_PATH_FLAG_POS, SHARED_PATH_RES, SIGMA_ACCUMULATE_POS, SIGMA_BIT_POS, SIGMA_COPY_POS,
+    SIGMA_COPY_RES, SIGMA_RANGE_RES, SIGNING_KEY_INVERSE_POS, SIGNING_KEY_INVERSE_RES,
+    SPLIT_AMOUNT_POS, SPLIT_CHAIN_RES, SPLIT_FLAG_POS, SPLIT_POSITION_POS, SPLIT_REGISTERS_RES,
+    SPLIT_REGISTER_WIDTH, SPLIT_REMAINING_POS, SPLIT_SENT_POS, SUBNET_ID_MESSAGE_INDEX,
+    TRACE_WIDTH, TRANSACTION_CYCLE_LENGTH, TREE_ARK_INDEX, TX_COUNT_POS, TX_COUNT_RES,
+    TX_DIGEST_HASH_POS, TX_DIGEST_HASH_RES, TX_DIGEST_POS, TX_DIGEST_RES,
+    TX_DIGEST_SETUP_MASK_INDEX, TX_TUPLE_NUM_CHUNKS, TX_TUPLE_WIDTH, TX_VOLUME_POS, TX_VOLUME_RES,
+    VALUE_COPY_MASK_INDEX, WITHDRAWAL_FLAG_POS, WITHDRAWAL_FLAG_RES,
 };
-use super::merkle;
+use super::merkle::{self, TreeHasher};
+use super::range;
 use super::schnorr;
 //use super::schnorr::constants::SCALAR_MUL_LENGTH;
-use super::utils::{
-    field::enforce_double_and_add_step,
-    periodic_columns::{fill, pad, stitch},
+use super::periodic::{PeriodicColumnSet, Segment};
+use super::utils::{bits, boundary};
+use crate::utils::{
+    are_equal, is_binary, not,
+    rescue::{self, Hash, Rescue63},
+    EvaluationResult,
 };
-use crate::utils::{are_equal, not, EvaluationResult};
+use core::marker::PhantomData;
 use winterfell::{
-    math::{fields::f63::BaseElement, FieldElement},
-    Air, AirContext, Assertion, ByteWriter, EvaluationFrame, ProofOptions, Serializable, TraceInfo,
-    TransitionConstraintDegree,
+    crypto::Hasher,
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
+    Air, AirContext, Assertion, ByteReader, ByteWriter, Deserializable, DeserializationError,
+    EvaluationFrame, ProofOptions, Serializable, SliceReader, TraceInfo,
+    TransitionConstraintDegree, VerifierError,
 };
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
 // MERKLE PATH VERIFICATION AIR
 // ================================================================================================
 
+/// Public inputs of a state-transition proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PublicInputs {
+    /// Merkle tree root prior to applying the transactions
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::elements"))]
     pub initial_root: [BaseElement; HASH_RATE_WIDTH],
+    /// Merkle tree root after applying all the transactions
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::elements"))]
     pub final_root: [BaseElement; HASH_RATE_WIDTH],
+    /// Depth of the Merkle tree
+    pub tree_depth: usize,
+    /// Index of the leaf collecting the transaction fees
+    pub fee_collector: usize,
+    /// Withdrawals performed by the transactions, ordered by transaction index
+    pub withdrawals: Vec<Withdrawal>,
+    /// Deposits performed by the transactions, ordered by transaction index
+    pub deposits: Vec<Deposit>,
+    /// Memos attached to the transactions, ordered by transaction index
+    pub memos: Vec<Memo>,
+    /// Bit length of the range proofs on amounts, fees and balances
+    pub range_log: usize,
+    /// Identifier of the hash function of the Merkle tree nodes
+    pub hash_id: u8,
+    /// Merkle tree roots prior to each transaction but the first, if exposed by the proof
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::encoding::serde::option_vec_elements")
+    )]
+    pub intermediate_roots: Option<Vec<[BaseElement; HASH_RATE_WIDTH]>>,
+    /// Running digest of the (sender, receiver, amount) tuples of all transactions but no-ops,
+    /// if exposed by the proof
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::encoding::serde::option_elements")
+    )]
+    pub transactions_digest: Option<[BaseElement; HASH_RATE_WIDTH]>,
+    /// Commitment to the nullifiers of all transactions but deposits and no-ops, if exposed by
+    /// the proof
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::encoding::serde::option_elements")
+    )]
+    pub nullifiers_commitment: Option<[BaseElement; HASH_RATE_WIDTH]>,
+    /// Number and volume of the transactions, if exposed by the proof
+    pub batch_statistics: Option<BatchStatistics>,
+    /// Identifier of the subnet whose transactions are proven, signed along with each of them
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub subnet_id: BaseElement,
+    /// Epoch which all the proven transactions are signed for
+    pub epoch: u32,
+    /// Whether the proof binds the sender's and receiver's leaves of each transaction to the
+    /// indices derived from their public keys by `account_index`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub derived_indices: bool,
+    /// Whether the proof supports refunds, transfers of a negative amount debiting the
+    /// receiver, which signs them, and whose deltas are proven within `[-2^k, 2^k)`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub signed_deltas: bool,
+}
+
+impl PublicInputs {
+    /// Returns new public inputs for a state-transition between the two provided roots
+    /// of a Merkle tree of depth `tree_depth`, with fees credited to the leaf at index
+    /// `fee_collector`, funds exiting the tree through the provided `withdrawals` and
+    /// entering it through the provided `deposits`.
+    pub fn new(
+        initial_root: Hash,
+        final_root: Hash,
+        tree_depth: usize,
+        fee_collector: usize,
+        withdrawals: Vec<Withdrawal>,
+        deposits: Vec<Deposit>,
+    ) -> Self {
+        PublicInputs {
+            initial_root: initial_root.to_elements(),
+            final_root: final_root.to_elements(),
+            tree_depth,
+            fee_collector,
+            withdrawals,
+            deposits,
+            memos: Vec::new(),
+            range_log: RANGE_LOG,
+            hash_id: Rescue63::HASH_ID,
+            intermediate_roots: None,
+            transactions_digest: None,
+            nullifiers_commitment: None,
+            batch_statistics: None,
+            subnet_id: BaseElement::ZERO,
+            epoch: 0,
+            derived_indices: false,
+            signed_deltas: false,
+        }
+    }
+
+    /// Returns these public inputs, with the provided `memos` attached to the transactions.
+    /// The proof then binds the memo signed with each transaction to these memos, any other
+    /// transaction having no memo.
+    pub fn with_memos(mut self, memos: Vec<Memo>) -> Self {
+        self.memos = memos;
+        self
+    }
+
+    /// Returns these public inputs, for transactions of the subnet identified by `subnet_id`
+    /// instead of the default subnet zero. The proof then binds the signatures of all the
+    /// transactions to this subnet.
+    pub fn with_subnet_id(mut self, subnet_id: BaseElement) -> Self {
+        self.subnet_id = subnet_id;
+        self
+    }
+
+    /// Returns these public inputs, for transactions signed for the provided `epoch` instead
+    /// of epoch zero.
+    pub fn with_epoch(mut self, epoch: u32) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Returns these public inputs, with amounts, fees and balances being range-checked
+    /// over `range_log` bits instead of the default `RANGE_LOG`.
+    pub fn with_range_log(mut self, range_log: usize) -> Self {
+        self.range_log = range_log;
+        self
+    }
+
+    /// Returns these public inputs, with the nodes of the Merkle tree being hashed with `H`
+    /// instead of the default Rescue63.
+    pub fn with_hasher<H: TreeHasher>(mut self) -> Self {
+        self.hash_id = H::HASH_ID;
+        self
+    }
+
+    /// Returns these public inputs, exposing the Merkle tree roots prior to each transaction
+    /// but the first, in order. The proof then binds the tree state in between every two
+    /// consecutive transactions to these roots.
+    pub fn with_intermediate_roots(mut self, intermediate_roots: &[Hash]) -> Self {
+        self.intermediate_roots = Some(
+            intermediate_roots
+                .iter()
+                .map(|root| root.to_elements())
+                .collect(),
+        );
+        self
+    }
+
+    /// Returns these public inputs, exposing the running digest of the (sender, receiver,
+    /// amount) tuples of all transactions but no-ops, as computed by
+    /// `TransactionMetadata::transactions_digest`. The proof then binds the transfers it
+    /// covers to this digest.
+    pub fn with_transactions_digest(mut self, transactions_digest: Hash) -> Self {
+        self.transactions_digest = Some(transactions_digest.to_elements());
+        self
+    }
+
+    /// Returns these public inputs, exposing the commitment to the provided `nullifiers`, as
+    /// returned in order by `TransactionMetadata::nullifiers`. The proof then binds the
+    /// transactions it covers to these nullifiers, so that a batch submitted twice can be
+    /// detected from the nullifiers already processed.
+    pub fn with_nullifiers(mut self, nullifiers: &[Hash]) -> Self {
+        let commitment = nullifiers
+            .iter()
+            .fold(Hash::default(), |commitment, &nullifier| {
+                update_nullifiers_commitment(commitment, nullifier)
+            });
+        self.nullifiers_commitment = Some(commitment.to_elements());
+        self
+    }
+
+    /// Returns these public inputs, exposing the provided `statistics` of the proven batch, as
+    /// computed by `TransactionMetadata::batch_statistics`.
+    pub fn with_batch_statistics(mut self, statistics: BatchStatistics) -> Self {
+        self.batch_statistics = Some(statistics);
+        self
+    }
+
+    /// Returns these public inputs, for a proof binding the sender's and receiver's leaves of
+    /// each transaction to the indices derived from their public keys by `account_index`, as
+    /// checked by `TransactionMetadata::verify_derived_indices`.
+    ///
+    /// The receivers of outputs of split transfers, whose keys are not part of the execution
+    /// trace, and the leaves of no-ops, which they leave untouched, are not bound. Rekeyings
+    /// leave the account at its leaf, which must then be derived from both the former and the
+    /// new key.
+    pub fn with_derived_indices(mut self) -> Self {
+        self.derived_indices = true;
+        self
+    }
+
+    /// Returns these public inputs, for a proof supporting refunds, i.e. transfers of a
+    /// negative amount whose range proof decomposes delta offset by `2^range_log` when its sign
+    /// is set, so that it lies within `[-2^range_log, 2^range_log)`.
+    ///
+    /// Refunds debit the receiver, which signs them over its own nonce, transfer no fee, and
+    /// are subtracted from the volume of the batch. Their range log must not exceed
+    /// `MAX_SIGNED_RANGE_LOG`, for negative deltas not to wrap around the field modulus.
+    pub fn with_signed_deltas(mut self) -> Self {
+        self.signed_deltas = true;
+        self
+    }
+
+    /// Returns the hash chain committing to the initial root, the intermediate roots and the
+    /// final root, in order, as bound to the proof. Returns `None` if the intermediate roots
+    /// are not exposed.
+    ///
+    /// The chain starts from the digest of the initial root, each following link being the
+    /// digest of the previous link followed by the next root.
+    pub fn intermediate_roots_commitment(&self) -> Option<Hash> {
+        let intermediate_roots = self.intermediate_roots.as_ref()?;
+        let mut commitment = Rescue63::digest(&self.initial_root);
+        for root in intermediate_roots.iter().chain([self.final_root].iter()) {
+            let mut elements = [BaseElement::ZERO; HASH_RATE_WIDTH * 2];
+            elements[..HASH_RATE_WIDTH].copy_from_slice(&commitment.to_elements());
+            elements[HASH_RATE_WIDTH..].copy_from_slice(root);
+            commitment = Rescue63::digest(&elements);
+        }
+
+        Some(commitment)
+    }
+
+    /// Returns the byte representation of these public inputs, including the full lists of
+    /// withdrawals, deposits, memos and intermediate roots, the digest of transactions, the
+    /// commitment to nullifiers, the statistics of the batch, whether indices are derived and
+    /// whether deltas are signed.
+    ///
+    /// Contrary to the `Serializable` encoding bound to the proof, which only commits to the
+    /// deposits, memos and intermediate roots, this encoding can be read back with
+    /// `PublicInputs::from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut target = Vec::new();
+        target.write(&self.initial_root[..]);
+        target.write(&self.final_root[..]);
+        target.write_u8(self.tree_depth as u8);
+        target.write_u64(self.fee_collector as u64);
+        target.write_u8(self.range_log as u8);
+        target.write_u8(self.hash_id);
+        self.subnet_id.write_into(&mut target);
+        target.write_u32(self.epoch);
+        target.write_u32(self.withdrawals.len() as u32);
+        for withdrawal in self.withdrawals.iter() {
+            withdrawal.write_into(&mut target);
+        }
+        target.write_u32(self.deposits.len() as u32);
+        for deposit in self.deposits.iter() {
+            deposit.write_into(&mut target);
+        }
+        target.write_u32(self.memos.len() as u32);
+        for memo in self.memos.iter() {
+            memo.write_into(&mut target);
+        }
+        match &self.intermediate_roots {
+            Some(intermediate_roots) => {
+                target.write_u8(1);
+                target.write_u32(intermediate_roots.len() as u32);
+                for root in intermediate_roots.iter() {
+                    target.write(&root[..]);
+                }
+            }
+            None => target.write_u8(0),
+        }
+        match &self.transactions_digest {
+            Some(transactions_digest) => {
+                target.write_u8(1);
+                target.write(&transactions_digest[..]);
+            }
+            None => target.write_u8(0),
+        }
+        match &self.nullifiers_commitment {
+            Some(nullifiers_commitment) => {
+                target.write_u8(1);
+                target.write(&nullifiers_commitment[..]);
+            }
+            None => target.write_u8(0),
+        }
+        match &self.batch_statistics {
+            Some(statistics) => {
+                target.write_u8(1);
+                statistics.write_into(&mut target);
+            }
+            None => target.write_u8(0),
+        }
+        target.write_u8(self.derived_indices as u8);
+        target.write_u8(self.signed_deltas as u8);
+
+        target
+    }
+
+    /// Reads public inputs from the provided bytes, as serialized with
+    /// `PublicInputs::to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        let mut source = SliceReader::new(bytes);
+        let initial_root = read_root(&mut source)?;
+        let final_root = read_root(&mut source)?;
+        let tree_depth = source.read_u8()? as usize;
+        let fee_collector = source.read_u64()? as usize;
+        let range_log = source.read_u8()? as usize;
+        check_parameters(tree_depth, fee_collector, range_log)?;
+        let hash_id = source.read_u8()?;
+        let subnet_id = BaseElement::read_from(&mut source)?;
+        let epoch = source.read_u32()?;
+
+        let num_withdrawals = source.read_u32()? as usize;
+        // counts are untrusted, and each entry takes at least one byte
+        let mut withdrawals = Vec::with_capacity(num_withdrawals.min(bytes.len()));
+        for _ in 0..num_withdrawals {
+            withdrawals.push(Withdrawal::read_from(&mut source)?);
+        }
+        let num_deposits = source.read_u32()? as usize;
+        let mut deposits = Vec::with_capacity(num_deposits.min(bytes.len()));
+        for _ in 0..num_deposits {
+            deposits.push(Deposit::read_from(&mut source)?);
+        }
+        let num_memos = source.read_u32()? as usize;
+        let mut memos = Vec::with_capacity(num_memos.min(bytes.len()));
+        for _ in 0..num_memos {
+            memos.push(Memo::read_from(&mut source)?);
+        }
+        let intermediate_roots = match source.read_u8()? {
+            0 => None,
+            1 => {
+                let num_roots = source.read_u32()? as usize;
+                let mut intermediate_roots = Vec::with_capacity(num_roots.min(bytes.len()));
+                for _ in 0..num_roots {
+                    intermediate_roots.push(read_root(&mut source)?);
+                }
+                Some(intermediate_roots)
+            }
+            tag => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid intermediate roots flag {}",
+                    tag
+                )))
+            }
+        };
+        let transactions_digest = match source.read_u8()? {
+            0 => None,
+            1 => Some(read_root(&mut source)?),
+            tag => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid transactions digest flag {}",
+                    tag
+                )))
+            }
+        };
+        let nullifiers_commitment = match source.read_u8()? {
+            0 => None,
+            1 => Some(read_root(&mut source)?),
+            tag => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid nullifiers commitment flag {}",
+                    tag
+                )))
+            }
+        };
+        let batch_statistics = match source.read_u8()? {
+            0 => None,
+            1 => Some(BatchStatistics::read_from(&mut source)?),
+            tag => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid batch statistics flag {}",
+                    tag
+                )))
+            }
+        };
+        let derived_indices = match source.read_u8()? {
+            0 => false,
+            1 => true,
+            tag => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid derived indices flag {}",
+                    tag
+                )))
+            }
+        };
+        let signed_deltas = match source.read_u8()? {
+            0 => false,
+            1 => true,
+            tag => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid signed deltas flag {}",
+                    tag
+                )))
+            }
+        };
+        check_signed_range_log(signed_deltas, range_log)?;
+        if source.has_more_bytes() {
+            return Err(DeserializationError::UnconsumedBytes);
+        }
+
+        Ok(PublicInputs {
+            initial_root,
+            final_root,
+            tree_depth,
+            fee_collector,
+            withdrawals,
+            deposits,
+            memos,
+            range_log,
+            hash_id,
+            intermediate_roots,
+            transactions_digest,
+            nullifiers_commitment,
+            batch_statistics,
+            subnet_id,
+            epoch,
+            derived_indices,
+            signed_deltas,
+        })
+    }
+
+    /// Returns the canonical hex encoding of these public inputs, i.e. the lowercase hex
+    /// encoding of `PublicInputs::to_bytes`, for transport in text-based formats.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    /// Reads public inputs from the provided hex string, as encoded with
+    /// `PublicInputs::to_hex`. Uppercase digits are accepted as well.
+    pub fn from_hex(hex_string: &str) -> Result<Self, DeserializationError> {
+        let bytes = hex::decode(hex_string).map_err(|err| {
+            DeserializationError::InvalidValue(format!("invalid hex encoding: {}", err))
+        })?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the digest committing to the list of deposits, as bound to the proof.
+    pub fn deposits_digest(&self) -> Hash {
+        let elements: Vec<BaseElement> = self
+            .deposits
+            .iter()
+            .flat_map(|deposit| {
+                [
+                    BaseElement::from(deposit.tx_index as u64),
+                    BaseElement::from(deposit.amount),
+                    BaseElement::from(deposit.receiver as u64),
+                ]
+            })
+            .collect();
+
+        Rescue63::digest(&elements)
+    }
+
+    /// Returns the digest committing to the list of memos, as bound to the proof.
+    pub fn memos_digest(&self) -> Hash {
+        let elements: Vec<BaseElement> = self
+            .memos
+            .iter()
+            .flat_map(|memo| {
+                let mut elements = [BaseElement::ZERO; MEMO_WIDTH + 1];
+                elements[0] = BaseElement::from(memo.tx_index as u64);
+                elements[1..].copy_from_slice(&encode_memo(&memo.data));
+                elements
+            })
+            .collect();
+
+        Rescue63::digest(&elements)
+    }
 }
 
 impl Serializable for PublicInputs {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
         target.write(&self.initial_root[..]);
         target.write(&self.final_root[..]);
+        target.write_u8(self.tree_depth as u8);
+        target.write_u64(self.fee_collector as u64);
+        target.write_u8(self.range_log as u8);
+        target.write_u8(self.hash_id);
+        self.subnet_id.write_into(target);
+        target.write_u32(self.epoch);
+        target.write_u32(self.withdrawals.len() as u32);
+        for withdrawal in self.withdrawals.iter() {
+            withdrawal.write_into(target);
+        }
+        target.write_u32(self.deposits.len() as u32);
+        target.write(&self.deposits_digest().to_elements()[..]);
+        target.write_u32(self.memos.len() as u32);
+        target.write(&self.memos_digest().to_elements()[..]);
+        if let Some(commitment) = self.intermediate_roots_commitment() {
+            target.write(&commitment.to_elements()[..]);
+        }
+        if let Some(transactions_digest) = &self.transactions_digest {
+            target.write(&transactions_digest[..]);
+        }
+        if let Some(nullifiers_commitment) = &self.nullifiers_commitment {
+            target.write(&nullifiers_commitment[..]);
+        }
+        if let Some(statistics) = &self.batch_statistics {
+            statistics.write_into(target);
+        }
+        target.write_u8(self.derived_indices as u8);
+        target.write_u8(self.signed_deltas as u8);
+    }
+}
+
+/// A withdrawal of funds out of the Merkle tree, to be credited on L1 to `recipient`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Withdrawal {
+    /// Index of the withdrawal transaction within the proven batch
+    pub tx_index: usize,
+    /// Amount burnt from the sender's leaf
+    pub amount: u64,
+    /// L1 address of the recipient
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::bytes"))]
+    pub recipient: [u8; 20],
+}
+
+impl Withdrawal {
+    /// Returns a new withdrawal of `amount` to `recipient`, performed by the
+    /// transaction at position `tx_index` within the batch.
+    pub fn new(tx_index: usize, amount: u64, recipient: [u8; 20]) -> Self {
+        Withdrawal {
+            tx_index,
+            amount,
+            recipient,
+        }
+    }
+}
+
+impl Serializable for Withdrawal {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.tx_index as u32);
+        target.write_u64(self.amount);
+        target.write_u8_slice(&self.recipient);
+    }
+}
+
+impl Deserializable for Withdrawal {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let tx_index = source.read_u32()? as usize;
+        let amount = source.read_u64()?;
+        let recipient = source.read_u8_array::<20>()?;
+
+        Ok(Withdrawal::new(tx_index, amount, recipient))
+    }
+}
+
+/// A deposit of funds into the Merkle tree, minted into the leaf at index `receiver`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Deposit {
+    /// Index of the deposit transaction within the proven batch
+    pub tx_index: usize,
+    /// Amount minted into the receiver's leaf
+    pub amount: u64,
+    /// Index of the receiver's leaf
+    pub receiver: usize,
+}
+
+impl Deposit {
+    /// Returns a new deposit of `amount` into the leaf at index `receiver`, performed
+    /// by the transaction at position `tx_index` within the batch.
+    pub fn new(tx_index: usize, amount: u64, receiver: usize) -> Self {
+        Deposit {
+            tx_index,
+            amount,
+            receiver,
+        }
+    }
+}
+
+impl Serializable for Deposit {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.tx_index as u32);
+        target.write_u64(self.amount);
+        target.write_u64(self.receiver as u64);
+    }
+}
+
+impl Deserializable for Deposit {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let tx_index = source.read_u32()? as usize;
+        let amount = source.read_u64()?;
+        let receiver = source.read_u64()? as usize;
+
+        Ok(Deposit::new(tx_index, amount, receiver))
+    }
+}
+
+/// A memo attached to a transaction, such as an invoice identifier, signed by its sender
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Memo {
+    /// Index of the transaction within the proven batch
+    pub tx_index: usize,
+    /// Content of the memo
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::bytes"))]
+    pub data: [u8; 32],
+}
+
+impl Memo {
+    /// Returns a new memo holding `data`, attached to the transaction at position
+    /// `tx_index` within the batch.
+    pub fn new(tx_index: usize, data: [u8; 32]) -> Self {
+        Memo { tx_index, data }
+    }
+}
+
+impl Serializable for Memo {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.tx_index as u32);
+        target.write_u8_slice(&self.data);
+    }
+}
+
+impl Deserializable for Memo {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let tx_index = source.read_u32()? as usize;
+        let data = source.read_u8_array::<32>()?;
+
+        Ok(Memo::new(tx_index, data))
+    }
+}
+
+/// Statistics of a proven batch, computed and constrained within the AIR program so that
+/// monitoring systems can read them from the public inputs of its proof
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchStatistics {
+    /// Number of transactions of the batch, no-ops excluded
+    pub num_transactions: usize,
+    /// Sum of the amounts of all transactions, withdrawals and deposits included, reduced
+    /// modulo the field modulus
+    pub volume: u64,
+}
+
+impl BatchStatistics {
+    /// Returns the statistics of a batch of `num_transactions` transactions other than
+    /// no-ops, transferring `volume` in total.
+    pub fn new(num_transactions: usize, volume: u64) -> Self {
+        BatchStatistics {
+            num_transactions,
+            volume,
+        }
+    }
+}
+
+impl Serializable for BatchStatistics {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.num_transactions as u32);
+        target.write_u64(self.volume);
+    }
+}
+
+impl Deserializable for BatchStatistics {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let num_transactions = source.read_u32()? as usize;
+        let volume = source.read_u64()?;
+        if volume >= BaseElement::MODULUS {
+            return Err(DeserializationError::InvalidValue(format!(
+                "batch volume {} is not a canonical field element",
+                volume
+            )));
+        }
+
+        Ok(BatchStatistics::new(num_transactions, volume))
+    }
+}
+
+/// Reads the elements of a Merkle tree root from the provided `source` reader.
+fn read_root<R: ByteReader>(
+    source: &mut R,
+) -> Result<[BaseElement; HASH_RATE_WIDTH], DeserializationError> {
+    let mut root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+    root.copy_from_slice(&BaseElement::read_batch_from(source, HASH_RATE_WIDTH)?);
+
+    Ok(root)
+}
+
+/// Checks that deserialized public inputs describe a tree of supported depth, a fee collector
+/// within this tree and a supported range log.
+pub(crate) fn check_parameters(
+    tree_depth: usize,
+    fee_collector: usize,
+    range_log: usize,
+) -> Result<(), DeserializationError> {
+    if tree_depth == 0 || tree_depth > MAX_MERKLE_TREE_DEPTH {
+        return Err(DeserializationError::InvalidValue(format!(
+            "tree depth must be between 1 and {}, but was {}",
+            MAX_MERKLE_TREE_DEPTH, tree_depth
+        )));
+    }
+    if fee_collector >= 1 << tree_depth {
+        return Err(DeserializationError::InvalidValue(format!(
+            "fee collector index must be smaller than 2^{}, but was {}",
+            tree_depth, fee_collector
+        )));
+    }
+    if range_log == 0 || range_log > MAX_RANGE_LOG {
+        return Err(DeserializationError::InvalidValue(format!(
+            "range log must be between 1 and {}, but was {}",
+            MAX_RANGE_LOG, range_log
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks that deserialized public inputs supporting signed deltas have a range log small
+/// enough for negative deltas not to wrap around the field modulus.
+pub(crate) fn check_signed_range_log(
+    signed_deltas: bool,
+    range_log: usize,
+) -> Result<(), DeserializationError> {
+    if signed_deltas && range_log > MAX_SIGNED_RANGE_LOG {
+        return Err(DeserializationError::InvalidValue(format!(
+            "range log of signed deltas must be at most {}, but was {}",
+            MAX_SIGNED_RANGE_LOG, range_log
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks that public inputs only refer to transactions of a batch of `num_transactions`
+/// transactions, and that no deposit credits a leaf outside of the tree or is also a
+/// withdrawal.
+///
+/// The number of transactions is only known from the trace length of the proof to verify, as
+/// periodic columns repeat every transaction cycle: the same program verifies batches of any
+/// size. This length is chosen by the prover, so that public inputs must be checked against
+/// it before instantiating a `TransactionAir` program.
+pub(crate) fn check_num_transactions(
+    pub_inputs: &PublicInputs,
+    num_transactions: usize,
+) -> Result<(), VerifierError> {
+    let invalid = |message: String| Err(VerifierError::ProofDeserializationError(message));
+    if num_transactions == 0 {
+        return invalid(String::from("trace must hold at least one transaction"));
+    }
+    for (i, withdrawal) in pub_inputs.withdrawals.iter().enumerate() {
+        if withdrawal.tx_index >= num_transactions {
+            return invalid(format!(
+                "withdrawal index must be smaller than {}, but was {}",
+                num_transactions, withdrawal.tx_index
+            ));
+        }
+        if i > 0 && pub_inputs.withdrawals[i - 1].tx_index >= withdrawal.tx_index {
+            return invalid(String::from(
+                "withdrawals must be sorted by increasing transaction index",
+            ));
+        }
+    }
+    for (i, deposit) in pub_inputs.deposits.iter().enumerate() {
+        if deposit.tx_index >= num_transactions {
+            return invalid(format!(
+                "deposit index must be smaller than {}, but was {}",
+                num_transactions, deposit.tx_index
+            ));
+        }
+        if i > 0 && pub_inputs.deposits[i - 1].tx_index >= deposit.tx_index {
+            return invalid(String::from(
+                "deposits must be sorted by increasing transaction index",
+            ));
+        }
+        if deposit.receiver >= 1 << pub_inputs.tree_depth {
+            return invalid(format!(
+                "deposit receiver index must be smaller than 2^{}, but was {}",
+                pub_inputs.tree_depth, deposit.receiver
+            ));
+        }
+        if pub_inputs
+            .withdrawals
+            .iter()
+            .any(|withdrawal| withdrawal.tx_index == deposit.tx_index)
+        {
+            return invalid(String::from(
+                "a transaction cannot be both a withdrawal and a deposit",
+            ));
+        }
+    }
+    for (i, memo) in pub_inputs.memos.iter().enumerate() {
+        if memo.tx_index >= num_transactions {
+            return invalid(format!(
+                "memo index must be smaller than {}, but was {}",
+                num_transactions, memo.tx_index
+            ));
+        }
+        if i > 0 && pub_inputs.memos[i - 1].tx_index >= memo.tx_index {
+            return invalid(String::from(
+                "memos must be sorted by increasing transaction index",
+            ));
+        }
+    }
+    if let Some(intermediate_roots) = &pub_inputs.intermediate_roots {
+        if intermediate_roots.len() + 1 != num_transactions {
+            return invalid(format!(
+                "number of intermediate roots must be {}, but was {}",
+                num_transactions - 1,
+                intermediate_roots.len()
+            ));
+        }
+    }
+    if let Some(statistics) = &pub_inputs.batch_statistics {
+        if statistics.num_transactions > num_transactions {
+            return invalid(format!(
+                "number of transactions of the batch statistics must be at most {}, but was {}",
+                num_transactions, statistics.num_transactions
+            ));
+        }
+        if statistics.volume >= BaseElement::MODULUS {
+            return invalid(String::from(
+                "batch volume must be a canonical field element",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes an L1 recipient address into the registers otherwise holding the receiver's
+/// public key, as little-endian chunks of 7, 7 and 6 bytes followed by zero elements.
+pub(crate) fn encode_recipient(recipient: &[u8; 20]) -> [BaseElement; AFFINE_POINT_WIDTH] {
+    let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    for (element, chunk) in elements.iter_mut().zip(recipient.chunks(7)) {
+        let mut bytes = [0u8; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        *element = BaseElement::from(u64::from_le_bytes(bytes));
+    }
+
+    elements
+}
+
+/// Decodes an L1 recipient address encoded with `encode_recipient`.
+pub(crate) fn decode_recipient(elements: &[BaseElement]) -> [u8; 20] {
+    let mut recipient = [0u8; 20];
+    for (chunk, element) in recipient.chunks_mut(7).zip(elements.iter()) {
+        let len = chunk.len();
+        chunk.copy_from_slice(&element.to_repr().to_le_bytes()[..len]);
+    }
+
+    recipient
+}
+
+/// Encodes a memo into the memo registers, as little-endian chunks of 7 bytes, the last
+/// one holding the remaining 4 bytes.
+pub(crate) fn encode_memo(memo: &[u8; 32]) -> [BaseElement; MEMO_WIDTH] {
+    let mut elements = [BaseElement::ZERO; MEMO_WIDTH];
+    for (element, chunk) in elements.iter_mut().zip(memo.chunks(7)) {
+        let mut bytes = [0u8; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        *element = BaseElement::from(u64::from_le_bytes(bytes));
+    }
+
+    elements
+}
+
+/// Decodes a memo encoded with `encode_memo`.
+pub(crate) fn decode_memo(elements: &[BaseElement]) -> [u8; 32] {
+    let mut memo = [0u8; 32];
+    for (chunk, element) in memo.chunks_mut(7).zip(elements.iter()) {
+        let len = chunk.len();
+        chunk.copy_from_slice(&element.to_repr().to_le_bytes()[..len]);
     }
+
+    memo
 }
 
-pub struct TransactionAir {
+/// Returns the commitment to nullifiers `commitment` updated with `nullifier`. As within the
+/// AIR program, the nullifier is merged with the previous commitment.
+pub(crate) fn update_nullifiers_commitment(commitment: Hash, nullifier: Hash) -> Hash {
+    <Rescue63 as Hasher>::merge(&[nullifier, commitment])
+}
+
+pub struct TransactionAir<H: TreeHasher = Rescue63> {
     context: AirContext<BaseElement>,
     initial_root: [BaseElement; HASH_RATE_WIDTH],
     final_root: [BaseElement; HASH_RATE_WIDTH],
+    tree_depth: usize,
+    fee_collector: usize,
+    withdrawals: Vec<Withdrawal>,
+    deposits: Vec<Deposit>,
+    memos: Vec<Memo>,
+    range_log: usize,
+    intermediate_roots: Option<Vec<[BaseElement; HASH_RATE_WIDTH]>>,
+    transactions_digest: Option<[BaseElement; HASH_RATE_WIDTH]>,
+    nullifiers_commitment: Option<[BaseElement; HASH_RATE_WIDTH]>,
+    batch_statistics: Option<BatchStatistics>,
+    subnet_id: BaseElement,
+    epoch: u32,
+    derived_indices: bool,
+    signed_deltas: bool,
+    delta_sign_weight: BaseElement,
+    deposit_key: [BaseElement; AFFINE_POINT_WIDTH],
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> TransactionAir<H> {
+    /// Returns whether this AIR program binds the indices of accounts to their keys.
+    pub(crate) fn binds_derived_indices(&self) -> bool {
+        self.derived_indices
+    }
 }
 
-impl Air for TransactionAir {
+impl<H: TreeHasher> Air for TransactionAir<H> {
     type BaseField = BaseElement;
     type PublicInputs = PublicInputs;
 
     // CONSTRUCTOR
     // --------------------------------------------------------------------------------------------
     fn new(trace_info: TraceInfo, pub_inputs: PublicInputs, options: ProofOptions) -> Self {
-        // Constraint degrees for enforcement of Rescue hash rounds
-        let mut degrees = merkle::update::transition_constraint_degrees(TRANSACTION_CYCLE_LENGTH);
-        // The constraint at the receiver position has higher degree than in Merkle sub-AIR program
-        degrees[RECEIVER_BIT_POS] =
-            TransitionConstraintDegree::with_cycles(3, vec![TRANSACTION_CYCLE_LENGTH]);
-        degrees[INT_ROOT_EQUALITY_RES] =
-            TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]);
-
-        let schnorr_degrees = schnorr::transition_constraint_degrees(2, TRANSACTION_CYCLE_LENGTH);
-        // Update the constraint degrees with the ones for Schnorr
-        for index in 0..PROJECTIVE_POINT_WIDTH {
-            degrees[index] = schnorr_degrees[index].clone();
-            degrees[index + PROJECTIVE_POINT_WIDTH + 1] =
-                schnorr_degrees[index + PROJECTIVE_POINT_WIDTH + 1].clone();
-        }
-
-        // Append the degrees for the copy columns followed by range proof equalities
-        degrees.append(&mut vec![
-            TransitionConstraintDegree::with_cycles(
-                1,
-                vec![TRANSACTION_CYCLE_LENGTH]
-            );
-            SIGMA_RANGE_RES - SENDER_KEY_POINT_RES + 1
-        ]);
+        let degrees = transition_constraint_degrees(pub_inputs.derived_indices);
 
         assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(
+            pub_inputs.tree_depth > 0 && pub_inputs.tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        assert!(
+            pub_inputs.fee_collector < 1 << pub_inputs.tree_depth,
+            "fee collector index must be smaller than 2^{}",
+            pub_inputs.tree_depth
+        );
+        assert!(
+            pub_inputs.range_log > 0 && pub_inputs.range_log <= MAX_RANGE_LOG,
+            "range log must be between 1 and {}",
+            MAX_RANGE_LOG
+        );
+        assert!(
+            !pub_inputs.signed_deltas || pub_inputs.range_log <= MAX_SIGNED_RANGE_LOG,
+            "range log of signed deltas must be at most {}",
+            MAX_SIGNED_RANGE_LOG
+        );
+        assert_eq!(
+            pub_inputs.hash_id,
+            H::HASH_ID,
+            "public inputs and AIR program must use the same tree hash function"
+        );
         TransactionAir {
             context: AirContext::new(trace_info, degrees, options),
             initial_root: pub_inputs.initial_root,
             final_root: pub_inputs.final_root,
+            tree_depth: pub_inputs.tree_depth,
+            fee_collector: pub_inputs.fee_collector,
+            withdrawals: pub_inputs.withdrawals,
+            deposits: pub_inputs.deposits,
+            memos: pub_inputs.memos,
+            range_log: pub_inputs.range_log,
+            intermediate_roots: pub_inputs.intermediate_roots,
+            transactions_digest: pub_inputs.transactions_digest,
+            nullifiers_commitment: pub_inputs.nullifiers_commitment,
+            batch_statistics: pub_inputs.batch_statistics,
+            subnet_id: pub_inputs.subnet_id,
+            epoch: pub_inputs.epoch,
+            derived_indices: pub_inputs.derived_indices,
+            signed_deltas: pub_inputs.signed_deltas,
+            delta_sign_weight: if pub_inputs.signed_deltas {
+                range::sign_weight(pub_inputs.range_log)
+            } else {
+                BaseElement::ZERO
+            },
+            deposit_key: super::deposit_key(),
+            _hasher: PhantomData,
         }
     }
 
@@ -136,24 +1069,30 @@ impl Air for TransactionAir {
         let schnorr_hash_digest_register_flag =
             &periodic_values[SCHNORR_DIGEST_MASK_INDEX..SCHNORR_HASH_MASK_INDEX];
         let schnorr_hash_flag = periodic_values[SCHNORR_HASH_MASK_INDEX];
+        let schnorr_hash_hold_flag = periodic_values[SCHNORR_HASH_HOLD_MASK_INDEX];
         let hash_internal_input_flags =
             &periodic_values[HASH_INTERNAL_INPUT_MASKS_INDEX..RANGE_PROOF_STEP_MASK_INDEX];
 
         let range_proof_flag = periodic_values[RANGE_PROOF_STEP_MASK_INDEX];
         let range_proof_finish_flag = periodic_values[RANGE_PROOF_FINISH_MASK_INDEX];
         let copy_values_flag = periodic_values[VALUE_COPY_MASK_INDEX];
-        let ark = &periodic_values[ARK_INDEX..];
+        let copy_key_flag = periodic_values[KEY_COPY_MASK_INDEX];
+        let index_weight = periodic_values[INDEX_WEIGHTS_INDEX];
+        let tx_digest_setup_flag = periodic_values[TX_DIGEST_SETUP_MASK_INDEX];
+        let nullifiers_finish_flag = periodic_values[NULLIFIERS_FINISH_MASK_INDEX];
+        let ark = &periodic_values[ARK_INDEX..TREE_ARK_INDEX];
+        let tree_ark = &periodic_values[TREE_ARK_INDEX..];
 
         // Generate dependent masks from existing masks
-        let copy_hash_flag = not(schnorr_hash_flag) * schnorr_mask;
-        let final_point_addition_flag = not(scalar_mult_flag) * schnorr_mask;
+        let final_reduction_flag = not(scalar_mult_flag) * schnorr_mask;
         let addition_flag = not(doubling_flag) * scalar_mult_flag;
 
-        evaluate_constraints(
+        evaluate_constraints::<H, E>(
             result,
             current,
             next,
             ark,
+            tree_ark,
             transaction_setup_flag,
             transaction_hash_flag,
             hash_input_flag,
@@ -162,253 +1101,427 @@ impl Air for TransactionAir {
             doubling_flag,
             addition_flag,
             schnorr_hash_digest_register_flag,
-            final_point_addition_flag,
+            final_reduction_flag,
             schnorr_hash_flag,
-            copy_hash_flag,
+            schnorr_hash_hold_flag,
             hash_internal_input_flags,
             range_proof_flag,
             range_proof_finish_flag,
             copy_values_flag,
-        )
+            copy_key_flag,
+            index_weight,
+            tx_digest_setup_flag,
+            nullifiers_finish_flag,
+            &self.deposit_key.map(E::from),
+            E::from(self.subnet_id),
+            E::from(self.epoch),
+            if self.signed_deltas { E::ONE } else { E::ZERO },
+            E::from(self.delta_sign_weight),
+        );
+
+        evaluate_committee_constraints(
+            result,
+            current,
+            next,
+            ark,
+            transaction_setup_flag,
+            transaction_hash_flag,
+            hash_input_flag,
+            hash_flag,
+            transaction_finish_flag,
+            copy_values_flag,
+            copy_key_flag,
+            index_weight,
+            &periodic_values[KEY_INDEX_SETUP_MASK_INDEX..ARK_INDEX],
+        );
+
+        // Enforce, if required, that the leaves of the transaction are the ones derived from
+        // the keys they hold
+        if self.derived_indices {
+            evaluate_key_index_constraints(
+                result,
+                current,
+                next,
+                ark,
+                tx_digest_setup_flag,
+                schnorr_hash_flag,
+                schnorr_hash_hold_flag,
+                hash_internal_input_flags,
+                &periodic_values[KEY_INDEX_SETUP_MASK_INDEX..ARK_INDEX],
+            );
+        }
     }
 
     fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
         // Assert the presence of the appropriate initial and final tree roots
         let last_step = self.trace_length() - 1;
-        vec![
-            Assertion::single(PREV_TREE_ROOT_POS, 0, self.initial_root[0]),
-            Assertion::single(PREV_TREE_ROOT_POS + 1, 0, self.initial_root[1]),
-            Assertion::single(PREV_TREE_ROOT_POS, last_step, self.final_root[0]),
-            Assertion::single(PREV_TREE_ROOT_POS + 1, last_step, self.final_root[1]),
-        ]
+        let mut assertions = boundary::values_at(PREV_TREE_ROOT_POS, 0, &self.initial_root);
+        assertions.extend(boundary::values_at(
+            PREV_TREE_ROOT_POS,
+            last_step,
+            &self.final_root,
+        ));
+
+        // Assert that the fees of every transaction are credited to the designated leaf,
+        // by enforcing the bits of its index along the fee collector's authentication path
+        assertions.extend(boundary::periodic_bits_at(
+            COLLECTOR_BIT_POS,
+            HASH_CYCLE_LENGTH,
+            HASH_CYCLE_LENGTH,
+            TRANSACTION_CYCLE_LENGTH,
+            self.fee_collector,
+            self.tree_depth,
+        ));
+
+        // Assert which transactions are withdrawals, and that each of them burns the
+        // expected amount on behalf of the expected recipient
+        let num_transactions = self.trace_length() / TRANSACTION_CYCLE_LENGTH;
+        let mut withdrawal_flags = vec![BaseElement::ZERO; num_transactions];
+        for withdrawal in self.withdrawals.iter() {
+            let step = withdrawal.tx_index * TRANSACTION_CYCLE_LENGTH + 1;
+            withdrawal_flags[withdrawal.tx_index] = BaseElement::ONE;
+            assertions.push(boundary::count_at(DELTA_COPY_POS, step, withdrawal.amount));
+            assertions.extend(boundary::values_at(
+                RECEIVER_KEY_POINT_POS,
+                step,
+                &encode_recipient(&withdrawal.recipient),
+            ));
+        }
+        assertions.push(Assertion::sequence(
+            WITHDRAWAL_FLAG_POS,
+            1,
+            TRANSACTION_CYCLE_LENGTH,
+            withdrawal_flags,
+        ));
+
+        // Assert which transactions are deposits, and that each of them mints the expected
+        // amount, without fee, into the expected leaf
+        let mut deposit_flags = vec![BaseElement::ZERO; num_transactions];
+        for deposit in self.deposits.iter() {
+            let first_step = deposit.tx_index * TRANSACTION_CYCLE_LENGTH;
+            deposit_flags[deposit.tx_index] = BaseElement::ONE;
+            assertions.push(boundary::count_at(
+                DELTA_COPY_POS,
+                first_step + 1,
+                deposit.amount,
+            ));
+            assertions.push(boundary::count_at(FEE_COPY_POS, first_step + 1, 0));
+            assertions.extend(boundary::bits_at(
+                RECEIVER_BIT_POS,
+                first_step + HASH_CYCLE_LENGTH,
+                HASH_CYCLE_LENGTH,
+                deposit.receiver,
+                self.tree_depth,
+            ));
+        }
+        assertions.push(Assertion::sequence(
+            DEPOSIT_FLAG_POS,
+            1,
+            TRANSACTION_CYCLE_LENGTH,
+            deposit_flags,
+        ));
+
+        // Assert the memo signed with each transaction, transactions without memo signing
+        // zero elements instead
+        let mut memo_elements = vec![vec![BaseElement::ZERO; num_transactions]; MEMO_WIDTH];
+        for memo in self.memos.iter() {
+            for (offset, &value) in encode_memo(&memo.data).iter().enumerate() {
+                memo_elements[offset][memo.tx_index] = value;
+            }
+        }
+        assertions.extend(boundary::sequences_at(
+            MEMO_POS,
+            1,
+            TRANSACTION_CYCLE_LENGTH,
+            memo_elements,
+        ));
+
+        // Assert that the first transaction does not continue a split transfer, and that the
+        // last one does not leave any amount of a split transfer unsent
+        assertions.push(boundary::count_at(SPLIT_POSITION_POS, 1, 1));
+        assertions.push(boundary::count_at(SPLIT_REMAINING_POS, last_step, 0));
+
+        // Likewise, assert that the first transaction does not approve a committee transfer,
+        // and that the last one does not leave any approval missing
+        assertions.push(boundary::count_at(APPROVAL_FLAG_POS, 0, 0));
+        assertions.push(boundary::count_at(APPROVALS_LEFT_POS, last_step, 0));
+
+        // Assert that the hash state computing the challenge of each signature only holds the
+        // x-coordinate of its nonce point, followed by zeros and the domain of challenges
+        let mut challenge_state = [BaseElement::ZERO; HASH_STATE_WIDTH - POINT_COORDINATE_WIDTH];
+        challenge_state[HASH_STATE_WIDTH - POINT_COORDINATE_WIDTH - 1] = CHALLENGE_DOMAIN;
+        assertions.extend(boundary::periodic_values_at(
+            SCHNORR_HASH_STATE_POS + POINT_COORDINATE_WIDTH,
+            MERKLE_UPDATE_LENGTH,
+            TRANSACTION_CYCLE_LENGTH,
+            &challenge_state,
+        ));
+
+        // Assert that the running digest of transactions starts from zero, and, if exposed, the
+        // digest of all transactions, which is carried over until the last step
+        assertions.extend(boundary::zeros_at(TX_DIGEST_POS, 0, HASH_RATE_WIDTH));
+        if let Some(transactions_digest) = &self.transactions_digest {
+            assertions.extend(boundary::values_at(
+                TX_DIGEST_POS,
+                last_step,
+                transactions_digest,
+            ));
+        }
+
+        // Likewise for the commitment to nullifiers
+        assertions.extend(boundary::zeros_at(NULLIFIERS_POS, 0, HASH_RATE_WIDTH));
+        if let Some(nullifiers_commitment) = &self.nullifiers_commitment {
+            assertions.extend(boundary::values_at(
+                NULLIFIERS_POS,
+                last_step,
+                nullifiers_commitment,
+            ));
+        }
+
+        // Likewise for the number and volume of transactions
+        assertions.push(boundary::count_at(TX_COUNT_POS, 0, 0));
+        assertions.push(Assertion::single(TX_VOLUME_POS, 0, BaseElement::ZERO));
+        if let Some(statistics) = &self.batch_statistics {
+            assertions.push(boundary::count_at(
+                TX_COUNT_POS,
+                last_step,
+                statistics.num_transactions as u64,
+            ));
+            assertions.push(Assertion::single(
+                TX_VOLUME_POS,
+                last_step,
+                BaseElement::new(statistics.volume),
+            ));
+        }
+
+        // Assert, if exposed, the roots in between every two consecutive transactions, which
+        // are carried over from the end of a transaction to the beginning of the next one
+        if let Some(intermediate_roots) = &self.intermediate_roots {
+            for (i, root) in intermediate_roots.iter().enumerate() {
+                let step = (i + 1) * TRANSACTION_CYCLE_LENGTH;
+                assertions.extend(boundary::values_at(PREV_TREE_ROOT_POS, step, root));
+            }
+        }
+
+        assertions
     }
 
     fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
-        periodic_columns()
+        periodic_columns::<H>(self.tree_depth, self.range_log)
     }
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
 
-pub fn periodic_columns() -> Vec<Vec<BaseElement>> {
-    // Create empty columns to start with
-    let mut columns = vec![Vec::new(); ARK_INDEX + HASH_STATE_WIDTH * 2];
-    // Initialize the length of the stitched masks
-    let mut length = 0;
-    // Add the columns for the pre-Merkle component
-    let pre_merkle_columns = merkle::init::periodic_columns();
-    stitch(
-        &mut columns,
-        pre_merkle_columns,
-        (ARK_INDEX..ARK_INDEX + HASH_STATE_WIDTH * 2)
-            .enumerate()
-            .collect(),
+pub fn periodic_columns<H: TreeHasher>(
+    tree_depth: usize,
+    range_log: usize,
+) -> Vec<Vec<BaseElement>> {
+    // Segments of the transaction cycle over which the sub-AIR programs are evaluated: the
+    // Merkle authentication paths are hashed over the first half of the cycle, while the
+    // Schnorr signature and the range proofs are verified alongside over the second half
+    let setup = Segment::new(0, 1);
+    let merkle_update = Segment::new(0, transaction_hash_length(tree_depth));
+    let schnorr = Segment::new(MERKLE_UPDATE_LENGTH, SIG_CYCLE_LENGTH);
+    let range_proof = Segment::new(MERKLE_UPDATE_LENGTH, range_log);
+    // The hashes of the keys are decomposed once the longest range proofs are over, from the
+    // step following their setup
+    let key_index = Segment::new(schnorr.start + MAX_RANGE_LOG + 1, KEY_INDEX_NUM_BITS);
+    // The values are copied along the cycle up to the last chunk of the signed message read as
+    // internal inputs of the Schnorr hash, to the no-op flag read once the tuple of the
+    // transaction is hashed, and to the end of the range proofs, or up to the flags selecting
+    // the indices checked against the decompositions of the hashes of the keys
+    let value_copy = Segment::new(setup.end(), key_index.end() - setup.end());
+    debug_assert!(key_index.end() >= schnorr.start + TOTAL_HASH_LENGTH.max(range_log));
+    // The sender's key is read by the scalar multiplication of the Schnorr component up to
+    // the last step of the cycle, and is thus copied up to it
+    let key_copy = Segment::new(setup.end(), schnorr.end() - 1 - setup.end());
+
+    let mut layout = PeriodicColumnSet::new(
+        TREE_ARK_INDEX + HASH_STATE_WIDTH * 2,
+        TRANSACTION_CYCLE_LENGTH,
     );
+    // Add the round constants of the Merkle tree hash for the pre-Merkle and Merkle
+    // components, and the Rescue ones for hashing the Schnorr messages
+    layout.set_columns(TREE_ARK_INDEX, merkle::init::periodic_columns::<H>());
+    layout.set_columns(ARK_INDEX, rescue::get_round_constants());
+
+    // Add the columns for the Merkle components
     // TODO: Change to make use of modified Merkle init component
-    //length += NUM_HASH_ROUNDS;
-    pad(&mut columns, vec![SETUP_MASK_INDEX], 1, BaseElement::ONE);
-    pad(
-        &mut columns,
-        vec![VALUE_COPY_MASK_INDEX],
-        1,
-        BaseElement::ZERO,
-    );
-    pad(
-        &mut columns,
-        vec![MERKLE_MASK_INDEX, FINISH_MASK_INDEX, HASH_MASK_INDEX],
-        length,
-        BaseElement::ZERO,
-    );
-
-    // Add the columns for the Merkle component
-    let merkle_columns = merkle::update::periodic_columns();
-    stitch(
-        &mut columns,
-        merkle_columns.clone(),
-        vec![(2, HASH_INPUT_MASK_INDEX)],
-    );
-    length = TRANSACTION_HASH_LENGTH;
-    fill(
-        &mut columns,
-        merkle_columns,
-        vec![
-            (1, MERKLE_MASK_INDEX),
-            (3, FINISH_MASK_INDEX),
-            (4, HASH_MASK_INDEX),
-        ],
-        length,
-    );
-
-    // Pad the columns up to the transition to Schnorr
-    length = MERKLE_UPDATE_LENGTH;
-    pad(
-        &mut columns,
-        vec![
-            SETUP_MASK_INDEX,
-            MERKLE_MASK_INDEX,
-            FINISH_MASK_INDEX,
-            HASH_MASK_INDEX,
-            SCHNORR_MASK_INDEX,
-            SCALAR_MULT_MASK_INDEX,
-            DOUBLING_MASK_INDEX,
-            SCHNORR_HASH_MASK_INDEX,
-            RANGE_PROOF_STEP_MASK_INDEX,
-            RANGE_PROOF_FINISH_MASK_INDEX,
-        ],
-        length,
-        BaseElement::ZERO,
-    );
-    pad(
-        &mut columns,
-        (SCHNORR_DIGEST_MASK_INDEX..SCHNORR_HASH_MASK_INDEX).collect(),
-        length,
-        BaseElement::ZERO,
-    );
-    pad(
-        &mut columns,
-        vec![VALUE_COPY_MASK_INDEX],
-        length,
-        BaseElement::ONE,
-    );
+    layout.mask(SETUP_MASK_INDEX, setup, BaseElement::ONE);
+    let mut merkle_columns = merkle::update::periodic_columns::<H>(tree_depth);
+    for (merkle_index, index) in [
+        (1, MERKLE_MASK_INDEX),
+        (3, FINISH_MASK_INDEX),
+        (4, HASH_MASK_INDEX),
+    ]
+    .iter()
+    {
+        layout.place(*index, merkle_update, &merkle_columns[*merkle_index]);
+    }
+    layout.set_column(HASH_INPUT_MASK_INDEX, merkle_columns.swap_remove(2));
 
     // Add the columns for the Schnorr component
     let schnorr_columns = schnorr::periodic_columns();
-    stitch(
-        &mut columns,
-        schnorr_columns,
-        vec![
-            SCHNORR_MASK_INDEX,
-            SCALAR_MULT_MASK_INDEX,
-            DOUBLING_MASK_INDEX,
-            SCHNORR_DIGEST_MASK_INDEX,
-            SCHNORR_DIGEST_MASK_INDEX + 1,
-            SCHNORR_DIGEST_MASK_INDEX + 2,
-            SCHNORR_DIGEST_MASK_INDEX + 3,
-            SCHNORR_HASH_MASK_INDEX,
-        ]
-        .into_iter()
-        .enumerate()
-        .collect(),
-    );
-    // Create columns for the input copy masks
-    pad(
-        &mut columns,
-        (HASH_INTERNAL_INPUT_MASKS_INDEX..RANGE_PROOF_STEP_MASK_INDEX).collect(),
-        length,
-        BaseElement::ZERO,
-    );
-    let mut input_masks = vec![
-        vec![BaseElement::ZERO; SIG_CYCLE_LENGTH];
-        RANGE_PROOF_STEP_MASK_INDEX - HASH_INTERNAL_INPUT_MASKS_INDEX
-    ];
-    for (input_num, mask) in input_masks
-        .iter_mut()
-        .enumerate()
-        .take(RANGE_PROOF_STEP_MASK_INDEX - HASH_INTERNAL_INPUT_MASKS_INDEX)
+    for (schnorr_index, index) in [
+        SCHNORR_MASK_INDEX,
+        SCALAR_MULT_MASK_INDEX,
+        DOUBLING_MASK_INDEX,
+        SCHNORR_DIGEST_MASK_INDEX,
+        SCHNORR_DIGEST_MASK_INDEX + 1,
+        SCHNORR_DIGEST_MASK_INDEX + 2,
+        SCHNORR_DIGEST_MASK_INDEX + 3,
+        SCHNORR_HASH_MASK_INDEX,
+    ]
+    .iter()
+    .enumerate()
     {
-        mask[(input_num + 1) * HASH_CYCLE_LENGTH - 1] = BaseElement::ONE;
+        layout.place(*index, schnorr, &schnorr_columns[schnorr_index]);
     }
-    stitch(
-        &mut columns,
-        input_masks,
-        (HASH_INTERNAL_INPUT_MASKS_INDEX..RANGE_PROOF_STEP_MASK_INDEX)
-            .enumerate()
-            .collect(),
+    layout.place(
+        SCHNORR_HASH_HOLD_MASK_INDEX,
+        schnorr,
+        &schnorr_columns[schnorr::HASH_HOLD_FLAG_INDEX],
     );
+    // Add the input copy masks, at the end of every hash cycle of the Schnorr hash
+    for (input_num, index) in
+        (HASH_INTERNAL_INPUT_MASKS_INDEX..RANGE_PROOF_STEP_MASK_INDEX).enumerate()
+    {
+        layout.set(
+            index,
+            schnorr.step((input_num + 1) * HASH_CYCLE_LENGTH - 1),
+            BaseElement::ONE,
+        );
+    }
 
     // Add the columns for the range proof component
-    let range_proof_mask = vec![BaseElement::ONE; RANGE_LOG];
-    let mut range_proof_finish_mask = vec![BaseElement::ZERO; RANGE_LOG];
-    range_proof_finish_mask[RANGE_LOG - 1] = BaseElement::ONE;
-    stitch(
-        &mut columns,
-        vec![range_proof_mask, range_proof_finish_mask],
-        vec![RANGE_PROOF_STEP_MASK_INDEX, RANGE_PROOF_FINISH_MASK_INDEX]
-            .into_iter()
-            .enumerate()
-            .collect(),
-    );
-
-    // Pad out the copy constraints
-    let hash_input_length = 3 * HASH_CYCLE_LENGTH - 1;
-    length += if hash_input_length > RANGE_LOG {
-        hash_input_length
-    } else {
-        RANGE_LOG
-    };
-    pad(
-        &mut columns,
-        vec![VALUE_COPY_MASK_INDEX],
-        length,
-        BaseElement::ONE,
+    bits::place_step_mask(
+        &mut layout,
+        RANGE_PROOF_STEP_MASK_INDEX,
+        range_proof,
+        range_log,
+    );
+    bits::place_finish_mask(
+        &mut layout,
+        RANGE_PROOF_FINISH_MASK_INDEX,
+        range_proof,
+        range_log,
     );
 
-    // Pad to finish the cycle length
-    length = TRANSACTION_CYCLE_LENGTH;
-    pad(
-        &mut columns,
-        vec![
-            SETUP_MASK_INDEX,
-            MERKLE_MASK_INDEX,
-            FINISH_MASK_INDEX,
-            HASH_MASK_INDEX,
-            SCHNORR_MASK_INDEX,
-            SCALAR_MULT_MASK_INDEX,
-            DOUBLING_MASK_INDEX,
-            SCHNORR_HASH_MASK_INDEX,
-            RANGE_PROOF_STEP_MASK_INDEX,
-            RANGE_PROOF_FINISH_MASK_INDEX,
-            VALUE_COPY_MASK_INDEX,
-        ],
-        length,
-        BaseElement::ZERO,
-    );
-    pad(
-        &mut columns,
-        (SCHNORR_DIGEST_MASK_INDEX..SCHNORR_DIGEST_MASK_INDEX + 4).collect(),
-        length,
-        BaseElement::ZERO,
-    );
-    pad(
-        &mut columns,
-        (HASH_INTERNAL_INPUT_MASKS_INDEX..HASH_INTERNAL_INPUT_MASKS_INDEX + 3).collect(),
-        length,
-        BaseElement::ZERO,
-    );
-    columns
-}
+    layout.mask(VALUE_COPY_MASK_INDEX, value_copy, BaseElement::ONE);
+    layout.mask(KEY_COPY_MASK_INDEX, key_copy, BaseElement::ONE);
 
-#[allow(clippy::too_many_arguments)]
-pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
-    result: &mut [E],
-    current: &[E],
-    next: &[E],
-    ark: &[E],
-    transaction_setup_flag: E,
-    transaction_hash_flag: E,
-    hash_input_flag: E,
-    hash_flag: E,
-    transaction_finish_flag: E,
-    doubling_flag: E,
-    addition_flag: E,
-    schnorr_hash_digest_register_flag: &[E],
-    final_point_addition_flag: E,
-    schnorr_hash_flag: E,
-    copy_hash_flag: E,
+    // Add the weights of the sender's and receiver's index bits, at the steps preceding their
+    // insertion along the Merkle authentication paths
+    bits::place_weights(
+        &mut layout,
+        INDEX_WEIGHTS_INDEX,
+        merkle_update,
+        tree_depth,
+        HASH_CYCLE_LENGTH,
+    );
+
+    // Add the masks initializing the hash state of the running digest of transactions at the
+    // transition to Schnorr, and updating the commitment to nullifiers once the hash state has
+    // absorbed the tuple of the transaction, then its nullifier
+    layout.set(
+        TX_DIGEST_SETUP_MASK_INDEX,
+        schnorr.start - 1,
+        BaseElement::ONE,
+    );
+    layout.set(
+        NULLIFIERS_FINISH_MASK_INDEX,
+        schnorr.step((TX_TUPLE_NUM_CHUNKS + 2) * HASH_CYCLE_LENGTH - 1),
+        BaseElement::ONE,
+    );
+
+    // Add the columns for the decompositions of the hashes of the keys, least significant bit
+    // first, compared along the way with the bits of the field modulus minus one. The indices
+    // are checked once their bits are inserted, and the hashes once all bits are
+    layout.set(
+        KEY_INDEX_SETUP_MASK_INDEX,
+        key_index.start - 1,
+        BaseElement::ONE,
+    );
+    layout.mask(KEY_INDEX_STEP_MASK_INDEX, key_index, BaseElement::ONE);
+    bits::place_weights(
+        &mut layout,
+        KEY_INDEX_WEIGHTS_INDEX,
+        key_index,
+        KEY_INDEX_NUM_BITS,
+        1,
+    );
+    for bit in 0..KEY_INDEX_NUM_BITS {
+        if (BaseElement::MODULUS - 1) >> bit & 1 == 1 {
+            layout.set(KEY_INDEX_BOUND_INDEX, key_index.step(bit), BaseElement::ONE);
+        }
+    }
+    layout.set(
+        KEY_INDEX_CHECK_MASK_INDEX,
+        key_index.step(tree_depth - 1),
+        BaseElement::ONE,
+    );
+    bits::place_finish_mask(
+        &mut layout,
+        KEY_INDEX_FINISH_MASK_INDEX,
+        key_index,
+        KEY_INDEX_NUM_BITS,
+    );
+
+    layout.into_columns()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate_constraints<H: TreeHasher, E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    ark: &[E],
+    tree_ark: &[E],
+    transaction_setup_flag: E,
+    transaction_hash_flag: E,
+    hash_input_flag: E,
+    hash_flag: E,
+    transaction_finish_flag: E,
+    doubling_flag: E,
+    addition_flag: E,
+    schnorr_hash_digest_register_flag: &[E],
+    final_reduction_flag: E,
+    schnorr_hash_flag: E,
+    schnorr_hash_hold_flag: E,
     hash_internal_input_flags: &[E],
     range_proof_flag: E,
     range_proof_finish_flag: E,
     copy_values_flag: E,
+    copy_key_flag: E,
+    index_weight: E,
+    tx_digest_setup_flag: E,
+    nullifiers_finish_flag: E,
+    deposit_key: &[E],
+    subnet_id: E,
+    epoch: E,
+    signed_deltas: E,
+    delta_sign_weight: E,
 ) {
-    merkle::init::evaluate_constraints(result, current, next, ark, transaction_setup_flag);
-    // Enforce no change in registers representing keys
+    merkle::init::evaluate_constraints::<H, E>(
+        result,
+        current,
+        next,
+        tree_ark,
+        transaction_setup_flag,
+    );
+    // Enforce no change in registers representing keys, except for the sender's key of
+    // rekeying transactions, which is replaced by the new key copied in the receiver's key
+    // registers, and signed as such
+    let rekey_flag = next[REKEY_FLAG_POS];
     for i in 0..AFFINE_POINT_WIDTH {
         result.agg_constraint(
             VALUE_CONSTRAINT_RES + i,
             transaction_setup_flag,
             are_equal(
-                current[SENDER_INITIAL_POS + i],
+                not(rekey_flag) * current[SENDER_INITIAL_POS + i]
+                    + rekey_flag * next[RECEIVER_KEY_POINT_POS + i],
                 current[SENDER_UPDATED_POS + i],
             ),
         );
@@ -422,104 +1535,215 @@ pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
             ),
         );
     }
-    // Enforce no change in the receiver's nonce
+    // Enforce no change in the receiver's nonce, unless for refunds, which the receiver signs
+    // and whose nonce they consume
+    let delta_sign = next[DELTA_SIGN_POS];
     result.agg_constraint(
         VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 2,
         transaction_setup_flag,
         are_equal(
-            current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH + 1],
+            current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH + 1] + delta_sign,
             current[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH + 1],
         ),
     );
-    // Enforce that the change in balances cancels out
+    // Enforce no change in the fee collector's key and nonce
+    for i in 0..AFFINE_POINT_WIDTH {
+        result.agg_constraint(
+            VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 2 + 1 + i,
+            transaction_setup_flag,
+            are_equal(
+                current[COLLECTOR_INITIAL_POS + i],
+                current[COLLECTOR_UPDATED_POS + i],
+            ),
+        );
+    }
+    result.agg_constraint(
+        VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 3 + 1,
+        transaction_setup_flag,
+        are_equal(
+            current[COLLECTOR_INITIAL_POS + AFFINE_POINT_WIDTH + 1],
+            current[COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH + 1],
+        ),
+    );
+    // Enforce that the change in balances cancels out, the sender paying for both
+    // the amount sent to the receiver (or burnt, for withdrawals) and the fee.
+    // For deposits, the amount is minted instead
+    let withdrawal_flag = next[WITHDRAWAL_FLAG_POS];
+    let deposit_flag = next[DEPOSIT_FLAG_POS];
     result.agg_constraint(
         BALANCE_CONSTRAINT_RES,
         transaction_setup_flag,
         are_equal(
             current[SENDER_INITIAL_POS + AFFINE_POINT_WIDTH]
-                - current[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH],
+                - current[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH]
+                + deposit_flag * next[DELTA_COPY_POS],
             current[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH]
-                - current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH],
+                - current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH]
+                + current[COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH]
+                - current[COLLECTOR_INITIAL_POS + AFFINE_POINT_WIDTH]
+                + withdrawal_flag * next[DELTA_COPY_POS],
         ),
     );
-    // Enforce change in the sender's nonce
+    // Enforce that the sender's nonce is incremented by one, unless for deposits, no-ops,
+    // refunds and approvals. As the signed message contains the signer's nonce prior to the
+    // transaction, a signature cannot be replayed once its nonce has been consumed
+    let noop_flag = next[NOOP_FLAG_POS];
+    let approval_flag = next[APPROVAL_FLAG_POS];
     result.agg_constraint(
         NONCE_UPDATE_CONSTRAINT_RES,
         transaction_setup_flag,
         are_equal(
             current[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 1],
-            current[SENDER_INITIAL_POS + AFFINE_POINT_WIDTH + 1] + E::ONE,
+            current[SENDER_INITIAL_POS + AFFINE_POINT_WIDTH + 1]
+                + not(deposit_flag + noop_flag + delta_sign + approval_flag),
         ),
     );
 
-    // Enforce proper copying of keys at the beginning of the transaction
-    for (res_index, origin_index, copy_index) in [
-        (
-            SENDER_KEY_POINT_RES,
-            SENDER_INITIAL_POS,
-            SENDER_KEY_POINT_POS,
-        ),
-        (
-            RECEIVER_KEY_POINT_RES,
-            RECEIVER_INITIAL_POS,
-            RECEIVER_KEY_POINT_POS,
-        ),
-    ] {
-        for offset in 0..AFFINE_POINT_WIDTH {
-            result.agg_constraint(
-                res_index + offset,
-                transaction_setup_flag,
-                are_equal(next[copy_index + offset], current[origin_index + offset]),
-            );
-        }
+    // Enforce that the public key verifying the signature is the key of the sender's initial
+    // leaf, i.e. the leaf debited by the Merkle component, and not the one of the receiver or
+    // of the fee collector. Deposits and no-ops are not authorized by the sender, and are
+    // signed by the public deposit key instead, and refunds, which debit the receiver, are
+    // signed by the key of the receiver's initial leaf. Committee transfers and their
+    // approvals are signed by the key of a member, authenticated in the key tree of the
+    // committee from the leaf of its hash state. The Schnorr component reads the key from its
+    // copy registers at every addition of the scalar multiplication, so that the copy is
+    // enforced until the end of the signature verification, on slots of its own
+    let deposit_key_flag = deposit_flag + noop_flag;
+    let committee_flag = next[COMMITTEE_FLAG_POS];
+    for offset in 0..AFFINE_POINT_WIDTH {
+        result.agg_constraint(
+            SENDER_KEY_POINT_RES + offset,
+            transaction_setup_flag,
+            are_equal(
+                next[SENDER_KEY_POINT_POS + offset],
+                not(deposit_key_flag + delta_sign + committee_flag)
+                    * current[SENDER_INITIAL_POS + offset]
+                    + deposit_key_flag * deposit_key[offset]
+                    + delta_sign * current[RECEIVER_INITIAL_POS + offset]
+                    + committee_flag * current[KEY_PATH_POS + offset],
+            ),
+        );
+        result.agg_constraint(
+            SENDER_KEY_POINT_RES + offset,
+            copy_key_flag,
+            are_equal(
+                next[SENDER_KEY_POINT_POS + offset],
+                current[SENDER_KEY_POINT_POS + offset],
+            ),
+        );
+    }
+
+    // Enforce proper copying of the receiver's key at the beginning of the transaction. For
+    // withdrawals, the receiver's key registers hold the recipient address instead, enforced
+    // by assertions, and for split transfers they hold the signed outputs, the receiver being
+    // bound to its output by its index. Approvals carry the receiver's key of the transfer
+    // they approve instead, read from the first step
+    let split_flag = next[SPLIT_FLAG_POS];
+    for offset in 0..AFFINE_POINT_WIDTH {
+        result.agg_constraint(
+            RECEIVER_KEY_POINT_RES + offset,
+            transaction_setup_flag * (not(withdrawal_flag) - split_flag - approval_flag),
+            are_equal(
+                next[RECEIVER_KEY_POINT_POS + offset],
+                current[RECEIVER_INITIAL_POS + offset],
+            ),
+        );
+        result.agg_constraint(
+            RECEIVER_KEY_POINT_RES + offset,
+            transaction_setup_flag * approval_flag,
+            are_equal(
+                next[RECEIVER_KEY_POINT_POS + offset],
+                current[RECEIVER_KEY_POINT_POS + offset],
+            ),
+        );
     }
-    // Enforce proper computation of delta at the beginning of the transaction
+    // Enforce proper computation of delta and the fee at the beginning of the transaction.
+    // Withdrawals leave the receiver's balance untouched, and approvals all balances, the
+    // amount and fee they carry being the ones of the transfer they approve
     result.agg_constraint(
         DELTA_COPY_RES,
         transaction_setup_flag,
         are_equal(
-            next[DELTA_COPY_POS],
-            current[SENDER_INITIAL_POS + AFFINE_POINT_WIDTH]
-                - current[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH],
+            (not(withdrawal_flag) - approval_flag) * next[DELTA_COPY_POS],
+            current[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH]
+                - current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH],
         ),
     );
-    // Enforce proper copying of sigma and the nonce
-    for (res_index, origin_index, copy_index) in [
+    result.agg_constraint(
+        FEE_COPY_RES,
+        transaction_setup_flag,
+        are_equal(
+            not(approval_flag) * next[FEE_COPY_POS],
+            current[COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH]
+                - current[COLLECTOR_INITIAL_POS + AFFINE_POINT_WIDTH],
+        ),
+    );
+    // Enforce proper copying of sigma, the signer's nonce prior to the transaction, the
+    // latter being read as part of the signed message, and the receiver's updated balance.
+    // The signer is the receiver for refunds, and the sender otherwise, while approvals carry
+    // the nonce of the transfer they approve. The nonce register of the leaf also holds the
+    // domain separator of leaves
+    let sender_nonce = current[SENDER_INITIAL_POS + AFFINE_POINT_WIDTH + 1];
+    let receiver_nonce = current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH + 1];
+    for (res_index, origin, copy_index) in [
         (
             SIGMA_COPY_RES,
-            SENDER_UPDATED_POS + AFFINE_POINT_WIDTH,
+            current[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH],
             SIGMA_COPY_POS,
         ),
         (
-            NONCE_COPY_RES,
-            SENDER_INITIAL_POS + AFFINE_POINT_WIDTH + 1,
-            NONCE_COPY_POS,
+            RECEIVER_BALANCE_COPY_RES,
+            current[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH],
+            RECEIVER_BALANCE_COPY_POS,
         ),
     ] {
         result.agg_constraint(
             res_index,
             transaction_setup_flag,
-            are_equal(next[copy_index], current[origin_index]),
+            are_equal(next[copy_index], origin),
         );
     }
+    result.agg_constraint(
+        NONCE_COPY_RES,
+        transaction_setup_flag,
+        not(approval_flag)
+            * are_equal(
+                next[NONCE_COPY_POS],
+                sender_nonce + delta_sign * (receiver_nonce - sender_nonce) - E::from(LEAF_DOMAIN),
+            )
+            + approval_flag * are_equal(next[NONCE_COPY_POS], current[NONCE_COPY_POS]),
+    );
 
-    // Enforce proper copying of keys, delta, and nonce for the remainder of the transaction
+    // Enforce proper copying of the receiver's key, delta, nonce, fee, flags and balances for
+    // the remainder of the transaction. The signed values are copied up to the last step, from
+    // which they are carried to the first step of an approval
+    let carry_flag = not(transaction_setup_flag + copy_key_flag);
+    for offset in 0..AFFINE_POINT_WIDTH {
+        result.agg_constraint(
+            RECEIVER_KEY_POINT_RES + offset,
+            copy_key_flag + carry_flag * approval_flag,
+            are_equal(
+                next[RECEIVER_KEY_POINT_POS + offset],
+                current[RECEIVER_KEY_POINT_POS + offset],
+            ),
+        );
+    }
     for (res_index, copy_index) in [
-        (SENDER_KEY_POINT_RES, SENDER_KEY_POINT_POS),
-        (RECEIVER_KEY_POINT_RES, RECEIVER_KEY_POINT_POS),
+        (DELTA_COPY_RES, DELTA_COPY_POS),
+        (NONCE_COPY_RES, NONCE_COPY_POS),
+        (FEE_COPY_RES, FEE_COPY_POS),
     ] {
-        for offset in 0..AFFINE_POINT_WIDTH {
-            result.agg_constraint(
-                res_index + offset,
-                copy_values_flag,
-                are_equal(next[copy_index + offset], current[copy_index + offset]),
-            );
-        }
+        result.agg_constraint(
+            res_index,
+            copy_key_flag + carry_flag * approval_flag,
+            are_equal(next[copy_index], current[copy_index]),
+        );
     }
     for (res_index, copy_index) in [
-        (DELTA_COPY_RES, DELTA_COPY_POS),
         (SIGMA_COPY_RES, SIGMA_COPY_POS),
-        (NONCE_COPY_RES, NONCE_COPY_POS),
+        (WITHDRAWAL_FLAG_RES, WITHDRAWAL_FLAG_POS),
+        (DEPOSIT_FLAG_RES, DEPOSIT_FLAG_POS),
+        (RECEIVER_BALANCE_COPY_RES, RECEIVER_BALANCE_COPY_POS),
     ] {
         result.agg_constraint(
             res_index,
@@ -527,19 +1751,282 @@ pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
             are_equal(next[copy_index], current[copy_index]),
         );
     }
+    for offset in 0..MEMO_WIDTH {
+        result.agg_constraint(
+            MEMO_COPY_RES + offset,
+            copy_key_flag + (transaction_setup_flag + carry_flag) * approval_flag,
+            are_equal(next[MEMO_POS + offset], current[MEMO_POS + offset]),
+        );
+    }
 
-    merkle::update::evaluate_constraints(
+    // Enforce the split registers to be set at the beginning of the transaction, and copied
+    // for the remainder of the transaction up to the beginning of the next one, where they
+    // are read to chain the outputs of split transfers. The signed amount is the sum of all
+    // outputs for split transfers, and the amount sent so far accumulates the outputs of the
+    // current split transfer
+    let position_flags = &next[SPLIT_POSITION_POS..SPLIT_POSITION_POS + MAX_SPLIT_OUTPUTS];
+    let first_output_flag = position_flags[0];
+    let outputs = &next[RECEIVER_KEY_POINT_POS..RECEIVER_KEY_POINT_POS + AFFINE_POINT_WIDTH];
+    let mut outputs_amount = E::ZERO;
+    let mut output_amount = E::ZERO;
+    let mut output_receiver = E::ZERO;
+    for (output, &position_flag) in outputs.chunks(2).zip(position_flags.iter()) {
+        outputs_amount += output[0];
+        output_amount += position_flag * output[0];
+        output_receiver += position_flag * output[1];
+    }
+    result.agg_constraint(
+        SPLIT_REGISTERS_RES,
+        transaction_setup_flag,
+        is_binary(split_flag),
+    );
+    for (offset, &position_flag) in position_flags.iter().enumerate() {
+        result.agg_constraint(
+            SPLIT_REGISTERS_RES + 1 + offset,
+            transaction_setup_flag,
+            is_binary(position_flag),
+        );
+    }
+    result.agg_constraint(
+        SPLIT_REGISTERS_RES + 1 + MAX_SPLIT_OUTPUTS,
+        transaction_setup_flag,
+        are_equal(
+            next[SPLIT_AMOUNT_POS],
+            next[DELTA_COPY_POS] + split_flag * (outputs_amount - next[DELTA_COPY_POS]),
+        ),
+    );
+    result.agg_constraint(
+        SPLIT_REGISTERS_RES + 2 + MAX_SPLIT_OUTPUTS,
+        transaction_setup_flag,
+        are_equal(
+            next[SPLIT_SENT_POS],
+            not(first_output_flag) * current[SPLIT_SENT_POS] + next[DELTA_COPY_POS],
+        ),
+    );
+    result.agg_constraint(
+        SPLIT_REGISTERS_RES + 3 + MAX_SPLIT_OUTPUTS,
+        transaction_setup_flag,
+        are_equal(
+            next[SPLIT_REMAINING_POS],
+            next[SPLIT_AMOUNT_POS] - next[SPLIT_SENT_POS],
+        ),
+    );
+    for offset in 0..SPLIT_REGISTER_WIDTH {
+        result.agg_constraint(
+            SPLIT_REGISTERS_RES + offset,
+            not(transaction_setup_flag),
+            are_equal(
+                next[SPLIT_FLAG_POS + offset],
+                current[SPLIT_FLAG_POS + offset],
+            ),
+        );
+    }
+
+    // Enforce the accumulation of the receiver's and sender's indices from the bits of their
+    // authentication paths, starting over at the beginning of each transaction. Approvals
+    // carry the indices of the transfer they approve instead, which are part of the message
+    for (res_index, index_pos, bit_pos) in [
+        (RECEIVER_INDEX_RES, RECEIVER_INDEX_POS, RECEIVER_BIT_POS),
+        (SENDER_INDEX_RES, SENDER_INDEX_POS, SENDER_BIT_POS),
+    ] {
+        bits::enforce_weighted_step(
+            result,
+            res_index,
+            current,
+            next,
+            index_pos,
+            bit_pos,
+            not(transaction_setup_flag) + transaction_setup_flag * approval_flag,
+            index_weight * not(approval_flag),
+        );
+    }
+
+    // Enforce the chaining of split transfers, whose outputs are performed by consecutive
+    // transactions in order, the previous transaction's split registers being read from the
+    // current step. Any transaction has exactly one position flag, which is the first one
+    // unless for split transfers, which can neither withdraw nor deposit their amount
+    let mut num_position_flags = E::ZERO;
+    let mut position_flags_shift = E::ZERO;
+    for (offset, &position_flag) in position_flags.iter().enumerate() {
+        num_position_flags += position_flag;
+        if offset > 0 {
+            position_flags_shift += position_flag * not(current[SPLIT_POSITION_POS + offset - 1]);
+        }
+    }
+    result.agg_constraint(
+        SPLIT_CHAIN_RES,
+        transaction_setup_flag,
+        are_equal(num_position_flags, E::ONE),
+    );
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 1,
+        transaction_setup_flag,
+        not(split_flag) * not(first_output_flag),
+    );
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 2,
+        transaction_setup_flag,
+        split_flag * (withdrawal_flag + deposit_flag),
+    );
+    // Any output but the first one follows the previous output of a split transfer of the
+    // same signed amount, and the first one follows a completed split transfer, if any. The
+    // latter two constraints share their result, being selected by the first position flag
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 3,
+        transaction_setup_flag,
+        not(first_output_flag) * not(current[SPLIT_FLAG_POS]),
+    );
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 4,
+        transaction_setup_flag,
+        position_flags_shift,
+    );
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 5,
+        transaction_setup_flag,
+        first_output_flag * (current[SPLIT_AMOUNT_POS] - current[SPLIT_SENT_POS])
+            + not(first_output_flag) * are_equal(next[SPLIT_AMOUNT_POS], current[SPLIT_AMOUNT_POS]),
+    );
+    // The amount and receiver's index of a split transfer are the ones of the output at its
+    // position, the latter being checked once the receiver's index is accumulated
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 6,
+        transaction_setup_flag,
+        split_flag * are_equal(next[DELTA_COPY_POS], output_amount),
+    );
+    result.agg_constraint(
+        SPLIT_CHAIN_RES + 6,
+        range_proof_finish_flag,
+        split_flag * are_equal(next[RECEIVER_INDEX_POS], output_receiver),
+    );
+
+    // Enforce no-op transactions to be neither withdrawals, deposits nor split transfers, and
+    // to transfer no amount nor fee. Their sender's nonce being left untouched as well, they
+    // do not update the tree. The no-op flag is copied along the transaction, up to the
+    // update of the running digest of transactions, which skips no-ops
+    result.agg_constraint(NOOP_RES, transaction_setup_flag, is_binary(noop_flag));
+    result.agg_constraint(
+        NOOP_RES,
+        copy_values_flag,
+        are_equal(next[NOOP_FLAG_POS], current[NOOP_FLAG_POS]),
+    );
+    result.agg_constraint(
+        NOOP_RES + 1,
+        transaction_setup_flag,
+        noop_flag * (withdrawal_flag + deposit_flag + split_flag),
+    );
+    result.agg_constraint(
+        NOOP_RES + 2,
+        transaction_setup_flag,
+        noop_flag * next[DELTA_COPY_POS],
+    );
+    result.agg_constraint(
+        NOOP_RES + 3,
+        transaction_setup_flag,
+        noop_flag * next[FEE_COPY_POS],
+    );
+
+    // Likewise, enforce rekeying transactions to be neither withdrawals, deposits, split
+    // transfers nor no-ops, and to transfer no amount nor fee, so that the sender's balance is
+    // left untouched. As the receiver's key is copied from the receiver's leaf once the sender
+    // is updated, the receiver's leaf holds the new key, i.e. is the sender's own leaf or a
+    // leaf of the same key
+    result.agg_constraint(REKEY_RES, transaction_setup_flag, is_binary(rekey_flag));
+    result.agg_constraint(
+        REKEY_RES,
+        copy_values_flag,
+        are_equal(next[REKEY_FLAG_POS], current[REKEY_FLAG_POS]),
+    );
+    result.agg_constraint(
+        REKEY_RES + 1,
+        transaction_setup_flag,
+        rekey_flag * (withdrawal_flag + deposit_flag + split_flag + noop_flag),
+    );
+    result.agg_constraint(
+        REKEY_RES + 2,
+        transaction_setup_flag,
+        rekey_flag * next[DELTA_COPY_POS],
+    );
+    result.agg_constraint(
+        REKEY_RES + 3,
+        transaction_setup_flag,
+        rekey_flag * next[FEE_COPY_POS],
+    );
+
+    // Enforce the sign of delta to be binary, and unset unless the proof supports signed
+    // deltas. The sign alone flags refunds, which transfer no fee, the receiver not paying for
+    // the transaction it authorizes. The sign is copied along the transaction, up to the end
+    // of the range proof of delta
+    result.agg_constraint(
+        DELTA_SIGN_RES,
+        transaction_setup_flag,
+        delta_sign * (delta_sign - signed_deltas),
+    );
+    result.agg_constraint(
+        DELTA_SIGN_RES,
+        copy_values_flag,
+        are_equal(next[DELTA_SIGN_POS], current[DELTA_SIGN_POS]),
+    );
+    result.agg_constraint(
+        DELTA_SIGN_RES + 1,
+        transaction_setup_flag,
+        delta_sign * (withdrawal_flag + deposit_flag + split_flag + noop_flag + rekey_flag),
+    );
+    result.agg_constraint(
+        DELTA_SIGN_RES + 2,
+        transaction_setup_flag,
+        delta_sign * next[FEE_COPY_POS],
+    );
+
+    merkle::update::evaluate_constraints::<H, E>(
         result,
         current,
         next,
-        ark,
+        tree_ark,
         transaction_hash_flag,
         hash_input_flag,
         hash_flag,
         transaction_finish_flag,
     );
 
-    // Set up the internal inputs
+    // Above the lowest common ancestor of the sender's and receiver's leaves, the receiver's
+    // initial path hashes the same nodes as the sender's updated path, from the level hashing
+    // both leaves' ancestors onwards. The shared path flag is set over these levels, changing
+    // only between two levels and never unset before the end of the hashes, and ties the hash
+    // states of both paths once the inputs of each level are in place, so that the siblings
+    // shared by both paths are only provided once
+    let shared_path_flag = next[SHARED_PATH_FLAG_POS];
+    let hash_init_flag = transaction_hash_flag * hash_input_flag;
+    result.agg_constraint(
+        SHARED_PATH_RES,
+        transaction_hash_flag,
+        is_binary(shared_path_flag),
+    );
+    result.agg_constraint(
+        SHARED_PATH_RES,
+        transaction_hash_flag * not(hash_input_flag),
+        are_equal(shared_path_flag, current[SHARED_PATH_FLAG_POS]),
+    );
+    result.agg_constraint(
+        SHARED_PATH_RES,
+        hash_init_flag,
+        current[SHARED_PATH_FLAG_POS] * not(shared_path_flag),
+    );
+    for i in 0..HASH_STATE_WIDTH {
+        result.agg_constraint(
+            SHARED_PATH_RES + 1 + i,
+            hash_init_flag,
+            shared_path_flag
+                * are_equal(next[RECEIVER_INITIAL_POS + i], next[SENDER_UPDATED_POS + i]),
+        );
+    }
+
+    // Set up the internal inputs, absorbed by the Schnorr hash at the steps selected by any of
+    // the input copy masks
+    let mut schnorr_hash_input_flag = E::ZERO;
+    for &flag in hash_internal_input_flags.iter() {
+        schnorr_hash_input_flag += flag;
+    }
     let mut hash_internal_inputs = [E::ZERO; HASH_RATE_WIDTH];
     for k in 0..schnorr::constants::NUM_HASH_ITER - 1 {
         for i in 0..HASH_RATE_WIDTH {
@@ -547,15 +2034,51 @@ pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
             let from_receiver = !from_sender && (k * HASH_RATE_WIDTH + i < AFFINE_POINT_WIDTH * 2);
             let from_delta = k * HASH_RATE_WIDTH + i == AFFINE_POINT_WIDTH * 2;
             let from_nonce = k * HASH_RATE_WIDTH + i == AFFINE_POINT_WIDTH * 2 + 1;
+            let from_fee = k * HASH_RATE_WIDTH + i == AFFINE_POINT_WIDTH * 2 + 2;
+            let from_withdrawal_flag = k * HASH_RATE_WIDTH + i == AFFINE_POINT_WIDTH * 2 + 3;
+            let from_memo = (AFFINE_POINT_WIDTH * 2 + 4..AFFINE_POINT_WIDTH * 2 + 4 + MEMO_WIDTH)
+                .contains(&(k * HASH_RATE_WIDTH + i));
+            let from_subnet = k * HASH_RATE_WIDTH + i == SUBNET_ID_MESSAGE_INDEX;
+            let from_epoch = k * HASH_RATE_WIDTH + i == EPOCH_MESSAGE_INDEX;
+            let from_sender_index = k * HASH_RATE_WIDTH + i == SENDER_INDEX_MESSAGE_INDEX;
+            let from_receiver_index = k * HASH_RATE_WIDTH + i == RECEIVER_INDEX_MESSAGE_INDEX;
 
             let cell = if from_sender {
                 next[SENDER_KEY_POINT_POS + k * HASH_RATE_WIDTH + i]
             } else if from_receiver {
                 next[RECEIVER_KEY_POINT_POS + k * HASH_RATE_WIDTH + i - AFFINE_POINT_WIDTH]
             } else if from_delta {
-                next[DELTA_COPY_POS]
+                next[SPLIT_AMOUNT_POS]
             } else if from_nonce {
-                next[NONCE_COPY_POS]
+                // The outputs of a split transfer sign the nonce prior to the first of them
+                let mut position = E::ZERO;
+                for offset in 1..MAX_SPLIT_OUTPUTS {
+                    position += E::from(offset as u8) * next[SPLIT_POSITION_POS + offset];
+                }
+                next[NONCE_COPY_POS] - position
+            } else if from_fee {
+                next[FEE_COPY_POS]
+            } else if from_withdrawal_flag {
+                next[WITHDRAWAL_FLAG_POS]
+                    + E::from(2u8) * next[SPLIT_FLAG_POS]
+                    + E::from(3u8) * next[REKEY_FLAG_POS]
+                    + E::from(4u8) * next[DELTA_SIGN_POS]
+                    + E::from(5u8) * next[COMMITTEE_FLAG_POS]
+            } else if from_memo {
+                next[MEMO_POS + k * HASH_RATE_WIDTH + i - AFFINE_POINT_WIDTH * 2 - 4]
+            } else if from_subnet {
+                // The subnet identifier is a public input, absorbed as a constant so that a
+                // signature only verifies within the subnet it was produced for
+                subnet_id
+            } else if from_epoch {
+                // All transactions of the batch hence sign the epoch of the public inputs
+                epoch
+            } else if from_sender_index {
+                // The indices are accumulated from the bits of the authentication paths, so
+                // that a signature cannot be applied to other leaves holding the same keys
+                next[SENDER_INDEX_POS]
+            } else if from_receiver_index {
+                not(next[SPLIT_FLAG_POS]) * next[RECEIVER_INDEX_POS]
             } else {
                 E::ZERO
             };
@@ -573,14 +2096,169 @@ pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
         addition_flag,
         schnorr_hash_digest_register_flag,
         &next[SENDER_KEY_POINT_POS..SENDER_KEY_POINT_POS + AFFINE_POINT_WIDTH],
-        final_point_addition_flag,
+        final_reduction_flag,
         schnorr_hash_flag,
-        copy_hash_flag,
+        schnorr_hash_input_flag,
+        schnorr_hash_hold_flag,
         &hash_internal_inputs,
     );
 
+    // Enforce the hash of the running digest of transactions along with the (sender, receiver,
+    // delta) tuple of the transaction, whose first chunk is read at the transition to Schnorr
+    // and the next ones are fed along the internal inputs of the Schnorr hash
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            TX_DIGEST_HASH_RES + i,
+            tx_digest_setup_flag,
+            are_equal(next[TX_DIGEST_HASH_POS + i], current[TX_DIGEST_POS + i]),
+        );
+        result.agg_constraint(
+            TX_DIGEST_HASH_RES + HASH_RATE_WIDTH + i,
+            tx_digest_setup_flag,
+            are_equal(
+                next[TX_DIGEST_HASH_POS + HASH_RATE_WIDTH + i],
+                next[SENDER_KEY_POINT_POS + i],
+            ),
+        );
+    }
+    // Once the tuple is absorbed, the hash state is reset to the sender's key followed by its
+    // nonce and a domain separator, which are permuted into the nullifier of the transaction.
+    // The nullifier is then merged with the commitment to the previous nullifiers. The hash
+    // state is finally reset to the sender's key followed by a zero and another domain
+    // separator, permuted into the hash whose low bits are the index derived from the key
+    let tx_digest_finish_flag = hash_internal_input_flags[TX_TUPLE_NUM_CHUNKS - 1];
+    let nullifier_input_flag = hash_internal_input_flags[TX_TUPLE_NUM_CHUNKS];
+    let key_index_input_flag = hash_internal_input_flags[TX_TUPLE_NUM_CHUNKS + 1];
+    let mut tx_digest_hash_inputs = [E::ZERO; HASH_RATE_WIDTH];
+    for (k, &flag) in hash_internal_input_flags[..TX_TUPLE_NUM_CHUNKS - 1]
+        .iter()
+        .enumerate()
+    {
+        for (i, input) in tx_digest_hash_inputs.iter_mut().enumerate() {
+            let index = (k + 1) * HASH_RATE_WIDTH + i;
+            if index < TX_TUPLE_WIDTH {
+                *input += flag * next[SENDER_KEY_POINT_POS + index];
+            }
+        }
+    }
+    for (i, input) in tx_digest_hash_inputs.iter_mut().enumerate() {
+        let nullifier_cell = if i + HASH_RATE_WIDTH < AFFINE_POINT_WIDTH {
+            next[SENDER_KEY_POINT_POS + HASH_RATE_WIDTH + i]
+        } else if i + HASH_RATE_WIDTH == AFFINE_POINT_WIDTH {
+            next[NONCE_COPY_POS]
+        } else {
+            E::from(NULLIFIER_DOMAIN)
+        };
+        let key_index_cell = if i + HASH_RATE_WIDTH < AFFINE_POINT_WIDTH {
+            next[SENDER_KEY_POINT_POS + HASH_RATE_WIDTH + i]
+        } else if i + HASH_RATE_WIDTH == HASH_STATE_WIDTH - 1 {
+            E::from(ACCOUNT_INDEX_DOMAIN)
+        } else {
+            E::ZERO
+        };
+        *input += tx_digest_finish_flag * nullifier_cell;
+        *input += nullifier_input_flag * next[NULLIFIERS_POS + i];
+        *input += key_index_input_flag * key_index_cell;
+    }
+    rescue::enforce_round(
+        &mut result[TX_DIGEST_HASH_RES..TX_DIGEST_HASH_RES + HASH_STATE_WIDTH],
+        &current[TX_DIGEST_HASH_POS..TX_DIGEST_HASH_POS + HASH_STATE_WIDTH],
+        &next[TX_DIGEST_HASH_POS..TX_DIGEST_HASH_POS + HASH_STATE_WIDTH],
+        ark,
+        schnorr_hash_flag,
+    );
+    // As for the hash of the signed message, the hash state is held once all the chunks are
+    // absorbed
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            TX_DIGEST_HASH_RES + i,
+            schnorr_hash_input_flag + schnorr_hash_hold_flag
+                - tx_digest_finish_flag
+                - key_index_input_flag,
+            are_equal(
+                next[TX_DIGEST_HASH_POS + i],
+                current[TX_DIGEST_HASH_POS + i],
+            ),
+        );
+        result.agg_constraint(
+            TX_DIGEST_HASH_RES + i,
+            tx_digest_finish_flag + key_index_input_flag,
+            are_equal(next[TX_DIGEST_HASH_POS + i], next[SENDER_KEY_POINT_POS + i]),
+        );
+        result.agg_constraint(
+            TX_DIGEST_HASH_RES + HASH_RATE_WIDTH + i,
+            schnorr_hash_input_flag,
+            are_equal(
+                next[TX_DIGEST_HASH_POS + HASH_RATE_WIDTH + i],
+                tx_digest_hash_inputs[i],
+            ),
+        );
+        result.agg_constraint(
+            TX_DIGEST_HASH_RES + HASH_RATE_WIDTH + i,
+            schnorr_hash_hold_flag,
+            are_equal(
+                next[TX_DIGEST_HASH_POS + HASH_RATE_WIDTH + i],
+                current[TX_DIGEST_HASH_POS + HASH_RATE_WIDTH + i],
+            ),
+        );
+    }
+
+    // Enforce the running digest of transactions to be carried over, and updated with the
+    // output of its hash state unless for no-ops and approvals
+    let skip_flag = current[NOOP_FLAG_POS] + current[APPROVAL_FLAG_POS];
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            TX_DIGEST_RES + i,
+            E::ONE,
+            are_equal(
+                next[TX_DIGEST_POS + i],
+                current[TX_DIGEST_POS + i]
+                    + tx_digest_finish_flag
+                        * not(skip_flag)
+                        * (current[TX_DIGEST_HASH_POS + i] - current[TX_DIGEST_POS + i]),
+            ),
+        );
+    }
+
+    // Enforce the commitment to nullifiers to be carried over, and updated with the output of
+    // the hash state unless for deposits, no-ops and approvals, which consume no nonce
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            NULLIFIERS_RES + i,
+            E::ONE,
+            are_equal(
+                next[NULLIFIERS_POS + i],
+                current[NULLIFIERS_POS + i]
+                    + nullifiers_finish_flag
+                        * not(current[DEPOSIT_FLAG_POS] + skip_flag)
+                        * (current[TX_DIGEST_HASH_POS + i] - current[NULLIFIERS_POS + i]),
+            ),
+        );
+    }
+
+    // Enforce the number and volume of transactions to be carried over, and respectively
+    // incremented and increased by delta along with the running digest of transactions, i.e.
+    // unless for no-ops and approvals
+    result.agg_constraint(
+        TX_COUNT_RES,
+        E::ONE,
+        are_equal(
+            next[TX_COUNT_POS],
+            current[TX_COUNT_POS] + tx_digest_finish_flag * not(skip_flag),
+        ),
+    );
+    result.agg_constraint(
+        TX_VOLUME_RES,
+        E::ONE,
+        are_equal(
+            next[TX_VOLUME_POS],
+            current[TX_VOLUME_POS]
+                + tx_digest_finish_flag * not(skip_flag) * current[DELTA_COPY_POS],
+        ),
+    );
+
     // Enforce constraints for the range proofs
-    enforce_double_and_add_step(
+    bits::enforce_double_and_add_step(
         result,
         current,
         next,
@@ -588,7 +2266,7 @@ pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
         DELTA_BIT_POS,
         range_proof_flag,
     );
-    enforce_double_and_add_step(
+    bits::enforce_double_and_add_step(
         result,
         current,
         next,
@@ -596,15 +2274,631 @@ pub fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
         SIGMA_BIT_POS,
         range_proof_flag,
     );
-    // Enforce that the values at the ends of the range proofs are actually the values promised
+    bits::enforce_double_and_add_step(
+        result,
+        current,
+        next,
+        FEE_ACCUMULATE_POS,
+        FEE_BIT_POS,
+        range_proof_flag,
+    );
+    bits::enforce_double_and_add_step(
+        result,
+        current,
+        next,
+        RECEIVER_BALANCE_ACCUMULATE_POS,
+        RECEIVER_BALANCE_BIT_POS,
+        range_proof_flag,
+    );
+    // Enforce that the values at the ends of the range proofs are actually the values promised.
+    // Negative deltas are decomposed offset by the weight of their sign, which is only non-zero
+    // for proofs supporting signed deltas
     result.agg_constraint(
         DELTA_RANGE_RES,
         range_proof_finish_flag,
-        are_equal(next[DELTA_ACCUMULATE_POS], next[DELTA_COPY_POS]),
+        are_equal(
+            next[DELTA_ACCUMULATE_POS] - delta_sign_weight * next[DELTA_SIGN_POS],
+            next[DELTA_COPY_POS],
+        ),
     );
     result.agg_constraint(
         SIGMA_RANGE_RES,
         range_proof_finish_flag,
-        are_equal(next[DELTA_ACCUMULATE_POS], next[DELTA_COPY_POS]),
+        are_equal(next[SIGMA_ACCUMULATE_POS], next[SIGMA_COPY_POS]),
+    );
+    result.agg_constraint(
+        FEE_RANGE_RES,
+        range_proof_finish_flag,
+        are_equal(next[FEE_ACCUMULATE_POS], next[FEE_COPY_POS]),
+    );
+    result.agg_constraint(
+        RECEIVER_BALANCE_RANGE_RES,
+        range_proof_finish_flag,
+        are_equal(
+            next[RECEIVER_BALANCE_ACCUMULATE_POS],
+            next[RECEIVER_BALANCE_COPY_POS],
+        ),
+    );
+}
+
+/// Enforces the verification of committee transfers, sent from accounts whose key is the
+/// pseudo-key of a committee, i.e. the root of the key tree of its members followed by its
+/// threshold `t`, and signed by `t` distinct members.
+///
+/// A committee transfer is signed by a first member, and followed by `t - 1` approvals, each
+/// signed by one more member, which carry the signed values of the transfer and leave all
+/// balances and nonces untouched. The key of each member is authenticated in the key tree
+/// along the Merkle phase, and the indices of the members' keys are strictly increasing, the
+/// gap between consecutive indices minus one being decomposed over `tree_depth` bits along
+/// the decompositions of the hashes of the keys, so that no member signs twice. The number of
+/// approvals left is carried from one transaction to the next, and is zero once they are over.
+///
+/// The last coordinate of the key verifying the signature is non-zero for all transactions,
+/// which rules out the pseudo-key of a committee, so that its account is only debited by
+/// committee transfers.
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate_committee_constraints<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    ark: &[E],
+    transaction_setup_flag: E,
+    transaction_hash_flag: E,
+    hash_input_flag: E,
+    hash_flag: E,
+    transaction_finish_flag: E,
+    copy_values_flag: E,
+    copy_key_flag: E,
+    index_weight: E,
+    key_index_flags: &[E],
+) {
+    // Enforce the committee and approval flags to be binary and copied along the transaction.
+    // The approval flag is read at the last step of the previous transaction, and thus set from
+    // the first step. Approvals follow committee transfers, which are plain transfers
+    let committee_flag = next[COMMITTEE_FLAG_POS];
+    let approval_flag = next[APPROVAL_FLAG_POS];
+    for (res_index, flag_pos) in [
+        (COMMITTEE_RES, COMMITTEE_FLAG_POS),
+        (COMMITTEE_RES + 1, APPROVAL_FLAG_POS),
+    ] {
+        result.agg_constraint(res_index, transaction_setup_flag, is_binary(next[flag_pos]));
+        result.agg_constraint(
+            res_index,
+            copy_values_flag,
+            are_equal(next[flag_pos], current[flag_pos]),
+        );
+    }
+    result.agg_constraint(
+        COMMITTEE_RES + 2,
+        transaction_setup_flag,
+        are_equal(approval_flag, current[APPROVAL_FLAG_POS]),
+    );
+    result.agg_constraint(
+        COMMITTEE_RES + 3,
+        transaction_setup_flag,
+        approval_flag * not(committee_flag)
+            + committee_flag
+                * (next[WITHDRAWAL_FLAG_POS]
+                    + next[DEPOSIT_FLAG_POS]
+                    + next[SPLIT_FLAG_POS]
+                    + next[NOOP_FLAG_POS]
+                    + next[REKEY_FLAG_POS]
+                    + next[DELTA_SIGN_POS]),
+    );
+
+    // Enforce the authentication path of the member's key in the key tree, hashed with Rescue
+    // alongside the Merkle authentication paths. The member's leaf holds its key followed by a
+    // zero balance and nonce, and the path ends at the root of the key tree
+    merkle::update::evaluate_merkle_path_auth::<Rescue63, E>(
+        &mut result[KEY_PATH_RES..KEY_TREE_ROOT_RES],
+        &current[KEY_PATH_POS..SIGNING_KEY_INVERSE_POS],
+        &next[KEY_PATH_POS..SIGNING_KEY_INVERSE_POS],
+        ark,
+        transaction_hash_flag,
+        hash_input_flag,
+        hash_flag,
+    );
+    result.agg_constraint(
+        KEY_TREE_ROOT_RES,
+        transaction_setup_flag,
+        committee_flag * current[KEY_PATH_POS + AFFINE_POINT_WIDTH],
+    );
+    result.agg_constraint(
+        KEY_TREE_ROOT_RES + 1,
+        transaction_setup_flag,
+        committee_flag
+            * are_equal(
+                current[KEY_PATH_POS + AFFINE_POINT_WIDTH + 1],
+                E::from(LEAF_DOMAIN),
+            ),
+    );
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            KEY_TREE_ROOT_RES + i,
+            transaction_finish_flag,
+            committee_flag * are_equal(next[KEY_PATH_POS + i], current[COMMITTEE_ROOT_POS + i]),
+        );
+    }
+
+    // Enforce the root of the key tree and the number of approvals left to be read from the
+    // pseudo-key of the sender's leaf, or carried from the previous transaction for approvals,
+    // and copied along the transaction. No approval is left once the approvals are over
+    let threshold = current[SENDER_INITIAL_POS + HASH_RATE_WIDTH];
+    for i in 0..HASH_RATE_WIDTH {
+        let sender_root = current[SENDER_INITIAL_POS + i];
+        result.agg_constraint(
+            COMMITTEE_ROOT_RES + i,
+            transaction_setup_flag,
+            are_equal(
+                next[COMMITTEE_ROOT_POS + i],
+                sender_root + approval_flag * (current[COMMITTEE_ROOT_POS + i] - sender_root),
+            ),
+        );
+        result.agg_constraint(
+            COMMITTEE_ROOT_RES + i,
+            not(transaction_setup_flag),
+            are_equal(
+                next[COMMITTEE_ROOT_POS + i],
+                current[COMMITTEE_ROOT_POS + i],
+            ),
+        );
+    }
+    result.agg_constraint(
+        APPROVALS_LEFT_RES,
+        transaction_setup_flag,
+        are_equal(
+            next[APPROVALS_LEFT_POS],
+            committee_flag * (threshold - E::ONE)
+                + approval_flag * (current[APPROVALS_LEFT_POS] - threshold),
+        ),
     );
+    result.agg_constraint(
+        APPROVALS_LEFT_RES,
+        not(transaction_setup_flag),
+        are_equal(next[APPROVALS_LEFT_POS], current[APPROVALS_LEFT_POS]),
+    );
+    result.agg_constraint(
+        APPROVALS_LEFT_RES + 1,
+        not(transaction_setup_flag + copy_key_flag),
+        not(approval_flag) * current[APPROVALS_LEFT_POS],
+    );
+
+    // Enforce the accumulation of the member's index from the bits of its authentication
+    // path, and the copy of the index of the previous transaction's member
+    bits::enforce_weighted_step(
+        result,
+        MEMBER_INDEX_RES,
+        current,
+        next,
+        MEMBER_INDEX_POS,
+        KEY_PATH_POS + HASH_STATE_WIDTH,
+        not(transaction_setup_flag),
+        index_weight,
+    );
+    result.agg_constraint(
+        MEMBER_INDEX_RES + 1,
+        transaction_setup_flag,
+        are_equal(next[PREVIOUS_MEMBER_INDEX_POS], current[MEMBER_INDEX_POS]),
+    );
+    result.agg_constraint(
+        MEMBER_INDEX_RES + 1,
+        not(transaction_setup_flag),
+        are_equal(
+            next[PREVIOUS_MEMBER_INDEX_POS],
+            current[PREVIOUS_MEMBER_INDEX_POS],
+        ),
+    );
+
+    // Enforce the decomposition of the gap between the indices of the previous and current
+    // members, minus one, whose `tree_depth` low bits are checked for approvals, so that the
+    // index of each member is greater than the one of the previous member
+    let flag = |index: usize| key_index_flags[index - KEY_INDEX_SETUP_MASK_INDEX];
+    let step_flag = flag(KEY_INDEX_STEP_MASK_INDEX);
+    let bit = next[MEMBER_GAP_BIT_POS];
+    result.agg_constraint(MEMBER_GAP_RES, step_flag, is_binary(bit));
+    result.agg_constraint(
+        MEMBER_GAP_RES + 1,
+        step_flag,
+        are_equal(
+            next[MEMBER_GAP_ACCUMULATE_POS],
+            current[MEMBER_GAP_ACCUMULATE_POS],
+        ),
+    );
+    result.agg_constraint(MEMBER_GAP_RES + 1, flag(KEY_INDEX_WEIGHTS_INDEX), -bit);
+    result.agg_constraint(
+        MEMBER_GAP_RES + 1,
+        flag(KEY_INDEX_SETUP_MASK_INDEX),
+        next[MEMBER_GAP_ACCUMULATE_POS],
+    );
+    result.agg_constraint(
+        MEMBER_GAP_RES + 2,
+        flag(KEY_INDEX_CHECK_MASK_INDEX),
+        approval_flag
+            * are_equal(
+                next[MEMBER_GAP_ACCUMULATE_POS] + E::ONE,
+                next[MEMBER_INDEX_POS] - next[PREVIOUS_MEMBER_INDEX_POS],
+            ),
+    );
+
+    // Enforce approvals to carry the amount and fee of the transfer they approve, on slots of
+    // their own as their copies are bound to the balances of other transactions
+    for (offset, copy_index) in [DELTA_COPY_POS, FEE_COPY_POS].iter().enumerate() {
+        result.agg_constraint(
+            APPROVAL_COPY_RES + offset,
+            transaction_setup_flag,
+            approval_flag * are_equal(next[*copy_index], current[*copy_index]),
+        );
+    }
+
+    // Enforce the last coordinate of the key verifying the signature to be non-zero
+    result.agg_constraint(
+        SIGNING_KEY_INVERSE_RES,
+        transaction_setup_flag,
+        are_equal(
+            next[SENDER_KEY_POINT_POS + AFFINE_POINT_WIDTH - 1] * next[SIGNING_KEY_INVERSE_POS],
+            E::ONE,
+        ),
+    );
+}
+
+/// Enforces that the sender's and receiver's indices are the low bits of the hashes of their
+/// public keys, as derived by `account_index`, for proofs binding the indices of accounts to
+/// their keys.
+///
+/// The hash of the sender's key is computed by the last iteration of the hash state of the
+/// digest of transactions, and the one of the receiver's key alongside, by a hash state of its
+/// own re-initialized at every input of the Schnorr hash. Both are then decomposed into their
+/// bits, least significant first, the value accumulated from the bits being checked against
+/// the index once `tree_depth` bits are inserted and against the hash once all are. As the
+/// modulus fits in as many bits, the accumulated value is compared along the way with the
+/// modulus minus one, so that the decomposition of the hash is the canonical one.
+///
+/// The deposit key signing deposits and no-ops, the receiver's key signing refunds and the
+/// members' keys signing committee transfers are not bound to the sender's leaf, nor the
+/// recipient of withdrawals and the outputs of split transfers read in place of the receiver's
+/// key to the receiver's leaf, nor the leaf left untouched by no-ops.
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate_key_index_constraints<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    ark: &[E],
+    tx_digest_setup_flag: E,
+    schnorr_hash_flag: E,
+    schnorr_hash_hold_flag: E,
+    hash_internal_input_flags: &[E],
+    key_index_flags: &[E],
+) {
+    let mut schnorr_hash_input_flag = E::ZERO;
+    for &flag in hash_internal_input_flags.iter() {
+        schnorr_hash_input_flag += flag;
+    }
+
+    // Enforce the hash of the receiver's key, which is held once the hash of the signed
+    // message is over
+    rescue::enforce_round(
+        &mut result[KEY_INDEX_HASH_RES..KEY_INDEX_HASH_RES + HASH_STATE_WIDTH],
+        &current[KEY_INDEX_HASH_POS..KEY_INDEX_HASH_POS + HASH_STATE_WIDTH],
+        &next[KEY_INDEX_HASH_POS..KEY_INDEX_HASH_POS + HASH_STATE_WIDTH],
+        ark,
+        schnorr_hash_flag,
+    );
+    for i in 0..HASH_STATE_WIDTH {
+        let input = if i < AFFINE_POINT_WIDTH {
+            next[RECEIVER_KEY_POINT_POS + i]
+        } else if i == HASH_STATE_WIDTH - 1 {
+            E::from(ACCOUNT_INDEX_DOMAIN)
+        } else {
+            E::ZERO
+        };
+        result.agg_constraint(
+            KEY_INDEX_HASH_RES + i,
+            tx_digest_setup_flag + schnorr_hash_input_flag,
+            are_equal(next[KEY_INDEX_HASH_POS + i], input),
+        );
+        result.agg_constraint(
+            KEY_INDEX_HASH_RES + i,
+            schnorr_hash_hold_flag,
+            are_equal(
+                next[KEY_INDEX_HASH_POS + i],
+                current[KEY_INDEX_HASH_POS + i],
+            ),
+        );
+    }
+
+    // Enforce the decompositions of the hashes of the keys. The sender's index is not bound
+    // for deposits, no-ops, refunds and committee transfers, and the receiver's one for
+    // withdrawals, outputs of split transfers and no-ops
+    let noop_flag = next[NOOP_FLAG_POS];
+    for (res, bit_pos, accumulate_pos, bound_pos, index_pos, hash_pos, check_flag) in [
+        (
+            SENDER_KEY_INDEX_RES,
+            SENDER_KEY_INDEX_BIT_POS,
+            SENDER_KEY_INDEX_ACCUMULATE_POS,
+            SENDER_KEY_INDEX_BOUND_POS,
+            SENDER_INDEX_POS,
+            TX_DIGEST_HASH_POS,
+            not(next[DEPOSIT_FLAG_POS]
+                + noop_flag
+                + next[DELTA_SIGN_POS]
+                + next[COMMITTEE_FLAG_POS]),
+        ),
+        (
+            RECEIVER_KEY_INDEX_RES,
+            RECEIVER_KEY_INDEX_BIT_POS,
+            RECEIVER_KEY_INDEX_ACCUMULATE_POS,
+            RECEIVER_KEY_INDEX_BOUND_POS,
+            RECEIVER_INDEX_POS,
+            KEY_INDEX_HASH_POS,
+            not(next[WITHDRAWAL_FLAG_POS] + next[SPLIT_FLAG_POS] + noop_flag),
+        ),
+    ] {
+        evaluate_key_index_decomposition(
+            &mut result[res..res + NUM_KEY_INDEX_CONSTRAINTS],
+            current,
+            next,
+            [bit_pos, accumulate_pos, bound_pos, index_pos, hash_pos],
+            check_flag,
+            key_index_flags,
+        );
+    }
+}
+
+/// Enforces the decomposition of the hash of a key, held at the provided position, into the
+/// bit, accumulated value and bound registers at the provided positions, and checks the
+/// index held at the provided position under `check_flag`.
+fn evaluate_key_index_decomposition<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    [bit_pos, accumulate_pos, bound_pos, index_pos, hash_pos]: [usize; 5],
+    check_flag: E,
+    key_index_flags: &[E],
+) {
+    let flag = |index: usize| key_index_flags[index - KEY_INDEX_SETUP_MASK_INDEX];
+    let setup_flag = flag(KEY_INDEX_SETUP_MASK_INDEX);
+    let step_flag = flag(KEY_INDEX_STEP_MASK_INDEX);
+    let weight = flag(KEY_INDEX_WEIGHTS_INDEX);
+    let bound_bit = flag(KEY_INDEX_BOUND_INDEX);
+    let index_flag = flag(KEY_INDEX_CHECK_MASK_INDEX);
+    let finish_flag = flag(KEY_INDEX_FINISH_MASK_INDEX);
+
+    let bit = next[bit_pos];
+    result.agg_constraint(0, step_flag, is_binary(bit));
+
+    // The weighted bit is subtracted under the weights themselves, which are only set along
+    // with the step flag, so that the degree of the constraint is not raised by both
+    result.agg_constraint(
+        1,
+        step_flag,
+        are_equal(next[accumulate_pos], current[accumulate_pos]),
+    );
+    result.agg_constraint(1, weight, -bit);
+    result.agg_constraint(1, setup_flag, next[accumulate_pos]);
+
+    // The bound flag tells whether the bits inserted so far are at most the ones of the
+    // modulus minus one: it is kept by a bit equal to the one of the modulus, set by a smaller
+    // one and cleared by a greater one. As for the weights, the bits of the modulus are only
+    // set along with the step flag
+    let bound = current[bound_pos];
+    result.agg_constraint(2, step_flag, are_equal(next[bound_pos], not(bit) * bound));
+    result.agg_constraint(2, bound_bit, -(not(bit) * not(bound) + bit * bound));
+    result.agg_constraint(2, setup_flag, are_equal(next[bound_pos], E::ONE));
+
+    result.agg_constraint(
+        3,
+        index_flag,
+        check_flag * are_equal(next[accumulate_pos], next[index_pos]),
+    );
+    result.agg_constraint(
+        4,
+        finish_flag,
+        are_equal(next[accumulate_pos], next[hash_pos]),
+    );
+    result.agg_constraint(5, finish_flag, are_equal(next[bound_pos], E::ONE));
+}
+
+/// Returns the degrees of the transition constraints of the transaction AIR program, in the
+/// order of their evaluations, followed by the ones binding the indices of accounts to their
+/// keys if `derived_indices` is set.
+pub(crate) fn transition_constraint_degrees(
+    derived_indices: bool,
+) -> Vec<TransitionConstraintDegree> {
+    // Constraint degrees for enforcement of Rescue hash rounds
+    let mut degrees = merkle::update::transition_constraint_degrees(TRANSACTION_CYCLE_LENGTH);
+    // The constraint at the receiver position has higher degree than in Merkle sub-AIR program
+    degrees[RECEIVER_BIT_POS] =
+        TransitionConstraintDegree::with_cycles(3, vec![TRANSACTION_CYCLE_LENGTH]);
+    // The binary constraints of the range proofs on sigma and on the receiver's updated
+    // balance lie outside the Merkle registers in the legacy layout
+    #[cfg(feature = "legacy-layout")]
+    {
+        degrees[SIGMA_BIT_POS] =
+            TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]);
+        degrees[RECEIVER_BALANCE_BIT_POS] =
+            TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]);
+    }
+    // The balance conservation depends on the withdrawal flag
+    degrees[BALANCE_CONSTRAINT_RES] =
+        TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]);
+
+    let schnorr_degrees = schnorr::transition_constraint_degrees(2, TRANSACTION_CYCLE_LENGTH);
+    // Update the constraint degrees with the ones for Schnorr, of the accumulated point and of
+    // the point added to it
+    for index in (0..PROJECTIVE_POINT_WIDTH).chain(ADDEND_POS..ADDEND_POS + AFFINE_POINT_WIDTH) {
+        degrees[index] = schnorr_degrees[index].clone();
+    }
+
+    // Append the degrees for the copy columns followed by range proof equalities
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            1,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        RECEIVER_BALANCE_RANGE_RES - RECEIVER_KEY_POINT_RES
+            + 1
+    ]);
+    // The copies of the receiver's key, delta and the fee depend on the withdrawal, deposit
+    // and approval flags, and the one of the signer's nonce on the sign of delta as well
+    for index in (RECEIVER_KEY_POINT_RES..RECEIVER_KEY_POINT_RES + AFFINE_POINT_WIDTH)
+        .chain([DELTA_COPY_RES, FEE_COPY_RES])
+    {
+        degrees[index] = TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]);
+    }
+    degrees[NONCE_COPY_RES] =
+        TransitionConstraintDegree::with_cycles(3, vec![TRANSACTION_CYCLE_LENGTH]);
+    // Append the degrees for the split registers and the indices, followed by
+    // the chaining of split transfers and the no-op transactions, most of them multiplying
+    // two registers
+    degrees.truncate(SPLIT_REGISTERS_RES);
+    degrees.resize(
+        NOOP_RES + NUM_NOOP_CONSTRAINTS,
+        TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]),
+    );
+    for index in [
+        SPLIT_REGISTERS_RES + SPLIT_REGISTER_WIDTH - 1,
+        SPLIT_CHAIN_RES,
+    ] {
+        degrees[index] = TransitionConstraintDegree::with_cycles(1, vec![TRANSACTION_CYCLE_LENGTH]);
+    }
+    // Outputs are selected by their position flags, for split transfers only
+    degrees[SPLIT_CHAIN_RES + NUM_SPLIT_CHAIN_CONSTRAINTS - 1] =
+        TransitionConstraintDegree::with_cycles(3, vec![TRANSACTION_CYCLE_LENGTH]);
+    // Append the degrees for the copy of the memo, carried to approvals
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        MEMO_WIDTH
+    ]);
+    // Append the degrees for the running digest of transactions, updated depending on the
+    // no-op flag, followed by the Rescue rounds of its hash state
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        HASH_RATE_WIDTH
+    ]);
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            3,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        HASH_STATE_WIDTH
+    ]);
+    // Append the degrees for the commitment to nullifiers, updated depending on the deposit
+    // and no-op flags, followed by the number and volume of transactions
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        HASH_RATE_WIDTH
+    ]);
+    degrees.push(TransitionConstraintDegree::with_cycles(
+        1,
+        vec![TRANSACTION_CYCLE_LENGTH],
+    ));
+    degrees.push(TransitionConstraintDegree::with_cycles(
+        2,
+        vec![TRANSACTION_CYCLE_LENGTH],
+    ));
+    // Append the degrees for the binding of the sender's key, selected by the deposit and
+    // no-op flags
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        AFFINE_POINT_WIDTH
+    ]);
+    // Append the degrees for the rekeying transactions, which also select the sender's updated
+    // key
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        NUM_REKEY_CONSTRAINTS
+    ]);
+    // Append the degrees for the shared path flag and the hash states it ties, selected
+    // between the levels of the Merkle phase
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH, HASH_CYCLE_LENGTH]
+        );
+        NUM_SHARED_PATH_CONSTRAINTS
+    ]);
+    for degree in &mut degrees[VALUE_CONSTRAINT_RES..VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH] {
+        *degree = TransitionConstraintDegree::with_cycles(2, vec![TRANSACTION_CYCLE_LENGTH]);
+    }
+    // Append the degrees for the sign of delta, multiplying itself or the flags and fee of the
+    // transaction
+    debug_assert_eq!(degrees.len(), DELTA_SIGN_RES);
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        NUM_DELTA_SIGN_CONSTRAINTS
+    ]);
+
+    // Append the degrees for the committee and approval flags, the authentication path of the
+    // member's key and its root, the values carried to approvals and the indices of members,
+    // most of them selected by the flags of the transaction
+    for degree in [2, 2, 1, 2] {
+        degrees.push(TransitionConstraintDegree::with_cycles(
+            degree,
+            vec![TRANSACTION_CYCLE_LENGTH],
+        ));
+    }
+    degrees.append(
+        &mut merkle::update::transition_constraint_degrees(TRANSACTION_CYCLE_LENGTH)
+            [..HASH_STATE_WIDTH + 1]
+            .to_vec(),
+    );
+    degrees.append(&mut vec![
+        TransitionConstraintDegree::with_cycles(
+            2,
+            vec![TRANSACTION_CYCLE_LENGTH]
+        );
+        HASH_RATE_WIDTH * 2
+    ]);
+    for degree in [2, 2, 1, 1, 2, 1, 2, 2, 2, 2] {
+        degrees.push(TransitionConstraintDegree::with_cycles(
+            degree,
+            vec![TRANSACTION_CYCLE_LENGTH],
+        ));
+    }
+    debug_assert_eq!(degrees.len(), KEY_INDEX_HASH_RES);
+
+    // Append the degrees for the hash of the receiver's key and the decompositions of the
+    // hashes of the keys, whose bits are compared with the ones of the modulus and whose
+    // indices are selected by the flags of the transaction
+    if derived_indices {
+        degrees.append(&mut vec![
+            TransitionConstraintDegree::with_cycles(
+                3,
+                vec![TRANSACTION_CYCLE_LENGTH]
+            );
+            HASH_STATE_WIDTH
+        ]);
+        for _ in 0..2 {
+            for degree in [2, 1, 2, 2, 1, 1] {
+                degrees.push(TransitionConstraintDegree::with_cycles(
+                    degree,
+                    vec![TRANSACTION_CYCLE_LENGTH],
+                ));
+            }
+        }
+        debug_assert_eq!(
+            degrees.len(),
+            KEY_INDEX_HASH_RES + NUM_DERIVED_INDEX_CONSTRAINTS
+        );
+    }
+
+    degrees
 }
@@ -0,0 +1,371 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::merkle::constants::{
+    transaction_hash_length, AFFINE_POINT_WIDTH, HASH_CYCLE_LENGTH, HASH_RATE_WIDTH,
+    HASH_STATE_WIDTH, MAX_MERKLE_TREE_DEPTH, NODE_DOMAIN,
+};
+use crate::merkle::{hasher::domain_offset, TreeHasher};
+use crate::periodic::{PeriodicColumnSet, Segment};
+use crate::utils::rescue::{Rescue63, HASH_CYCLE_MASK};
+use crate::utils::{are_equal, boundary, is_binary, not, EvaluationResult};
+use core::marker::PhantomData;
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    Air, AirContext, Assertion, ByteWriter, EvaluationFrame, ProofOptions, Serializable, TraceInfo,
+    TransitionConstraintDegree,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Beginning position of the hash state computing the root of the tree with an empty leaf
+/// at the position of the inserted account
+pub(crate) const EMPTY_PATH_POS: usize = 0;
+/// Position of the register holding the bits of the position of the inserted account
+pub(crate) const INDEX_BIT_POS: usize = HASH_STATE_WIDTH;
+/// Beginning position of the hash state computing the root of the tree with the inserted
+/// account
+pub(crate) const ACCOUNT_PATH_POS: usize = HASH_STATE_WIDTH + 1;
+/// Beginning position of the root of the tree, as of the accounts inserted so far
+pub(crate) const ROOT_POS: usize = 2 * HASH_STATE_WIDTH + 1;
+/// Position of the sum of the balances of the accounts inserted so far
+pub(crate) const SUPPLY_POS: usize = ROOT_POS + HASH_RATE_WIDTH;
+/// Position of the flag telling whether the cycle inserts an account, or is padding
+pub(crate) const ACTIVE_FLAG_POS: usize = SUPPLY_POS + 1;
+/// Width of the execution trace
+pub(crate) const TRACE_WIDTH: usize = ACTIVE_FLAG_POS + 1;
+
+/// Position of the balance within the account state
+const BALANCE_OFFSET: usize = AFFINE_POINT_WIDTH;
+
+// Indices of the constraints beyond the ones of the registers they constrain
+const ROOT_MATCH_RES: usize = TRACE_WIDTH;
+const EMPTY_LEAF_RES: usize = ROOT_MATCH_RES + HASH_RATE_WIDTH;
+const ACTIVE_BINARY_RES: usize = EMPTY_LEAF_RES + HASH_RATE_WIDTH;
+
+// Indices of the periodic columns
+const SETUP_MASK_INDEX: usize = 0;
+const PATH_MASK_INDEX: usize = 1;
+const HASH_INPUT_MASK_INDEX: usize = 2;
+const FINISH_MASK_INDEX: usize = 3;
+const HASH_MASK_INDEX: usize = 4;
+const EMPTY_HASH_MASK_INDEX: usize = 5;
+const LEAF_INPUT_MASK_INDEX: usize = 6;
+const ARK_INDEX: usize = 7;
+
+/// Returns the length of the cycle inserting one account into a tree of depth `tree_depth`,
+/// which leaves at least one step free after the authentication path is hashed.
+pub(crate) const fn cycle_length(tree_depth: usize) -> usize {
+    (transaction_hash_length(tree_depth) + 2).next_power_of_two()
+}
+
+// SOLVENCY AIR
+// ================================================================================================
+
+/// Public inputs of a proof of solvency of a tree of accounts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputs {
+    /// Root of the audited tree
+    pub root: [BaseElement; HASH_RATE_WIDTH],
+    /// Sum of the balances of all the accounts of the tree, modulo the field modulus
+    pub total_supply: BaseElement,
+    /// Depth of the tree
+    pub tree_depth: usize,
+    /// Identifier of the hash function of the tree nodes
+    pub hash_id: u8,
+}
+
+impl Serializable for PublicInputs {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write(&self.root[..]);
+        target.write(self.total_supply);
+        target.write_u8(self.tree_depth as u8);
+        target.write_u8(self.hash_id);
+    }
+}
+
+/// AIR program rebuilding a tree of accounts from the empty tree, one account per cycle,
+/// while summing their balances.
+///
+/// Each cycle hashes the authentication path of the inserted account twice with the same
+/// siblings: once from an empty leaf, which must resolve to the root of the tree so far,
+/// and once from the account, which resolves to the next root. As an account can only be
+/// inserted into an empty leaf, every account of the final tree is summed exactly once.
+pub struct SolvencyAir<H: TreeHasher = Rescue63> {
+    context: AirContext<BaseElement>,
+    root: [BaseElement; HASH_RATE_WIDTH],
+    total_supply: BaseElement,
+    tree_depth: usize,
+    empty_root: [BaseElement; HASH_RATE_WIDTH],
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> Air for SolvencyAir<H> {
+    type BaseField = BaseElement;
+    type PublicInputs = PublicInputs;
+
+    // CONSTRUCTOR
+    // --------------------------------------------------------------------------------------------
+    fn new(trace_info: TraceInfo, pub_inputs: PublicInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(
+            pub_inputs.tree_depth > 0 && pub_inputs.tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        assert_eq!(
+            pub_inputs.hash_id,
+            H::HASH_ID,
+            "public inputs and AIR program must use the same tree hash function"
+        );
+        let cycle_length = cycle_length(pub_inputs.tree_depth);
+        // both lengths are powers of two
+        assert!(
+            trace_info.length() >= cycle_length,
+            "execution trace must span at least one cycle of {} steps",
+            cycle_length
+        );
+
+        let degrees = transition_constraint_degrees(cycle_length);
+        SolvencyAir {
+            context: AirContext::new(trace_info, degrees, options),
+            root: pub_inputs.root,
+            total_supply: pub_inputs.total_supply,
+            tree_depth: pub_inputs.tree_depth,
+            empty_root: H::empty_subtree_roots(pub_inputs.tree_depth)[pub_inputs.tree_depth]
+                .to_elements(),
+            _hasher: PhantomData,
+        }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        debug_assert_eq!(TRACE_WIDTH, current.len());
+        debug_assert_eq!(TRACE_WIDTH, next.len());
+
+        evaluate_constraints::<H, E>(result, current, next, periodic_values);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        // the tree is empty at the start of the trace, and holds all the accounts of the
+        // audited tree at its end
+        let last_step = self.trace_length() - 1;
+        let mut assertions = boundary::values_at(ROOT_POS, 0, &self.empty_root);
+        assertions.extend(boundary::values_at(ROOT_POS, last_step, &self.root));
+        assertions.push(boundary::count_at(SUPPLY_POS, 0, 0));
+        assertions.push(Assertion::single(SUPPLY_POS, last_step, self.total_supply));
+
+        assertions
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        periodic_columns::<H>(self.tree_depth)
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+pub(crate) fn periodic_columns<H: TreeHasher>(tree_depth: usize) -> Vec<Vec<BaseElement>> {
+    // Both authentication paths are hashed over the first steps of the cycle, the leaf of the
+    // account being hashed over the first hash cycle while the empty leaf is kept as is
+    let path = Segment::new(0, transaction_hash_length(tree_depth));
+    let hashes = Segment::new(0, (tree_depth + 1) * HASH_CYCLE_LENGTH);
+    let node_hashes = Segment::new(HASH_CYCLE_LENGTH, tree_depth * HASH_CYCLE_LENGTH);
+
+    let mut hash_input_mask = vec![BaseElement::ZERO; HASH_CYCLE_LENGTH];
+    hash_input_mask[HASH_CYCLE_LENGTH - 1] = BaseElement::ONE;
+
+    let mut layout =
+        PeriodicColumnSet::new(ARK_INDEX + HASH_STATE_WIDTH * 2, cycle_length(tree_depth));
+    layout.set(SETUP_MASK_INDEX, 0, BaseElement::ONE);
+    layout.mask(PATH_MASK_INDEX, path, BaseElement::ONE);
+    layout.place(
+        HASH_INPUT_MASK_INDEX,
+        Segment::new(0, tree_depth * HASH_CYCLE_LENGTH),
+        &hash_input_mask,
+    );
+    layout.set(
+        FINISH_MASK_INDEX,
+        path.step(path.length - 1),
+        BaseElement::ONE,
+    );
+    layout.place(HASH_MASK_INDEX, hashes, &HASH_CYCLE_MASK);
+    layout.place(EMPTY_HASH_MASK_INDEX, node_hashes, &HASH_CYCLE_MASK);
+    layout.set(
+        LEAF_INPUT_MASK_INDEX,
+        HASH_CYCLE_LENGTH - 1,
+        BaseElement::ONE,
+    );
+    layout.set_columns(ARK_INDEX, H::get_round_constants());
+
+    layout.into_columns()
+}
+
+pub(crate) fn evaluate_constraints<H: TreeHasher, E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    periodic_values: &[E],
+) {
+    // split periodic values into masks and hash round constants
+    let setup_flag = periodic_values[SETUP_MASK_INDEX];
+    let path_flag = periodic_values[PATH_MASK_INDEX];
+    let hash_input_flag = periodic_values[HASH_INPUT_MASK_INDEX];
+    let finish_flag = periodic_values[FINISH_MASK_INDEX];
+    let hash_flag = periodic_values[HASH_MASK_INDEX];
+    let empty_hash_flag = periodic_values[EMPTY_HASH_MASK_INDEX];
+    let leaf_input_flag = periodic_values[LEAF_INPUT_MASK_INDEX];
+    let ark = &periodic_values[ARK_INDEX..];
+
+    // Enforce that the bits of the position of the account are binary
+    let bit = next[INDEX_BIT_POS];
+    result.agg_constraint(INDEX_BIT_POS, path_flag, is_binary(bit));
+    let not_bit = not(bit);
+
+    // Enforce the hashing of both authentication paths, the empty leaf being left unhashed
+    // over the first hash cycle
+    for (pos, round_flag) in [
+        (EMPTY_PATH_POS, empty_hash_flag),
+        (ACCOUNT_PATH_POS, hash_flag),
+    ] {
+        H::enforce_round(
+            &mut result[pos..pos + HASH_STATE_WIDTH],
+            &current[pos..pos + HASH_STATE_WIDTH],
+            &next[pos..pos + HASH_STATE_WIDTH],
+            ark,
+            round_flag,
+        );
+
+        let copy_flag = path_flag * not(round_flag + hash_input_flag);
+        for i in 0..HASH_RATE_WIDTH {
+            result.agg_constraint(
+                pos + i,
+                copy_flag,
+                are_equal(current[pos + i], next[pos + i]),
+            );
+
+            // the accumulated hash goes into the rate registers when the index bit is zero,
+            // and into the capacity registers otherwise, offset by the domain separator of
+            // internal nodes
+            result.agg_constraint(
+                pos + i,
+                hash_input_flag,
+                not_bit * are_equal(current[pos + i], next[pos + i]),
+            );
+            result.agg_constraint(
+                pos + HASH_RATE_WIDTH + i,
+                hash_input_flag,
+                bit * are_equal(
+                    current[pos + i] + domain_offset::<E>(NODE_DOMAIN, HASH_RATE_WIDTH + i),
+                    next[pos + HASH_RATE_WIDTH + i],
+                ),
+            );
+        }
+    }
+
+    // Enforce that both paths are fed the same siblings
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            EMPTY_PATH_POS + i,
+            hash_input_flag,
+            bit * are_equal(next[ACCOUNT_PATH_POS + i], next[EMPTY_PATH_POS + i]),
+        );
+    }
+    for i in HASH_RATE_WIDTH..HASH_STATE_WIDTH {
+        result.agg_constraint(
+            EMPTY_PATH_POS + i,
+            hash_input_flag,
+            not_bit * are_equal(next[ACCOUNT_PATH_POS + i], next[EMPTY_PATH_POS + i]),
+        );
+    }
+
+    // Enforce that the account is inserted into an empty leaf
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(
+            EMPTY_LEAF_RES + i,
+            leaf_input_flag,
+            current[EMPTY_PATH_POS + i],
+        );
+    }
+
+    // Enforce that the root is carried over, and that an active cycle starts from the current
+    // root and ends with the root of the tree holding the account
+    let active = current[ACTIVE_FLAG_POS];
+    let insertion_flag = finish_flag * active;
+    for i in 0..HASH_RATE_WIDTH {
+        let root = current[ROOT_POS + i];
+        result.agg_constraint(
+            ROOT_POS + i,
+            E::ONE,
+            are_equal(
+                next[ROOT_POS + i],
+                root + insertion_flag * (next[ACCOUNT_PATH_POS + i] - root),
+            ),
+        );
+        result.agg_constraint(
+            ROOT_MATCH_RES + i,
+            insertion_flag,
+            are_equal(next[EMPTY_PATH_POS + i], root),
+        );
+    }
+
+    // Enforce that the balance of an inserted account is added to the supply
+    result.agg_constraint(
+        SUPPLY_POS,
+        E::ONE,
+        are_equal(
+            next[SUPPLY_POS],
+            current[SUPPLY_POS] + setup_flag * active * current[ACCOUNT_PATH_POS + BALANCE_OFFSET],
+        ),
+    );
+
+    // Enforce that the active flag is binary, and constant along the authentication paths
+    result.agg_constraint(
+        ACTIVE_FLAG_POS,
+        path_flag,
+        are_equal(next[ACTIVE_FLAG_POS], active),
+    );
+    result.agg_constraint(ACTIVE_BINARY_RES, setup_flag, is_binary(active));
+}
+
+pub(crate) fn transition_constraint_degrees(
+    cycle_length: usize,
+) -> Vec<TransitionConstraintDegree> {
+    let degree = |degree| TransitionConstraintDegree::with_cycles(degree, vec![cycle_length]);
+
+    // Hash rounds of the empty path, index bits, and hash rounds of the account path
+    let mut degrees = vec![degree(3); HASH_STATE_WIDTH];
+    degrees.push(degree(2));
+    degrees.append(&mut vec![degree(3); HASH_STATE_WIDTH]);
+    // Root, supply and active flag copies
+    degrees.append(&mut vec![degree(2); HASH_RATE_WIDTH]);
+    degrees.push(degree(2));
+    degrees.push(degree(1));
+    // Root match, empty leaf and binary active flag
+    degrees.append(&mut vec![degree(2); HASH_RATE_WIDTH]);
+    degrees.append(&mut vec![degree(1); HASH_RATE_WIDTH]);
+    degrees.push(degree(2));
+    debug_assert_eq!(degrees.len(), ACTIVE_BINARY_RES + 1);
+
+    degrees
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Proofs of solvency, letting auditors check that the balances of all the accounts of a
+//! tree sum up to a claimed total supply, given the root of the tree only.
+//!
+//! The AIR program of this module is independent from the one of the state transitions. It
+//! inserts the accounts one at a time into the empty tree, and sums their balances along the
+//! way. The sum is computed within the base field, and is thus the total supply modulo the
+//! field modulus; the total supply of a subnet is expected to stay well below it.
+
+use crate::merkle::TreeHasher;
+use crate::utils::rescue::Rescue63;
+use crate::AccountState;
+use winterfell::{ProofOptions, Prover, StarkProof, VerifierError};
+
+#[cfg(feature = "std")]
+use log::debug;
+#[cfg(feature = "std")]
+use std::time::Instant;
+#[cfg(feature = "std")]
+use winterfell::{math::log2, Trace};
+
+mod air;
+pub use air::PublicInputs;
+use air::SolvencyAir;
+
+mod prover;
+use prover::SolvencyProver;
+
+#[cfg(test)]
+mod tests;
+
+// SOLVENCY PROOFS
+// ================================================================================================
+
+/// Proves that the tree of depth `tree_depth` hashed with Rescue63 and holding the provided
+/// accounts, keyed by their position, has the sum of their balances as total supply.
+///
+/// The root of the tree and the total supply are given by the public inputs of the proof,
+/// which can be retrieved natively from a `StateTree` holding the same accounts.
+///
+/// # Panics
+/// Panics if two accounts share the same position, or if any position is out of the tree.
+pub fn prove_solvency(
+    options: ProofOptions,
+    tree_depth: usize,
+    accounts: &[(usize, AccountState)],
+) -> StarkProof {
+    prove_solvency_with_hasher::<Rescue63>(options, tree_depth, accounts)
+}
+
+/// Proves the total supply of a tree of accounts similarly to `prove_solvency`, with the
+/// nodes of the tree being hashed with `H`.
+///
+/// # Panics
+/// Panics under the same conditions as `prove_solvency`.
+pub fn prove_solvency_with_hasher<H: TreeHasher>(
+    options: ProofOptions,
+    tree_depth: usize,
+    accounts: &[(usize, AccountState)],
+) -> StarkProof {
+    #[cfg(feature = "std")]
+    debug!(
+        "Generating proof of solvency of {} accounts in a Merkle tree of depth {}\n\
+        ---------------------",
+        accounts.len(),
+        tree_depth
+    );
+
+    let prover = SolvencyProver::<H>::new(options, tree_depth);
+
+    // generate the execution trace
+    #[cfg(feature = "std")]
+    let now = Instant::now();
+    let trace = prover.build_trace(accounts);
+    #[cfg(feature = "std")]
+    debug!(
+        "Generated execution trace of {} registers and 2^{} steps in {} ms",
+        trace.width(),
+        log2(trace.length()),
+        now.elapsed().as_millis()
+    );
+
+    // generate the proof
+    prover.prove(trace).unwrap()
+}
+
+/// Verifies a proof that the tree of accounts hashed with Rescue63 whose root is contained
+/// in the provided public inputs has their total supply.
+pub fn verify_solvency(proof: StarkProof, pub_inputs: PublicInputs) -> Result<(), VerifierError> {
+    verify_solvency_with_hasher::<Rescue63>(proof, pub_inputs)
+}
+
+/// Verifies a proof of solvency of a tree of accounts hashed with `H`.
+pub fn verify_solvency_with_hasher<H: TreeHasher>(
+    proof: StarkProof,
+    pub_inputs: PublicInputs,
+) -> Result<(), VerifierError> {
+    winterfell::verify::<SolvencyAir<H>>(proof, pub_inputs)
+}
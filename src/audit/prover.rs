@@ -0,0 +1,192 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::air::{
+    cycle_length, PublicInputs, SolvencyAir, ACCOUNT_PATH_POS, ACTIVE_FLAG_POS, EMPTY_PATH_POS,
+    INDEX_BIT_POS, ROOT_POS, SUPPLY_POS, TRACE_WIDTH,
+};
+use crate::merkle::constants::{
+    transaction_hash_length, AFFINE_POINT_WIDTH, HASH_CYCLE_LENGTH, HASH_RATE_WIDTH,
+    HASH_STATE_WIDTH, LEAF_DOMAIN, MAX_MERKLE_TREE_DEPTH,
+};
+use crate::merkle::update::update_merkle_update_auth_state;
+use crate::merkle::{StateTree, TreeHasher};
+use crate::utils::rescue::Hash;
+use crate::AccountState;
+use core::marker::PhantomData;
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    ProofOptions, Prover, Trace, TraceTable,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// SOLVENCY PROVER
+// ================================================================================================
+
+/// An account inserted by a cycle of the execution trace, along with the root of the tree
+/// prior to its insertion and the sum of the balances of the accounts inserted before it
+struct Insertion {
+    index: usize,
+    account: AccountState,
+    path: Vec<Hash>,
+    root: Hash,
+    supply: BaseElement,
+    active: bool,
+}
+
+pub struct SolvencyProver<H: TreeHasher> {
+    options: ProofOptions,
+    tree_depth: usize,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> SolvencyProver<H> {
+    pub fn new(options: ProofOptions, tree_depth: usize) -> Self {
+        assert!(
+            tree_depth > 0 && tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        Self {
+            options,
+            tree_depth,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Builds the execution trace inserting the provided accounts, keyed by their position,
+    /// into the empty tree, padded with inactive cycles up to a power of two.
+    pub fn build_trace(&self, accounts: &[(usize, AccountState)]) -> TraceTable<BaseElement> {
+        let mut accounts = accounts.to_vec();
+        accounts.sort_by_key(|(index, _)| *index);
+        assert!(
+            accounts.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "accounts must be at distinct positions"
+        );
+
+        // Rebuild the tree, recording the authentication path of every account prior to its
+        // insertion, which holds the siblings of the account in the final tree
+        let mut tree = StateTree::<H>::new_with_hasher(self.tree_depth);
+        let mut supply = BaseElement::ZERO;
+        let mut insertions = Vec::with_capacity(accounts.len().max(1).next_power_of_two());
+        for (index, account) in accounts {
+            insertions.push(Insertion {
+                index,
+                account,
+                path: tree.open(index),
+                root: tree.root(),
+                supply,
+                active: true,
+            });
+            tree.set_account(index, account);
+            supply += account.to_elements()[AFFINE_POINT_WIDTH];
+        }
+        while !insertions.len().is_power_of_two() || insertions.is_empty() {
+            insertions.push(Insertion {
+                index: 0,
+                account: AccountState::from_elements(&[BaseElement::ZERO; AFFINE_POINT_WIDTH + 2]),
+                path: tree.open(0),
+                root: tree.root(),
+                supply,
+                active: false,
+            });
+        }
+
+        let cycle_length = cycle_length(self.tree_depth);
+        let mut trace = TraceTable::new(TRACE_WIDTH, insertions.len() * cycle_length);
+        trace.fragments(cycle_length).for_each(|mut cycle_trace| {
+            let insertion = &insertions[cycle_trace.index()];
+            cycle_trace.fill(
+                |state| init_solvency_state(insertion, state),
+                |step, state| update_solvency_state::<H>(step, self.tree_depth, insertion, state),
+            )
+        });
+
+        // set index bit at the second step to one, before the actual bits are inserted, and
+        // flip the active flag at the last step of the first cycle, where it is not read, so
+        // that the degree of the constraints involving these registers is stable
+        trace.set(INDEX_BIT_POS, 1, BaseElement::ONE);
+        let active = trace.get(ACTIVE_FLAG_POS, cycle_length - 1);
+        trace.set(ACTIVE_FLAG_POS, cycle_length - 1, BaseElement::ONE - active);
+
+        trace
+    }
+}
+
+impl<H: TreeHasher> Prover for SolvencyProver<H> {
+    type BaseField = BaseElement;
+    type Air = SolvencyAir<H>;
+    type Trace = TraceTable<BaseElement>;
+
+    fn get_pub_inputs(&self, trace: &Self::Trace) -> PublicInputs {
+        let last_step = trace.length() - 1;
+        let mut root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+        for (i, element) in root.iter_mut().enumerate() {
+            *element = trace.get(ROOT_POS + i, last_step);
+        }
+
+        PublicInputs {
+            root,
+            total_supply: trace.get(SUPPLY_POS, last_step),
+            tree_depth: self.tree_depth,
+            hash_id: H::HASH_ID,
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+}
+
+// TRACE INITIALIZATION
+// ================================================================================================
+
+fn init_solvency_state(insertion: &Insertion, state: &mut [BaseElement]) {
+    state[EMPTY_PATH_POS..EMPTY_PATH_POS + HASH_STATE_WIDTH].fill(BaseElement::ZERO);
+    state[INDEX_BIT_POS] = BaseElement::ZERO;
+    state[ACCOUNT_PATH_POS..ACCOUNT_PATH_POS + HASH_STATE_WIDTH]
+        .copy_from_slice(&insertion.account.to_elements());
+    state[ACCOUNT_PATH_POS + HASH_STATE_WIDTH - 1] += LEAF_DOMAIN;
+    state[ROOT_POS..ROOT_POS + HASH_RATE_WIDTH].copy_from_slice(&insertion.root.to_elements());
+    state[SUPPLY_POS] = insertion.supply;
+    state[ACTIVE_FLAG_POS] = BaseElement::from(insertion.active as u8);
+}
+
+// TRANSITION FUNCTION
+// ================================================================================================
+
+fn update_solvency_state<H: TreeHasher>(
+    step: usize,
+    tree_depth: usize,
+    insertion: &Insertion,
+    state: &mut [BaseElement],
+) {
+    let path_length = transaction_hash_length(tree_depth);
+    if step == 0 {
+        state[SUPPLY_POS] += state[ACTIVE_FLAG_POS] * state[ACCOUNT_PATH_POS + AFFINE_POINT_WIDTH];
+    }
+    if step < path_length {
+        update_merkle_update_auth_state::<H>(
+            step,
+            insertion.index,
+            &insertion.path,
+            &mut state[EMPTY_PATH_POS..ROOT_POS],
+        );
+        // the empty leaf is not hashed, the leaf of the account being hashed alongside
+        if step < HASH_CYCLE_LENGTH - 1 {
+            state[EMPTY_PATH_POS..EMPTY_PATH_POS + HASH_STATE_WIDTH].fill(BaseElement::ZERO);
+        }
+    }
+    if step == path_length - 1 && insertion.active {
+        for i in 0..HASH_RATE_WIDTH {
+            state[ROOT_POS + i] = state[ACCOUNT_PATH_POS + i];
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::air::{cycle_length, transition_constraint_degrees, SolvencyAir, SUPPLY_POS};
+use super::prover::SolvencyProver;
+use super::PublicInputs;
+use crate::diagnostics::{evaluate_constraint_degrees, first_unsatisfied_constraint};
+use crate::merkle::{constants::AFFINE_POINT_WIDTH, StateTree};
+use crate::utils::rescue::Rescue63;
+use crate::AccountState;
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    Air, FieldExtension, HashFunction, ProofOptions, Prover, Trace, TraceInfo, TraceTable,
+};
+
+const TREE_DEPTH: usize = 4;
+
+/// Returns an account whose key and balance are derived from `seed`.
+fn account(seed: u64) -> AccountState {
+    let mut elements = [BaseElement::new(seed); AFFINE_POINT_WIDTH + 2];
+    elements[AFFINE_POINT_WIDTH + 1] = BaseElement::ZERO;
+    AccountState::from_elements(&elements)
+}
+
+fn accounts() -> Vec<(usize, AccountState)> {
+    [9, 3, 12]
+        .iter()
+        .map(|&index| (index, account(index as u64 + 1)))
+        .collect()
+}
+
+#[test]
+fn solvency_trace_satisfies_constraints() {
+    let accounts = accounts();
+    let mut tree = StateTree::new(TREE_DEPTH);
+    for (index, account) in accounts.iter() {
+        tree.set_account(*index, *account);
+    }
+
+    let prover = SolvencyProver::<Rescue63>::new(build_options(), TREE_DEPTH);
+    let trace = prover.build_trace(&accounts);
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    assert_eq!(pub_inputs.root, tree.root().to_elements());
+    assert_eq!(pub_inputs.total_supply, BaseElement::new(4 + 10 + 13));
+
+    let air = build_air(&trace, pub_inputs);
+    assert_eq!(
+        first_unsatisfied_constraint(&air, &trace).map(|(_, step)| step),
+        None
+    );
+    for assertion in air.get_assertions() {
+        assert_eq!(
+            trace.get(assertion.register(), assertion.first_step()),
+            assertion.values()[0]
+        );
+    }
+}
+
+#[test]
+fn solvency_trace_with_inflated_supply_fails() {
+    let prover = SolvencyProver::<Rescue63>::new(build_options(), TREE_DEPTH);
+    let mut trace = prover.build_trace(&accounts());
+    let pub_inputs = prover.get_pub_inputs(&trace);
+
+    // inflating the supply after the first account is inserted breaks its accumulation
+    let step = trace.length() / 4 + 5;
+    for step in step..trace.length() {
+        let supply = trace.get(SUPPLY_POS, step);
+        trace.set(SUPPLY_POS, step, supply + BaseElement::ONE);
+    }
+    let air = build_air(&trace, pub_inputs);
+    assert_eq!(
+        first_unsatisfied_constraint(&air, &trace).map(|(_, step)| step),
+        Some(step - 1)
+    );
+}
+
+#[test]
+fn solvency_constraint_degrees() {
+    let prover = SolvencyProver::<Rescue63>::new(build_options(), TREE_DEPTH);
+    let trace = prover.build_trace(&accounts());
+    let air = build_air(&trace, prover.get_pub_inputs(&trace));
+    let declared = transition_constraint_degrees(cycle_length(TREE_DEPTH));
+    for (declared, actual) in evaluate_constraint_degrees(&air, &declared, &trace) {
+        assert_eq!(declared, actual);
+    }
+}
+
+#[test]
+fn solvency_proof_verification() {
+    let accounts = accounts();
+    let proof = super::prove_solvency(build_options(), TREE_DEPTH, &accounts);
+
+    let mut tree = StateTree::new(TREE_DEPTH);
+    for (index, account) in accounts.iter() {
+        tree.set_account(*index, *account);
+    }
+    let pub_inputs = PublicInputs {
+        root: tree.root().to_elements(),
+        total_supply: BaseElement::new(27),
+        tree_depth: TREE_DEPTH,
+        hash_id: 0,
+    };
+    assert!(super::verify_solvency(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // Claiming another total supply must be rejected
+    let pub_inputs = PublicInputs {
+        total_supply: BaseElement::new(28),
+        ..pub_inputs
+    };
+    assert!(super::verify_solvency(proof, pub_inputs).is_err());
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn build_options() -> ProofOptions {
+    ProofOptions::new(
+        42,
+        4,
+        0,
+        HashFunction::Blake3_256,
+        FieldExtension::None,
+        4,
+        256,
+    )
+}
+
+fn build_air(trace: &TraceTable<BaseElement>, pub_inputs: PublicInputs) -> SolvencyAir {
+    SolvencyAir::new(
+        TraceInfo::new(trace.width(), trace.length()),
+        pub_inputs,
+        build_options(),
+    )
+}
@@ -0,0 +1,843 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::constants::schnorr_const::{MESSAGE_LENGTH, POINT_COORDINATE_WIDTH};
+use super::merkle::{MemoryStore, StateTree, TransactionWitness, TreeHasher, TreeStore};
+use super::range::{self, MAX_RANGE_LOG, MAX_SIGNED_RANGE_LOG, RANGE_LOG};
+use super::schnorr::threshold::{self, SignatureShare};
+use super::utils::rescue::Rescue63;
+use super::validation::{check_balances, BalanceError};
+use super::{
+    account_index, build_tx_message, schnorr, sign_noop, AccountState, SplitOutputs,
+    TransactionKind, TransactionMetadata,
+};
+use core::fmt;
+use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// TRANSACTION ERROR
+// ================================================================================================
+
+/// Errors raised when adding a transaction to a `TransactionBatchBuilder`
+#[derive(Debug, PartialEq)]
+pub enum TransactionError {
+    /// The transaction at the given position refers to a leaf outside of the tree
+    InvalidIndex(usize),
+    /// The transaction at the given position refers to a leaf holding no account
+    UnknownAccount(usize),
+    /// The transaction at the given position sends from or to the fee collector
+    FeeCollectorAccount(usize),
+    /// The transaction at the given position does not match the constraints of its kind:
+    /// transfers and refunds must have distinct sender and receiver, withdrawals, deposits,
+    /// no-ops and rekeyings must have identical ones, deposits and refunds cannot pay any fee,
+    /// no-ops and rekeyings transfer nothing, refunds transfer a negative amount and
+    /// require signed deltas, and committee transfers must be sent from the account of a
+    /// committee to a distinct receiver
+    InvalidKind(usize),
+    /// The nonce of the transaction at the given position is not the one of its signer, i.e.
+    /// its sender or the receiver of a refund, or the signer's nonce cannot be incremented any
+    /// further
+    InvalidNonce(usize),
+    /// The sender of the transaction at the given position cannot pay its amount and fee
+    InsufficientBalance(usize),
+    /// The transaction at the given position involves an amount, fee or balance which
+    /// does not fit in the range-checked bits
+    OutOfRange(usize),
+    /// The signature of the transaction at the given position is invalid
+    InvalidSignature(usize),
+    /// The transaction at the given position is the output of a split transfer, which must
+    /// be added along with all its outputs as a `SplitTransfer`
+    SplitOutput(usize),
+    /// The transaction at the given position is a committee transfer or an approval of one,
+    /// which must be added along with its signature shares as a `CommitteeTransfer`
+    CommitteeTransaction(usize),
+    /// No transaction was added to the batch
+    EmptyBatch,
+    /// The state tree does not hold the state resulting from the transactions prior to the
+    /// one at the given position
+    StateMismatch(usize),
+    /// The transaction at the given position refers to an account stored at another leaf than
+    /// the one derived from its public key, while the batch requires derived indices
+    UnderivedIndex(usize),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransactionError::InvalidIndex(index) => {
+                write!(
+                    f,
+                    "transaction {} refers to a leaf outside of the tree",
+                    index
+                )
+            }
+            TransactionError::UnknownAccount(index) => {
+                write!(f, "transaction {} refers to an unknown account", index)
+            }
+            TransactionError::FeeCollectorAccount(index) => {
+                write!(f, "transaction {} involves the fee collector", index)
+            }
+            TransactionError::InvalidKind(index) => {
+                write!(f, "transaction {} does not match its kind", index)
+            }
+            TransactionError::InvalidNonce(index) => {
+                write!(f, "transaction {} has an invalid nonce", index)
+            }
+            TransactionError::InsufficientBalance(index) => {
+                write!(
+                    f,
+                    "sender of transaction {} has an insufficient balance",
+                    index
+                )
+            }
+            TransactionError::OutOfRange(index) => {
+                write!(f, "transaction {} exceeds the range-checked values", index)
+            }
+            TransactionError::InvalidSignature(index) => {
+                write!(f, "transaction {} has an invalid signature", index)
+            }
+            TransactionError::SplitOutput(index) => {
+                write!(
+                    f,
+                    "transaction {} is a single output of a split transfer",
+                    index
+                )
+            }
+            TransactionError::CommitteeTransaction(index) => {
+                write!(
+                    f,
+                    "transaction {} is a single transaction of a committee transfer",
+                    index
+                )
+            }
+            TransactionError::EmptyBatch => write!(f, "batch contains no transaction"),
+            TransactionError::StateMismatch(index) => {
+                write!(
+                    f,
+                    "state does not result from the transactions prior to transaction {}",
+                    index
+                )
+            }
+            TransactionError::UnderivedIndex(index) => {
+                write!(
+                    f,
+                    "transaction {} refers to an account outside of its derived leaf",
+                    index
+                )
+            }
+        }
+    }
+}
+
+// TRANSACTION
+// ================================================================================================
+
+/// A transaction from a sender to a receiver, identified by the index of their leaves.
+///
+/// Withdrawals and rekeyings use the sender's leaf as receiver, and deposits use the
+/// receiver's leaf as sender.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transaction {
+    /// Index of the sender leaf
+    pub sender: usize,
+    /// Index of the receiver leaf
+    pub receiver: usize,
+    /// Amount debited from the sender, negative for refunds debiting the receiver
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub amount: BaseElement,
+    /// Fee paid by the sender to the fee collector
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub fee: BaseElement,
+    /// Nonce of the sender prior to this transaction, or of the receiver for refunds
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub nonce: BaseElement,
+    /// Kind of this transaction
+    pub kind: TransactionKind,
+    /// Schnorr signature of this transaction by the sender, or by the receiver for refunds
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::signature"))]
+    pub signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
+    /// Optional memo signed with this transaction, e.g. an invoice identifier
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::encoding::serde::option_bytes")
+    )]
+    pub memo: Option<[u8; 32]>,
+}
+
+// SPLIT TRANSFER
+// ================================================================================================
+
+/// A transfer from a sender to several receivers, authorized by a single signature over all
+/// its outputs, and performed by one transaction per output.
+///
+/// The sender pays the fee for each output, and its nonce is incremented by each of them.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitTransfer {
+    /// Index of the sender leaf
+    pub sender: usize,
+    /// Receivers and amounts of the outputs
+    pub outputs: SplitOutputs,
+    /// Fee paid by the sender to the fee collector for each output
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub fee: BaseElement,
+    /// Nonce of the sender prior to this transfer
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::element"))]
+    pub nonce: BaseElement,
+    /// Schnorr signature of this transfer by the sender
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::signature"))]
+    pub signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
+    /// Optional memo signed with this transfer, and attached to each of its outputs
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::encoding::serde::option_bytes")
+    )]
+    pub memo: Option<[u8; 32]>,
+}
+
+impl SplitTransfer {
+    /// Returns the transaction performing the output at index `position` of this transfer.
+    ///
+    /// # Panics
+    /// Panics if `position` is not below the number of outputs.
+    pub fn transaction(&self, position: usize) -> Transaction {
+        Transaction {
+            sender: self.sender,
+            receiver: self.outputs.receiver(position),
+            amount: self.outputs.amount(position),
+            fee: self.fee,
+            nonce: self.nonce + BaseElement::new(position as u64),
+            kind: TransactionKind::Split {
+                outputs: self.outputs,
+                position,
+            },
+            signature: self.signature,
+            memo: self.memo,
+        }
+    }
+}
+
+// COMMITTEE TRANSFER
+// ================================================================================================
+
+/// A transfer from the account of a committee, authorized by `t` distinct members out of the
+/// ones whose keys are committed in its key tree, `t` being the threshold of the committee.
+///
+/// The transfer is performed by a first transaction, signed by the first member, followed by
+/// one approval per further member, each of them leaving the state untouched. The message
+/// signed by the members is the one of the transfer, each of them with its own key in place of
+/// the committee's pseudo-key, as for `schnorr::threshold::signer_message`.
+#[derive(Clone, Debug)]
+pub struct CommitteeTransfer {
+    /// Index of the committee's leaf
+    pub sender: usize,
+    /// Index of the receiver leaf
+    pub receiver: usize,
+    /// Amount debited from the committee
+    pub amount: BaseElement,
+    /// Fee paid by the committee to the fee collector
+    pub fee: BaseElement,
+    /// Nonce of the committee prior to this transfer
+    pub nonce: BaseElement,
+    /// Signature shares of the members authorizing this transfer, by strictly increasing
+    /// positions in the key tree, whose paths must have the depth of the tree of accounts
+    pub shares: Vec<SignatureShare>,
+    /// Optional memo signed with this transfer
+    pub memo: Option<[u8; 32]>,
+}
+
+impl CommitteeTransfer {
+    /// Returns the transaction signed by the share at index `position` of this transfer, i.e.
+    /// the transfer itself for the first share and an approval of it for the next ones.
+    ///
+    /// # Panics
+    /// Panics if `position` is not below the number of shares.
+    pub fn transaction(&self, position: usize) -> Transaction {
+        Transaction {
+            sender: self.sender,
+            receiver: self.receiver,
+            amount: self.amount,
+            fee: self.fee,
+            nonce: self.nonce,
+            kind: if position == 0 {
+                TransactionKind::Committee
+            } else {
+                TransactionKind::Approval
+            },
+            signature: self.shares[position].signature,
+            memo: self.memo,
+        }
+    }
+}
+
+// TRANSACTION BATCH BUILDER
+// ================================================================================================
+
+/// A builder for `TransactionMetadata`, applying validated transactions one after the other
+/// to a `StateTree`, and collecting the Merkle paths and intermediate roots proven by the
+/// state-transition AIR program.
+///
+/// The state tree is borrowed for the lifetime of the builder, and hence holds the state
+/// resulting from all added transactions once the batch is built.
+#[derive(Debug)]
+pub struct TransactionBatchBuilder<'a, H: TreeHasher = Rescue63, S: TreeStore = MemoryStore> {
+    state: &'a mut StateTree<H, S>,
+    fee_collector: usize,
+    range_log: usize,
+    signed_deltas: bool,
+    subnet_id: BaseElement,
+    epoch: u32,
+    derived_indices: bool,
+    transactions: Vec<Transaction>,
+    witnesses: Vec<TransactionWitness>,
+    shares: Vec<Option<SignatureShare>>,
+}
+
+impl<'a, H: TreeHasher, S: TreeStore> TransactionBatchBuilder<'a, H, S> {
+    /// Returns a new builder applying transactions to `state`, with fees credited to the
+    /// account at index `fee_collector`.
+    ///
+    /// # Panics
+    /// Panics if the fee collector holds no account.
+    pub fn new(state: &'a mut StateTree<H, S>, fee_collector: usize) -> Self {
+        assert!(
+            state.get_account(fee_collector).is_some(),
+            "fee collector must hold an account"
+        );
+
+        TransactionBatchBuilder {
+            state,
+            fee_collector,
+            range_log: RANGE_LOG,
+            signed_deltas: false,
+            subnet_id: BaseElement::ZERO,
+            epoch: 0,
+            derived_indices: false,
+            transactions: Vec::new(),
+            witnesses: Vec::new(),
+            shares: Vec::new(),
+        }
+    }
+
+    /// Returns this builder, checking that amounts, fees and balances fit in `range_log`
+    /// bits instead of the default `RANGE_LOG`.
+    ///
+    /// # Panics
+    /// Panics if `range_log` is not between 1 and `MAX_RANGE_LOG`, or exceeds
+    /// `MAX_SIGNED_RANGE_LOG` for a builder accepting signed deltas.
+    pub fn with_range_log(mut self, range_log: usize) -> Self {
+        assert!(
+            range_log > 0 && range_log <= MAX_RANGE_LOG,
+            "range log must be between 1 and {}",
+            MAX_RANGE_LOG
+        );
+        assert!(
+            !self.signed_deltas || range_log <= MAX_SIGNED_RANGE_LOG,
+            "range log of signed deltas must be at most {}",
+            MAX_SIGNED_RANGE_LOG
+        );
+        self.range_log = range_log;
+        self
+    }
+
+    /// Returns this builder, accepting refunds, i.e. `TransactionKind::Refund` transactions of
+    /// amounts down to -2^range_log, which credit their sender and debit their receiver. Refunds are signed
+    /// by the receiver over its own nonce, which they increment, and pay no fee. The batch
+    /// must then be proven by a `TransactionProver` with signed deltas as well.
+    ///
+    /// # Panics
+    /// Panics if the range log of this builder exceeds `MAX_SIGNED_RANGE_LOG`.
+    pub fn with_signed_deltas(mut self) -> Self {
+        assert!(
+            self.range_log <= MAX_SIGNED_RANGE_LOG,
+            "range log of signed deltas must be at most {}",
+            MAX_SIGNED_RANGE_LOG
+        );
+        self.signed_deltas = true;
+        self
+    }
+
+    /// Returns this builder, for transactions signed for the subnet identified by `subnet_id`
+    /// instead of the default subnet zero.
+    pub fn with_subnet_id(mut self, subnet_id: BaseElement) -> Self {
+        self.subnet_id = subnet_id;
+        self
+    }
+
+    /// Returns this builder, for transactions signed for the provided `epoch` instead of
+    /// epoch zero. Transactions signed for any other epoch are rejected.
+    pub fn with_epoch(mut self, epoch: u32) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Returns this builder, rejecting transactions whose sender or receiver is not stored at
+    /// the leaf derived from its public key by `account_index`.
+    ///
+    /// Rekeyings leave the account at its leaf, so that they are rejected unless the new key
+    /// derives the same leaf as the former one. Accounts whose new key derives another leaf
+    /// must instead be moved there by a transfer of their balance.
+    pub fn with_derived_indices(mut self) -> Self {
+        self.derived_indices = true;
+        self
+    }
+
+    /// Returns the current state of the accounts.
+    pub fn state(&self) -> &StateTree<H, S> {
+        self.state
+    }
+
+    /// Returns the number of transactions added to this builder.
+    pub fn num_transactions(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Returns the message to be signed by the sender of `transaction`, or by its receiver for
+    /// refunds, against the current state of the accounts. The signature of `transaction` is
+    /// ignored.
+    pub fn message(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<[BaseElement; MESSAGE_LENGTH], TransactionError> {
+        let (s_old_value, r_old_value) = get_accounts(
+            self.state,
+            self.fee_collector,
+            self.num_transactions(),
+            transaction,
+        )?;
+
+        Ok(build_tx_message(
+            &s_old_value.pub_key_elements(),
+            &r_old_value.pub_key_elements(),
+            transaction.sender,
+            transaction.receiver,
+            transaction.amount,
+            transaction.nonce,
+            transaction.fee,
+            transaction.kind,
+            transaction.memo,
+            self.subnet_id,
+            self.epoch,
+        ))
+    }
+
+    /// Validates `transaction` against the current state of the accounts, and applies it.
+    ///
+    /// Returns an error, leaving the state untouched, if the transaction refers to unknown
+    /// accounts or to the fee collector, does not match its kind, has an invalid nonce or
+    /// signature, or would make any balance fall out of range. Outputs of split transfers
+    /// and committee transfers are rejected, and must be added with `add_split_transfer` and
+    /// `add_committee_transfer` instead.
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<(), TransactionError> {
+        if transaction.kind.is_split() {
+            return Err(TransactionError::SplitOutput(self.num_transactions()));
+        }
+        if transaction.kind.is_committee() {
+            return Err(TransactionError::CommitteeTransaction(
+                self.num_transactions(),
+            ));
+        }
+
+        self.apply_transaction(transaction)
+    }
+
+    /// Validates the outputs of `transfer` against the current state of the accounts, and
+    /// applies them one after the other.
+    ///
+    /// Returns an error, leaving the state untouched, if any of its outputs would be rejected
+    /// by `add_transaction`.
+    pub fn add_split_transfer(&mut self, transfer: SplitTransfer) -> Result<(), TransactionError> {
+        // Outputs are validated against the state updated by the previous ones, which is
+        // restored if any of them is rejected
+        let previous_accounts: Vec<_> = [transfer.sender, self.fee_collector]
+            .iter()
+            .copied()
+            .chain((0..transfer.outputs.len()).map(|i| transfer.outputs.receiver(i)))
+            .map(|leaf| (leaf, self.state.get_account(leaf).copied()))
+            .collect();
+        let num_transactions = self.num_transactions();
+
+        for position in 0..transfer.outputs.len() {
+            if let Err(err) = self.apply_transaction(transfer.transaction(position)) {
+                for &(leaf, account) in previous_accounts.iter() {
+                    if let Some(account) = account {
+                        self.state.set_account(leaf, account);
+                    }
+                }
+                self.transactions.truncate(num_transactions);
+                self.witnesses.truncate(num_transactions);
+                self.shares.truncate(num_transactions);
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates `transfer` against the current state of the accounts, and applies it,
+    /// followed by one approval per further share.
+    ///
+    /// Returns an error, leaving the state untouched, if the transfer would be rejected by
+    /// `add_transaction` as a plain transfer, if its sender is not the account of a committee,
+    /// or if its shares are not exactly as many as the threshold of the committee, or do not
+    /// authorize the transfer as checked by `schnorr::threshold::verify_threshold`.
+    pub fn add_committee_transfer(
+        &mut self,
+        transfer: CommitteeTransfer,
+    ) -> Result<(), TransactionError> {
+        let index = self.num_transactions();
+        let transaction = transfer.transaction(0);
+        let message = self.message(&transaction)?;
+        let (root, committee_threshold) =
+            get_accounts(self.state, self.fee_collector, index, &transaction)?
+                .0
+                .committee()
+                .ok_or(TransactionError::InvalidKind(index))?;
+        if transfer.shares.len() != committee_threshold
+            || transfer
+                .shares
+                .iter()
+                .any(|share| share.path.len() != self.state.depth() + 1)
+            || !threshold::verify_threshold(message, root, committee_threshold, &transfer.shares)
+        {
+            return Err(TransactionError::InvalidSignature(index));
+        }
+
+        // Approvals leave the state untouched, so that only the transfer itself may be
+        // rejected
+        self.apply_transaction(transaction)?;
+        self.shares[index] = Some(transfer.shares[0].clone());
+        for position in 1..transfer.shares.len() {
+            let approval = transfer.transaction(position);
+            let witness = self.state.apply_transaction(&approval, self.fee_collector);
+            self.transactions.push(approval);
+            self.witnesses.push(witness);
+            self.shares.push(Some(transfer.shares[position].clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Adds a no-op transaction on the account at index `account`, which leaves the state
+    /// untouched. No-ops are signed with the public deposit key rather than by the account's
+    /// owner, and can hence pad the batch to the required number of transactions.
+    ///
+    /// Returns an error if the account is unknown, or is the fee collector.
+    pub fn add_noop(&mut self, account: usize) -> Result<(), TransactionError> {
+        let nonce = self
+            .state
+            .get_account(account)
+            .map_or(BaseElement::ZERO, |value| value.nonce);
+        let mut transaction = Transaction {
+            sender: account,
+            receiver: account,
+            amount: BaseElement::ZERO,
+            fee: BaseElement::ZERO,
+            nonce,
+            kind: TransactionKind::NoOp,
+            signature: ([BaseElement::ZERO; POINT_COORDINATE_WIDTH], Scalar::zero()),
+            memo: None,
+        };
+        transaction.signature = sign_noop(self.message(&transaction)?);
+
+        self.add_transaction(transaction)
+    }
+
+    /// Returns the `TransactionMetadata` of all the transactions added to this builder.
+    ///
+    /// Returns an error if no transaction was added.
+    pub fn build(self) -> Result<TransactionMetadata, TransactionError> {
+        if self.transactions.is_empty() {
+            return Err(TransactionError::EmptyBatch);
+        }
+
+        Ok(TransactionMetadata::from_witnesses(
+            &self.transactions,
+            self.witnesses,
+            self.state.root(),
+            self.fee_collector,
+        )
+        .with_signature_shares(self.shares)
+        .with_subnet_id(self.subnet_id)
+        .with_epoch(self.epoch))
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Validates `transaction` against the current state of the accounts, and applies it.
+    fn apply_transaction(&mut self, transaction: Transaction) -> Result<(), TransactionError> {
+        check_transaction(
+            self.state,
+            self.fee_collector,
+            self.range_log,
+            self.signed_deltas,
+            self.subnet_id,
+            self.epoch,
+            self.num_transactions(),
+            &transaction,
+        )?;
+        if self.derived_indices {
+            let index = self.num_transactions();
+            let depth = self.state.depth();
+            for &leaf in [transaction.sender, transaction.receiver].iter() {
+                let account = self
+                    .state
+                    .get_account(leaf)
+                    .ok_or(TransactionError::UnknownAccount(index))?;
+                if account.derived_index(depth) != leaf {
+                    return Err(TransactionError::UnderivedIndex(index));
+                }
+            }
+            if let TransactionKind::Rekey { new_key } = transaction.kind {
+                if account_index(&new_key, depth) != transaction.sender {
+                    return Err(TransactionError::UnderivedIndex(index));
+                }
+            }
+        }
+
+        let witness = self
+            .state
+            .apply_transaction(&transaction, self.fee_collector);
+        self.transactions.push(transaction);
+        self.witnesses.push(witness);
+        self.shares.push(None);
+
+        Ok(())
+    }
+}
+
+// INCREMENTAL METADATA
+// ================================================================================================
+
+impl TransactionMetadata {
+    /// Returns a `TransactionMetadata` holding no transaction yet, starting from the current
+    /// state of `state`, with fees credited to the account at index `fee_collector`.
+    ///
+    /// Transactions are then added one at a time with `push_transaction`, as they arrive.
+    ///
+    /// # Panics
+    /// Panics if the fee collector holds no account.
+    pub fn empty<H: TreeHasher, S: TreeStore>(
+        state: &StateTree<H, S>,
+        fee_collector: usize,
+    ) -> Self {
+        assert!(
+            state.get_account(fee_collector).is_some(),
+            "fee collector must hold an account"
+        );
+
+        TransactionMetadata {
+            tree_depth: state.depth(),
+            initial_roots: Vec::new(),
+            final_root: state.root(),
+            s_old_values: Vec::new(),
+            r_old_values: Vec::new(),
+            c_old_values: Vec::new(),
+            s_indices: Vec::new(),
+            r_indices: Vec::new(),
+            fee_collector,
+            s_paths: Vec::new(),
+            r_paths: Vec::new(),
+            c_paths: Vec::new(),
+            deltas: Vec::new(),
+            fees: Vec::new(),
+            kinds: Vec::new(),
+            signatures: Vec::new(),
+            memos: Vec::new(),
+            shares: Vec::new(),
+            subnet_id: BaseElement::ZERO,
+            epoch: 0,
+        }
+    }
+
+    /// Validates `transaction` against `state`, applies it, and appends it to these
+    /// transactions along with the state it witnesses.
+    ///
+    /// This allows a sequencer to accumulate the witnesses of a batch one transaction at a
+    /// time as they arrive, releasing `state` in between, and to prove the whole batch once
+    /// complete. `state` must hold the state resulting from the transactions already
+    /// added, i.e. must not be updated in between but through this method.
+    ///
+    /// Returns an error, leaving the state untouched, if `state` does not match these
+    /// transactions, or if the transaction would be rejected by
+    /// `TransactionBatchBuilder::add_transaction`, checking amounts, fees and balances over
+    /// the default `RANGE_LOG` bits. Committee transfers are rejected as well.
+    pub fn push_transaction<H: TreeHasher, S: TreeStore>(
+        &mut self,
+        transaction: Transaction,
+        state: &mut StateTree<H, S>,
+    ) -> Result<(), TransactionError> {
+        let index = self.num_transactions();
+        if state.depth() != self.tree_depth || state.root() != self.final_root {
+            return Err(TransactionError::StateMismatch(index));
+        }
+        if transaction.kind.is_split() {
+            return Err(TransactionError::SplitOutput(index));
+        }
+        if transaction.kind.is_committee() {
+            return Err(TransactionError::CommitteeTransaction(index));
+        }
+        check_transaction(
+            state,
+            self.fee_collector,
+            RANGE_LOG,
+            false,
+            self.subnet_id,
+            self.epoch,
+            index,
+            &transaction,
+        )?;
+
+        let witness = state.apply_transaction(&transaction, self.fee_collector);
+        self.initial_roots.push(witness.initial_root);
+        self.final_root = state.root();
+        self.s_old_values.push(witness.s_old_value);
+        self.r_old_values.push(witness.r_old_value);
+        self.c_old_values.push(witness.c_old_value);
+        self.s_indices.push(transaction.sender);
+        self.r_indices.push(transaction.receiver);
+        self.s_paths.push(witness.s_path);
+        self.r_paths.push(witness.r_path);
+        self.c_paths.push(witness.c_path);
+        self.deltas.push(transaction.amount);
+        self.fees.push(transaction.fee);
+        self.kinds.push(transaction.kind);
+        self.signatures.push(transaction.signature);
+        self.memos.push(transaction.memo);
+        self.shares.push(None);
+
+        Ok(())
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Validates `transaction`, at position `index` in its batch, against the current state of
+/// the accounts in `state`, with fees credited to the account at index `fee_collector`,
+/// amounts, fees and balances range-checked over `range_log` bits, amounts being signed if
+/// `signed_deltas`, and the signature checked against a message bound to the subnet
+/// `subnet_id` and to `epoch`.
+#[allow(clippy::too_many_arguments)]
+fn check_transaction<H: TreeHasher, S: TreeStore>(
+    state: &StateTree<H, S>,
+    fee_collector: usize,
+    range_log: usize,
+    signed_deltas: bool,
+    subnet_id: BaseElement,
+    epoch: u32,
+    index: usize,
+    transaction: &Transaction,
+) -> Result<(), TransactionError> {
+    let (s_index, r_index) = (transaction.sender, transaction.receiver);
+    let (amount, fee, kind) = (transaction.amount, transaction.fee, transaction.kind);
+    let (s_old_value, r_old_value) = get_accounts(state, fee_collector, index, transaction)?;
+    let c_old_value = state.get_account(fee_collector).unwrap();
+
+    let kind_matches = match kind {
+        TransactionKind::Transfer => s_index != r_index,
+        TransactionKind::Withdrawal { .. } => s_index == r_index,
+        TransactionKind::Deposit => s_index == r_index && fee == BaseElement::ZERO,
+        TransactionKind::Split { .. } => s_index != r_index,
+        TransactionKind::NoOp | TransactionKind::Rekey { .. } => {
+            s_index == r_index && amount == BaseElement::ZERO && fee == BaseElement::ZERO
+        }
+        TransactionKind::Refund => {
+            signed_deltas
+                && s_index != r_index
+                && range::is_negative(amount)
+                && fee == BaseElement::ZERO
+        }
+        TransactionKind::Committee => s_index != r_index && s_old_value.committee().is_some(),
+        TransactionKind::Approval => false,
+    };
+    if !kind_matches {
+        return Err(TransactionError::InvalidKind(index));
+    }
+    // Deposits and no-ops leave the nonce untouched, but still sign it as part of their
+    // message. Refunds consume the nonce of the receiver, which signs them. Incrementing the
+    // nonce past its limit would change the kind of the signer
+    let signer = if kind.is_refund() {
+        r_old_value
+    } else {
+        s_old_value
+    };
+    if transaction.nonce != signer.nonce
+        || (!kind.is_deposit() && !kind.is_noop() && !signer.can_increment_nonce())
+    {
+        return Err(TransactionError::InvalidNonce(index));
+    }
+
+    check_balances(
+        range_log,
+        kind,
+        amount,
+        fee,
+        [
+            s_old_value.balance,
+            r_old_value.balance,
+            c_old_value.balance,
+        ],
+    )
+    .map_err(|err| match err {
+        BalanceError::InsufficientBalance => TransactionError::InsufficientBalance(index),
+        BalanceError::OutOfRange => TransactionError::OutOfRange(index),
+    })?;
+
+    let message = build_tx_message(
+        &s_old_value.pub_key_elements(),
+        &r_old_value.pub_key_elements(),
+        s_index,
+        r_index,
+        amount,
+        transaction.nonce,
+        fee,
+        kind,
+        transaction.memo,
+        subnet_id,
+        epoch,
+    );
+    // Committee transfers are signed by the shares of their members instead, checked by
+    // `add_committee_transfer`
+    if !kind.is_committee() && !schnorr::verify_signature(message, transaction.signature) {
+        return Err(TransactionError::InvalidSignature(index));
+    }
+
+    Ok(())
+}
+
+/// Returns the current states of the sender and receiver of `transaction`, at position
+/// `index` in its batch, in `state`.
+fn get_accounts<H: TreeHasher, S: TreeStore>(
+    state: &StateTree<H, S>,
+    fee_collector: usize,
+    index: usize,
+    transaction: &Transaction,
+) -> Result<(AccountState, AccountState), TransactionError> {
+    let tree_size = 1 << state.depth();
+    for &leaf in [transaction.sender, transaction.receiver].iter() {
+        if leaf >= tree_size {
+            return Err(TransactionError::InvalidIndex(index));
+        }
+        if leaf == fee_collector {
+            return Err(TransactionError::FeeCollectorAccount(index));
+        }
+    }
+
+    match (
+        state.get_account(transaction.sender),
+        state.get_account(transaction.receiver),
+    ) {
+        (Some(s_value), Some(r_value)) => Ok((*s_value, *r_value)),
+        _ => Err(TransactionError::UnknownAccount(index)),
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Command-line interface generating, proving and verifying batches of transactions.
+//!
+//! Batches and proofs are read and written in binary form, or in JSON form for files
+//! with a `.json` extension.
+
+use certificate_stark::merkle::TreeHasher;
+use certificate_stark::utils::rescue::Rescue63;
+use certificate_stark::{
+    ProofOptionsBuilder, ProofProfile, ProverConfig, PublicInputs, TransactionMetadata,
+    TransactionProver,
+};
+use gumdrop::Options;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::process;
+use winterfell::{FieldExtension, HashFunction, Serializable, StarkProof};
+
+// COMMAND-LINE OPTIONS
+// ================================================================================================
+
+#[derive(Debug, Options)]
+struct CliOptions {
+    #[options(help = "print this help message")]
+    help: bool,
+
+    #[options(command)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Options)]
+enum Command {
+    #[options(help = "generate a batch of random transactions")]
+    GenExample(GenExampleOptions),
+
+    #[options(help = "prove the state-transition of a batch of transactions")]
+    Prove(ProveOptions),
+
+    #[options(help = "verify a state-transition proof")]
+    Verify(VerifyOptions),
+}
+
+#[derive(Debug, Options)]
+struct GenExampleOptions {
+    #[options(help = "print this help message")]
+    help: bool,
+
+    #[options(help = "number of transactions (default 4)", short = "n")]
+    num_tx: Option<usize>,
+
+    #[options(
+        help = "hex-encoded 32-byte seed, for reproducible batches",
+        short = "s"
+    )]
+    seed: Option<String>,
+
+    #[options(required, help = "output batch file", short = "o")]
+    output: String,
+}
+
+#[derive(Debug, Options)]
+struct ProveOptions {
+    #[options(help = "print this help message")]
+    help: bool,
+
+    #[options(required, help = "input batch file", short = "i")]
+    input: String,
+
+    #[options(required, help = "output proof file", short = "o")]
+    output: String,
+
+    #[options(
+        help = "proof options profile (default balanced; can be fast or high-security)",
+        short = "p"
+    )]
+    profile: Option<String>,
+
+    #[options(
+        help = "field extension, overriding the one of the profile (none, quadratic or cubic)",
+        short = "e"
+    )]
+    field_extension: Option<String>,
+
+    #[options(
+        help = "FRI folding factor, overriding the one of the profile (4, 8 or 16)",
+        short = "f"
+    )]
+    fri_folding_factor: Option<usize>,
+
+    #[options(
+        help = "FRI maximum remainder size, overriding the one of the profile",
+        short = "r"
+    )]
+    fri_max_remainder_size: Option<usize>,
+
+    #[options(
+        help = "hash function of the proof, overriding the default one (blake3 or sha3)",
+        short = "H"
+    )]
+    hash_fn: Option<String>,
+
+    #[options(
+        help = "number of prover threads (requires the concurrent feature)",
+        short = "t"
+    )]
+    threads: Option<usize>,
+}
+
+#[derive(Debug, Options)]
+struct VerifyOptions {
+    #[options(help = "print this help message")]
+    help: bool,
+
+    #[options(required, help = "input proof file", short = "i")]
+    input: String,
+
+    #[options(
+        help = "minimum conjectured security level of the proof, in bits",
+        short = "m"
+    )]
+    min_security: Option<u32>,
+}
+
+fn main() {
+    let options = CliOptions::parse_args_default_or_exit();
+    let result = match options.command {
+        Some(Command::GenExample(options)) => gen_example(options),
+        Some(Command::Prove(options)) => prove(options),
+        Some(Command::Verify(options)) => verify(options),
+        None => {
+            eprintln!("{}", CliOptions::usage());
+            eprintln!("\nAvailable commands:\n{}", Command::usage());
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+// COMMANDS
+// ================================================================================================
+
+fn gen_example(options: GenExampleOptions) -> Result<(), String> {
+    let num_tx = options.num_tx.unwrap_or(4);
+    if num_tx == 0 {
+        return Err("number of transactions must be positive".into());
+    }
+    let tx_metadata = match options.seed {
+        Some(seed) => {
+            let mut seed_bytes = [0u8; 32];
+            hex::decode_to_slice(seed, &mut seed_bytes)
+                .map_err(|err| format!("invalid seed: {}", err))?;
+            TransactionMetadata::build_random_with_seed(num_tx, seed_bytes)
+        }
+        None => TransactionMetadata::build_random(num_tx),
+    };
+
+    write_batch(&options.output, &tx_metadata)?;
+    println!(
+        "Generated {} transactions in a Merkle tree of depth {}",
+        tx_metadata.num_transactions(),
+        tx_metadata.tree_depth()
+    );
+
+    Ok(())
+}
+
+fn prove(options: ProveOptions) -> Result<(), String> {
+    let profile = match options.profile.as_deref() {
+        None | Some("balanced") => ProofProfile::Balanced,
+        Some("fast") => ProofProfile::Fast,
+        Some("high-security") => ProofProfile::HighSecurity,
+        Some(profile) => return Err(format!("unknown proof profile {}", profile)),
+    };
+    let mut options_builder = ProofOptionsBuilder::from_profile(profile);
+    match options.field_extension.as_deref() {
+        None => {}
+        Some("none") => options_builder = options_builder.field_extension(FieldExtension::None),
+        Some("quadratic") => {
+            options_builder = options_builder.field_extension(FieldExtension::Quadratic)
+        }
+        Some("cubic") => options_builder = options_builder.field_extension(FieldExtension::Cubic),
+        Some(extension) => return Err(format!("unknown field extension {}", extension)),
+    }
+    match options.hash_fn.as_deref() {
+        None => {}
+        Some("blake3") => options_builder = options_builder.hash_fn(HashFunction::Blake3_256),
+        Some("sha3") => options_builder = options_builder.hash_fn(HashFunction::Sha3_256),
+        Some(hash_fn) => return Err(format!("unknown hash function {}", hash_fn)),
+    }
+    if let Some(fri_folding_factor) = options.fri_folding_factor {
+        options_builder = options_builder.fri_folding_factor(fri_folding_factor);
+    }
+    if let Some(fri_max_remainder_size) = options.fri_max_remainder_size {
+        options_builder = options_builder.fri_max_remainder_size(fri_max_remainder_size);
+    }
+    let proof_options = options_builder
+        .try_build()
+        .map_err(|err| format!("invalid proof options: {}", err))?;
+    let tx_metadata = read_batch(&options.input)?;
+    if !tx_metadata.verify_signatures() {
+        return Err("batch contains invalid signatures".into());
+    }
+
+    let config = match options.threads {
+        None => ProverConfig::new(),
+        Some(0) => return Err("number of threads must be positive".into()),
+        Some(num_threads) => ProverConfig::new().with_num_threads(num_threads),
+    };
+
+    let prover = TransactionProver::new(proof_options).with_config(config);
+    let (proof, report) = prover
+        .prove_with_report(&tx_metadata)
+        .map_err(|err| err.to_string())?;
+    write_proof(&options.output, &proof, &prover.public_inputs(&tx_metadata))?;
+    println!("{}", report);
+
+    Ok(())
+}
+
+fn verify(options: VerifyOptions) -> Result<(), String> {
+    let (proof, pub_inputs) = read_proof(&options.input)?;
+    if pub_inputs.hash_id != Rescue63::HASH_ID {
+        return Err("unsupported tree hash function".into());
+    }
+    match options.min_security {
+        Some(min_security) => {
+            certificate_stark::verify_with_min_security(proof, pub_inputs, min_security)
+                .map_err(|err| err.to_string())?
+        }
+        None => certificate_stark::verify_with_hasher::<Rescue63>(proof, pub_inputs)
+            .map_err(|err| format!("invalid proof: {}", err))?,
+    }
+    println!("Proof verified");
+
+    Ok(())
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns whether the file at `path` holds JSON rather than binary data.
+fn is_json(path: &str) -> bool {
+    Path::new(path).extension() == Some("json".as_ref())
+}
+
+fn write_batch(path: &str, tx_metadata: &TransactionMetadata) -> Result<(), String> {
+    let bytes = tx_metadata.to_bytes();
+    if is_json(path) {
+        let value = json!({
+            "num_transactions": tx_metadata.num_transactions(),
+            "tree_depth": tx_metadata.tree_depth(),
+            "fee_collector": tx_metadata.fee_collector(),
+            "data": hex::encode(bytes),
+        });
+        write_json(path, &value)
+    } else {
+        fs::write(path, bytes).map_err(|err| format!("cannot write {}: {}", path, err))
+    }
+}
+
+fn read_batch(path: &str) -> Result<TransactionMetadata, String> {
+    let bytes = if is_json(path) {
+        read_hex_field(&read_json(path)?, "data")?
+    } else {
+        fs::read(path).map_err(|err| format!("cannot read {}: {}", path, err))?
+    };
+
+    TransactionMetadata::from_bytes(&bytes).map_err(|err| format!("invalid batch: {}", err))
+}
+
+/// Writes a proof along with its public inputs. The binary form consists of the length of
+/// the serialized public inputs as a little-endian u32, the public inputs and the proof.
+fn write_proof(path: &str, proof: &StarkProof, pub_inputs: &PublicInputs) -> Result<(), String> {
+    let pub_inputs_bytes = pub_inputs.to_bytes();
+    let proof_bytes = proof.to_bytes();
+    if is_json(path) {
+        let value = json!({
+            "security_level": proof.security_level(true),
+            "public_inputs": pub_inputs.to_hex(),
+            "proof": hex::encode(proof_bytes),
+        });
+        write_json(path, &value)
+    } else {
+        let mut bytes = (pub_inputs_bytes.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&pub_inputs_bytes);
+        bytes.extend_from_slice(&proof_bytes);
+        fs::write(path, bytes).map_err(|err| format!("cannot write {}: {}", path, err))
+    }
+}
+
+fn read_proof(path: &str) -> Result<(StarkProof, PublicInputs), String> {
+    let (pub_inputs_bytes, proof_bytes) = if is_json(path) {
+        let value = read_json(path)?;
+        (
+            read_hex_field(&value, "public_inputs")?,
+            read_hex_field(&value, "proof")?,
+        )
+    } else {
+        let bytes = fs::read(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+        if bytes.len() < 4 {
+            return Err("invalid proof file".into());
+        }
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(&bytes[..4]);
+        let len = u32::from_le_bytes(len_bytes) as usize;
+        if bytes.len() < 4 + len {
+            return Err("invalid proof file".into());
+        }
+        (bytes[4..4 + len].to_vec(), bytes[4 + len..].to_vec())
+    };
+
+    let pub_inputs = PublicInputs::from_bytes(&pub_inputs_bytes)
+        .map_err(|err| format!("invalid public inputs: {}", err))?;
+    let proof =
+        StarkProof::from_bytes(&proof_bytes).map_err(|err| format!("invalid proof: {}", err))?;
+
+    Ok((proof, pub_inputs))
+}
+
+fn write_json(path: &str, value: &Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+    fs::write(path, json).map_err(|err| format!("cannot write {}: {}", path, err))
+}
+
+fn read_json(path: &str) -> Result<Value, String> {
+    let json = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+    serde_json::from_str(&json).map_err(|err| format!("invalid JSON in {}: {}", path, err))
+}
+
+fn read_hex_field(value: &Value, field: &str) -> Result<Vec<u8>, String> {
+    let hex_string = value[field]
+        .as_str()
+        .ok_or_else(|| format!("missing field {}", field))?;
+
+    hex::decode(hex_string).map_err(|err| format!("invalid field {}: {}", field, err))
+}
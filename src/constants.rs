@@ -19,7 +19,7 @@ pub(crate) mod merkle_const {
 }
 
 pub(crate) mod range_const {
-    pub(crate) use crate::range::RANGE_LOG;
+    pub(crate) use crate::range::{MAX_RANGE_LOG, MAX_SIGNED_RANGE_LOG, RANGE_LOG};
 }
 
 pub(crate) mod rescue_const {
@@ -30,9 +30,22 @@ pub(crate) mod schnorr_const {
     pub(crate) use crate::schnorr::constants::*;
 }
 
+pub(crate) use crate::MAX_SPLIT_OUTPUTS;
+use winterfell::math::{fields::f63::BaseElement, StarkField};
+
 /// Total trace width for the state transition AIR program
-// The extra registers are for copying the public keys, delta and the new sender balance
-pub(crate) const TRACE_WIDTH: usize = NONCE_COPY_POS + 3;
+// The extra registers are for copying the public keys, delta, the new sender balance, the
+// sender's nonce, the fee, the withdrawal and deposit flags and the receiver's updated balance,
+// followed by the registers chaining the outputs of split transfers, the receiver's and
+// sender's indices, the no-op flag, the memo, the running digest of transactions, the
+// commitment to their nullifiers, the running number and volume of transactions, the
+// rekeying flag, the flag of the levels shared by the sender's and receiver's paths, the sign
+// of delta, the committee and approval flags, the root of the committee's key tree, the number
+// of approvals left, the indices of the current and previous members, the authentication path
+// of the member's key and the inverse of the signing key's last coordinate. The
+// `legacy-layout` feature adds the range proof registers for sigma and the receiver's updated
+// balance after the copied values, instead of sharing Merkle registers.
+pub(crate) const TRACE_WIDTH: usize = SIGNING_KEY_INVERSE_POS + 1;
 
 /// The width of the trace used for Merkle registers
 pub(crate) const MERKLE_REGISTER_WIDTH: usize = merkle_const::TRACE_WIDTH;
@@ -47,25 +60,142 @@ pub(crate) const DELTA_COPY_POS: usize =
 /// Position of the register copying the sender's updated balance
 pub(crate) const SIGMA_COPY_POS: usize =
     MERKLE_REGISTER_WIDTH + schnorr_const::AFFINE_POINT_WIDTH * 2 + 1;
-/// Position of the register copying the sender's updated nonce
+/// Position of the register copying the sender's nonce prior to the transaction, which is
+/// part of the signed message
 pub(crate) const NONCE_COPY_POS: usize =
     MERKLE_REGISTER_WIDTH + schnorr_const::AFFINE_POINT_WIDTH * 2 + 2;
+/// Position of the register copying the fee
+pub(crate) const FEE_COPY_POS: usize =
+    MERKLE_REGISTER_WIDTH + schnorr_const::AFFINE_POINT_WIDTH * 2 + 3;
+/// Position of the register flagging withdrawal transactions
+pub(crate) const WITHDRAWAL_FLAG_POS: usize =
+    MERKLE_REGISTER_WIDTH + schnorr_const::AFFINE_POINT_WIDTH * 2 + 4;
+/// Position of the register flagging deposit transactions
+pub(crate) const DEPOSIT_FLAG_POS: usize =
+    MERKLE_REGISTER_WIDTH + schnorr_const::AFFINE_POINT_WIDTH * 2 + 5;
+/// Position of the register copying the receiver's updated balance
+pub(crate) const RECEIVER_BALANCE_COPY_POS: usize =
+    MERKLE_REGISTER_WIDTH + schnorr_const::AFFINE_POINT_WIDTH * 2 + 6;
 
 //  Indices for the constraint results for various components
-/// Beginning index of constraints for the copy of the sender's public key
-pub(crate) const SENDER_KEY_POINT_RES: usize = merkle_const::PREV_TREE_MATCH_RES + 2;
 /// Beginning index of constraints for the copy of the receiver's public key
-pub(crate) const RECEIVER_KEY_POINT_RES: usize = SENDER_KEY_POINT_RES + 2;
+pub(crate) const RECEIVER_KEY_POINT_RES: usize = merkle_const::PREV_TREE_MATCH_RES + 2;
 /// Index of constraint for copying delta
 pub(crate) const DELTA_COPY_RES: usize = RECEIVER_KEY_POINT_RES + 2;
 /// Index of constraint for copying the sender's updated balance
 pub(crate) const SIGMA_COPY_RES: usize = DELTA_COPY_RES + 1;
-/// Index of constraint for copying the sender's updated nonce
+/// Index of constraint for copying the sender's nonce prior to the transaction
 pub(crate) const NONCE_COPY_RES: usize = SIGMA_COPY_RES + 1;
+/// Index of constraint for copying the fee
+pub(crate) const FEE_COPY_RES: usize = NONCE_COPY_RES + 1;
+/// Index of constraint for copying the withdrawal flag
+pub(crate) const WITHDRAWAL_FLAG_RES: usize = FEE_COPY_RES + 1;
+/// Index of constraint for copying the deposit flag
+pub(crate) const DEPOSIT_FLAG_RES: usize = WITHDRAWAL_FLAG_RES + 1;
+/// Index of constraint for copying the receiver's updated balance
+pub(crate) const RECEIVER_BALANCE_COPY_RES: usize = DEPOSIT_FLAG_RES + 1;
 /// Index of constraint for enforcing equality fo accumulated delta
-pub(crate) const DELTA_RANGE_RES: usize = NONCE_COPY_RES + 1;
+pub(crate) const DELTA_RANGE_RES: usize = RECEIVER_BALANCE_COPY_RES + 1;
 /// Index of constraint for enforcing equality fo accumulated sigma
 pub(crate) const SIGMA_RANGE_RES: usize = DELTA_RANGE_RES + 1;
+/// Index of constraint for enforcing equality of accumulated fee
+pub(crate) const FEE_RANGE_RES: usize = SIGMA_RANGE_RES + 1;
+/// Index of constraint for enforcing equality of the accumulated receiver's updated balance
+pub(crate) const RECEIVER_BALANCE_RANGE_RES: usize = FEE_RANGE_RES + 1;
+/// Beginning index of constraints for the split registers, which are binary for the flag
+/// and the position registers, and copied along each transaction
+pub(crate) const SPLIT_REGISTERS_RES: usize = RECEIVER_BALANCE_RANGE_RES + 1;
+/// Index of constraint for accumulating the receiver's index
+pub(crate) const RECEIVER_INDEX_RES: usize = SPLIT_REGISTERS_RES + SPLIT_REGISTER_WIDTH;
+/// Index of constraint for accumulating the sender's index
+pub(crate) const SENDER_INDEX_RES: usize = RECEIVER_INDEX_RES + 1;
+/// Beginning index of constraints for chaining the outputs of split transfers
+pub(crate) const SPLIT_CHAIN_RES: usize = SENDER_INDEX_RES + 1;
+/// Number of constraints for chaining the outputs of split transfers
+pub(crate) const NUM_SPLIT_CHAIN_CONSTRAINTS: usize = 7;
+/// Beginning index of constraints for no-op transactions, whose flag is binary and exclusive
+/// of the other flags, and which transfer no amount nor fee
+pub(crate) const NOOP_RES: usize = SPLIT_CHAIN_RES + NUM_SPLIT_CHAIN_CONSTRAINTS;
+/// Number of constraints for no-op transactions
+pub(crate) const NUM_NOOP_CONSTRAINTS: usize = 4;
+/// Beginning index of constraints for copying the memo along each transaction
+pub(crate) const MEMO_COPY_RES: usize = NOOP_RES + NUM_NOOP_CONSTRAINTS;
+/// Beginning index of constraints for accumulating the running digest of transactions
+pub(crate) const TX_DIGEST_RES: usize = MEMO_COPY_RES + MEMO_WIDTH;
+/// Beginning index of constraints for the hash state computing the running digest of
+/// transactions
+pub(crate) const TX_DIGEST_HASH_RES: usize = TX_DIGEST_RES + merkle_const::HASH_RATE_WIDTH;
+/// Beginning index of constraints for accumulating the commitment to the nullifiers of
+/// transactions
+pub(crate) const NULLIFIERS_RES: usize = TX_DIGEST_HASH_RES + merkle_const::HASH_STATE_WIDTH;
+/// Index of constraint for counting transactions
+pub(crate) const TX_COUNT_RES: usize = NULLIFIERS_RES + merkle_const::HASH_RATE_WIDTH;
+/// Index of constraint for accumulating the volume of transactions
+pub(crate) const TX_VOLUME_RES: usize = TX_COUNT_RES + 1;
+/// Beginning index of constraints binding the public key verifying the signature to the
+/// sender's leaf, one for each coordinate element so that they cannot offset one another
+pub(crate) const SENDER_KEY_POINT_RES: usize = TX_VOLUME_RES + 1;
+/// Beginning index of constraints for rekeying transactions, whose flag is binary and
+/// exclusive of the other flags, and which transfer no amount nor fee
+pub(crate) const REKEY_RES: usize = SENDER_KEY_POINT_RES + schnorr_const::AFFINE_POINT_WIDTH;
+/// Number of constraints for rekeying transactions
+pub(crate) const NUM_REKEY_CONSTRAINTS: usize = 4;
+/// Beginning index of constraints tying the receiver's initial authentication path to the
+/// sender's updated one above the lowest common ancestor of their leaves
+pub(crate) const SHARED_PATH_RES: usize = REKEY_RES + NUM_REKEY_CONSTRAINTS;
+/// Number of constraints on the shared path flag and of the shared hash states
+pub(crate) const NUM_SHARED_PATH_CONSTRAINTS: usize = merkle_const::HASH_STATE_WIDTH + 1;
+/// Beginning index of constraints on the sign of delta, which is binary, only set by proofs
+/// supporting signed deltas, and flags refunds, exclusive of all other flags and transferring
+/// no fee
+pub(crate) const DELTA_SIGN_RES: usize = SHARED_PATH_RES + NUM_SHARED_PATH_CONSTRAINTS;
+/// Number of constraints on the sign of delta
+pub(crate) const NUM_DELTA_SIGN_CONSTRAINTS: usize = 3;
+/// Beginning index of constraints on the committee and approval flags, which are binary,
+/// copied along each transaction, carried from a committee transfer or approval to the next
+/// approval, and exclusive of all other flags
+pub(crate) const COMMITTEE_RES: usize = DELTA_SIGN_RES + NUM_DELTA_SIGN_CONSTRAINTS;
+/// Number of constraints on the committee and approval flags
+pub(crate) const NUM_COMMITTEE_CONSTRAINTS: usize = 4;
+/// Beginning index of constraints for the authentication path of the member's key in the key
+/// tree of the committee
+pub(crate) const KEY_PATH_RES: usize = COMMITTEE_RES + NUM_COMMITTEE_CONSTRAINTS;
+/// Beginning index of constraints checking the member's leaf and the root of its path against
+/// the key tree of the committee
+pub(crate) const KEY_TREE_ROOT_RES: usize = KEY_PATH_RES + merkle_const::HASH_STATE_WIDTH + 1;
+/// Beginning index of constraints for the root of the key tree, read from the sender's leaf
+/// and carried along the approvals of a committee transfer
+pub(crate) const COMMITTEE_ROOT_RES: usize = KEY_TREE_ROOT_RES + merkle_const::HASH_RATE_WIDTH;
+/// Index of constraint for the number of approvals left, followed by the one checking that
+/// none are left once the approvals are over
+pub(crate) const APPROVALS_LEFT_RES: usize = COMMITTEE_ROOT_RES + merkle_const::HASH_RATE_WIDTH;
+/// Index of constraint for accumulating the member's index, followed by the one copying the
+/// index of the previous member
+pub(crate) const MEMBER_INDEX_RES: usize = APPROVALS_LEFT_RES + 2;
+/// Beginning index of constraints for the decomposition of the gap between the indices of
+/// consecutive members, on its bits, their accumulated value and the check of the gap
+pub(crate) const MEMBER_GAP_RES: usize = MEMBER_INDEX_RES + 2;
+/// Index of constraint for carrying delta to approvals, followed by the one for the fee
+pub(crate) const APPROVAL_COPY_RES: usize = MEMBER_GAP_RES + 3;
+/// Index of constraint for the inverse of the last coordinate of the signing key, which is
+/// thus never zero as the one of the pseudo-key of a committee's account
+pub(crate) const SIGNING_KEY_INVERSE_RES: usize = APPROVAL_COPY_RES + 2;
+/// Beginning index of constraints for the hash state deriving the receiver's index from its
+/// public key, only evaluated by proofs binding the indices of accounts to their keys
+pub(crate) const KEY_INDEX_HASH_RES: usize = SIGNING_KEY_INVERSE_RES + 1;
+/// Beginning index of constraints for the decomposition of the hash of the sender's key,
+/// whose low bits are its index, only evaluated by proofs binding the indices of accounts to
+/// their keys
+pub(crate) const SENDER_KEY_INDEX_RES: usize = KEY_INDEX_HASH_RES + merkle_const::HASH_STATE_WIDTH;
+/// Beginning index of constraints for the decomposition of the hash of the receiver's key
+pub(crate) const RECEIVER_KEY_INDEX_RES: usize = SENDER_KEY_INDEX_RES + NUM_KEY_INDEX_CONSTRAINTS;
+/// Number of constraints for the decomposition of the hash of a key, on its bits, their
+/// accumulated value and its comparison with the field modulus, followed by the checks of the
+/// index, of the hash and of the canonicity of the decomposition
+pub(crate) const NUM_KEY_INDEX_CONSTRAINTS: usize = 6;
+/// Number of constraints only evaluated by proofs binding the indices of accounts to their keys
+pub(crate) const NUM_DERIVED_INDEX_CONSTRAINTS: usize =
+    merkle_const::HASH_STATE_WIDTH + NUM_KEY_INDEX_CONSTRAINTS * 2;
 
 /// The width of the trace used for Schnorr registers
 pub(crate) const SCHNORR_REGISTER_WIDTH: usize = schnorr_const::TRACE_WIDTH;
@@ -73,10 +203,167 @@ pub(crate) const SCHNORR_REGISTER_WIDTH: usize = schnorr_const::TRACE_WIDTH;
 pub(crate) const DELTA_BIT_POS: usize = SCHNORR_REGISTER_WIDTH;
 /// Position of the accumulated value for delta
 pub(crate) const DELTA_ACCUMULATE_POS: usize = SCHNORR_REGISTER_WIDTH + 1;
+/// Position of the bit decomposition of the fee
+pub(crate) const FEE_BIT_POS: usize = SCHNORR_REGISTER_WIDTH + 2;
+/// Position of the accumulated value for the fee
+pub(crate) const FEE_ACCUMULATE_POS: usize = SCHNORR_REGISTER_WIDTH + 3;
+/// Position of the bit decomposition of sigma. The range proofs on sigma and on the receiver's
+/// updated balance run alongside the signature verification, on Merkle registers left idle
+/// after the hash state of the digest of transactions, unless the `legacy-layout` feature
+/// keeps them in dedicated registers after the copied values
+#[cfg(not(feature = "legacy-layout"))]
+pub(crate) const SIGMA_BIT_POS: usize = TX_DIGEST_HASH_POS + merkle_const::HASH_STATE_WIDTH;
 /// Position of the bit decomposition of sigma
-pub(crate) const SIGMA_BIT_POS: usize = NONCE_COPY_POS + 1;
-/// Position of the accumulated value for delta
-pub(crate) const SIGMA_ACCUMULATE_POS: usize = NONCE_COPY_POS + 2;
+#[cfg(feature = "legacy-layout")]
+pub(crate) const SIGMA_BIT_POS: usize = RECEIVER_BALANCE_COPY_POS + 1;
+/// Position of the accumulated value for sigma
+pub(crate) const SIGMA_ACCUMULATE_POS: usize = SIGMA_BIT_POS + 1;
+/// Position of the bit decomposition of the receiver's updated balance
+pub(crate) const RECEIVER_BALANCE_BIT_POS: usize = SIGMA_BIT_POS + 2;
+/// Position of the accumulated value for the receiver's updated balance
+pub(crate) const RECEIVER_BALANCE_ACCUMULATE_POS: usize = SIGMA_BIT_POS + 3;
+
+/// Beginning position of the hash state deriving the receiver's index from its public key,
+/// alongside the last iteration of the hash of the digest of transactions deriving the
+/// sender's one, on Merkle registers left idle after the range proofs
+#[cfg(not(feature = "legacy-layout"))]
+pub(crate) const KEY_INDEX_HASH_POS: usize = RECEIVER_BALANCE_ACCUMULATE_POS + 1;
+/// Beginning position of the hash state deriving the receiver's index from its public key
+#[cfg(feature = "legacy-layout")]
+pub(crate) const KEY_INDEX_HASH_POS: usize = TX_DIGEST_HASH_POS + merkle_const::HASH_STATE_WIDTH;
+/// Position of the bit decomposition of the hash of the sender's key, least significant bit
+/// first, reusing the registers of the range proof on delta once it is over
+pub(crate) const SENDER_KEY_INDEX_BIT_POS: usize = DELTA_BIT_POS;
+/// Position of the value accumulated from the bits of the hash of the sender's key
+pub(crate) const SENDER_KEY_INDEX_ACCUMULATE_POS: usize = DELTA_ACCUMULATE_POS;
+/// Position of the flag telling whether the value accumulated from the bits of the hash of
+/// the sender's key is at most the field modulus minus one over the bits inserted so far
+pub(crate) const SENDER_KEY_INDEX_BOUND_POS: usize = FEE_BIT_POS;
+/// Position of the bit decomposition of the hash of the receiver's key, reusing the registers
+/// of the range proof on sigma once it is over
+pub(crate) const RECEIVER_KEY_INDEX_BIT_POS: usize = SIGMA_BIT_POS;
+/// Position of the value accumulated from the bits of the hash of the receiver's key
+pub(crate) const RECEIVER_KEY_INDEX_ACCUMULATE_POS: usize = SIGMA_ACCUMULATE_POS;
+/// Position of the comparison of the hash of the receiver's key with the field modulus
+pub(crate) const RECEIVER_KEY_INDEX_BOUND_POS: usize = RECEIVER_BALANCE_BIT_POS;
+/// Number of bits of the decomposition of the hash of a key, i.e. of the field modulus
+pub(crate) const KEY_INDEX_NUM_BITS: usize = 63;
+
+/// Number of registers chaining the outputs of split transfers
+pub(crate) const SPLIT_REGISTER_WIDTH: usize = MAX_SPLIT_OUTPUTS + 4;
+/// Position of the register flagging outputs of split transfers. This register, as the
+/// following split registers, holds the values of the previous transaction at the first step
+/// of each transaction
+#[cfg(not(feature = "legacy-layout"))]
+pub(crate) const SPLIT_FLAG_POS: usize = RECEIVER_BALANCE_COPY_POS + 1;
+/// Position of the register flagging outputs of split transfers
+#[cfg(feature = "legacy-layout")]
+pub(crate) const SPLIT_FLAG_POS: usize = RECEIVER_BALANCE_ACCUMULATE_POS + 1;
+/// Beginning position of the registers flagging the position of the output of a split
+/// transfer, the first one being set for other transactions
+pub(crate) const SPLIT_POSITION_POS: usize = SPLIT_FLAG_POS + 1;
+/// Position of the register holding the signed amount, i.e. the sum of all outputs of a split
+/// transfer, or delta for other transactions
+pub(crate) const SPLIT_AMOUNT_POS: usize = SPLIT_POSITION_POS + MAX_SPLIT_OUTPUTS;
+/// Position of the register holding the amount sent by a split transfer up to the current
+/// output included, or delta for other transactions
+pub(crate) const SPLIT_SENT_POS: usize = SPLIT_AMOUNT_POS + 1;
+/// Position of the register holding the amount remaining to be sent by a split transfer
+/// after the current output
+pub(crate) const SPLIT_REMAINING_POS: usize = SPLIT_SENT_POS + 1;
+/// Position of the register accumulating the receiver's index from its Merkle path bits
+pub(crate) const RECEIVER_INDEX_POS: usize = SPLIT_FLAG_POS + SPLIT_REGISTER_WIDTH;
+/// Position of the register accumulating the sender's index from its Merkle path bits, which
+/// is part of the signed message
+pub(crate) const SENDER_INDEX_POS: usize = RECEIVER_INDEX_POS + 1;
+/// Position of the register flagging no-op transactions
+pub(crate) const NOOP_FLAG_POS: usize = SENDER_INDEX_POS + 1;
+/// Number of registers holding the memo of a transaction, as chunks of 7 bytes
+pub(crate) const MEMO_WIDTH: usize = 5;
+/// Beginning position of the registers copying the memo of the transaction, which is part of
+/// the signed message
+pub(crate) const MEMO_POS: usize = NOOP_FLAG_POS + 1;
+/// Index of the subnet identifier within the signed message, following the memo. It is not
+/// copied in the trace, being a public input absorbed as a constant by the message hash
+pub(crate) const SUBNET_ID_MESSAGE_INDEX: usize =
+    schnorr_const::AFFINE_POINT_WIDTH * 2 + 4 + MEMO_WIDTH;
+/// Index of the epoch within the signed message, following the subnet identifier. As the
+/// latter, it is a public input absorbed as a constant by the message hash
+pub(crate) const EPOCH_MESSAGE_INDEX: usize = SUBNET_ID_MESSAGE_INDEX + 1;
+/// Index of the sender's index within the signed message, following the epoch, read from the
+/// register accumulating it, so that a signature only applies to the leaves it was produced for
+pub(crate) const SENDER_INDEX_MESSAGE_INDEX: usize = EPOCH_MESSAGE_INDEX + 1;
+/// Index of the receiver's index within the signed message, following the sender's one. Split
+/// transfers leave it to zero, as their outputs already sign the index of each receiver
+pub(crate) const RECEIVER_INDEX_MESSAGE_INDEX: usize = SENDER_INDEX_MESSAGE_INDEX + 1;
+/// Beginning position of the registers holding the running digest of the transactions prior to
+/// the current one, updated with the (sender, receiver, delta) tuple of every transaction but
+/// no-ops once hashed
+pub(crate) const TX_DIGEST_POS: usize = MEMO_POS + MEMO_WIDTH;
+/// Number of elements of the (sender, receiver, delta) tuple of a transaction, read from the
+/// consecutive copies of the keys and of delta
+pub(crate) const TX_TUPLE_WIDTH: usize = schnorr_const::AFFINE_POINT_WIDTH * 2 + 1;
+/// Number of hash iterations absorbing the tuple of a transaction, one chunk at a time
+pub(crate) const TX_TUPLE_NUM_CHUNKS: usize =
+    (TX_TUPLE_WIDTH - 1) / merkle_const::HASH_RATE_WIDTH + 1;
+/// Beginning position of the hash state absorbing the running digest of transactions and the
+/// tuple of the current transaction, then computing the nullifier of the transaction and
+/// merging it with the commitment to the previous nullifiers. It runs alongside the hash of the
+/// signed message, on Merkle registers left idle during the signature verification
+pub(crate) const TX_DIGEST_HASH_POS: usize = FEE_ACCUMULATE_POS + 1;
+/// Beginning position of the registers holding the commitment to the nullifiers of the
+/// transactions prior to the current one, updated with the nullifier of every transaction
+/// consuming a nonce, i.e. neither deposits nor no-ops
+pub(crate) const NULLIFIERS_POS: usize = TX_DIGEST_POS + merkle_const::HASH_RATE_WIDTH;
+/// Position of the register counting the transactions prior to the current one, no-ops
+/// excluded, incremented along with the running digest of transactions
+pub(crate) const TX_COUNT_POS: usize = NULLIFIERS_POS + merkle_const::HASH_RATE_WIDTH;
+/// Position of the register accumulating the amounts of the transactions prior to the current
+/// one, read from the copy of delta
+pub(crate) const TX_VOLUME_POS: usize = TX_COUNT_POS + 1;
+/// Position of the register flagging rekeying transactions, whose sender's key is replaced by
+/// the copy of the receiver's key, i.e. the new key they sign
+pub(crate) const REKEY_FLAG_POS: usize = TX_VOLUME_POS + 1;
+/// Position of the register flagging the levels of the Merkle phase above the lowest common
+/// ancestor of the sender's and receiver's leaves, from the level hashing both of its children,
+/// over which the receiver's initial authentication path is the sender's updated one
+pub(crate) const SHARED_PATH_FLAG_POS: usize = REKEY_FLAG_POS + 1;
+/// Position of the register holding the sign of delta, set for refunds debiting the receiver,
+/// whose range proof then decomposes delta offset by the weight of the range
+pub(crate) const DELTA_SIGN_POS: usize = SHARED_PATH_FLAG_POS + 1;
+/// Position of the register flagging committee transfers, sent from an account whose key is
+/// the pseudo-key of a committee and signed by one of its members
+pub(crate) const COMMITTEE_FLAG_POS: usize = DELTA_SIGN_POS + 1;
+/// Position of the register flagging approvals, following a committee transfer or another
+/// approval, each of them verifying the signature of one more member of the committee
+pub(crate) const APPROVAL_FLAG_POS: usize = COMMITTEE_FLAG_POS + 1;
+/// Number of registers carried from a committee transfer to its approvals, holding the values
+/// of the previous transaction at the first step of each transaction
+pub(crate) const COMMITTEE_REGISTER_WIDTH: usize = merkle_const::HASH_RATE_WIDTH + 3;
+/// Beginning position of the registers holding the root of the committee's key tree, read
+/// from the sender's leaf
+pub(crate) const COMMITTEE_ROOT_POS: usize = APPROVAL_FLAG_POS + 1;
+/// Position of the register holding the number of approvals left to reach the threshold
+pub(crate) const APPROVALS_LEFT_POS: usize = COMMITTEE_ROOT_POS + merkle_const::HASH_RATE_WIDTH;
+/// Position of the register accumulating the index of the member's key from the bits of its
+/// authentication path
+pub(crate) const MEMBER_INDEX_POS: usize = APPROVALS_LEFT_POS + 1;
+/// Position of the register holding the index of the member of the previous transaction
+pub(crate) const PREVIOUS_MEMBER_INDEX_POS: usize = MEMBER_INDEX_POS + 1;
+/// Beginning position of the hash state of the authentication path of the member's key in
+/// the key tree, followed by the bits of its index, over the Merkle phase
+pub(crate) const KEY_PATH_POS: usize = PREVIOUS_MEMBER_INDEX_POS + 1;
+/// Position of the bit decomposition of the gap between the indices of consecutive members,
+/// minus one, reusing the registers of the authentication path once it is over
+pub(crate) const MEMBER_GAP_BIT_POS: usize = KEY_PATH_POS;
+/// Position of the value accumulated from the bits of the gap between members' indices
+pub(crate) const MEMBER_GAP_ACCUMULATE_POS: usize = KEY_PATH_POS + 1;
+/// Position of the register holding the inverse of the last coordinate of the signing key
+pub(crate) const SIGNING_KEY_INVERSE_POS: usize = KEY_PATH_POS + merkle_const::HASH_STATE_WIDTH + 1;
+/// Domain separator of the hash of a sender's public key and nonce into a nullifier
+pub(crate) const NULLIFIER_DOMAIN: BaseElement = BaseElement::new(0x6e756c6c696679);
+/// Domain separator of the hash of a public key into the index of its account
+pub(crate) const ACCOUNT_INDEX_DOMAIN: BaseElement = BaseElement::new(0x6163636f756e74);
 
 /// Total length for verifying a transaction
 // Dominated by the Merkle authentication paths and the Schnorr signature verification
@@ -103,8 +390,10 @@ pub(crate) const DOUBLING_MASK_INDEX: usize = SCALAR_MULT_MASK_INDEX + 1;
 pub(crate) const SCHNORR_DIGEST_MASK_INDEX: usize = DOUBLING_MASK_INDEX + 1;
 /// The index for the Schnorr hash mask
 pub(crate) const SCHNORR_HASH_MASK_INDEX: usize = SCHNORR_DIGEST_MASK_INDEX + 4;
+/// The index for the mask holding the Schnorr hash state once the message is hashed
+pub(crate) const SCHNORR_HASH_HOLD_MASK_INDEX: usize = SCHNORR_HASH_MASK_INDEX + 1;
 /// The starting index for the flags for copying parts to the hash internal inputs
-pub(crate) const HASH_INTERNAL_INPUT_MASKS_INDEX: usize = SCHNORR_HASH_MASK_INDEX + 1;
+pub(crate) const HASH_INTERNAL_INPUT_MASKS_INDEX: usize = SCHNORR_HASH_HOLD_MASK_INDEX + 1;
 /// The index for the mask specifying range proof computations
 pub(crate) const RANGE_PROOF_STEP_MASK_INDEX: usize =
     HASH_INTERNAL_INPUT_MASKS_INDEX + schnorr_const::NUM_HASH_ITER - 1;
@@ -112,5 +401,63 @@ pub(crate) const RANGE_PROOF_STEP_MASK_INDEX: usize =
 pub(crate) const RANGE_PROOF_FINISH_MASK_INDEX: usize = RANGE_PROOF_STEP_MASK_INDEX + 1;
 /// The index for the mask checking carry-over of values from Merkle to Schnorr
 pub(crate) const VALUE_COPY_MASK_INDEX: usize = RANGE_PROOF_FINISH_MASK_INDEX + 1;
+/// The index for the mask checking carry-over of the sender's key until the end of the
+/// signature verification
+pub(crate) const KEY_COPY_MASK_INDEX: usize = VALUE_COPY_MASK_INDEX + 1;
+/// The index for the weights of the sender's and receiver's index bits
+pub(crate) const INDEX_WEIGHTS_INDEX: usize = KEY_COPY_MASK_INDEX + 1;
+/// The index for the mask initializing the hash state of the running digest of transactions
+pub(crate) const TX_DIGEST_SETUP_MASK_INDEX: usize = INDEX_WEIGHTS_INDEX + 1;
+/// The index for the mask updating the commitment to nullifiers once hashed
+pub(crate) const NULLIFIERS_FINISH_MASK_INDEX: usize = TX_DIGEST_SETUP_MASK_INDEX + 1;
+/// The index for the mask setting up the decompositions of the hashes of the keys
+pub(crate) const KEY_INDEX_SETUP_MASK_INDEX: usize = NULLIFIERS_FINISH_MASK_INDEX + 1;
+/// The index for the mask specifying the steps of the decompositions of the hashes of the keys
+pub(crate) const KEY_INDEX_STEP_MASK_INDEX: usize = KEY_INDEX_SETUP_MASK_INDEX + 1;
+/// The index for the weights of the bits of the hashes of the keys
+pub(crate) const KEY_INDEX_WEIGHTS_INDEX: usize = KEY_INDEX_STEP_MASK_INDEX + 1;
+/// The index for the bits of the field modulus minus one, against which the hashes of the keys
+/// are compared
+pub(crate) const KEY_INDEX_BOUND_INDEX: usize = KEY_INDEX_WEIGHTS_INDEX + 1;
+/// The index for the mask checking the indices once their bits are inserted
+pub(crate) const KEY_INDEX_CHECK_MASK_INDEX: usize = KEY_INDEX_BOUND_INDEX + 1;
+/// The index for the mask checking the hashes of the keys once all their bits are inserted
+pub(crate) const KEY_INDEX_FINISH_MASK_INDEX: usize = KEY_INDEX_CHECK_MASK_INDEX + 1;
 /// The starting index for the Rescue round constants
-pub(crate) const ARK_INDEX: usize = VALUE_COPY_MASK_INDEX + 1;
+pub(crate) const ARK_INDEX: usize = KEY_INDEX_FINISH_MASK_INDEX + 1;
+/// The starting index for the round constants of the Merkle tree hash function
+pub(crate) const TREE_ARK_INDEX: usize = ARK_INDEX + merkle_const::HASH_STATE_WIDTH * 2;
+
+// The tuple of a transaction is read from the copies of its keys and delta, and absorbed by the
+// hash iterations of the signed message, followed by two iterations for the nullifier and one
+// for the index of the sender, on registers left idle by the Merkle component
+const _: () = assert!(
+    SENDER_KEY_POINT_POS + TX_TUPLE_WIDTH == DELTA_COPY_POS + 1
+        && TX_TUPLE_NUM_CHUNKS + 3 <= schnorr_const::NUM_HASH_ITER
+        && TX_DIGEST_HASH_POS + merkle_const::HASH_STATE_WIDTH <= merkle_const::PREV_TREE_ROOT_POS,
+    "the digest of transactions must fit within the signature verification"
+);
+
+// The range proofs on sigma and on the receiver's updated balance share the Merkle registers
+// following the hash state of the digest of transactions
+#[cfg(not(feature = "legacy-layout"))]
+const _: () = assert!(
+    RECEIVER_BALANCE_ACCUMULATE_POS < merkle_const::PREV_TREE_ROOT_POS,
+    "the range proofs must fit within the idle Merkle registers"
+);
+
+// The hash state deriving the receiver's index follows, and the decompositions of the hashes of
+// the keys fit between the end of the range proofs and the end of the signature verification
+const _: () = assert!(
+    KEY_INDEX_HASH_POS + merkle_const::HASH_STATE_WIDTH <= merkle_const::PREV_TREE_ROOT_POS
+        && KEY_INDEX_HASH_POS >= TX_DIGEST_HASH_POS + merkle_const::HASH_STATE_WIDTH
+        && range_const::MAX_RANGE_LOG + 1 + KEY_INDEX_NUM_BITS <= schnorr_const::SIG_CYCLE_LENGTH
+        && 1u64 << KEY_INDEX_NUM_BITS > BaseElement::MODULUS,
+    "the derivation of indices must fit within the signature verification"
+);
+
+// The registers carried from a committee transfer to its approvals are contiguous
+const _: () = assert!(
+    PREVIOUS_MEMBER_INDEX_POS + 1 == COMMITTEE_ROOT_POS + COMMITTEE_REGISTER_WIDTH,
+    "the committee registers must be contiguous"
+);
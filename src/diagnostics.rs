@@ -0,0 +1,648 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::air::transition_constraint_degrees;
+use super::constants::merkle_const::{
+    BALANCE_CONSTRAINT_RES, COLLECTOR_BIT_POS, COLLECTOR_INITIAL_POS, COLLECTOR_UPDATED_POS,
+    HASH_RATE_WIDTH, HASH_STATE_WIDTH, INT_ROOT_EQUALITY_RES, NONCE_UPDATE_CONSTRAINT_RES,
+    PREV_TREE_MATCH_RES, PREV_TREE_ROOT_POS, RECEIVER_BIT_POS, RECEIVER_INITIAL_POS,
+    RECEIVER_UPDATED_POS, SENDER_BIT_POS, SENDER_INITIAL_POS, SENDER_UPDATED_POS,
+    VALUE_CONSTRAINT_RES,
+};
+use super::constants::schnorr_const::{
+    ADDEND_POS, ADDITION_FLAG_POS, AFFINE_POINT_WIDTH, HASH_STATE_POS as SCHNORR_HASH_STATE_POS,
+    H_BIT_POS, H_LIMBS_POS, PROJECTIVE_POINT_WIDTH, S_BIT_POS,
+};
+use super::constants::*;
+use super::merkle::TreeHasher;
+use super::TransactionAir;
+use core::{fmt, ops::Range};
+use winterfell::{
+    math::{fft, fields::f63::BaseElement, polynom, FieldElement, StarkField},
+    Air, EvaluationFrame, Trace, TraceTable, TransitionConstraintDegree,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// CONSTRAINT GROUPS
+// ================================================================================================
+
+/// A named group of transition constraints of the transaction AIR program.
+///
+/// The constraints of a group are evaluated into consecutive slots of the constraint
+/// evaluations. Sub-programs running at different steps of a transaction, such as the Merkle
+/// authentication paths and the Schnorr signature verification, share the same slots, so
+/// that a slot can belong to several groups.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintGroup {
+    /// Human-readable name of the constraints
+    pub name: &'static str,
+    /// Slots of the constraint evaluations holding the constraints
+    pub slots: Range<usize>,
+    /// First register constrained by the group, the following slots constraining the following
+    /// registers, or `None` if the constraints span several registers each
+    pub first_register: Option<usize>,
+}
+
+impl ConstraintGroup {
+    /// Returns the register constrained at the provided slot, if known.
+    pub fn register(&self, slot: usize) -> Option<usize> {
+        if self.slots.contains(&slot) {
+            self.first_register
+                .map(|register| register + slot - self.slots.start)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the groups of transition constraints of the transaction AIR program.
+pub fn constraint_groups() -> Vec<ConstraintGroup> {
+    let on_registers = |name, start: usize, length: usize| ConstraintGroup {
+        name,
+        slots: start..start + length,
+        first_register: Some(start),
+    };
+    let copying = |name, start: usize, length: usize, register: usize| ConstraintGroup {
+        name,
+        slots: start..start + length,
+        first_register: Some(register),
+    };
+    let spanning = |name, start: usize, length: usize| ConstraintGroup {
+        name,
+        slots: start..start + length,
+        first_register: None,
+    };
+
+    vec![
+        // Merkle authentication paths, over the first half of each transaction
+        on_registers(
+            "Merkle: sender's initial leaf hash",
+            SENDER_INITIAL_POS,
+            HASH_STATE_WIDTH,
+        ),
+        on_registers("Merkle: sender's index bit", SENDER_BIT_POS, 1),
+        on_registers(
+            "Merkle: sender's updated leaf hash",
+            SENDER_UPDATED_POS,
+            HASH_STATE_WIDTH,
+        ),
+        on_registers(
+            "Merkle: receiver's initial leaf hash",
+            RECEIVER_INITIAL_POS,
+            HASH_STATE_WIDTH,
+        ),
+        on_registers("Merkle: receiver's index bit", RECEIVER_BIT_POS, 1),
+        on_registers(
+            "Merkle: receiver's updated leaf hash",
+            RECEIVER_UPDATED_POS,
+            HASH_STATE_WIDTH,
+        ),
+        on_registers(
+            "Merkle: fee collector's initial leaf hash",
+            COLLECTOR_INITIAL_POS,
+            HASH_STATE_WIDTH,
+        ),
+        on_registers("Merkle: fee collector's index bit", COLLECTOR_BIT_POS, 1),
+        on_registers(
+            "Merkle: fee collector's updated leaf hash",
+            COLLECTOR_UPDATED_POS,
+            HASH_STATE_WIDTH,
+        ),
+        on_registers(
+            "Merkle: previous tree root",
+            PREV_TREE_ROOT_POS,
+            HASH_RATE_WIDTH,
+        ),
+        // Schnorr signature verification, over the second half of each transaction
+        on_registers("Schnorr: s.G + h.P accumulator", 0, PROJECTIVE_POINT_WIDTH),
+        on_registers("Schnorr: addition flag", ADDITION_FLAG_POS, 1),
+        on_registers("Schnorr: bit of s", S_BIT_POS, 1),
+        on_registers("Schnorr: bit of h", H_BIT_POS, 1),
+        on_registers("Schnorr: added point", ADDEND_POS, AFFINE_POINT_WIDTH),
+        on_registers(
+            "Schnorr: challenge limbs",
+            H_LIMBS_POS,
+            SCHNORR_HASH_STATE_POS - H_LIMBS_POS,
+        ),
+        on_registers(
+            "Schnorr: challenge hash state",
+            SCHNORR_HASH_STATE_POS,
+            SCHNORR_REGISTER_WIDTH - SCHNORR_HASH_STATE_POS,
+        ),
+        // Range proofs, alongside the signature verification
+        on_registers("range proof: bit of delta", DELTA_BIT_POS, 1),
+        on_registers("range proof: accumulated delta", DELTA_ACCUMULATE_POS, 1),
+        on_registers("range proof: bit of the fee", FEE_BIT_POS, 1),
+        on_registers("range proof: accumulated fee", FEE_ACCUMULATE_POS, 1),
+        on_registers("range proof: bit of sigma", SIGMA_BIT_POS, 1),
+        on_registers("range proof: accumulated sigma", SIGMA_ACCUMULATE_POS, 1),
+        on_registers(
+            "range proof: bit of the receiver's balance",
+            RECEIVER_BALANCE_BIT_POS,
+            1,
+        ),
+        on_registers(
+            "range proof: accumulated receiver's balance",
+            RECEIVER_BALANCE_ACCUMULATE_POS,
+            1,
+        ),
+        // Setup of each transaction
+        spanning(
+            "setup: sender's key, unchanged unless rekeyed",
+            VALUE_CONSTRAINT_RES,
+            AFFINE_POINT_WIDTH,
+        ),
+        spanning(
+            "setup: unchanged receiver's key",
+            VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH,
+            AFFINE_POINT_WIDTH,
+        ),
+        spanning(
+            "setup: receiver's nonce, unchanged unless refunded",
+            VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 2,
+            1,
+        ),
+        spanning(
+            "setup: unchanged fee collector's key and nonce",
+            VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 2 + 1,
+            AFFINE_POINT_WIDTH + 1,
+        ),
+        spanning("setup: balance update", BALANCE_CONSTRAINT_RES, 1),
+        spanning(
+            "setup: sender's nonce update",
+            NONCE_UPDATE_CONSTRAINT_RES,
+            1,
+        ),
+        spanning(
+            "Merkle: intermediate roots equality",
+            INT_ROOT_EQUALITY_RES,
+            2 * HASH_RATE_WIDTH,
+        ),
+        spanning("Merkle: previous tree root match", PREV_TREE_MATCH_RES, 2),
+        // Values copied along each transaction
+        spanning("copy: receiver's key", RECEIVER_KEY_POINT_RES, 2),
+        copying("copy: delta", DELTA_COPY_RES, 1, DELTA_COPY_POS),
+        copying(
+            "copy: sender's updated balance",
+            SIGMA_COPY_RES,
+            1,
+            SIGMA_COPY_POS,
+        ),
+        copying("copy: signer's nonce", NONCE_COPY_RES, 1, NONCE_COPY_POS),
+        copying("copy: fee", FEE_COPY_RES, 1, FEE_COPY_POS),
+        copying(
+            "copy: withdrawal flag",
+            WITHDRAWAL_FLAG_RES,
+            1,
+            WITHDRAWAL_FLAG_POS,
+        ),
+        copying("copy: deposit flag", DEPOSIT_FLAG_RES, 1, DEPOSIT_FLAG_POS),
+        copying(
+            "copy: receiver's updated balance",
+            RECEIVER_BALANCE_COPY_RES,
+            1,
+            RECEIVER_BALANCE_COPY_POS,
+        ),
+        copying("copy: memo", MEMO_COPY_RES, MEMO_WIDTH, MEMO_POS),
+        // Results of the range proofs
+        spanning("range proof: delta", DELTA_RANGE_RES, 1),
+        spanning("range proof: sigma", SIGMA_RANGE_RES, 1),
+        spanning("range proof: fee", FEE_RANGE_RES, 1),
+        spanning(
+            "range proof: receiver's balance",
+            RECEIVER_BALANCE_RANGE_RES,
+            1,
+        ),
+        // Split transfers, no-ops and leaf indices
+        copying(
+            "split transfer: registers",
+            SPLIT_REGISTERS_RES,
+            SPLIT_REGISTER_WIDTH,
+            SPLIT_FLAG_POS,
+        ),
+        copying(
+            "receiver's index accumulation",
+            RECEIVER_INDEX_RES,
+            1,
+            RECEIVER_INDEX_POS,
+        ),
+        copying(
+            "sender's index accumulation",
+            SENDER_INDEX_RES,
+            1,
+            SENDER_INDEX_POS,
+        ),
+        spanning(
+            "split transfer: outputs chaining",
+            SPLIT_CHAIN_RES,
+            NUM_SPLIT_CHAIN_CONSTRAINTS,
+        ),
+        spanning("no-op: flag and amounts", NOOP_RES, NUM_NOOP_CONSTRAINTS),
+        // Running digest of transactions and commitment to nullifiers
+        copying(
+            "running digest of transactions",
+            TX_DIGEST_RES,
+            HASH_RATE_WIDTH,
+            TX_DIGEST_POS,
+        ),
+        copying(
+            "transactions digest and nullifiers hash state",
+            TX_DIGEST_HASH_RES,
+            HASH_STATE_WIDTH,
+            TX_DIGEST_HASH_POS,
+        ),
+        copying(
+            "commitment to nullifiers",
+            NULLIFIERS_RES,
+            HASH_RATE_WIDTH,
+            NULLIFIERS_POS,
+        ),
+        copying("number of transactions", TX_COUNT_RES, 1, TX_COUNT_POS),
+        copying("volume of transactions", TX_VOLUME_RES, 1, TX_VOLUME_POS),
+        // Binding of the key verifying the signature to the sender's leaf, to the deposit key
+        // for deposits and no-ops, or to the receiver's leaf for refunds, up to the end of the
+        // signature verification
+        copying(
+            "Schnorr: signing key from the sender's leaf",
+            SENDER_KEY_POINT_RES,
+            AFFINE_POINT_WIDTH,
+            SENDER_KEY_POINT_POS,
+        ),
+        spanning(
+            "rekeying: flag and amounts",
+            REKEY_RES,
+            NUM_REKEY_CONSTRAINTS,
+        ),
+        spanning("Merkle: shared path flag", SHARED_PATH_RES, 1),
+        spanning(
+            "Merkle: receiver's path shared with the sender's",
+            SHARED_PATH_RES + 1,
+            NUM_SHARED_PATH_CONSTRAINTS - 1,
+        ),
+        copying("refund: sign of delta", DELTA_SIGN_RES, 1, DELTA_SIGN_POS),
+        spanning(
+            "refund: flags and fee",
+            DELTA_SIGN_RES + 1,
+            NUM_DELTA_SIGN_CONSTRAINTS - 1,
+        ),
+        // Committee transfers and their approvals, each signed by a member of the committee
+        // whose key is authenticated in its key tree
+        copying("committee: flag", COMMITTEE_RES, 1, COMMITTEE_FLAG_POS),
+        copying(
+            "committee: approval flag",
+            COMMITTEE_RES + 1,
+            1,
+            APPROVAL_FLAG_POS,
+        ),
+        spanning(
+            "committee: approvals following committee transfers",
+            COMMITTEE_RES + 2,
+            NUM_COMMITTEE_CONSTRAINTS - 2,
+        ),
+        copying(
+            "committee: member's key path",
+            KEY_PATH_RES,
+            HASH_STATE_WIDTH + 1,
+            KEY_PATH_POS,
+        ),
+        spanning(
+            "committee: member's leaf and key tree root",
+            KEY_TREE_ROOT_RES,
+            HASH_RATE_WIDTH,
+        ),
+        copying(
+            "committee: key tree root",
+            COMMITTEE_ROOT_RES,
+            HASH_RATE_WIDTH,
+            COMMITTEE_ROOT_POS,
+        ),
+        copying(
+            "committee: approvals left",
+            APPROVALS_LEFT_RES,
+            1,
+            APPROVALS_LEFT_POS,
+        ),
+        spanning("committee: no approval left", APPROVALS_LEFT_RES + 1, 1),
+        copying(
+            "committee: member's index",
+            MEMBER_INDEX_RES,
+            1,
+            MEMBER_INDEX_POS,
+        ),
+        copying(
+            "committee: previous member's index",
+            MEMBER_INDEX_RES + 1,
+            1,
+            PREVIOUS_MEMBER_INDEX_POS,
+        ),
+        copying(
+            "committee: gap between members' indices",
+            MEMBER_GAP_RES,
+            2,
+            MEMBER_GAP_BIT_POS,
+        ),
+        spanning(
+            "committee: increasing members' indices",
+            MEMBER_GAP_RES + 2,
+            1,
+        ),
+        spanning(
+            "committee: amount and fee of approvals",
+            APPROVAL_COPY_RES,
+            2,
+        ),
+        copying(
+            "signing key inverse",
+            SIGNING_KEY_INVERSE_RES,
+            1,
+            SIGNING_KEY_INVERSE_POS,
+        ),
+        // Binding of the indices of accounts to their keys, if required by the public inputs
+        copying(
+            "derived indices: receiver's key hash state",
+            KEY_INDEX_HASH_RES,
+            HASH_STATE_WIDTH,
+            KEY_INDEX_HASH_POS,
+        ),
+        spanning(
+            "derived indices: sender's key hash decomposition",
+            SENDER_KEY_INDEX_RES,
+            NUM_KEY_INDEX_CONSTRAINTS,
+        ),
+        spanning(
+            "derived indices: receiver's key hash decomposition",
+            RECEIVER_KEY_INDEX_RES,
+            NUM_KEY_INDEX_CONSTRAINTS,
+        ),
+    ]
+}
+
+/// Returns the names of the groups of transition constraints evaluated at the provided slot.
+pub fn constraint_names(slot: usize) -> Vec<&'static str> {
+    constraint_groups()
+        .into_iter()
+        .filter(|group| group.slots.contains(&slot))
+        .map(|group| group.name)
+        .collect()
+}
+
+// CONSTRAINT FAILURE
+// ================================================================================================
+
+/// A transition constraint of the transaction AIR program not satisfied by an execution trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintFailure {
+    /// Slot of the constraint evaluations holding the failing constraint
+    pub constraint: usize,
+    /// Names of the groups of constraints evaluated at this slot
+    pub names: Vec<&'static str>,
+    /// Step of the trace at which the constraint fails, against the following one
+    pub step: usize,
+    /// Registers constrained at this slot, if known
+    pub registers: Vec<usize>,
+}
+
+impl ConstraintFailure {
+    fn new(constraint: usize, step: usize) -> Self {
+        let groups: Vec<_> = constraint_groups()
+            .into_iter()
+            .filter(|group| group.slots.contains(&constraint))
+            .collect();
+        let mut registers: Vec<_> = groups
+            .iter()
+            .filter_map(|group| group.register(constraint))
+            .collect();
+        registers.sort_unstable();
+        registers.dedup();
+
+        Self {
+            constraint,
+            names: groups.iter().map(|group| group.name).collect(),
+            step,
+            registers,
+        }
+    }
+}
+
+impl fmt::Display for ConstraintFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "constraint {} ({}) fails at step {}",
+            self.constraint,
+            self.names.join(" / "),
+            self.step
+        )?;
+        if !self.registers.is_empty() {
+            write!(f, " on registers {:?}", self.registers)?;
+        }
+        Ok(())
+    }
+}
+
+// CONSTRAINT DEGREE
+// ================================================================================================
+
+/// The degree of a transition constraint of the transaction AIR program over an execution
+/// trace, along with the one declared for it when building the AIR program.
+///
+/// The prover relies on the declared degrees to size the constraint evaluation domain, so that
+/// a constraint whose actual degree differs from its declared one only surfaces as a failure to
+/// verify the resulting proofs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintDegree {
+    /// Slot of the constraint evaluations holding the constraint
+    pub constraint: usize,
+    /// Names of the groups of constraints evaluated at this slot
+    pub names: Vec<&'static str>,
+    /// Degree of the constraint polynomial declared by the AIR program
+    pub declared: usize,
+    /// Degree of the constraint polynomial over the execution trace
+    pub actual: usize,
+}
+
+impl ConstraintDegree {
+    /// Returns true if the actual degree of the constraint is the declared one.
+    pub fn is_consistent(&self) -> bool {
+        self.declared == self.actual
+    }
+}
+
+impl fmt::Display for ConstraintDegree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "constraint {} ({}) has degree {}, declared {}",
+            self.constraint,
+            self.names.join(" / "),
+            self.actual,
+            self.declared
+        )
+    }
+}
+
+// CONSTRAINT EVALUATION
+// ================================================================================================
+
+/// Evaluates the transition constraints of `air` over every step of `trace`, and returns the
+/// first one not satisfied, if any.
+pub(crate) fn check_transition_constraints<H: TreeHasher>(
+    air: &TransactionAir<H>,
+    trace: &TraceTable<BaseElement>,
+) -> Result<(), ConstraintFailure> {
+    match first_unsatisfied_constraint(air, trace) {
+        Some((constraint, step)) => Err(ConstraintFailure::new(constraint, step)),
+        None => Ok(()),
+    }
+}
+
+/// Evaluates the transition constraints of any AIR program over every step of `trace`, and
+/// returns the slot and step of the first one not satisfied, if any.
+pub(crate) fn first_unsatisfied_constraint<A: Air<BaseField = BaseElement>>(
+    air: &A,
+    trace: &TraceTable<BaseElement>,
+) -> Option<(usize, usize)> {
+    let periodic_columns = air.get_periodic_column_values();
+    let mut periodic_values = vec![BaseElement::ZERO; periodic_columns.len()];
+    let mut frame = EvaluationFrame::new(trace.width());
+    let mut result = vec![BaseElement::ZERO; air.context().num_transition_constraints()];
+
+    // the transition constraints do not apply from the last step
+    for step in 0..trace.length() - 1 {
+        trace.read_row_into(step, frame.current_mut());
+        trace.read_row_into(step + 1, frame.next_mut());
+        for (value, column) in periodic_values.iter_mut().zip(periodic_columns.iter()) {
+            *value = column[step % column.len()];
+        }
+        for evaluation in result.iter_mut() {
+            *evaluation = BaseElement::ZERO;
+        }
+
+        air.evaluate_transition(&frame, &periodic_values, &mut result);
+        if let Some(constraint) = result
+            .iter()
+            .position(|&evaluation| evaluation != BaseElement::ZERO)
+        {
+            return Some((constraint, step));
+        }
+    }
+
+    None
+}
+
+/// Evaluates the degree of each transition constraint of `air` over `trace`, and reports it
+/// along with the degree declared in `TransactionAir::new`.
+pub(crate) fn check_constraint_degrees<H: TreeHasher>(
+    air: &TransactionAir<H>,
+    trace: &TraceTable<BaseElement>,
+) -> Vec<ConstraintDegree> {
+    evaluate_constraint_degrees(
+        air,
+        &transition_constraint_degrees(air.binds_derived_indices()),
+        trace,
+    )
+    .into_iter()
+    .enumerate()
+    .map(|(constraint, (declared, actual))| ConstraintDegree {
+        constraint,
+        names: constraint_names(constraint),
+        declared,
+        actual,
+    })
+    .collect()
+}
+
+/// Evaluates the transition constraints of any AIR program over a coset of a domain extending
+/// the one of `trace`, and returns for each of them the evaluation degree of its `declared`
+/// degree along with the degree of the polynomial interpolating its evaluations.
+///
+/// The extended domain is twice as large as needed for the declared degrees, so that actual
+/// degrees up to twice the declared ones are reported exactly. Larger degrees wrap around the
+/// size of the domain, and are very unlikely to match the declared ones.
+pub(crate) fn evaluate_constraint_degrees<A: Air<BaseField = BaseElement>>(
+    air: &A,
+    declared: &[TransitionConstraintDegree],
+    trace: &TraceTable<BaseElement>,
+) -> Vec<(usize, usize)> {
+    let trace_length = trace.length();
+    let declared: Vec<_> = declared
+        .iter()
+        .map(|degree| degree.get_evaluation_degree(trace_length))
+        .collect();
+    let max_degree = declared.iter().copied().max().unwrap_or(0);
+    let domain_size = ((max_degree + 1).next_power_of_two() * 2).max(trace_length * 2);
+    let blowup = domain_size / trace_length;
+    let offset = BaseElement::GENERATOR;
+
+    // interpolate the columns of the trace and evaluate them over the extended domain
+    let trace_inv_twiddles = fft::get_inv_twiddles::<BaseElement>(trace_length);
+    let trace_twiddles = fft::get_twiddles::<BaseElement>(trace_length);
+    let columns: Vec<Vec<BaseElement>> = (0..trace.width())
+        .map(|register| {
+            let mut column: Vec<_> = (0..trace_length)
+                .map(|step| trace.get(register, step))
+                .collect();
+            fft::interpolate_poly(&mut column, &trace_inv_twiddles);
+            fft::evaluate_poly_with_offset(&column, &trace_twiddles, offset, blowup)
+        })
+        .collect();
+
+    // a periodic column of cycle length c is a polynomial in x^(n/c), evaluated over a domain
+    // of size c.blowup whose points repeat along the extended domain
+    let periodic_columns: Vec<Vec<BaseElement>> = air
+        .get_periodic_column_values()
+        .into_iter()
+        .map(|mut column| {
+            let cycle_length = column.len();
+            fft::interpolate_poly(&mut column, &fft::get_inv_twiddles(cycle_length));
+            fft::evaluate_poly_with_offset(
+                &column,
+                &fft::get_twiddles(cycle_length),
+                offset.exp((trace_length / cycle_length) as u64),
+                blowup,
+            )
+        })
+        .collect();
+
+    let num_constraints = air.context().num_transition_constraints();
+    let mut evaluations = vec![vec![BaseElement::ZERO; domain_size]; num_constraints];
+    let mut periodic_values = vec![BaseElement::ZERO; periodic_columns.len()];
+    let mut frame = EvaluationFrame::new(trace.width());
+    let mut result = vec![BaseElement::ZERO; num_constraints];
+    for point in 0..domain_size {
+        // the next step is one trace domain generator further, i.e. blowup points further
+        let next_point = (point + blowup) % domain_size;
+        for (register, column) in columns.iter().enumerate() {
+            frame.current_mut()[register] = column[point];
+            frame.next_mut()[register] = column[next_point];
+        }
+        for (value, column) in periodic_values.iter_mut().zip(periodic_columns.iter()) {
+            *value = column[point % column.len()];
+        }
+        for evaluation in result.iter_mut() {
+            *evaluation = BaseElement::ZERO;
+        }
+
+        air.evaluate_transition(&frame, &periodic_values, &mut result);
+        for (constraint, &evaluation) in result.iter().enumerate() {
+            evaluations[constraint][point] = evaluation;
+        }
+    }
+
+    let inv_twiddles = fft::get_inv_twiddles::<BaseElement>(domain_size);
+    evaluations
+        .into_iter()
+        .zip(declared)
+        .map(|(mut evaluations, declared)| {
+            fft::interpolate_poly_with_offset(&mut evaluations, &inv_twiddles, offset);
+            (declared, polynom::degree_of(&evaluations))
+        })
+        .collect()
+}
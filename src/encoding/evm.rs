@@ -0,0 +1,452 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Calldata-friendly encoding of state-transition proofs and their public inputs, for
+//! on-chain verifiers.
+//!
+//! Both encodings are sequences of 32-byte big-endian words. Field elements, indices and
+//! amounts take one word each, L1 addresses are right-aligned as Solidity addresses, and
+//! memos take one word as `bytes32`.
+//!
+//! Public inputs start with a head of `PUBLIC_INPUTS_HEAD_LENGTH` words at fixed offsets:
+//!
+//! | word   | content                                                  |
+//! |--------|----------------------------------------------------------|
+//! | 0..7   | initial root                                             |
+//! | 7..14  | final root                                               |
+//! | 14     | tree depth                                               |
+//! | 15     | fee collector index                                      |
+//! | 16     | range log                                                |
+//! | 17     | tree hash identifier                                     |
+//! | 18     | flags of the exposed optional inputs                     |
+//! | 19..26 | transactions digest, zero if not exposed                 |
+//! | 26..33 | nullifiers commitment, zero if not exposed               |
+//! | 33     | subnet identifier                                        |
+//! | 34     | epoch                                                    |
+//! | 35     | number of transactions, zero if not exposed              |
+//! | 36     | volume of transactions, zero if not exposed              |
+//! | 37     | byte offset of the withdrawals array                     |
+//! | 38     | byte offset of the deposits array                        |
+//! | 39     | byte offset of the memos array                           |
+//! | 40     | byte offset of the intermediate roots array              |
+//!
+//! The flags have their bits 0, 1, 2 and 3 set when the intermediate roots, the transactions
+//! digest, the nullifiers commitment and the batch statistics are respectively exposed, their
+//! bit 4 set when the indices of accounts are bound to their keys, and their bit 5 set when
+//! deltas are signed.
+//!
+//! Each array consists of its number of items followed by the items, withdrawals being
+//! encoded as (transaction index, amount, recipient), deposits as (transaction index, amount,
+//! receiver index), memos as (transaction index, data) and intermediate roots as their
+//! elements. Arrays follow the head in this order.
+//!
+//! Proofs start with a head of `PROOF_HEAD_LENGTH` words exposing their trace length and
+//! options, so that verifiers can check the proof parameters without parsing the proof:
+//! trace length, number of queries, blow-up factor, grinding factor, hash function (0 for
+//! BLAKE3-256, 1 for SHA3-256), field extension degree, FRI folding factor, FRI maximum
+//! remainder size, and byte length of the proof. The head is followed by the proof, as
+//! serialized by winterfell, padded with zeros to a whole number of words.
+
+use crate::air::{check_parameters, check_signed_range_log};
+use crate::constants::merkle_const::HASH_RATE_WIDTH;
+use crate::{BatchStatistics, Deposit, Memo, PublicInputs, Withdrawal};
+use core::convert::TryFrom;
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
+    DeserializationError, HashFunction, StarkProof,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Size in bytes of an EVM word
+pub const WORD_SIZE: usize = 32;
+
+/// Number of words of the head of encoded public inputs
+pub const PUBLIC_INPUTS_HEAD_LENGTH: usize = 41;
+
+/// Number of words of the head of encoded proofs
+pub const PROOF_HEAD_LENGTH: usize = 9;
+
+/// Flag set when the intermediate roots are exposed
+const INTERMEDIATE_ROOTS_FLAG: u64 = 1;
+
+/// Flag set when the transactions digest is exposed
+const TRANSACTIONS_DIGEST_FLAG: u64 = 2;
+
+/// Flag set when the nullifiers commitment is exposed
+const NULLIFIERS_COMMITMENT_FLAG: u64 = 4;
+
+/// Flag set when the batch statistics are exposed
+const BATCH_STATISTICS_FLAG: u64 = 8;
+
+/// Flag set when the indices of accounts are bound to their keys
+const DERIVED_INDICES_FLAG: u64 = 16;
+
+/// Flag set when deltas are signed, to support refunds
+const SIGNED_DELTAS_FLAG: u64 = 32;
+
+// PUBLIC INPUTS
+// ================================================================================================
+
+/// Encodes the provided public inputs as EVM words, in the layout described in the module
+/// documentation.
+pub fn encode_public_inputs(pub_inputs: &PublicInputs) -> Vec<u8> {
+    let mut flags = 0;
+    if pub_inputs.intermediate_roots.is_some() {
+        flags |= INTERMEDIATE_ROOTS_FLAG;
+    }
+    if pub_inputs.transactions_digest.is_some() {
+        flags |= TRANSACTIONS_DIGEST_FLAG;
+    }
+    if pub_inputs.nullifiers_commitment.is_some() {
+        flags |= NULLIFIERS_COMMITMENT_FLAG;
+    }
+    if pub_inputs.batch_statistics.is_some() {
+        flags |= BATCH_STATISTICS_FLAG;
+    }
+    if pub_inputs.derived_indices {
+        flags |= DERIVED_INDICES_FLAG;
+    }
+    if pub_inputs.signed_deltas {
+        flags |= SIGNED_DELTAS_FLAG;
+    }
+    let no_root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+    let intermediate_roots = pub_inputs.intermediate_roots.as_deref().unwrap_or(&[]);
+
+    let mut target = Vec::new();
+    write_elements(&mut target, &pub_inputs.initial_root);
+    write_elements(&mut target, &pub_inputs.final_root);
+    write_word(&mut target, pub_inputs.tree_depth as u64);
+    write_word(&mut target, pub_inputs.fee_collector as u64);
+    write_word(&mut target, pub_inputs.range_log as u64);
+    write_word(&mut target, pub_inputs.hash_id as u64);
+    write_word(&mut target, flags);
+    write_elements(
+        &mut target,
+        pub_inputs.transactions_digest.as_ref().unwrap_or(&no_root),
+    );
+    write_elements(
+        &mut target,
+        pub_inputs
+            .nullifiers_commitment
+            .as_ref()
+            .unwrap_or(&no_root),
+    );
+    write_elements(&mut target, &[pub_inputs.subnet_id]);
+    write_word(&mut target, pub_inputs.epoch as u64);
+    let statistics = pub_inputs.batch_statistics.unwrap_or_default();
+    write_word(&mut target, statistics.num_transactions as u64);
+    write_word(&mut target, statistics.volume);
+
+    // Arrays are laid out one after the other, each starting with its number of items
+    let withdrawals_offset = PUBLIC_INPUTS_HEAD_LENGTH;
+    let deposits_offset = withdrawals_offset + 1 + 3 * pub_inputs.withdrawals.len();
+    let memos_offset = deposits_offset + 1 + 3 * pub_inputs.deposits.len();
+    let intermediate_roots_offset = memos_offset + 1 + 2 * pub_inputs.memos.len();
+    for &offset in [
+        withdrawals_offset,
+        deposits_offset,
+        memos_offset,
+        intermediate_roots_offset,
+    ]
+    .iter()
+    {
+        write_word(&mut target, (offset * WORD_SIZE) as u64);
+    }
+
+    write_word(&mut target, pub_inputs.withdrawals.len() as u64);
+    for withdrawal in pub_inputs.withdrawals.iter() {
+        write_word(&mut target, withdrawal.tx_index as u64);
+        write_word(&mut target, withdrawal.amount);
+        target.extend_from_slice(&[0u8; WORD_SIZE - 20]);
+        target.extend_from_slice(&withdrawal.recipient);
+    }
+    write_word(&mut target, pub_inputs.deposits.len() as u64);
+    for deposit in pub_inputs.deposits.iter() {
+        write_word(&mut target, deposit.tx_index as u64);
+        write_word(&mut target, deposit.amount);
+        write_word(&mut target, deposit.receiver as u64);
+    }
+    write_word(&mut target, pub_inputs.memos.len() as u64);
+    for memo in pub_inputs.memos.iter() {
+        write_word(&mut target, memo.tx_index as u64);
+        target.extend_from_slice(&memo.data);
+    }
+    write_word(&mut target, intermediate_roots.len() as u64);
+    for root in intermediate_roots.iter() {
+        write_elements(&mut target, root);
+    }
+
+    target
+}
+
+/// Reads public inputs from the provided EVM words, as encoded with `encode_public_inputs`.
+///
+/// Returns an error if the words do not hold valid public inputs, or do not follow exactly
+/// the layout produced by `encode_public_inputs`.
+pub fn decode_public_inputs(bytes: &[u8]) -> Result<PublicInputs, DeserializationError> {
+    let words = Words::new(bytes)?;
+    let tree_depth = words.read_usize(14)?;
+    let fee_collector = words.read_usize(15)?;
+    let range_log = words.read_usize(16)?;
+    check_parameters(tree_depth, fee_collector, range_log)?;
+    let hash_id = u8::try_from(words.read_u64(17)?)
+        .map_err(|_| invalid_value("hash identifier does not fit in a byte"))?;
+    let flags = words.read_u64(18)?;
+    let epoch = u32::try_from(words.read_u64(34)?)
+        .map_err(|_| invalid_value("epoch does not fit in 32 bits"))?;
+    if flags >= 64 {
+        return Err(invalid_value("unknown flags"));
+    }
+    let signed_deltas = flags & SIGNED_DELTAS_FLAG != 0;
+    check_signed_range_log(signed_deltas, range_log)?;
+
+    let withdrawals = words
+        .read_array(37, 3)?
+        .map(|index| {
+            let mut recipient = [0u8; 20];
+            recipient.copy_from_slice(&words.read_bytes(index + 2)?[WORD_SIZE - 20..]);
+            Ok(Withdrawal::new(
+                words.read_usize(index)?,
+                words.read_u64(index + 1)?,
+                recipient,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let deposits = words
+        .read_array(38, 3)?
+        .map(|index| {
+            Ok(Deposit::new(
+                words.read_usize(index)?,
+                words.read_u64(index + 1)?,
+                words.read_usize(index + 2)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let memos = words
+        .read_array(39, 2)?
+        .map(|index| {
+            let mut data = [0u8; WORD_SIZE];
+            data.copy_from_slice(words.read_bytes(index + 1)?);
+            Ok(Memo::new(words.read_usize(index)?, data))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let intermediate_roots = words
+        .read_array(40, HASH_RATE_WIDTH)?
+        .map(|index| words.read_root(index))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let pub_inputs = PublicInputs {
+        initial_root: words.read_root(0)?,
+        final_root: words.read_root(7)?,
+        tree_depth,
+        fee_collector,
+        withdrawals,
+        deposits,
+        memos,
+        range_log,
+        hash_id,
+        intermediate_roots: if flags & INTERMEDIATE_ROOTS_FLAG != 0 {
+            Some(intermediate_roots)
+        } else {
+            None
+        },
+        transactions_digest: if flags & TRANSACTIONS_DIGEST_FLAG != 0 {
+            Some(words.read_root(19)?)
+        } else {
+            None
+        },
+        nullifiers_commitment: if flags & NULLIFIERS_COMMITMENT_FLAG != 0 {
+            Some(words.read_root(26)?)
+        } else {
+            None
+        },
+        batch_statistics: if flags & BATCH_STATISTICS_FLAG != 0 {
+            Some(BatchStatistics::new(
+                words.read_usize(35)?,
+                words.read_element(36)?.to_repr(),
+            ))
+        } else {
+            None
+        },
+        subnet_id: words.read_element(33)?,
+        epoch,
+        derived_indices: flags & DERIVED_INDICES_FLAG != 0,
+        signed_deltas,
+    };
+
+    // Rejecting any other layout, e.g. overlapping arrays or trailing words, ensures that
+    // each public inputs have a single encoding
+    if encode_public_inputs(&pub_inputs) != bytes {
+        return Err(invalid_value("non-canonical encoding"));
+    }
+
+    Ok(pub_inputs)
+}
+
+// PROOFS
+// ================================================================================================
+
+/// Encodes the provided proof as EVM words, in the layout described in the module
+/// documentation.
+pub fn encode_proof(proof: &StarkProof) -> Vec<u8> {
+    let options = proof.options();
+    let fri_options = options.to_fri_options();
+    let proof_bytes = proof.to_bytes();
+
+    let mut target = Vec::new();
+    write_word(&mut target, proof.trace_length() as u64);
+    write_word(&mut target, options.num_queries() as u64);
+    write_word(&mut target, options.blowup_factor() as u64);
+    write_word(&mut target, options.grinding_factor() as u64);
+    write_word(
+        &mut target,
+        match options.hash_fn() {
+            HashFunction::Blake3_256 => 0,
+            HashFunction::Sha3_256 => 1,
+        },
+    );
+    write_word(&mut target, options.field_extension().degree() as u64);
+    write_word(&mut target, fri_options.folding_factor() as u64);
+    write_word(&mut target, fri_options.max_remainder_size() as u64);
+    write_word(&mut target, proof_bytes.len() as u64);
+    target.extend_from_slice(&proof_bytes);
+    target.resize(
+        target.len() + (WORD_SIZE - proof_bytes.len() % WORD_SIZE) % WORD_SIZE,
+        0,
+    );
+
+    target
+}
+
+/// Reads a proof from the provided EVM words, as encoded with `encode_proof`.
+///
+/// Returns an error if the words do not hold a valid proof, or if its head does not match
+/// the trace length and options of the proof.
+pub fn decode_proof(bytes: &[u8]) -> Result<StarkProof, DeserializationError> {
+    let words = Words::new(bytes)?;
+    let proof_length = words.read_usize(PROOF_HEAD_LENGTH - 1)?;
+    let proof_bytes = bytes
+        .get(PROOF_HEAD_LENGTH * WORD_SIZE..)
+        .and_then(|proof_bytes| proof_bytes.get(..proof_length))
+        .ok_or(DeserializationError::UnexpectedEOF)?;
+    let proof = StarkProof::from_bytes(proof_bytes)?;
+
+    if encode_proof(&proof) != bytes {
+        return Err(invalid_value("proof head does not match the proof"));
+    }
+
+    Ok(proof)
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Appends `value` to `target` as a big-endian word.
+fn write_word(target: &mut Vec<u8>, value: u64) {
+    target.extend_from_slice(&[0u8; WORD_SIZE - 8]);
+    target.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Appends the canonical representation of each of `elements` to `target`, one per word.
+fn write_elements(target: &mut Vec<u8>, elements: &[BaseElement]) {
+    for element in elements.iter() {
+        write_word(target, element.to_repr());
+    }
+}
+
+fn invalid_value(message: &str) -> DeserializationError {
+    DeserializationError::InvalidValue(message.into())
+}
+
+/// A sequence of EVM words, indexed from the start of the encoding.
+struct Words<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Words<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, DeserializationError> {
+        if !bytes.chunks_exact(WORD_SIZE).remainder().is_empty() {
+            return Err(invalid_value("length is not a multiple of the word size"));
+        }
+
+        Ok(Words { bytes })
+    }
+
+    fn read_bytes(&self, index: usize) -> Result<&'a [u8], DeserializationError> {
+        index
+            .checked_mul(WORD_SIZE)
+            .and_then(|start| self.bytes.get(start..start.checked_add(WORD_SIZE)?))
+            .ok_or(DeserializationError::UnexpectedEOF)
+    }
+
+    fn read_u64(&self, index: usize) -> Result<u64, DeserializationError> {
+        let bytes = self.read_bytes(index)?;
+        if bytes[..WORD_SIZE - 8].iter().any(|&byte| byte != 0) {
+            return Err(invalid_value("word does not fit in 64 bits"));
+        }
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&bytes[WORD_SIZE - 8..]);
+
+        Ok(u64::from_be_bytes(value))
+    }
+
+    fn read_usize(&self, index: usize) -> Result<usize, DeserializationError> {
+        usize::try_from(self.read_u64(index)?)
+            .map_err(|_| invalid_value("word does not fit in a usize"))
+    }
+
+    fn read_root(
+        &self,
+        index: usize,
+    ) -> Result<[BaseElement; HASH_RATE_WIDTH], DeserializationError> {
+        let mut root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+        for (i, element) in root.iter_mut().enumerate() {
+            *element = self.read_element(index + i)?;
+        }
+
+        Ok(root)
+    }
+
+    fn read_element(&self, index: usize) -> Result<BaseElement, DeserializationError> {
+        let value = self.read_u64(index)?;
+        if value >= BaseElement::MODULUS {
+            return Err(invalid_value("field element is not canonical"));
+        }
+
+        Ok(BaseElement::new(value))
+    }
+
+    /// Returns the indices of the first word of each item of the array whose byte offset is
+    /// stored at the word `offset_index`, given the number of words per item.
+    fn read_array(
+        &self,
+        offset_index: usize,
+        item_length: usize,
+    ) -> Result<impl Iterator<Item = usize>, DeserializationError> {
+        let offset = self.read_usize(offset_index)?;
+        if offset % WORD_SIZE != 0 {
+            return Err(invalid_value("array offset is not aligned on a word"));
+        }
+        let start = offset / WORD_SIZE + 1;
+        let num_items = self.read_usize(start - 1)?;
+        let end = num_items
+            .checked_mul(item_length)
+            .and_then(|length| length.checked_add(start))
+            .ok_or(DeserializationError::UnexpectedEOF)?;
+        if end > self.bytes.len() / WORD_SIZE {
+            return Err(DeserializationError::UnexpectedEOF);
+        }
+
+        Ok((start..end).step_by(item_length))
+    }
+}
@@ -0,0 +1,15 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// Encoding of proofs and public inputs as EVM calldata
+pub mod evm;
+
+/// Serde helpers for the field elements, curve points and proofs held by the types of this
+/// crate
+#[cfg(feature = "serde")]
+pub mod serde;
@@ -0,0 +1,348 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serde helpers for the field elements, curve points, byte arrays and proofs held by the
+//! types of this crate, to be used with `#[serde(with = "...")]`.
+//!
+//! The representations are chosen for JSON consumers: field elements are decimal strings,
+//! as their 63 bits do not fit in double-precision numbers, while scalars, compressed curve
+//! points, byte arrays and serialized proofs are lowercase hex strings.
+
+use crate::{SplitOutputs, MAX_SPLIT_OUTPUTS};
+use core::convert::TryInto;
+use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+use winterfell::{
+    math::{
+        curves::curve_f63::{AffinePoint, Scalar},
+        fields::f63::BaseElement,
+        StarkField,
+    },
+    StarkProof,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec::Vec};
+
+// REPRESENTATIONS
+// ================================================================================================
+
+/// A field element, represented as the decimal string of its canonical value
+struct Element(BaseElement);
+
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0.to_repr())
+    }
+}
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let value: u64 = string
+            .parse()
+            .map_err(|_| D::Error::custom(format!("invalid field element {}", string)))?;
+        if value >= BaseElement::MODULUS {
+            return Err(D::Error::custom(format!(
+                "field element {} is not canonical",
+                value
+            )));
+        }
+
+        Ok(Element(BaseElement::new(value)))
+    }
+}
+
+/// An array of field elements, represented as a sequence of elements
+struct Elements<const N: usize>([BaseElement; N]);
+
+impl<const N: usize> Serialize for Elements<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(N))?;
+        for &element in self.0.iter() {
+            seq.serialize_element(&Element(element))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Elements<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let elements: Vec<BaseElement> = Vec::<Element>::deserialize(deserializer)?
+            .into_iter()
+            .map(|element| element.0)
+            .collect();
+        let num_elements = elements.len();
+        let elements = elements.try_into().map_err(|_| {
+            D::Error::custom(format!(
+                "expected {} field elements, got {}",
+                N, num_elements
+            ))
+        })?;
+
+        Ok(Elements(elements))
+    }
+}
+
+/// An array of bytes, represented as a hex string
+struct Bytes<const N: usize>([u8; N]);
+
+impl<const N: usize> Serialize for Bytes<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.0))
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Bytes<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let mut bytes = [0u8; N];
+        hex::decode_to_slice(&string, &mut bytes)
+            .map_err(|err| D::Error::custom(format!("invalid {}-byte hex string: {}", N, err)))?;
+
+        Ok(Bytes(bytes))
+    }
+}
+
+// FIELD ELEMENTS
+// ================================================================================================
+
+/// Serializes a field element as the decimal string of its canonical value.
+pub mod element {
+    use super::*;
+
+    /// Serializes `element` with `serializer`.
+    pub fn serialize<S: Serializer>(
+        element: &BaseElement,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Element(*element).serialize(serializer)
+    }
+
+    /// Deserializes a field element with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BaseElement, D::Error> {
+        Ok(Element::deserialize(deserializer)?.0)
+    }
+}
+
+/// Serializes an array of field elements, e.g. a Merkle tree root, as a sequence of
+/// decimal strings.
+pub mod elements {
+    use super::*;
+
+    /// Serializes `elements` with `serializer`.
+    pub fn serialize<S: Serializer, const N: usize>(
+        elements: &[BaseElement; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Elements(*elements).serialize(serializer)
+    }
+
+    /// Deserializes an array of field elements with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[BaseElement; N], D::Error> {
+        Ok(Elements::deserialize(deserializer)?.0)
+    }
+}
+
+/// Serializes an optional array of field elements, as a sequence of decimal strings or
+/// as null.
+pub mod option_elements {
+    use super::*;
+
+    /// Serializes `elements` with `serializer`.
+    pub fn serialize<S: Serializer, const N: usize>(
+        elements: &Option<[BaseElement; N]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        elements.map(Elements).serialize(serializer)
+    }
+
+    /// Deserializes an optional array of field elements with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Option<[BaseElement; N]>, D::Error> {
+        Ok(Option::<Elements<N>>::deserialize(deserializer)?.map(|elements| elements.0))
+    }
+}
+
+/// Serializes an optional list of arrays of field elements, e.g. intermediate Merkle tree
+/// roots, as a sequence of sequences of decimal strings or as null.
+pub mod option_vec_elements {
+    use super::*;
+
+    /// Serializes `elements` with `serializer`.
+    pub fn serialize<S: Serializer, const N: usize>(
+        elements: &Option<Vec<[BaseElement; N]>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        elements
+            .as_ref()
+            .map(|elements| elements.iter().copied().map(Elements).collect::<Vec<_>>())
+            .serialize(serializer)
+    }
+
+    /// Deserializes an optional list of arrays of field elements with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Option<Vec<[BaseElement; N]>>, D::Error> {
+        Ok(Option::<Vec<Elements<N>>>::deserialize(deserializer)?
+            .map(|elements| elements.into_iter().map(|elements| elements.0).collect()))
+    }
+}
+
+// BYTES
+// ================================================================================================
+
+/// Serializes an array of bytes, e.g. an L1 address or a memo, as a hex string.
+pub mod bytes {
+    use super::*;
+
+    /// Serializes `bytes` with `serializer`.
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Bytes(*bytes).serialize(serializer)
+    }
+
+    /// Deserializes an array of bytes with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        Ok(Bytes::deserialize(deserializer)?.0)
+    }
+}
+
+/// Serializes an optional array of bytes as a hex string or as null.
+pub mod option_bytes {
+    use super::*;
+
+    /// Serializes `bytes` with `serializer`.
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &Option<[u8; N]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        bytes.map(Bytes).serialize(serializer)
+    }
+
+    /// Deserializes an optional array of bytes with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Option<[u8; N]>, D::Error> {
+        Ok(Option::<Bytes<N>>::deserialize(deserializer)?.map(|bytes| bytes.0))
+    }
+}
+
+// CURVE POINTS AND SIGNATURES
+// ================================================================================================
+
+/// Serializes a curve point, e.g. a public key, as the hex string of its compressed encoding.
+pub mod point {
+    use super::*;
+
+    /// Serializes `point` with `serializer`.
+    pub fn serialize<S: Serializer>(point: &AffinePoint, serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes(point.to_compressed()).serialize(serializer)
+    }
+
+    /// Deserializes a curve point with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<AffinePoint, D::Error> {
+        let bytes = Bytes::deserialize(deserializer)?;
+        Option::from(AffinePoint::from_compressed(&bytes.0))
+            .ok_or_else(|| D::Error::custom("invalid compressed curve point"))
+    }
+}
+
+/// Serializes a Schnorr signature as the sequence of the x-coordinate of its nonce point,
+/// as decimal strings, followed by the hex string of its scalar.
+pub mod signature {
+    use super::*;
+    use crate::utils::ecc::POINT_COORDINATE_WIDTH;
+
+    type Signature = ([BaseElement; POINT_COORDINATE_WIDTH], Scalar);
+
+    /// Serializes `signature` with `serializer`.
+    pub fn serialize<S: Serializer>(
+        signature: &Signature,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (Elements(signature.0), Bytes(signature.1.to_bytes())).serialize(serializer)
+    }
+
+    /// Deserializes a Schnorr signature with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Signature, D::Error> {
+        let (x, scalar) =
+            <(Elements<POINT_COORDINATE_WIDTH>, Bytes<32>)>::deserialize(deserializer)?;
+        let scalar = Option::from(Scalar::from_bytes(&scalar.0))
+            .ok_or_else(|| D::Error::custom("invalid signature scalar"))?;
+
+        Ok((x.0, scalar))
+    }
+}
+
+// PROOFS
+// ================================================================================================
+
+/// Serializes a state-transition proof as the hex string of its serialized bytes, so that
+/// types wrapping proofs can derive `Serialize` and `Deserialize`.
+pub mod proof {
+    use super::*;
+
+    /// Serializes `proof` with `serializer`.
+    pub fn serialize<S: Serializer>(proof: &StarkProof, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(proof.to_bytes()))
+    }
+
+    /// Deserializes a state-transition proof with `deserializer`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StarkProof, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let bytes = hex::decode(&string)
+            .map_err(|err| D::Error::custom(format!("invalid hex string: {}", err)))?;
+
+        StarkProof::from_bytes(&bytes)
+            .map_err(|err| D::Error::custom(format!("invalid proof: {}", err)))
+    }
+}
+
+// SPLIT OUTPUTS
+// ================================================================================================
+
+impl Serialize for SplitOutputs {
+    /// Serializes these outputs as a sequence of (receiver index, amount) pairs.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for position in 0..self.len() {
+            seq.serialize_element(&(self.receiver(position), Element(self.amount(position))))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for SplitOutputs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let outputs: Vec<(usize, BaseElement)> =
+            Vec::<(usize, Element)>::deserialize(deserializer)?
+                .into_iter()
+                .map(|(receiver, amount)| (receiver, amount.0))
+                .collect();
+        if outputs.is_empty() || outputs.len() > MAX_SPLIT_OUTPUTS {
+            return Err(D::Error::custom(format!(
+                "number of split outputs must be between 1 and {}, but was {}",
+                MAX_SPLIT_OUTPUTS,
+                outputs.len()
+            )));
+        }
+
+        Ok(SplitOutputs::new(&outputs))
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{ConstraintFailure, MetadataError};
+use core::fmt;
+use winterfell::{ProverError, VerifierError};
+
+// ERROR
+// ================================================================================================
+
+/// Errors raised when proving or verifying the state-transition of a batch of transactions
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The transactions to be proven are invalid
+    InvalidMetadata(MetadataError),
+    /// The prover failed to generate a proof
+    ProvingFailed(ProverError),
+    /// The proof failed to verify
+    InvalidProof(VerifierError),
+    /// The conjectured security level of the proof, in bits, is below the required minimum
+    InsufficientSecurity {
+        /// Conjectured security level of the proof
+        security_level: u32,
+        /// Minimum security level required by the verifier
+        min_security: u32,
+    },
+    /// The proof generation was aborted through its `CancellationToken`
+    Cancelled,
+    /// The execution trace does not satisfy a transition constraint, as found by the checks
+    /// enabled with `TransactionProver::with_constraint_checks`
+    UnsatisfiedConstraint(ConstraintFailure),
+    /// The execution trace of the batch would exceed the memory cap of the `ProverConfig`
+    MemoryLimitExceeded {
+        /// Estimated size of the execution trace, in bytes
+        required: usize,
+        /// Maximum size of the execution trace allowed by the configuration, in bytes
+        limit: usize,
+    },
+    /// The batch holds more transactions than allowed by the `ProverConfig`
+    TooManyTransactions {
+        /// Number of transactions of the batch
+        num_transactions: usize,
+        /// Maximum number of transactions allowed by the configuration
+        limit: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidMetadata(err) => write!(f, "invalid transactions: {}", err),
+            Error::ProvingFailed(err) => write!(f, "proof generation failed: {}", err),
+            Error::InvalidProof(err) => write!(f, "proof verification failed: {}", err),
+            Error::InsufficientSecurity {
+                security_level,
+                min_security,
+            } => write!(
+                f,
+                "proof provides {} bits of conjectured security, but at least {} are required",
+                security_level, min_security
+            ),
+            Error::Cancelled => write!(f, "proof generation was cancelled"),
+            Error::UnsatisfiedConstraint(failure) => {
+                write!(f, "invalid execution trace: {}", failure)
+            }
+            Error::MemoryLimitExceeded { required, limit } => write!(
+                f,
+                "execution trace would take {} bytes, but at most {} are allowed",
+                required, limit
+            ),
+            Error::TooManyTransactions {
+                num_transactions,
+                limit,
+            } => write!(
+                f,
+                "batch holds {} transactions, but at most {} are allowed",
+                num_transactions, limit
+            ),
+        }
+    }
+}
+
+impl From<MetadataError> for Error {
+    fn from(err: MetadataError) -> Self {
+        Error::InvalidMetadata(err)
+    }
+}
+
+impl From<ProverError> for Error {
+    fn from(err: ProverError) -> Self {
+        Error::ProvingFailed(err)
+    }
+}
+
+impl From<VerifierError> for Error {
+    fn from(err: VerifierError) -> Self {
+        Error::InvalidProof(err)
+    }
+}
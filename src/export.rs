@@ -0,0 +1,330 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Export of execution traces, to inspect them in external tools when developing constraints.
+//!
+//! Traces are written as CSV, with one row per step and one column per register, labelled
+//! after the values they hold, e.g. `schnorr.acc.x0` or `merkle.sender.path.bit`. The first
+//! registers are shared between the Merkle authentication paths, over the first half of each
+//! transaction, and the signature verification, over the second half, so that their labels
+//! list all their uses separated by slashes.
+//!
+//! Traces of large batches can be exported one transaction segment at a time instead, with
+//! `write_csv_segments` and `TransactionProver::trace_segments`, without holding the whole
+//! trace table.
+//!
+//! ```no_run
+//! # fn example(batch: certificate_stark::TransactionMetadata) -> std::io::Result<()> {
+//! use certificate_stark::{export, ProofProfile, TransactionProver};
+//!
+//! let prover = TransactionProver::new(ProofProfile::Fast.to_proof_options());
+//! let trace = prover.build_trace(&batch);
+//! export::write_csv(&trace, std::fs::File::create("trace.csv")?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::constants::merkle_const::{
+    COLLECTOR_BIT_POS, COLLECTOR_INITIAL_POS, COLLECTOR_UPDATED_POS, HASH_RATE_WIDTH,
+    HASH_STATE_WIDTH, PREV_TREE_ROOT_POS, RECEIVER_BIT_POS, RECEIVER_INITIAL_POS,
+    RECEIVER_UPDATED_POS, SENDER_BIT_POS, SENDER_INITIAL_POS, SENDER_UPDATED_POS,
+};
+use super::constants::schnorr_const::{
+    ADDEND_POS, ADDITION_FLAG_POS, HASH_STATE_POS as SCHNORR_HASH_STATE_POS, H_BIT_POS,
+    H_LIMBS_POS, POINT_COORDINATE_WIDTH, S_BIT_POS,
+};
+use super::constants::*;
+use super::TraceSegment;
+use std::{
+    io::{self, Write},
+    ops::Range,
+};
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
+    Trace, TraceTable,
+};
+
+// REGISTER LABELS
+// ================================================================================================
+
+/// Returns the labels of the registers of the execution trace of the transaction AIR program.
+pub fn register_labels() -> Vec<String> {
+    let mut labels = vec![Vec::new(); TRACE_WIDTH];
+    let mut label = |start: usize, names: Vec<String>| {
+        for (register, name) in labels[start..start + names.len()].iter_mut().zip(names) {
+            register.push(name);
+        }
+    };
+
+    // Merkle authentication paths, over the first half of each transaction
+    for (account, initial, bit, updated) in [
+        (
+            "sender",
+            SENDER_INITIAL_POS,
+            SENDER_BIT_POS,
+            SENDER_UPDATED_POS,
+        ),
+        (
+            "receiver",
+            RECEIVER_INITIAL_POS,
+            RECEIVER_BIT_POS,
+            RECEIVER_UPDATED_POS,
+        ),
+        (
+            "collector",
+            COLLECTOR_INITIAL_POS,
+            COLLECTOR_BIT_POS,
+            COLLECTOR_UPDATED_POS,
+        ),
+    ]
+    .iter()
+    {
+        label(
+            *initial,
+            indexed(&format!("merkle.{}.initial.", account), HASH_STATE_WIDTH),
+        );
+        label(*bit, vec![format!("merkle.{}.path.bit", account)]);
+        label(
+            *updated,
+            indexed(&format!("merkle.{}.updated.", account), HASH_STATE_WIDTH),
+        );
+    }
+    label(
+        PREV_TREE_ROOT_POS,
+        indexed("merkle.prev_root.", HASH_RATE_WIDTH),
+    );
+
+    // Schnorr signature verification, range proofs, hash of the digest of transactions and
+    // decompositions of the hashes of keys, over the second half of each transaction
+    label(0, point("schnorr.acc.", &["x", "y", "z"]));
+    label(ADDITION_FLAG_POS, vec!["schnorr.add.flag".into()]);
+    label(S_BIT_POS, vec!["schnorr.s.bit".into()]);
+    label(H_BIT_POS, vec!["schnorr.h.bit".into()]);
+    label(ADDEND_POS, point("schnorr.add.", &["x", "y"]));
+    label(
+        H_LIMBS_POS,
+        indexed("schnorr.h.limb", SCHNORR_HASH_STATE_POS - H_LIMBS_POS),
+    );
+    label(
+        SCHNORR_HASH_STATE_POS,
+        indexed(
+            "schnorr.hash.",
+            SCHNORR_REGISTER_WIDTH - SCHNORR_HASH_STATE_POS,
+        ),
+    );
+    label(
+        DELTA_BIT_POS,
+        vec![
+            "range.delta.bit".into(),
+            "range.delta.acc".into(),
+            "range.fee.bit".into(),
+            "range.fee.acc".into(),
+        ],
+    );
+    label(
+        SENDER_KEY_INDEX_BIT_POS,
+        vec!["key_index.sender.bit".into(), "key_index.sender.acc".into()],
+    );
+    label(
+        SENDER_KEY_INDEX_BOUND_POS,
+        vec!["key_index.sender.bound".into()],
+    );
+    label(
+        TX_DIGEST_HASH_POS,
+        indexed("digest.hash.", HASH_STATE_WIDTH),
+    );
+    label(
+        SIGMA_BIT_POS,
+        vec![
+            "range.sigma.bit".into(),
+            "range.sigma.acc".into(),
+            "range.receiver_balance.bit".into(),
+            "range.receiver_balance.acc".into(),
+        ],
+    );
+    label(
+        RECEIVER_KEY_INDEX_BIT_POS,
+        vec![
+            "key_index.receiver.bit".into(),
+            "key_index.receiver.acc".into(),
+        ],
+    );
+    label(
+        RECEIVER_KEY_INDEX_BOUND_POS,
+        vec!["key_index.receiver.bound".into()],
+    );
+    label(
+        KEY_INDEX_HASH_POS,
+        indexed("key_index.receiver.hash.", HASH_STATE_WIDTH),
+    );
+
+    // Values copied along each transaction
+    label(SENDER_KEY_POINT_POS, point("copy.sender_key.", &["x", "y"]));
+    label(
+        RECEIVER_KEY_POINT_POS,
+        point("copy.receiver_key.", &["x", "y"]),
+    );
+    label(
+        DELTA_COPY_POS,
+        vec![
+            "copy.delta".into(),
+            "copy.sigma".into(),
+            "copy.nonce".into(),
+            "copy.fee".into(),
+            "copy.withdrawal".into(),
+            "copy.deposit".into(),
+            "copy.receiver_balance".into(),
+        ],
+    );
+
+    // Split transfers, no-ops, memo, running digest of transactions, nullifiers, statistics,
+    // rekeyings, levels shared by the sender's and receiver's paths and sign of delta
+    label(SPLIT_FLAG_POS, vec!["split.flag".into()]);
+    label(
+        SPLIT_POSITION_POS,
+        indexed("split.position", MAX_SPLIT_OUTPUTS),
+    );
+    label(
+        SPLIT_AMOUNT_POS,
+        vec![
+            "split.amount".into(),
+            "split.sent".into(),
+            "split.remaining".into(),
+            "receiver.index".into(),
+            "sender.index".into(),
+            "noop.flag".into(),
+        ],
+    );
+    label(MEMO_POS, indexed("memo.", MEMO_WIDTH));
+    label(TX_DIGEST_POS, indexed("digest.", HASH_RATE_WIDTH));
+    label(NULLIFIERS_POS, indexed("nullifiers.", HASH_RATE_WIDTH));
+    label(
+        TX_COUNT_POS,
+        vec!["stats.count".into(), "stats.volume".into()],
+    );
+    label(
+        REKEY_FLAG_POS,
+        vec![
+            "rekey.flag".into(),
+            "merkle.shared.flag".into(),
+            "delta.sign".into(),
+        ],
+    );
+
+    // Committee transfers and their approvals, and the key verifying the signature
+    label(
+        COMMITTEE_FLAG_POS,
+        vec!["committee.flag".into(), "approval.flag".into()],
+    );
+    label(
+        COMMITTEE_ROOT_POS,
+        indexed("committee.root.", HASH_RATE_WIDTH),
+    );
+    label(
+        APPROVALS_LEFT_POS,
+        vec![
+            "committee.approvals_left".into(),
+            "committee.member".into(),
+            "committee.previous_member".into(),
+        ],
+    );
+    label(
+        KEY_PATH_POS,
+        indexed("committee.key_path.", HASH_STATE_WIDTH),
+    );
+    label(
+        KEY_PATH_POS + HASH_STATE_WIDTH,
+        vec!["committee.key_path.bit".into()],
+    );
+    label(
+        MEMBER_GAP_BIT_POS,
+        vec!["committee.gap.bit".into(), "committee.gap.acc".into()],
+    );
+    label(SIGNING_KEY_INVERSE_POS, vec!["signing_key.inverse".into()]);
+
+    labels.into_iter().map(|names| names.join("/")).collect()
+}
+
+// CSV EXPORT
+// ================================================================================================
+
+/// Writes the provided execution trace as CSV, with a header row of register labels.
+pub fn write_csv<W: Write>(trace: &TraceTable<BaseElement>, writer: W) -> io::Result<()> {
+    write_csv_steps(trace, 0..trace.length(), writer)
+}
+
+/// Writes the provided steps of an execution trace as CSV, with a header row of register
+/// labels, e.g. to export the cycle of a single transaction out of a large batch.
+///
+/// # Panics
+/// Panics if the steps are out of the bounds of the trace, or if the trace is not an
+/// execution trace of the transaction AIR program.
+pub fn write_csv_steps<W: Write>(
+    trace: &TraceTable<BaseElement>,
+    steps: Range<usize>,
+    writer: W,
+) -> io::Result<()> {
+    assert_eq!(trace.width(), TRACE_WIDTH, "invalid trace width");
+    assert!(steps.end <= trace.length(), "steps out of the trace");
+
+    let mut writer = io::BufWriter::new(writer);
+    writeln!(writer, "step,{}", register_labels().join(","))?;
+    let mut row = vec![BaseElement::ZERO; TRACE_WIDTH];
+    for step in steps {
+        trace.read_row_into(step, &mut row);
+        write_row(&mut writer, step, &row)?;
+    }
+
+    writer.flush()
+}
+
+/// Writes the execution trace made of the provided segments as CSV, with a header row of
+/// register labels, each segment being written once reached. Along with
+/// `TransactionProver::trace_segments`, this exports the trace of a large batch one
+/// transaction at a time, without holding the whole trace table.
+pub fn write_csv_segments<W: Write>(
+    segments: impl IntoIterator<Item = TraceSegment>,
+    writer: W,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    writeln!(writer, "step,{}", register_labels().join(","))?;
+    for segment in segments {
+        for step in 0..segment.length() {
+            write_row(&mut writer, segment.first_step() + step, segment.row(step))?;
+        }
+    }
+
+    writer.flush()
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Writes a CSV row made of the provided step followed by the values of its registers.
+fn write_row<W: Write>(writer: &mut W, step: usize, row: &[BaseElement]) -> io::Result<()> {
+    write!(writer, "{}", step)?;
+    for value in row {
+        write!(writer, ",{}", value.to_repr())?;
+    }
+    writeln!(writer)
+}
+
+/// Returns `length` labels made of the provided prefix followed by their index.
+fn indexed(prefix: &str, length: usize) -> Vec<String> {
+    (0..length).map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+/// Returns the labels of the limbs of the provided coordinates of a curve point.
+fn point(prefix: &str, coordinates: &[&str]) -> Vec<String> {
+    coordinates
+        .iter()
+        .flat_map(|coordinate| {
+            indexed(&format!("{}{}", prefix, coordinate), POINT_COORDINATE_WIDTH)
+        })
+        .collect()
+}
@@ -0,0 +1,189 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::constants::schnorr_const::AFFINE_POINT_WIDTH;
+use super::constants::TX_TUPLE_WIDTH;
+use super::utils::rescue::Hash;
+use super::PublicInputs;
+use core::fmt;
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// INCLUSION ERROR
+// ================================================================================================
+
+/// Errors raised when verifying an `InclusionProof`
+#[derive(Debug, PartialEq, Eq)]
+pub enum InclusionError {
+    /// The public inputs do not expose the running digest of transactions
+    DigestNotExposed,
+    /// The transaction and the ones following it do not lead to the digest of transactions
+    /// exposed by the public inputs
+    DigestMismatch,
+    /// The position of the transaction does not match the number of transactions exposed by
+    /// the public inputs
+    PositionMismatch,
+}
+
+impl fmt::Display for InclusionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InclusionError::DigestNotExposed => {
+                write!(f, "public inputs do not expose the transactions digest")
+            }
+            InclusionError::DigestMismatch => {
+                write!(f, "transaction does not lead to the transactions digest")
+            }
+            InclusionError::PositionMismatch => {
+                write!(
+                    f,
+                    "transaction position does not match the batch statistics"
+                )
+            }
+        }
+    }
+}
+
+// INCLUSION PROOF
+// ================================================================================================
+
+/// A proof that a transaction was part of a batch, against the running digest of transactions
+/// exposed by its state-transition proof.
+///
+/// The digest is a hash chain over the (sender key, receiver key, amount) tuples of the
+/// transactions of the batch, no-ops excluded. The inclusion proof holds the digest prior to
+/// the transaction, its tuple, and the tuples of the transactions following it, so that the
+/// verifier can recompute the chain up to the exposed digest. Its size thus grows with the
+/// number of following transactions, which it discloses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    position: usize,
+    previous_digest: Hash,
+    tuple: [BaseElement; TX_TUPLE_WIDTH],
+    following: Vec<[BaseElement; TX_TUPLE_WIDTH]>,
+}
+
+impl InclusionProof {
+    /// Returns a new proof that the transaction at `position` among the transactions of a
+    /// batch, no-ops excluded, has the provided tuple, given the digest of the transactions
+    /// prior to it and the tuples of the ones following it.
+    pub(crate) fn new(
+        position: usize,
+        previous_digest: Hash,
+        tuple: [BaseElement; TX_TUPLE_WIDTH],
+        following: Vec<[BaseElement; TX_TUPLE_WIDTH]>,
+    ) -> Self {
+        InclusionProof {
+            position,
+            previous_digest,
+            tuple,
+            following,
+        }
+    }
+
+    /// Returns the position of the transaction among the transactions of the batch, no-ops
+    /// excluded.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the key of the sender of the transaction as signed, i.e. the deposit key for
+    /// deposits.
+    pub fn sender_key(&self) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        let mut key = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        key.copy_from_slice(&self.tuple[..AFFINE_POINT_WIDTH]);
+        key
+    }
+
+    /// Returns the key of the receiver of the transaction as signed, i.e. the encoded
+    /// recipient address for withdrawals, and the encoded outputs for split transfers.
+    pub fn receiver_key(&self) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        let mut key = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        key.copy_from_slice(&self.tuple[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2]);
+        key
+    }
+
+    /// Returns the amount of the transaction.
+    pub fn amount(&self) -> BaseElement {
+        self.tuple[AFFINE_POINT_WIDTH * 2]
+    }
+
+    /// Returns the running digest of transactions this proof leads to.
+    pub fn digest(&self) -> Hash {
+        core::iter::once(&self.tuple)
+            .chain(self.following.iter())
+            .fold(self.previous_digest, |digest, tuple| {
+                super::update_transactions_digest(digest, tuple)
+            })
+    }
+
+    /// Verifies that the transaction was part of the batch proven with the provided public
+    /// inputs, which must expose the digest of transactions. When they also expose the batch
+    /// statistics, the position of the transaction is checked against their number.
+    pub fn verify(&self, pub_inputs: &PublicInputs) -> Result<(), InclusionError> {
+        let digest = pub_inputs
+            .transactions_digest
+            .ok_or(InclusionError::DigestNotExposed)?;
+        if self.digest().to_elements() != digest {
+            return Err(InclusionError::DigestMismatch);
+        }
+        if let Some(statistics) = &pub_inputs.batch_statistics {
+            if statistics.num_transactions != self.position + 1 + self.following.len() {
+                return Err(InclusionError::PositionMismatch);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serializable for InclusionProof {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.position as u32);
+        self.previous_digest.write_into(target);
+        target.write(&self.tuple[..]);
+        target.write_u32(self.following.len() as u32);
+        for tuple in self.following.iter() {
+            target.write(&tuple[..]);
+        }
+    }
+}
+
+impl Deserializable for InclusionProof {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let position = source.read_u32()? as usize;
+        let previous_digest = Hash::read_from(source)?;
+        let tuple = read_tuple(source)?;
+        let num_following = source.read_u32()? as usize;
+        let following = (0..num_following)
+            .map(|_| read_tuple(source))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(InclusionProof::new(
+            position,
+            previous_digest,
+            tuple,
+            following,
+        ))
+    }
+}
+
+/// Reads the tuple of a transaction from the provided `source` reader.
+fn read_tuple<R: ByteReader>(
+    source: &mut R,
+) -> Result<[BaseElement; TX_TUPLE_WIDTH], DeserializationError> {
+    let mut tuple = [BaseElement::ZERO; TX_TUPLE_WIDTH];
+    tuple.copy_from_slice(&BaseElement::read_batch_from(source, TX_TUPLE_WIDTH)?);
+
+    Ok(tuple)
+}
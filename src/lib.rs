@@ -20,6 +20,11 @@
 #[macro_use]
 extern crate alloc;
 
+/// The proof-of-solvency AIR program, summing the balances of a tree of accounts
+pub mod audit;
+/// The account membership AIR program, answering queries of light clients about a tree of
+/// accounts
+pub mod membership;
 /// The Merkle sub-AIR programs
 pub mod merkle;
 /// The range proof sub-AIR program
@@ -30,40 +35,124 @@ pub mod schnorr;
 pub mod utils;
 use utils::rescue::Rescue63;
 
+/// Proof options presets and builder
+pub mod options;
+pub use options::{OptionsError, ProofOptionsBuilder, ProofProfile, ProverConfig};
+
+/// Account states stored in the Merkle tree leaves
+pub mod account;
+pub use account::{account_index, AccountKind, AccountState, MULTISIG_NONCE_FLAG};
+
+/// Transactions and batches of transactions
+pub mod batch;
+pub use batch::{
+    CommitteeTransfer, SplitTransfer, Transaction, TransactionBatchBuilder, TransactionError,
+};
+
+/// Native replay of transaction batches, recomputing the roots exposed by their proofs
+pub mod replay;
+pub use replay::ReplayError;
+
+/// Validation of transaction metadata prior to proving
+pub mod validation;
+pub use validation::MetadataError;
+
+/// Named transition constraints, checked over execution traces before proving, and their
+/// degrees
+pub mod diagnostics;
+pub use diagnostics::{ConstraintDegree, ConstraintFailure};
+
+/// Encodings of proofs and public inputs for external verifiers
+pub mod encoding;
+
+/// Periodic columns of AIR programs composed of sub-AIR programs
+pub mod periodic;
+
+/// Aggregation of chained state-transition proofs
+pub mod aggregation;
+pub use aggregation::{AggregatedProof, AggregationError, ProofChain};
+
+/// Proofs of inclusion of transactions against the digest of transactions of a batch
+pub mod inclusion;
+pub use inclusion::{InclusionError, InclusionProof};
+
+/// Generators of invalid transactions, for testing integrations
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Export of execution traces with labelled registers
+#[cfg(feature = "trace-export")]
+pub mod export;
+
+/// Asynchronous prover service running proof generation on a pool of worker threads
+#[cfg(feature = "service")]
+pub mod service;
+
+/// WebAssembly bindings for verifying and proving in the browser
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+mod errors;
+pub use errors::Error;
+
+mod padding;
+
 mod air;
-use air::{PublicInputs, TransactionAir};
+use air::TransactionAir;
+pub use air::{BatchStatistics, Deposit, Memo, PublicInputs, Withdrawal};
+
+mod observer;
+pub use observer::ProverObserver;
 
 mod prover;
-use prover::TransactionProver;
+pub use prover::{CancellationToken, ProofReport, TraceSegment, TraceSegments, TransactionProver};
 
 mod constants;
 
 mod trace;
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "tracing")))]
 use log::debug;
-use rand_core::{OsRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, OsRng, RngCore, SeedableRng};
+use schnorr::keys::{SecretKey, PUBLIC_KEY_SIZE};
+use schnorr::threshold::SignatureShare;
+#[cfg(feature = "tracing")]
+use tracing::debug_span;
 use utils::rescue::Hash;
 use winterfell::{
-    crypto::{Hasher, MerkleTree},
+    crypto::Hasher,
     math::{
         curves::curve_f63::{AffinePoint, Scalar},
         fields::f63::BaseElement,
         FieldElement, StarkField,
     },
-    FieldExtension, HashFunction, ProofOptions, Prover, StarkProof, VerifierError,
+    ByteReader, ByteWriter, Deserializable, DeserializationError, ProofOptions, Serializable,
+    SliceReader, StarkProof, VerifierError,
 };
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
-
+use alloc::{
+    collections::{btree_map::Entry, BTreeMap},
+    vec::Vec,
+};
 #[cfg(feature = "std")]
+use std::collections::{btree_map::Entry, BTreeMap};
+
+#[cfg(all(feature = "std", not(feature = "tracing")))]
 use std::time::Instant;
-#[cfg(feature = "std")]
-use winterfell::{crypto::Digest, math::log2, Trace};
+#[cfg(any(feature = "std", feature = "tracing"))]
+use winterfell::crypto::Digest;
 
-use constants::merkle_const::MERKLE_TREE_DEPTH;
-use constants::schnorr_const::{AFFINE_POINT_WIDTH, POINT_COORDINATE_WIDTH};
+use constants::merkle_const::{MAX_MERKLE_TREE_DEPTH, MERKLE_TREE_DEPTH};
+use constants::schnorr_const::{
+    AFFINE_POINT_WIDTH, MESSAGE_LENGTH, POINT_COORDINATE_WIDTH, RATE_WIDTH,
+};
+use constants::{
+    EPOCH_MESSAGE_INDEX, MEMO_WIDTH, RECEIVER_INDEX_MESSAGE_INDEX, SENDER_INDEX_MESSAGE_INDEX,
+    SUBNET_ID_MESSAGE_INDEX, TRANSACTION_CYCLE_LENGTH, TX_TUPLE_NUM_CHUNKS, TX_TUPLE_WIDTH,
+};
+use merkle::{StateTree, TransactionWitness, TreeHasher};
 
 #[cfg(test)]
 mod tests;
@@ -71,21 +160,143 @@ mod tests;
 // STATE-TRANSITION MULTIPLE TRANSACTIONS EXAMPLE
 // ================================================================================================
 
-/// Outputs a new `TransactionExample` with `num_transactions` random transactions.
+/// Outputs a new `TransactionExample` with `num_transactions` random transactions,
+/// using the `Balanced` proof profile.
 pub fn get_example(num_transactions: usize) -> TransactionExample {
-    TransactionExample::new(
-        // TODO: make it customizable
-        ProofOptions::new(
-            42,
-            8,
-            0,
-            HashFunction::Blake3_256,
-            FieldExtension::None,
-            4,
-            256,
-        ),
-        num_transactions,
-    )
+    get_example_with_profile(ProofProfile::Balanced, num_transactions)
+}
+
+/// Outputs a new `TransactionExample` with `num_transactions` random transactions,
+/// using the provided proof profile.
+pub fn get_example_with_profile(
+    profile: ProofProfile,
+    num_transactions: usize,
+) -> TransactionExample {
+    get_example_with_options(profile.to_proof_options(), num_transactions)
+}
+
+/// Outputs a new `TransactionExample` with `num_transactions` random transactions,
+/// using the provided proof options.
+pub fn get_example_with_options(
+    options: ProofOptions,
+    num_transactions: usize,
+) -> TransactionExample {
+    TransactionExample::new(options, num_transactions)
+}
+
+/// Verifies a proof of valid state-transition of a set of transactions, moving the
+/// Merkle tree of accounts of depth `tree_depth` from `initial_root` to `final_root`,
+/// with all transaction fees credited to the leaf at index `fee_collector`, funds
+/// exiting the tree through exactly the provided `withdrawals` and entering it through
+/// exactly the provided `deposits`.
+///
+/// Contrary to `TransactionExample::verify`, this does not require any knowledge
+/// of the transactions themselves.
+pub fn verify_transaction_proof(
+    proof: StarkProof,
+    initial_root: Hash,
+    final_root: Hash,
+    tree_depth: usize,
+    fee_collector: usize,
+    withdrawals: Vec<Withdrawal>,
+    deposits: Vec<Deposit>,
+) -> Result<(), VerifierError> {
+    let pub_inputs = PublicInputs::new(
+        initial_root,
+        final_root,
+        tree_depth,
+        fee_collector,
+        withdrawals,
+        deposits,
+    );
+    verify_with_hasher::<Rescue63>(proof, pub_inputs)
+}
+
+/// Verifies a proof of valid state-transition against the provided public inputs, the
+/// nodes of the Merkle tree of accounts being hashed with `H`.
+///
+/// Returns an error if the public inputs refer to transactions which are not held by the
+/// trace of the proof.
+pub fn verify_with_hasher<H: TreeHasher>(
+    proof: StarkProof,
+    pub_inputs: PublicInputs,
+) -> Result<(), VerifierError> {
+    let num_transactions = proof.trace_length() / TRANSACTION_CYCLE_LENGTH;
+    air::check_num_transactions(&pub_inputs, num_transactions)?;
+    winterfell::verify::<TransactionAir<H>>(proof, pub_inputs)
+}
+
+/// Verifies a proof of valid state-transition against the provided public inputs, after
+/// checking that it provides at least `min_bits` bits of conjectured security.
+///
+/// The security level is derived from the options the proof was generated with, i.e. its
+/// number of queries, blowup factor, grinding factor and field extension, which are chosen
+/// by the prover. This protects verifiers from valid proofs generated with down-graded
+/// options, which `verify_with_hasher` would accept.
+pub fn verify_with_min_security(
+    proof: StarkProof,
+    pub_inputs: PublicInputs,
+    min_bits: u32,
+) -> Result<(), Error> {
+    verify_with_hasher_and_min_security::<Rescue63>(proof, pub_inputs, min_bits)
+}
+
+/// Verifies a proof of valid state-transition against the provided public inputs, the
+/// nodes of the Merkle tree of accounts being hashed with `H`, after checking that it
+/// provides at least `min_bits` bits of conjectured security.
+pub fn verify_with_hasher_and_min_security<H: TreeHasher>(
+    proof: StarkProof,
+    pub_inputs: PublicInputs,
+    min_bits: u32,
+) -> Result<(), Error> {
+    let security_level = proof.security_level(true);
+    if security_level < min_bits {
+        return Err(Error::InsufficientSecurity {
+            security_level,
+            min_security: min_bits,
+        });
+    }
+
+    verify_with_hasher::<H>(proof, pub_inputs).map_err(Error::from)
+}
+
+/// Verifies a batch of state-transition proofs against their respective public inputs,
+/// and returns the verification result of each proof in the same order.
+///
+/// # Panics
+/// Panics if `proofs` and `pub_inputs` are not of the same length.
+pub fn verify_batch(
+    proofs: Vec<StarkProof>,
+    pub_inputs: Vec<PublicInputs>,
+) -> Vec<Result<(), VerifierError>> {
+    verify_batch_with_hasher::<Rescue63>(proofs, pub_inputs)
+}
+
+/// Verifies a batch of state-transition proofs against their respective public inputs, the
+/// nodes of the Merkle trees of accounts being hashed with `H`, and returns the verification
+/// result of each proof in the same order.
+///
+/// The proofs are verified one after the other with `verify_with_hasher`, no work being
+/// shared between them: this is a convenience only, and is not faster than verifying each
+/// proof on its own.
+///
+/// # Panics
+/// Panics if `proofs` and `pub_inputs` are not of the same length.
+pub fn verify_batch_with_hasher<H: TreeHasher>(
+    proofs: Vec<StarkProof>,
+    pub_inputs: Vec<PublicInputs>,
+) -> Vec<Result<(), VerifierError>> {
+    assert_eq!(
+        proofs.len(),
+        pub_inputs.len(),
+        "number of proofs and public inputs must match"
+    );
+
+    proofs
+        .into_iter()
+        .zip(pub_inputs)
+        .map(|(proof, pub_inputs)| verify_with_hasher::<H>(proof, pub_inputs))
+        .collect()
 }
 
 /// A struct to perform state-transition validity
@@ -99,12 +310,24 @@ pub struct TransactionExample {
 impl TransactionExample {
     /// Outputs a new `TransactionExample` with `num_transactions` random transactions.
     pub fn new(options: ProofOptions, num_transactions: usize) -> TransactionExample {
+        Self::new_with_tree_depth(options, num_transactions, MERKLE_TREE_DEPTH)
+    }
+
+    /// Outputs a new `TransactionExample` with `num_transactions` random transactions
+    /// in a Merkle tree of depth `tree_depth`.
+    pub fn new_with_tree_depth(
+        options: ProofOptions,
+        num_transactions: usize,
+        tree_depth: usize,
+    ) -> TransactionExample {
         assert!(
-            (MERKLE_TREE_DEPTH + 1).is_power_of_two(),
-            "tree depth must be one less than a power of 2"
+            tree_depth > 0 && tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
         );
         // Create a Merkle tree for which we know all of the values
-        let tx_metadata = TransactionMetadata::build_random(num_transactions);
+        let tx_metadata =
+            TransactionMetadata::build_random_with_tree_depth(num_transactions, tree_depth);
 
         TransactionExample {
             options,
@@ -112,41 +335,23 @@ impl TransactionExample {
         }
     }
 
-    /// Proves the state-transition of a set of transactions
-    pub fn prove(&self) -> StarkProof {
-        // generate the execution trace
-        #[cfg(feature = "std")]
-        debug!(
-            "Generating proof for proving update in a Merkle tree of depth {}\n\
-            ---------------------",
-            MERKLE_TREE_DEPTH
-        );
-
-        let prover = TransactionProver::new(self.options.clone());
-
-        // generate the execution trace
-        #[cfg(feature = "std")]
-        let now = Instant::now();
-        let trace = prover.build_trace(&self.tx_metadata);
-        #[cfg(feature = "std")]
-        debug!(
-            "Generated execution trace of {} registers and 2^{} steps in {} ms",
-            trace.width(),
-            log2(trace.length()),
-            now.elapsed().as_millis()
-        );
+    /// Returns the metadata of the transactions of this example.
+    pub fn tx_metadata(&self) -> &TransactionMetadata {
+        &self.tx_metadata
+    }
 
-        // generate the proof
-        prover.prove(trace).unwrap()
+    /// Proves the state-transition of a set of transactions
+    ///
+    /// Returns an error if the transactions are invalid, or if the proof generation fails.
+    pub fn prove(&self) -> Result<StarkProof, Error> {
+        TransactionProver::new(self.options.clone()).prove(&self.tx_metadata)
     }
 
     /// Verifies a proof of valid state-transition of a set of transactions
     pub fn verify(&self, proof: StarkProof) -> Result<(), VerifierError> {
-        let pub_inputs = PublicInputs {
-            initial_root: self.tx_metadata.initial_roots[0].to_elements(),
-            final_root: self.tx_metadata.final_root.to_elements(),
-        };
-        winterfell::verify::<TransactionAir>(proof, pub_inputs)
+        let pub_inputs =
+            TransactionProver::new(self.options.clone()).public_inputs(&self.tx_metadata);
+        verify_with_hasher::<Rescue63>(proof, pub_inputs)
     }
 
     #[cfg(test)]
@@ -156,41 +361,263 @@ impl TransactionExample {
         let pub_inputs = PublicInputs {
             initial_root,
             final_root: [final_root[0]; utils::rescue::RATE_WIDTH],
+            tree_depth: self.tx_metadata.tree_depth,
+            fee_collector: self.tx_metadata.fee_collector,
+            withdrawals: self.tx_metadata.withdrawals(),
+            deposits: self.tx_metadata.deposits(),
+            memos: self.tx_metadata.memos(),
+            range_log: range::RANGE_LOG,
+            hash_id: Rescue63::HASH_ID,
+            intermediate_roots: None,
+            transactions_digest: None,
+            nullifiers_commitment: None,
+            batch_statistics: None,
+            subnet_id: self.tx_metadata.subnet_id,
+            epoch: self.tx_metadata.epoch,
+            derived_indices: false,
+            signed_deltas: false,
         };
-        winterfell::verify::<TransactionAir>(proof, pub_inputs)
+        verify_with_hasher::<Rescue63>(proof, pub_inputs)
     }
 }
 
 // TRANSACTION METADATA
 // ================================================================================================
 
+/// The kind of a transaction, determining what happens to the amount debited from the sender
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransactionKind {
+    /// The amount is credited to the receiver's leaf
+    Transfer,
+    /// The amount is burnt from the tree, to be credited on L1 to `recipient`.
+    /// The receiver's leaf is then the sender's own leaf, left untouched.
+    Withdrawal {
+        /// L1 address of the recipient
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::bytes"))]
+        recipient: [u8; 20],
+    },
+    /// The amount is minted into the receiver's leaf, as observed on L1.
+    /// The sender's leaf is then the receiver's own leaf, left untouched, and
+    /// the transaction is signed with the public deposit key.
+    Deposit,
+    /// The amount is credited to the receiver's leaf, as the output at index `position` of
+    /// a transfer split across several receivers. All outputs are signed at once by the
+    /// sender, and must be applied by consecutive transactions, in order.
+    Split {
+        /// Receivers and amounts of all the outputs of the split transfer
+        outputs: SplitOutputs,
+        /// Index of the output performed by this transaction
+        position: usize,
+    },
+    /// Nothing is transferred, the sender's leaf being the receiver's own leaf, left
+    /// untouched. No-ops pad batches to the required number of transactions, and are signed
+    /// with the public deposit key rather than by the sender.
+    NoOp,
+    /// The public key of the sender's leaf is replaced by `new_key`, e.g. to recover an
+    /// account whose key is compromised. The receiver's leaf is then the sender's own leaf,
+    /// left untouched, no amount nor fee is transferred, and the transaction is signed with
+    /// the sender's current key over the new one, in place of the receiver's key.
+    Rekey {
+        /// Public key replacing the current one of the sender
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::serde::point"))]
+        new_key: AffinePoint,
+    },
+    /// The amount, negative, is credited to the sender's leaf and debited from the receiver's
+    /// one, e.g. to pay back a previous transfer. The receiver authorizes it in place of the
+    /// sender, by signing it over its own nonce, which it increments, and no fee is paid.
+    /// Refunds can only be proven with signed deltas.
+    Refund,
+    /// The amount is credited to the receiver's leaf, as for transfers, from the leaf of a
+    /// committee, whose key is the pseudo-key returned by `AccountState::new_committee`. The
+    /// transaction is signed by a first member of the committee, and followed by one approval
+    /// for each further member required by the committee's threshold.
+    Committee,
+    /// Nothing is transferred, the transaction approving the committee transfer it follows
+    /// with the signature of one more member of the committee over the same message. Its
+    /// sender's and receiver's leaves are the ones of the committee transfer, left untouched.
+    Approval,
+}
+
+impl TransactionKind {
+    /// Returns true if this is a withdrawal transaction.
+    pub fn is_withdrawal(&self) -> bool {
+        matches!(self, TransactionKind::Withdrawal { .. })
+    }
+
+    /// Returns true if this is a deposit transaction.
+    pub fn is_deposit(&self) -> bool {
+        matches!(self, TransactionKind::Deposit)
+    }
+
+    /// Returns true if this is an output of a split transfer.
+    pub fn is_split(&self) -> bool {
+        matches!(self, TransactionKind::Split { .. })
+    }
+
+    /// Returns true if this is a no-op transaction.
+    pub fn is_noop(&self) -> bool {
+        matches!(self, TransactionKind::NoOp)
+    }
+
+    /// Returns true if this is a rekeying transaction.
+    pub fn is_rekey(&self) -> bool {
+        matches!(self, TransactionKind::Rekey { .. })
+    }
+
+    /// Returns true if this is a refund transaction.
+    pub fn is_refund(&self) -> bool {
+        matches!(self, TransactionKind::Refund)
+    }
+
+    /// Returns true if this is a committee transfer or an approval of one, signed by a member
+    /// of a committee.
+    pub fn is_committee(&self) -> bool {
+        matches!(self, TransactionKind::Committee | TransactionKind::Approval)
+    }
+
+    /// Returns true if this is an approval of a committee transfer.
+    pub fn is_approval(&self) -> bool {
+        matches!(self, TransactionKind::Approval)
+    }
+}
+
+/// Maximum number of receivers a single transfer can be split across
+pub const MAX_SPLIT_OUTPUTS: usize = AFFINE_POINT_WIDTH / 2;
+
+/// The outputs of a transfer split across up to `MAX_SPLIT_OUTPUTS` receivers, each of them
+/// consisting of the index of a receiver's leaf and of the amount credited to it.
+///
+/// The outputs take the place of the receiver's public key in the signed message, which
+/// holds their sum as amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SplitOutputs {
+    receivers: [usize; MAX_SPLIT_OUTPUTS],
+    amounts: [BaseElement; MAX_SPLIT_OUTPUTS],
+    len: usize,
+}
+
+impl SplitOutputs {
+    /// Returns new split outputs crediting, in order, each amount to the leaf at the
+    /// paired index.
+    ///
+    /// # Panics
+    /// Panics if there is no output, or more than `MAX_SPLIT_OUTPUTS` of them.
+    pub fn new(outputs: &[(usize, BaseElement)]) -> Self {
+        assert!(
+            !outputs.is_empty() && outputs.len() <= MAX_SPLIT_OUTPUTS,
+            "number of split outputs must be between 1 and {}",
+            MAX_SPLIT_OUTPUTS
+        );
+        let mut receivers = [0; MAX_SPLIT_OUTPUTS];
+        let mut amounts = [BaseElement::ZERO; MAX_SPLIT_OUTPUTS];
+        for (position, &(receiver, amount)) in outputs.iter().enumerate() {
+            receivers[position] = receiver;
+            amounts[position] = amount;
+        }
+
+        SplitOutputs {
+            receivers,
+            amounts,
+            len: outputs.len(),
+        }
+    }
+
+    /// Returns the number of outputs.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there is no output, which cannot happen for outputs built with
+    /// `SplitOutputs::new`.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of the receiver's leaf of the output at index `position`.
+    pub fn receiver(&self, position: usize) -> usize {
+        self.receivers[..self.len][position]
+    }
+
+    /// Returns the amount credited by the output at index `position`.
+    pub fn amount(&self, position: usize) -> BaseElement {
+        self.amounts[..self.len][position]
+    }
+
+    /// Returns the sum of the amounts of all outputs, as signed by the sender.
+    pub fn total(&self) -> BaseElement {
+        self.amounts
+            .iter()
+            .fold(BaseElement::ZERO, |sum, &amount| sum + amount)
+    }
+
+    /// Returns the sum of the amounts of the outputs up to index `position` included.
+    pub(crate) fn sent(&self, position: usize) -> BaseElement {
+        self.amounts[..=position]
+            .iter()
+            .fold(BaseElement::ZERO, |sum, &amount| sum + amount)
+    }
+
+    /// Returns the elements standing for these outputs in the signed message, i.e. the
+    /// amount and receiver index of each output in order, padded with zeros.
+    pub(crate) fn to_elements(self) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        for position in 0..self.len {
+            elements[2 * position] = self.amounts[position];
+            elements[2 * position + 1] = BaseElement::from(self.receivers[position] as u64);
+        }
+
+        elements
+    }
+}
+
 /// A set of variables indicating a series of `num_transactions` updates in a Merkle tree,
-/// represented as transactions from a sender to a receiver.
+/// represented as transactions from a sender to a receiver (or out of and into the tree for
+/// withdrawals and deposits), with a fee paid by the sender to a designated fee collector.
+/// Deposits do not pay any fee.
 ///
 /// - `initial_roots`: intermediate Merkle tree roots prior each transaction
 /// - `final_root`: final Merkle tree root after applying all transactions
-/// - `s_old_values`: sender leaves prior each transaction. Each tree leaf represents:
-/// - the account public key's x affine coordinate
-/// - the account public key's y affine coordinate
-/// - the account balance
-/// - the account nonce
-/// - `r_old_values` : receiver leaves prior each transaction
+/// - `s_old_values`: sender account states prior each transaction
+/// - `r_old_values` : receiver account states prior each transaction
+/// - `c_old_values` : fee collector account states prior each transaction
+/// - `fee_collector` : index of the fee collector leaf
 /// - `s_paths` : sender's Merkle path prior each transaction
 /// - `r_paths` : receiver's Merkle path prior each transaction
+/// - `c_paths` : fee collector's Merkle path prior each transaction
 /// - `deltas` : amounts to be sent in each transaction
+/// - `fees` : fees to be paid to the fee collector in each transaction
+/// - `kinds` : kinds of each transaction, i.e. transfers, withdrawals, deposits, outputs of
+///   split transfers or no-ops
 /// - `signatures` : signatures for each transaction
+/// - `memos` : optional memos signed with each transaction, none by default
+/// - `shares` : signature shares of the members signing committee transfers and their
+///   approvals, none by default
+/// - `subnet_id` : identifier of the subnet the transactions are signed for, zero by default
+/// - `epoch` : epoch all the transactions are signed for, zero by default
+/// - `tree_depth` : depth of the Merkle tree, inferred from the Merkle paths
 #[derive(Clone, Debug)]
 pub struct TransactionMetadata {
+    tree_depth: usize,
     initial_roots: Vec<Hash>,
     final_root: Hash,
-    s_old_values: Vec<[BaseElement; AFFINE_POINT_WIDTH + 2]>,
-    r_old_values: Vec<[BaseElement; AFFINE_POINT_WIDTH + 2]>,
+    s_old_values: Vec<AccountState>,
+    r_old_values: Vec<AccountState>,
+    c_old_values: Vec<AccountState>,
     s_indices: Vec<usize>,
     r_indices: Vec<usize>,
+    fee_collector: usize,
     s_paths: Vec<Vec<Hash>>,
     r_paths: Vec<Vec<Hash>>,
+    c_paths: Vec<Vec<Hash>>,
     deltas: Vec<BaseElement>,
+    fees: Vec<BaseElement>,
+    kinds: Vec<TransactionKind>,
     signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
+    memos: Vec<Option<[u8; 32]>>,
+    shares: Vec<Option<SignatureShare>>,
+    subnet_id: BaseElement,
+    epoch: u32,
 }
 
 impl TransactionMetadata {
@@ -199,141 +626,343 @@ impl TransactionMetadata {
     pub fn new(
         initial_roots: Vec<Hash>,
         final_root: Hash,
-        s_old_values: Vec<[BaseElement; AFFINE_POINT_WIDTH + 2]>,
-        r_old_values: Vec<[BaseElement; AFFINE_POINT_WIDTH + 2]>,
+        s_old_values: Vec<AccountState>,
+        r_old_values: Vec<AccountState>,
+        c_old_values: Vec<AccountState>,
         s_indices: Vec<usize>,
         r_indices: Vec<usize>,
+        fee_collector: usize,
         s_paths: Vec<Vec<Hash>>,
         r_paths: Vec<Vec<Hash>>,
+        c_paths: Vec<Vec<Hash>>,
         deltas: Vec<BaseElement>,
+        fees: Vec<BaseElement>,
+        kinds: Vec<TransactionKind>,
         signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
     ) -> Self {
         // Enforce that all vectors are of equal length
         assert_eq!(initial_roots.len(), s_old_values.len());
         assert_eq!(initial_roots.len(), r_old_values.len());
+        assert_eq!(initial_roots.len(), c_old_values.len());
         assert_eq!(initial_roots.len(), s_indices.len());
         assert_eq!(initial_roots.len(), r_indices.len());
         assert_eq!(initial_roots.len(), s_paths.len());
+        assert_eq!(initial_roots.len(), r_paths.len());
+        assert_eq!(initial_roots.len(), c_paths.len());
         assert_eq!(initial_roots.len(), deltas.len());
+        assert_eq!(initial_roots.len(), fees.len());
+        assert_eq!(initial_roots.len(), kinds.len());
         assert_eq!(initial_roots.len(), signatures.len());
 
+        // Enforce that all Merkle paths are consistent with a single tree depth
+        let tree_depth = s_paths
+            .first()
+            .map_or(MERKLE_TREE_DEPTH, |path| path.len() - 1);
+        assert!(
+            tree_depth > 0 && tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        for path in s_paths.iter().chain(r_paths.iter()).chain(c_paths.iter()) {
+            assert_eq!(path.len(), tree_depth + 1);
+        }
+        assert!(
+            fee_collector < 1 << tree_depth,
+            "fee collector index must be smaller than 2^{}",
+            tree_depth
+        );
+
+        let memos = vec![None; initial_roots.len()];
+        let shares = vec![None; initial_roots.len()];
         TransactionMetadata {
+            tree_depth,
             initial_roots,
             final_root,
             s_old_values,
             r_old_values,
+            c_old_values,
             s_indices,
             r_indices,
+            fee_collector,
             s_paths,
             r_paths,
+            c_paths,
             deltas,
+            fees,
+            kinds,
             signatures,
+            memos,
+            shares,
+            subnet_id: BaseElement::ZERO,
+            epoch: 0,
+        }
+    }
+
+    /// Returns these transactions, with the provided `memos` attached to them. The memo of
+    /// each transaction, if any, is part of the message signed by its sender.
+    ///
+    /// # Panics
+    /// Panics if there is not exactly one memo or `None` per transaction.
+    pub fn with_memos(mut self, memos: Vec<Option<[u8; 32]>>) -> Self {
+        assert_eq!(
+            memos.len(),
+            self.num_transactions(),
+            "number of memos and transactions must match"
+        );
+        self.memos = memos;
+        self
+    }
+
+    /// Returns these transactions, with the provided signature `shares` of the members of
+    /// committees signing them. Committee transfers and their approvals are each signed by one
+    /// member, whose share replaces the signature of the transaction and authenticates its key
+    /// in the key tree of the committee, while other transactions take `None`.
+    ///
+    /// # Panics
+    /// Panics if there is not exactly one share or `None` per transaction, if a committee
+    /// transfer or approval has no share, or if the path of a share does not match the depth
+    /// of the tree of accounts.
+    pub fn with_signature_shares(mut self, shares: Vec<Option<SignatureShare>>) -> Self {
+        assert_eq!(
+            shares.len(),
+            self.num_transactions(),
+            "number of signature shares and transactions must match"
+        );
+        for (i, share) in shares.iter().enumerate() {
+            match share {
+                Some(share) => {
+                    assert!(
+                        self.kinds[i].is_committee(),
+                        "only committee transfers and approvals are signed by shares"
+                    );
+                    assert_eq!(
+                        share.path.len(),
+                        self.tree_depth + 1,
+                        "key trees must have the depth of the tree of accounts"
+                    );
+                    self.signatures[i] = share.signature;
+                }
+                None => assert!(
+                    !self.kinds[i].is_committee(),
+                    "committee transfers and approvals must be signed by shares"
+                ),
+            }
         }
+        self.shares = shares;
+        self
+    }
+
+    /// Returns these transactions, signed for the subnet identified by `subnet_id` instead of
+    /// the default subnet zero. The subnet identifier is part of the message signed by each
+    /// sender, so that signatures cannot be replayed on another subnet.
+    pub fn with_subnet_id(mut self, subnet_id: BaseElement) -> Self {
+        self.subnet_id = subnet_id;
+        self
+    }
+
+    /// Returns these transactions, signed for the provided `epoch` instead of epoch zero. All
+    /// the transactions of a batch commit to the same epoch, which is part of the message
+    /// signed by each sender and exposed in the public inputs of their proof.
+    pub fn with_epoch(mut self, epoch: u32) -> Self {
+        self.epoch = epoch;
+        self
     }
 
     /// Builds a `TransactionMetadata` object from a set of `num_transactions` random transactions
     pub fn build_random(num_transactions: usize) -> Self {
-        #[cfg(feature = "std")]
+        Self::build_random_with_tree_depth(num_transactions, MERKLE_TREE_DEPTH)
+    }
+
+    /// Builds a `TransactionMetadata` object from a set of `num_transactions` random transactions
+    /// in a Merkle tree of depth `tree_depth`
+    pub fn build_random_with_tree_depth(num_transactions: usize, tree_depth: usize) -> Self {
+        Self::build_random_with_kinds(
+            &vec![TransactionKind::Transfer; num_transactions],
+            tree_depth,
+        )
+    }
+
+    /// Builds a `TransactionMetadata` object from a set of random transactions of the
+    /// provided `kinds` in a Merkle tree of depth `tree_depth`
+    ///
+    /// # Panics
+    /// Panics if any of the `kinds` is the output of a split transfer, a rekeying or a
+    /// committee transfer or approval, which must be built with a `TransactionBatchBuilder`
+    /// instead.
+    pub fn build_random_with_kinds(kinds: &[TransactionKind], tree_depth: usize) -> Self {
+        Self::build_random_with_hasher::<Rescue63>(kinds, tree_depth)
+    }
+
+    /// Builds a `TransactionMetadata` object from a set of random transactions of the
+    /// provided `kinds` in a Merkle tree of depth `tree_depth`, whose nodes are hashed with `H`
+    ///
+    /// # Panics
+    /// Panics if any of the `kinds` is the output of a split transfer, a rekeying or a
+    /// committee transfer or approval.
+    pub fn build_random_with_hasher<H: TreeHasher>(
+        kinds: &[TransactionKind],
+        tree_depth: usize,
+    ) -> Self {
+        Self::build_random_with_rng::<H, _>(kinds, tree_depth, &mut OsRng)
+    }
+
+    /// Builds a `TransactionMetadata` object from a set of `num_transactions` random transactions
+    /// drawn from a ChaCha20 generator seeded with `seed`.
+    ///
+    /// The same seed always yields the same transactions, keys and signatures, which makes
+    /// benchmarks and failures reproducible across machines.
+    pub fn build_random_with_seed(num_transactions: usize, seed: [u8; 32]) -> Self {
+        Self::build_random_with_rng::<Rescue63, _>(
+            &vec![TransactionKind::Transfer; num_transactions],
+            MERKLE_TREE_DEPTH,
+            &mut ChaCha20Rng::from_seed(seed),
+        )
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Builds a `TransactionMetadata` object from a set of random transactions of the
+    /// provided `kinds` in a Merkle tree of depth `tree_depth`, whose nodes are hashed with `H`,
+    /// all randomness being drawn from `rng`
+    fn build_random_with_rng<H: TreeHasher, R: RngCore + CryptoRng>(
+        kinds: &[TransactionKind],
+        tree_depth: usize,
+        rng: &mut R,
+    ) -> Self {
+        assert!(
+            !kinds
+                .iter()
+                .any(|kind| kind.is_split() || kind.is_rekey() || kind.is_committee()),
+            "random split transfers, rekeyings and committee transfers are not supported"
+        );
+        let num_transactions = kinds.len();
+        #[cfg(feature = "tracing")]
+        let _span = debug_span!("build_random", num_transactions, tree_depth).entered();
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         let now = Instant::now();
-        let mut rng = OsRng;
-        let tree_size = usize::pow(2, MERKLE_TREE_DEPTH as u32);
-        // Ensure values are of appropriate size
-        // TODO: Change this and the size bound on delta if RANGE_LOG changes
-        let mut secret_keys = vec![Scalar::default(); tree_size];
-        let mut values = vec![[BaseElement::ZERO; AFFINE_POINT_WIDTH + 2]; tree_size];
+        let tree_size = usize::pow(2, tree_depth as u32);
+        // Only the accounts touched by the transactions are materialized. Balances, amounts
+        // and fees are drawn below the field modulus, and are hence only valid for the default
+        // RANGE_LOG
+        let mut secret_keys = BTreeMap::new();
 
         // Initialize the vectors
-        let mut s_secret_keys = vec![Scalar::zero(); num_transactions];
-        let mut s_old_values = vec![[BaseElement::ZERO; AFFINE_POINT_WIDTH + 2]; num_transactions];
-        let mut r_old_values = vec![[BaseElement::ZERO; AFFINE_POINT_WIDTH + 2]; num_transactions];
+        let mut s_secret_keys = vec![SecretKey::from(Scalar::one()); num_transactions];
         let mut s_indices = vec![0; num_transactions];
         let mut r_indices = vec![0; num_transactions];
-        const EMPTY_PATH: Vec<Hash> = Vec::new();
-        let mut s_paths = vec![EMPTY_PATH; num_transactions];
-        let mut r_paths = vec![EMPTY_PATH; num_transactions];
-        let mut deltas = vec![BaseElement::ZERO; num_transactions];
-        #[cfg(feature = "std")]
+        let mut transactions = Vec::with_capacity(num_transactions);
+        let mut witnesses = Vec::with_capacity(num_transactions);
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         debug!("Initialized vectors in {} ms", now.elapsed().as_millis(),);
 
         // Create the empty Merkle tree
-        #[cfg(feature = "std")]
+        #[cfg(feature = "tracing")]
+        let span = debug_span!("build_tree", tree_depth).entered();
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         let now = Instant::now();
-        let mut tree = MerkleTree::<Rescue63>::build_empty(MERKLE_TREE_DEPTH);
-        #[cfg(feature = "std")]
+        let mut state = StateTree::<H>::new_with_hasher(tree_depth);
+        #[cfg(feature = "tracing")]
+        span.exit();
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         debug!(
             "Built empty Merkle tree of depth {} in {} ms",
-            MERKLE_TREE_DEPTH,
+            tree_depth,
             now.elapsed().as_millis(),
         );
 
-        #[cfg(feature = "std")]
+        // Fill in a random fee collector account in the tree
+        let fee_collector = rng.next_u64() as usize % tree_size;
+        let skey = SecretKey::random(&mut *rng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.insert(fee_collector, skey);
+        let balance = rng.next_u64() % BaseElement::MODULUS;
+        // nonces are drawn below the multisig flag, as the accounts are controlled by a single key
+        let nonce = rng.next_u64() % MULTISIG_NONCE_FLAG;
+        let val = AccountState::new(pkey, BaseElement::from(balance), BaseElement::from(nonce));
+        state.set_account(fee_collector, val);
+
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         let now = Instant::now();
-        // Fill in random sender values in the tree
-        for s_index in s_indices.iter_mut() {
-            // Get a random index to instantiate values for
+        #[cfg(feature = "tracing")]
+        let span = debug_span!("sender_accounts", num_transactions).entered();
+        // Fill in random sender values in the tree, deposits having no sender
+        for (s_index, kind) in s_indices.iter_mut().zip(kinds.iter()) {
+            if kind.is_deposit() {
+                continue;
+            }
+            // Get a random index to instantiate values for, distinct from the fee collector
             *s_index = rng.next_u64() as usize % tree_size;
+            while *s_index == fee_collector {
+                *s_index = rng.next_u64() as usize % tree_size;
+            }
             //s_indices[transaction_num] = s_index;
-            let skey = Scalar::random(&mut rng);
-            secret_keys[*s_index] = skey;
-            let pkey = AffinePoint::from(AffinePoint::generator() * skey);
-            let balance = rng.next_u64();
-            let nonce = rng.next_u64();
-            let mut val = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
-            val[0..POINT_COORDINATE_WIDTH].copy_from_slice(&pkey.get_x());
-            val[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&pkey.get_y());
-            val[AFFINE_POINT_WIDTH] = BaseElement::from(balance);
-            val[AFFINE_POINT_WIDTH + 1] = BaseElement::from(nonce);
-            values[*s_index] = val;
-            let leaf = Rescue63::merge(&[
-                Hash::new(val[0], val[1], val[2], val[3], val[4], val[5], val[6]),
-                Hash::new(val[7], val[8], val[9], val[10], val[11], val[12], val[13]),
-            ]);
-            // Update the tree with the new leaf
-            tree.update_leaf(*s_index, leaf);
-        }
-        #[cfg(feature = "std")]
+            let skey = SecretKey::random(&mut *rng);
+            let pkey = skey.public_key().to_point();
+            secret_keys.insert(*s_index, skey);
+            let balance = rng.next_u64() % BaseElement::MODULUS;
+            let nonce = rng.next_u64() % MULTISIG_NONCE_FLAG;
+            let val = AccountState::new(pkey, BaseElement::from(balance), BaseElement::from(nonce));
+            state.set_account(*s_index, val);
+        }
+        #[cfg(feature = "tracing")]
+        span.exit();
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         debug!(
             "Filled in {} sender accounts in {} ms",
             num_transactions,
             now.elapsed().as_millis(),
         );
 
-        #[cfg(feature = "std")]
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         let now = Instant::now();
+        #[cfg(feature = "tracing")]
+        let span = debug_span!(
+            "receiver_accounts",
+            num_transactions,
+            new_accounts = tracing::field::Empty
+        )
+        .entered();
         // Fill in random receiver values in the tree
-        //#[cfg(feature = "std")]
+        //#[cfg(all(feature = "std", not(feature = "tracing")))]
         #[allow(dead_code)]
         let mut new_accounts = 0;
         for transaction_num in 0..num_transactions {
-            // Make sure receiver is not the same as sender
+            // Withdrawals do not credit any receiver, and use the sender's leaf instead, as
+            // no-ops do
+            if kinds[transaction_num].is_withdrawal() || kinds[transaction_num].is_noop() {
+                r_indices[transaction_num] = s_indices[transaction_num];
+                continue;
+            }
+            // Make sure receiver is not the same as sender nor the fee collector
             let mut r_index = rng.next_u64() as usize % tree_size;
-            while s_indices[transaction_num] == r_index {
+            while s_indices[transaction_num] == r_index || r_index == fee_collector {
                 r_index = rng.next_u64() as usize % tree_size;
             }
             r_indices[transaction_num] = r_index;
+            // Deposits use the receiver's leaf as sender
+            if kinds[transaction_num].is_deposit() {
+                s_indices[transaction_num] = r_index;
+            }
             // Determine if the receiver has an "account" already
-            if secret_keys[r_index] == Scalar::default() {
-                let skey = Scalar::random(&mut rng);
-                secret_keys[r_index] = skey;
-                let pkey = AffinePoint::from(AffinePoint::generator() * skey);
-                let balance = rng.next_u64();
-                let nonce = rng.next_u64();
-                let mut val = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
-                val[0..POINT_COORDINATE_WIDTH].copy_from_slice(&pkey.get_x());
-                val[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&pkey.get_y());
-                val[AFFINE_POINT_WIDTH] = BaseElement::from(balance);
-                val[AFFINE_POINT_WIDTH + 1] = BaseElement::from(nonce);
-                values[r_index] = val;
-                let leaf = Rescue63::merge(&[
-                    Hash::new(val[0], val[1], val[2], val[3], val[4], val[5], val[6]),
-                    Hash::new(val[7], val[8], val[9], val[10], val[11], val[12], val[13]),
-                ]);
-                // Update the tree with the new leaf
-                tree.update_leaf(r_index, leaf);
+            if let Entry::Vacant(entry) = secret_keys.entry(r_index) {
+                let skey = SecretKey::random(&mut *rng);
+                let pkey = skey.public_key().to_point();
+                entry.insert(skey);
+                let balance = rng.next_u64() % BaseElement::MODULUS;
+                let nonce = rng.next_u64() % MULTISIG_NONCE_FLAG;
+                let val =
+                    AccountState::new(pkey, BaseElement::from(balance), BaseElement::from(nonce));
+                state.set_account(r_index, val);
                 new_accounts += 1;
             }
         }
-        #[cfg(feature = "std")]
+        #[cfg(feature = "tracing")]
+        {
+            span.record("new_accounts", new_accounts);
+            span.exit();
+        }
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         debug!(
             "Selected {} receiver accounts (creating {} new accounts) in {} ms",
             num_transactions,
@@ -341,143 +970,998 @@ impl TransactionMetadata {
             now.elapsed().as_millis(),
         );
 
-        let mut initial_roots = Vec::new();
-
-        #[cfg(feature = "std")]
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         let now = Instant::now();
+        #[cfg(feature = "tracing")]
+        let span = debug_span!(
+            "apply_transactions",
+            num_transactions,
+            final_root = tracing::field::Empty
+        )
+        .entered();
         // Repeat basic process for every transaction
         for transaction_num in 0..num_transactions {
             // Select the indices for this trancaction
             let s_index = s_indices[transaction_num];
             let r_index = r_indices[transaction_num];
-            // ensure that delta is small enough to not overflow the receiver's balance
-            // or underflow the sender's balance and make the AIR program fail
-            let delta_value = rng.next_u64()
-                % core::cmp::min(
-                    values[s_index][AFFINE_POINT_WIDTH].to_repr(),
-                    u64::MAX - values[r_index][AFFINE_POINT_WIDTH].to_repr(),
-                );
-            let delta = BaseElement::from(delta_value);
-
-            // Store the old values, indices, and delta
-            initial_roots.push(*tree.root());
-            s_secret_keys[transaction_num] = secret_keys[s_index];
-            s_old_values[transaction_num] = values[s_index];
-            r_old_values[transaction_num] = values[r_index];
-            s_indices[transaction_num] = s_index;
-            r_indices[transaction_num] = r_index;
-            deltas[transaction_num] = delta;
-
-            // Compute Merkle path for the leaf specified by the sender index
-            s_paths[transaction_num] = tree.prove(s_index).unwrap();
-
-            // Update the Merkle tree with the new values at the same indices
-            values[s_index][AFFINE_POINT_WIDTH] -= delta;
-            values[s_index][AFFINE_POINT_WIDTH + 1] += BaseElement::ONE;
-            values[r_index][AFFINE_POINT_WIDTH] += delta;
-            let s_leaf = Rescue63::merge(&[
-                Hash::new(
-                    values[s_index][0],
-                    values[s_index][1],
-                    values[s_index][2],
-                    values[s_index][3],
-                    values[s_index][4],
-                    values[s_index][5],
-                    values[s_index][6],
-                ),
-                Hash::new(
-                    values[s_index][7],
-                    values[s_index][8],
-                    values[s_index][9],
-                    values[s_index][10],
-                    values[s_index][11],
-                    values[s_index][12],
-                    values[s_index][13],
-                ),
-            ]);
-            let r_leaf = Rescue63::merge(&[
-                Hash::new(
-                    values[r_index][0],
-                    values[r_index][1],
-                    values[r_index][2],
-                    values[r_index][3],
-                    values[r_index][4],
-                    values[r_index][5],
-                    values[r_index][6],
-                ),
-                Hash::new(
-                    values[r_index][7],
-                    values[r_index][8],
-                    values[r_index][9],
-                    values[r_index][10],
-                    values[r_index][11],
-                    values[r_index][12],
-                    values[r_index][13],
-                ),
-            ]);
-            tree.update_leaf(s_index, s_leaf);
-            tree.update_leaf(r_index, r_leaf);
+            let kind = kinds[transaction_num];
+            let s_old_value = *state.get_account(s_index).unwrap();
+            let s_balance = s_old_value.balance.to_repr();
+            let r_balance = state.get_account(r_index).unwrap().balance.to_repr();
+            let c_balance = state.get_account(fee_collector).unwrap().balance.to_repr();
+            // ensure that delta is small enough to not overflow the receiver's balance past the
+            // field modulus or underflow the sender's balance and make the AIR program fail
+            let delta_value = if kind.is_noop() {
+                0
+            } else if kind.is_withdrawal() {
+                rng.next_u64() % s_balance
+            } else {
+                rng.next_u64() % core::cmp::min(s_balance, BaseElement::MODULUS - r_balance)
+            };
+            // ensure that the fee is small enough to not underflow the sender's balance
+            // once delta is deducted, or overflow the fee collector's balance past the field
+            // modulus
+            let fee_value = if kind.is_deposit() || kind.is_noop() {
+                0
+            } else {
+                rng.next_u64()
+                    % core::cmp::min(s_balance - delta_value, BaseElement::MODULUS - c_balance)
+            };
 
-            // Compute Merkle path for the leaf specified by the receiver index
-            r_paths[transaction_num] = tree.prove(r_index).unwrap();
+            s_secret_keys[transaction_num] = if kind.is_deposit() || kind.is_noop() {
+                SecretKey::from(Scalar::one())
+            } else {
+                secret_keys[&s_index].clone()
+            };
+            // Transactions are signed once all of them have been applied
+            let transaction = Transaction {
+                sender: s_index,
+                receiver: r_index,
+                amount: BaseElement::from(delta_value),
+                fee: BaseElement::from(fee_value),
+                nonce: s_old_value.nonce,
+                kind,
+                signature: ([BaseElement::ZERO; POINT_COORDINATE_WIDTH], Scalar::zero()),
+                memo: None,
+            };
+            witnesses.push(state.apply_transaction(&transaction, fee_collector));
+            transactions.push(transaction);
         }
-        let final_root = *tree.root();
-        #[cfg(feature = "std")]
+        let final_root = state.root();
+        #[cfg(feature = "tracing")]
+        {
+            span.record("final_root", hex::encode(final_root.as_bytes()).as_str());
+            span.exit();
+        }
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         debug!(
             "Updated Merkle tree with {} transactions to root {} in {} ms",
             num_transactions,
-            hex::encode(<<Rescue63 as Hasher>::Digest>::as_bytes(&final_root)),
+            hex::encode(final_root.as_bytes()),
             now.elapsed().as_millis(),
         );
 
-        #[cfg(feature = "std")]
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         let now = Instant::now();
-        let mut signatures = Vec::with_capacity(num_transactions);
-        for i in 0..num_transactions {
-            // A message consists in sender's pkey, receiver's pkey, amount to be sent and sender's nonce.
+        #[cfg(feature = "tracing")]
+        let span = debug_span!("sign_transactions", num_transactions).entered();
+        for (i, (transaction, witness)) in transactions.iter_mut().zip(witnesses.iter()).enumerate()
+        {
+            // A message consists in sender's pkey, receiver's pkey (or recipient address),
+            // amount to be sent, sender's nonce, fee and withdrawal flag, followed by the
+            // memo, if any.
             let message = build_tx_message(
-                &s_old_values[i][0..AFFINE_POINT_WIDTH],
-                &r_old_values[i][0..AFFINE_POINT_WIDTH],
-                deltas[i],
-                s_old_values[i][AFFINE_POINT_WIDTH + 1],
+                &witness.s_old_value.pub_key_elements(),
+                &witness.r_old_value.pub_key_elements(),
+                transaction.sender,
+                transaction.receiver,
+                transaction.amount,
+                transaction.nonce,
+                transaction.fee,
+                transaction.kind,
+                transaction.memo,
+                BaseElement::ZERO,
+                0,
             );
-            signatures.push(schnorr::sign(message, s_secret_keys[i]));
+            transaction.signature = s_secret_keys[i].sign_with_rng(message, rng);
         }
+        #[cfg(feature = "tracing")]
+        span.exit();
 
-        #[cfg(feature = "std")]
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
         debug!(
             "Computed {} Schnorr signatures in {} ms",
             num_transactions,
             now.elapsed().as_millis(),
         );
 
+        TransactionMetadata::from_witnesses(&transactions, witnesses, final_root, fee_collector)
+    }
+
+    /// Returns the `TransactionMetadata` of the provided transactions, given the state of
+    /// the tree witnessed prior to each of them, and the final root after all of them.
+    pub(crate) fn from_witnesses(
+        transactions: &[Transaction],
+        witnesses: Vec<TransactionWitness>,
+        final_root: Hash,
+        fee_collector: usize,
+    ) -> Self {
+        let num_transactions = witnesses.len();
+        let mut initial_roots = Vec::with_capacity(num_transactions);
+        let mut s_old_values = Vec::with_capacity(num_transactions);
+        let mut r_old_values = Vec::with_capacity(num_transactions);
+        let mut c_old_values = Vec::with_capacity(num_transactions);
+        let mut s_paths = Vec::with_capacity(num_transactions);
+        let mut r_paths = Vec::with_capacity(num_transactions);
+        let mut c_paths = Vec::with_capacity(num_transactions);
+        for witness in witnesses {
+            initial_roots.push(witness.initial_root);
+            s_old_values.push(witness.s_old_value);
+            r_old_values.push(witness.r_old_value);
+            c_old_values.push(witness.c_old_value);
+            s_paths.push(witness.s_path);
+            r_paths.push(witness.r_path);
+            c_paths.push(witness.c_path);
+        }
+
         TransactionMetadata::new(
             initial_roots,
             final_root,
             s_old_values,
             r_old_values,
+            c_old_values,
+            transactions.iter().map(|tx| tx.sender).collect(),
+            transactions.iter().map(|tx| tx.receiver).collect(),
+            fee_collector,
+            s_paths,
+            r_paths,
+            c_paths,
+            transactions.iter().map(|tx| tx.amount).collect(),
+            transactions.iter().map(|tx| tx.fee).collect(),
+            transactions.iter().map(|tx| tx.kind).collect(),
+            transactions.iter().map(|tx| tx.signature).collect(),
+        )
+        .with_memos(transactions.iter().map(|tx| tx.memo).collect())
+    }
+}
+
+impl TransactionMetadata {
+    /// Returns the number of transactions contained in this `TransactionMetadata`.
+    pub fn num_transactions(&self) -> usize {
+        self.initial_roots.len()
+    }
+
+    /// Returns the depth of the Merkle tree these transactions apply to.
+    pub fn tree_depth(&self) -> usize {
+        self.tree_depth
+    }
+
+    /// Returns the index of the leaf collecting the fees of these transactions.
+    pub fn fee_collector(&self) -> usize {
+        self.fee_collector
+    }
+
+    /// Returns the identifier of the subnet these transactions are signed for.
+    pub fn subnet_id(&self) -> BaseElement {
+        self.subnet_id
+    }
+
+    /// Returns the epoch these transactions are signed for.
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// Returns the Merkle tree roots prior to each of these transactions.
+    pub fn initial_roots(&self) -> &[Hash] {
+        &self.initial_roots
+    }
+
+    /// Returns the Merkle tree root after applying all these transactions.
+    pub fn final_root(&self) -> Hash {
+        self.final_root
+    }
+
+    /// Returns the sender account states prior to each of these transactions.
+    pub fn s_old_values(&self) -> &[AccountState] {
+        &self.s_old_values
+    }
+
+    /// Returns the receiver account states prior to each of these transactions.
+    pub fn r_old_values(&self) -> &[AccountState] {
+        &self.r_old_values
+    }
+
+    /// Returns the fee collector account states prior to each of these transactions.
+    pub fn c_old_values(&self) -> &[AccountState] {
+        &self.c_old_values
+    }
+
+    /// Returns the indices of the sender leaves of these transactions.
+    pub fn s_indices(&self) -> &[usize] {
+        &self.s_indices
+    }
+
+    /// Returns the indices of the receiver leaves of these transactions.
+    pub fn r_indices(&self) -> &[usize] {
+        &self.r_indices
+    }
+
+    /// Returns the Merkle paths of the senders of these transactions.
+    pub fn s_paths(&self) -> &[Vec<Hash>] {
+        &self.s_paths
+    }
+
+    /// Returns the Merkle paths of the receivers of these transactions.
+    pub fn r_paths(&self) -> &[Vec<Hash>] {
+        &self.r_paths
+    }
+
+    /// Returns the Merkle paths of the fee collector in each of these transactions.
+    pub fn c_paths(&self) -> &[Vec<Hash>] {
+        &self.c_paths
+    }
+
+    /// Returns the amounts sent in each of these transactions.
+    pub fn deltas(&self) -> &[BaseElement] {
+        &self.deltas
+    }
+
+    /// Returns the fees paid to the fee collector in each of these transactions.
+    pub fn fees(&self) -> &[BaseElement] {
+        &self.fees
+    }
+
+    /// Returns the kinds of each of these transactions.
+    pub fn kinds(&self) -> &[TransactionKind] {
+        &self.kinds
+    }
+
+    /// Returns the signatures of each of these transactions.
+    pub fn signatures(&self) -> &[([BaseElement; POINT_COORDINATE_WIDTH], Scalar)] {
+        &self.signatures
+    }
+
+    /// Returns the signature shares of the members signing each of these transactions, for
+    /// committee transfers and their approvals.
+    pub fn signature_shares(&self) -> &[Option<SignatureShare>] {
+        &self.shares
+    }
+
+    /// Returns the public inputs of a state-transition proof for these transactions, with
+    /// the default range log and the nodes of the Merkle tree being hashed with Rescue63.
+    ///
+    /// Use `TransactionProver::public_inputs` for the public inputs matching the
+    /// configuration of a given prover.
+    pub fn public_inputs(&self) -> PublicInputs {
+        PublicInputs::new(
+            self.initial_roots[0],
+            self.final_root,
+            self.tree_depth,
+            self.fee_collector,
+            self.withdrawals(),
+            self.deposits(),
+        )
+        .with_memos(self.memos())
+        .with_subnet_id(self.subnet_id)
+        .with_epoch(self.epoch)
+    }
+
+    /// Returns the withdrawals performed by these transactions, as exposed in the
+    /// public inputs of their state-transition proof.
+    pub fn withdrawals(&self) -> Vec<Withdrawal> {
+        self.kinds
+            .iter()
+            .enumerate()
+            .filter_map(|(tx_index, kind)| match kind {
+                TransactionKind::Withdrawal { recipient } => Some(Withdrawal::new(
+                    tx_index,
+                    self.deltas[tx_index].to_repr(),
+                    *recipient,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the deposits performed by these transactions, as exposed in the
+    /// public inputs of their state-transition proof.
+    pub fn deposits(&self) -> Vec<Deposit> {
+        self.kinds
+            .iter()
+            .enumerate()
+            .filter(|(_, kind)| kind.is_deposit())
+            .map(|(tx_index, _)| {
+                Deposit::new(
+                    tx_index,
+                    self.deltas[tx_index].to_repr(),
+                    self.r_indices[tx_index],
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the memos attached to these transactions, as exposed in the public inputs
+    /// of their state-transition proof. An all-zero memo cannot be told apart from a missing
+    /// one, and is not exposed.
+    pub fn memos(&self) -> Vec<Memo> {
+        self.memos
+            .iter()
+            .enumerate()
+            .filter_map(|(tx_index, memo)| match memo {
+                Some(data) if data.iter().any(|&byte| byte != 0) => {
+                    Some(Memo::new(tx_index, *data))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the running digest of the (sender, receiver, amount) tuples of these
+    /// transactions, as exposed in the public inputs of their state-transition proof when
+    /// generated with `TransactionProver::with_transactions_digest`.
+    ///
+    /// Starting from zero, every transaction but no-ops and approvals updates the digest with
+    /// the keys of its sender and receiver as signed, i.e. the deposit key for deposits, the
+    /// receiver's key in place of the sender's one for refunds and the key of the first member
+    /// for committee transfers, and the recipient address or the outputs for withdrawals and
+    /// split transfers, followed by its amount. No-ops
+    /// being skipped, the digest does not depend on the padding of the transactions.
+    pub fn transactions_digest(&self) -> Hash {
+        self.running_transactions_digests()[self.num_transactions()]
+    }
+
+    /// Returns the running digest of transactions prior to each of these transactions,
+    /// followed by the digest of all of them.
+    pub(crate) fn running_transactions_digests(&self) -> Vec<Hash> {
+        let mut digests = Vec::with_capacity(self.num_transactions() + 1);
+        let mut digest = Hash::default();
+        digests.push(digest);
+        for i in 0..self.num_transactions() {
+            if !self.kinds[i].is_noop() && !self.kinds[i].is_approval() {
+                digest = update_transactions_digest(digest, &self.transaction_tuple(i));
+            }
+            digests.push(digest);
+        }
+
+        digests
+    }
+
+    /// Returns a proof that the transaction at `tx_index` is part of these transactions,
+    /// verifiable against the digest of transactions exposed by their state-transition proof,
+    /// e.g. to settle a dispute over a transfer. Returns `None` for no-ops and approvals, which
+    /// the digest skips.
+    ///
+    /// # Panics
+    /// Panics if `tx_index` is out of bounds.
+    pub fn inclusion_proof(&self, tx_index: usize) -> Option<InclusionProof> {
+        assert!(
+            tx_index < self.num_transactions(),
+            "transaction index out of bounds"
+        );
+        if self.kinds[tx_index].is_noop() || self.kinds[tx_index].is_approval() {
+            return None;
+        }
+        let position = self.kinds[..tx_index]
+            .iter()
+            .filter(|kind| !kind.is_noop() && !kind.is_approval())
+            .count();
+        let following = (tx_index + 1..self.num_transactions())
+            .filter(|&i| !self.kinds[i].is_noop() && !self.kinds[i].is_approval())
+            .map(|i| self.transaction_tuple(i))
+            .collect();
+
+        Some(InclusionProof::new(
+            position,
+            self.running_transactions_digests()[tx_index],
+            self.transaction_tuple(tx_index),
+            following,
+        ))
+    }
+
+    /// Returns the (sender key, receiver key, amount) tuple of the transaction at `tx_index`,
+    /// as hashed into the digest of transactions.
+    fn transaction_tuple(&self, tx_index: usize) -> [BaseElement; TX_TUPLE_WIDTH] {
+        let message = build_tx_message(
+            &self.signer_key_elements(tx_index),
+            &self.r_old_values[tx_index].pub_key_elements(),
+            self.s_indices[tx_index],
+            self.r_indices[tx_index],
+            self.deltas[tx_index],
+            self.nonce_account(tx_index).nonce,
+            self.fees[tx_index],
+            self.kinds[tx_index],
+            self.memos[tx_index],
+            self.subnet_id,
+            self.epoch,
+        );
+
+        transaction_tuple(&message, self.deltas[tx_index])
+    }
+
+    /// Returns the nullifiers of these transactions, in order, skipping deposits, no-ops and
+    /// approvals which consume no nonce. The nullifier of a transaction is the one of the
+    /// account whose nonce it consumes prior to the transaction, i.e. of its sender or of the
+    /// receiver of a refund, as returned by `AccountState::nullifier`, the key of a committee
+    /// being replaced with the one of the member signing its transfer.
+    ///
+    /// Their commitment is exposed in the public inputs of the state-transition proof when
+    /// generated with `TransactionProver::with_nullifiers`, so that a verifier keeping track of
+    /// the nullifiers already processed can detect a batch submitted twice.
+    pub fn nullifiers(&self) -> Vec<Hash> {
+        (0..self.num_transactions())
+            .filter(|&i| self.consumes_nonce(i))
+            .map(|i| self.nullifier(i))
+            .collect()
+    }
+
+    /// Returns the commitment to the nullifiers of the transactions prior to each of these
+    /// transactions, followed by the commitment to the nullifiers of all of them.
+    pub(crate) fn running_nullifiers_commitments(&self) -> Vec<Hash> {
+        let mut commitments = Vec::with_capacity(self.num_transactions() + 1);
+        let mut commitment = Hash::default();
+        commitments.push(commitment);
+        for i in 0..self.num_transactions() {
+            if self.consumes_nonce(i) {
+                commitment = air::update_nullifiers_commitment(commitment, self.nullifier(i));
+            }
+            commitments.push(commitment);
+        }
+
+        commitments
+    }
+
+    /// Returns the state prior to the transaction at `tx_index` of the account whose nonce it
+    /// signs, i.e. of the receiver for refunds, which the receiver authorizes, and of the
+    /// sender otherwise.
+    ///
+    /// Approvals sign the nonce of the committee transfer they approve, prior to it.
+    pub(crate) fn nonce_account(&self, tx_index: usize) -> AccountState {
+        match self.kinds[tx_index] {
+            TransactionKind::Refund => self.r_old_values[tx_index],
+            TransactionKind::Approval => self.s_old_values[self.committee_transfer_index(tx_index)],
+            _ => self.s_old_values[tx_index],
+        }
+    }
+
+    /// Returns the position of the committee transfer approved by the transaction at
+    /// `tx_index`, i.e. of the last committee transfer up to it.
+    ///
+    /// # Panics
+    /// Panics if no committee transfer precedes the transaction.
+    pub(crate) fn committee_transfer_index(&self, tx_index: usize) -> usize {
+        (0..=tx_index)
+            .rev()
+            .find(|&i| self.kinds[i] == TransactionKind::Committee)
+            .expect("approvals must follow a committee transfer")
+    }
+
+    /// Returns the coordinates of the key signing the transaction at `tx_index` in place of
+    /// its sender, i.e. the key of the member signing committee transfers and approvals, and
+    /// the sender's key otherwise. The deposit key and the receiver's key signing deposits,
+    /// no-ops and refunds are selected by `build_tx_message`.
+    ///
+    /// # Panics
+    /// Panics if a committee transfer or approval has no signature share.
+    pub(crate) fn signer_key_elements(&self, tx_index: usize) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        if self.kinds[tx_index].is_committee() {
+            let share = self.shares[tx_index]
+                .as_ref()
+                .expect("committee transfers and approvals must be signed by shares");
+            AccountState::new(share.key, BaseElement::ZERO, BaseElement::ZERO).pub_key_elements()
+        } else {
+            self.s_old_values[tx_index].pub_key_elements()
+        }
+    }
+
+    /// Returns whether the transaction at `tx_index` consumes a nonce, i.e. is neither a
+    /// deposit, a no-op nor an approval.
+    fn consumes_nonce(&self, tx_index: usize) -> bool {
+        let kind = self.kinds[tx_index];
+        !kind.is_deposit() && !kind.is_noop() && !kind.is_approval()
+    }
+
+    /// Returns the nullifier of the transaction at `tx_index`, hashing the key of the member
+    /// signing committee transfers in place of the one of the committee.
+    fn nullifier(&self, tx_index: usize) -> Hash {
+        let mut account = self.nonce_account(tx_index);
+        if let Some(share) = &self.shares[tx_index] {
+            account.pub_key = share.key;
+        }
+        account.nullifier()
+    }
+
+    /// Returns the number of these transactions, no-ops and approvals excluded, and the sum of
+    /// their amounts, refunds being subtracted, as exposed in the public inputs of the
+    /// state-transition proof when generated with `TransactionProver::with_batch_statistics`.
+    pub fn batch_statistics(&self) -> BatchStatistics {
+        let [num_transactions, volume] = self.running_batch_statistics()[self.num_transactions()];
+        BatchStatistics::new(num_transactions.to_repr() as usize, volume.to_repr())
+    }
+
+    /// Returns the number and volume of the transactions prior to each of these transactions,
+    /// followed by the ones of all of them.
+    pub(crate) fn running_batch_statistics(&self) -> Vec<[BaseElement; 2]> {
+        let mut statistics = Vec::with_capacity(self.num_transactions() + 1);
+        let mut current = [BaseElement::ZERO; 2];
+        statistics.push(current);
+        for i in 0..self.num_transactions() {
+            if !self.kinds[i].is_noop() && !self.kinds[i].is_approval() {
+                current[0] += BaseElement::ONE;
+                current[1] += self.deltas[i];
+            }
+            statistics.push(current);
+        }
+
+        statistics
+    }
+
+    /// Checks the signatures of all these transactions at once, returning `true` if and
+    /// only if all of them are valid. This is meant to pre-validate a batch of transactions
+    /// before spending time proving their state-transition.
+    pub fn verify_signatures(&self) -> bool {
+        let messages: Vec<_> = (0..self.num_transactions())
+            .map(|i| {
+                build_tx_message(
+                    &self.signer_key_elements(i),
+                    &self.r_old_values[i].pub_key_elements(),
+                    self.s_indices[i],
+                    self.r_indices[i],
+                    self.deltas[i],
+                    self.nonce_account(i).nonce,
+                    self.fees[i],
+                    self.kinds[i],
+                    self.memos[i],
+                    self.subnet_id,
+                    self.epoch,
+                )
+            })
+            .collect();
+        let pkeys: Vec<_> = messages
+            .iter()
+            .map(|message| {
+                let mut pkey_coords = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+                pkey_coords.copy_from_slice(&message[0..AFFINE_POINT_WIDTH]);
+                AffinePoint::from_raw_coordinates(pkey_coords)
+            })
+            .collect();
+
+        schnorr::verify_batch(&messages, &self.signatures, &pkeys)
+    }
+
+    /// Reads a `TransactionMetadata` object from the provided bytes, as
+    /// serialized with `Serializable::to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        let mut source = SliceReader::new(bytes);
+        let tx_metadata = Self::read_from(&mut source)?;
+        if source.has_more_bytes() {
+            return Err(DeserializationError::UnconsumedBytes);
+        }
+
+        Ok(tx_metadata)
+    }
+}
+
+impl Serializable for TransactionMetadata {
+    /// Serializes `self` and writes the resulting bytes into the `target` writer.
+    ///
+    /// The number of transactions, the tree depth and the fee collector index are written
+    /// first, followed by the final root, the subnet identifier, the epoch and, for each
+    /// transaction, its initial root, sender, receiver and fee collector leaves, indices,
+    /// Merkle paths, amount, fee, kind, signature and optional memo. Committee transfers and
+    /// approvals are followed by the index, key and path of their signature share after their
+    /// kind, the share's signature being the one of the transaction.
+    ///
+    /// Above the lowest common ancestor of the sender's and receiver's leaves, both paths hold
+    /// the same siblings, so that the receiver's path is written as the number of its topmost
+    /// siblings shared with the sender's path, followed by its remaining elements only.
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.num_transactions() as u32);
+        target.write_u8(self.tree_depth as u8);
+        target.write_u64(self.fee_collector as u64);
+        self.final_root.write_into(target);
+        self.subnet_id.write_into(target);
+        target.write_u32(self.epoch);
+        for i in 0..self.num_transactions() {
+            self.initial_roots[i].write_into(target);
+            self.s_old_values[i].write_into(target);
+            self.r_old_values[i].write_into(target);
+            self.c_old_values[i].write_into(target);
+            target.write_u64(self.s_indices[i] as u64);
+            target.write_u64(self.r_indices[i] as u64);
+            target.write(&self.s_paths[i][..]);
+            let shared = shared_path_length(&self.s_paths[i], &self.r_paths[i]);
+            target.write_u8(shared as u8);
+            target.write(&self.r_paths[i][..=self.tree_depth - shared]);
+            target.write(&self.c_paths[i][..]);
+            self.deltas[i].write_into(target);
+            self.fees[i].write_into(target);
+            match self.kinds[i] {
+                TransactionKind::Transfer => target.write_u8(0),
+                TransactionKind::Withdrawal { recipient } => {
+                    target.write_u8(1);
+                    target.write_u8_slice(&recipient);
+                }
+                TransactionKind::Deposit => target.write_u8(2),
+                TransactionKind::Split { outputs, position } => {
+                    target.write_u8(3);
+                    target.write_u8(outputs.len() as u8);
+                    target.write_u8(position as u8);
+                    for output in 0..outputs.len() {
+                        target.write_u64(outputs.receiver(output) as u64);
+                        outputs.amount(output).write_into(target);
+                    }
+                }
+                TransactionKind::NoOp => target.write_u8(4),
+                TransactionKind::Rekey { new_key } => {
+                    target.write_u8(5);
+                    target.write_u8_slice(&new_key.to_compressed());
+                }
+                TransactionKind::Refund => target.write_u8(6),
+                TransactionKind::Committee => target.write_u8(7),
+                TransactionKind::Approval => target.write_u8(8),
+            }
+            if let Some(share) = &self.shares[i] {
+                target.write_u64(share.index as u64);
+                target.write_u8_slice(&share.key.to_compressed());
+                target.write(&share.path[..]);
+            }
+            target.write(&self.signatures[i].0[..]);
+            target.write_u8_slice(&self.signatures[i].1.to_bytes());
+            match self.memos[i] {
+                Some(memo) => {
+                    target.write_u8(1);
+                    target.write_u8_slice(&memo);
+                }
+                None => target.write_u8(0),
+            }
+        }
+    }
+}
+
+impl Deserializable for TransactionMetadata {
+    /// Reads a `TransactionMetadata` object from the specified `source` reader.
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let num_transactions = source.read_u32()? as usize;
+        let tree_depth = source.read_u8()? as usize;
+        if tree_depth == 0 || tree_depth > MAX_MERKLE_TREE_DEPTH {
+            return Err(DeserializationError::InvalidValue(format!(
+                "tree depth must be between 1 and {}, but was {}",
+                MAX_MERKLE_TREE_DEPTH, tree_depth
+            )));
+        }
+        let fee_collector = source.read_u64()? as usize;
+        if fee_collector >= 1 << tree_depth {
+            return Err(DeserializationError::InvalidValue(format!(
+                "fee collector index must be smaller than 2^{}, but was {}",
+                tree_depth, fee_collector
+            )));
+        }
+        let final_root = Hash::read_from(source)?;
+        let subnet_id = BaseElement::read_from(source)?;
+        let epoch = source.read_u32()?;
+
+        // the number of transactions is untrusted, so that vectors are not preallocated
+        let mut initial_roots = Vec::new();
+        let mut s_old_values = Vec::new();
+        let mut r_old_values = Vec::new();
+        let mut c_old_values = Vec::new();
+        let mut s_indices = Vec::new();
+        let mut r_indices = Vec::new();
+        let mut s_paths = Vec::new();
+        let mut r_paths = Vec::new();
+        let mut c_paths = Vec::new();
+        let mut deltas = Vec::new();
+        let mut fees = Vec::new();
+        let mut kinds = Vec::new();
+        let mut signatures = Vec::new();
+        let mut memos = Vec::new();
+        let mut shares = Vec::new();
+        for _ in 0..num_transactions {
+            initial_roots.push(Hash::read_from(source)?);
+            s_old_values.push(AccountState::read_from(source)?);
+            r_old_values.push(AccountState::read_from(source)?);
+            c_old_values.push(AccountState::read_from(source)?);
+            s_indices.push(source.read_u64()? as usize);
+            r_indices.push(source.read_u64()? as usize);
+            let s_path = Hash::read_batch_from(source, tree_depth + 1)?;
+            let shared = source.read_u8()? as usize;
+            if shared > tree_depth {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "receiver path cannot share {} siblings in a tree of depth {}",
+                    shared, tree_depth
+                )));
+            }
+            let mut r_path = Hash::read_batch_from(source, tree_depth + 1 - shared)?;
+            r_path.extend_from_slice(&s_path[tree_depth + 1 - shared..]);
+            s_paths.push(s_path);
+            r_paths.push(r_path);
+            c_paths.push(Hash::read_batch_from(source, tree_depth + 1)?);
+            deltas.push(BaseElement::read_from(source)?);
+            fees.push(BaseElement::read_from(source)?);
+            kinds.push(match source.read_u8()? {
+                0 => TransactionKind::Transfer,
+                1 => TransactionKind::Withdrawal {
+                    recipient: source.read_u8_array::<20>()?,
+                },
+                2 => TransactionKind::Deposit,
+                3 => {
+                    let num_outputs = source.read_u8()? as usize;
+                    let position = source.read_u8()? as usize;
+                    if num_outputs == 0
+                        || num_outputs > MAX_SPLIT_OUTPUTS
+                        || position >= num_outputs
+                    {
+                        return Err(DeserializationError::InvalidValue(format!(
+                            "invalid split output {} out of {}",
+                            position, num_outputs
+                        )));
+                    }
+                    let mut outputs = Vec::with_capacity(num_outputs);
+                    for _ in 0..num_outputs {
+                        outputs
+                            .push((source.read_u64()? as usize, BaseElement::read_from(source)?));
+                    }
+                    TransactionKind::Split {
+                        outputs: SplitOutputs::new(&outputs),
+                        position,
+                    }
+                }
+                4 => TransactionKind::NoOp,
+                5 => {
+                    let bytes = source.read_u8_array::<PUBLIC_KEY_SIZE>()?;
+                    let new_key: Option<AffinePoint> = AffinePoint::from_compressed(&bytes).into();
+                    let new_key = new_key.ok_or_else(|| {
+                        DeserializationError::InvalidValue(format!(
+                            "invalid compressed public key {:02x?}",
+                            bytes
+                        ))
+                    })?;
+                    TransactionKind::Rekey { new_key }
+                }
+                6 => TransactionKind::Refund,
+                7 => TransactionKind::Committee,
+                8 => TransactionKind::Approval,
+                tag => {
+                    return Err(DeserializationError::InvalidValue(format!(
+                        "invalid transaction kind {}",
+                        tag
+                    )))
+                }
+            });
+            let member = match kinds[kinds.len() - 1] {
+                TransactionKind::Committee | TransactionKind::Approval => {
+                    let index = source.read_u64()? as usize;
+                    let bytes = source.read_u8_array::<PUBLIC_KEY_SIZE>()?;
+                    let key: Option<AffinePoint> = AffinePoint::from_compressed(&bytes).into();
+                    let key = key.ok_or_else(|| {
+                        DeserializationError::InvalidValue(format!(
+                            "invalid compressed public key {:02x?}",
+                            bytes
+                        ))
+                    })?;
+                    Some((index, key, Hash::read_batch_from(source, tree_depth + 1)?))
+                }
+                _ => None,
+            };
+
+            let mut r_x = [BaseElement::ZERO; POINT_COORDINATE_WIDTH];
+            r_x.copy_from_slice(&BaseElement::read_batch_from(
+                source,
+                POINT_COORDINATE_WIDTH,
+            )?);
+            let s = Scalar::from_bytes(&source.read_u8_array::<32>()?);
+            if bool::from(s.is_none()) {
+                return Err(DeserializationError::InvalidValue(
+                    "invalid signature scalar".into(),
+                ));
+            }
+            signatures.push((r_x, s.unwrap()));
+            shares.push(member.map(|(index, key, path)| SignatureShare {
+                index,
+                key,
+                path,
+                signature: (r_x, s.unwrap()),
+            }));
+            memos.push(match source.read_u8()? {
+                0 => None,
+                1 => Some(source.read_u8_array::<32>()?),
+                tag => {
+                    return Err(DeserializationError::InvalidValue(format!(
+                        "invalid memo flag {}",
+                        tag
+                    )))
+                }
+            });
+        }
+
+        Ok(TransactionMetadata {
+            tree_depth,
+            initial_roots,
+            final_root,
+            s_old_values,
+            r_old_values,
+            c_old_values,
             s_indices,
             r_indices,
+            fee_collector,
             s_paths,
             r_paths,
+            c_paths,
             deltas,
+            fees,
+            kinds,
             signatures,
-        )
+            memos,
+            shares,
+            subnet_id,
+            epoch,
+        })
     }
 }
 
+// The transaction fields signed by the sender, followed by the memo, the subnet identifier, the
+// epoch and the indices of the sender and receiver, must fit in the signed message, whose
+// remaining elements are left to zero
+const _: () = assert!(
+    MESSAGE_LENGTH > SUBNET_ID_MESSAGE_INDEX
+        && MESSAGE_LENGTH > EPOCH_MESSAGE_INDEX
+        && MESSAGE_LENGTH > SENDER_INDEX_MESSAGE_INDEX
+        && MESSAGE_LENGTH > RECEIVER_INDEX_MESSAGE_INDEX,
+    "signed messages must hold the transaction fields, the memo, the subnet identifier, the epoch and the leaf indices"
+);
+
+/// Returns the message signed by a transaction, `nonce` being the one of the account signing
+/// it, i.e. of its receiver for refunds, of the committee transfer they approve for approvals
+/// and of its sender otherwise, and `s_addr` the key of the member signing committee transfers
+/// and approvals.
+#[allow(clippy::too_many_arguments)]
 fn build_tx_message(
     s_addr: &[BaseElement],
     r_addr: &[BaseElement],
+    s_index: usize,
+    r_index: usize,
     amount: BaseElement,
     nonce: BaseElement,
-) -> [BaseElement; AFFINE_POINT_WIDTH * 2 + 4] {
-    let mut message = [BaseElement::ZERO; AFFINE_POINT_WIDTH * 2 + 4];
+    fee: BaseElement,
+    kind: TransactionKind,
+    memo: Option<[u8; 32]>,
+    subnet_id: BaseElement,
+    epoch: u32,
+) -> [BaseElement; MESSAGE_LENGTH] {
+    let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
 
-    message[0..AFFINE_POINT_WIDTH].copy_from_slice(s_addr);
-    message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2].copy_from_slice(r_addr);
+    match kind {
+        // Refunds are authorized by the receiver they debit, whose key takes the place of the
+        // sender's one, as the deposit key does for deposits
+        TransactionKind::Transfer => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(s_addr);
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2].copy_from_slice(r_addr);
+        }
+        TransactionKind::Withdrawal { recipient } => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(s_addr);
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2]
+                .copy_from_slice(&air::encode_recipient(&recipient));
+            message[AFFINE_POINT_WIDTH * 2 + 3] = BaseElement::ONE;
+        }
+        TransactionKind::Deposit | TransactionKind::NoOp => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(&deposit_key());
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2].copy_from_slice(r_addr);
+        }
+        TransactionKind::Split { outputs, .. } => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(s_addr);
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2]
+                .copy_from_slice(&outputs.to_elements());
+            message[AFFINE_POINT_WIDTH * 2 + 3] = BaseElement::from(2u8);
+        }
+        TransactionKind::Rekey { new_key } => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(s_addr);
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH + POINT_COORDINATE_WIDTH]
+                .copy_from_slice(&new_key.get_x());
+            message[AFFINE_POINT_WIDTH + POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH * 2]
+                .copy_from_slice(&new_key.get_y());
+            message[AFFINE_POINT_WIDTH * 2 + 3] = BaseElement::from(3u8);
+        }
+        // Refunds are authorized by the receiver they debit, whose key takes the place of the
+        // sender's one, as the deposit key does for deposits
+        TransactionKind::Refund => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(r_addr);
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2].copy_from_slice(r_addr);
+            message[AFFINE_POINT_WIDTH * 2 + 3] = BaseElement::from(4u8);
+        }
+        // Committee transfers and their approvals are signed by members of the committee,
+        // each of them with its own key in place of the sender's one, as for
+        // `schnorr::threshold::signer_message`
+        TransactionKind::Committee | TransactionKind::Approval => {
+            message[0..AFFINE_POINT_WIDTH].copy_from_slice(s_addr);
+            message[AFFINE_POINT_WIDTH..AFFINE_POINT_WIDTH * 2].copy_from_slice(r_addr);
+            message[AFFINE_POINT_WIDTH * 2 + 3] = BaseElement::from(5u8);
+        }
+    }
+    // All outputs of a split transfer share the same message, holding the sum of their
+    // amounts and the sender's nonce prior to the first output
+    let (amount, nonce) = match kind {
+        TransactionKind::Split { outputs, position } => {
+            (outputs.total(), nonce - BaseElement::from(position as u64))
+        }
+        _ => (amount, nonce),
+    };
     message[AFFINE_POINT_WIDTH * 2] = amount;
     message[AFFINE_POINT_WIDTH * 2 + 1] = nonce;
+    message[AFFINE_POINT_WIDTH * 2 + 2] = fee;
+    if let Some(memo) = memo {
+        message[AFFINE_POINT_WIDTH * 2 + 4..AFFINE_POINT_WIDTH * 2 + 4 + MEMO_WIDTH]
+            .copy_from_slice(&air::encode_memo(&memo));
+    }
+    message[SUBNET_ID_MESSAGE_INDEX] = subnet_id;
+    message[EPOCH_MESSAGE_INDEX] = BaseElement::from(epoch);
+    // The outputs of a split transfer sign the index of each of their receivers instead
+    message[SENDER_INDEX_MESSAGE_INDEX] = BaseElement::new(s_index as u64);
+    if !kind.is_split() {
+        message[RECEIVER_INDEX_MESSAGE_INDEX] = BaseElement::new(r_index as u64);
+    }
 
     message
 }
+
+/// Returns the tuple of a transaction signing `message` with amount `delta`, i.e. the keys of
+/// its sender and receiver as signed, followed by delta.
+fn transaction_tuple(
+    message: &[BaseElement; MESSAGE_LENGTH],
+    delta: BaseElement,
+) -> [BaseElement; TX_TUPLE_WIDTH] {
+    let mut tuple = [BaseElement::ZERO; TX_TUPLE_WIDTH];
+    tuple[..AFFINE_POINT_WIDTH * 2].copy_from_slice(&message[..AFFINE_POINT_WIDTH * 2]);
+    tuple[AFFINE_POINT_WIDTH * 2] = delta;
+
+    tuple
+}
+
+/// Returns the running digest of transactions `digest` updated with the provided tuple of a
+/// transaction. As within the AIR program, the digest is merged with each chunk of the tuple
+/// in turn, the last one being padded with zeros.
+fn update_transactions_digest(digest: Hash, tuple: &[BaseElement; TX_TUPLE_WIDTH]) -> Hash {
+    let mut chunks = [BaseElement::ZERO; TX_TUPLE_NUM_CHUNKS * RATE_WIDTH];
+    chunks[..TX_TUPLE_WIDTH].copy_from_slice(tuple);
+
+    chunks.chunks(RATE_WIDTH).fold(digest, |digest, chunk| {
+        let chunk = Hash::new(
+            chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6],
+        );
+        <Rescue63 as Hasher>::merge(&[digest, chunk])
+    })
+}
+
+/// Returns the number of topmost siblings of the Merkle path `other` equal to the ones of
+/// `path`, excluding the leaf both paths start with. This is the number of levels above the
+/// lowest common ancestor of their leaves for paths taken from the same tree.
+fn shared_path_length(path: &[Hash], other: &[Hash]) -> usize {
+    path[1..]
+        .iter()
+        .rev()
+        .zip(other[1..].iter().rev())
+        .take_while(|(sibling, other)| sibling == other)
+        .count()
+}
+
+/// Returns the public key signing deposits, whose secret key is one. Deposits are
+/// authorized by the public inputs rather than by their signature.
+fn deposit_key() -> [BaseElement; AFFINE_POINT_WIDTH] {
+    let generator = AffinePoint::generator();
+    let mut key = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    key[0..POINT_COORDINATE_WIDTH].copy_from_slice(&generator.get_x());
+    key[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&generator.get_y());
+
+    key
+}
+
+/// Returns the signature of `message` with the deposit key, as used by no-op transactions.
+/// The signature nonce is derived from a fixed seed, as no-ops need not hide the secret key.
+pub(crate) fn sign_noop(
+    message: [BaseElement; MESSAGE_LENGTH],
+) -> ([BaseElement; POINT_COORDINATE_WIDTH], Scalar) {
+    schnorr::sign_with_rng(
+        message,
+        &SecretKey::from(Scalar::one()),
+        &mut ChaCha20Rng::from_seed([0u8; 32]),
+    )
+}
@@ -0,0 +1,370 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::account_index;
+use crate::constants::schnorr_const::POINT_COORDINATE_WIDTH;
+use crate::merkle::constants::{
+    transaction_hash_length, AFFINE_POINT_WIDTH, HASH_CYCLE_LENGTH, HASH_RATE_WIDTH,
+    HASH_STATE_WIDTH, MAX_MERKLE_TREE_DEPTH, NODE_DOMAIN,
+};
+use crate::merkle::{hasher::domain_offset, TreeHasher};
+use crate::periodic::{PeriodicColumnSet, Segment};
+use crate::utils::rescue::{Rescue63, HASH_CYCLE_MASK};
+use crate::utils::{are_equal, boundary, is_binary, not, EvaluationResult};
+use core::marker::PhantomData;
+use winterfell::{
+    math::{curves::curve_f63::AffinePoint, fields::f63::BaseElement, FieldElement},
+    Air, AirContext, Assertion, ByteWriter, EvaluationFrame, ProofOptions, Serializable, TraceInfo,
+    TransitionConstraintDegree,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Beginning position of the hash state, computing the leaf then the root of the tree
+pub(crate) const PATH_POS: usize = 0;
+/// Position of the register holding the bits of the position of the leaf
+pub(crate) const INDEX_BIT_POS: usize = HASH_STATE_WIDTH;
+/// Position of the flag telling whether the leaf is empty
+pub(crate) const EMPTY_FLAG_POS: usize = HASH_STATE_WIDTH + 1;
+/// Beginning position of the coefficients showing that the key of the leaf differs from the
+/// queried one
+pub(crate) const KEY_DIFF_POS: usize = HASH_STATE_WIDTH + 2;
+/// Width of the execution trace
+pub(crate) const TRACE_WIDTH: usize = KEY_DIFF_POS + AFFINE_POINT_WIDTH;
+
+// Indices of the constraints beyond the ones of the registers they constrain
+const EMPTY_BINARY_RES: usize = EMPTY_FLAG_POS + 1;
+const KEY_DIFF_RES: usize = EMPTY_BINARY_RES + 1;
+
+// Indices of the periodic columns
+const SETUP_MASK_INDEX: usize = 0;
+const PATH_MASK_INDEX: usize = 1;
+const HASH_INPUT_MASK_INDEX: usize = 2;
+const HASH_MASK_INDEX: usize = 3;
+const LEAF_INPUT_MASK_INDEX: usize = 4;
+const ARK_INDEX: usize = 5;
+
+/// Returns the length of the execution trace of a query against a tree of depth
+/// `tree_depth`, which leaves at least one step free after the authentication path is
+/// hashed.
+pub(crate) const fn trace_length(tree_depth: usize) -> usize {
+    (transaction_hash_length(tree_depth) + 2).next_power_of_two()
+}
+
+// ACCOUNT QUERY
+// ================================================================================================
+
+/// A statement about the accounts of a tree, proven against its root
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountQuery {
+    /// The tree holds an account of this public key and balance, at an undisclosed leaf.
+    Member {
+        /// Public key of the account
+        pub_key: AffinePoint,
+        /// Balance of the account
+        balance: BaseElement,
+    },
+    /// The tree holds no account of this public key at the leaf derived from it by
+    /// `account_index`, the leaf being either empty or holding the account of another key.
+    NonMember {
+        /// Public key of the account
+        pub_key: AffinePoint,
+    },
+}
+
+impl AccountQuery {
+    /// Returns the public key this query is about.
+    pub fn pub_key(&self) -> AffinePoint {
+        match self {
+            AccountQuery::Member { pub_key, .. } | AccountQuery::NonMember { pub_key } => *pub_key,
+        }
+    }
+
+    /// Returns the affine coordinates of the queried public key, x coordinate first.
+    pub(crate) fn key_elements(&self) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        let pub_key = self.pub_key();
+        let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+        elements[..POINT_COORDINATE_WIDTH].copy_from_slice(&pub_key.get_x());
+        elements[POINT_COORDINATE_WIDTH..].copy_from_slice(&pub_key.get_y());
+
+        elements
+    }
+}
+
+// MEMBERSHIP AIR
+// ================================================================================================
+
+/// Public inputs of a proof answering an `AccountQuery`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputs {
+    /// Root of the queried tree
+    pub root: [BaseElement; HASH_RATE_WIDTH],
+    /// Depth of the tree
+    pub tree_depth: usize,
+    /// Identifier of the hash function of the tree nodes
+    pub hash_id: u8,
+    /// The proven statement
+    pub query: AccountQuery,
+}
+
+impl Serializable for PublicInputs {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write(&self.root[..]);
+        target.write_u8(self.tree_depth as u8);
+        target.write_u8(self.hash_id);
+        target.write(&self.query.key_elements()[..]);
+        match self.query {
+            AccountQuery::Member { balance, .. } => {
+                target.write_u8(0);
+                target.write(balance);
+            }
+            AccountQuery::NonMember { .. } => target.write_u8(1),
+        }
+    }
+}
+
+/// AIR program hashing a single authentication path, from a leaf to the root of the tree.
+///
+/// For membership queries, the leaf is the hash of an account whose key and balance are
+/// asserted to be the queried ones, its position being kept private. For non-membership
+/// queries, the position is asserted to be the one derived from the queried key, and the leaf
+/// is either empty, or the hash of an account whose key differs from the queried one in at
+/// least one coordinate, as shown by private coefficients combining their differences to one.
+pub struct MembershipAir<H: TreeHasher = Rescue63> {
+    context: AirContext<BaseElement>,
+    root: [BaseElement; HASH_RATE_WIDTH],
+    tree_depth: usize,
+    query: AccountQuery,
+    key: [BaseElement; AFFINE_POINT_WIDTH],
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> Air for MembershipAir<H> {
+    type BaseField = BaseElement;
+    type PublicInputs = PublicInputs;
+
+    // CONSTRUCTOR
+    // --------------------------------------------------------------------------------------------
+    fn new(trace_info: TraceInfo, pub_inputs: PublicInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(
+            pub_inputs.tree_depth > 0 && pub_inputs.tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        assert_eq!(
+            pub_inputs.hash_id,
+            H::HASH_ID,
+            "public inputs and AIR program must use the same tree hash function"
+        );
+        let trace_length = trace_length(pub_inputs.tree_depth);
+        assert_eq!(trace_length, trace_info.length());
+
+        let non_member = matches!(pub_inputs.query, AccountQuery::NonMember { .. });
+        let degrees = transition_constraint_degrees(trace_length, non_member);
+        MembershipAir {
+            context: AirContext::new(trace_info, degrees, options),
+            root: pub_inputs.root,
+            tree_depth: pub_inputs.tree_depth,
+            query: pub_inputs.query,
+            key: pub_inputs.query.key_elements(),
+            _hasher: PhantomData,
+        }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        debug_assert_eq!(TRACE_WIDTH, current.len());
+        debug_assert_eq!(TRACE_WIDTH, next.len());
+
+        evaluate_constraints::<H, E>(result, current, next, periodic_values);
+
+        // Enforce that a non-empty leaf holds another key than the queried one
+        if let AccountQuery::NonMember { .. } = self.query {
+            let key_diff = (0..AFFINE_POINT_WIDTH).fold(E::ZERO, |sum, i| {
+                sum + (current[PATH_POS + i] - E::from(self.key[i])) * current[KEY_DIFF_POS + i]
+            });
+            result.agg_constraint(
+                KEY_DIFF_RES,
+                periodic_values[SETUP_MASK_INDEX] * not(current[EMPTY_FLAG_POS]),
+                are_equal(key_diff, E::ONE),
+            );
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        // the authentication path resolves to the root of the queried tree
+        let root_step = transaction_hash_length(self.tree_depth);
+        let mut assertions = boundary::values_at(PATH_POS, root_step, &self.root);
+
+        match self.query {
+            AccountQuery::Member { balance, .. } => {
+                // the leaf is the hash of an account of the queried key and balance
+                assertions.extend(boundary::values_at(PATH_POS, 0, &self.key));
+                assertions.push(Assertion::single(PATH_POS + AFFINE_POINT_WIDTH, 0, balance));
+                assertions.push(boundary::count_at(EMPTY_FLAG_POS, 0, 0));
+            }
+            AccountQuery::NonMember { pub_key } => {
+                // the leaf is the one derived from the queried key, whose bits are inserted
+                // at the start of every level of the path
+                assertions.extend(boundary::bits_at(
+                    INDEX_BIT_POS,
+                    HASH_CYCLE_LENGTH,
+                    HASH_CYCLE_LENGTH,
+                    account_index(&pub_key, self.tree_depth),
+                    self.tree_depth,
+                ));
+            }
+        }
+
+        assertions
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        periodic_columns::<H>(self.tree_depth)
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+pub(crate) fn periodic_columns<H: TreeHasher>(tree_depth: usize) -> Vec<Vec<BaseElement>> {
+    let path = Segment::new(0, transaction_hash_length(tree_depth));
+    let hashes = Segment::new(0, (tree_depth + 1) * HASH_CYCLE_LENGTH);
+
+    let mut hash_input_mask = vec![BaseElement::ZERO; HASH_CYCLE_LENGTH];
+    hash_input_mask[HASH_CYCLE_LENGTH - 1] = BaseElement::ONE;
+
+    let mut layout =
+        PeriodicColumnSet::new(ARK_INDEX + HASH_STATE_WIDTH * 2, trace_length(tree_depth));
+    layout.set(SETUP_MASK_INDEX, 0, BaseElement::ONE);
+    layout.mask(PATH_MASK_INDEX, path, BaseElement::ONE);
+    layout.place(
+        HASH_INPUT_MASK_INDEX,
+        Segment::new(0, tree_depth * HASH_CYCLE_LENGTH),
+        &hash_input_mask,
+    );
+    layout.place(HASH_MASK_INDEX, hashes, &HASH_CYCLE_MASK);
+    layout.set(
+        LEAF_INPUT_MASK_INDEX,
+        HASH_CYCLE_LENGTH - 1,
+        BaseElement::ONE,
+    );
+    layout.set_columns(ARK_INDEX, H::get_round_constants());
+
+    layout.into_columns()
+}
+
+pub(crate) fn evaluate_constraints<H: TreeHasher, E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    periodic_values: &[E],
+) {
+    // split periodic values into masks and hash round constants
+    let setup_flag = periodic_values[SETUP_MASK_INDEX];
+    let path_flag = periodic_values[PATH_MASK_INDEX];
+    let hash_input_flag = periodic_values[HASH_INPUT_MASK_INDEX];
+    let hash_flag = periodic_values[HASH_MASK_INDEX];
+    let leaf_input_flag = periodic_values[LEAF_INPUT_MASK_INDEX];
+    let ark = &periodic_values[ARK_INDEX..];
+
+    // Enforce that the bits of the position of the leaf are binary
+    let bit = next[INDEX_BIT_POS];
+    result.agg_constraint(INDEX_BIT_POS, path_flag, is_binary(bit));
+    let not_bit = not(bit);
+
+    // Enforce the hashing of the leaf, then of the authentication path
+    H::enforce_round(
+        &mut result[PATH_POS..PATH_POS + HASH_STATE_WIDTH],
+        &current[PATH_POS..PATH_POS + HASH_STATE_WIDTH],
+        &next[PATH_POS..PATH_POS + HASH_STATE_WIDTH],
+        ark,
+        hash_flag,
+    );
+
+    let empty = current[EMPTY_FLAG_POS];
+    let copy_flag = path_flag * not(hash_flag + hash_input_flag);
+    for i in 0..HASH_RATE_WIDTH {
+        let digest = current[PATH_POS + i];
+        result.agg_constraint(
+            PATH_POS + i,
+            copy_flag,
+            are_equal(current[PATH_POS + i], next[PATH_POS + i]),
+        );
+
+        // the accumulated hash goes into the rate registers when the index bit is zero,
+        // and into the capacity registers otherwise, offset by the domain separator of
+        // internal nodes
+        result.agg_constraint(
+            PATH_POS + i,
+            hash_input_flag,
+            not_bit * are_equal(digest, next[PATH_POS + i]),
+        );
+        result.agg_constraint(
+            PATH_POS + HASH_RATE_WIDTH + i,
+            hash_input_flag,
+            bit * are_equal(
+                digest + domain_offset::<E>(NODE_DOMAIN, HASH_RATE_WIDTH + i),
+                next[PATH_POS + HASH_RATE_WIDTH + i],
+            ),
+        );
+
+        // The hash of an empty leaf is replaced with zero when inserted into the path. The
+        // leaf input flag is only set along with the hash input flag, so that subtracting the
+        // digest under its own flag keeps the degree of these constraints within the one of
+        // the hash rounds, instead of multiplying both flags
+        result.agg_constraint(PATH_POS + i, leaf_input_flag, -(not_bit * empty * digest));
+        result.agg_constraint(
+            PATH_POS + HASH_RATE_WIDTH + i,
+            leaf_input_flag,
+            -(bit * empty * digest),
+        );
+    }
+
+    // Enforce that the empty flag is binary, and constant along the authentication path
+    result.agg_constraint(
+        EMPTY_FLAG_POS,
+        path_flag,
+        are_equal(next[EMPTY_FLAG_POS], empty),
+    );
+    result.agg_constraint(EMPTY_BINARY_RES, setup_flag, is_binary(empty));
+}
+
+pub(crate) fn transition_constraint_degrees(
+    trace_length: usize,
+    non_member: bool,
+) -> Vec<TransitionConstraintDegree> {
+    let degree = |degree| TransitionConstraintDegree::with_cycles(degree, vec![trace_length]);
+
+    // Hash rounds, index bits, and empty flag
+    let mut degrees = vec![degree(3); HASH_STATE_WIDTH];
+    degrees.push(degree(2));
+    degrees.push(degree(1));
+    degrees.push(degree(2));
+    if non_member {
+        degrees.push(degree(3));
+    }
+    debug_assert_eq!(degrees.len(), KEY_DIFF_RES + non_member as usize);
+
+    degrees
+}
@@ -0,0 +1,102 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Proofs that an account is or is not held by a tree of accounts, against its root only,
+//! so that light clients can query the state of a subnet without downloading its tree.
+//!
+//! Membership proofs disclose the public key and balance of the account, but neither its
+//! position nor its nonce. Non-membership proofs rely on accounts being stored at the leaf
+//! derived from their key, as done by `StateTree::insert_account`: they show that this leaf
+//! holds no account of the queried key, which says nothing of the other leaves in trees
+//! filled otherwise.
+
+use crate::merkle::{StateTree, TreeHasher, TreeStore};
+use crate::utils::rescue::Rescue63;
+use winterfell::{
+    math::curves::curve_f63::AffinePoint, ProofOptions, Prover, StarkProof, VerifierError,
+};
+
+mod air;
+use air::MembershipAir;
+pub use air::{AccountQuery, PublicInputs};
+
+mod prover;
+use prover::MembershipProver;
+
+#[cfg(test)]
+mod tests;
+
+// ACCOUNT QUERY PROOFS
+// ================================================================================================
+
+/// Proves that `tree` holds the account at position `index`. The proof answers an
+/// `AccountQuery::Member` query with the key and balance of the account.
+///
+/// # Panics
+/// Panics if the tree holds no account at `index`.
+pub fn prove_membership<H: TreeHasher, S: TreeStore>(
+    options: ProofOptions,
+    tree: &StateTree<H, S>,
+    index: usize,
+) -> StarkProof {
+    let account = *tree
+        .get_account(index)
+        .expect("tree must hold an account at the provided position");
+    let query = AccountQuery::Member {
+        pub_key: account.pub_key,
+        balance: account.balance,
+    };
+    prove_query(options, tree, query, index)
+}
+
+/// Proves that `tree` holds no account of `pub_key` at the leaf derived from it, answering an
+/// `AccountQuery::NonMember` query.
+///
+/// # Panics
+/// Panics if the tree holds an account of `pub_key` at this leaf.
+pub fn prove_non_membership<H: TreeHasher, S: TreeStore>(
+    options: ProofOptions,
+    tree: &StateTree<H, S>,
+    pub_key: AffinePoint,
+) -> StarkProof {
+    let index = crate::account_index(&pub_key, tree.depth());
+    assert!(
+        !matches!(tree.get_account(index), Some(account) if account.pub_key == pub_key),
+        "tree must not hold an account of the provided key at its derived position"
+    );
+    prove_query(options, tree, AccountQuery::NonMember { pub_key }, index)
+}
+
+/// Verifies a proof answering the query of the provided public inputs against a tree hashed
+/// with Rescue63.
+pub fn verify_query(proof: StarkProof, pub_inputs: PublicInputs) -> Result<(), VerifierError> {
+    verify_query_with_hasher::<Rescue63>(proof, pub_inputs)
+}
+
+/// Verifies a proof answering the query of the provided public inputs against a tree hashed
+/// with `H`.
+pub fn verify_query_with_hasher<H: TreeHasher>(
+    proof: StarkProof,
+    pub_inputs: PublicInputs,
+) -> Result<(), VerifierError> {
+    winterfell::verify::<MembershipAir<H>>(proof, pub_inputs)
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn prove_query<H: TreeHasher, S: TreeStore>(
+    options: ProofOptions,
+    tree: &StateTree<H, S>,
+    query: AccountQuery,
+    index: usize,
+) -> StarkProof {
+    let prover = MembershipProver::<H>::new(options, tree.depth(), query);
+    let trace = prover.build_trace(index, tree.get_account(index).copied(), &tree.open(index));
+    prover.prove(trace).unwrap()
+}
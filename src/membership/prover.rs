@@ -0,0 +1,168 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::air::{
+    trace_length, AccountQuery, MembershipAir, PublicInputs, EMPTY_FLAG_POS, INDEX_BIT_POS,
+    KEY_DIFF_POS, PATH_POS, TRACE_WIDTH,
+};
+use crate::merkle::constants::{
+    transaction_hash_length, AFFINE_POINT_WIDTH, HASH_CYCLE_LENGTH, HASH_RATE_WIDTH,
+    HASH_STATE_WIDTH, LEAF_DOMAIN, NODE_DOMAIN, NUM_HASH_ROUNDS,
+};
+use crate::merkle::TreeHasher;
+use crate::utils::rescue::Hash;
+use crate::AccountState;
+use core::marker::PhantomData;
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    ProofOptions, Prover, Trace, TraceTable,
+};
+
+// MEMBERSHIP PROVER
+// ================================================================================================
+
+pub struct MembershipProver<H: TreeHasher> {
+    options: ProofOptions,
+    tree_depth: usize,
+    query: AccountQuery,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> MembershipProver<H> {
+    pub fn new(options: ProofOptions, tree_depth: usize, query: AccountQuery) -> Self {
+        Self {
+            options,
+            tree_depth,
+            query,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Builds the execution trace hashing the leaf at position `index`, holding `account` if
+    /// any, along its authentication path `path`, as returned by `StateTree::open`.
+    pub fn build_trace(
+        &self,
+        index: usize,
+        account: Option<AccountState>,
+        path: &[Hash],
+    ) -> TraceTable<BaseElement> {
+        let trace_length = trace_length(self.tree_depth);
+        let mut trace = TraceTable::new(TRACE_WIDTH, trace_length);
+        trace.fill(
+            |state| init_membership_state(&self.query, account, state),
+            |step, state| update_membership_state::<H>(step, self.tree_depth, index, path, state),
+        );
+
+        // set index bit at the second step to one, before the actual bits are inserted, and
+        // flip the empty flag and the key difference coefficients at the last step, where
+        // they are not read, so that the degree of the constraints involving these registers
+        // is stable
+        trace.set(INDEX_BIT_POS, 1, BaseElement::ONE);
+        let empty = trace.get(EMPTY_FLAG_POS, trace_length - 1);
+        trace.set(EMPTY_FLAG_POS, trace_length - 1, BaseElement::ONE - empty);
+        for i in 0..AFFINE_POINT_WIDTH {
+            let coefficient = trace.get(KEY_DIFF_POS + i, trace_length - 1);
+            trace.set(
+                KEY_DIFF_POS + i,
+                trace_length - 1,
+                BaseElement::ONE - coefficient,
+            );
+        }
+
+        trace
+    }
+}
+
+impl<H: TreeHasher> Prover for MembershipProver<H> {
+    type BaseField = BaseElement;
+    type Air = MembershipAir<H>;
+    type Trace = TraceTable<BaseElement>;
+
+    fn get_pub_inputs(&self, trace: &Self::Trace) -> PublicInputs {
+        let root_step = transaction_hash_length(self.tree_depth);
+        let mut root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+        for (i, element) in root.iter_mut().enumerate() {
+            *element = trace.get(PATH_POS + i, root_step);
+        }
+
+        PublicInputs {
+            root,
+            tree_depth: self.tree_depth,
+            hash_id: H::HASH_ID,
+            query: self.query,
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+}
+
+// TRACE INITIALIZATION
+// ================================================================================================
+
+fn init_membership_state(
+    query: &AccountQuery,
+    account: Option<AccountState>,
+    state: &mut [BaseElement],
+) {
+    state.fill(BaseElement::ZERO);
+    match account {
+        Some(account) => {
+            let elements = account.to_elements();
+            state[PATH_POS..PATH_POS + HASH_STATE_WIDTH].copy_from_slice(&elements);
+            state[PATH_POS + HASH_STATE_WIDTH - 1] += LEAF_DOMAIN;
+            // for non-membership, combine the first coordinate in which the keys differ to one
+            let key = query.key_elements();
+            if let AccountQuery::NonMember { .. } = query {
+                if let Some(i) = (0..AFFINE_POINT_WIDTH).find(|&i| elements[i] != key[i]) {
+                    state[KEY_DIFF_POS + i] = (elements[i] - key[i]).inv();
+                }
+            }
+        }
+        None => state[EMPTY_FLAG_POS] = BaseElement::ONE,
+    }
+}
+
+// TRANSITION FUNCTION
+// ================================================================================================
+
+fn update_membership_state<H: TreeHasher>(
+    step: usize,
+    tree_depth: usize,
+    index: usize,
+    path: &[Hash],
+    state: &mut [BaseElement],
+) {
+    if step >= transaction_hash_length(tree_depth) {
+        return;
+    }
+    let level = step / HASH_CYCLE_LENGTH;
+    let cycle_pos = step % HASH_CYCLE_LENGTH;
+    if cycle_pos < NUM_HASH_ROUNDS {
+        H::apply_round(&mut state[PATH_POS..PATH_POS + HASH_STATE_WIDTH], step);
+    } else {
+        // the hash of an empty leaf is replaced with zero
+        if level == 0 && state[EMPTY_FLAG_POS] == BaseElement::ONE {
+            state[PATH_POS..PATH_POS + HASH_RATE_WIDTH].fill(BaseElement::ZERO);
+        }
+        let sibling = path[level + 1].to_elements();
+        let index_bit = (index >> level) & 1;
+        if index_bit == 0 {
+            state[PATH_POS + HASH_RATE_WIDTH..PATH_POS + HASH_STATE_WIDTH]
+                .copy_from_slice(&sibling);
+        } else {
+            for i in 0..HASH_RATE_WIDTH {
+                state[PATH_POS + HASH_RATE_WIDTH + i] = state[PATH_POS + i];
+                state[PATH_POS + i] = sibling[i];
+            }
+        }
+        state[PATH_POS + HASH_STATE_WIDTH - 1] += NODE_DOMAIN;
+        state[INDEX_BIT_POS] = BaseElement::from(index_bit as u8);
+    }
+}
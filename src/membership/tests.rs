@@ -0,0 +1,193 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::air::{trace_length, transition_constraint_degrees, MembershipAir};
+use super::prover::MembershipProver;
+use super::{AccountQuery, PublicInputs};
+use crate::diagnostics::{evaluate_constraint_degrees, first_unsatisfied_constraint};
+use crate::merkle::{constants::AFFINE_POINT_WIDTH, StateTree};
+use crate::utils::rescue::Rescue63;
+use crate::{account_index, AccountState};
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    Air, FieldExtension, HashFunction, ProofOptions, Prover, Trace, TraceInfo, TraceTable,
+    TransitionConstraintDegree,
+};
+
+const TREE_DEPTH: usize = 4;
+
+/// Returns an account whose key and balance are derived from `seed`.
+fn account(seed: u64) -> AccountState {
+    let mut elements = [BaseElement::new(seed); AFFINE_POINT_WIDTH + 2];
+    elements[AFFINE_POINT_WIDTH + 1] = BaseElement::ZERO;
+    AccountState::from_elements(&elements)
+}
+
+/// Returns a tree holding accounts at the leaves derived from their keys, along with the
+/// account of a key whose leaf is left empty.
+fn tree() -> (StateTree, AccountState) {
+    let mut tree = StateTree::new(TREE_DEPTH);
+    for seed in 1..4 {
+        tree.insert_account(account(seed)).unwrap();
+    }
+    let absent = (4..)
+        .map(account)
+        .find(|account| {
+            tree.get_account(account.derived_index(TREE_DEPTH))
+                .is_none()
+        })
+        .unwrap();
+
+    (tree, absent)
+}
+
+/// Builds the trace answering `query` with the leaf at `index`, and checks it against the
+/// constraints and assertions of the AIR program, returning whether they all hold.
+fn check_query(tree: &StateTree, query: AccountQuery, index: usize) -> bool {
+    let prover = MembershipProver::<Rescue63>::new(build_options(), TREE_DEPTH, query);
+    let trace = prover.build_trace(index, tree.get_account(index).copied(), &tree.open(index));
+    let pub_inputs = prover.get_pub_inputs(&trace);
+    assert_eq!(pub_inputs.root, tree.root().to_elements());
+
+    let air = build_air(&trace, pub_inputs);
+    first_unsatisfied_constraint(&air, &trace).is_none()
+        && air.get_assertions().iter().all(|assertion| {
+            trace.get(assertion.register(), assertion.first_step()) == assertion.values()[0]
+        })
+}
+
+#[test]
+fn membership_query_traces() {
+    let (tree, absent) = tree();
+    let present = account(2);
+    let index = present.derived_index(TREE_DEPTH);
+    let member = |balance| AccountQuery::Member {
+        pub_key: present.pub_key,
+        balance,
+    };
+    assert!(check_query(&tree, member(present.balance), index));
+    assert!(!check_query(
+        &tree,
+        member(present.balance + BaseElement::ONE),
+        index
+    ));
+
+    // a key absent from the tree cannot be shown to be a member
+    let query = AccountQuery::Member {
+        pub_key: absent.pub_key,
+        balance: BaseElement::ZERO,
+    };
+    assert!(!check_query(&tree, query, index));
+    assert!(!check_query(&tree, query, absent.derived_index(TREE_DEPTH)));
+}
+
+#[test]
+fn non_membership_query_traces() {
+    let (mut tree, absent) = tree();
+    let query = AccountQuery::NonMember {
+        pub_key: absent.pub_key,
+    };
+    let index = account_index(&absent.pub_key, TREE_DEPTH);
+    assert!(check_query(&tree, query, index));
+    // the query must be answered with the leaf derived from the key
+    assert!(!check_query(&tree, query, index ^ 1));
+
+    // a leaf holding another key also shows non-membership, unlike one holding the queried key
+    tree.set_account(index, account(100));
+    assert!(check_query(&tree, query, index));
+    tree.set_account(index, absent);
+    assert!(!check_query(&tree, query, index));
+}
+
+#[test]
+fn membership_proof_verification() {
+    let (tree, absent) = tree();
+    let present = account(3);
+    let proof = super::prove_membership(build_options(), &tree, present.derived_index(TREE_DEPTH));
+    let pub_inputs = PublicInputs {
+        root: tree.root().to_elements(),
+        tree_depth: TREE_DEPTH,
+        hash_id: 0,
+        query: AccountQuery::Member {
+            pub_key: present.pub_key,
+            balance: present.balance,
+        },
+    };
+    assert!(super::verify_query(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof does not answer a query about another key
+    let pub_inputs = PublicInputs {
+        query: AccountQuery::NonMember {
+            pub_key: absent.pub_key,
+        },
+        ..pub_inputs
+    };
+    assert!(super::verify_query(proof, pub_inputs.clone()).is_err());
+
+    let proof = super::prove_non_membership(build_options(), &tree, absent.pub_key);
+    assert!(super::verify_query(proof, pub_inputs).is_ok());
+}
+
+#[test]
+fn membership_constraint_degrees() {
+    let (tree, absent) = tree();
+    let present = account(1);
+    let queries = [
+        (
+            AccountQuery::Member {
+                pub_key: present.pub_key,
+                balance: present.balance,
+            },
+            present.derived_index(TREE_DEPTH),
+        ),
+        (
+            AccountQuery::NonMember {
+                pub_key: absent.pub_key,
+            },
+            absent.derived_index(TREE_DEPTH),
+        ),
+    ];
+    for (query, index) in queries {
+        let prover = MembershipProver::<Rescue63>::new(build_options(), TREE_DEPTH, query);
+        let trace = prover.build_trace(index, tree.get_account(index).copied(), &tree.open(index));
+        let air = build_air(&trace, prover.get_pub_inputs(&trace));
+        let non_member = matches!(query, AccountQuery::NonMember { .. });
+        let mut declared = transition_constraint_degrees(trace_length(TREE_DEPTH), non_member);
+        for (declared, actual) in evaluate_constraint_degrees(&air, &declared, &trace) {
+            assert_eq!(declared, actual);
+        }
+
+        // a constraint declared with a lower degree than its actual one is reported
+        declared[0] = TransitionConstraintDegree::with_cycles(2, vec![trace_length(TREE_DEPTH)]);
+        let (declared, actual) = evaluate_constraint_degrees(&air, &declared, &trace)[0];
+        assert!(declared < actual);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn build_options() -> ProofOptions {
+    ProofOptions::new(
+        42,
+        4,
+        0,
+        HashFunction::Blake3_256,
+        FieldExtension::None,
+        4,
+        256,
+    )
+}
+
+fn build_air(trace: &TraceTable<BaseElement>, pub_inputs: PublicInputs) -> MembershipAir {
+    MembershipAir::new(
+        TraceInfo::new(trace.width(), trace.length()),
+        pub_inputs,
+        build_options(),
+    )
+}
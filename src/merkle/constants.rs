@@ -11,6 +11,17 @@ pub use crate::utils::rescue::{
     HASH_CYCLE_LENGTH, NUM_HASH_ROUNDS, RATE_WIDTH as HASH_RATE_WIDTH,
     STATE_WIDTH as HASH_STATE_WIDTH,
 };
+use winterfell::math::fields::f63::BaseElement;
+
+// DOMAIN SEPARATION OF TREE HASHES
+// ================================================================================================
+
+/// Domain separator added to the last register of the initial hash state of the leaves of the
+/// tree, i.e. to the nonce of their account; ASCII encoding of "leaf"
+pub const LEAF_DOMAIN: BaseElement = BaseElement::new(0x6c656166);
+/// Domain separator added to the last register of the initial hash state of the internal
+/// nodes of the tree, i.e. to the last element of their right child; ASCII encoding of "node"
+pub const NODE_DOMAIN: BaseElement = BaseElement::new(0x6e6f6465);
 
 // CONSTANTS USED IN MERKLE INITIALIZATION AND AUTHENTICATION
 // ================================================================================================
@@ -23,11 +34,19 @@ pub const MERKLE_TREE_DEPTH: usize = 3;
 /// The depth to a leaf in the Merkle tree
 #[cfg(not(test))]
 pub const MERKLE_TREE_DEPTH: usize = 15;
-/// The number of steps required for hashes in a transaction
-pub const TRANSACTION_HASH_LENGTH: usize = HASH_CYCLE_LENGTH * MERKLE_TREE_DEPTH + NUM_HASH_ROUNDS;
+/// The maximum depth to a leaf in the Merkle tree, such that all hashes
+/// of a transaction fit within a single transaction cycle
+pub const MAX_MERKLE_TREE_DEPTH: usize =
+    (TRANSACTION_CYCLE_LENGTH - NUM_HASH_ROUNDS) / HASH_CYCLE_LENGTH;
 /// The number of steps required for a single transaction
 pub const TRANSACTION_CYCLE_LENGTH: usize = 512;
 
+/// Returns the number of steps required for hashes in a transaction
+/// for a Merkle tree of depth `tree_depth`
+pub const fn transaction_hash_length(tree_depth: usize) -> usize {
+    HASH_CYCLE_LENGTH * tree_depth + NUM_HASH_ROUNDS
+}
+
 //  Indices of the beginning state registers for the main components
 /// Beginning position of the hash states for the sender's initial value
 pub const SENDER_INITIAL_POS: usize = 0;
@@ -41,23 +60,32 @@ pub const RECEIVER_INITIAL_POS: usize = 2 * HASH_STATE_WIDTH + 1;
 pub const RECEIVER_BIT_POS: usize = 3 * HASH_STATE_WIDTH + 1;
 /// Beginning position of the hash states for the receiver's updated value
 pub const RECEIVER_UPDATED_POS: usize = 3 * HASH_STATE_WIDTH + 2;
+/// Beginning position of the hash states for the fee collector's initial value
+pub const COLLECTOR_INITIAL_POS: usize = 4 * HASH_STATE_WIDTH + 2;
+/// Position of the state register for the fee collector index bit representation
+pub const COLLECTOR_BIT_POS: usize = 5 * HASH_STATE_WIDTH + 2;
+/// Beginning position of the hash states for the fee collector's updated value
+pub const COLLECTOR_UPDATED_POS: usize = 5 * HASH_STATE_WIDTH + 3;
 /// Beginning position of the previous tree root carrying state
-pub const PREV_TREE_ROOT_POS: usize = 4 * HASH_STATE_WIDTH + 2;
+pub const PREV_TREE_ROOT_POS: usize = 6 * HASH_STATE_WIDTH + 3;
 
 //  Indices for the constraint results for various components
 /// Beginning index of constraints for the sender's initial value
 pub const SENDER_INITIAL_RES: usize = 0;
 /// Beginning index of constraints for the receiver's initial value
 pub const RECEIVER_INITIAL_RES: usize = 2 * HASH_STATE_WIDTH + 1;
+/// Beginning index of constraints for the fee collector's initial value
+pub const COLLECTOR_INITIAL_RES: usize = 4 * HASH_STATE_WIDTH + 2;
 /// Beginning index of constraints for previous tree root carry
-pub const PREV_TREE_ROOT_RES: usize = 4 * HASH_STATE_WIDTH + 2;
+pub const PREV_TREE_ROOT_RES: usize = 6 * HASH_STATE_WIDTH + 3;
 /// Beginning index of constraints for unchanged values
 pub const VALUE_CONSTRAINT_RES: usize = TRACE_WIDTH;
 /// Index of constraint for balance update equality
-pub const BALANCE_CONSTRAINT_RES: usize = TRACE_WIDTH + AFFINE_POINT_WIDTH * 2 + 1;
+pub const BALANCE_CONSTRAINT_RES: usize = TRACE_WIDTH + AFFINE_POINT_WIDTH * 3 + 2;
 /// Index of constraint for updating the sender's nonce
 pub const NONCE_UPDATE_CONSTRAINT_RES: usize = BALANCE_CONSTRAINT_RES + 1;
-/// Beginning index of intermediate tree root equality constraints
+/// Beginning index of intermediate tree root equality constraints, between the sender
+/// and receiver updates and then between the receiver and fee collector updates
 pub const INT_ROOT_EQUALITY_RES: usize = NONCE_UPDATE_CONSTRAINT_RES + 1;
 /// Beginning index of constraints for previous tree root matching
-pub const PREV_TREE_MATCH_RES: usize = INT_ROOT_EQUALITY_RES + HASH_RATE_WIDTH;
+pub const PREV_TREE_MATCH_RES: usize = INT_ROOT_EQUALITY_RES + 2 * HASH_RATE_WIDTH;
@@ -0,0 +1,272 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::constants::{AFFINE_POINT_WIDTH, LEAF_DOMAIN, NODE_DOMAIN};
+use super::sparse::{EMPTY_SUBTREE_ROOTS, PRECOMPUTED_EMPTY_DEPTH};
+use crate::utils::rescue::{
+    self, Hash, Rescue63, RoundConstants, NUM_HASH_ROUNDS, RATE_WIDTH, STATE_WIDTH,
+};
+use core::{fmt::Debug, marker::PhantomData};
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// TREE HASHER
+// ================================================================================================
+
+/// A hash function for the nodes of the Merkle tree of accounts, usable both natively and
+/// within the AIR programs.
+///
+/// The AIR programs lay out the hash state over `rescue::STATE_WIDTH` registers, the two
+/// merged nodes filling the first and second halves of the state, and the resulting digest
+/// being read from the first half. A permutation is computed over a cycle of
+/// `rescue::HASH_CYCLE_LENGTH` steps, the first `rescue::NUM_HASH_ROUNDS` of which apply one
+/// round each, with `2 * rescue::STATE_WIDTH` round constants per step. Implementors must
+/// follow this layout, with round constraints of degree at most 3.
+///
+/// Leaves and internal nodes are hashed in distinct domains, `LEAF_DOMAIN` or `NODE_DOMAIN`
+/// being added to the last register of the initial hash state, so that the digest of a leaf
+/// is not the digest of the two halves of its value merged as nodes.
+pub trait TreeHasher: Clone + Debug + Send + Sync + 'static {
+    /// Identifier of this hash function, committed to in the public inputs of the proofs
+    const HASH_ID: u8;
+
+    /// Returns the hash of the two provided nodes.
+    fn merge(values: &[Hash; 2]) -> Hash {
+        Self::merge_in_domain(values, NODE_DOMAIN)
+    }
+
+    /// Returns the hash of the two provided values, filling the first and second halves of
+    /// the hash state, with `domain` added to the last register of the initial state.
+    fn merge_in_domain(values: &[Hash; 2], domain: BaseElement) -> Hash {
+        let mut state = [BaseElement::ZERO; STATE_WIDTH];
+        state[..RATE_WIDTH].copy_from_slice(&values[0].to_elements());
+        state[RATE_WIDTH..].copy_from_slice(&values[1].to_elements());
+        state[STATE_WIDTH - 1] += domain;
+        for step in 0..NUM_HASH_ROUNDS {
+            Self::apply_round(&mut state, step);
+        }
+
+        Hash::new(
+            state[0], state[1], state[2], state[3], state[4], state[5], state[6],
+        )
+    }
+
+    /// Returns the roots of empty subtrees, indexed by their height from 0 to `depth`.
+    /// The empty leaf is the default `Hash` (all zeros).
+    fn empty_subtree_roots(depth: usize) -> Vec<Hash> {
+        let mut roots = Vec::with_capacity(depth + 1);
+        roots.push(Hash::default());
+        for height in 0..depth {
+            roots.push(Self::merge(&[roots[height], roots[height]]));
+        }
+
+        roots
+    }
+
+    /// Applies the permutation round for the provided step of the hash cycle to `state`.
+    fn apply_round(state: &mut [BaseElement], step: usize);
+
+    /// When flag = 1, enforces constraints for a single round of the permutation, with
+    /// `ark` being the round constants of the current step.
+    fn enforce_round<E: FieldElement + From<BaseElement>>(
+        result: &mut [E],
+        current: &[E],
+        next: &[E],
+        ark: &[E],
+        flag: E,
+    );
+
+    /// Returns the round constants over a hash cycle, arranged in column-major form.
+    fn get_round_constants() -> Vec<Vec<BaseElement>>;
+}
+
+// ROUND CONSTANT SETS
+// ================================================================================================
+
+/// A set of round constants of the Rescue permutation, for deployments hashing their tree of
+/// accounts with their own instance of Rescue63 through `SeededRescue63`.
+///
+/// The constants are meant to be derived once from the seed with
+/// `rescue::generate_round_constants`, and hard-coded so that they are not derived again for
+/// every hash. `RoundConstantSet::is_derived_from_seed` lets anyone check them.
+pub trait RoundConstantSet: Clone + Debug + Send + Sync + 'static {
+    /// Identifier of Rescue63 with these constants, which must differ from the ones of other
+    /// tree hashers, e.g. 0 for Rescue63 with the default constants
+    const HASH_ID: u8;
+
+    /// Seed string the constants are derived from
+    const SEED: &'static str;
+
+    /// The round constants, as derived from `SEED`
+    const ARK: RoundConstants;
+
+    /// Returns whether `ARK` are the round constants derived from `SEED`.
+    fn is_derived_from_seed() -> bool {
+        rescue::generate_round_constants(Self::SEED) == Self::ARK
+    }
+}
+
+/// Rescue63 with the round constants of the provided set, hashing the tree of accounts of a
+/// deployment with its own constants.
+#[derive(Clone, Debug)]
+pub struct SeededRescue63<C: RoundConstantSet>(PhantomData<C>);
+
+// LEAF HASHING
+// ================================================================================================
+
+/// Returns the Merkle tree leaf of an account, given its public key coordinates followed by
+/// its balance and nonce, hashed with Rescue63 as done within the AIR programs.
+pub fn hash_leaf(value: &[BaseElement; AFFINE_POINT_WIDTH + 2]) -> Hash {
+    hash_leaf_with_hasher::<Rescue63>(value)
+}
+
+/// Returns the Merkle tree leaf of an account, given its public key coordinates followed by
+/// its balance and nonce, hashed with the provided tree hasher.
+///
+/// The account value is split into two halves, which are merged in the domain of leaves.
+pub fn hash_leaf_with_hasher<H: TreeHasher>(value: &[BaseElement; AFFINE_POINT_WIDTH + 2]) -> Hash {
+    H::merge_in_domain(
+        &[
+            Hash::new(
+                value[0], value[1], value[2], value[3], value[4], value[5], value[6],
+            ),
+            Hash::new(
+                value[7], value[8], value[9], value[10], value[11], value[12], value[13],
+            ),
+        ],
+        LEAF_DOMAIN,
+    )
+}
+
+/// Returns the offset added to the register `i` of the initial hash state by the domain
+/// separator `domain`, i.e. `domain` for the last register and zero for the others.
+pub(crate) fn domain_offset<E: FieldElement + From<BaseElement>>(
+    domain: BaseElement,
+    i: usize,
+) -> E {
+    if i == STATE_WIDTH - 1 {
+        E::from(domain)
+    } else {
+        E::ZERO
+    }
+}
+
+// PATH VERIFICATION
+// ================================================================================================
+
+/// Returns whether `path` authenticates `leaf` at position `index` in the Merkle tree of
+/// accounts with root `root`, hashed with Rescue63 as done within the AIR programs.
+///
+/// As returned by `StateTree::open`, the path starts with a leaf, which is ignored in favor
+/// of `leaf`, followed by the siblings from the bottom of the tree up to its root.
+pub fn verify_path(leaf: Hash, index: usize, path: &[Hash], root: Hash) -> bool {
+    verify_path_with_hasher::<Rescue63>(leaf, index, path, root)
+}
+
+/// Returns whether `path` authenticates `leaf` at position `index` in the Merkle tree of
+/// accounts with root `root`, hashed with the provided tree hasher.
+///
+/// This mirrors the Merkle update AIR program, which merges `leaf` with every sibling in
+/// turn, on either side depending on the corresponding bit of `index`. The path must hold
+/// one sibling per level of the tree, and `index` must fit in as many bits.
+pub fn verify_path_with_hasher<H: TreeHasher>(
+    leaf: Hash,
+    index: usize,
+    path: &[Hash],
+    root: Hash,
+) -> bool {
+    let depth = match path.len().checked_sub(1) {
+        Some(depth) => depth,
+        None => return false,
+    };
+    if index.checked_shr(depth as u32).unwrap_or(0) != 0 {
+        return false;
+    }
+
+    compute_root::<H>(index, leaf, path) == root
+}
+
+/// Returns the root of the tree holding `leaf` at position `index`, given the Merkle path
+/// of this position, whose first element (the leaf itself) is ignored.
+pub(crate) fn compute_root<H: TreeHasher>(index: usize, leaf: Hash, path: &[Hash]) -> Hash {
+    let mut node = leaf;
+    let mut index = index;
+    for sibling in path[1..].iter() {
+        node = if index & 1 == 0 {
+            H::merge(&[node, *sibling])
+        } else {
+            H::merge(&[*sibling, node])
+        };
+        index >>= 1;
+    }
+
+    node
+}
+
+// RESCUE63 IMPLEMENTATION
+// ================================================================================================
+
+impl TreeHasher for Rescue63 {
+    const HASH_ID: u8 = 0;
+
+    fn empty_subtree_roots(depth: usize) -> Vec<Hash> {
+        let mut roots = EMPTY_SUBTREE_ROOTS.to_vec();
+        // Empty subtree roots above the precomputed depth are computed on the fly
+        for height in PRECOMPUTED_EMPTY_DEPTH..depth {
+            roots.push(<Self as TreeHasher>::merge(&[roots[height], roots[height]]));
+        }
+        roots.truncate(depth + 1);
+
+        roots
+    }
+
+    fn apply_round(state: &mut [BaseElement], step: usize) {
+        rescue::apply_round(state, step)
+    }
+
+    fn enforce_round<E: FieldElement + From<BaseElement>>(
+        result: &mut [E],
+        current: &[E],
+        next: &[E],
+        ark: &[E],
+        flag: E,
+    ) {
+        rescue::enforce_round(result, current, next, ark, flag)
+    }
+
+    fn get_round_constants() -> Vec<Vec<BaseElement>> {
+        rescue::get_round_constants()
+    }
+}
+
+// SEEDED RESCUE63 IMPLEMENTATION
+// ================================================================================================
+
+impl<C: RoundConstantSet> TreeHasher for SeededRescue63<C> {
+    const HASH_ID: u8 = C::HASH_ID;
+
+    fn apply_round(state: &mut [BaseElement], step: usize) {
+        rescue::apply_round_with_constants(state, step, &C::ARK)
+    }
+
+    fn enforce_round<E: FieldElement + From<BaseElement>>(
+        result: &mut [E],
+        current: &[E],
+        next: &[E],
+        ark: &[E],
+        flag: E,
+    ) {
+        rescue::enforce_round(result, current, next, ark, flag)
+    }
+
+    fn get_round_constants() -> Vec<Vec<BaseElement>> {
+        rescue::round_constants_columns(&C::ARK)
+    }
+}
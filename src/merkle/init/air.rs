@@ -6,7 +6,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::utils::rescue;
+use crate::merkle::TreeHasher;
+use crate::utils::rescue::Rescue63;
 
 use super::constants::*;
 
@@ -80,83 +81,73 @@ impl Air for PreMerkleAir {
         // split periodic values into masks and Rescue round constants
         let ark = &periodic_values;
 
-        evaluate_constraints(result, current, next, ark, FieldElement::ONE);
+        evaluate_constraints::<Rescue63, E>(result, current, next, ark, FieldElement::ONE);
     }
 
     fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        // leaves are hashed in the domain of leaves, added to their nonce
+        let mut s_inputs = self.s_inputs;
+        s_inputs[AFFINE_POINT_WIDTH + 1] += LEAF_DOMAIN;
+        let mut r_inputs = self.r_inputs;
+        r_inputs[AFFINE_POINT_WIDTH + 1] += LEAF_DOMAIN;
+
         let mut assertions = vec![];
         //check initial sender values against public inputs
-        for i in 0..AFFINE_POINT_WIDTH + 2 {
-            assertions.push(Assertion::single(
-                SENDER_INITIAL_POS + i,
-                0,
-                self.s_inputs[i],
-            ));
+        for (i, &value) in s_inputs.iter().enumerate() {
+            assertions.push(Assertion::single(SENDER_INITIAL_POS + i, 0, value));
         }
 
         //check updated sender values against public inputs
-        for i in 0..AFFINE_POINT_WIDTH {
-            assertions.push(Assertion::single(
-                SENDER_UPDATED_POS + i,
-                0,
-                self.s_inputs[i],
-            ));
+        for (i, &value) in s_inputs[..AFFINE_POINT_WIDTH].iter().enumerate() {
+            assertions.push(Assertion::single(SENDER_UPDATED_POS + i, 0, value));
         }
         assertions.push(Assertion::single(
             SENDER_UPDATED_POS + AFFINE_POINT_WIDTH,
             0,
-            self.s_inputs[AFFINE_POINT_WIDTH] - self.delta,
+            s_inputs[AFFINE_POINT_WIDTH] - self.delta,
         ));
         assertions.push(Assertion::single(
             SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 1,
             0,
-            self.s_inputs[AFFINE_POINT_WIDTH + 1] + BaseElement::ONE,
+            s_inputs[AFFINE_POINT_WIDTH + 1] + BaseElement::ONE,
         ));
 
         //check initial receiver values against public inputs
-        for i in 0..AFFINE_POINT_WIDTH + 2 {
-            assertions.push(Assertion::single(
-                RECEIVER_INITIAL_POS + i,
-                0,
-                self.r_inputs[i],
-            ));
+        for (i, &value) in r_inputs.iter().enumerate() {
+            assertions.push(Assertion::single(RECEIVER_INITIAL_POS + i, 0, value));
         }
 
         //check updated receiver values against public inputs
-        for i in 0..AFFINE_POINT_WIDTH {
-            assertions.push(Assertion::single(
-                RECEIVER_UPDATED_POS + i,
-                0,
-                self.r_inputs[i],
-            ));
+        for (i, &value) in r_inputs[..AFFINE_POINT_WIDTH].iter().enumerate() {
+            assertions.push(Assertion::single(RECEIVER_UPDATED_POS + i, 0, value));
         }
         assertions.push(Assertion::single(
             RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH,
             0,
-            self.r_inputs[AFFINE_POINT_WIDTH] + self.delta,
+            r_inputs[AFFINE_POINT_WIDTH] + self.delta,
         ));
         assertions.push(Assertion::single(
             RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH + 1,
             0,
-            self.r_inputs[AFFINE_POINT_WIDTH + 1],
+            r_inputs[AFFINE_POINT_WIDTH + 1],
         ));
 
         assertions
     }
 
     fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
-        periodic_columns()
+        periodic_columns::<Rescue63>()
     }
 }
 
 // HELPER FUNCTIONS
 // ------------------------------------------------------------------------------------------------
 
-pub(crate) fn periodic_columns() -> Vec<Vec<BaseElement>> {
-    rescue::get_round_constants()
+pub(crate) fn periodic_columns<H: TreeHasher>() -> Vec<Vec<BaseElement>> {
+    H::get_round_constants()
 }
 
-pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
+pub(crate) fn evaluate_constraints<H: TreeHasher, E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
@@ -168,15 +159,15 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     // to ignore unused binary decompositions of the sender and receiver paths.
 
     // Always check correct evaluation of the hash round
-    rescue::enforce_round(
+    H::enforce_round(
         &mut result[SENDER_INITIAL_POS..SENDER_INITIAL_POS + HASH_STATE_WIDTH],
         &current[SENDER_INITIAL_POS..SENDER_INITIAL_POS + HASH_STATE_WIDTH],
         &next[SENDER_INITIAL_POS..SENDER_INITIAL_POS + HASH_STATE_WIDTH],
         ark,
         transaction_setup_flag,
     );
-    // Also enforce contraints for Rescue on new path
-    rescue::enforce_round(
+    // Also enforce contraints for the hash on new path
+    H::enforce_round(
         &mut result[SENDER_UPDATED_POS - 1..SENDER_UPDATED_POS - 1 + HASH_STATE_WIDTH],
         &current[SENDER_UPDATED_POS..SENDER_UPDATED_POS + HASH_STATE_WIDTH],
         &next[SENDER_UPDATED_POS..SENDER_UPDATED_POS + HASH_STATE_WIDTH],
@@ -185,14 +176,14 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     );
 
     // Repeat for the receiver
-    rescue::enforce_round(
+    H::enforce_round(
         &mut result[RECEIVER_INITIAL_POS - 1..RECEIVER_INITIAL_POS - 1 + HASH_STATE_WIDTH],
         &current[RECEIVER_INITIAL_POS..RECEIVER_INITIAL_POS + HASH_STATE_WIDTH],
         &next[RECEIVER_INITIAL_POS..RECEIVER_INITIAL_POS + HASH_STATE_WIDTH],
         ark,
         transaction_setup_flag,
     );
-    rescue::enforce_round(
+    H::enforce_round(
         &mut result[RECEIVER_UPDATED_POS - 2..RECEIVER_UPDATED_POS - 2 + HASH_STATE_WIDTH],
         &current[RECEIVER_UPDATED_POS..RECEIVER_UPDATED_POS + HASH_STATE_WIDTH],
         &next[RECEIVER_UPDATED_POS..RECEIVER_UPDATED_POS + HASH_STATE_WIDTH],
@@ -7,7 +7,7 @@
 // except according to those terms.
 
 pub(crate) use crate::merkle::constants::{
-    AFFINE_POINT_WIDTH, HASH_CYCLE_LENGTH, HASH_STATE_WIDTH, RECEIVER_INITIAL_POS,
+    AFFINE_POINT_WIDTH, HASH_CYCLE_LENGTH, HASH_STATE_WIDTH, LEAF_DOMAIN, RECEIVER_INITIAL_POS,
     RECEIVER_UPDATED_POS, SENDER_INITIAL_POS, SENDER_UPDATED_POS,
 };
 
@@ -8,9 +8,11 @@
 
 use winterfell::{
     math::{fields::f63::BaseElement, FieldElement},
-    FieldExtension, HashFunction, ProofOptions, Prover, StarkProof, VerifierError,
+    ProofOptions, Prover, StarkProof, VerifierError,
 };
 
+use crate::ProofProfile;
+
 #[cfg(feature = "std")]
 use log::debug;
 #[cfg(feature = "std")]
@@ -35,20 +37,16 @@ mod tests;
 // MERKLE TREE UPDATE EXAMPLE
 // ================================================================================================
 
-/// Outputs a new `PreMerkleExample` for proving correct hashing of leaf values
+/// Outputs a new `PreMerkleExample` for proving correct hashing of leaf values,
+/// using the `Balanced` proof profile.
 pub fn get_example() -> PreMerkleExample {
-    PreMerkleExample::new(
-        // TODO: make it customizable
-        ProofOptions::new(
-            42,
-            8,
-            0,
-            HashFunction::Blake3_256,
-            FieldExtension::None,
-            4,
-            256,
-        ),
-    )
+    get_example_with_options(ProofProfile::Balanced.to_proof_options())
+}
+
+/// Outputs a new `PreMerkleExample` for proving correct hashing of leaf values,
+/// using the provided proof options.
+pub fn get_example_with_options(options: ProofOptions) -> PreMerkleExample {
+    PreMerkleExample::new(options)
 }
 
 /// A struct to perform leaf hash validity
@@ -16,10 +16,14 @@ use crate::utils::rescue;
 
 pub(crate) fn init_merkle_initialization_state(
     state: &mut [BaseElement],
-    s_inputs: [BaseElement; AFFINE_POINT_WIDTH + 2],
-    r_inputs: [BaseElement; AFFINE_POINT_WIDTH + 2],
+    mut s_inputs: [BaseElement; AFFINE_POINT_WIDTH + 2],
+    mut r_inputs: [BaseElement; AFFINE_POINT_WIDTH + 2],
     delta: BaseElement,
 ) {
+    // Leaves are hashed in the domain of leaves, added to their nonce
+    s_inputs[AFFINE_POINT_WIDTH + 1] += LEAF_DOMAIN;
+    r_inputs[AFFINE_POINT_WIDTH + 1] += LEAF_DOMAIN;
+
     // Sender's initial state in the initial merkle tree.
     // The first AFFINE_POINT_WIDTH registers are the sender's public key...
     state[SENDER_INITIAL_POS..SENDER_INITIAL_POS + AFFINE_POINT_WIDTH]
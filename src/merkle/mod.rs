@@ -7,7 +7,29 @@
 // except according to those terms.
 
 pub(crate) mod constants;
+/// Hash functions for the nodes of the Merkle tree
+pub mod hasher;
 /// The Merkle initialization module for hashing at the leaf level
 pub mod init;
+/// A sparse Merkle tree with pre-computed empty subtrees
+pub mod sparse;
+/// The state of the accounts, maintained between proofs
+pub mod state;
+/// Storage backends of the nodes and accounts of state trees
+pub mod store;
 /// The Merkle update module for computing authentication paths
 pub mod update;
+
+pub use constants::{LEAF_DOMAIN, NODE_DOMAIN};
+pub(crate) use hasher::compute_root;
+pub use hasher::{
+    hash_leaf, hash_leaf_with_hasher, verify_path, verify_path_with_hasher, RoundConstantSet,
+    SeededRescue63, TreeHasher,
+};
+pub use state::{StateDiff, StateSnapshot, StateTree, TransactionWitness};
+#[cfg(feature = "file-store")]
+pub use store::{FileStore, FileStoreError};
+pub use store::{MemoryStore, StoreError, TreeLayout, TreeStore};
+pub use update::{
+    prove_update, prove_update_with_hasher, verify_update, verify_update_with_hasher,
+};
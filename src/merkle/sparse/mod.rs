@@ -0,0 +1,511 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::constants::MAX_MERKLE_TREE_DEPTH;
+use super::store::{MemoryStore, StoreError, TreeLayout, TreeStore};
+use super::TreeHasher;
+use crate::utils::rescue::{Hash, Rescue63};
+use core::marker::PhantomData;
+use winterfell::math::fields::f63::BaseElement;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Depth up to which the roots of empty subtrees are hard-coded
+pub const PRECOMPUTED_EMPTY_DEPTH: usize = 32;
+
+/// Roots of empty subtrees with Rescue63, indexed by their height. The empty leaf is the
+/// default `Hash` (all zeros), and each subsequent entry is the merge of two copies of the
+/// previous one, in the domain of internal nodes.
+pub const EMPTY_SUBTREE_ROOTS: [Hash; PRECOMPUTED_EMPTY_DEPTH + 1] = [
+    Hash::new(
+        BaseElement::new(0x0),
+        BaseElement::new(0x0),
+        BaseElement::new(0x0),
+        BaseElement::new(0x0),
+        BaseElement::new(0x0),
+        BaseElement::new(0x0),
+        BaseElement::new(0x0),
+    ),
+    Hash::new(
+        BaseElement::new(0x3edbc61cd31a3edd),
+        BaseElement::new(0x6ddc38f586a5238),
+        BaseElement::new(0x524d4599a6e07cf),
+        BaseElement::new(0x275d3efcdb49db73),
+        BaseElement::new(0xede5274e5b04148),
+        BaseElement::new(0x85f61ea4436d776),
+        BaseElement::new(0x29ce497723c400),
+    ),
+    Hash::new(
+        BaseElement::new(0x1dc13a8ee4df9e10),
+        BaseElement::new(0xa856bbf53b62b08),
+        BaseElement::new(0x126dd01405e01b13),
+        BaseElement::new(0x247ed1a670923d9e),
+        BaseElement::new(0x3ea80a490432aec7),
+        BaseElement::new(0x1e93dccfd3a0da39),
+        BaseElement::new(0x307376690664527e),
+    ),
+    Hash::new(
+        BaseElement::new(0x3c27a4c88b22d45),
+        BaseElement::new(0x1061c01d47d3dade),
+        BaseElement::new(0x2413d769e722ddf0),
+        BaseElement::new(0x1488603d9ce79aae),
+        BaseElement::new(0x316a802aa78e1bdd),
+        BaseElement::new(0x73eb0e4354da4f1),
+        BaseElement::new(0x326c2bb922e3b321),
+    ),
+    Hash::new(
+        BaseElement::new(0x47f5a0afd960be9),
+        BaseElement::new(0x3c12038adedf924c),
+        BaseElement::new(0x1d0db4d11008114f),
+        BaseElement::new(0x31dc3c59d08a2dd3),
+        BaseElement::new(0x24a70e018cbc12c),
+        BaseElement::new(0x2f93e6e8f9d98ce),
+        BaseElement::new(0x3e7d5e5c07737eee),
+    ),
+    Hash::new(
+        BaseElement::new(0x20af8f98e310f166),
+        BaseElement::new(0xaa5b375936db36c),
+        BaseElement::new(0x1cd858b15d2bf2e2),
+        BaseElement::new(0x328df736c896e190),
+        BaseElement::new(0x32daf0697857bd51),
+        BaseElement::new(0x38aeae15d9aceae4),
+        BaseElement::new(0xfccf383c64f9648),
+    ),
+    Hash::new(
+        BaseElement::new(0x29c05bdb28eda088),
+        BaseElement::new(0x48f71ae483619ba),
+        BaseElement::new(0xdaa1af6c66125c9),
+        BaseElement::new(0xb2bcc2a2958dc11),
+        BaseElement::new(0x38120e505435de31),
+        BaseElement::new(0x2f896ddae9f1aae9),
+        BaseElement::new(0x1f3182ee48765fb1),
+    ),
+    Hash::new(
+        BaseElement::new(0x277d1ef3084030f1),
+        BaseElement::new(0x30854b0303ff006),
+        BaseElement::new(0x6ea10f6f654e9cd),
+        BaseElement::new(0x2424479857b8caff),
+        BaseElement::new(0x3b69490185562d82),
+        BaseElement::new(0x33c831ba09e6d43),
+        BaseElement::new(0x2482f625afc1c87),
+    ),
+    Hash::new(
+        BaseElement::new(0x20e5a4e84aad4820),
+        BaseElement::new(0x257e81d98a67c2c2),
+        BaseElement::new(0x180045c9821d849f),
+        BaseElement::new(0x2ba3b0288dcc24fa),
+        BaseElement::new(0x38e55f847f988216),
+        BaseElement::new(0x36161f60c7ba57a9),
+        BaseElement::new(0x3903499e99ee2f02),
+    ),
+    Hash::new(
+        BaseElement::new(0x34fa38210945feb2),
+        BaseElement::new(0x314c765e62f7abdf),
+        BaseElement::new(0xb3dc159d7f0c584),
+        BaseElement::new(0x2e7178d0c6d1c9f),
+        BaseElement::new(0x289555ae5c37dc4a),
+        BaseElement::new(0x56f72a08f346e9a),
+        BaseElement::new(0x3a4a0b47a769856),
+    ),
+    Hash::new(
+        BaseElement::new(0xda1106948b4ab4e),
+        BaseElement::new(0xb242bd9a56ecfac),
+        BaseElement::new(0x416bb91f20722b18),
+        BaseElement::new(0x14ce6447665e6a22),
+        BaseElement::new(0x2651c97f2344ea1c),
+        BaseElement::new(0x326ce01d1a3db536),
+        BaseElement::new(0x27aa9a3430a0833a),
+    ),
+    Hash::new(
+        BaseElement::new(0x1d824898a1bfec14),
+        BaseElement::new(0x1a28a34e290dd3e1),
+        BaseElement::new(0xf47a16eada578cb),
+        BaseElement::new(0x33496fa1e40a697),
+        BaseElement::new(0x1f57791767c7238b),
+        BaseElement::new(0x40b8e86f1fd6bbf6),
+        BaseElement::new(0x1f579d4903ef2e24),
+    ),
+    Hash::new(
+        BaseElement::new(0x77b6cbf06518950),
+        BaseElement::new(0x3e74070c49216e3d),
+        BaseElement::new(0x37095ae4f3226dfa),
+        BaseElement::new(0x10fb040bb6eed005),
+        BaseElement::new(0x1f2a8b8a96eb46b1),
+        BaseElement::new(0x39222f4191e89826),
+        BaseElement::new(0x1ea12f84deb9e886),
+    ),
+    Hash::new(
+        BaseElement::new(0xdabcf3dd71e242c),
+        BaseElement::new(0x611048a537e10b5),
+        BaseElement::new(0x3b43b9b94f02d8d9),
+        BaseElement::new(0x331cdf723a470f06),
+        BaseElement::new(0xe7d053cfd6c6d79),
+        BaseElement::new(0x1aa2179e1b273c0d),
+        BaseElement::new(0x355c79b33d82d2c7),
+    ),
+    Hash::new(
+        BaseElement::new(0x185de5409d8a7082),
+        BaseElement::new(0x17c24665464c0260),
+        BaseElement::new(0x25f6b3518dea451e),
+        BaseElement::new(0x37ea53b1a9c47f33),
+        BaseElement::new(0x2cacf9822e48f310),
+        BaseElement::new(0x2982b46d172af765),
+        BaseElement::new(0x2cda364401d7bf5e),
+    ),
+    Hash::new(
+        BaseElement::new(0x576d5ef3dd1f09c),
+        BaseElement::new(0xf23a4fb39f3c845),
+        BaseElement::new(0x17295475b713dc0c),
+        BaseElement::new(0x12da9a4f0f64698),
+        BaseElement::new(0x1e68d7e992be4b23),
+        BaseElement::new(0x4f7f85ccabe0f1a),
+        BaseElement::new(0x39615a84d0425a9a),
+    ),
+    Hash::new(
+        BaseElement::new(0x12c5738682428ed6),
+        BaseElement::new(0x2fcf74ab137e6810),
+        BaseElement::new(0x38f0b4ebe5059e1b),
+        BaseElement::new(0x10b9d61505c15dcf),
+        BaseElement::new(0x10bdc96263ef51da),
+        BaseElement::new(0x3a9bb798fd1daa8e),
+        BaseElement::new(0x3867aa3faf133c21),
+    ),
+    Hash::new(
+        BaseElement::new(0x36f1821b86ef54d4),
+        BaseElement::new(0x1b58d70c5332ce92),
+        BaseElement::new(0x12b572e6e411ebab),
+        BaseElement::new(0x3d8b5a1dd54517b6),
+        BaseElement::new(0x3c937336d552deed),
+        BaseElement::new(0x2c95ed44113f9fee),
+        BaseElement::new(0x1ab13533bfede25a),
+    ),
+    Hash::new(
+        BaseElement::new(0x21be170cf4d8018b),
+        BaseElement::new(0xb17d6a1c58c20ce),
+        BaseElement::new(0xe1456a60dc4aed7),
+        BaseElement::new(0x192c019e77775265),
+        BaseElement::new(0xcebb4b859b6f1e6),
+        BaseElement::new(0x3429f28cf213ecfe),
+        BaseElement::new(0x3ed8a0c54899bc27),
+    ),
+    Hash::new(
+        BaseElement::new(0xa2292e9b00aff01),
+        BaseElement::new(0x36034901c883e184),
+        BaseElement::new(0xdca96ccc30d7fae),
+        BaseElement::new(0x1ac6de9638e190c6),
+        BaseElement::new(0x237debf62ad1de4a),
+        BaseElement::new(0xf329a444d65e912),
+        BaseElement::new(0xb99f458fa775c4a),
+    ),
+    Hash::new(
+        BaseElement::new(0x84d532988691be0),
+        BaseElement::new(0x182726d0482cf789),
+        BaseElement::new(0x151b1cef05b7a2f3),
+        BaseElement::new(0xf7f212d0ea2a65c),
+        BaseElement::new(0x185874ec299e61c3),
+        BaseElement::new(0xf8256d46fb42c73),
+        BaseElement::new(0x74d8adc8c8ae3d8),
+    ),
+    Hash::new(
+        BaseElement::new(0x2559833f61d59908),
+        BaseElement::new(0x3b5300f480be90d8),
+        BaseElement::new(0x2b5e87bc9677053b),
+        BaseElement::new(0xb627cd1f8925d66),
+        BaseElement::new(0x40904be8e7353e06),
+        BaseElement::new(0x105e124951582464),
+        BaseElement::new(0x220eea8c7d8926dd),
+    ),
+    Hash::new(
+        BaseElement::new(0x123358bffa61bd00),
+        BaseElement::new(0x774af7ad8ca3ad4),
+        BaseElement::new(0x2a093cca34e38aba),
+        BaseElement::new(0x10ffa0d665064c60),
+        BaseElement::new(0x8e6f50654d3cb0),
+        BaseElement::new(0x1d4f80d54500ec0f),
+        BaseElement::new(0x2c4d09d28cd8dd5d),
+    ),
+    Hash::new(
+        BaseElement::new(0x3b3efda1a173e37a),
+        BaseElement::new(0x17593513982bbbbb),
+        BaseElement::new(0x22fc22d3e83eff12),
+        BaseElement::new(0xce2d7866a9f3891),
+        BaseElement::new(0x2e873cbb6197f793),
+        BaseElement::new(0x2ef9017605e62e28),
+        BaseElement::new(0x304e39557ea7ccd),
+    ),
+    Hash::new(
+        BaseElement::new(0x1890e4529a20f564),
+        BaseElement::new(0x225d7d0965f4d1d2),
+        BaseElement::new(0x2bd6396570877859),
+        BaseElement::new(0x40d04fe9fef29243),
+        BaseElement::new(0x250fb3e39a39fdac),
+        BaseElement::new(0x3156546c984c8f07),
+        BaseElement::new(0x7d32bf165cbf81f),
+    ),
+    Hash::new(
+        BaseElement::new(0x60ce86506b2e2e),
+        BaseElement::new(0x40a7513a05f11e3c),
+        BaseElement::new(0x15733a962d0f9efa),
+        BaseElement::new(0x8e186737aa7b088),
+        BaseElement::new(0x346af72f63bdf601),
+        BaseElement::new(0x36838ef4dac7ec4f),
+        BaseElement::new(0x39974e10aad507e1),
+    ),
+    Hash::new(
+        BaseElement::new(0x2947c51a8dfc8584),
+        BaseElement::new(0x21f7084826cd5019),
+        BaseElement::new(0x15ae0af0dbd2e958),
+        BaseElement::new(0x71ce4dd69cc3dec),
+        BaseElement::new(0xbfe40944abd8084),
+        BaseElement::new(0x30286c95022638fc),
+        BaseElement::new(0x26a3ac4858c79dd4),
+    ),
+    Hash::new(
+        BaseElement::new(0xfcb96dda585d62b),
+        BaseElement::new(0x28a185f9997f867d),
+        BaseElement::new(0xa325c4a509b38cb),
+        BaseElement::new(0x17c536ff4e1e773b),
+        BaseElement::new(0x1b839ef7c5a255b),
+        BaseElement::new(0x151b111b9e922962),
+        BaseElement::new(0x92b8624fab87820),
+    ),
+    Hash::new(
+        BaseElement::new(0x713f117024f3ee9),
+        BaseElement::new(0xa573c7e302a2fa9),
+        BaseElement::new(0x24dd2c532657c657),
+        BaseElement::new(0x2b11d4883af38012),
+        BaseElement::new(0xe742c7318c4b5ec),
+        BaseElement::new(0x15d7eb188dcb8e9),
+        BaseElement::new(0x602a5cfe1866938),
+    ),
+    Hash::new(
+        BaseElement::new(0x18a5295ff1eafd4b),
+        BaseElement::new(0x235444446d1f89c3),
+        BaseElement::new(0x6b9060936f4cf3e),
+        BaseElement::new(0x4143c0c8aa190a5f),
+        BaseElement::new(0x2c28f7d4979d7041),
+        BaseElement::new(0x2a6cedba01d128cd),
+        BaseElement::new(0x6aa0e7227855efd),
+    ),
+    Hash::new(
+        BaseElement::new(0x3e115b68b8544c62),
+        BaseElement::new(0x11397b25ed229344),
+        BaseElement::new(0x397d8aa760444827),
+        BaseElement::new(0x2433ac0d708ac257),
+        BaseElement::new(0x13d63e47167671c3),
+        BaseElement::new(0xd974891cc7b001e),
+        BaseElement::new(0xd4da97f9d6c5430),
+    ),
+    Hash::new(
+        BaseElement::new(0x1885d510b4e4a1f2),
+        BaseElement::new(0x1016d7db4c87bba6),
+        BaseElement::new(0x25a05f0ce7d96a6a),
+        BaseElement::new(0x2fab0206cd0f16c),
+        BaseElement::new(0x29711045757e3eda),
+        BaseElement::new(0x1ed5f12f24e56a06),
+        BaseElement::new(0x1e76dd8cd7f1afe8),
+    ),
+    Hash::new(
+        BaseElement::new(0x16c17b0b34ac1c61),
+        BaseElement::new(0x153ef772d4a5df18),
+        BaseElement::new(0x1b5133e8a8cd25e5),
+        BaseElement::new(0x239fa204386f5334),
+        BaseElement::new(0x1787b0929184c80b),
+        BaseElement::new(0x25f9d212ba39ca33),
+        BaseElement::new(0x310b4976c533e82c),
+    ),
+];
+
+// SPARSE MERKLE TREE
+// ================================================================================================
+
+/// A sparse Merkle tree using `H` as its node hash function, Rescue63 by default.
+///
+/// Only the nodes lying on the path of a leaf that has been set are stored, in a `TreeStore`
+/// kept in memory by default; all other nodes are implicitly roots of empty subtrees. This
+/// allows instantiating trees of large depth (e.g. 2^32 leaves) without allocating their full
+/// set of leaves.
+#[derive(Clone, Debug)]
+pub struct SparseMerkleTree<H: TreeHasher = Rescue63, S: TreeStore = MemoryStore> {
+    depth: usize,
+    empty_roots: Vec<Hash>,
+    /// Non-empty nodes, keyed by (height above the leaves, index in that layer)
+    store: S,
+    _hasher: PhantomData<H>,
+}
+
+impl SparseMerkleTree {
+    /// Returns a new empty sparse Merkle tree of the provided depth, hashed with Rescue63.
+    ///
+    /// # Panics
+    /// Panics if `depth` is zero or greater than `MAX_MERKLE_TREE_DEPTH`.
+    pub fn new(depth: usize) -> Self {
+        Self::new_with_hasher(depth)
+    }
+}
+
+impl<H: TreeHasher> SparseMerkleTree<H> {
+    /// Returns a new empty sparse Merkle tree of the provided depth, hashed with `H`.
+    ///
+    /// # Panics
+    /// Panics if `depth` is zero or greater than `MAX_MERKLE_TREE_DEPTH`.
+    pub fn new_with_hasher(depth: usize) -> Self {
+        Self::with_store_and_hasher(depth, MemoryStore::new()).expect("empty store holds no tree")
+    }
+}
+
+impl<S: TreeStore> SparseMerkleTree<Rescue63, S> {
+    /// Returns the sparse Merkle tree of the provided depth, hashed with Rescue63, whose nodes
+    /// are held by `store`. The tree is empty if the store holds no tree yet.
+    ///
+    /// Returns an error if the store holds a tree of another depth, or hashed with another
+    /// hash function.
+    ///
+    /// # Panics
+    /// Panics if `depth` is zero or greater than `MAX_MERKLE_TREE_DEPTH`.
+    pub fn with_store(depth: usize, store: S) -> Result<Self, StoreError> {
+        Self::with_store_and_hasher(depth, store)
+    }
+}
+
+impl<H: TreeHasher, S: TreeStore> SparseMerkleTree<H, S> {
+    /// Returns the sparse Merkle tree of the provided depth, hashed with `H`, whose nodes are
+    /// held by `store`. The tree is empty if the store holds no tree yet.
+    ///
+    /// Returns an error if the store holds a tree of another depth, or hashed with another
+    /// hash function.
+    ///
+    /// # Panics
+    /// Panics if `depth` is zero or greater than `MAX_MERKLE_TREE_DEPTH`.
+    pub fn with_store_and_hasher(depth: usize, mut store: S) -> Result<Self, StoreError> {
+        assert!(
+            depth > 0 && depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        TreeLayout {
+            depth,
+            hash_id: H::HASH_ID,
+        }
+        .check_or_record(&mut store)?;
+
+        Ok(SparseMerkleTree {
+            depth,
+            empty_roots: H::empty_subtree_roots(depth),
+            store,
+            _hasher: PhantomData,
+        })
+    }
+
+    /// Returns the store holding the nodes of this tree.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns a mutable reference to the store holding the nodes of this tree.
+    pub(crate) fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Consumes this tree, and returns the store holding its nodes.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Returns the depth of this tree.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the root of this tree.
+    pub fn root(&self) -> Hash {
+        self.get_node(self.depth, 0)
+    }
+
+    /// Returns the leaf at position `index`.
+    pub fn get_leaf(&self, index: usize) -> Hash {
+        self.get_node(0, index)
+    }
+
+    /// Sets the leaf at position `index` to `value`, and updates all nodes on its path to the root.
+    ///
+    /// # Panics
+    /// Panics if `index` is not a valid leaf position.
+    pub fn update_leaf(&mut self, index: usize, value: Hash) {
+        self.check_index(index);
+        self.store.set_node(0, index, value);
+
+        let mut node = value;
+        let mut index = index;
+        for height in 0..self.depth {
+            let sibling = self.get_node(height, index ^ 1);
+            node = if index & 1 == 0 {
+                H::merge(&[node, sibling])
+            } else {
+                H::merge(&[sibling, node])
+            };
+            index >>= 1;
+            self.store.set_node(height + 1, index, node);
+        }
+    }
+
+    /// Resets the leaf at position `index` to the empty leaf, and updates all nodes on its path
+    /// to the root.
+    ///
+    /// # Panics
+    /// Panics if `index` is not a valid leaf position.
+    pub(crate) fn clear_leaf(&mut self, index: usize) {
+        self.update_leaf(index, self.empty_roots[0]);
+    }
+
+    /// Returns a Merkle path to the leaf at position `index`. Similarly to
+    /// `winterfell::crypto::MerkleTree`, the first element of the path is the
+    /// leaf itself, followed by the siblings from the leaf level up to the root.
+    ///
+    /// # Panics
+    /// Panics if `index` is not a valid leaf position.
+    pub fn prove(&self, index: usize) -> Vec<Hash> {
+        self.check_index(index);
+        let mut path = Vec::with_capacity(self.depth + 1);
+        path.push(self.get_leaf(index));
+
+        let mut index = index;
+        for height in 0..self.depth {
+            path.push(self.get_node(height, index ^ 1));
+            index >>= 1;
+        }
+
+        path
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    fn get_node(&self, height: usize, index: usize) -> Hash {
+        match self.store.get_node(height, index) {
+            Some(node) => *node,
+            None => self.empty_roots[height],
+        }
+    }
+
+    fn check_index(&self, index: usize) {
+        assert!(
+            (index as u128) < (1u128 << self.depth),
+            "leaf index {} is out of bounds for a tree of depth {}",
+            index,
+            self.depth
+        );
+    }
+}
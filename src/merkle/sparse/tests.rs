@@ -0,0 +1,183 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{SparseMerkleTree, EMPTY_SUBTREE_ROOTS, PRECOMPUTED_EMPTY_DEPTH};
+use crate::merkle::{
+    constants::{AFFINE_POINT_WIDTH, LEAF_DOMAIN},
+    hash_leaf, hash_leaf_with_hasher, verify_path, TreeHasher,
+};
+use crate::utils::rescue::{Hash, Rescue63};
+use rand_core::{OsRng, RngCore};
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+#[test]
+fn sparse_merkle_tree_empty_roots() {
+    let mut root = Hash::default();
+    for expected in EMPTY_SUBTREE_ROOTS.iter() {
+        assert_eq!(root, *expected);
+        root = Rescue63::merge(&[root, root]);
+    }
+
+    let tree = SparseMerkleTree::new(PRECOMPUTED_EMPTY_DEPTH);
+    assert_eq!(tree.root(), EMPTY_SUBTREE_ROOTS[PRECOMPUTED_EMPTY_DEPTH]);
+
+    let tree = SparseMerkleTree::new(PRECOMPUTED_EMPTY_DEPTH + 1);
+    assert_eq!(tree.root(), root);
+}
+
+#[test]
+fn sparse_merkle_tree_matches_dense_tree() {
+    let depth = 4;
+    let mut rng = OsRng;
+    let mut leaves = vec![Hash::default(); 1 << depth];
+    let mut sparse_tree = SparseMerkleTree::new(depth);
+    assert_eq!(dense_root(&leaves), sparse_tree.root());
+
+    for _ in 0..5 {
+        let index = rng.next_u64() as usize % (1 << depth);
+        let leaf = Rescue63::digest(&[BaseElement::random(&mut rng)]);
+        leaves[index] = leaf;
+        sparse_tree.update_leaf(index, leaf);
+
+        let root = dense_root(&leaves);
+        assert_eq!(root, sparse_tree.root());
+        assert!(verify_path(leaf, index, &sparse_tree.prove(index), root));
+    }
+}
+
+#[test]
+fn sparse_merkle_tree_full_depth_path() {
+    let mut rng = OsRng;
+    let mut tree = SparseMerkleTree::new(32);
+    let index = rng.next_u32() as usize;
+    let leaf = Rescue63::digest(&[BaseElement::random(&mut rng)]);
+    tree.update_leaf(index, leaf);
+    assert_eq!(tree.get_leaf(index), leaf);
+
+    let path = tree.prove(index);
+    assert_eq!(path.len(), 33);
+    let mut node = path[0];
+    for (height, sibling) in path.iter().skip(1).enumerate() {
+        node = if (index >> height) & 1 == 0 {
+            Rescue63::merge(&[node, *sibling])
+        } else {
+            Rescue63::merge(&[*sibling, node])
+        };
+    }
+    assert_eq!(node, tree.root());
+}
+
+#[test]
+fn sparse_merkle_tree_hash_leaf() {
+    let mut rng = OsRng;
+    let mut value = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
+    for element in value.iter_mut() {
+        *element = BaseElement::random(&mut rng);
+    }
+
+    let leaf = hash_leaf(&value);
+    assert_eq!(leaf, hash_leaf_with_hasher::<Rescue63>(&value));
+    assert_eq!(
+        leaf,
+        Rescue63::merge_in_domain(&value_halves(&value), LEAF_DOMAIN)
+    );
+
+    value[AFFINE_POINT_WIDTH] += BaseElement::ONE;
+    assert_ne!(leaf, hash_leaf(&value));
+}
+
+#[test]
+fn sparse_merkle_tree_path_verification() {
+    let depth = 4;
+    let mut rng = OsRng;
+    let mut tree = SparseMerkleTree::new(depth);
+    let index = rng.next_u64() as usize % (1 << depth);
+    let leaf = Rescue63::digest(&[BaseElement::random(&mut rng)]);
+    tree.update_leaf(index, leaf);
+    let path = tree.prove(index);
+    assert!(verify_path(leaf, index, &path, tree.root()));
+
+    // The leaf is read from its own argument rather than from the path
+    let other_leaf = Rescue63::digest(&[BaseElement::random(&mut rng)]);
+    assert!(!verify_path(other_leaf, index, &path, tree.root()));
+    tree.update_leaf(index, other_leaf);
+    assert!(verify_path(other_leaf, index, &path, tree.root()));
+
+    // Stale paths, wrong positions and truncated paths are rejected
+    tree.update_leaf(index ^ 1, leaf);
+    assert!(!verify_path(other_leaf, index, &path, tree.root()));
+    let path = tree.prove(index);
+    assert!(!verify_path(other_leaf, index ^ 1, &path, tree.root()));
+    assert!(!verify_path(
+        other_leaf,
+        index + (1 << depth),
+        &path,
+        tree.root()
+    ));
+    assert!(!verify_path(other_leaf, index, &path[..depth], tree.root()));
+    assert!(!verify_path(other_leaf, index, &[], tree.root()));
+}
+
+#[test]
+fn sparse_merkle_tree_leaf_not_replayed_as_node() {
+    let depth = 4;
+    let mut rng = OsRng;
+    let mut value = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
+    for element in value.iter_mut() {
+        *element = BaseElement::random(&mut rng);
+    }
+
+    // Leaves and internal nodes are hashed in distinct domains
+    let leaf = hash_leaf(&value);
+    let halves = value_halves(&value);
+    assert_ne!(leaf, Rescue63::merge(&halves));
+
+    // The halves of the value of a leaf do not authenticate as its children, one level below
+    let mut tree = SparseMerkleTree::new(depth);
+    let index = rng.next_u64() as usize % (1 << depth);
+    tree.update_leaf(index, leaf);
+    let path = tree.prove(index);
+    assert!(verify_path(leaf, index, &path, tree.root()));
+
+    let mut extended_path = vec![halves[0], halves[1]];
+    extended_path.extend_from_slice(&path[1..]);
+    assert!(!verify_path(
+        halves[0],
+        index << 1,
+        &extended_path,
+        tree.root()
+    ));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the root of the dense tree holding the provided leaves.
+fn dense_root(leaves: &[Hash]) -> Hash {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| Rescue63::merge(&[pair[0], pair[1]]))
+            .collect();
+    }
+
+    level[0]
+}
+
+/// Returns the two halves of the value of an account, hashed into its leaf.
+fn value_halves(value: &[BaseElement; AFFINE_POINT_WIDTH + 2]) -> [Hash; 2] {
+    [
+        Hash::new(
+            value[0], value[1], value[2], value[3], value[4], value[5], value[6],
+        ),
+        Hash::new(
+            value[7], value[8], value[9], value[10], value[11], value[12], value[13],
+        ),
+    ]
+}
@@ -0,0 +1,393 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::sparse::SparseMerkleTree;
+use super::store::{MemoryStore, StoreError, TreeStore};
+use super::TreeHasher;
+use crate::utils::rescue::{Hash, Rescue63};
+use crate::{AccountState, Transaction, TransactionKind};
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+// TRANSACTION WITNESS
+// ================================================================================================
+
+/// The tree state prior to a transaction, as witnessed by the state-transition AIR program
+#[derive(Clone, Debug)]
+pub struct TransactionWitness {
+    /// Root of the tree prior to the transaction
+    pub initial_root: Hash,
+    /// Sender account state prior to the transaction
+    pub s_old_value: AccountState,
+    /// Receiver account state prior to the transaction, read after the sender's update
+    pub r_old_value: AccountState,
+    /// Fee collector account state prior to the transaction
+    pub c_old_value: AccountState,
+    /// Merkle path of the sender prior to the transaction
+    pub s_path: Vec<Hash>,
+    /// Merkle path of the receiver, after the sender and receiver updates
+    pub r_path: Vec<Hash>,
+    /// Merkle path of the fee collector, after all updates
+    pub c_path: Vec<Hash>,
+}
+
+// SNAPSHOTS
+// ================================================================================================
+
+/// A past state of a `StateTree`, which the tree can be compared with or rolled back to.
+///
+/// Snapshots hold no account: the tree records the previous state of the accounts it updates
+/// for as long as it has snapshots, until `StateTree::release_snapshots` is called.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateSnapshot {
+    root: Hash,
+    generation: u64,
+    position: usize,
+    last_serial: Option<u64>,
+}
+
+impl StateSnapshot {
+    /// Returns the root of the tree when this snapshot was taken.
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+}
+
+/// The accounts of a `StateTree` changed since a `StateSnapshot`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Root of the tree when the snapshot was taken
+    pub previous_root: Hash,
+    /// Current root of the tree
+    pub root: Hash,
+    /// State of the changed accounts when the snapshot was taken, if they existed, and their
+    /// current state, keyed by their position in the tree
+    pub changes: BTreeMap<usize, (Option<AccountState>, AccountState)>,
+}
+
+/// The state of an account prior to an update, as recorded while the tree has snapshots
+#[derive(Clone, Debug)]
+struct JournalEntry {
+    serial: u64,
+    index: usize,
+    previous: Option<AccountState>,
+}
+
+// STATE TREE
+// ================================================================================================
+
+/// The state of a set of accounts, along with the sparse Merkle tree committing to it.
+///
+/// It allows maintaining the state between successive state-transition proofs. Accounts and
+/// nodes are kept in a `TreeStore`, in memory by default, or e.g. in a `FileStore` for the
+/// state to survive process restarts.
+#[derive(Clone, Debug)]
+pub struct StateTree<H: TreeHasher = Rescue63, S: TreeStore = MemoryStore> {
+    tree: SparseMerkleTree<H, S>,
+    /// Previous states of the accounts updated since the oldest snapshot, if any
+    journal: Option<Vec<JournalEntry>>,
+    next_serial: u64,
+    generation: u64,
+}
+
+impl StateTree {
+    /// Returns a new state tree of the provided depth holding no account, hashed with Rescue63.
+    pub fn new(depth: usize) -> Self {
+        Self::new_with_hasher(depth)
+    }
+}
+
+impl<H: TreeHasher> StateTree<H> {
+    /// Returns a new state tree of the provided depth holding no account, hashed with `H`.
+    pub fn new_with_hasher(depth: usize) -> Self {
+        StateTree {
+            tree: SparseMerkleTree::new_with_hasher(depth),
+            journal: None,
+            next_serial: 0,
+            generation: 0,
+        }
+    }
+}
+
+impl<S: TreeStore> StateTree<Rescue63, S> {
+    /// Returns the state tree of the provided depth, hashed with Rescue63, whose accounts and
+    /// nodes are held by `store`, e.g. as persisted by a previous process. The tree holds no
+    /// account if the store holds no tree yet.
+    ///
+    /// Returns an error if the store holds a tree of another depth, or hashed with another
+    /// hash function.
+    pub fn with_store(depth: usize, store: S) -> Result<Self, StoreError> {
+        Self::with_store_and_hasher(depth, store)
+    }
+}
+
+impl<H: TreeHasher, S: TreeStore> StateTree<H, S> {
+    /// Returns the state tree of the provided depth, hashed with `H`, whose accounts and
+    /// nodes are held by `store`, as `StateTree::with_store` does for Rescue63.
+    pub fn with_store_and_hasher(depth: usize, store: S) -> Result<Self, StoreError> {
+        Ok(StateTree {
+            tree: SparseMerkleTree::with_store_and_hasher(depth, store)?,
+            journal: None,
+            next_serial: 0,
+            generation: 0,
+        })
+    }
+
+    /// Persists the accounts and nodes updated since the previous commit to the store of this
+    /// tree. This is a no-op for trees kept in memory.
+    pub fn commit(&mut self) -> Result<(), S::Error> {
+        self.tree.store_mut().commit()
+    }
+
+    /// Returns the store holding the accounts and nodes of this tree.
+    pub fn store(&self) -> &S {
+        self.tree.store()
+    }
+
+    /// Consumes this tree, and returns the store holding its accounts and nodes.
+    pub fn into_store(self) -> S {
+        self.tree.into_store()
+    }
+
+    /// Returns the depth of this tree.
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    /// Returns the root of this tree.
+    pub fn root(&self) -> Hash {
+        self.tree.root()
+    }
+
+    /// Returns the state of the account at position `index`, if any.
+    pub fn get_account(&self, index: usize) -> Option<&AccountState> {
+        self.tree.store().get_account(index)
+    }
+
+    /// Sets the account at position `index` to `account`, and updates the tree accordingly.
+    ///
+    /// # Panics
+    /// Panics if `index` is not a valid leaf position.
+    pub fn set_account(&mut self, index: usize, account: AccountState) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push(JournalEntry {
+                serial: self.next_serial,
+                index,
+                previous: self.tree.store().get_account(index).copied(),
+            });
+            self.next_serial += 1;
+        }
+        self.tree.update_leaf(index, account.to_leaf::<H>());
+        self.tree.store_mut().set_account(index, account);
+    }
+
+    /// Sets `account` at the leaf derived from its public key by `account_index`, and returns
+    /// the index of this leaf.
+    ///
+    /// Returns `None`, leaving the tree untouched, if the leaf already holds the account of
+    /// another public key.
+    pub fn insert_account(&mut self, account: AccountState) -> Option<usize> {
+        let index = account.derived_index(self.depth());
+        match self.get_account(index) {
+            Some(existing) if existing.pub_key != account.pub_key => None,
+            _ => {
+                self.set_account(index, account);
+                Some(index)
+            }
+        }
+    }
+
+    /// Returns a snapshot of the current state of this tree, which it can later be compared
+    /// with through `diff`, or rolled back to through `rollback`, e.g. when a batch fails
+    /// validation midway through `TransactionMetadata::push_transaction`.
+    ///
+    /// The tree records the previous state of the accounts it updates from the first snapshot
+    /// onwards, and until `release_snapshots` is called.
+    pub fn snapshot(&mut self) -> StateSnapshot {
+        let journal = self.journal.get_or_insert_with(Vec::new);
+        StateSnapshot {
+            root: self.tree.root(),
+            generation: self.generation,
+            position: journal.len(),
+            last_serial: journal.last().map(|entry| entry.serial),
+        }
+    }
+
+    /// Returns the accounts changed since `snapshot` was taken, along with the roots of this
+    /// tree then and now. Accounts updated back to their state at the snapshot are omitted.
+    ///
+    /// # Panics
+    /// Panics if `snapshot` is no longer valid, i.e. if snapshots were released since it was
+    /// taken, or if the tree was rolled back to a state prior to it.
+    pub fn diff(&self, snapshot: &StateSnapshot) -> StateDiff {
+        // the first entry of each account holds its state at the snapshot
+        let mut changes = BTreeMap::new();
+        for entry in self.snapshot_entries(snapshot).iter() {
+            changes
+                .entry(entry.index)
+                .or_insert_with(|| (entry.previous, self.account(entry.index)));
+        }
+        changes.retain(|_, (previous, current)| *previous != Some(*current));
+
+        StateDiff {
+            previous_root: snapshot.root,
+            root: self.tree.root(),
+            changes,
+        }
+    }
+
+    /// Restores the accounts, and hence the root, of this tree as they were when `snapshot`
+    /// was taken. Accounts created since are removed. The snapshot remains valid, while the
+    /// ones taken after it are invalidated.
+    ///
+    /// # Panics
+    /// Panics if `snapshot` is no longer valid, i.e. if snapshots were released since it was
+    /// taken, or if the tree was rolled back to a state prior to it.
+    pub fn rollback(&mut self, snapshot: &StateSnapshot) {
+        let entries = self.snapshot_entries(snapshot).to_vec();
+        for entry in entries.into_iter().rev() {
+            match entry.previous {
+                Some(account) => {
+                    self.tree.update_leaf(entry.index, account.to_leaf::<H>());
+                    self.tree.store_mut().set_account(entry.index, account);
+                }
+                None => {
+                    self.tree.clear_leaf(entry.index);
+                    self.tree.store_mut().remove_account(entry.index);
+                }
+            }
+        }
+        if let Some(journal) = self.journal.as_mut() {
+            journal.truncate(snapshot.position);
+        }
+        debug_assert_eq!(self.tree.root(), snapshot.root, "rollback diverged");
+    }
+
+    /// Stops recording the previous state of updated accounts, and invalidates all the
+    /// snapshots of this tree.
+    pub fn release_snapshots(&mut self) {
+        self.journal = None;
+        self.generation += 1;
+    }
+
+    /// Returns the Merkle path of the leaf at position `index`, starting with the leaf itself.
+    ///
+    /// # Panics
+    /// Panics if `index` is not a valid leaf position.
+    pub fn open(&self, index: usize) -> Vec<Hash> {
+        self.tree.prove(index)
+    }
+
+    /// Applies `transaction` to this tree, with its fee credited to the account at position
+    /// `fee_collector`, and returns the state witnessed by the AIR program.
+    ///
+    /// The sender is debited and its nonce incremented (unless for deposits and no-ops), and
+    /// its key replaced for rekeyings, then the receiver is credited (unless for withdrawals),
+    /// and finally the fee collector. Refunds, of a negative amount, instead credit the sender
+    /// and debit the receiver, whose nonce is incremented as it signs them. Approvals of
+    /// committee transfers leave all accounts untouched.
+    /// The transaction is not validated, which is done by `TransactionBatchBuilder`.
+    ///
+    /// # Panics
+    /// Panics if the sender, receiver or fee collector hold no account.
+    pub fn apply_transaction(
+        &mut self,
+        transaction: &Transaction,
+        fee_collector: usize,
+    ) -> TransactionWitness {
+        let (s_index, r_index) = (transaction.sender, transaction.receiver);
+        let initial_root = self.root();
+        let s_old_value = self.account(s_index);
+        let c_old_value = self.account(fee_collector);
+        let s_path = self.open(s_index);
+
+        let approval = transaction.kind.is_approval();
+        let mut s_value = s_old_value;
+        if !transaction.kind.is_deposit() && !transaction.kind.is_noop() && !approval {
+            s_value.balance -= transaction.amount + transaction.fee;
+        }
+        if !transaction.kind.is_deposit()
+            && !transaction.kind.is_noop()
+            && !transaction.kind.is_refund()
+            && !approval
+        {
+            s_value.nonce += BaseElement::ONE;
+        }
+        if let TransactionKind::Rekey { new_key } = transaction.kind {
+            s_value.pub_key = new_key;
+        }
+        self.set_account(s_index, s_value);
+        // The receiver's account is read after the sender's update, as they coincide
+        // for withdrawals, deposits, no-ops and rekeyings
+        let r_old_value = self.account(r_index);
+        let mut r_value = r_old_value;
+        if !transaction.kind.is_withdrawal() && !approval {
+            r_value.balance += transaction.amount;
+        }
+        if transaction.kind.is_refund() {
+            r_value.nonce += BaseElement::ONE;
+        }
+        self.set_account(r_index, r_value);
+        let r_path = self.open(r_index);
+
+        let mut c_value = c_old_value;
+        if !approval {
+            c_value.balance += transaction.fee;
+        }
+        self.set_account(fee_collector, c_value);
+        let c_path = self.open(fee_collector);
+
+        TransactionWitness {
+            initial_root,
+            s_old_value,
+            r_old_value,
+            c_old_value,
+            s_path,
+            r_path,
+            c_path,
+        }
+    }
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the entries of the journal recorded since `snapshot` was taken.
+    ///
+    /// # Panics
+    /// Panics if `snapshot` is no longer valid.
+    fn snapshot_entries(&self, snapshot: &StateSnapshot) -> &[JournalEntry] {
+        let journal = match self.journal.as_ref() {
+            Some(journal) if snapshot.generation == self.generation => journal,
+            _ => panic!("snapshot was released"),
+        };
+        let last_serial = snapshot
+            .position
+            .checked_sub(1)
+            .and_then(|position| journal.get(position))
+            .map(|entry| entry.serial);
+        assert!(
+            snapshot.position <= journal.len() && last_serial == snapshot.last_serial,
+            "snapshot was invalidated by a rollback"
+        );
+
+        &journal[snapshot.position..]
+    }
+
+    /// Returns the state of the account at position `index`.
+    ///
+    /// # Panics
+    /// Panics if there is no account at position `index`.
+    fn account(&self, index: usize) -> AccountState {
+        *self
+            .get_account(index)
+            .unwrap_or_else(|| panic!("no account at position {}", index))
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{TreeLayout, TreeStore};
+use crate::utils::rescue::Hash;
+use crate::AccountState;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use winterfell::{ByteReader, ByteWriter, Deserializable, Serializable, SliceReader};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Bytes starting every store file
+const MAGIC: [u8; 4] = *b"CSTS";
+
+/// Version of the format of store files
+const VERSION: u8 = 2;
+
+/// Length of the header of store files: magic bytes, version, tree depth and hash identifier
+const HEADER_LENGTH: usize = MAGIC.len() + 3;
+
+/// Length of the prefix and of the checksum surrounding the records of a batch
+const BATCH_LENGTH_SIZE: usize = 4;
+const CHECKSUM_SIZE: usize = 4;
+
+/// Tags of the records of store files
+const NODE_RECORD: u8 = 0;
+const ACCOUNT_RECORD: u8 = 1;
+const REMOVED_ACCOUNT_RECORD: u8 = 2;
+
+// FILE STORE ERROR
+// ================================================================================================
+
+/// Errors raised when reading or writing a `FileStore`
+#[derive(Debug)]
+pub enum FileStoreError {
+    /// The store file could not be read or written
+    Io(io::Error),
+    /// The store file is not a store file, or holds invalid records
+    Corrupted(String),
+}
+
+impl fmt::Display for FileStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileStoreError::Io(err) => write!(f, "store file I/O error: {}", err),
+            FileStoreError::Corrupted(reason) => write!(f, "corrupted store file: {}", reason),
+        }
+    }
+}
+
+impl From<io::Error> for FileStoreError {
+    fn from(err: io::Error) -> Self {
+        FileStoreError::Io(err)
+    }
+}
+
+// FILE STORE
+// ================================================================================================
+
+/// A `TreeStore` persisting nodes and accounts to a file, so that a `StateTree` survives
+/// process restarts without being rebuilt from all historical transactions.
+///
+/// The whole tree is kept in memory, and the file is an append-only log: every `commit`
+/// appends the nodes and accounts updated since the previous one as a single length-prefixed
+/// batch followed by its CRC32 checksum, and flushes it to disk. Batches left incomplete or
+/// torn by a crash fail their checksum, and are discarded along with the rest of the file
+/// when the store is reopened, so that the tree is restored as of its last valid commit. As
+/// overwritten entries remain in the log, it can be rewritten with the current entries only
+/// through `compact`.
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    file: File,
+    layout: Option<TreeLayout>,
+    nodes: BTreeMap<(usize, usize), Hash>,
+    accounts: BTreeMap<usize, AccountState>,
+    dirty_nodes: BTreeSet<(usize, usize)>,
+    dirty_accounts: BTreeSet<usize>,
+    persisted_length: u64,
+}
+
+impl FileStore {
+    /// Opens the store file at `path`, creating it if it does not exist, and loads the tree
+    /// it holds. The file is truncated after its last valid batch.
+    ///
+    /// Returns an error if the file cannot be read, or if it is not a store file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let mut store = FileStore {
+            path,
+            file,
+            layout: None,
+            nodes: BTreeMap::new(),
+            accounts: BTreeMap::new(),
+            dirty_nodes: BTreeSet::new(),
+            dirty_accounts: BTreeSet::new(),
+            persisted_length: 0,
+        };
+        let valid_length = store.load(&bytes)?;
+        if valid_length < bytes.len() {
+            // discard the batches of an interrupted commit
+            store.file.set_len(valid_length as u64)?;
+            store.file.sync_data()?;
+        }
+        store.persisted_length = valid_length as u64;
+
+        Ok(store)
+    }
+
+    /// Returns the path of the store file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Commits pending updates, and rewrites the store file with the current nodes and
+    /// accounts only, discarding the overwritten ones.
+    ///
+    /// The file is replaced atomically, so that it holds either the previous log or the
+    /// compacted one should the process be interrupted. The directory holding it is synced
+    /// once it is replaced, so that the compacted log survives a crash.
+    pub fn compact(&mut self) -> Result<(), FileStoreError> {
+        let layout = match self.layout {
+            Some(layout) => layout,
+            None => return Ok(()),
+        };
+        let nodes: Vec<_> = self.nodes.keys().copied().collect();
+        let accounts: Vec<_> = self.accounts.keys().copied().collect();
+        let mut bytes = header(layout);
+        bytes.extend_from_slice(&self.batch(&nodes, &accounts));
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(&bytes)?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        sync_parent_dir(&self.path)?;
+
+        self.file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        self.persisted_length = bytes.len() as u64;
+        self.dirty_nodes.clear();
+        self.dirty_accounts.clear();
+        Ok(())
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Loads the header and batches of `bytes` into this store, and returns the length of
+    /// their valid part, i.e. up to the first batch which is incomplete or fails its
+    /// checksum.
+    fn load(&mut self, bytes: &[u8]) -> Result<usize, FileStoreError> {
+        let magic_length = bytes.len().min(MAGIC.len());
+        if bytes[..magic_length] != MAGIC[..magic_length] {
+            return Err(FileStoreError::Corrupted("not a store file".into()));
+        }
+        // a file shorter than its header was left by an interrupted first commit
+        if bytes.len() < HEADER_LENGTH {
+            return Ok(0);
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(FileStoreError::Corrupted(format!(
+                "unsupported version {}",
+                bytes[MAGIC.len()]
+            )));
+        }
+        self.layout = Some(TreeLayout {
+            depth: bytes[MAGIC.len() + 1] as usize,
+            hash_id: bytes[MAGIC.len() + 2],
+        });
+
+        let mut offset = HEADER_LENGTH;
+        while bytes.len() >= offset + BATCH_LENGTH_SIZE {
+            let mut length = [0u8; BATCH_LENGTH_SIZE];
+            length.copy_from_slice(&bytes[offset..offset + BATCH_LENGTH_SIZE]);
+            let records_end = offset + BATCH_LENGTH_SIZE + u32::from_le_bytes(length) as usize;
+            let end = records_end + CHECKSUM_SIZE;
+            if end > bytes.len() {
+                break;
+            }
+            let mut checksum = [0u8; CHECKSUM_SIZE];
+            checksum.copy_from_slice(&bytes[records_end..end]);
+            if crc32(&bytes[offset..records_end]) != u32::from_le_bytes(checksum) {
+                break;
+            }
+            self.load_batch(&bytes[offset + BATCH_LENGTH_SIZE..records_end])?;
+            offset = end;
+        }
+
+        Ok(offset)
+    }
+
+    /// Loads the records of a complete batch into this store.
+    fn load_batch(&mut self, batch: &[u8]) -> Result<(), FileStoreError> {
+        let corrupted = |err| FileStoreError::Corrupted(format!("invalid record: {}", err));
+        let mut source = SliceReader::new(batch);
+        while source.has_more_bytes() {
+            match source.read_u8().map_err(corrupted)? {
+                NODE_RECORD => {
+                    let height = source.read_u8().map_err(corrupted)? as usize;
+                    let index = source.read_u64().map_err(corrupted)? as usize;
+                    let node = Hash::read_from(&mut source).map_err(corrupted)?;
+                    self.nodes.insert((height, index), node);
+                }
+                ACCOUNT_RECORD => {
+                    let index = source.read_u64().map_err(corrupted)? as usize;
+                    let account = AccountState::read_from(&mut source).map_err(corrupted)?;
+                    self.accounts.insert(index, account);
+                }
+                REMOVED_ACCOUNT_RECORD => {
+                    let index = source.read_u64().map_err(corrupted)? as usize;
+                    self.accounts.remove(&index);
+                }
+                tag => {
+                    return Err(FileStoreError::Corrupted(format!(
+                        "unknown record tag {}",
+                        tag
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a length-prefixed batch of records of the provided nodes and accounts, the
+    /// accounts no longer held by this store being recorded as removed, followed by the
+    /// checksum of its length and records.
+    fn batch(&self, nodes: &[(usize, usize)], accounts: &[usize]) -> Vec<u8> {
+        let mut records = Vec::new();
+        for key in nodes.iter() {
+            records.write_u8(NODE_RECORD);
+            records.write_u8(key.0 as u8);
+            records.write_u64(key.1 as u64);
+            self.nodes[key].write_into(&mut records);
+        }
+        for index in accounts.iter() {
+            match self.accounts.get(index) {
+                Some(account) => {
+                    records.write_u8(ACCOUNT_RECORD);
+                    records.write_u64(*index as u64);
+                    account.write_into(&mut records);
+                }
+                None => {
+                    records.write_u8(REMOVED_ACCOUNT_RECORD);
+                    records.write_u64(*index as u64);
+                }
+            }
+        }
+
+        let mut batch = (records.len() as u32).to_le_bytes().to_vec();
+        batch.extend_from_slice(&records);
+        let checksum = crc32(&batch);
+        batch.extend_from_slice(&checksum.to_le_bytes());
+        batch
+    }
+}
+
+impl TreeStore for FileStore {
+    type Error = FileStoreError;
+
+    fn layout(&self) -> Option<TreeLayout> {
+        self.layout
+    }
+
+    fn set_layout(&mut self, layout: TreeLayout) {
+        self.layout = Some(layout);
+    }
+
+    fn get_node(&self, height: usize, index: usize) -> Option<&Hash> {
+        self.nodes.get(&(height, index))
+    }
+
+    fn set_node(&mut self, height: usize, index: usize, node: Hash) {
+        self.nodes.insert((height, index), node);
+        self.dirty_nodes.insert((height, index));
+    }
+
+    fn get_account(&self, index: usize) -> Option<&AccountState> {
+        self.accounts.get(&index)
+    }
+
+    fn set_account(&mut self, index: usize, account: AccountState) {
+        self.accounts.insert(index, account);
+        self.dirty_accounts.insert(index);
+    }
+
+    fn remove_account(&mut self, index: usize) {
+        self.accounts.remove(&index);
+        self.dirty_accounts.insert(index);
+    }
+
+    /// Appends the nodes and accounts updated since the previous commit to the store file,
+    /// preceded by the header of the file on the first commit, and flushes it to disk.
+    fn commit(&mut self) -> Result<(), FileStoreError> {
+        let mut bytes = Vec::new();
+        if self.persisted_length == 0 {
+            match self.layout {
+                Some(layout) => bytes = header(layout),
+                None => return Ok(()),
+            }
+        }
+        if !self.dirty_nodes.is_empty() || !self.dirty_accounts.is_empty() {
+            let nodes: Vec<_> = self.dirty_nodes.iter().copied().collect();
+            let accounts: Vec<_> = self.dirty_accounts.iter().copied().collect();
+            bytes.extend_from_slice(&self.batch(&nodes, &accounts));
+        }
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        self.file.seek(SeekFrom::Start(self.persisted_length))?;
+        self.file.write_all(&bytes)?;
+        self.file.sync_data()?;
+        self.persisted_length += bytes.len() as u64;
+        self.dirty_nodes.clear();
+        self.dirty_accounts.clear();
+        Ok(())
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the header of a store file holding a tree of the provided layout.
+fn header(layout: TreeLayout) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    header.push(layout.depth as u8);
+    header.push(layout.hash_id);
+    header
+}
+
+/// Returns the CRC32 checksum (IEEE polynomial, reflected) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Flushes the entry of `path` in its parent directory to disk, so that a file renamed to
+/// `path` is found there after a crash.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// Directories cannot be opened as files outside of Unix platforms, so that renames are not
+/// synced there.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,190 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::utils::rescue::Hash;
+use crate::AccountState;
+use core::{convert::Infallible, fmt};
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+#[cfg(feature = "file-store")]
+mod file;
+#[cfg(feature = "file-store")]
+pub use file::{FileStore, FileStoreError};
+
+#[cfg(test)]
+mod tests;
+
+// TREE LAYOUT
+// ================================================================================================
+
+/// The shape of the tree held by a `TreeStore`, recorded along with its nodes so that a
+/// store is never reopened with another depth or hash function than the ones it was filled
+/// with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeLayout {
+    /// Depth of the tree
+    pub depth: usize,
+    /// Identifier of the hash function of the nodes, as given by `TreeHasher::HASH_ID`
+    pub hash_id: u8,
+}
+
+/// Errors raised when opening a tree over a `TreeStore` holding another tree
+#[derive(Debug, PartialEq, Eq)]
+pub enum StoreError {
+    /// The store holds a tree of another depth
+    DepthMismatch {
+        /// Depth of the opened tree
+        expected: usize,
+        /// Depth of the tree held by the store
+        found: usize,
+    },
+    /// The store holds a tree hashed with another hash function
+    HasherMismatch {
+        /// Identifier of the hash function of the opened tree
+        expected: u8,
+        /// Identifier of the hash function of the tree held by the store
+        found: u8,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::DepthMismatch { expected, found } => write!(
+                f,
+                "store holds a tree of depth {} instead of {}",
+                found, expected
+            ),
+            StoreError::HasherMismatch { expected, found } => write!(
+                f,
+                "store holds a tree hashed with hash function {} instead of {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl TreeLayout {
+    /// Checks that this layout matches the one recorded by `store`, or records it in the store
+    /// if it holds no tree yet.
+    pub(crate) fn check_or_record<S: TreeStore>(self, store: &mut S) -> Result<(), StoreError> {
+        match store.layout() {
+            None => {
+                store.set_layout(self);
+                Ok(())
+            }
+            Some(layout) if layout.depth != self.depth => Err(StoreError::DepthMismatch {
+                expected: self.depth,
+                found: layout.depth,
+            }),
+            Some(layout) if layout.hash_id != self.hash_id => Err(StoreError::HasherMismatch {
+                expected: self.hash_id,
+                found: layout.hash_id,
+            }),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+// TREE STORE
+// ================================================================================================
+
+/// Storage of the non-empty nodes of a `SparseMerkleTree`, and of the accounts of a
+/// `StateTree`.
+///
+/// Nodes are keyed by their height above the leaves and their index within their layer.
+/// Updates may be buffered by the store until `commit` is called, but must be visible to
+/// the getters as soon as they are made.
+pub trait TreeStore {
+    /// Error raised when committing updates to the underlying storage
+    type Error: fmt::Debug;
+
+    /// Returns the layout of the tree held by this store, or `None` if it holds no tree yet.
+    fn layout(&self) -> Option<TreeLayout>;
+
+    /// Records the layout of the tree held by this store.
+    fn set_layout(&mut self, layout: TreeLayout);
+
+    /// Returns the node at position `index` of the layer at `height` above the leaves, if it
+    /// was set.
+    fn get_node(&self, height: usize, index: usize) -> Option<&Hash>;
+
+    /// Sets the node at position `index` of the layer at `height` above the leaves.
+    fn set_node(&mut self, height: usize, index: usize, node: Hash);
+
+    /// Returns the state of the account at position `index`, if any.
+    fn get_account(&self, index: usize) -> Option<&AccountState>;
+
+    /// Sets the state of the account at position `index`.
+    fn set_account(&mut self, index: usize, account: AccountState);
+
+    /// Removes the account at position `index`, if any.
+    fn remove_account(&mut self, index: usize);
+
+    /// Persists all the updates made since the previous commit.
+    fn commit(&mut self) -> Result<(), Self::Error>;
+}
+
+// MEMORY STORE
+// ================================================================================================
+
+/// A `TreeStore` keeping nodes and accounts in memory only, for trees that need not survive
+/// the process. This is the default store of `SparseMerkleTree` and `StateTree`.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    layout: Option<TreeLayout>,
+    nodes: BTreeMap<(usize, usize), Hash>,
+    accounts: BTreeMap<usize, AccountState>,
+}
+
+impl MemoryStore {
+    /// Returns a new store holding no tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TreeStore for MemoryStore {
+    type Error = Infallible;
+
+    fn layout(&self) -> Option<TreeLayout> {
+        self.layout
+    }
+
+    fn set_layout(&mut self, layout: TreeLayout) {
+        self.layout = Some(layout);
+    }
+
+    fn get_node(&self, height: usize, index: usize) -> Option<&Hash> {
+        self.nodes.get(&(height, index))
+    }
+
+    fn set_node(&mut self, height: usize, index: usize, node: Hash) {
+        self.nodes.insert((height, index), node);
+    }
+
+    fn get_account(&self, index: usize) -> Option<&AccountState> {
+        self.accounts.get(&index)
+    }
+
+    fn set_account(&mut self, index: usize, account: AccountState) {
+        self.accounts.insert(index, account);
+    }
+
+    fn remove_account(&mut self, index: usize) {
+        self.accounts.remove(&index);
+    }
+
+    fn commit(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{MemoryStore, StoreError, TreeLayout, TreeStore};
+use crate::merkle::{constants::AFFINE_POINT_WIDTH, StateTree};
+use crate::AccountState;
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+/// Returns an account whose key and balance are derived from `seed`.
+fn account(seed: u64) -> AccountState {
+    let mut elements = [BaseElement::new(seed); AFFINE_POINT_WIDTH + 2];
+    elements[AFFINE_POINT_WIDTH + 1] = BaseElement::ZERO;
+    AccountState::from_elements(&elements)
+}
+
+#[test]
+fn memory_store_state_tree() {
+    let mut state = StateTree::new(4);
+    let mut stored_state = StateTree::with_store(4, MemoryStore::new()).unwrap();
+    for &index in [0, 3, 9].iter() {
+        state.set_account(index, account(index as u64));
+        stored_state.set_account(index, account(index as u64));
+    }
+    stored_state.commit().unwrap();
+    assert_eq!(stored_state.root(), state.root());
+
+    // the store can be reopened with the same layout only
+    let store = stored_state.into_store();
+    assert_eq!(
+        store.layout(),
+        Some(TreeLayout {
+            depth: 4,
+            hash_id: 0
+        })
+    );
+    let reopened = StateTree::with_store(4, store.clone()).unwrap();
+    assert_eq!(reopened.root(), state.root());
+    assert_eq!(reopened.get_account(9), Some(&account(9)));
+    assert_eq!(reopened.get_account(1), None);
+    assert_eq!(
+        StateTree::with_store(5, store.clone()).unwrap_err(),
+        StoreError::DepthMismatch {
+            expected: 5,
+            found: 4
+        }
+    );
+    assert_eq!(
+        TreeLayout {
+            depth: 4,
+            hash_id: 0x80
+        }
+        .check_or_record(&mut store.clone()),
+        Err(StoreError::HasherMismatch {
+            expected: 0x80,
+            found: 0
+        })
+    );
+}
+
+#[cfg(feature = "file-store")]
+#[test]
+fn file_store_state_tree() {
+    use super::{FileStore, FileStoreError};
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join(format!("state-tree-{}.store", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    for index in 0..16 {
+        state.set_account(index * 7, account(index as u64));
+    }
+    state.commit().unwrap();
+    // updates are persisted once committed only
+    state.set_account(1, account(42));
+    state.commit().unwrap();
+    let root = state.root();
+    state.set_account(2, account(43));
+    drop(state);
+
+    let state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(state.root(), root);
+    assert_eq!(state.get_account(1), Some(&account(42)));
+    assert_eq!(state.get_account(2), None);
+    assert!(StateTree::with_store(4, state.into_store()).is_err());
+
+    // the batch of an interrupted commit is discarded
+    let length = fs::metadata(&path).unwrap().len();
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[100, 0, 0, 0, 1, 2, 3]).unwrap();
+    drop(file);
+    let state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().len(), length);
+    assert_eq!(state.root(), root);
+    drop(state);
+
+    // so is a complete batch failing its checksum, along with the rest of the file
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[3, 0, 0, 0, 1, 2, 3, 0, 0, 0, 0]).unwrap();
+    file.write_all(&[100, 0, 0, 0]).unwrap();
+    drop(file);
+    let mut state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().len(), length);
+    assert_eq!(state.root(), root);
+
+    // a torn last batch rolls the tree back to the previous commit
+    state.set_account(3, account(46));
+    state.commit().unwrap();
+    drop(state);
+    let mut bytes = fs::read(&path).unwrap();
+    let last_byte = bytes.len() - 1;
+    bytes[last_byte] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+    let mut state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().len(), length);
+    assert_eq!(state.root(), root);
+    assert_eq!(state.get_account(3), None);
+
+    // compaction drops overwritten entries
+    for _ in 0..4 {
+        state.set_account(1, account(44));
+        state.commit().unwrap();
+    }
+    let root = state.root();
+    let mut store = state.into_store();
+    let length = fs::metadata(&path).unwrap().len();
+    store.compact().unwrap();
+    assert!(fs::metadata(&path).unwrap().len() < length);
+    drop(store);
+    let state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(state.root(), root);
+    assert_eq!(state.get_account(1), Some(&account(44)));
+
+    // rolled back accounts are persisted as removed
+    let mut state = state;
+    let snapshot = state.snapshot();
+    state.set_account(2, account(45));
+    state.commit().unwrap();
+    state.rollback(&snapshot);
+    state.commit().unwrap();
+    drop(state);
+    let state = StateTree::with_store(8, FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(state.root(), root);
+    assert_eq!(state.get_account(2), None);
+    drop(state);
+
+    fs::write(&path, b"not a store file").unwrap();
+    assert!(matches!(
+        FileStore::open(&path),
+        Err(FileStoreError::Corrupted(_))
+    ));
+    fs::remove_file(&path).unwrap();
+}
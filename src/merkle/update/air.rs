@@ -7,8 +7,10 @@
 // except according to those terms.
 
 use super::constants::*;
-use crate::utils::rescue::{self, HASH_CYCLE_MASK};
-use crate::utils::{are_equal, is_binary, not, EvaluationResult};
+use crate::merkle::{hasher::domain_offset, TreeHasher};
+use crate::utils::rescue::{Rescue63, HASH_CYCLE_MASK};
+use crate::utils::{are_equal, boundary, is_binary, not, EvaluationResult};
+use core::marker::PhantomData;
 use winterfell::{
     math::{fields::f63::BaseElement, FieldElement},
     Air, AirContext, Assertion, ByteWriter, EvaluationFrame, ProofOptions, Serializable, TraceInfo,
@@ -21,25 +23,37 @@ use alloc::vec::Vec;
 // MERKLE PATH VERIFICATION AIR
 // ================================================================================================
 
+/// Public inputs of a proof of update of a sparse Merkle tree
+#[derive(Clone, Debug)]
 pub struct PublicInputs {
+    /// Root of the tree prior to the update
     pub initial_root: [BaseElement; HASH_RATE_WIDTH],
+    /// Root of the tree after the update
     pub final_root: [BaseElement; HASH_RATE_WIDTH],
+    /// Depth of the tree
+    pub tree_depth: usize,
+    /// Identifier of the hash function of the tree nodes
+    pub hash_id: u8,
 }
 
 impl Serializable for PublicInputs {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
         target.write(&self.initial_root[..]);
         target.write(&self.final_root[..]);
+        target.write_u8(self.tree_depth as u8);
+        target.write_u8(self.hash_id);
     }
 }
 
-pub struct MerkleAir {
+pub struct MerkleAir<H: TreeHasher = Rescue63> {
     context: AirContext<BaseElement>,
     initial_root: [BaseElement; HASH_RATE_WIDTH],
     final_root: [BaseElement; HASH_RATE_WIDTH],
+    tree_depth: usize,
+    _hasher: PhantomData<H>,
 }
 
-impl Air for MerkleAir {
+impl<H: TreeHasher> Air for MerkleAir<H> {
     type BaseField = BaseElement;
     type PublicInputs = PublicInputs;
 
@@ -49,10 +63,22 @@ impl Air for MerkleAir {
         let degrees = transition_constraint_degrees(TRANSACTION_CYCLE_LENGTH);
 
         assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(
+            pub_inputs.tree_depth > 0 && pub_inputs.tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
+        );
+        assert_eq!(
+            pub_inputs.hash_id,
+            H::HASH_ID,
+            "public inputs and AIR program must use the same tree hash function"
+        );
         MerkleAir {
             context: AirContext::new(trace_info, degrees, options),
             initial_root: pub_inputs.initial_root,
             final_root: pub_inputs.final_root,
+            tree_depth: pub_inputs.tree_depth,
+            _hasher: PhantomData,
         }
     }
 
@@ -68,11 +94,11 @@ impl Air for MerkleAir {
     ) {
         let current = frame.current();
         let next = frame.next();
-        // expected state width is 4 hashes and 2 bit decompositions
+        // expected state width is 6 hashes and 3 bit decompositions
         debug_assert_eq!(TRACE_WIDTH, current.len());
         debug_assert_eq!(TRACE_WIDTH, next.len());
 
-        // split periodic values into masks and Rescue round constants
+        // split periodic values into masks and hash round constants
         let transaction_setup_flag = periodic_values[0];
         let transaction_hash_flag = periodic_values[1];
         let hash_input_flag = periodic_values[2];
@@ -111,7 +137,28 @@ impl Air for MerkleAir {
             ),
         );
 
-        // Enforce that the change in balances cancels out
+        // Enforce no change in the fee collector's key and nonce
+        for i in 0..AFFINE_POINT_WIDTH {
+            result.agg_constraint(
+                VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 2 + 1 + i,
+                transaction_setup_flag,
+                are_equal(
+                    current[COLLECTOR_INITIAL_POS + i],
+                    current[COLLECTOR_UPDATED_POS + i],
+                ),
+            );
+        }
+        result.agg_constraint(
+            VALUE_CONSTRAINT_RES + AFFINE_POINT_WIDTH * 3 + 1,
+            transaction_setup_flag,
+            are_equal(
+                current[COLLECTOR_INITIAL_POS + AFFINE_POINT_WIDTH + 1],
+                current[COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH + 1],
+            ),
+        );
+
+        // Enforce that the change in balances cancels out, the sender paying
+        // for both the amount sent to the receiver and the fee
         result.agg_constraint(
             BALANCE_CONSTRAINT_RES,
             transaction_setup_flag,
@@ -119,7 +166,9 @@ impl Air for MerkleAir {
                 current[SENDER_INITIAL_POS + AFFINE_POINT_WIDTH]
                     - current[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH],
                 current[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH]
-                    - current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH],
+                    - current[RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH]
+                    + current[COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH]
+                    - current[COLLECTOR_INITIAL_POS + AFFINE_POINT_WIDTH],
             ),
         );
 
@@ -133,7 +182,7 @@ impl Air for MerkleAir {
             ),
         );
 
-        evaluate_constraints(
+        evaluate_constraints::<H, E>(
             result,
             current,
             next,
@@ -150,51 +199,43 @@ impl Air for MerkleAir {
         // registers are reset to ZERO every 8 steps
         // Now we must also resolve to the new root and the next registers are also
         // hash capacity registers
-        // Additionally, we repeat all of this for the receiver
+        // Additionally, we repeat all of this for the receiver and the fee collector
         let last_step = self.trace_length() - 1;
-        let mut vec = Vec::with_capacity(HASH_STATE_WIDTH);
-        for i in 0..HASH_RATE_WIDTH {
-            vec.push(Assertion::single(
-                PREV_TREE_ROOT_POS + i,
-                0,
-                self.initial_root[i],
-            ));
-        }
-        for i in 0..HASH_RATE_WIDTH {
-            vec.push(Assertion::single(
-                PREV_TREE_ROOT_POS + i,
-                last_step,
-                self.final_root[i],
-            ));
-        }
+        let mut vec = boundary::values_at(PREV_TREE_ROOT_POS, 0, &self.initial_root);
+        vec.extend(boundary::values_at(
+            PREV_TREE_ROOT_POS,
+            last_step,
+            &self.final_root,
+        ));
 
         vec
     }
 
     fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
-        periodic_columns()
+        periodic_columns::<H>(self.tree_depth)
     }
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
 
-pub(crate) fn periodic_columns() -> Vec<Vec<BaseElement>> {
+pub(crate) fn periodic_columns<H: TreeHasher>(tree_depth: usize) -> Vec<Vec<BaseElement>> {
+    let transaction_hash_length = transaction_hash_length(tree_depth);
     // Mask for anything that must be applied at the beginning of a transaction
     let mut transaction_setup_mask = vec![BaseElement::ZERO; TRANSACTION_CYCLE_LENGTH];
     transaction_setup_mask[0] = BaseElement::ONE;
     // Mask for indicating hashes are still being computs
-    let mut transaction_hash_mask = vec![BaseElement::ONE; TRANSACTION_HASH_LENGTH];
+    let mut transaction_hash_mask = vec![BaseElement::ONE; transaction_hash_length];
     transaction_hash_mask.append(&mut vec![
         BaseElement::ZERO;
-        TRANSACTION_CYCLE_LENGTH - TRANSACTION_HASH_LENGTH
+        TRANSACTION_CYCLE_LENGTH - transaction_hash_length
     ]);
     // Mask for any setup between levels of the Merkle tree
     let mut hash_input_mask = vec![BaseElement::ZERO; HASH_CYCLE_LENGTH];
     hash_input_mask[HASH_CYCLE_LENGTH - 1] = BaseElement::ONE;
     // Mask for anything that must be applied at the end of the hashes
     let mut transaction_finish_hash_mask = vec![BaseElement::ZERO; TRANSACTION_CYCLE_LENGTH];
-    transaction_finish_hash_mask[TRANSACTION_HASH_LENGTH - 1] = BaseElement::ONE;
+    transaction_finish_hash_mask[transaction_hash_length - 1] = BaseElement::ONE;
     // Mask for when to apply hash rounds
     let mut hash_mask = Vec::new();
     for i in 0..TRANSACTION_CYCLE_LENGTH {
@@ -207,12 +248,12 @@ pub(crate) fn periodic_columns() -> Vec<Vec<BaseElement>> {
         transaction_finish_hash_mask,
     ];
     result.append(&mut vec![hash_mask]);
-    result.append(&mut rescue::get_round_constants());
+    result.append(&mut H::get_round_constants());
     result
 }
 
 #[allow(clippy::too_many_arguments)]
-pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
+pub(crate) fn evaluate_constraints<H: TreeHasher, E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
@@ -227,7 +268,7 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     let not_transaction_finish_flag = not(transaction_finish_flag);
 
     // Enforce the proper computation of hashes along the Merkle authentication peth
-    evaluate_merkle_update_auth(
+    evaluate_merkle_update_auth::<H, E>(
         &mut result[SENDER_INITIAL_RES..RECEIVER_INITIAL_RES],
         &current[SENDER_INITIAL_POS..RECEIVER_INITIAL_POS],
         &next[SENDER_INITIAL_POS..RECEIVER_INITIAL_POS],
@@ -236,10 +277,19 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
         hash_input_flag,
         hash_flag,
     );
-    evaluate_merkle_update_auth(
-        &mut result[RECEIVER_INITIAL_RES..PREV_TREE_ROOT_RES],
-        &current[RECEIVER_INITIAL_POS..PREV_TREE_ROOT_POS],
-        &next[RECEIVER_INITIAL_POS..PREV_TREE_ROOT_POS],
+    evaluate_merkle_update_auth::<H, E>(
+        &mut result[RECEIVER_INITIAL_RES..COLLECTOR_INITIAL_RES],
+        &current[RECEIVER_INITIAL_POS..COLLECTOR_INITIAL_POS],
+        &next[RECEIVER_INITIAL_POS..COLLECTOR_INITIAL_POS],
+        ark,
+        transaction_hash_flag,
+        hash_input_flag,
+        hash_flag,
+    );
+    evaluate_merkle_update_auth::<H, E>(
+        &mut result[COLLECTOR_INITIAL_RES..PREV_TREE_ROOT_RES],
+        &current[COLLECTOR_INITIAL_POS..PREV_TREE_ROOT_POS],
+        &next[COLLECTOR_INITIAL_POS..PREV_TREE_ROOT_POS],
         ark,
         transaction_hash_flag,
         hash_input_flag,
@@ -259,11 +309,15 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
         result.agg_constraint(
             PREV_TREE_ROOT_RES + i,
             transaction_finish_flag,
-            are_equal(next[PREV_TREE_ROOT_POS + i], next[RECEIVER_UPDATED_POS + i]),
+            are_equal(
+                next[PREV_TREE_ROOT_POS + i],
+                next[COLLECTOR_UPDATED_POS + i],
+            ),
         );
     }
 
-    // Enforce equality of the intermediate hash for continuity between sender and receiver updates
+    // Enforce equality of the intermediate hashes for continuity between sender, receiver
+    // and fee collector updates
     for i in 0..HASH_RATE_WIDTH {
         result.agg_constraint(
             INT_ROOT_EQUALITY_RES + i,
@@ -273,9 +327,17 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
                 current[RECEIVER_INITIAL_POS + i],
             ),
         );
+        result.agg_constraint(
+            INT_ROOT_EQUALITY_RES + HASH_RATE_WIDTH + i,
+            transaction_finish_flag,
+            are_equal(
+                current[RECEIVER_UPDATED_POS + i],
+                current[COLLECTOR_INITIAL_POS + i],
+            ),
+        );
     }
 
-    // Enforce a match between the previous root after recevier update and the current root before sender update
+    // Enforce a match between the previous root after fee collector update and the current root before sender update
     for i in 0..HASH_RATE_WIDTH {
         result.agg_constraint(
             PREV_TREE_MATCH_RES + i,
@@ -288,7 +350,7 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     }
 }
 
-pub(crate) fn evaluate_merkle_update_auth<E: FieldElement + From<BaseElement>>(
+pub(crate) fn evaluate_merkle_update_auth<H: TreeHasher, E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
@@ -312,8 +374,8 @@ pub(crate) fn evaluate_merkle_update_auth<E: FieldElement + From<BaseElement>>(
 
     // Perform these steps for each of the hash register clusters
     for (res_index, reg_index) in [(0, 0), (HASH_STATE_WIDTH + 1, HASH_STATE_WIDTH + 1)] {
-        // When transaction_hash_flag = hash_flag = 1, constraints for Rescue round are enforced
-        rescue::enforce_round(
+        // When transaction_hash_flag = hash_flag = 1, constraints for the hash round are enforced
+        H::enforce_round(
             &mut result[res_index..res_index + HASH_STATE_WIDTH],
             &current[reg_index..reg_index + HASH_STATE_WIDTH],
             &next[reg_index..reg_index + HASH_STATE_WIDTH],
@@ -331,7 +393,8 @@ pub(crate) fn evaluate_merkle_update_auth<E: FieldElement + From<BaseElement>>(
 
             // When hash_flag = 0, make sure accumulated hash is placed in the right place in the hash
             // state for the next round of hashing. Specifically: when index bit = 0, accumulated hash
-            // must go into the capacity registers, and when index bit = 1, it must go into the rate registers
+            // must go into the capacity registers, and when index bit = 1, it must go into the rate registers,
+            // offset by the domain separator of internal nodes
             result.agg_constraint(
                 res_index + i,
                 hash_init_flag,
@@ -342,7 +405,7 @@ pub(crate) fn evaluate_merkle_update_auth<E: FieldElement + From<BaseElement>>(
                 res_index + HASH_RATE_WIDTH + i,
                 hash_init_flag,
                 bit * are_equal(
-                    current[reg_index + i],
+                    current[reg_index + i] + domain_offset::<E>(NODE_DOMAIN, HASH_RATE_WIDTH + i),
                     next[reg_index + HASH_RATE_WIDTH + i],
                 ),
             );
@@ -368,10 +431,54 @@ pub(crate) fn evaluate_merkle_update_auth<E: FieldElement + From<BaseElement>>(
     }
 }
 
+/// Enforces the hashes along a single authentication path, laid out as one hash cluster
+/// followed by the register of the bits of the index, as the first cluster of an update.
+pub(crate) fn evaluate_merkle_path_auth<H: TreeHasher, E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    ark: &[E],
+    transaction_hash_flag: E,
+    hash_input_flag: E,
+    hash_flag: E,
+) {
+    let hash_copy_flag = transaction_hash_flag * not(hash_flag + hash_input_flag);
+    let hash_init_flag = transaction_hash_flag * hash_input_flag;
+
+    let bit = next[HASH_STATE_WIDTH];
+    result.agg_constraint(HASH_STATE_WIDTH, transaction_hash_flag, is_binary(bit));
+    let not_bit = not(bit);
+
+    H::enforce_round(
+        &mut result[..HASH_STATE_WIDTH],
+        &current[..HASH_STATE_WIDTH],
+        &next[..HASH_STATE_WIDTH],
+        ark,
+        hash_flag,
+    );
+
+    for i in 0..HASH_RATE_WIDTH {
+        result.agg_constraint(i, hash_copy_flag, are_equal(current[i], next[i]));
+
+        // The accumulated hash stays in the rate registers when the bit is zero, and moves
+        // to the capacity registers, offset by the domain separator of internal nodes, when
+        // it is one
+        result.agg_constraint(i, hash_init_flag, not_bit * are_equal(current[i], next[i]));
+        result.agg_constraint(
+            HASH_RATE_WIDTH + i,
+            hash_init_flag,
+            bit * are_equal(
+                current[i] + domain_offset::<E>(NODE_DOMAIN, HASH_RATE_WIDTH + i),
+                next[HASH_RATE_WIDTH + i],
+            ),
+        );
+    }
+}
+
 pub(crate) fn transition_constraint_degrees(
     cycle_length: usize,
 ) -> Vec<TransitionConstraintDegree> {
-    // Constraint degrees for enforcement of Rescue hash rounds
+    // Constraint degrees for enforcement of hash rounds
     let mut hash_constraint_degrees =
         vec![TransitionConstraintDegree::with_cycles(3, vec![cycle_length]); HASH_STATE_WIDTH];
 
@@ -394,6 +501,7 @@ pub(crate) fn transition_constraint_degrees(
 
     // Degrees for all constraints
     let mut degrees = update_auth_degrees.clone();
+    degrees.append(&mut update_auth_degrees.clone());
     degrees.append(&mut update_auth_degrees);
     degrees.append(&mut remaining_degrees);
 
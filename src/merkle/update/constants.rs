@@ -9,11 +9,14 @@
 // CONSTANTS
 // ================================================================================================
 
+pub(crate) use crate::merkle::constants::transaction_hash_length;
 pub(crate) use crate::merkle::constants::{
-    AFFINE_POINT_WIDTH, BALANCE_CONSTRAINT_RES, HASH_CYCLE_LENGTH, HASH_RATE_WIDTH,
-    HASH_STATE_WIDTH, INT_ROOT_EQUALITY_RES, MERKLE_TREE_DEPTH, NONCE_UPDATE_CONSTRAINT_RES,
-    NUM_HASH_ROUNDS, PREV_TREE_MATCH_RES, PREV_TREE_ROOT_POS, PREV_TREE_ROOT_RES, RECEIVER_BIT_POS,
-    RECEIVER_INITIAL_POS, RECEIVER_INITIAL_RES, RECEIVER_UPDATED_POS, SENDER_BIT_POS,
-    SENDER_INITIAL_POS, SENDER_INITIAL_RES, SENDER_UPDATED_POS, TRACE_WIDTH,
-    TRANSACTION_CYCLE_LENGTH, TRANSACTION_HASH_LENGTH, VALUE_CONSTRAINT_RES,
+    AFFINE_POINT_WIDTH, BALANCE_CONSTRAINT_RES, COLLECTOR_BIT_POS, COLLECTOR_INITIAL_POS,
+    COLLECTOR_INITIAL_RES, COLLECTOR_UPDATED_POS, HASH_CYCLE_LENGTH, HASH_RATE_WIDTH,
+    HASH_STATE_WIDTH, INT_ROOT_EQUALITY_RES, LEAF_DOMAIN, MAX_MERKLE_TREE_DEPTH, MERKLE_TREE_DEPTH,
+    NODE_DOMAIN, NONCE_UPDATE_CONSTRAINT_RES, NUM_HASH_ROUNDS, PREV_TREE_MATCH_RES,
+    PREV_TREE_ROOT_POS, PREV_TREE_ROOT_RES, RECEIVER_BIT_POS, RECEIVER_INITIAL_POS,
+    RECEIVER_INITIAL_RES, RECEIVER_UPDATED_POS, SENDER_BIT_POS, SENDER_INITIAL_POS,
+    SENDER_INITIAL_RES, SENDER_UPDATED_POS, TRACE_WIDTH, TRANSACTION_CYCLE_LENGTH,
+    VALUE_CONSTRAINT_RES,
 };
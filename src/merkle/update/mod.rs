@@ -6,8 +6,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::TransactionMetadata;
-use winterfell::{FieldExtension, HashFunction, ProofOptions, Prover, StarkProof, VerifierError};
+use super::TreeHasher;
+use crate::utils::rescue::Rescue63;
+use crate::{ProofProfile, TransactionMetadata};
+use winterfell::{ProofOptions, Prover, StarkProof, VerifierError};
 
 #[cfg(feature = "std")]
 use log::debug;
@@ -17,18 +19,25 @@ use std::time::Instant;
 use winterfell::{math::log2, Trace};
 
 pub(crate) mod constants;
-use constants::MERKLE_TREE_DEPTH;
+use constants::{MAX_MERKLE_TREE_DEPTH, MERKLE_TREE_DEPTH};
 
 mod trace;
 
-pub(crate) use trace::{init_merkle_update_state, update_merkle_update_state};
+pub(crate) use trace::{
+    init_merkle_update_state, update_merkle_path_state, update_merkle_update_auth_state,
+    update_merkle_update_state,
+};
 mod air;
 
 mod prover;
 use prover::MerkleProver;
 
-pub(crate) use air::{evaluate_constraints, periodic_columns, transition_constraint_degrees};
-use air::{MerkleAir, PublicInputs};
+use air::MerkleAir;
+pub use air::PublicInputs;
+pub(crate) use air::{
+    evaluate_constraints, evaluate_merkle_path_auth, periodic_columns,
+    transition_constraint_degrees,
+};
 
 #[cfg(test)]
 mod tests;
@@ -36,21 +45,19 @@ mod tests;
 // MERKLE TREE MULTIPLE TRANSACTIONS EXAMPLE
 // ================================================================================================
 
-/// Outputs a new `TransactionExample` with `num_transactions` random transactions.
+/// Outputs a new `TransactionExample` with `num_transactions` random transactions,
+/// using the `Balanced` proof profile.
 pub fn get_example(num_transactions: usize) -> TransactionExample {
-    TransactionExample::new(
-        // TODO: make it customizable
-        ProofOptions::new(
-            42,
-            8,
-            0,
-            HashFunction::Blake3_256,
-            FieldExtension::None,
-            4,
-            256,
-        ),
-        num_transactions,
-    )
+    get_example_with_options(ProofProfile::Balanced.to_proof_options(), num_transactions)
+}
+
+/// Outputs a new `TransactionExample` with `num_transactions` random transactions,
+/// using the provided proof options.
+pub fn get_example_with_options(
+    options: ProofOptions,
+    num_transactions: usize,
+) -> TransactionExample {
+    TransactionExample::new(options, num_transactions)
 }
 
 /// A struct to perform authentication paths validity
@@ -64,12 +71,24 @@ pub struct TransactionExample {
 impl TransactionExample {
     /// Outputs a new `TransactionExample` with `num_transactions` random transactions.
     pub fn new(options: ProofOptions, num_transactions: usize) -> TransactionExample {
+        Self::new_with_tree_depth(options, num_transactions, MERKLE_TREE_DEPTH)
+    }
+
+    /// Outputs a new `TransactionExample` with `num_transactions` random transactions
+    /// in a Merkle tree of depth `tree_depth`.
+    pub fn new_with_tree_depth(
+        options: ProofOptions,
+        num_transactions: usize,
+        tree_depth: usize,
+    ) -> TransactionExample {
         assert!(
-            (MERKLE_TREE_DEPTH + 1).is_power_of_two(),
-            "tree depth must be one less than a power of 2"
+            tree_depth > 0 && tree_depth <= MAX_MERKLE_TREE_DEPTH,
+            "tree depth must be between 1 and {}",
+            MAX_MERKLE_TREE_DEPTH
         );
         // Create a Merkle tree for which we know all of the values
-        let tx_metadata = TransactionMetadata::build_random(num_transactions);
+        let tx_metadata =
+            TransactionMetadata::build_random_with_tree_depth(num_transactions, tree_depth);
 
         TransactionExample {
             options,
@@ -79,30 +98,7 @@ impl TransactionExample {
 
     /// Proves the validity of the authentication paths of a given set of transactions users
     pub fn prove(&self) -> StarkProof {
-        // generate the execution trace
-        #[cfg(feature = "std")]
-        debug!(
-            "Generating proof for proving update in a Merkle tree of depth {}\n\
-            ---------------------",
-            MERKLE_TREE_DEPTH
-        );
-
-        let prover = MerkleProver::new(self.options.clone());
-
-        // generate the execution trace
-        #[cfg(feature = "std")]
-        let now = Instant::now();
-        let trace = prover.build_trace(&self.tx_metadata);
-        #[cfg(feature = "std")]
-        debug!(
-            "Generated execution trace of {} registers and 2^{} steps in {} ms",
-            trace.width(),
-            log2(trace.length()),
-            now.elapsed().as_millis()
-        );
-
-        // generate the proof
-        prover.prove(trace).unwrap()
+        prove_update(self.options.clone(), &self.tx_metadata)
     }
 
     /// Verifies the validity of a proof of correct authentication paths computation
@@ -110,8 +106,10 @@ impl TransactionExample {
         let pub_inputs = PublicInputs {
             initial_root: self.tx_metadata.initial_roots[0].to_elements(),
             final_root: self.tx_metadata.final_root.to_elements(),
+            tree_depth: self.tx_metadata.tree_depth,
+            hash_id: Rescue63::HASH_ID,
         };
-        winterfell::verify::<MerkleAir>(proof, pub_inputs)
+        verify_update(proof, pub_inputs)
     }
 
     #[cfg(test)]
@@ -121,7 +119,80 @@ impl TransactionExample {
         let pub_inputs = PublicInputs {
             initial_root,
             final_root: [final_root[0]; constants::HASH_RATE_WIDTH],
+            tree_depth: self.tx_metadata.tree_depth,
+            hash_id: Rescue63::HASH_ID,
         };
         winterfell::verify::<MerkleAir>(proof, pub_inputs)
     }
 }
+
+// MERKLE UPDATE PROOFS
+// ================================================================================================
+
+/// Proves the update of a sparse Merkle tree hashed with Rescue63 from the first of
+/// `tx_metadata.initial_roots` to `tx_metadata.final_root`, by applying in order the
+/// balance and nonce updates of the provided transfers to the sender, receiver and fee
+/// collector leaves, along their authentication paths. No signature is verified, which
+/// allows proving state migrations on their own.
+///
+/// # Panics
+/// Panics if the number of transactions is not a power of two, if any of them is not a
+/// transfer, or if their leaf values and authentication paths are not consistent with the
+/// intermediate roots.
+pub fn prove_update(options: ProofOptions, tx_metadata: &TransactionMetadata) -> StarkProof {
+    prove_update_with_hasher::<Rescue63>(options, tx_metadata)
+}
+
+/// Proves the update of a sparse Merkle tree similarly to `prove_update`, with the nodes
+/// of the tree being hashed with `H`.
+///
+/// # Panics
+/// Panics under the same conditions as `prove_update`.
+pub fn prove_update_with_hasher<H: TreeHasher>(
+    options: ProofOptions,
+    tx_metadata: &TransactionMetadata,
+) -> StarkProof {
+    assert!(
+        tx_metadata.initial_roots.len().is_power_of_two(),
+        "number of transactions must be a power of two"
+    );
+
+    #[cfg(feature = "std")]
+    debug!(
+        "Generating proof for proving update in a Merkle tree of depth {}\n\
+        ---------------------",
+        tx_metadata.tree_depth
+    );
+
+    let prover = MerkleProver::<H>::new(options, tx_metadata.tree_depth);
+
+    // generate the execution trace
+    #[cfg(feature = "std")]
+    let now = Instant::now();
+    let trace = prover.build_trace(tx_metadata);
+    #[cfg(feature = "std")]
+    debug!(
+        "Generated execution trace of {} registers and 2^{} steps in {} ms",
+        trace.width(),
+        log2(trace.length()),
+        now.elapsed().as_millis()
+    );
+
+    // generate the proof
+    prover.prove(trace).unwrap()
+}
+
+/// Verifies a proof of update of a sparse Merkle tree hashed with Rescue63 between the
+/// roots contained in the provided public inputs.
+pub fn verify_update(proof: StarkProof, pub_inputs: PublicInputs) -> Result<(), VerifierError> {
+    verify_update_with_hasher::<Rescue63>(proof, pub_inputs)
+}
+
+/// Verifies a proof of update of a sparse Merkle tree hashed with `H` between the roots
+/// contained in the provided public inputs.
+pub fn verify_update_with_hasher<H: TreeHasher>(
+    proof: StarkProof,
+    pub_inputs: PublicInputs,
+) -> Result<(), VerifierError> {
+    winterfell::verify::<MerkleAir<H>>(proof, pub_inputs)
+}
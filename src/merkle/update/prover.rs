@@ -8,29 +8,55 @@ use super::trace::*;
 use super::MerkleAir;
 use super::PublicInputs;
 
-use crate::TransactionMetadata;
+use crate::merkle::TreeHasher;
+use crate::{TransactionKind, TransactionMetadata};
+use core::marker::PhantomData;
 
 // MERKLE UPDATE PROVER
 // ================================================================================================
 
-pub struct MerkleProver {
+pub struct MerkleProver<H: TreeHasher> {
     options: ProofOptions,
+    tree_depth: usize,
+    _hasher: PhantomData<H>,
 }
 
-impl MerkleProver {
-    pub fn new(options: ProofOptions) -> Self {
-        Self { options }
+impl<H: TreeHasher> MerkleProver<H> {
+    pub fn new(options: ProofOptions, tree_depth: usize) -> Self {
+        Self {
+            options,
+            tree_depth,
+            _hasher: PhantomData,
+        }
     }
 
     pub fn build_trace(&self, tx_metadata: &TransactionMetadata) -> TraceTable<BaseElement> {
+        assert_eq!(
+            self.tree_depth, tx_metadata.tree_depth,
+            "prover and transaction metadata must use the same tree depth"
+        );
+        // Withdrawals and deposits burn and mint funds, which breaks the balance conservation
+        // enforced by this program
+        assert!(
+            tx_metadata
+                .kinds
+                .iter()
+                .all(|kind| *kind == TransactionKind::Transfer),
+            "Merkle update program only supports transfers"
+        );
+        let tree_depth = tx_metadata.tree_depth;
         let initial_roots = &tx_metadata.initial_roots;
         let s_old_values = &tx_metadata.s_old_values;
         let r_old_values = &tx_metadata.r_old_values;
+        let c_old_values = &tx_metadata.c_old_values;
         let s_indices = &tx_metadata.s_indices;
         let r_indices = &tx_metadata.r_indices;
+        let c_index = tx_metadata.fee_collector;
         let s_paths = &tx_metadata.s_paths;
         let r_paths = &tx_metadata.r_paths;
+        let c_paths = &tx_metadata.c_paths;
         let deltas = &tx_metadata.deltas;
+        let fees = &tx_metadata.fees;
 
         let num_transactions = tx_metadata.initial_roots.len();
 
@@ -49,17 +75,23 @@ impl MerkleProver {
                             initial_roots[i],
                             s_old_values[i],
                             r_old_values[i],
+                            c_old_values[i],
                             deltas[i],
+                            fees[i],
+                            TransactionKind::Transfer,
                             state,
                         );
                     },
                     |step, state| {
-                        update_merkle_update_state(
+                        update_merkle_update_state::<H>(
                             step,
+                            tree_depth,
                             s_indices[i],
                             r_indices[i],
-                            s_paths[i].clone(),
-                            r_paths[i].clone(),
+                            c_index,
+                            &s_paths[i],
+                            &r_paths[i],
+                            &c_paths[i],
                             state,
                         );
                     },
@@ -72,14 +104,15 @@ impl MerkleProver {
         // of the index bit constraint is stable.
         trace.set(SENDER_BIT_POS, 1, BaseElement::ONE);
         trace.set(RECEIVER_BIT_POS, 1, BaseElement::ONE);
+        trace.set(COLLECTOR_BIT_POS, 1, BaseElement::ONE);
 
         trace
     }
 }
 
-impl Prover for MerkleProver {
+impl<H: TreeHasher> Prover for MerkleProver<H> {
     type BaseField = BaseElement;
-    type Air = MerkleAir;
+    type Air = MerkleAir<H>;
     type Trace = TraceTable<BaseElement>;
 
     fn get_pub_inputs(&self, trace: &Self::Trace) -> PublicInputs {
@@ -104,6 +137,8 @@ impl Prover for MerkleProver {
                 trace.get(PREV_TREE_ROOT_POS + 5, last_step),
                 trace.get(PREV_TREE_ROOT_POS + 6, last_step),
             ],
+            tree_depth: self.tree_depth,
+            hash_id: H::HASH_ID,
         }
     }
 
@@ -6,6 +6,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::merkle::TreeHasher;
+use crate::utils::rescue::Rescue63;
+use crate::TransactionMetadata;
 use winterfell::{FieldExtension, HashFunction, ProofOptions};
 
 #[test]
@@ -37,6 +40,29 @@ fn transaction_test_basic_proof_verification_fail() {
     assert!(verified.is_err());
 }
 
+#[test]
+fn transaction_test_standalone_update_proof() {
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 8);
+    let proof = super::prove_update(build_options(1), &tx_metadata);
+
+    let pub_inputs = super::PublicInputs {
+        initial_root: tx_metadata.initial_roots[0].to_elements(),
+        final_root: tx_metadata.final_root.to_elements(),
+        tree_depth: tx_metadata.tree_depth,
+        hash_id: Rescue63::HASH_ID,
+    };
+    assert!(super::verify_update(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // Swapping the roots must be rejected
+    let pub_inputs = super::PublicInputs {
+        initial_root: pub_inputs.final_root,
+        final_root: pub_inputs.initial_root,
+        tree_depth: pub_inputs.tree_depth,
+        hash_id: pub_inputs.hash_id,
+    };
+    assert!(super::verify_update(proof, pub_inputs).is_err());
+}
+
 fn build_options(extension: u8) -> ProofOptions {
     ProofOptions::new(
         42,
@@ -7,45 +7,92 @@
 // except according to those terms.
 
 use super::constants::*;
+use crate::merkle::TreeHasher;
+use crate::utils::ecc::POINT_COORDINATE_WIDTH;
 use crate::utils::rescue::{self, RATE_WIDTH};
+use crate::{AccountState, TransactionKind};
 use winterfell::math::{fields::f63::BaseElement, FieldElement};
 
 #[cfg(feature = "concurrent")]
 use winterfell::iterators::*;
 
-#[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
-
 // TRACE INITIALIZATION
 // ================================================================================================
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn init_merkle_update_state(
     initial_root: rescue::Hash,
-    s_old_value: [BaseElement; AFFINE_POINT_WIDTH + 2],
-    r_old_value: [BaseElement; AFFINE_POINT_WIDTH + 2],
+    s_old_value: AccountState,
+    r_old_value: AccountState,
+    c_old_value: AccountState,
     delta: BaseElement,
+    fee: BaseElement,
+    kind: TransactionKind,
     state: &mut [BaseElement],
 ) {
     // Initialize the first row of any given transaction
     let init_root = initial_root.to_elements();
+    let s_old_value = s_old_value.to_elements();
+    let r_old_value = r_old_value.to_elements();
+    let c_old_value = c_old_value.to_elements();
 
     state[SENDER_INITIAL_POS..SENDER_INITIAL_POS + AFFINE_POINT_WIDTH + 2]
         .copy_from_slice(&s_old_value);
     state[SENDER_BIT_POS] = BaseElement::ZERO;
     state[SENDER_UPDATED_POS..SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 2]
         .copy_from_slice(&s_old_value);
-    // Update sender's balance
-    state[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH] -= delta;
-    // Update sender's nonce
-    state[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 1] += BaseElement::ONE;
+    // Update sender's balance and nonce, unless delta is deposited into the tree or nothing
+    // is transferred. Refunds consume the nonce of the receiver, which signs them, and
+    // approvals leave all accounts unchanged, their committee transfer having updated them
+    let unchanged = kind.is_noop() || kind.is_approval();
+    if !kind.is_deposit() && !unchanged {
+        state[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH] -= delta + fee;
+    }
+    if !kind.is_deposit() && !unchanged && !kind.is_refund() {
+        state[SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 1] += BaseElement::ONE;
+    }
+    // Replace the sender's key for rekeyings
+    if let TransactionKind::Rekey { new_key } = kind {
+        state[SENDER_UPDATED_POS..SENDER_UPDATED_POS + POINT_COORDINATE_WIDTH]
+            .copy_from_slice(&new_key.get_x());
+        state[SENDER_UPDATED_POS + POINT_COORDINATE_WIDTH..SENDER_UPDATED_POS + AFFINE_POINT_WIDTH]
+            .copy_from_slice(&new_key.get_y());
+    }
 
     state[RECEIVER_INITIAL_POS..RECEIVER_INITIAL_POS + AFFINE_POINT_WIDTH + 2]
         .copy_from_slice(&r_old_value);
     state[RECEIVER_BIT_POS] = BaseElement::ZERO;
     state[RECEIVER_UPDATED_POS..RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH + 2]
         .copy_from_slice(&r_old_value);
-    // Update receivers's balance
-    state[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH] += delta;
+    // Update receivers's balance, unless delta is withdrawn from the tree
+    if !kind.is_withdrawal() && !kind.is_approval() {
+        state[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH] += delta;
+    }
+    if kind.is_refund() {
+        state[RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH + 1] += BaseElement::ONE;
+    }
+
+    state[COLLECTOR_INITIAL_POS..COLLECTOR_INITIAL_POS + AFFINE_POINT_WIDTH + 2]
+        .copy_from_slice(&c_old_value);
+    state[COLLECTOR_BIT_POS] = BaseElement::ZERO;
+    state[COLLECTOR_UPDATED_POS..COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH + 2]
+        .copy_from_slice(&c_old_value);
+    // Update fee collector's balance
+    if !kind.is_approval() {
+        state[COLLECTOR_UPDATED_POS + AFFINE_POINT_WIDTH] += fee;
+    }
+
+    // Hash all leaves in the domain of leaves
+    for pos in [
+        SENDER_INITIAL_POS,
+        SENDER_UPDATED_POS,
+        RECEIVER_INITIAL_POS,
+        RECEIVER_UPDATED_POS,
+        COLLECTOR_INITIAL_POS,
+        COLLECTOR_UPDATED_POS,
+    ] {
+        state[pos + HASH_STATE_WIDTH - 1] += LEAF_DOMAIN;
+    }
 
     state[PREV_TREE_ROOT_POS..PREV_TREE_ROOT_POS + RATE_WIDTH].copy_from_slice(&init_root);
 }
@@ -53,53 +100,64 @@ pub(crate) fn init_merkle_update_state(
 // TRANSITION FUNCTION
 // ================================================================================================
 
-pub(crate) fn update_merkle_update_state(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_merkle_update_state<H: TreeHasher>(
     step: usize,
+    tree_depth: usize,
     s_index: usize,
     r_index: usize,
-    s_branch: Vec<rescue::Hash>,
-    r_branch: Vec<rescue::Hash>,
+    c_index: usize,
+    s_branch: &[rescue::Hash],
+    r_branch: &[rescue::Hash],
+    c_branch: &[rescue::Hash],
     state: &mut [BaseElement],
 ) {
     // Execute the transition function for all steps
     //
-    // For the first NUM_HASH_ROUNDS steps of each cycle, compute a single round of Rescue
+    // For the first NUM_HASH_ROUNDS steps of each cycle, compute a single round of the tree
     // hash in registers [0..HASH_STATE_WIDTH]. On the final step, insert the next branch node
     // into the trace in the positions defined by the next bit of the leaf index. If the bit
     // is ZERO, the next node goes into the rate registers, if it is ONE, the node goes into
     // the capacity registers. On all steps between these, the values are simply copied.
 
     let transaction_pos = step;
+    let transaction_hash_length = transaction_hash_length(tree_depth);
 
     // Perform steps only if the Merkle tree authetication is still in progress
-    if transaction_pos < TRANSACTION_HASH_LENGTH {
+    if transaction_pos < transaction_hash_length {
         // The hashes for a transaction are being computed, so fill with update authentication path steps
-        update_merkle_update_auth_state(
+        update_merkle_update_auth_state::<H>(
             transaction_pos,
             s_index,
             s_branch,
             &mut state[SENDER_INITIAL_POS..RECEIVER_INITIAL_POS],
         );
-        update_merkle_update_auth_state(
+        update_merkle_update_auth_state::<H>(
             transaction_pos,
             r_index,
             r_branch,
-            &mut state[RECEIVER_INITIAL_POS..PREV_TREE_ROOT_POS],
+            &mut state[RECEIVER_INITIAL_POS..COLLECTOR_INITIAL_POS],
+        );
+        update_merkle_update_auth_state::<H>(
+            transaction_pos,
+            c_index,
+            c_branch,
+            &mut state[COLLECTOR_INITIAL_POS..PREV_TREE_ROOT_POS],
         );
     }
-    if transaction_pos == TRANSACTION_HASH_LENGTH - 1 {
+    if transaction_pos == transaction_hash_length - 1 {
         // The hashes for the transaction have completed, so copy
         // the previous root to store until the next cycle
         for i in 0..RATE_WIDTH {
-            state[PREV_TREE_ROOT_POS + i] = state[RECEIVER_UPDATED_POS + i];
+            state[PREV_TREE_ROOT_POS + i] = state[COLLECTOR_UPDATED_POS + i];
         }
     }
 }
 
-pub(crate) fn update_merkle_update_auth_state(
+pub(crate) fn update_merkle_update_auth_state<H: TreeHasher>(
     transaction_pos: usize,
     index: usize,
-    branch: Vec<rescue::Hash>,
+    branch: &[rescue::Hash],
     state: &mut [BaseElement],
 ) {
     // Compute the segment of the path we are on and the position in that cycle
@@ -107,8 +165,8 @@ pub(crate) fn update_merkle_update_auth_state(
     let cycle_pos = transaction_pos % HASH_CYCLE_LENGTH;
     // The hashes for a transaction are being computed, so enforce as usual
     if cycle_pos < NUM_HASH_ROUNDS {
-        rescue::apply_round(&mut state[0..HASH_STATE_WIDTH], transaction_pos);
-        rescue::apply_round(
+        H::apply_round(&mut state[0..HASH_STATE_WIDTH], transaction_pos);
+        H::apply_round(
             &mut state[HASH_STATE_WIDTH + 1..2 * HASH_STATE_WIDTH + 1],
             transaction_pos,
         );
@@ -133,7 +191,40 @@ pub(crate) fn update_merkle_update_auth_state(
             }
         }
 
+        // Hash the next level in the domain of internal nodes
+        state[HASH_STATE_WIDTH - 1] += NODE_DOMAIN;
+        state[2 * HASH_STATE_WIDTH] += NODE_DOMAIN;
+
         // Store the index bit in the "middle lane"
         state[HASH_STATE_WIDTH] = index_bit;
     }
 }
+
+/// Computes a single authentication path, laid out as one hash cluster followed by the
+/// register of the bits of `index`, as the first cluster of an update.
+pub(crate) fn update_merkle_path_state<H: TreeHasher>(
+    transaction_pos: usize,
+    index: usize,
+    branch: &[rescue::Hash],
+    state: &mut [BaseElement],
+) {
+    let cycle_num = transaction_pos / HASH_CYCLE_LENGTH;
+    let cycle_pos = transaction_pos % HASH_CYCLE_LENGTH;
+    if cycle_pos < NUM_HASH_ROUNDS {
+        H::apply_round(&mut state[0..HASH_STATE_WIDTH], transaction_pos);
+    } else if cycle_pos == HASH_CYCLE_LENGTH - 1 {
+        let branch_node = branch[cycle_num + 1].to_elements();
+        let index_bit = BaseElement::from(((index >> cycle_num) & 1) as u128);
+        if index_bit == BaseElement::ZERO {
+            state[RATE_WIDTH..HASH_STATE_WIDTH].copy_from_slice(&branch_node);
+        } else {
+            for i in 0..RATE_WIDTH {
+                state[RATE_WIDTH + i] = state[i];
+                state[i] = branch_node[i];
+            }
+        }
+
+        state[HASH_STATE_WIDTH - 1] += NODE_DOMAIN;
+        state[HASH_STATE_WIDTH] = index_bit;
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::fmt::Debug;
+
+// PROVER OBSERVER
+// ================================================================================================
+
+/// An observer of the progress of the proof generation of a `TransactionProver`, e.g. to
+/// display a progress bar while proving large batches.
+///
+/// All callbacks do nothing by default. They are invoked on the proving thread, except for
+/// `on_transaction_filled` which is invoked from the threads filling the trace when the
+/// `concurrent` feature is enabled, in no particular order.
+///
+/// Winterfell does not report on the steps of the proof generation itself, so that the
+/// low-degree extension of the trace, the constraint evaluation and the FRI layers are all
+/// performed in between `on_proving_started` and `on_proof_generated`.
+pub trait ProverObserver: Debug + Send + Sync {
+    /// Called before building the execution trace of `num_transactions` transactions, padding
+    /// transactions included, over `trace_length` steps.
+    fn on_trace_started(&self, _num_transactions: usize, _trace_length: usize) {}
+
+    /// Called once the trace segment of the transaction at `tx_index` is filled.
+    fn on_transaction_filled(&self, _tx_index: usize) {}
+
+    /// Called once the execution trace of `trace_width` registers and `trace_length` steps
+    /// is built.
+    fn on_trace_built(&self, _trace_width: usize, _trace_length: usize) {}
+
+    /// Called when winterfell starts generating the proof of the built trace.
+    fn on_proving_started(&self) {}
+
+    /// Called once the proof is generated, with its size in bytes.
+    fn on_proof_generated(&self, _proof_size: usize) {}
+}
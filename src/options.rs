@@ -0,0 +1,437 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::fmt;
+use winterfell::{FieldExtension, HashFunction, ProofOptions};
+
+#[cfg(feature = "concurrent")]
+use std::sync::Arc;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Hash function of the random coin and of the commitments of the proof profiles, i.e.
+/// SHA3-256 when the `sha3` feature is enabled, and BLAKE3-256 otherwise
+#[cfg(not(feature = "sha3"))]
+pub const DEFAULT_HASH_FN: HashFunction = HashFunction::Blake3_256;
+
+/// Hash function of the random coin and of the commitments of the proof profiles, i.e.
+/// SHA3-256 when the `sha3` feature is enabled, and BLAKE3-256 otherwise
+#[cfg(feature = "sha3")]
+pub const DEFAULT_HASH_FN: HashFunction = HashFunction::Sha3_256;
+
+/// Maximum number of queries accepted by winterfell
+const MAX_NUM_QUERIES: usize = 128;
+
+/// Minimum blow-up factor accepted by winterfell
+const MIN_BLOWUP_FACTOR: usize = 2;
+
+/// Maximum blow-up factor accepted by winterfell
+const MAX_BLOWUP_FACTOR: usize = 128;
+
+/// Maximum grinding factor accepted by winterfell
+const MAX_GRINDING_FACTOR: u32 = 32;
+
+/// Minimum FRI folding factor accepted by winterfell
+const MIN_FRI_FOLDING_FACTOR: usize = 4;
+
+/// Maximum FRI folding factor accepted by winterfell
+const MAX_FRI_FOLDING_FACTOR: usize = 16;
+
+/// Minimum FRI remainder size accepted by winterfell
+const MIN_FRI_REMAINDER_SIZE: usize = 32;
+
+/// Maximum FRI remainder size accepted by winterfell
+const MAX_FRI_REMAINDER_SIZE: usize = 1024;
+
+// PROOF PROFILES
+// ================================================================================================
+
+/// Preset parameter sets for generating state-transition proofs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProofProfile {
+    /// Low number of queries, for testing and benchmarking purposes.
+    Fast,
+    /// Default parameters, matching the ones historically used by `get_example`.
+    #[default]
+    Balanced,
+    /// Larger blow-up factor, grinding and a quadratic field extension,
+    /// at the cost of slower proving and larger proofs.
+    HighSecurity,
+}
+
+impl ProofProfile {
+    /// Returns the `ProofOptions` associated to this profile.
+    pub fn to_proof_options(self) -> ProofOptions {
+        ProofOptionsBuilder::from_profile(self).build()
+    }
+}
+
+// OPTIONS ERROR
+// ================================================================================================
+
+/// Errors raised when building `ProofOptions` from invalid parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    /// The number of queries is zero or greater than 128
+    InvalidNumQueries(usize),
+    /// The blow-up factor is not a power of two between 2 and 128
+    InvalidBlowupFactor(usize),
+    /// The grinding factor is greater than 32
+    InvalidGrindingFactor(u32),
+    /// The FRI folding factor is not one of 4, 8 or 16
+    InvalidFriFoldingFactor(usize),
+    /// The FRI maximum remainder size is not a power of two between 32 and 1024
+    InvalidFriMaxRemainderSize(usize),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionsError::InvalidNumQueries(num_queries) => write!(
+                f,
+                "number of queries must be between 1 and {}, but was {}",
+                MAX_NUM_QUERIES, num_queries
+            ),
+            OptionsError::InvalidBlowupFactor(blowup_factor) => write!(
+                f,
+                "blow-up factor must be a power of two between {} and {}, but was {}",
+                MIN_BLOWUP_FACTOR, MAX_BLOWUP_FACTOR, blowup_factor
+            ),
+            OptionsError::InvalidGrindingFactor(grinding_factor) => write!(
+                f,
+                "grinding factor must be at most {}, but was {}",
+                MAX_GRINDING_FACTOR, grinding_factor
+            ),
+            OptionsError::InvalidFriFoldingFactor(fri_folding_factor) => write!(
+                f,
+                "FRI folding factor must be a power of two between {} and {}, but was {}",
+                MIN_FRI_FOLDING_FACTOR, MAX_FRI_FOLDING_FACTOR, fri_folding_factor
+            ),
+            OptionsError::InvalidFriMaxRemainderSize(fri_max_remainder_size) => write!(
+                f,
+                "FRI maximum remainder size must be a power of two between {} and {}, but was {}",
+                MIN_FRI_REMAINDER_SIZE, MAX_FRI_REMAINDER_SIZE, fri_max_remainder_size
+            ),
+        }
+    }
+}
+
+// PROOF OPTIONS BUILDER
+// ================================================================================================
+
+/// A builder for `ProofOptions`, starting from one of the preset
+/// `ProofProfile` and allowing to override any individual parameter.
+#[derive(Clone, Debug)]
+pub struct ProofOptionsBuilder {
+    num_queries: usize,
+    blowup_factor: usize,
+    grinding_factor: u32,
+    hash_fn: HashFunction,
+    field_extension: FieldExtension,
+    fri_folding_factor: usize,
+    fri_max_remainder_size: usize,
+}
+
+impl Default for ProofOptionsBuilder {
+    fn default() -> Self {
+        Self::from_profile(ProofProfile::default())
+    }
+}
+
+impl ProofOptionsBuilder {
+    /// Returns a new builder initialized with the `Balanced` profile.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new builder initialized with the parameters of the provided profile.
+    pub fn from_profile(profile: ProofProfile) -> Self {
+        match profile {
+            ProofProfile::Fast => Self::fast(),
+            ProofProfile::Balanced => Self::balanced(),
+            ProofProfile::HighSecurity => Self::high_security(),
+        }
+    }
+
+    /// Returns a new builder initialized with the `Fast` profile.
+    pub fn fast() -> Self {
+        ProofOptionsBuilder {
+            num_queries: 28,
+            blowup_factor: 8,
+            grinding_factor: 0,
+            hash_fn: DEFAULT_HASH_FN,
+            field_extension: FieldExtension::None,
+            fri_folding_factor: 8,
+            fri_max_remainder_size: 256,
+        }
+    }
+
+    /// Returns a new builder initialized with the `Balanced` profile.
+    pub fn balanced() -> Self {
+        ProofOptionsBuilder {
+            num_queries: 42,
+            blowup_factor: 8,
+            grinding_factor: 0,
+            hash_fn: DEFAULT_HASH_FN,
+            field_extension: FieldExtension::None,
+            fri_folding_factor: 4,
+            fri_max_remainder_size: 256,
+        }
+    }
+
+    /// Returns a new builder initialized with the `HighSecurity` profile.
+    pub fn high_security() -> Self {
+        ProofOptionsBuilder {
+            num_queries: 48,
+            blowup_factor: 16,
+            grinding_factor: 16,
+            hash_fn: DEFAULT_HASH_FN,
+            field_extension: FieldExtension::Quadratic,
+            fri_folding_factor: 4,
+            fri_max_remainder_size: 256,
+        }
+    }
+
+    /// Sets the number of queries.
+    pub fn num_queries(mut self, num_queries: usize) -> Self {
+        self.num_queries = num_queries;
+        self
+    }
+
+    /// Sets the blow-up factor.
+    pub fn blowup_factor(mut self, blowup_factor: usize) -> Self {
+        self.blowup_factor = blowup_factor;
+        self
+    }
+
+    /// Sets the grinding factor.
+    pub fn grinding_factor(mut self, grinding_factor: u32) -> Self {
+        self.grinding_factor = grinding_factor;
+        self
+    }
+
+    /// Sets the hash function used for commitments and for drawing the random coin, e.g.
+    /// SHA3-256 for verifiers running in environments where it is cheaper than BLAKE3-256.
+    pub fn hash_fn(mut self, hash_fn: HashFunction) -> Self {
+        self.hash_fn = hash_fn;
+        self
+    }
+
+    /// Sets the field extension used for composition and DEEP polynomials.
+    pub fn field_extension(mut self, field_extension: FieldExtension) -> Self {
+        self.field_extension = field_extension;
+        self
+    }
+
+    /// Sets the FRI folding factor, i.e. the factor by which the degree of the polynomial is
+    /// reduced at each FRI layer. Larger factors result in fewer layers and hence smaller
+    /// proofs, at the cost of more work per layer.
+    pub fn fri_folding_factor(mut self, fri_folding_factor: usize) -> Self {
+        self.fri_folding_factor = fri_folding_factor;
+        self
+    }
+
+    /// Sets the FRI maximum remainder size, i.e. the size below which the last FRI layer is
+    /// sent in the clear instead of being folded further.
+    pub fn fri_max_remainder_size(mut self, fri_max_remainder_size: usize) -> Self {
+        self.fri_max_remainder_size = fri_max_remainder_size;
+        self
+    }
+
+    /// Builds the `ProofOptions`, after checking that all parameters are accepted by
+    /// winterfell.
+    ///
+    /// Returns an error describing the first invalid parameter otherwise.
+    pub fn try_build(&self) -> Result<ProofOptions, OptionsError> {
+        if self.num_queries == 0 || self.num_queries > MAX_NUM_QUERIES {
+            return Err(OptionsError::InvalidNumQueries(self.num_queries));
+        }
+        if !self.blowup_factor.is_power_of_two()
+            || self.blowup_factor < MIN_BLOWUP_FACTOR
+            || self.blowup_factor > MAX_BLOWUP_FACTOR
+        {
+            return Err(OptionsError::InvalidBlowupFactor(self.blowup_factor));
+        }
+        if self.grinding_factor > MAX_GRINDING_FACTOR {
+            return Err(OptionsError::InvalidGrindingFactor(self.grinding_factor));
+        }
+        if !self.fri_folding_factor.is_power_of_two()
+            || self.fri_folding_factor < MIN_FRI_FOLDING_FACTOR
+            || self.fri_folding_factor > MAX_FRI_FOLDING_FACTOR
+        {
+            return Err(OptionsError::InvalidFriFoldingFactor(
+                self.fri_folding_factor,
+            ));
+        }
+        if !self.fri_max_remainder_size.is_power_of_two()
+            || self.fri_max_remainder_size < MIN_FRI_REMAINDER_SIZE
+            || self.fri_max_remainder_size > MAX_FRI_REMAINDER_SIZE
+        {
+            return Err(OptionsError::InvalidFriMaxRemainderSize(
+                self.fri_max_remainder_size,
+            ));
+        }
+
+        Ok(self.build())
+    }
+
+    /// Builds the `ProofOptions`.
+    ///
+    /// # Panics
+    /// Panics if any of the parameters is rejected by `ProofOptions::new`, see `try_build`.
+    pub fn build(&self) -> ProofOptions {
+        ProofOptions::new(
+            self.num_queries,
+            self.blowup_factor,
+            self.grinding_factor,
+            self.hash_fn,
+            self.field_extension,
+            self.fri_folding_factor,
+            self.fri_max_remainder_size,
+        )
+    }
+}
+
+// PROVER CONFIGURATION
+// ================================================================================================
+
+/// Configuration of the computing resources used to generate proofs, independently of
+/// the `ProofOptions` defining the proofs themselves.
+///
+/// When the `concurrent` feature is enabled, the execution trace is built, extended and
+/// evaluated against the constraints in parallel, by default over the global thread pool,
+/// with one thread per logical core. Without this feature, proofs are generated on the
+/// calling thread, and the number of threads is ignored.
+///
+/// The memory held by the execution trace can also be capped, so that batches too large for
+/// the host are rejected before any allocation, as can the number of transactions per batch.
+/// The AIR program itself supports any number of transactions, its periodic columns only
+/// depending on the length of a transaction cycle.
+///
+/// A dedicated thread pool is spawned once, when the number of threads is set, and shared by
+/// the clones of the configuration, so that its threads are reused across proofs.
+#[derive(Clone, Debug, Default)]
+pub struct ProverConfig {
+    num_threads: Option<usize>,
+    max_trace_memory: Option<usize>,
+    max_transactions: Option<usize>,
+    #[cfg(feature = "concurrent")]
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+// Two configurations are equal when they request the same resources, whether they share
+// their thread pool or not
+impl PartialEq for ProverConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_threads == other.num_threads
+            && self.max_trace_memory == other.max_trace_memory
+            && self.max_transactions == other.max_transactions
+    }
+}
+
+impl Eq for ProverConfig {}
+
+impl ProverConfig {
+    /// Returns a new configuration, generating proofs over the global thread pool when the
+    /// `concurrent` feature is enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns this configuration, generating proofs over a dedicated pool of `num_threads`
+    /// threads when the `concurrent` feature is enabled. The pool is spawned here, and reused
+    /// by every proof generated with this configuration.
+    ///
+    /// # Panics
+    /// Panics if `num_threads` is zero, or if the thread pool cannot be spawned.
+    pub fn with_num_threads(mut self, num_threads: usize) -> Self {
+        assert!(num_threads > 0, "number of threads must be positive");
+        self.num_threads = Some(num_threads);
+        #[cfg(feature = "concurrent")]
+        {
+            self.thread_pool = Some(Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .build()
+                    .expect("failed to spawn the prover threads"),
+            ));
+        }
+        self
+    }
+
+    /// Returns whether proofs are generated in parallel, i.e. whether the `concurrent`
+    /// feature is enabled.
+    pub fn is_concurrent(&self) -> bool {
+        cfg!(feature = "concurrent")
+    }
+
+    /// Returns the number of threads over which proofs are generated.
+    #[cfg(feature = "concurrent")]
+    pub fn num_threads(&self) -> usize {
+        self.num_threads.unwrap_or_else(rayon::current_num_threads)
+    }
+
+    /// Returns the number of threads over which proofs are generated, i.e. one as the
+    /// `concurrent` feature is disabled.
+    #[cfg(not(feature = "concurrent"))]
+    pub fn num_threads(&self) -> usize {
+        1
+    }
+
+    /// Returns this configuration, rejecting batches whose execution trace would take more
+    /// than `max_trace_memory` bytes, as estimated by
+    /// `TransactionProver::estimate_trace_memory`.
+    pub fn with_max_trace_memory(mut self, max_trace_memory: usize) -> Self {
+        self.max_trace_memory = Some(max_trace_memory);
+        self
+    }
+
+    /// Returns the maximum size of the execution trace, in bytes, if capped.
+    pub fn max_trace_memory(&self) -> Option<usize> {
+        self.max_trace_memory
+    }
+
+    /// Returns this configuration, rejecting batches of more than `max_transactions`
+    /// transactions, padding excluded.
+    ///
+    /// # Panics
+    /// Panics if `max_transactions` is zero.
+    pub fn with_max_transactions(mut self, max_transactions: usize) -> Self {
+        assert!(
+            max_transactions > 0,
+            "maximum number of transactions must be positive"
+        );
+        self.max_transactions = Some(max_transactions);
+        self
+    }
+
+    /// Returns the maximum number of transactions per batch, if capped.
+    pub fn max_transactions(&self) -> Option<usize> {
+        self.max_transactions
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Runs `op` within the thread pool of this configuration, so that the parallel
+    /// iterators of winterfell and of this crate are executed over its threads.
+    #[cfg(feature = "concurrent")]
+    pub(crate) fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.install(op),
+            None => op(),
+        }
+    }
+
+    /// Runs `op` on the calling thread.
+    #[cfg(not(feature = "concurrent"))]
+    pub(crate) fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        op()
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::merkle::{compute_root, TreeHasher};
+use super::{build_tx_message, sign_noop, TransactionKind, TransactionMetadata};
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+// METADATA PADDING
+// ================================================================================================
+
+impl TransactionMetadata {
+    /// Returns these transactions followed by as many no-op transactions as needed for
+    /// their number to be a power of two, the nodes of the Merkle tree being hashed with `H`.
+    ///
+    /// The no-ops apply to the receiver of the last transaction, in the tree resulting from
+    /// all transactions, so that the final root, withdrawals and deposits are left unchanged.
+    ///
+    /// # Panics
+    /// Panics if there are no transactions.
+    pub(crate) fn padded_with_hasher<H: TreeHasher>(&self) -> TransactionMetadata {
+        let num_transactions = self.num_transactions();
+        assert!(num_transactions > 0, "cannot pad an empty batch");
+        let mut tx_metadata = self.clone();
+        if num_transactions.is_power_of_two() {
+            return tx_metadata;
+        }
+
+        let last = num_transactions - 1;
+        let (r_index, fee_collector) = (self.r_indices[last], self.fee_collector);
+
+        // The receiver path of the last transaction is opened before the fee collector
+        // update, which only changes the sibling at the level where both paths meet
+        let mut r_value = self.r_old_values[last];
+        if !self.kinds[last].is_withdrawal() {
+            r_value.balance += self.deltas[last];
+        }
+        let mut c_value = self.c_old_values[last];
+        c_value.balance += self.fees[last];
+        let level = (usize::BITS - (r_index ^ fee_collector).leading_zeros() - 1) as usize;
+        let mut r_path = self.r_paths[last].clone();
+        r_path[0] = r_value.to_leaf::<H>();
+        r_path[level + 1] = compute_root::<H>(
+            fee_collector,
+            c_value.to_leaf::<H>(),
+            &self.c_paths[last][..=level],
+        );
+
+        let message = build_tx_message(
+            &r_value.pub_key_elements(),
+            &r_value.pub_key_elements(),
+            r_index,
+            r_index,
+            BaseElement::ZERO,
+            r_value.nonce,
+            BaseElement::ZERO,
+            TransactionKind::NoOp,
+            None,
+            self.subnet_id,
+            self.epoch,
+        );
+        let signature = sign_noop(message);
+
+        let num_padding = num_transactions.next_power_of_two() - num_transactions;
+        for _ in 0..num_padding {
+            tx_metadata.initial_roots.push(self.final_root);
+            tx_metadata.s_old_values.push(r_value);
+            tx_metadata.r_old_values.push(r_value);
+            tx_metadata.c_old_values.push(c_value);
+            tx_metadata.s_indices.push(r_index);
+            tx_metadata.r_indices.push(r_index);
+            tx_metadata.s_paths.push(r_path.clone());
+            tx_metadata.r_paths.push(r_path.clone());
+            tx_metadata.c_paths.push(self.c_paths[last].clone());
+            tx_metadata.deltas.push(BaseElement::ZERO);
+            tx_metadata.fees.push(BaseElement::ZERO);
+            tx_metadata.kinds.push(TransactionKind::NoOp);
+            tx_metadata.signatures.push(signature);
+            tx_metadata.memos.push(None);
+            tx_metadata.shares.push(None);
+        }
+
+        tx_metadata
+    }
+}
@@ -0,0 +1,802 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Periodic columns of AIR programs composed of sub-AIR programs.
+//!
+//! A composed program runs each of its sub-AIR programs over a [`Segment`] of a cycle of
+//! fixed length, the flags and constants of each sub-AIR program being placed over its own
+//! segment. A [`PeriodicColumnSet`] builds these columns, checking that every segment fits
+//! within the cycle, that columns repeating with a shorter period divide it, and that no two
+//! placements overlap within a column, so that a misplaced segment fails when building the
+//! columns rather than as a failed constraint when proving. Values placed over a segment must
+//! also be aligned with their own period, e.g. the cycle of the sub-AIR program they belong
+//! to, so that its flags line up with the round constants shared by all sub-AIR programs.
+//!
+//! Builder methods panic on invalid placements, and have `try_` counterparts returning a
+//! [`PeriodicError`] instead.
+//!
+//! ```
+//! use certificate_stark::periodic::{PeriodicColumnSet, Segment};
+//! use winterfell::math::{fields::f63::BaseElement, FieldElement};
+//!
+//! // a sub-AIR program of 3 steps evaluated from step 2 of a cycle of 8 steps
+//! let segment = Segment::new(2, 3);
+//! let mut columns = PeriodicColumnSet::new(2, 8);
+//! columns.mask(0, segment, BaseElement::ONE);
+//! columns.set(1, segment.step(2), BaseElement::ONE);
+//! assert_eq!(columns.into_columns()[0][2..6], [BaseElement::ONE, BaseElement::ONE, BaseElement::ONE, BaseElement::ZERO]);
+//! ```
+
+use core::fmt;
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// PERIODIC ERROR
+// ================================================================================================
+
+/// Errors raised when assembling periodic columns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeriodicError {
+    /// The column does not exist
+    ColumnOutOfBounds {
+        /// Index of the column
+        column: usize,
+        /// Number of columns
+        num_columns: usize,
+    },
+    /// Values were placed over steps of the column already holding values
+    Overlap {
+        /// Index of the column
+        column: usize,
+        /// Segment over which values were placed
+        segment: Segment,
+        /// Segment already placed in the column
+        placed: Segment,
+    },
+    /// Values were set over the whole column, which already holds values
+    ColumnAlreadySet {
+        /// Index of the column
+        column: usize,
+    },
+    /// No values were given for the column
+    EmptyValues {
+        /// Index of the column
+        column: usize,
+    },
+    /// The number of values of the column is not a power of two
+    InvalidPeriod {
+        /// Index of the column
+        column: usize,
+        /// Number of values of the column
+        period: usize,
+    },
+    /// The values repeated over the whole column do not divide its cycle
+    CycleMismatch {
+        /// Index of the column
+        column: usize,
+        /// Number of values of the column
+        period: usize,
+        /// Length of the cycle
+        cycle_length: usize,
+    },
+    /// The segment ends after the cycle
+    SegmentOutOfCycle {
+        /// Segment over which values were placed
+        segment: Segment,
+        /// Length of the cycle
+        cycle_length: usize,
+    },
+    /// The segment does not start at the beginning of a period of its values, or spans a
+    /// partial period of them after the first one
+    MisalignedSegment {
+        /// Index of the column
+        column: usize,
+        /// Segment over which values were placed
+        segment: Segment,
+        /// Number of values placed over the segment
+        period: usize,
+    },
+    /// Values were stitched after a number of steps which is not a multiple of the cycle
+    MisalignedOffset {
+        /// Index of the column
+        column: usize,
+        /// Step at which the values were stitched
+        offset: usize,
+        /// Length of the cycle
+        cycle_length: usize,
+    },
+}
+
+impl fmt::Display for PeriodicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PeriodicError::ColumnOutOfBounds {
+                column,
+                num_columns,
+            } => write!(f, "no column {} among {} columns", column, num_columns),
+            PeriodicError::Overlap {
+                column,
+                segment,
+                placed,
+            } => write!(
+                f,
+                "segment {:?} overlaps segment {:?} already placed in column {}",
+                segment, placed, column
+            ),
+            PeriodicError::ColumnAlreadySet { column } => {
+                write!(f, "column {} already holds values", column)
+            }
+            PeriodicError::EmptyValues { column } => {
+                write!(f, "no values to place in column {}", column)
+            }
+            PeriodicError::InvalidPeriod { column, period } => write!(
+                f,
+                "period {} of column {} is not a power of two",
+                period, column
+            ),
+            PeriodicError::CycleMismatch {
+                column,
+                period,
+                cycle_length,
+            } => write!(
+                f,
+                "period {} of column {} does not divide the cycle length {}",
+                period, column, cycle_length
+            ),
+            PeriodicError::SegmentOutOfCycle {
+                segment,
+                cycle_length,
+            } => write!(
+                f,
+                "segment {:?} exceeds the cycle length {}",
+                segment, cycle_length
+            ),
+            PeriodicError::MisalignedSegment {
+                column,
+                segment,
+                period,
+            } => write!(
+                f,
+                "segment {:?} of column {} is not aligned with the period {} of its values",
+                segment, column, period
+            ),
+            PeriodicError::MisalignedOffset {
+                column,
+                offset,
+                cycle_length,
+            } => write!(
+                f,
+                "values stitched at step {} of column {} are not aligned with the cycle length {}",
+                offset, column, cycle_length
+            ),
+        }
+    }
+}
+
+// SEGMENTS
+// ================================================================================================
+
+/// A range of steps of a cycle of the execution trace, over which a sub-AIR program is
+/// evaluated, its flags being set by periodic columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Segment {
+    /// First step of the segment within the cycle
+    pub start: usize,
+    /// Number of steps of the segment
+    pub length: usize,
+}
+
+impl Segment {
+    /// Returns the segment of `length` steps starting at step `start` of the cycle.
+    pub const fn new(start: usize, length: usize) -> Self {
+        Self { start, length }
+    }
+
+    /// Returns the step following the last one of this segment.
+    pub const fn end(&self) -> usize {
+        self.start + self.length
+    }
+
+    /// Returns the `index`-th step of this segment, within the cycle.
+    ///
+    /// # Panics
+    /// Panics if `index` is not smaller than the length of this segment.
+    pub fn step(&self, index: usize) -> usize {
+        assert!(
+            index < self.length,
+            "step {} out of a segment of {} steps",
+            index,
+            self.length
+        );
+        self.start + index
+    }
+
+    /// Returns whether this segment shares at least one step with `other`.
+    pub fn overlaps(&self, other: &Segment) -> bool {
+        self.start < other.end() && other.start < self.end()
+    }
+}
+
+// PERIODIC COLUMN SET
+// ================================================================================================
+
+/// Builder of the periodic columns of an AIR program composed of sub-AIR programs, each of
+/// them being evaluated over a `Segment` of a cycle of fixed length.
+///
+/// All columns span the whole cycle and are zero by default, so that the flags of a sub-AIR
+/// program only need to be placed over its own segment. Columns repeating with a shorter
+/// period, such as round constants, can be set as a whole with `set_column`, in which case
+/// nothing else can be placed in them.
+#[derive(Debug)]
+pub struct PeriodicColumnSet {
+    columns: Vec<Vec<BaseElement>>,
+    placed: Vec<Vec<Segment>>,
+    cycle_length: usize,
+}
+
+impl PeriodicColumnSet {
+    /// Returns a set of `num_columns` zero columns over a cycle of `cycle_length` steps.
+    ///
+    /// # Panics
+    /// Panics if `cycle_length` is not a power of two.
+    pub fn new(num_columns: usize, cycle_length: usize) -> Self {
+        assert!(
+            cycle_length.is_power_of_two(),
+            "cycle length {} is not a power of two",
+            cycle_length
+        );
+        Self {
+            columns: vec![vec![BaseElement::ZERO; cycle_length]; num_columns],
+            placed: vec![Vec::new(); num_columns],
+            cycle_length,
+        }
+    }
+
+    /// Returns the number of columns of this set.
+    pub fn num_columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Returns the length of the cycle of this set.
+    pub fn cycle_length(&self) -> usize {
+        self.cycle_length
+    }
+
+    /// Sets the column at `index` to `values`, repeated with their own period.
+    ///
+    /// # Panics
+    /// Panics if `try_set_column` returns an error.
+    pub fn set_column(&mut self, index: usize, values: Vec<BaseElement>) {
+        self.try_set_column(index, values)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Sets the column at `index` to `values`, repeated with their own period.
+    ///
+    /// Returns an error if the column does not exist or already holds values, or if the
+    /// number of values is not a power of two dividing the cycle length.
+    pub fn try_set_column(
+        &mut self,
+        index: usize,
+        values: Vec<BaseElement>,
+    ) -> Result<(), PeriodicError> {
+        self.check_index(index)?;
+        check_period(index, values.len())?;
+        if values.len() > self.cycle_length {
+            return Err(PeriodicError::CycleMismatch {
+                column: index,
+                period: values.len(),
+                cycle_length: self.cycle_length,
+            });
+        }
+        if !self.placed[index].is_empty() {
+            return Err(PeriodicError::ColumnAlreadySet { column: index });
+        }
+
+        self.columns[index] = values;
+        self.placed[index].push(Segment::new(0, self.cycle_length));
+        Ok(())
+    }
+
+    /// Sets the columns from `first_index` onwards to `columns`, each of them repeated with
+    /// its own period.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `set_column`, for any of the columns.
+    pub fn set_columns(&mut self, first_index: usize, columns: Vec<Vec<BaseElement>>) {
+        for (offset, column) in columns.into_iter().enumerate() {
+            self.set_column(first_index + offset, column);
+        }
+    }
+
+    /// Writes `values` over `segment` in the column at `index`, repeating them when they are
+    /// shorter than the segment and truncating them when they are longer.
+    ///
+    /// # Panics
+    /// Panics if `try_place` returns an error.
+    pub fn place(&mut self, index: usize, segment: Segment, values: &[BaseElement]) {
+        self.try_place(index, segment, values)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Writes `values` over `segment` in the column at `index`, repeating them when they are
+    /// shorter than the segment and truncating them when they are longer.
+    ///
+    /// Returns an error if the column does not exist, if the segment does not fit within the
+    /// cycle or overlaps values already set in the column, or if `values` is empty. Unless
+    /// they are a single value, their number must also be a power of two, the segment must
+    /// start at a multiple of it, and repeated values must span a whole number of periods.
+    pub fn try_place(
+        &mut self,
+        index: usize,
+        segment: Segment,
+        values: &[BaseElement],
+    ) -> Result<(), PeriodicError> {
+        self.check_index(index)?;
+        if segment.end() > self.cycle_length {
+            return Err(PeriodicError::SegmentOutOfCycle {
+                segment,
+                cycle_length: self.cycle_length,
+            });
+        }
+        if values.is_empty() {
+            return Err(PeriodicError::EmptyValues { column: index });
+        }
+        if values.len() > 1 {
+            check_period(index, values.len())?;
+            let mask = values.len() - 1;
+            if segment.start & mask != 0
+                || (segment.length > values.len() && segment.length & mask != 0)
+            {
+                return Err(PeriodicError::MisalignedSegment {
+                    column: index,
+                    segment,
+                    period: values.len(),
+                });
+            }
+        }
+        if let Some(placed) = self.placed[index]
+            .iter()
+            .find(|placed| placed.overlaps(&segment))
+        {
+            return Err(PeriodicError::Overlap {
+                column: index,
+                segment,
+                placed: *placed,
+            });
+        }
+
+        let column = &mut self.columns[index];
+        for (i, value) in column[segment.start..segment.end()].iter_mut().enumerate() {
+            *value = values[i % values.len()];
+        }
+        self.placed[index].push(segment);
+        Ok(())
+    }
+
+    /// Sets the column at `index` to `value` over `segment`.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `place`.
+    pub fn mask(&mut self, index: usize, segment: Segment, value: BaseElement) {
+        self.place(index, segment, &[value]);
+    }
+
+    /// Sets the column at `index` to `value` at step `step` of the cycle.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `place`.
+    pub fn set(&mut self, index: usize, step: usize, value: BaseElement) {
+        self.mask(index, Segment::new(step, 1), value);
+    }
+
+    /// Returns the periodic columns of this set.
+    pub fn into_columns(self) -> Vec<Vec<BaseElement>> {
+        self.columns
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns an error if there is no column at `index`.
+    fn check_index(&self, index: usize) -> Result<(), PeriodicError> {
+        if index >= self.columns.len() {
+            return Err(PeriodicError::ColumnOutOfBounds {
+                column: index,
+                num_columns: self.columns.len(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Stitches some periodic values `additional_columns` within `original_columns`.
+/// Their positions are determined by the provided `index_map`, a `vec` of tuples
+/// `(additional_index, original_index)` stitching for each the column at position
+/// `additional_index` within `additional_columns` within `original_columns` at
+/// position `original_index`.
+///
+/// When plugging different sub-AIR programs together, it can be used to plug program
+/// specific periodic values at portions of the execution when the involved sub-AIR
+/// program is being evaluated. `PeriodicColumnSet` places them at given steps instead.
+///
+/// # Panics
+/// Panics if `try_stitch` returns an error.
+pub fn stitch(
+    original_columns: &mut [Vec<BaseElement>],
+    additional_columns: Vec<Vec<BaseElement>>,
+    index_map: Vec<(usize, usize)>,
+    cycle_length: usize,
+) {
+    try_stitch(
+        original_columns,
+        additional_columns,
+        index_map,
+        cycle_length,
+    )
+    .unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Stitches some periodic values `additional_columns` within `original_columns`, as
+/// `stitch` does, for a sub-AIR program whose cycle is `cycle_length` steps long.
+///
+/// Returns an error if an index of `index_map` is out of bounds, if an additional column
+/// is not periodic with respect to the cycle, i.e. its length is not a power of two, or if
+/// it would be stitched after a number of steps which is not a multiple of the cycle. The
+/// original columns are left unchanged when an error is returned.
+///
+/// # Panics
+/// Panics if `cycle_length` is not a power of two.
+pub fn try_stitch(
+    original_columns: &mut [Vec<BaseElement>],
+    additional_columns: Vec<Vec<BaseElement>>,
+    index_map: Vec<(usize, usize)>,
+    cycle_length: usize,
+) -> Result<(), PeriodicError> {
+    assert!(
+        cycle_length.is_power_of_two(),
+        "cycle length {} is not a power of two",
+        cycle_length
+    );
+    // columns may be stitched several times, so that their offsets are tracked as they grow
+    let mut lengths: Vec<usize> = original_columns.iter().map(Vec::len).collect();
+    for &(add_index, org_index) in index_map.iter() {
+        if add_index >= additional_columns.len() {
+            return Err(PeriodicError::ColumnOutOfBounds {
+                column: add_index,
+                num_columns: additional_columns.len(),
+            });
+        }
+        if org_index >= original_columns.len() {
+            return Err(PeriodicError::ColumnOutOfBounds {
+                column: org_index,
+                num_columns: original_columns.len(),
+            });
+        }
+        check_period(add_index, additional_columns[add_index].len())?;
+        if lengths[org_index] & (cycle_length - 1) != 0 {
+            return Err(PeriodicError::MisalignedOffset {
+                column: org_index,
+                offset: lengths[org_index],
+                cycle_length,
+            });
+        }
+        lengths[org_index] += additional_columns[add_index].len();
+    }
+
+    for (add_index, org_index) in index_map {
+        original_columns[org_index].extend_from_slice(&additional_columns[add_index]);
+    }
+    Ok(())
+}
+
+/// Checks that all the provided periodic columns are compatible with a cycle of
+/// `cycle_length` steps, i.e. that their lengths are powers of two, hence either dividing
+/// the cycle or spanning a whole number of cycles.
+///
+/// # Panics
+/// Panics if `cycle_length` is not a power of two.
+pub fn check_columns(
+    columns: &[Vec<BaseElement>],
+    cycle_length: usize,
+) -> Result<(), PeriodicError> {
+    assert!(
+        cycle_length.is_power_of_two(),
+        "cycle length {} is not a power of two",
+        cycle_length
+    );
+    for (index, column) in columns.iter().enumerate() {
+        check_period(index, column.len())?;
+    }
+
+    Ok(())
+}
+
+/// Returns an error if `period` is not a power of two, which includes zero.
+fn check_period(column: usize, period: usize) -> Result<(), PeriodicError> {
+    if period == 0 {
+        return Err(PeriodicError::EmptyValues { column });
+    }
+    if !period.is_power_of_two() {
+        return Err(PeriodicError::InvalidPeriod { column, period });
+    }
+
+    Ok(())
+}
+
+// TESTS
+// ================================================================================================
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn periodic_column_set() {
+        use super::{PeriodicColumnSet, Segment};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement, StarkField};
+
+        let segment = Segment::new(2, 3);
+        assert_eq!(segment.end(), 5);
+        assert_eq!(segment.step(1), 3);
+        assert!(segment.overlaps(&Segment::new(4, 2)));
+        assert!(segment.overlaps(&Segment::new(0, 8)));
+        assert!(!segment.overlaps(&Segment::new(5, 3)));
+        assert!(!segment.overlaps(&Segment::new(0, 2)));
+
+        let mut layout = PeriodicColumnSet::new(3, 8);
+        assert_eq!(layout.num_columns(), 3);
+        assert_eq!(layout.cycle_length(), 8);
+        layout.mask(0, segment, BaseElement::ONE);
+        layout.place(
+            1,
+            Segment::new(4, 4),
+            &[BaseElement::ONE, BaseElement::ZERO],
+        );
+        layout.set(1, 0, BaseElement::from(7u8));
+        layout.set_column(2, vec![BaseElement::ONE; 2]);
+
+        let to_u64 = |column: &Vec<BaseElement>| -> Vec<u64> {
+            column.iter().map(|&element| element.to_repr()).collect()
+        };
+        let columns = layout.into_columns();
+        assert_eq!(to_u64(&columns[0]), [0, 0, 1, 1, 1, 0, 0, 0]);
+        assert_eq!(to_u64(&columns[1]), [7, 0, 0, 0, 1, 0, 1, 0]);
+        assert_eq!(to_u64(&columns[2]), [1, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps segment")]
+    fn periodic_column_set_overlap() {
+        use super::{PeriodicColumnSet, Segment};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut layout = PeriodicColumnSet::new(1, 16);
+        layout.mask(0, Segment::new(2, 6), BaseElement::ONE);
+        layout.set(0, 7, BaseElement::ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the cycle length")]
+    fn periodic_column_set_segment_out_of_cycle() {
+        use super::{PeriodicColumnSet, Segment};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut layout = PeriodicColumnSet::new(1, 16);
+        layout.mask(0, Segment::new(12, 5), BaseElement::ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not divide the cycle length")]
+    fn periodic_column_set_misaligned_period() {
+        use super::PeriodicColumnSet;
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut layout = PeriodicColumnSet::new(1, 16);
+        layout.set_column(0, vec![BaseElement::ONE; 32]);
+    }
+
+    #[test]
+    fn periodic_column_set_misaligned_segments() {
+        use super::{PeriodicColumnSet, PeriodicError, Segment};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let values = [BaseElement::ONE; 8];
+        let mut layout = PeriodicColumnSet::new(1, 64);
+        assert_eq!(
+            layout.try_set_column(0, vec![BaseElement::ONE; 6]),
+            Err(PeriodicError::InvalidPeriod {
+                column: 0,
+                period: 6
+            })
+        );
+        assert_eq!(
+            layout.try_place(0, Segment::new(0, 4), &values[..3]),
+            Err(PeriodicError::InvalidPeriod {
+                column: 0,
+                period: 3
+            })
+        );
+        // a sub-AIR program of cycle 8 starting in the middle of one of its cycles
+        assert_eq!(
+            layout.try_place(0, Segment::new(4, 8), &values),
+            Err(PeriodicError::MisalignedSegment {
+                column: 0,
+                segment: Segment::new(4, 8),
+                period: 8
+            })
+        );
+        // a sub-AIR program of cycle 8 repeated over a partial cycle
+        assert_eq!(
+            layout.try_place(0, Segment::new(8, 12), &values),
+            Err(PeriodicError::MisalignedSegment {
+                column: 0,
+                segment: Segment::new(8, 12),
+                period: 8
+            })
+        );
+        assert_eq!(
+            layout.try_place(1, Segment::new(0, 8), &values),
+            Err(PeriodicError::ColumnOutOfBounds {
+                column: 1,
+                num_columns: 1
+            })
+        );
+
+        // truncated and whole repetitions of aligned values are accepted
+        assert_eq!(layout.try_place(0, Segment::new(8, 5), &values), Ok(()));
+        assert_eq!(layout.try_place(0, Segment::new(16, 16), &values), Ok(()));
+        assert_eq!(
+            layout.try_place(0, Segment::new(24, 8), &values),
+            Err(PeriodicError::Overlap {
+                column: 0,
+                segment: Segment::new(24, 8),
+                placed: Segment::new(16, 16)
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "already holds values")]
+    fn periodic_column_set_column_over_placed_values() {
+        use super::{PeriodicColumnSet, Segment};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut layout = PeriodicColumnSet::new(1, 16);
+        layout.mask(0, Segment::new(0, 2), BaseElement::ONE);
+        layout.set_column(0, vec![BaseElement::ONE; 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps segment")]
+    fn periodic_column_set_placed_over_column() {
+        use super::{PeriodicColumnSet, Segment};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut layout = PeriodicColumnSet::new(1, 16);
+        layout.set_column(0, vec![BaseElement::ONE; 4]);
+        layout.mask(0, Segment::new(8, 2), BaseElement::ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "no column 2")]
+    fn periodic_column_set_missing_column() {
+        use super::PeriodicColumnSet;
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut layout = PeriodicColumnSet::new(2, 16);
+        layout.set(2, 0, BaseElement::ONE);
+    }
+
+    #[test]
+    fn periodic_stitch() {
+        use super::stitch;
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut columns = vec![vec![BaseElement::ZERO; 2]; 2];
+        stitch(
+            &mut columns,
+            vec![vec![BaseElement::ONE], vec![BaseElement::ONE; 2]],
+            vec![(1, 0), (0, 1)],
+            2,
+        );
+        assert_eq!(
+            columns[0],
+            [
+                BaseElement::ZERO,
+                BaseElement::ZERO,
+                BaseElement::ONE,
+                BaseElement::ONE
+            ]
+        );
+        assert_eq!(
+            columns[1],
+            [BaseElement::ZERO, BaseElement::ZERO, BaseElement::ONE]
+        );
+    }
+
+    #[test]
+    fn periodic_stitch_misaligned() {
+        use super::{check_columns, try_stitch, PeriodicError};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        let mut columns = vec![vec![BaseElement::ZERO; 4], Vec::new()];
+        // appending a cycle of 8 steps after 4 steps of another column
+        assert_eq!(
+            try_stitch(
+                &mut columns,
+                vec![vec![BaseElement::ONE; 8]],
+                vec![(0, 1), (0, 0)],
+                8
+            ),
+            Err(PeriodicError::MisalignedOffset {
+                column: 0,
+                offset: 4,
+                cycle_length: 8
+            })
+        );
+        // columns are left unchanged on errors
+        assert_eq!(columns[1].len(), 0);
+        // stitching the same column twice tracks its growing offset
+        assert_eq!(
+            try_stitch(
+                &mut columns,
+                vec![vec![BaseElement::ONE; 4]],
+                vec![(0, 1), (0, 1)],
+                8
+            ),
+            Err(PeriodicError::MisalignedOffset {
+                column: 1,
+                offset: 4,
+                cycle_length: 8
+            })
+        );
+        assert_eq!(
+            try_stitch(
+                &mut columns,
+                vec![vec![BaseElement::ONE; 6]],
+                vec![(0, 1)],
+                8
+            ),
+            Err(PeriodicError::InvalidPeriod {
+                column: 0,
+                period: 6
+            })
+        );
+        assert_eq!(
+            try_stitch(&mut columns, vec![Vec::new()], vec![(0, 2)], 8),
+            Err(PeriodicError::ColumnOutOfBounds {
+                column: 2,
+                num_columns: 2
+            })
+        );
+
+        assert_eq!(
+            try_stitch(
+                &mut columns,
+                vec![vec![BaseElement::ONE; 16]],
+                vec![(0, 1)],
+                8
+            ),
+            Ok(())
+        );
+        assert_eq!(check_columns(&columns, 8), Ok(()));
+        columns.push(vec![BaseElement::ONE; 3]);
+        assert_eq!(
+            check_columns(&columns, 8),
+            Err(PeriodicError::InvalidPeriod {
+                column: 2,
+                period: 3
+            })
+        );
+    }
+}
@@ -1,108 +1,994 @@
 use bitvec::{order::Lsb0, view::AsBits};
-use winterfell::{math::fields::f63::BaseElement, ProofOptions, Prover, Trace, TraceTable};
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
+    Air, ProofOptions, Prover, StarkProof, Trace, TraceInfo, TraceTable,
+};
 
+use super::air::{decode_memo, decode_recipient};
 use super::constants::*;
+use super::diagnostics::{
+    check_constraint_degrees, check_transition_constraints, ConstraintDegree,
+};
+use super::merkle::TreeHasher;
+use super::range;
 use super::schnorr;
 use super::trace::*;
+use super::Error;
+use super::MetadataError;
+use super::ProofChain;
+use super::ProverConfig;
+use super::ProverObserver;
 use super::PublicInputs;
 use super::TransactionAir;
 use super::TransactionMetadata;
+use super::{AccountState, BatchStatistics, Deposit, Memo, TransactionKind, Withdrawal};
+use crate::utils::rescue::{Hash, Rescue63};
+use core::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
-use merkle_const::PREV_TREE_ROOT_POS;
+use merkle_const::{
+    COLLECTOR_BIT_POS, HASH_RATE_WIDTH, HASH_STATE_WIDTH, PREV_TREE_ROOT_POS, RECEIVER_BIT_POS,
+    TRANSACTION_CYCLE_LENGTH as MERKLE_UPDATE_LENGTH,
+};
+use range_const::{MAX_RANGE_LOG, MAX_SIGNED_RANGE_LOG, RANGE_LOG};
+use rescue_const::HASH_CYCLE_LENGTH;
 use schnorr_const::AFFINE_POINT_WIDTH;
 
+#[cfg(all(feature = "std", not(feature = "tracing")))]
+use log::debug;
+#[cfg(feature = "std")]
+use std::time::Instant;
+#[cfg(feature = "tracing")]
+use tracing::debug_span;
+#[cfg(all(feature = "std", not(feature = "tracing")))]
+use winterfell::math::log2;
+
+#[cfg(not(feature = "std"))]
+use alloc::{sync::Arc, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
 // TRANSACTION PROVER
 // ================================================================================================
 
-pub struct TransactionProver {
+/// A prover generating state-transition proofs for batches of transactions, the nodes of
+/// the Merkle tree of accounts being hashed with `H`, Rescue63 by default.
+#[derive(Clone, Debug)]
+pub struct TransactionProver<H: TreeHasher = Rescue63> {
     options: ProofOptions,
+    range_log: usize,
+    signed_deltas: bool,
+    intermediate_roots: bool,
+    transactions_digest: bool,
+    nullifiers: bool,
+    batch_statistics: bool,
+    derived_indices: bool,
+    constraint_checks: bool,
+    config: ProverConfig,
+    observer: Option<Arc<dyn ProverObserver>>,
+    cancellation: Option<CancellationToken>,
+    _hasher: PhantomData<H>,
 }
 
 impl TransactionProver {
+    /// Returns a new `TransactionProver` generating proofs with the provided options.
     pub fn new(options: ProofOptions) -> Self {
-        Self { options }
+        Self::new_with_range_log(options, RANGE_LOG)
+    }
+
+    /// Returns a new `TransactionProver` generating proofs with the provided options,
+    /// with amounts, fees and balances being range-checked over `range_log` bits.
+    pub fn new_with_range_log(options: ProofOptions, range_log: usize) -> Self {
+        Self::new_with_hasher(options, range_log)
+    }
+
+    /// Proves the state-transition of the provided transactions as a chain of proofs of
+    /// sub-batches of at most `max_batch_size` transactions, as split by
+    /// `TransactionMetadata::sub_batches`.
+    ///
+    /// The transactions are validated first as a whole. Returns an error if they are invalid,
+    /// or if the proof generation of any sub-batch fails.
+    ///
+    /// # Panics
+    /// Panics if `max_batch_size` is zero, or if there are no transactions.
+    pub fn prove_chain(
+        &self,
+        tx_metadata: &TransactionMetadata,
+        max_batch_size: usize,
+    ) -> Result<ProofChain, Error> {
+        self.validate(tx_metadata)?;
+
+        let mut proof_chain = ProofChain::new(
+            tx_metadata.initial_roots[0],
+            tx_metadata.tree_depth,
+            tx_metadata.fee_collector,
+        )
+        .with_range_log(self.range_log)
+        .with_subnet_id(tx_metadata.subnet_id);
+        if self.signed_deltas {
+            proof_chain = proof_chain.with_signed_deltas();
+        }
+        for sub_batch in tx_metadata.sub_batches(max_batch_size) {
+            let proof = self.prove(&sub_batch)?;
+            proof_chain
+                .push(proof, &self.public_inputs(&sub_batch))
+                .expect("sub-batches are chained by construction");
+        }
+
+        Ok(proof_chain)
+    }
+}
+
+impl<H: TreeHasher> TransactionProver<H> {
+    /// Returns a new `TransactionProver` generating proofs with the provided options,
+    /// with amounts, fees and balances being range-checked over `range_log` bits and
+    /// the nodes of the Merkle tree of accounts being hashed with `H`.
+    pub fn new_with_hasher(options: ProofOptions, range_log: usize) -> Self {
+        assert!(
+            range_log > 0 && range_log <= MAX_RANGE_LOG,
+            "range log must be between 1 and {}",
+            MAX_RANGE_LOG
+        );
+        Self {
+            options,
+            range_log,
+            signed_deltas: false,
+            intermediate_roots: false,
+            transactions_digest: false,
+            nullifiers: false,
+            batch_statistics: false,
+            derived_indices: false,
+            constraint_checks: false,
+            config: ProverConfig::default(),
+            observer: None,
+            cancellation: None,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns this prover, generating proofs supporting refunds, i.e. `TransactionKind::Refund`
+    /// transactions of a negative amount within [-2^range_log, 0), which credit their sender
+    /// and debit their receiver.
+    ///
+    /// Refunds must be signed by the receiver over its own nonce, which they increment, and
+    /// transfer no fee, as enforced by the AIR program.
+    ///
+    /// # Panics
+    /// Panics if the range log of this prover exceeds `MAX_SIGNED_RANGE_LOG`.
+    pub fn with_signed_deltas(mut self) -> Self {
+        assert!(
+            self.range_log <= MAX_SIGNED_RANGE_LOG,
+            "range log of signed deltas must be at most {}",
+            MAX_SIGNED_RANGE_LOG
+        );
+        self.signed_deltas = true;
+        self
+    }
+
+    /// Returns this prover, generating proofs exposing the Merkle tree roots in between
+    /// every two consecutive transactions in their public inputs.
+    pub fn with_intermediate_roots(mut self) -> Self {
+        self.intermediate_roots = true;
+        self
+    }
+
+    /// Returns this prover, generating proofs exposing the running digest of the (sender,
+    /// receiver, amount) tuples of the proven transactions in their public inputs.
+    pub fn with_transactions_digest(mut self) -> Self {
+        self.transactions_digest = true;
+        self
+    }
+
+    /// Returns this prover, generating proofs exposing the commitment to the nullifiers of the
+    /// proven transactions in their public inputs.
+    pub fn with_nullifiers(mut self) -> Self {
+        self.nullifiers = true;
+        self
+    }
+
+    /// Returns this prover, generating proofs exposing the number of proven transactions, no-ops
+    /// excluded, and the sum of their amounts in their public inputs.
+    pub fn with_batch_statistics(mut self) -> Self {
+        self.batch_statistics = true;
+        self
+    }
+
+    /// Returns this prover, generating proofs binding the indices of senders and receivers to
+    /// the leaves derived from their public keys by `account_index`.
+    ///
+    /// Transactions are then checked with `TransactionMetadata::verify_derived_indices`
+    /// before being proven. Within the proof, the receivers of split transfers and the leaves
+    /// of no-ops are not bound, their keys being absent from the execution trace.
+    pub fn with_derived_indices(mut self) -> Self {
+        self.derived_indices = true;
+        self
+    }
+
+    /// Returns this prover, evaluating the transition constraints over the execution trace
+    /// before generating a proof, and reporting the first one not satisfied.
+    ///
+    /// This is meant for debugging the AIR program, as it roughly doubles the proving time.
+    pub fn with_constraint_checks(mut self) -> Self {
+        self.constraint_checks = true;
+        self
+    }
+
+    /// Returns this prover, generating proofs with the computing resources of `config`.
+    pub fn with_config(mut self, config: ProverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns this prover, reporting the progress of the proof generation to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn ProverObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Returns this prover, aborting the proof generation once `token` is cancelled.
+    ///
+    /// The token is checked before filling each transaction segment of the trace, and
+    /// before handing the trace over to winterfell, which cannot be interrupted.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Returns the proof options used by this prover.
+    pub fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    /// Returns the bit length of the range proofs on amounts, fees and balances.
+    pub fn range_log(&self) -> usize {
+        self.range_log
+    }
+
+    /// Returns whether the generated proofs support refunds of negative amounts.
+    pub fn has_signed_deltas(&self) -> bool {
+        self.signed_deltas
+    }
+
+    /// Returns an estimate of the memory taken by the execution trace of a batch of
+    /// `num_transactions` transactions, in bytes.
+    ///
+    /// This accounts for the trace table built by `build_trace` only, i.e. for its width times
+    /// its length, padding transactions included, times the size of a field element. The
+    /// low-degree extension of the trace computed by winterfell takes at least blowup factor
+    /// times as much. The segments built by `trace_segments` instead take the size of a single
+    /// transaction each, whatever the number of transactions.
+    pub fn estimate_trace_memory(&self, num_transactions: usize) -> usize {
+        TRACE_WIDTH
+            * num_transactions.next_power_of_two()
+            * TRANSACTION_CYCLE_LENGTH
+            * BaseElement::ELEMENT_BYTES
+    }
+
+    /// Returns whether the generated proofs expose the intermediate Merkle tree roots.
+    pub fn exposes_intermediate_roots(&self) -> bool {
+        self.intermediate_roots
+    }
+
+    /// Returns whether the generated proofs expose the running digest of transactions.
+    pub fn exposes_transactions_digest(&self) -> bool {
+        self.transactions_digest
+    }
+
+    /// Returns whether the generated proofs expose the commitment to nullifiers.
+    pub fn exposes_nullifiers(&self) -> bool {
+        self.nullifiers
+    }
+
+    /// Returns whether the generated proofs expose the number and volume of transactions.
+    pub fn exposes_batch_statistics(&self) -> bool {
+        self.batch_statistics
     }
 
+    /// Returns whether the generated proofs bind the indices of accounts to their keys.
+    pub fn binds_derived_indices(&self) -> bool {
+        self.derived_indices
+    }
+
+    /// Returns the configuration of the computing resources used by this prover.
+    pub fn config(&self) -> &ProverConfig {
+        &self.config
+    }
+
+    /// Returns the public inputs of a state-transition proof for the provided transactions.
+    pub fn public_inputs(&self, tx_metadata: &TransactionMetadata) -> PublicInputs {
+        let mut pub_inputs = tx_metadata
+            .public_inputs()
+            .with_range_log(self.range_log)
+            .with_hasher::<H>();
+        if self.signed_deltas {
+            pub_inputs = pub_inputs.with_signed_deltas();
+        }
+        if self.transactions_digest {
+            pub_inputs = pub_inputs.with_transactions_digest(tx_metadata.transactions_digest());
+        }
+        if self.nullifiers {
+            pub_inputs = pub_inputs.with_nullifiers(&tx_metadata.nullifiers());
+        }
+        if self.batch_statistics {
+            pub_inputs = pub_inputs.with_batch_statistics(tx_metadata.batch_statistics());
+        }
+        if self.derived_indices {
+            pub_inputs = pub_inputs.with_derived_indices();
+        }
+
+        if self.intermediate_roots {
+            // padding transactions leave the final root unchanged
+            let mut roots = tx_metadata.initial_roots[1..].to_vec();
+            roots.resize(
+                tx_metadata.num_transactions().next_power_of_two() - 1,
+                tx_metadata.final_root,
+            );
+            pub_inputs.with_intermediate_roots(&roots)
+        } else {
+            pub_inputs
+        }
+    }
+
+    /// Proves the state-transition of the provided transactions.
+    ///
+    /// The execution trace is built one transaction segment at a time, directly into the
+    /// trace table. Winterfell still requires the whole trace table, and its low-degree
+    /// extension, to be held in memory, so that peak memory grows linearly with the number
+    /// of transactions.
+    ///
+    /// The transactions are validated first, against the range log, the signedness of deltas
+    /// and the hash function of this prover, and against their derived indices if bound.
+    /// Returns an error if they are invalid, or if the proof generation fails or is cancelled.
+    pub fn prove(&self, tx_metadata: &TransactionMetadata) -> Result<StarkProof, Error> {
+        self.validate(tx_metadata)?;
+        if self.derived_indices {
+            tx_metadata.verify_derived_indices()?;
+        }
+
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
+        debug!(
+            "Generating proof for proving update in a Merkle tree of depth {}\n\
+            ---------------------",
+            tx_metadata.tree_depth
+        );
+
+        // generate the execution trace
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
+        let now = Instant::now();
+        let trace = self.try_build_trace(tx_metadata)?;
+        #[cfg(all(feature = "std", not(feature = "tracing")))]
+        debug!(
+            "Generated execution trace of {} registers and 2^{} steps in {} ms",
+            trace.width(),
+            log2(trace.length()),
+            now.elapsed().as_millis()
+        );
+
+        // generate the proof
+        self.prove_trace(tx_metadata, trace)
+    }
+
+    /// Proves the state-transition of the provided transactions, and returns the proof along
+    /// with a report on its generation.
+    ///
+    /// Returns an error if the transactions are invalid, or if the proof generation fails or
+    /// is cancelled.
+    pub fn prove_with_report(
+        &self,
+        tx_metadata: &TransactionMetadata,
+    ) -> Result<(StarkProof, ProofReport), Error> {
+        self.validate(tx_metadata)?;
+        if self.derived_indices {
+            tx_metadata.verify_derived_indices()?;
+        }
+        #[cfg(feature = "std")]
+        let now = Instant::now();
+        let trace = self.try_build_trace(tx_metadata)?;
+        #[cfg(feature = "std")]
+        let trace_time = now.elapsed();
+        #[cfg(not(feature = "std"))]
+        let trace_time = Duration::default();
+        let (trace_width, trace_length) = (trace.width(), trace.length());
+
+        #[cfg(feature = "std")]
+        let now = Instant::now();
+        let proof = self.prove_trace(tx_metadata, trace)?;
+        #[cfg(feature = "std")]
+        let proving_time = now.elapsed();
+        #[cfg(not(feature = "std"))]
+        let proving_time = Duration::default();
+
+        let report = ProofReport {
+            num_transactions: tx_metadata.num_transactions(),
+            trace_width,
+            trace_length,
+            proof_size: proof.to_bytes().len(),
+            security_level: proof.security_level(true),
+            trace_time,
+            proving_time,
+        };
+
+        Ok((proof, report))
+    }
+
+    /// Builds the execution trace of the state-transition of the provided transactions.
+    ///
+    /// The trace holds one segment per transaction, and its length must be a power of two:
+    /// when the number of transactions is not, they are padded with no-op transactions on
+    /// the account of the last receiver, which leave the state of the tree unchanged.
+    ///
+    /// # Panics
+    /// Panics if there are no transactions, if the trace would exceed the memory cap of the
+    /// `ProverConfig` of this prover, or if its cancellation token is cancelled while building
+    /// the trace; `try_build_trace` returns an error instead.
+    pub fn build_trace(&self, tx_metadata: &TransactionMetadata) -> TraceTable<BaseElement> {
+        self.try_build_trace(tx_metadata)
+            .unwrap_or_else(|err| panic!("failed to build the execution trace: {}", err))
+    }
+
+    /// Builds the execution trace of the state-transition of the provided transactions, as
+    /// `build_trace` does, unless the cancellation token of this prover gets cancelled.
+    ///
+    /// Returns `Error::Cancelled` if it does, the remaining transaction segments being left
+    /// unfilled, or `Error::TooManyTransactions` or `Error::MemoryLimitExceeded` if the batch
+    /// would exceed the caps of the `ProverConfig` of this prover, in which case the trace is
+    /// not allocated.
+    ///
+    /// # Panics
+    /// Panics if there are no transactions.
+    //
     // The trace is composed as follows:
-    // (note that sigma here refers to sender_balance - delta)
+    // (note that sigma here refers to sender_balance - delta - fee, and rho to the receiver's
+    // updated balance)
     //
-    // | 4 * HASH_STATE + 2 + HASH_RATE |      2 * AFF_POINT + 3      | number of registers
-    // |          merkle::init          | copy_keys_delta_sigma_nonce | sub-programs
-    // |         merkle::update         | copy_keys_delta_sigma_nonce |
-    // |         schnorr::init          | copy_keys_delta_sigma_nonce |
-    // |         schnorr::verif         | range_proof_delta_and_sigma |
-    pub fn build_trace(&self, tx_metadata: &TransactionMetadata) -> TraceTable<BaseElement> {
-        let initial_roots = &tx_metadata.initial_roots;
-        let s_old_values = &tx_metadata.s_old_values;
-        let r_old_values = &tx_metadata.r_old_values;
-        let s_indices = &tx_metadata.s_indices;
-        let r_indices = &tx_metadata.r_indices;
-        let s_paths = &tx_metadata.s_paths;
-        let r_paths = &tx_metadata.r_paths;
-        let deltas = &tx_metadata.deltas;
-        let signatures = &tx_metadata.signatures;
-        let num_transactions = tx_metadata.initial_roots.len();
-        // allocate memory to hold the trace table
+    // (the hash state of the running digest of transactions and of the nullifiers overlays idle
+    // Merkle registers during the Schnorr signature verification)
+    //
+    // | 6 * HASH_STATE + 3 + HASH_RATE |             2 * AFF_POINT + 11            | SPLIT + 4 + MEMO + 2 * HASH_RATE        | number of registers
+    // |          merkle::init          | copy_keys_delta_sigma_nonce_fee_flags_rho | split_index_noop_memo_digest_null_stats | sub-programs
+    // |         merkle::update         | copy_keys_delta_sigma_nonce_fee_flags_rho | split_index_noop_memo_digest_null_stats |
+    // |         schnorr::init          | copy_keys_delta_sigma_nonce_fee_flags_rho | split_index_noop_memo_digest_null_stats |
+    // |  schnorr::verif + digest_null  |    range_proof_delta_sigma_fee_and_rho    | split_index_noop_memo_digest_null_stats |
+    pub fn try_build_trace(
+        &self,
+        tx_metadata: &TransactionMetadata,
+    ) -> Result<TraceTable<BaseElement>, Error> {
+        self.check_num_transactions(tx_metadata)?;
+        if let Some(limit) = self.config.max_trace_memory() {
+            let required = self.estimate_trace_memory(tx_metadata.num_transactions());
+            if required > limit {
+                return Err(Error::MemoryLimitExceeded { required, limit });
+            }
+        }
+        let builder = SegmentBuilder::<H>::new(tx_metadata, self.range_log, self.signed_deltas);
+        let num_transactions = builder.num_segments();
+        #[cfg(feature = "tracing")]
+        let _span = debug_span!(
+            "build_trace",
+            num_transactions,
+            trace_length = num_transactions * TRANSACTION_CYCLE_LENGTH,
+            depth = builder.tx_metadata.tree_depth
+        )
+        .entered();
+        if let Some(observer) = &self.observer {
+            observer.on_trace_started(
+                num_transactions,
+                num_transactions * TRANSACTION_CYCLE_LENGTH,
+            );
+        }
+        // allocate memory to hold the trace table, as winterfell proves over whole tables
         let mut trace = TraceTable::new(TRACE_WIDTH, num_transactions * TRANSACTION_CYCLE_LENGTH);
-        trace
-            .fragments(TRANSACTION_CYCLE_LENGTH)
-            .for_each(|mut transaction_trace| {
-                let i = transaction_trace.index();
-                let delta_bytes = deltas[i].to_bytes();
-                let delta_bits = delta_bytes.as_bits::<Lsb0>();
-                let sigma_bytes = (s_old_values[i][AFFINE_POINT_WIDTH] - deltas[i]).to_bytes();
-                let sigma_bits = sigma_bytes.as_bits::<Lsb0>();
-                let message = super::build_tx_message(
-                    &s_old_values[i][0..AFFINE_POINT_WIDTH],
-                    &r_old_values[i][0..AFFINE_POINT_WIDTH],
-                    deltas[i],
-                    s_old_values[i][AFFINE_POINT_WIDTH + 1],
-                );
-                let (pkey_point, sig_bytes, sig_hash_bytes) =
-                    schnorr::build_sig_info(&message, &signatures[i]);
-                let sig_bits = sig_bytes.as_bits::<Lsb0>();
-                let sig_hash_bits = sig_hash_bytes.as_bits::<Lsb0>();
-                transaction_trace.fill(
-                    |state| {
-                        init_transaction_state(
-                            initial_roots[i],
-                            s_old_values[i],
-                            r_old_values[i],
-                            deltas[i],
-                            state,
-                        );
-                    },
-                    |step, state| {
-                        update_transaction_state(
-                            step,
-                            s_indices[i],
-                            r_indices[i],
-                            s_paths[i].clone(),
-                            r_paths[i].clone(),
-                            delta_bits,
-                            sigma_bits,
-                            signatures[i],
-                            sig_bits,
-                            sig_hash_bits,
-                            message,
-                            pkey_point,
-                            state,
-                        );
-                    },
-                )
-            });
-        trace
+        // Each transaction segment is built on its own, and then copied into its fragment of the
+        // trace table, so that only one segment per thread is held besides the table. Segments
+        // are built in parallel when the `concurrent` feature is enabled.
+        self.config.install(|| {
+            trace
+                .fragments(TRANSACTION_CYCLE_LENGTH)
+                .for_each(|mut fragment| {
+                    // segments cannot be skipped once the iteration started, the remaining
+                    // ones are left empty instead
+                    if self.is_cancelled() {
+                        return;
+                    }
+                    let segment = builder.build(fragment.index());
+                    for step in 0..segment.length() {
+                        fragment.update_row(step, segment.row(step));
+                    }
+                    if let Some(observer) = &self.observer {
+                        observer.on_transaction_filled(segment.index());
+                    }
+                })
+        });
+        if self.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        if let Some(observer) = &self.observer {
+            observer.on_trace_built(trace.width(), trace.length());
+        }
+        Ok(trace)
+    }
+
+    /// Builds the execution trace of the state-transition of the provided transactions one
+    /// transaction segment at a time, as an iterator over the segments of the trace built by
+    /// `build_trace`, in order.
+    ///
+    /// Each segment is only built when the iterator reaches it, and holds the
+    /// `TRANSACTION_CYCLE_LENGTH` steps of a single transaction, so that the memory taken by
+    /// the trace is bounded by the size of a segment, whatever the number of transactions.
+    /// This lets traces of large batches be inspected or exported, e.g. with
+    /// `export::write_csv_segments`, without holding the whole trace table. Proving still
+    /// requires the whole table, as built by `build_trace`.
+    ///
+    /// The memory cap of the `ProverConfig` of this prover does not apply to segments, nor
+    /// does its cancellation token, which the caller can check between segments instead.
+    ///
+    /// Returns `Error::TooManyTransactions` if the batch holds more transactions than allowed
+    /// by the `ProverConfig` of this prover.
+    ///
+    /// # Panics
+    /// Panics if there are no transactions.
+    pub fn trace_segments(
+        &self,
+        tx_metadata: &TransactionMetadata,
+    ) -> Result<TraceSegments<H>, Error> {
+        self.check_num_transactions(tx_metadata)?;
+        Ok(TraceSegments {
+            builder: SegmentBuilder::new(tx_metadata, self.range_log, self.signed_deltas),
+            next_index: 0,
+        })
+    }
+
+    /// Evaluates the transition constraints over the provided execution trace of the given
+    /// transactions, as built by `build_trace`.
+    ///
+    /// Returns `Error::UnsatisfiedConstraint` with the slot, names, step and registers of the
+    /// first constraint not satisfied, if any.
+    pub fn check_constraints(
+        &self,
+        tx_metadata: &TransactionMetadata,
+        trace: &TraceTable<BaseElement>,
+    ) -> Result<(), Error> {
+        let air = TransactionAir::<H>::new(
+            TraceInfo::new(trace.width(), trace.length()),
+            self.public_inputs(tx_metadata),
+            self.options.clone(),
+        );
+        check_transition_constraints(&air, trace).map_err(Error::UnsatisfiedConstraint)
+    }
+
+    /// Evaluates the degree of each transition constraint over the provided execution trace of
+    /// the given transactions, as built by `build_trace`, against the degree declared for it
+    /// by the AIR program.
+    ///
+    /// A declared degree off from the actual one is otherwise only noticed when proofs fail to
+    /// verify. This interpolates the constraint evaluations over an extended domain, and is
+    /// thus about as costly as generating a proof.
+    pub fn constraint_degrees(
+        &self,
+        tx_metadata: &TransactionMetadata,
+        trace: &TraceTable<BaseElement>,
+    ) -> Vec<ConstraintDegree> {
+        let air = TransactionAir::<H>::new(
+            TraceInfo::new(trace.width(), trace.length()),
+            self.public_inputs(tx_metadata),
+            self.options.clone(),
+        );
+        check_constraint_degrees(&air, trace)
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Checks that the provided transactions can be proven with the range log, the signedness
+    /// of deltas and the hash function of this prover.
+    fn validate(&self, tx_metadata: &TransactionMetadata) -> Result<(), MetadataError> {
+        if self.signed_deltas {
+            tx_metadata.validate_signed_with_hasher::<H>(self.range_log)
+        } else {
+            tx_metadata.validate_with_hasher::<H>(self.range_log)
+        }
+    }
+
+    /// Generates the proof of the provided execution trace of the given transactions.
+    fn prove_trace(
+        &self,
+        tx_metadata: &TransactionMetadata,
+        trace: TraceTable<BaseElement>,
+    ) -> Result<StarkProof, Error> {
+        #[cfg(feature = "tracing")]
+        let _span = debug_span!(
+            "generate_proof",
+            num_transactions = tx_metadata.num_transactions(),
+            trace_length = trace.length(),
+            depth = tx_metadata.tree_depth
+        )
+        .entered();
+        let prover = BatchProver::<H> {
+            options: self.options.clone(),
+            tree_depth: tx_metadata.tree_depth,
+            fee_collector: tx_metadata.fee_collector,
+            range_log: self.range_log,
+            signed_deltas: self.signed_deltas,
+            subnet_id: tx_metadata.subnet_id,
+            epoch: tx_metadata.epoch,
+            intermediate_roots: self.intermediate_roots,
+            transactions_digest: self.transactions_digest,
+            nullifiers: self.nullifiers,
+            batch_statistics: self.batch_statistics,
+            derived_indices: self.derived_indices,
+            _hasher: PhantomData,
+        };
+        if self.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        if self.constraint_checks {
+            self.check_constraints(tx_metadata, &trace)?;
+        }
+        if let Some(observer) = &self.observer {
+            observer.on_proving_started();
+        }
+        let proof = self.config.install(|| prover.prove(trace))?;
+        if let Some(observer) = &self.observer {
+            observer.on_proof_generated(proof.to_bytes().len());
+        }
+
+        Ok(proof)
     }
+
+    /// Returns `Error::TooManyTransactions` if the provided batch holds more transactions
+    /// than allowed by the `ProverConfig` of this prover.
+    fn check_num_transactions(&self, tx_metadata: &TransactionMetadata) -> Result<(), Error> {
+        if let Some(limit) = self.config.max_transactions() {
+            let num_transactions = tx_metadata.num_transactions();
+            if num_transactions > limit {
+                return Err(Error::TooManyTransactions {
+                    num_transactions,
+                    limit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the cancellation token of this prover, if any, was cancelled.
+    fn is_cancelled(&self) -> bool {
+        matches!(&self.cancellation, Some(token) if token.is_cancelled())
+    }
+}
+
+// TRACE SEGMENTS
+// ================================================================================================
+
+/// The steps of the execution trace covering a single transaction, as built by
+/// `TransactionProver::trace_segments`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceSegment {
+    index: usize,
+    // the TRACE_WIDTH registers of each step, one step after the other
+    rows: Vec<BaseElement>,
 }
 
-impl Prover for TransactionProver {
+impl TraceSegment {
+    /// Returns the position of the transaction of this segment in its padded batch.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the step of the whole execution trace at which this segment starts.
+    pub fn first_step(&self) -> usize {
+        self.index * TRANSACTION_CYCLE_LENGTH
+    }
+
+    /// Returns the number of steps of this segment, i.e. `TRANSACTION_CYCLE_LENGTH`.
+    pub fn length(&self) -> usize {
+        self.rows.len() / TRACE_WIDTH
+    }
+
+    /// Returns the registers of this segment at the provided step, relative to its first step.
+    ///
+    /// # Panics
+    /// Panics if `step` is not below the length of this segment.
+    pub fn row(&self, step: usize) -> &[BaseElement] {
+        &self.rows[step * TRACE_WIDTH..(step + 1) * TRACE_WIDTH]
+    }
+
+    /// Returns the value of `register` at the provided step, relative to the first step of
+    /// this segment.
+    ///
+    /// # Panics
+    /// Panics if `register` is not below `TRACE_WIDTH`, or `step` below the length of this
+    /// segment.
+    pub fn get(&self, register: usize, step: usize) -> BaseElement {
+        self.row(step)[register]
+    }
+
+    fn set(&mut self, register: usize, step: usize, value: BaseElement) {
+        self.rows[step * TRACE_WIDTH + register] = value;
+    }
+}
+
+/// An iterator over the segments of the execution trace of a batch of transactions, each
+/// segment being built when reached, as returned by `TransactionProver::trace_segments`.
+#[derive(Debug)]
+pub struct TraceSegments<H: TreeHasher = Rescue63> {
+    builder: SegmentBuilder<H>,
+    next_index: usize,
+}
+
+impl<H: TreeHasher> Iterator for TraceSegments<H> {
+    type Item = TraceSegment;
+
+    fn next(&mut self) -> Option<TraceSegment> {
+        if self.next_index == self.builder.num_segments() {
+            return None;
+        }
+        let segment = self.builder.build(self.next_index);
+        self.next_index += 1;
+        Some(segment)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.builder.num_segments() - self.next_index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<H: TreeHasher> ExactSizeIterator for TraceSegments<H> {}
+
+/// Builds the segments of the execution trace of a batch of transactions, padded with no-ops,
+/// from the values shared by all segments, computed once beforehand.
+#[derive(Debug)]
+struct SegmentBuilder<H: TreeHasher> {
+    tx_metadata: TransactionMetadata,
+    range_log: usize,
+    signed_deltas: bool,
+    // the running digest of transactions, the commitment to nullifiers and the statistics of
+    // the batch are carried over from one transaction to the next, and are computed beforehand
+    // so that segments can be built independently
+    tx_digests: Vec<Hash>,
+    nullifiers_commitments: Vec<Hash>,
+    batch_statistics: Vec<[BaseElement; 2]>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> SegmentBuilder<H> {
+    /// Returns a builder of the segments of the provided transactions, padded with no-ops,
+    /// with range proofs over `range_log` bits, on signed deltas if `signed_deltas`.
+    fn new(tx_metadata: &TransactionMetadata, range_log: usize, signed_deltas: bool) -> Self {
+        let tx_metadata = tx_metadata.padded_with_hasher::<H>();
+        SegmentBuilder {
+            range_log,
+            signed_deltas,
+            tx_digests: tx_metadata.running_transactions_digests(),
+            nullifiers_commitments: tx_metadata.running_nullifiers_commitments(),
+            batch_statistics: tx_metadata.running_batch_statistics(),
+            tx_metadata,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns the number of segments of the trace, padding transactions included.
+    fn num_segments(&self) -> usize {
+        self.tx_metadata.initial_roots.len()
+    }
+
+    /// Builds the segment of the transaction at position `index`.
+    fn build(&self, index: usize) -> TraceSegment {
+        let mut segment = build_transaction_segment::<H>(
+            &self.tx_metadata,
+            self.range_log,
+            self.signed_deltas,
+            &self.tx_digests,
+            &self.nullifiers_commitments,
+            &self.batch_statistics,
+            index,
+        );
+        if index == 0 {
+            stabilize_constraint_degrees(&mut segment);
+        }
+        segment
+    }
+}
+
+// CANCELLATION TOKEN
+// ================================================================================================
+
+/// A token allowing to abort an in-flight proof generation from another thread, e.g. when
+/// the request it serves is superseded.
+///
+/// Clones of a token share its state, so that cancelling any of them cancels the proof
+/// generations of all the provers holding one.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Returns a new token, not cancelled yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels this token, and all its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether this token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// PROOF REPORT
+// ================================================================================================
+
+/// A report on the generation of a state-transition proof, allowing to log and compare
+/// proving configurations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofReport {
+    /// Number of transactions proven
+    pub num_transactions: usize,
+    /// Number of registers of the execution trace
+    pub trace_width: usize,
+    /// Number of steps of the execution trace
+    pub trace_length: usize,
+    /// Size of the serialized proof, in bytes
+    pub proof_size: usize,
+    /// Conjectured security level of the proof, in bits
+    pub security_level: u32,
+    /// Time spent building the execution trace, zero when the `std` feature is disabled
+    pub trace_time: Duration,
+    /// Time spent generating the proof from the execution trace, zero when the `std`
+    /// feature is disabled
+    pub proving_time: Duration,
+}
+
+impl fmt::Display for ProofReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} transactions, trace of {} registers and {} steps built in {} ms, \
+            proof of {} bytes and {} bits of conjectured security generated in {} ms",
+            self.num_transactions,
+            self.trace_width,
+            self.trace_length,
+            self.trace_time.as_millis(),
+            self.proof_size,
+            self.security_level,
+            self.proving_time.as_millis(),
+        )
+    }
+}
+
+// BATCH PROVER
+// ================================================================================================
+
+/// Winterfell prover for the execution trace of a given batch of transactions
+pub(crate) struct BatchProver<H: TreeHasher> {
+    options: ProofOptions,
+    tree_depth: usize,
+    fee_collector: usize,
+    range_log: usize,
+    signed_deltas: bool,
+    subnet_id: BaseElement,
+    epoch: u32,
+    intermediate_roots: bool,
+    transactions_digest: bool,
+    nullifiers: bool,
+    batch_statistics: bool,
+    derived_indices: bool,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: TreeHasher> Prover for BatchProver<H> {
     type BaseField = BaseElement;
-    type Air = TransactionAir;
+    type Air = TransactionAir<H>;
     type Trace = TraceTable<BaseElement>;
 
     fn get_pub_inputs(&self, trace: &Self::Trace) -> PublicInputs {
         let last_step = trace.length() - 1;
 
+        // Recover the withdrawals, deposits and memos from the values copied at the beginning
+        // of each transaction, and from the receiver's index bits for deposits
+        let mut withdrawals = Vec::new();
+        let mut deposits = Vec::new();
+        let mut memos = Vec::new();
+        for tx_index in 0..trace.length() / TRANSACTION_CYCLE_LENGTH {
+            let first_step = tx_index * TRANSACTION_CYCLE_LENGTH;
+            let step = first_step + 1;
+            if trace.get(WITHDRAWAL_FLAG_POS, step) == BaseElement::ONE {
+                let recipient: Vec<BaseElement> = (0..AFFINE_POINT_WIDTH)
+                    .map(|offset| trace.get(RECEIVER_KEY_POINT_POS + offset, step))
+                    .collect();
+                withdrawals.push(Withdrawal::new(
+                    tx_index,
+                    trace.get(DELTA_COPY_POS, step).to_repr(),
+                    decode_recipient(&recipient),
+                ));
+            }
+            if trace.get(DEPOSIT_FLAG_POS, step) == BaseElement::ONE {
+                let receiver = (0..self.tree_depth)
+                    .map(|level| {
+                        let bit = trace.get(
+                            RECEIVER_BIT_POS,
+                            first_step + (level + 1) * HASH_CYCLE_LENGTH,
+                        );
+                        (bit.to_repr() as usize) << level
+                    })
+                    .sum();
+                deposits.push(Deposit::new(
+                    tx_index,
+                    trace.get(DELTA_COPY_POS, step).to_repr(),
+                    receiver,
+                ));
+            }
+            let memo: Vec<BaseElement> = (0..MEMO_WIDTH)
+                .map(|offset| trace.get(MEMO_POS + offset, step))
+                .collect();
+            if memo.iter().any(|&element| element != BaseElement::ZERO) {
+                memos.push(Memo::new(tx_index, decode_memo(&memo)));
+            }
+        }
+
+        // Recover the intermediate roots, if exposed, from the beginning of each transaction
+        // but the first
+        let intermediate_roots = if self.intermediate_roots {
+            Some(
+                (1..trace.length() / TRANSACTION_CYCLE_LENGTH)
+                    .map(|tx_index| {
+                        let mut root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+                        for (offset, element) in root.iter_mut().enumerate() {
+                            *element = trace.get(
+                                PREV_TREE_ROOT_POS + offset,
+                                tx_index * TRANSACTION_CYCLE_LENGTH,
+                            );
+                        }
+                        root
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        // Recover the digest of transactions, if exposed, carried over until the last step
+        let transactions_digest = if self.transactions_digest {
+            let mut digest = [BaseElement::ZERO; HASH_RATE_WIDTH];
+            for (offset, element) in digest.iter_mut().enumerate() {
+                *element = trace.get(TX_DIGEST_POS + offset, last_step);
+            }
+            Some(digest)
+        } else {
+            None
+        };
+
+        // Likewise for the commitment to nullifiers
+        let nullifiers_commitment = if self.nullifiers {
+            let mut commitment = [BaseElement::ZERO; HASH_RATE_WIDTH];
+            for (offset, element) in commitment.iter_mut().enumerate() {
+                *element = trace.get(NULLIFIERS_POS + offset, last_step);
+            }
+            Some(commitment)
+        } else {
+            None
+        };
+
+        // And for the number and volume of transactions
+        let batch_statistics = if self.batch_statistics {
+            Some(BatchStatistics::new(
+                trace.get(TX_COUNT_POS, last_step).to_repr() as usize,
+                trace.get(TX_VOLUME_POS, last_step).to_repr(),
+            ))
+        } else {
+            None
+        };
+
         PublicInputs {
             initial_root: [
                 trace.get(PREV_TREE_ROOT_POS, 0),
@@ -122,6 +1008,21 @@ impl Prover for TransactionProver {
                 trace.get(PREV_TREE_ROOT_POS + 5, last_step),
                 trace.get(PREV_TREE_ROOT_POS + 6, last_step),
             ],
+            tree_depth: self.tree_depth,
+            fee_collector: self.fee_collector,
+            withdrawals,
+            deposits,
+            memos,
+            range_log: self.range_log,
+            hash_id: H::HASH_ID,
+            intermediate_roots,
+            transactions_digest,
+            nullifiers_commitment,
+            batch_statistics,
+            subnet_id: self.subnet_id,
+            epoch: self.epoch,
+            derived_indices: self.derived_indices,
+            signed_deltas: self.signed_deltas,
         }
     }
 
@@ -129,3 +1030,285 @@ impl Prover for TransactionProver {
         &self.options
     }
 }
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Builds the segment of the execution trace of the transaction at position `i`, with range
+/// proofs over `range_log` bits, on signed deltas if `signed_deltas`, and the running digests
+/// of transactions, commitments to nullifiers and batch statistics prior to each transaction.
+fn build_transaction_segment<H: TreeHasher>(
+    tx_metadata: &TransactionMetadata,
+    range_log: usize,
+    signed_deltas: bool,
+    tx_digests: &[Hash],
+    nullifiers_commitments: &[Hash],
+    batch_statistics: &[[BaseElement; 2]],
+    i: usize,
+) -> TraceSegment {
+    let s_old_value = tx_metadata.s_old_values[i];
+    let r_old_value = tx_metadata.r_old_values[i];
+    let delta = tx_metadata.deltas[i];
+    let fee = tx_metadata.fees[i];
+    let kind = tx_metadata.kinds[i];
+    let signature = tx_metadata.signatures[i];
+    let memo = tx_metadata.memos[i];
+
+    // The negative deltas of refunds are range-checked offset by the weight of their sign
+    let delta_sign_weight = if signed_deltas {
+        range::sign_weight(range_log)
+    } else {
+        BaseElement::ZERO
+    };
+    let delta_bytes = if kind.is_refund() {
+        delta + delta_sign_weight
+    } else {
+        delta
+    }
+    .to_bytes();
+    let delta_bits = delta_bytes.as_bits::<Lsb0>();
+    let fee_bytes = fee.to_bytes();
+    let fee_bits = fee_bytes.as_bits::<Lsb0>();
+    let sigma_bytes = match kind {
+        TransactionKind::Deposit | TransactionKind::Approval => s_old_value.balance,
+        _ => s_old_value.balance - delta - fee,
+    }
+    .to_bytes();
+    let sigma_bits = sigma_bytes.as_bits::<Lsb0>();
+    let receiver_balance_bytes = match kind {
+        TransactionKind::Withdrawal { .. } | TransactionKind::Approval => r_old_value.balance,
+        _ => r_old_value.balance + delta,
+    }
+    .to_bytes();
+    let receiver_balance_bits = receiver_balance_bytes.as_bits::<Lsb0>();
+    let nonce = tx_metadata.nonce_account(i).nonce;
+    let message = super::build_tx_message(
+        &tx_metadata.signer_key_elements(i),
+        &r_old_value.pub_key_elements(),
+        tx_metadata.s_indices[i],
+        tx_metadata.r_indices[i],
+        delta,
+        nonce,
+        fee,
+        kind,
+        memo,
+        tx_metadata.subnet_id,
+        tx_metadata.epoch,
+    );
+    let (pkey_point, sig_bytes, sig_hash_bytes) = schnorr::build_sig_info(&message, &signature);
+    let sig_bits = sig_bytes.as_bits::<Lsb0>();
+    let sig_hash_bits = sig_hash_bytes.as_bits::<Lsb0>();
+    let split_values = split_register_values(kind, delta);
+    let committee_values = committee_register_values(tx_metadata, i);
+    // The first transaction carries over the split registers of a plain transaction, and
+    // zero committee registers
+    let (previous_split_values, previous_indices, previous_committee_values) = match i {
+        0 => (
+            split_register_values(TransactionKind::Transfer, BaseElement::ZERO),
+            [0, 0],
+            [BaseElement::ZERO; COMMITTEE_REGISTER_WIDTH],
+        ),
+        _ => (
+            split_register_values(tx_metadata.kinds[i - 1], tx_metadata.deltas[i - 1]),
+            [tx_metadata.r_indices[i - 1], tx_metadata.s_indices[i - 1]],
+            committee_register_values(tx_metadata, i - 1),
+        ),
+    };
+    // Transactions not signed by a member of a committee authenticate a zero key at the first
+    // position of an empty key tree, whose root is left unchecked
+    let (member_key, member_index, member_path) = match &tx_metadata.shares[i] {
+        Some(share) => (
+            AccountState::new(share.key, BaseElement::ZERO, BaseElement::ZERO).pub_key_elements(),
+            share.index,
+            share.path.clone(),
+        ),
+        None => (
+            [BaseElement::ZERO; AFFINE_POINT_WIDTH],
+            0,
+            vec![Hash::default(); tx_metadata.tree_depth + 1],
+        ),
+    };
+    let mut rows = Vec::with_capacity(TRACE_WIDTH * TRANSACTION_CYCLE_LENGTH);
+    let mut state = vec![BaseElement::ZERO; TRACE_WIDTH];
+    init_transaction_state(
+        tx_metadata.initial_roots[i],
+        s_old_value,
+        r_old_value,
+        tx_metadata.c_old_values[i],
+        delta,
+        nonce,
+        fee,
+        kind,
+        memo,
+        member_key,
+        &previous_split_values,
+        previous_indices,
+        tx_digests[i],
+        nullifiers_commitments[i],
+        batch_statistics[i],
+        &previous_committee_values,
+        &mut state,
+    );
+    rows.extend_from_slice(&state);
+    for step in 0..TRANSACTION_CYCLE_LENGTH - 1 {
+        update_transaction_state::<H>(
+            step,
+            tx_metadata.tree_depth,
+            range_log,
+            delta_sign_weight,
+            tx_metadata.s_indices[i],
+            tx_metadata.r_indices[i],
+            tx_metadata.fee_collector,
+            &tx_metadata.s_paths[i],
+            &tx_metadata.r_paths[i],
+            &tx_metadata.c_paths[i],
+            delta_bits,
+            sigma_bits,
+            fee_bits,
+            receiver_balance_bits,
+            signature,
+            sig_bits,
+            sig_hash_bits,
+            message,
+            pkey_point,
+            &split_values,
+            member_index,
+            &member_path,
+            &committee_values,
+            &mut state,
+        );
+        rows.extend_from_slice(&state);
+    }
+
+    TraceSegment { index: i, rows }
+}
+
+/// Returns the values of the committee registers at the end of the transaction at position
+/// `i`, i.e. the root of the key tree read from the sender's leaf, or the one of the approved
+/// committee transfer for approvals, the number of approvals left, and the indices of the
+/// current and previous members.
+fn committee_register_values(
+    tx_metadata: &TransactionMetadata,
+    i: usize,
+) -> [BaseElement; COMMITTEE_REGISTER_WIDTH] {
+    let (root, approvals_left) = match tx_metadata.kinds[i] {
+        TransactionKind::Committee => {
+            let (root, threshold) = tx_metadata.s_old_values[i]
+                .committee()
+                .expect("committee transfers must be sent from a committee");
+            (root.to_elements(), threshold - 1)
+        }
+        TransactionKind::Approval => {
+            let transfer_index = tx_metadata.committee_transfer_index(i);
+            let (root, threshold) = tx_metadata.s_old_values[transfer_index]
+                .committee()
+                .expect("committee transfers must be sent from a committee");
+            (root.to_elements(), threshold - 1 - (i - transfer_index))
+        }
+        _ => {
+            let mut root = [BaseElement::ZERO; HASH_RATE_WIDTH];
+            root.copy_from_slice(
+                &tx_metadata.s_old_values[i].pub_key_elements()[..HASH_RATE_WIDTH],
+            );
+            (root, 0)
+        }
+    };
+    let member_index = |i: usize| {
+        tx_metadata.shares[i]
+            .as_ref()
+            .map_or(0, |share| share.index as u64)
+    };
+
+    let mut values = [BaseElement::ZERO; COMMITTEE_REGISTER_WIDTH];
+    values[..HASH_RATE_WIDTH].copy_from_slice(&root);
+    values[HASH_RATE_WIDTH] = BaseElement::new(approvals_left as u64);
+    values[HASH_RATE_WIDTH + 1] = BaseElement::new(member_index(i));
+    values[HASH_RATE_WIDTH + 2] = match i {
+        0 => BaseElement::ZERO,
+        _ => BaseElement::new(member_index(i - 1)),
+    };
+
+    values
+}
+
+/// Alters registers of the first segment of the trace at steps where they are never read by
+/// the constraints, so that the degree of the constraints involving them is stable.
+fn stabilize_constraint_degrees(segment: &mut TraceSegment) {
+    // set the fee collector index bit at the second step to one; this still results in a
+    // valid execution trace because actual index bits are inserted into the trace after
+    // step 7, but it ensures that the index bit register does not repeat identically for
+    // each transaction, and thus the degree of the index bit constraint is stable.
+    segment.set(COLLECTOR_BIT_POS, 1, BaseElement::ONE);
+    // similarly, shift the withdrawal, deposit, no-op and rekeying flags, the sign of delta
+    // and the memo registers at the first step. Each register is shifted by a distinct amount, so that the
+    // constraints involving their difference, like the balance update with both the withdrawal
+    // and deposit flags, are not left constant either.
+    for (shift, flag_pos) in [
+        WITHDRAWAL_FLAG_POS,
+        DEPOSIT_FLAG_POS,
+        NOOP_FLAG_POS,
+        REKEY_FLAG_POS,
+        DELTA_SIGN_POS,
+        COMMITTEE_FLAG_POS,
+    ]
+    .iter()
+    .copied()
+    .chain(MEMO_POS..MEMO_POS + MEMO_WIDTH)
+    .enumerate()
+    {
+        let first_flag = segment.get(flag_pos, 0);
+        segment.set(
+            flag_pos,
+            0,
+            first_flag + BaseElement::from(shift as u64 + 1),
+        );
+    }
+    // the copy of the sender's key is first read at the second step, and is shifted at the
+    // first one so that the key verifying the signatures, which the joint scalar
+    // multiplication combines with the generator, is not left constant when all transactions
+    // share their signing key, e.g. for batches of deposits.
+    let first_key = segment.get(SENDER_KEY_POINT_POS, 0);
+    segment.set(SENDER_KEY_POINT_POS, 0, first_key + BaseElement::ONE);
+    // likewise, the copy of the receiver's key is shifted at the first step, so that the
+    // outputs of split transfers read from it are not left constant when all transactions
+    // credit the same receiver.
+    let first_key = segment.get(RECEIVER_KEY_POINT_POS, 0);
+    segment.set(RECEIVER_KEY_POINT_POS, 0, first_key + BaseElement::ONE);
+    // the shared path flag is not read either once the Merkle phase is over, and is set there
+    // to differ from its values along the paths of the first transaction.
+    segment.set(
+        SHARED_PATH_FLAG_POS,
+        MERKLE_UPDATE_LENGTH,
+        BaseElement::new(2),
+    );
+    // the split and position flags and the remaining amount carried over to the first step
+    // are not read either for the first transaction, which cannot continue a split transfer,
+    // and are set to differ from the ones of the first transaction.
+    for register in (SPLIT_FLAG_POS..SPLIT_AMOUNT_POS).chain([SPLIT_REMAINING_POS]) {
+        let first_value = segment.get(register, 1);
+        segment.set(register, 0, BaseElement::ONE - first_value);
+    }
+    // likewise, the committee registers carried over to the first step are only read for
+    // approvals, which never start a batch, and the approval flag is not read at the last step
+    // of a transaction, where it is set so that the constraints of approvals are not left
+    // constant in batches without committee transfers.
+    for register in (COMMITTEE_ROOT_POS..MEMBER_INDEX_POS)
+        .chain([PREVIOUS_MEMBER_INDEX_POS, SIGNING_KEY_INVERSE_POS])
+    {
+        let first_value = segment.get(register, 0);
+        segment.set(register, 0, first_value + BaseElement::ONE);
+    }
+    segment.set(
+        APPROVAL_FLAG_POS,
+        TRANSACTION_CYCLE_LENGTH - 1,
+        BaseElement::ONE,
+    );
+    // finally, the bit of the member's path is only read once it is inserted, and the hash
+    // state of the path is not read once the Merkle phase is over, so that both are set there
+    // to differ from the path of a zero key at the first position, authenticated by all
+    // transactions outside of committees.
+    segment.set(KEY_PATH_POS + HASH_STATE_WIDTH, 1, BaseElement::ONE);
+    for register in KEY_PATH_POS..KEY_PATH_POS + HASH_STATE_WIDTH {
+        segment.set(register, MERKLE_UPDATE_LENGTH, BaseElement::ONE);
+    }
+}
@@ -6,7 +6,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use super::field;
+use super::bits;
+use super::MAX_RANGE_LOG;
+use crate::periodic::{PeriodicColumnSet, Segment};
+use crate::utils::boundary;
 use winterfell::{
     math::{fields::f63::BaseElement, FieldElement},
     Air, AirContext, Assertion, ByteWriter, EvaluationFrame, ProofOptions, Serializable, TraceInfo,
@@ -27,17 +30,20 @@ pub const TRACE_WIDTH: usize = 2;
 
 pub struct PublicInputs {
     pub number: BaseElement,
+    pub range_log: usize,
 }
 
 impl Serializable for PublicInputs {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
         target.write(self.number);
+        target.write_u8(self.range_log as u8);
     }
 }
 
 pub struct RangeProofAir {
     context: AirContext<BaseElement>,
     number: BaseElement,
+    range_log: usize,
 }
 
 impl Air for RangeProofAir {
@@ -47,11 +53,18 @@ impl Air for RangeProofAir {
     // CONSTRUCTOR
     // --------------------------------------------------------------------------------------------
     fn new(trace_info: TraceInfo, pub_inputs: PublicInputs, options: ProofOptions) -> Self {
-        let degrees = transition_constraint_degrees();
+        let degrees = transition_constraint_degrees(trace_info.length());
         assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(
+            pub_inputs.range_log > 0 && pub_inputs.range_log <= MAX_RANGE_LOG,
+            "range log must be between 1 and {}",
+            MAX_RANGE_LOG
+        );
+        assert_eq!(trace_length(pub_inputs.range_log), trace_info.length());
         RangeProofAir {
             context: AirContext::new(trace_info, degrees, options),
             number: pub_inputs.number,
+            range_log: pub_inputs.range_log,
         }
     }
 
@@ -62,7 +75,7 @@ impl Air for RangeProofAir {
     fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
         &self,
         frame: &EvaluationFrame<E>,
-        _periodic_values: &[E],
+        periodic_values: &[E],
         result: &mut [E],
     ) {
         let current = frame.current();
@@ -72,17 +85,21 @@ impl Air for RangeProofAir {
         debug_assert_eq!(TRACE_WIDTH, current.len());
         debug_assert_eq!(TRACE_WIDTH, next.len());
 
-        evaluate_constraints(result, current, next);
+        evaluate_constraints(result, current, next, periodic_values[0]);
     }
 
     fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
         // Assert starting and ending values
         vec![
             // Starting values (see initialization in build_trace())
-            Assertion::single(1, 0, BaseElement::ZERO),
-            Assertion::single(1, self.trace_length() - 1, self.number),
+            boundary::count_at(1, 0, 0),
+            Assertion::single(1, self.range_log, self.number),
         ]
     }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        vec![periodic_column(self.range_log, self.trace_length())]
+    }
 }
 
 // HELPER EVALUATORS
@@ -92,14 +109,35 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
+    range_proof_flag: E,
 ) {
     // Enforce a step of double-and-add in the field
-    field::enforce_double_and_add_step(result, current, next, 1, 0, FieldElement::ONE);
+    bits::enforce_double_and_add_step(result, current, next, 1, 0, range_proof_flag);
 }
 
-pub(crate) fn transition_constraint_degrees() -> Vec<TransitionConstraintDegree> {
+pub(crate) fn transition_constraint_degrees(
+    trace_length: usize,
+) -> Vec<TransitionConstraintDegree> {
     vec![
-        TransitionConstraintDegree::new(2),
-        TransitionConstraintDegree::new(1),
+        TransitionConstraintDegree::with_cycles(2, vec![trace_length]),
+        TransitionConstraintDegree::with_cycles(1, vec![trace_length]),
     ]
 }
+
+// HELPER FUNCTIONS
+// ------------------------------------------------------------------------------------------------
+
+/// Returns the length of the execution trace of a range proof over `range_log` bits,
+/// i.e. the smallest power of two strictly greater than `range_log`, and at least 8.
+pub(crate) fn trace_length(range_log: usize) -> usize {
+    core::cmp::max((range_log + 1).next_power_of_two(), 8)
+}
+
+/// Returns the mask activating the double-and-add steps of a range proof over `range_log`
+/// bits, for an execution trace of length `trace_length`.
+pub(crate) fn periodic_column(range_log: usize, trace_length: usize) -> Vec<BaseElement> {
+    let mut layout = PeriodicColumnSet::new(1, trace_length);
+    bits::place_step_mask(&mut layout, 0, Segment::new(0, trace_length), range_log);
+
+    layout.into_columns().remove(0)
+}
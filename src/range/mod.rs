@@ -7,8 +7,8 @@
 // except according to those terms.
 
 use winterfell::{
-    math::fields::f63::BaseElement, FieldExtension, HashFunction, ProofOptions, Prover, StarkProof,
-    VerifierError,
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
+    ProofOptions, Prover, StarkProof, VerifierError,
 };
 
 #[cfg(feature = "std")]
@@ -18,7 +18,8 @@ use std::time::Instant;
 #[cfg(feature = "std")]
 use winterfell::{math::log2, Trace};
 
-use super::utils::field;
+use super::utils::bits;
+use super::ProofProfile;
 
 mod air;
 use air::{PublicInputs, RangeProofAir};
@@ -31,26 +32,42 @@ pub(crate) use prover::{
 #[cfg(test)]
 mod tests;
 
-pub(crate) const RANGE_LOG: usize = 64;
+/// Default bit length of the numbers whose range is proven
+pub const RANGE_LOG: usize = 64;
+/// Maximum bit length of the numbers whose range can be proven
+pub const MAX_RANGE_LOG: usize = 64;
+/// Maximum bit length of the numbers whose range can be proven when they may be negative,
+/// so that all the numbers of the symmetric range [-2^k, 2^k) map to distinct field elements
+pub const MAX_SIGNED_RANGE_LOG: usize = 61;
+
+// SIGNED NUMBERS
+// ================================================================================================
+
+/// Returns true if `number`, read as a signed integer, is negative, i.e. lies in the upper
+/// half of the field.
+pub(crate) fn is_negative(number: BaseElement) -> bool {
+    number.to_repr() > BaseElement::MODULUS / 2
+}
+
+/// Returns the weight of the sign of numbers proven within [-2^`range_log`, 2^`range_log`),
+/// i.e. 2^`range_log`.
+pub(crate) fn sign_weight(range_log: usize) -> BaseElement {
+    BaseElement::new(2).exp(range_log as u64)
+}
 
 // RANGE PROOF EXAMPLE
 // ================================================================================================
 
-/// Outputs a new `RangeProofExample` of a given number
+/// Outputs a new `RangeProofExample` of a given number,
+/// using the `Balanced` proof profile.
 pub fn get_example(number: BaseElement) -> RangeProofExample {
-    RangeProofExample::new(
-        // TODO: make it customizable
-        ProofOptions::new(
-            42,
-            8,
-            0,
-            HashFunction::Blake3_256,
-            FieldExtension::None,
-            4,
-            256,
-        ),
-        number,
-    )
+    get_example_with_options(ProofProfile::Balanced.to_proof_options(), number)
+}
+
+/// Outputs a new `RangeProofExample` of a given number,
+/// using the provided proof options.
+pub fn get_example_with_options(options: ProofOptions, number: BaseElement) -> RangeProofExample {
+    RangeProofExample::new(options, number)
 }
 
 /// A struct to perform proofs of valid range of a number
@@ -64,10 +81,25 @@ pub struct RangeProofExample {
 impl RangeProofExample {
     /// Outputs a new `RangeProofExample` of a given number
     pub fn new(options: ProofOptions, number: BaseElement) -> RangeProofExample {
+        Self::new_with_range_log(options, number, RANGE_LOG)
+    }
+
+    /// Outputs a new `RangeProofExample` of a given number, proving that it
+    /// fits in `range_log` bits.
+    pub fn new_with_range_log(
+        options: ProofOptions,
+        number: BaseElement,
+        range_log: usize,
+    ) -> RangeProofExample {
+        assert!(
+            range_log > 0 && range_log <= MAX_RANGE_LOG,
+            "range log must be between 1 and {}",
+            MAX_RANGE_LOG
+        );
         RangeProofExample {
             options,
             number,
-            range_log: RANGE_LOG,
+            range_log,
         }
     }
 
@@ -81,12 +113,12 @@ impl RangeProofExample {
             self.range_log,
         );
 
-        let prover = RangeProver::new(self.options.clone());
+        let prover = RangeProver::new(self.options.clone(), self.range_log);
 
         // generate the execution trace
         #[cfg(feature = "std")]
         let now = Instant::now();
-        let trace = prover.build_trace(self.number, self.range_log);
+        let trace = prover.build_trace(self.number);
         #[cfg(feature = "std")]
         debug!(
             "Generated execution trace of {} registers and 2^{} steps in {} ms",
@@ -103,6 +135,7 @@ impl RangeProofExample {
     pub fn verify(&self, proof: StarkProof) -> Result<(), VerifierError> {
         let pub_inputs = PublicInputs {
             number: self.number,
+            range_log: self.range_log,
         };
         winterfell::verify::<RangeProofAir>(proof, pub_inputs)
     }
@@ -111,6 +144,7 @@ impl RangeProofExample {
     fn verify_with_wrong_inputs(&self, proof: StarkProof) -> Result<(), VerifierError> {
         let pub_inputs = PublicInputs {
             number: -self.number,
+            range_log: self.range_log,
         };
         winterfell::verify::<RangeProofAir>(proof, pub_inputs)
     }
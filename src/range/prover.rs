@@ -4,8 +4,8 @@ use winterfell::{
     ProofOptions, Prover, Trace, TraceTable,
 };
 
-use super::air::TRACE_WIDTH;
-use super::field;
+use super::air::{trace_length, TRACE_WIDTH};
+use super::bits;
 use super::PublicInputs;
 use super::RangeProofAir;
 
@@ -14,17 +14,19 @@ use super::RangeProofAir;
 
 pub struct RangeProver {
     options: ProofOptions,
+    range_log: usize,
 }
 
 impl RangeProver {
-    pub fn new(options: ProofOptions) -> Self {
-        Self { options }
+    pub fn new(options: ProofOptions, range_log: usize) -> Self {
+        Self { options, range_log }
     }
 
-    pub fn build_trace(&self, number: BaseElement, range_log: usize) -> TraceTable<BaseElement> {
-        // allocate memory to hold the trace table
-        let trace_length = range_log;
-        let mut trace = TraceTable::new(TRACE_WIDTH, trace_length);
+    pub fn build_trace(&self, number: BaseElement) -> TraceTable<BaseElement> {
+        // allocate memory to hold the trace table, the bits of the number being
+        // processed in the first `range_log` steps
+        let range_log = self.range_log;
+        let mut trace = TraceTable::new(TRACE_WIDTH, trace_length(range_log));
 
         let number_bytes = number.to_bytes();
         let number_bits = number_bytes.as_bits::<Lsb0>();
@@ -35,7 +37,7 @@ impl RangeProver {
             },
             |step, state| {
                 // execute the transition function for all steps
-                update_range_verification_state(step, range_log - 1, number_bits, state);
+                update_range_verification_state(step, range_log, number_bits, state);
             },
         );
 
@@ -50,7 +52,8 @@ impl Prover for RangeProver {
 
     fn get_pub_inputs(&self, trace: &Self::Trace) -> PublicInputs {
         PublicInputs {
-            number: trace.get(1, trace.length() - 1),
+            number: trace.get(1, self.range_log),
+            range_log: self.range_log,
         }
     }
 
@@ -74,11 +77,10 @@ pub(crate) fn init_range_verification_state(state: &mut [BaseElement]) {
 pub(crate) fn update_range_verification_state(
     step: usize,
     range_log: usize,
-    bits: &BitSlice<Lsb0, u8>,
+    number_bits: &BitSlice<Lsb0, u8>,
     state: &mut [BaseElement],
 ) {
     if step < range_log {
-        state[0] = BaseElement::from(bits[range_log - 1 - step] as u8);
-        field::apply_double_and_add_step(state, 1, 0);
+        bits::apply_decomposition_step(state, 1, 0, number_bits, range_log, step);
     }
 }
@@ -7,7 +7,7 @@
 // except according to those terms.
 
 use winterfell::{
-    math::{fields::f63::BaseElement, FieldElement},
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
     FieldExtension, HashFunction, ProofOptions,
 };
 
@@ -51,6 +51,28 @@ fn range_proof_max_input() {
     assert!(range.verify(proof).is_ok());
 }
 
+#[test]
+fn range_proof_custom_range_log() {
+    let range = Box::new(super::RangeProofExample::new_with_range_log(
+        build_options(1),
+        BaseElement::from((1u64 << 48) - 1),
+        48,
+    ));
+    let proof = range.prove();
+    assert!(range.verify(proof).is_ok());
+}
+
+#[test]
+#[should_panic]
+fn range_proof_input_too_large_for_range_log() {
+    let range = Box::new(super::RangeProofExample::new_with_range_log(
+        build_options(1),
+        BaseElement::from(1u64 << 32),
+        32,
+    ));
+    range.prove();
+}
+
 #[test]
 #[should_panic]
 fn range_proof_input_too_large() {
@@ -83,6 +105,28 @@ fn range_test_basic_proof_verification_fail() {
     assert!(verified.is_err());
 }
 
+#[test]
+fn signed_number_offset() {
+    let range_log = super::MAX_SIGNED_RANGE_LOG;
+    let weight = super::sign_weight(range_log);
+    assert_eq!(weight, BaseElement::from(1u64 << range_log));
+
+    // Negative numbers down to -2^range_log are offset by the weight of their sign into the
+    // range-checked bits
+    for number in [-BaseElement::ONE, -weight] {
+        assert!(super::is_negative(number));
+        assert!((number + weight).to_repr() < 1u64 << range_log);
+    }
+    for number in [
+        BaseElement::ZERO,
+        BaseElement::from(17u32),
+        weight - BaseElement::ONE,
+    ] {
+        assert!(!super::is_negative(number));
+        assert!(number.to_repr() < 1u64 << range_log);
+    }
+}
+
 fn build_options(extension: u8) -> ProofOptions {
     ProofOptions::new(
         42,
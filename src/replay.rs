@@ -0,0 +1,141 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Native replay of transaction batches, recomputing the roots a state-transition proof of
+//! these transactions must expose.
+//!
+//! Integrators running their own state machine can replay a batch from their copy of the tree
+//! before accepting a proof, and check that the roots claimed by its public inputs are the
+//! ones they expect. The validity of the transactions (signatures, nonces, balances) is
+//! enforced by the proof itself, and is thus not checked again.
+
+use super::batch::{Transaction, TransactionError};
+use super::merkle::{StateTree, TreeHasher, TreeStore};
+use super::utils::rescue::Hash;
+use super::PublicInputs;
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// REPLAY ERROR
+// ================================================================================================
+
+/// Errors raised when checking public inputs against a replayed batch
+#[derive(Debug, PartialEq)]
+pub enum ReplayError {
+    /// The transaction at the given position cannot be applied to the tree
+    Transaction(TransactionError),
+    /// The public inputs are for a tree of another depth than the replayed one
+    DepthMismatch,
+    /// The initial root of the public inputs is not the root of the replayed tree
+    InitialRootMismatch,
+    /// The final root of the public inputs is not the root after the replayed transactions
+    FinalRootMismatch,
+    /// The intermediate roots exposed by the public inputs are not the roots in between the
+    /// replayed transactions
+    IntermediateRootsMismatch,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Transaction(err) => write!(f, "cannot replay batch: {}", err),
+            ReplayError::DepthMismatch => {
+                write!(f, "public inputs are for a tree of another depth")
+            }
+            ReplayError::InitialRootMismatch => {
+                write!(f, "initial root does not match the replayed tree")
+            }
+            ReplayError::FinalRootMismatch => {
+                write!(f, "final root does not match the replayed batch")
+            }
+            ReplayError::IntermediateRootsMismatch => {
+                write!(f, "intermediate roots do not match the replayed batch")
+            }
+        }
+    }
+}
+
+impl From<TransactionError> for ReplayError {
+    fn from(err: TransactionError) -> Self {
+        ReplayError::Transaction(err)
+    }
+}
+
+// BATCH REPLAY
+// ================================================================================================
+
+/// Applies `transactions` in order to a copy of `initial_tree`, with fees credited to the
+/// account at index `fee_collector`, and returns the final root of the tree along with the
+/// roots prior to each transaction but the first, as exposed by
+/// `TransactionProver::with_intermediate_roots`.
+///
+/// Returns an error if a transaction refers to a leaf outside of the tree or holding no
+/// account, if the fee collector holds no account, or if a transaction is sent by or to the
+/// fee collector, which the AIR program does not support.
+pub fn apply_batch<H: TreeHasher, S: TreeStore + Clone>(
+    initial_tree: &StateTree<H, S>,
+    fee_collector: usize,
+    transactions: &[Transaction],
+) -> Result<(Hash, Vec<Hash>), TransactionError> {
+    let mut tree = initial_tree.clone();
+    let tree_size = 1 << tree.depth();
+    let mut intermediate_roots = Vec::with_capacity(transactions.len().saturating_sub(1));
+    for (position, transaction) in transactions.iter().enumerate() {
+        for &leaf in [transaction.sender, transaction.receiver, fee_collector].iter() {
+            if leaf >= tree_size {
+                return Err(TransactionError::InvalidIndex(position));
+            }
+            if tree.get_account(leaf).is_none() {
+                return Err(TransactionError::UnknownAccount(position));
+            }
+        }
+        if transaction.sender == fee_collector || transaction.receiver == fee_collector {
+            return Err(TransactionError::FeeCollectorAccount(position));
+        }
+        if position > 0 {
+            intermediate_roots.push(tree.root());
+        }
+        tree.apply_transaction(transaction, fee_collector);
+    }
+
+    Ok((tree.root(), intermediate_roots))
+}
+
+/// Replays `transactions` from `initial_tree` with `apply_batch`, with fees credited to the
+/// fee collector of `pub_inputs`, and checks that the roots of `pub_inputs` are the replayed
+/// ones: the initial and final roots, and the intermediate roots if they are exposed.
+pub fn check_public_inputs<H: TreeHasher, S: TreeStore + Clone>(
+    pub_inputs: &PublicInputs,
+    initial_tree: &StateTree<H, S>,
+    transactions: &[Transaction],
+) -> Result<(), ReplayError> {
+    if pub_inputs.tree_depth != initial_tree.depth() {
+        return Err(ReplayError::DepthMismatch);
+    }
+    if pub_inputs.initial_root != initial_tree.root().to_elements() {
+        return Err(ReplayError::InitialRootMismatch);
+    }
+    let (final_root, intermediate_roots) =
+        apply_batch(initial_tree, pub_inputs.fee_collector, transactions)?;
+    if pub_inputs.final_root != final_root.to_elements() {
+        return Err(ReplayError::FinalRootMismatch);
+    }
+    if let Some(exposed_roots) = &pub_inputs.intermediate_roots {
+        if !exposed_roots
+            .iter()
+            .copied()
+            .eq(intermediate_roots.iter().map(|root| root.to_elements()))
+        {
+            return Err(ReplayError::IntermediateRootsMismatch);
+        }
+    }
+
+    Ok(())
+}
@@ -6,11 +6,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use super::super::utils::periodic_columns::stitch;
 use super::constants::*;
 use super::rescue::{RATE_WIDTH as HASH_RATE_WIDTH, STATE_WIDTH as HASH_STATE_WIDTH};
-use super::{ecc, field, rescue};
-use crate::utils::{are_equal, is_zero, not, EvaluationResult};
+use super::{bits, ecc, rescue};
+use crate::periodic::{check_columns, stitch};
+use crate::utils::{are_equal, boundary, not, EvaluationResult};
 use winterfell::{
     math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement},
     Air, AirContext, Assertion, ByteWriter, EvaluationFrame, ProofOptions, Serializable, TraceInfo,
@@ -23,8 +23,12 @@ use alloc::vec::Vec;
 // SCHNORR AIR
 // ================================================================================================
 
+/// Public inputs of a proof of validity of Schnorr signatures
+#[derive(Clone, Debug)]
 pub struct PublicInputs {
-    pub messages: Vec<[BaseElement; AFFINE_POINT_WIDTH * 2 + 4]>,
+    /// Signed messages, each starting with the affine coordinates of the signer's public key
+    pub messages: Vec<[BaseElement; MESSAGE_LENGTH]>,
+    /// Signatures of the messages, as pairs of a nonce point x-coordinate and a scalar
     pub signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
 }
 
@@ -40,7 +44,7 @@ impl Serializable for PublicInputs {
 
 pub struct SchnorrAir {
     context: AirContext<BaseElement>,
-    messages: Vec<[BaseElement; AFFINE_POINT_WIDTH * 2 + 4]>,
+    messages: Vec<[BaseElement; MESSAGE_LENGTH]>,
     signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
 }
 
@@ -86,10 +90,11 @@ impl Air for SchnorrAir {
         let hash_flag = periodic_values[AFFINE_POINT_WIDTH + 7];
         let hash_internal_inputs =
             &periodic_values[AFFINE_POINT_WIDTH + 8..AFFINE_POINT_WIDTH + 15];
-        let ark = &periodic_values[AFFINE_POINT_WIDTH + 15..];
+        let hash_input_flag = periodic_values[AFFINE_POINT_WIDTH + 15];
+        let hash_hold_flag = periodic_values[AFFINE_POINT_WIDTH + 16];
+        let ark = &periodic_values[AFFINE_POINT_WIDTH + 17..];
 
-        let copy_hash_flag = not(hash_flag) * global_mask;
-        let final_point_addition_flag = not(scalar_mult_flag) * global_mask;
+        let final_reduction_flag = not(scalar_mult_flag) * global_mask;
         let addition_flag = not(doubling_flag) * scalar_mult_flag;
 
         evaluate_constraints(
@@ -101,134 +106,56 @@ impl Air for SchnorrAir {
             addition_flag,
             hash_digest_register_flag,
             pkey_point,
-            final_point_addition_flag,
+            final_reduction_flag,
             hash_flag,
-            copy_hash_flag,
+            hash_input_flag,
+            hash_hold_flag,
             hash_internal_inputs,
         );
     }
 
     fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
-        let signatures = transpose_signatures(&self.signatures);
+        let nonces = nonce_columns(&self.signatures);
         // Assert starting and ending values
-        let mut assertions = vec![];
-        // First projective points
-        for i in 0..PROJECTIVE_POINT_WIDTH {
-            if i == POINT_COORDINATE_WIDTH {
-                assertions.push(Assertion::periodic(
-                    i,
-                    0,
-                    SIG_CYCLE_LENGTH,
-                    BaseElement::ONE,
-                ));
-            } else {
-                assertions.push(Assertion::periodic(
-                    i,
-                    0,
-                    SIG_CYCLE_LENGTH,
-                    BaseElement::ZERO,
-                ));
-            }
-        }
-        assertions.push(Assertion::periodic(
-            PROJECTIVE_POINT_WIDTH,
+        // The accumulated point starts from the point at infinity, followed by the registers
+        // of the selected point and of the limbs of h, all zero
+        let mut initial_values = [BaseElement::ZERO; HASH_STATE_POS];
+        initial_values[POINT_COORDINATE_WIDTH] = BaseElement::ONE;
+        let mut assertions = boundary::periodic_values_at(0, 0, SIG_CYCLE_LENGTH, &initial_values);
+
+        // The hash state starts with the x-coordinate of the nonce point of each signature,
+        // followed by zeros and the domain of signature challenges in its last register
+        assertions.extend(boundary::sequences_at(
+            HASH_STATE_POS,
             0,
             SIG_CYCLE_LENGTH,
-            BaseElement::ZERO,
+            nonces.clone(),
+        ));
+        let mut hash_values = [BaseElement::ZERO; HASH_STATE_WIDTH - POINT_COORDINATE_WIDTH];
+        hash_values[HASH_STATE_WIDTH - POINT_COORDINATE_WIDTH - 1] = CHALLENGE_DOMAIN;
+        assertions.extend(boundary::periodic_values_at(
+            HASH_STATE_POS + POINT_COORDINATE_WIDTH,
+            0,
+            SIG_CYCLE_LENGTH,
+            &hash_values,
         ));
-        // Second projective points
-        for i in 0..PROJECTIVE_POINT_WIDTH {
-            if i == POINT_COORDINATE_WIDTH {
-                assertions.push(Assertion::periodic(
-                    i + PROJECTIVE_POINT_WIDTH + 1,
-                    0,
-                    SIG_CYCLE_LENGTH,
-                    BaseElement::ONE,
-                ));
-            } else {
-                assertions.push(Assertion::periodic(
-                    i + PROJECTIVE_POINT_WIDTH + 1,
-                    0,
-                    SIG_CYCLE_LENGTH,
-                    BaseElement::ZERO,
-                ));
-            }
-        }
-        for i in 0..5 {
-            assertions.push(Assertion::periodic(
-                i + 2 * PROJECTIVE_POINT_WIDTH + 1,
-                0,
-                SIG_CYCLE_LENGTH,
-                BaseElement::ZERO,
-            ));
-        }
 
-        // TODO: find a way to do this better with indexing
-        assertions.append(&mut vec![
-            Assertion::sequence(
-                2 * PROJECTIVE_POINT_WIDTH + 6,
-                0,
-                SIG_CYCLE_LENGTH,
-                signatures.0.clone(),
-            ),
-            Assertion::sequence(
-                2 * PROJECTIVE_POINT_WIDTH + 7,
-                0,
-                SIG_CYCLE_LENGTH,
-                signatures.1.clone(),
-            ),
-            Assertion::sequence(
-                2 * PROJECTIVE_POINT_WIDTH + 8,
-                0,
-                SIG_CYCLE_LENGTH,
-                signatures.2.clone(),
-            ),
-            Assertion::sequence(
-                2 * PROJECTIVE_POINT_WIDTH + 9,
-                0,
-                SIG_CYCLE_LENGTH,
-                signatures.3.clone(),
-            ),
-            Assertion::sequence(
-                2 * PROJECTIVE_POINT_WIDTH + 10,
-                0,
-                SIG_CYCLE_LENGTH,
-                signatures.4.clone(),
-            ),
-            Assertion::sequence(
-                2 * PROJECTIVE_POINT_WIDTH + 11,
-                0,
-                SIG_CYCLE_LENGTH,
-                signatures.5.clone(),
-            ),
-        ]);
-        for i in 0..HASH_RATE_WIDTH {
-            assertions.push(Assertion::periodic(
-                i + 2 * PROJECTIVE_POINT_WIDTH + POINT_COORDINATE_WIDTH + 6,
-                0,
-                SIG_CYCLE_LENGTH,
-                BaseElement::ZERO,
-            ));
-        }
         // Ending values
-        // We can compute R = S + h.P in the registers of S directly,
-        // hence checking the x_coord of R in the first registers (i.e. x(S))
-        // TODO: find a way to do this better with indexing
-        assertions.append(&mut vec![
-            Assertion::sequence(0, SCALAR_MUL_LENGTH + 1, SIG_CYCLE_LENGTH, signatures.0),
-            Assertion::sequence(1, SCALAR_MUL_LENGTH + 1, SIG_CYCLE_LENGTH, signatures.1),
-            Assertion::sequence(2, SCALAR_MUL_LENGTH + 1, SIG_CYCLE_LENGTH, signatures.2),
-            Assertion::sequence(3, SCALAR_MUL_LENGTH + 1, SIG_CYCLE_LENGTH, signatures.3),
-            Assertion::sequence(4, SCALAR_MUL_LENGTH + 1, SIG_CYCLE_LENGTH, signatures.4),
-            Assertion::sequence(5, SCALAR_MUL_LENGTH + 1, SIG_CYCLE_LENGTH, signatures.5),
-        ]);
+        // The accumulated point R = s.G + h.P is reduced to its affine x-coordinate in the
+        // first registers
+        assertions.extend(boundary::sequences_at(
+            0,
+            SCALAR_MUL_LENGTH + 1,
+            SIG_CYCLE_LENGTH,
+            nonces,
+        ));
 
         assertions
     }
 
     fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
         // Start with empty periodic columns
-        let mut columns = vec![Vec::new(); POINT_COORDINATE_WIDTH + PROJECTIVE_POINT_WIDTH + 3];
+        let mut columns = vec![Vec::new(); AFFINE_POINT_WIDTH + 17];
         // Stitch in the periodic columns applicable to all uses of Schnorr
         stitch(
             &mut columns,
@@ -242,7 +169,10 @@ impl Air for SchnorrAir {
                 (5, 5),
                 (6, 6),
                 (7, 7 + AFFINE_POINT_WIDTH),
+                (HASH_INPUT_FLAG_INDEX, AFFINE_POINT_WIDTH + 15),
+                (HASH_HOLD_FLAG_INDEX, AFFINE_POINT_WIDTH + 16),
             ],
+            SIG_CYCLE_LENGTH,
         );
         // Values to feed to the last registers of the hash state at the end of a cycle.
         // Always zero (i.e. resetting the rate) or equal to the chunks of the message.
@@ -252,7 +182,8 @@ impl Air for SchnorrAir {
                 HASH_RATE_WIDTH
             ];
 
-        // Public key coordinates extracted from the signed messages and to be used during scalar multiplications
+        // Public key coordinates extracted from the signed messages and to be used during the
+        // scalar multiplication
         let mut pub_keys = vec![
             vec![BaseElement::ZERO; SIG_CYCLE_LENGTH * self.signatures.len()];
             AFFINE_POINT_WIDTH
@@ -283,6 +214,7 @@ impl Air for SchnorrAir {
             &mut columns,
             pub_keys,
             (7..7 + AFFINE_POINT_WIDTH).enumerate().collect(),
+            SIG_CYCLE_LENGTH,
         );
         stitch(
             &mut columns,
@@ -290,11 +222,13 @@ impl Air for SchnorrAir {
             (8 + AFFINE_POINT_WIDTH..8 + AFFINE_POINT_WIDTH + HASH_RATE_WIDTH)
                 .enumerate()
                 .collect(),
+            SIG_CYCLE_LENGTH,
         );
 
         // Append the rescue round constants
         columns.append(&mut rescue::get_round_constants());
 
+        debug_assert_eq!(check_columns(&columns, SIG_CYCLE_LENGTH), Ok(()));
         columns
     }
 }
@@ -302,29 +236,34 @@ impl Air for SchnorrAir {
 // HELPER EVALUATORS
 // ------------------------------------------------------------------------------------------------
 
-/// when flag = 1, enforces that the next state of the computation is defined like so:
-/// - the first HASH_RATE_WIDTH registers are equal to the values from the previous step
-/// - the other HASH_RATE_WIDTH registers are equal to 0,
-///   and add the values of internal_inputs for hash merging if any (only at last round)
+/// Enforces the hash state to be carried over between Rescue permutations:
+/// - when input_flag = 1, the first HASH_RATE_WIDTH registers are copied to the next step, and
+///   the other HASH_RATE_WIDTH registers are set to the values of internal_inputs, i.e. the
+///   next chunk of the message
+/// - when hold_flag = 1, once the message is hashed, all the registers are copied to the next
+///   step, so that the digest is carried over until the end of the scalar multiplications
 fn enforce_hash_copy<E: FieldElement>(
     result: &mut [E],
     current: &[E],
     next: &[E],
-    flag: E,
+    input_flag: E,
+    hold_flag: E,
     internal_inputs: &[E],
 ) {
     for i in 0..HASH_RATE_WIDTH {
-        result.agg_constraint(i, flag, are_equal(current[i], next[i]));
+        result.agg_constraint(i, input_flag + hold_flag, are_equal(current[i], next[i]));
     }
 
-    // internal_inputs are either zero (no difference with original hash chain) when resetting the
-    // last registers or equal to the message elements, to be fed to the hash in an iterated way.
-    // See build_trace() for more info
     for i in 0..HASH_RATE_WIDTH {
         result.agg_constraint(
             HASH_RATE_WIDTH + i,
-            flag,
-            is_zero(next[HASH_RATE_WIDTH + i] - internal_inputs[i]),
+            input_flag,
+            are_equal(next[HASH_RATE_WIDTH + i], internal_inputs[i]),
+        );
+        result.agg_constraint(
+            HASH_RATE_WIDTH + i,
+            hold_flag,
+            are_equal(next[HASH_RATE_WIDTH + i], current[HASH_RATE_WIDTH + i]),
         );
     }
 }
@@ -332,6 +271,28 @@ fn enforce_hash_copy<E: FieldElement>(
 // HELPER FUNCTIONS
 // ------------------------------------------------------------------------------------------------
 
+/// Index of the column of `periodic_columns` selecting the steps absorbing a chunk of the
+/// message into the hash state
+pub(crate) const HASH_INPUT_FLAG_INDEX: usize = 8;
+/// Index of the column of `periodic_columns` selecting the steps holding the hash state once
+/// the message is hashed
+pub(crate) const HASH_HOLD_FLAG_INDEX: usize = HASH_INPUT_FLAG_INDEX + 1;
+
+/// Returns the periodic columns of a single signature verification, over SIG_CYCLE_LENGTH
+/// steps, in the following order:
+/// - the global mask, the scalar multiplication flag and the point doubling flag,
+/// - the four flags selecting the limb of the hash digest being decomposed,
+/// - the flag of the Rescue rounds,
+/// - the flag of the steps absorbing a chunk of the message, at the end of each hash
+///   iteration but the last one, i.e. steps `k * HASH_CYCLE_LENGTH + NUM_HASH_ROUNDS` for
+///   `k < NUM_HASH_ITER - 1`,
+/// - the flag of the steps holding the hash state, from the end of the last hash iteration
+///   until the end of the scalar multiplication, i.e. steps `TOTAL_HASH_LENGTH - 1` to
+///   `SCALAR_MUL_LENGTH`,
+/// - the Rescue round constants.
+///
+/// Every step of the hash state, up to the one reading the digest, is hence selected by
+/// exactly one of the round, input and hold flags.
 pub(crate) fn periodic_columns() -> Vec<Vec<BaseElement>> {
     // We are computing the values for one whole Schnorr trace, i.e.
     // having only 1 global period of length SIG_CYCLE_LENGTH.
@@ -377,6 +338,14 @@ pub(crate) fn periodic_columns() -> Vec<Vec<BaseElement>> {
         SIG_CYCLE_LENGTH - global_mask.len()
     ]);
 
+    // Flags for absorbing the chunks of the message, and for holding the digest afterwards
+    let mut hash_input_flag = vec![BaseElement::ZERO; SIG_CYCLE_LENGTH];
+    for k in 0..NUM_HASH_ITER - 1 {
+        hash_input_flag[k * HASH_CYCLE_LENGTH + NUM_HASH_ROUNDS] = BaseElement::ONE;
+    }
+    let mut hash_hold_flag = vec![BaseElement::ZERO; SIG_CYCLE_LENGTH];
+    hash_hold_flag[TOTAL_HASH_LENGTH - 1..=SCALAR_MUL_LENGTH].fill(BaseElement::ONE);
+
     // ARK constant values for the Rescue hash rounds
     let mut rescue_constants = rescue::get_round_constants();
 
@@ -385,6 +354,7 @@ pub(crate) fn periodic_columns() -> Vec<Vec<BaseElement>> {
     result.append(&mut vec![point_doubling_flag]);
     result.append(&mut hash_digest_register_flag);
     result.append(&mut vec![hash_flag]);
+    result.append(&mut vec![hash_input_flag, hash_hold_flag]);
     result.append(&mut rescue_constants);
 
     result
@@ -400,22 +370,18 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     addition_flag: E,
     hash_digest_register_flag: &[E],
     pkey_point: &[E],
-    final_point_addition_flag: E,
+    final_reduction_flag: E,
     hash_flag: E,
-    copy_hash_flag: E,
+    hash_input_flag: E,
+    hash_hold_flag: E,
     hash_internal_inputs: &[E],
 ) {
-    // Point to be used in the double-and-add operations of registers [0..PROJECTIVE_POINT_WIDTH] (s.G)
-    let generator_point: Vec<E> = GENERATOR.iter().map(|&coord| coord.into()).collect();
-
-    // Point to be used in the double-and-add operations of registers [PROJECTIVE_POINT_WIDTH + 1..PROJECTIVE_POINT_WIDTH * 2 + 1] (h.P)
-    let pkey_point: Vec<E> = pkey_point.to_vec();
+    // When scalar_mult_flag = 1, constraints for a step of the joint scalar multiplication
+    // s.G + h.P are enforced on the dedicated registers, doubling the accumulated point and
+    // adding G, P or G + P to it depending on the bits of s and h, as well as a double-and-add
+    // in the field for bin(h).
 
-    // When scalar_mult_flag = 1, constraints for a double-and-add
-    // step are enforced on the dedicated registers for S and h.P,
-    // as well as a double-and-add in the field for bin(h).
-
-    // Enforce a step of double-and-add in the group for s.G
+    // Enforce a step of doubling in the group for s.G + h.P
     ecc::enforce_point_doubling(
         &mut result[..PROJECTIVE_POINT_WIDTH + 1],
         &current[..PROJECTIVE_POINT_WIDTH + 1],
@@ -423,38 +389,24 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
         doubling_flag,
     );
 
-    ecc::enforce_point_addition_mixed(
-        &mut result[..PROJECTIVE_POINT_WIDTH + 1],
-        &current[..PROJECTIVE_POINT_WIDTH + 1],
-        &next[..PROJECTIVE_POINT_WIDTH + 1],
-        &generator_point,
-        addition_flag,
-    );
-
-    // Enforce a step of double-and-add in the group for h.P
-    ecc::enforce_point_doubling(
-        &mut result[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-        &current[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-        &next[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-        doubling_flag,
-    );
-
-    ecc::enforce_point_addition_mixed(
-        &mut result[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-        &current[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-        &next[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-        &pkey_point,
+    // Enforce a step of conditional addition in the group for s.G + h.P, of the point
+    // selected by the bits of s and h
+    ecc::enforce_joint_addition(
+        &mut result[..JOINT_STEP_WIDTH],
+        &current[..JOINT_STEP_WIDTH],
+        &next[..JOINT_STEP_WIDTH],
+        pkey_point,
         addition_flag,
     );
 
     // Enforce a step of double-and-add in the field for the hash digest limbs
     for (i, &flag) in hash_digest_register_flag.iter().enumerate().take(4) {
-        field::enforce_double_and_add_step_constrained(
-            &mut result[2 * PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 6],
-            &current[2 * PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 6],
-            &next[2 * PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 6],
-            4 - i,
-            0,
+        bits::enforce_double_and_add_step_constrained(
+            result,
+            current,
+            next,
+            H_LIMBS_POS + 3 - i,
+            H_BIT_POS,
             flag * doubling_flag, // Do not repeat it twice
         );
     }
@@ -462,70 +414,59 @@ pub(crate) fn evaluate_constraints<E: FieldElement + From<BaseElement>>(
     // Enforce temporary accumulators copy between double-and-add steps
     for i in 0..4 {
         result.agg_constraint(
-            2 * PROJECTIVE_POINT_WIDTH + 2 + i,
+            H_LIMBS_POS + i,
             addition_flag,
-            are_equal(
-                current[2 * PROJECTIVE_POINT_WIDTH + 2 + i],
-                next[2 * PROJECTIVE_POINT_WIDTH + 2 + i],
-            ),
+            are_equal(current[H_LIMBS_POS + i], next[H_LIMBS_POS + i]),
         );
     }
 
     // Enforce also copy for hash digest words cells outside of double-and-add steps
     for (i, &flag) in hash_digest_register_flag.iter().enumerate().take(4) {
         result.agg_constraint(
-            2 * PROJECTIVE_POINT_WIDTH + 5 - i,
+            H_LIMBS_POS + 3 - i,
             not(flag) * doubling_flag,
-            are_equal(
-                current[2 * PROJECTIVE_POINT_WIDTH + 5 - i],
-                next[2 * PROJECTIVE_POINT_WIDTH + 5 - i],
-            ),
+            are_equal(current[H_LIMBS_POS + 3 - i], next[H_LIMBS_POS + 3 - i]),
         );
     }
 
     // When hash_flag = 1, constraints for a Rescue round
     // are enforced on the dedicated registers
     rescue::enforce_round(
-        &mut result[2 * PROJECTIVE_POINT_WIDTH + 6..],
-        &current[2 * PROJECTIVE_POINT_WIDTH + 6..],
-        &next[2 * PROJECTIVE_POINT_WIDTH + 6..],
+        &mut result[HASH_STATE_POS..],
+        &current[HASH_STATE_POS..],
+        &next[HASH_STATE_POS..],
         ark,
         hash_flag,
     );
 
-    // When hash_flag = 0, constraints for copying hash values to the next step
-    // and updating the rate registers with self.message[i] elements are enforced.
-
+    // When hash_flag = 0, constraints for copying hash values to the next step, along with
+    // the chunks of the message to be absorbed, or holding the digest once the message is
+    // hashed, are enforced.
     enforce_hash_copy(
-        &mut result[2 * PROJECTIVE_POINT_WIDTH + 6..],
-        &current[2 * PROJECTIVE_POINT_WIDTH + 6..],
-        &next[2 * PROJECTIVE_POINT_WIDTH + 6..],
-        copy_hash_flag,
+        &mut result[HASH_STATE_POS..],
+        &current[HASH_STATE_POS..],
+        &next[HASH_STATE_POS..],
+        hash_input_flag,
+        hash_hold_flag,
         hash_internal_inputs,
     );
 
-    // When scalar_mult_flag = 0, compute the addition
-    // R = S + h.P and enforce h = hash output
-
-    // Add h.P to S, with the result stored directly in the coordinates of S
-    ecc::enforce_point_addition_reduce_x(
+    // When scalar_mult_flag = 0, reduce R = s.G + h.P to its affine x-coordinate, stored
+    // directly in the registers of its X coordinate, and enforce h = hash output
+    ecc::enforce_x_reduction(
         &mut result[..PROJECTIVE_POINT_WIDTH],
-        &current[..PROJECTIVE_POINT_WIDTH], // S
+        &current[..PROJECTIVE_POINT_WIDTH],
         &next[..PROJECTIVE_POINT_WIDTH],
-        &current[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 1], // h.P
-        final_point_addition_flag,
+        final_reduction_flag,
     );
 
     // Ensure that the accumulated value from the binary decomposition of h
     // matches the output of Rescue iterated hashes
     for i in 0..4 {
         result.agg_constraint(
-            2 * PROJECTIVE_POINT_WIDTH + 2 + i,
-            final_point_addition_flag,
-            are_equal(
-                current[2 * PROJECTIVE_POINT_WIDTH + 2 + i],
-                current[2 * PROJECTIVE_POINT_WIDTH + 6 + i],
-            ),
+            H_LIMBS_POS + i,
+            final_reduction_flag,
+            are_equal(current[H_LIMBS_POS + i], current[HASH_STATE_POS + i]),
         );
     }
 }
@@ -534,46 +475,43 @@ pub(crate) fn transition_constraint_degrees(
     num_tx: usize,
     cycle_length: usize,
 ) -> Vec<TransitionConstraintDegree> {
-    let bit_degree = if num_tx == 1 { 3 } else { 5 };
+    // The selection of G + P is quadratic in the public key, which is constant when
+    // verifying a single signature
+    let selection_degree = if num_tx == 1 { 3 } else { 5 };
 
-    // First scalar multiplication
+    // Joint scalar multiplication, whose addition steps dominate the doubling steps and the
+    // final reduction
     let mut degrees =
         vec![
             TransitionConstraintDegree::with_cycles(5, vec![cycle_length, cycle_length]);
-            POINT_COORDINATE_WIDTH
+            PROJECTIVE_POINT_WIDTH
         ];
 
-    // The x coordinate also stores the final point reduction, hence the first degrees are higher
-    for _ in 0..AFFINE_POINT_WIDTH {
+    // Addition flag and bits of s and h
+    for _ in 0..3 {
         degrees.push(TransitionConstraintDegree::with_cycles(
-            4,
+            2,
             vec![cycle_length, cycle_length],
         ));
     }
-    degrees.push(TransitionConstraintDegree::with_cycles(
-        2,
-        vec![cycle_length],
-    ));
 
-    // Second scalar multiplication
-    for _ in 0..PROJECTIVE_POINT_WIDTH {
+    // Point added to the accumulated one
+    for _ in 0..AFFINE_POINT_WIDTH {
         degrees.push(TransitionConstraintDegree::with_cycles(
-            bit_degree,
+            selection_degree,
             vec![cycle_length, cycle_length],
         ));
     }
-    degrees.push(TransitionConstraintDegree::with_cycles(
-        2,
-        vec![cycle_length],
-    ));
 
-    // Rescue hash
+    // Limbs of h
     for _ in 0..4 {
         degrees.push(TransitionConstraintDegree::with_cycles(
             1,
             vec![cycle_length, cycle_length],
         ));
     }
+
+    // Rescue hash
     for _ in 0..HASH_STATE_WIDTH {
         degrees.push(TransitionConstraintDegree::with_cycles(
             3,
@@ -586,33 +524,12 @@ pub(crate) fn transition_constraint_degrees(
 
 // TODO: Maybe simplify signature definition a little
 #[allow(clippy::type_complexity)]
-fn transpose_signatures(
+/// Returns the x-coordinates of the nonce points of `signatures`, one column per element of
+/// the coordinate.
+fn nonce_columns(
     signatures: &[([BaseElement; POINT_COORDINATE_WIDTH], Scalar)],
-) -> (
-    Vec<BaseElement>,
-    Vec<BaseElement>,
-    Vec<BaseElement>,
-    Vec<BaseElement>,
-    Vec<BaseElement>,
-    Vec<BaseElement>,
-    Vec<Scalar>,
-) {
-    let n = signatures.len();
-    let mut r1 = Vec::with_capacity(n);
-    let mut r2 = Vec::with_capacity(n);
-    let mut r3 = Vec::with_capacity(n);
-    let mut r4 = Vec::with_capacity(n);
-    let mut r5 = Vec::with_capacity(n);
-    let mut r6 = Vec::with_capacity(n);
-    let mut r7 = Vec::with_capacity(n);
-    for element in signatures {
-        r1.push(element.0[0]);
-        r2.push(element.0[1]);
-        r3.push(element.0[2]);
-        r4.push(element.0[3]);
-        r5.push(element.0[4]);
-        r6.push(element.0[5]);
-        r7.push(element.1);
-    }
-    (r1, r2, r3, r4, r5, r6, r7)
+) -> Vec<Vec<BaseElement>> {
+    (0..POINT_COORDINATE_WIDTH)
+        .map(|i| signatures.iter().map(|signature| signature.0[i]).collect())
+        .collect()
 }
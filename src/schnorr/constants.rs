@@ -7,19 +7,41 @@
 // except according to those terms.
 
 pub(crate) use super::ecc::{
-    AFFINE_POINT_WIDTH, GENERATOR, POINT_COORDINATE_WIDTH, PROJECTIVE_POINT_WIDTH,
+    AFFINE_POINT_WIDTH, JOINT_STEP_WIDTH, POINT_COORDINATE_WIDTH, PROJECTIVE_POINT_WIDTH,
 };
-pub(crate) use super::rescue::{HASH_CYCLE_LENGTH, HASH_CYCLE_MASK, NUM_HASH_ROUNDS, STATE_WIDTH};
+pub(crate) use super::rescue::{
+    HASH_CYCLE_LENGTH, HASH_CYCLE_MASK, NUM_HASH_ROUNDS, RATE_WIDTH, STATE_WIDTH,
+};
+use winterfell::math::fields::f63::BaseElement;
 
 // CONSTANTS
 // ================================================================================================
 
 // Rescue constants
 
-/// Number of hash iterations for hashing the message
-pub const NUM_HASH_ITER: usize = 5;
+/// Number of hash iterations for hashing the message, the first one hashing the x-coordinate
+/// of the nonce point and each of the others absorbing a chunk of RATE_WIDTH message elements
+// Can be increased to sign longer messages, as long as the challenge is obtained by the end of
+// the scalar multiplications, i.e. up to SCALAR_MUL_LENGTH / HASH_CYCLE_LENGTH iterations
+pub const NUM_HASH_ITER: usize = 7;
+/// Number of elements of a message to be signed
+pub const MESSAGE_LENGTH: usize = (NUM_HASH_ITER - 1) * RATE_WIDTH;
 /// Total number of steps for the iterated hash of the message to be signed
 pub const TOTAL_HASH_LENGTH: usize = HASH_CYCLE_LENGTH * NUM_HASH_ITER;
+/// Position of the first register of the hash state
+pub const HASH_STATE_POS: usize = H_LIMBS_POS + 4;
+/// Domain separator initializing the last register of the hash state, so that signature
+/// challenges cannot be confused with other Rescue digests; ASCII encoding of "schnorr"
+pub const CHALLENGE_DOMAIN: BaseElement = BaseElement::new(0x7363686e6f7272);
+/// Domain separator of the Rescue states deriving keys from seeds and parent keys;
+/// ASCII encoding of "derive"
+pub const KEY_DERIVATION_DOMAIN: BaseElement = BaseElement::new(0x646572697665);
+/// Domain separator of the Rescue states hashing the coefficients of aggregated keys;
+/// ASCII encoding of "musig"
+pub const KEY_AGGREGATION_DOMAIN: BaseElement = BaseElement::new(0x6d75736967);
+/// Domain separator of the Rescue states committing to the nonces of aggregated signatures;
+/// ASCII encoding of "commit"
+pub const NONCE_COMMITMENT_DOMAIN: BaseElement = BaseElement::new(0x636f6d6d6974);
 
 // Scalar multiplication constants
 
@@ -30,10 +52,31 @@ pub const TOTAL_HASH_LENGTH: usize = HASH_CYCLE_LENGTH * NUM_HASH_ITER;
 // from Fp, of which we can ignore the MSB of the first limb.
 pub const SCALAR_MUL_LENGTH: usize = 510;
 
+/// Position of the register flagging the addition steps adding a point to the accumulator
+pub const ADDITION_FLAG_POS: usize = PROJECTIVE_POINT_WIDTH;
+/// Position of the register holding the bits of s
+pub const S_BIT_POS: usize = PROJECTIVE_POINT_WIDTH + 1;
+/// Position of the register holding the bits of h
+pub const H_BIT_POS: usize = PROJECTIVE_POINT_WIDTH + 2;
+/// Position of the first register of the point added to the accumulator
+pub const ADDEND_POS: usize = PROJECTIVE_POINT_WIDTH + 3;
+/// Position of the first of the four registers accumulating the limbs of h
+pub const H_LIMBS_POS: usize = JOINT_STEP_WIDTH;
+
 // Periodic trace length
 
 /// Total number of registers in the trace
-// 2 points in projective coordinates, 2 binary decompositions, 4 field elements, 1 hash state
-pub const TRACE_WIDTH: usize = 2 * PROJECTIVE_POINT_WIDTH + 2 + 4 + STATE_WIDTH;
+// 1 joint scalar multiplication, 4 field elements, 1 hash state.
+// s.G + h.P is accumulated with Shamir's trick along a single chain of doublings, adding
+// G, P or G + P depending on the bits of s and h.
+pub const TRACE_WIDTH: usize = JOINT_STEP_WIDTH + 4 + STATE_WIDTH;
 /// Total number of steps in the trace for a single signature
-pub const SIG_CYCLE_LENGTH: usize = 512;
+// The iterated hash runs alongside the scalar multiplication, followed by the reduction of
+// the x-coordinate of its result
+pub const SIG_CYCLE_LENGTH: usize = (SCALAR_MUL_LENGTH + 2).next_power_of_two();
+
+// The hash digest is compared to the decomposed challenge once the scalar multiplications end
+const _: () = assert!(
+    NUM_HASH_ITER > 1 && TOTAL_HASH_LENGTH <= SCALAR_MUL_LENGTH,
+    "the message hash must fit within the scalar multiplications"
+);
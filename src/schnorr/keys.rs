@@ -0,0 +1,408 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Schnorr keys, and their hierarchical derivation from a seed.
+//!
+//! Keys are derived in a similar fashion to BIP32: an `ExtendedSecretKey` pairs a secret key
+//! with a chain code, from which child keys are derived by index. Children of index below
+//! `HARDENED_OFFSET` can also be derived from the parent `ExtendedPublicKey` alone, their
+//! public keys being those of the children derived from the parent `ExtendedSecretKey`,
+//! while hardened children require the parent secret key. Derivations are computed with
+//! Rescue over a state separated from other Rescue digests.
+
+use super::constants::{KEY_DERIVATION_DOMAIN, POINT_COORDINATE_WIDTH};
+use super::{scalar_from_elements, AFFINE_POINT_WIDTH, MESSAGE_LENGTH};
+use crate::utils::rescue::{Hash, RescueSponge};
+use core::fmt;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use winterfell::math::{
+    curves::curve_f63::{AffinePoint, Scalar},
+    fields::f63::BaseElement,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Index of the first hardened child key, which cannot be derived from the parent public key
+pub const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// Number of bytes of a serialized `SecretKey`
+pub const SECRET_KEY_SIZE: usize = 32;
+/// Number of bytes of a serialized `PublicKey`, i.e. its compressed point
+pub const PUBLIC_KEY_SIZE: usize = 49;
+/// Number of bytes of a serialized chain code
+pub const CHAIN_CODE_SIZE: usize = 56;
+
+// SECRET KEY
+// ================================================================================================
+
+/// A secret key signing Schnorr signatures.
+///
+/// The key is stored as the canonical encoding of its scalar, which is zeroized once the key
+/// is dropped, and keys are compared in constant time.
+#[derive(Clone)]
+pub struct SecretKey([u8; SECRET_KEY_SIZE]);
+
+impl SecretKey {
+    /// Samples a new secret key from the provided `rng`.
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        SecretKey::from(Scalar::random(rng))
+    }
+
+    /// Returns the secret key of the master key derived from `seed`.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        ExtendedSecretKey::from_seed(seed).secret_key()
+    }
+
+    /// Returns the public key associated to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(AffinePoint::from(
+            AffinePoint::generator() * self.to_scalar(),
+        ))
+    }
+
+    /// Computes a Schnorr signature of `message` with this secret key. The first
+    /// `AFFINE_POINT_WIDTH` elements of the message are expected to be the affine
+    /// coordinates of the associated public key.
+    pub fn sign(
+        &self,
+        message: [BaseElement; MESSAGE_LENGTH],
+    ) -> ([BaseElement; POINT_COORDINATE_WIDTH], Scalar) {
+        super::sign(message, self)
+    }
+
+    /// Computes a Schnorr signature of `message` with this secret key, sampling the nonce
+    /// from the provided `rng`.
+    pub fn sign_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        message: [BaseElement; MESSAGE_LENGTH],
+        rng: &mut R,
+    ) -> ([BaseElement; POINT_COORDINATE_WIDTH], Scalar) {
+        super::sign_with_rng(message, self, rng)
+    }
+
+    /// Returns the scalar of this secret key. Unlike the key itself, the returned scalar
+    /// is not zeroized once dropped.
+    pub fn to_scalar(&self) -> Scalar {
+        Scalar::from_bytes(&self.0).unwrap()
+    }
+
+    /// Serializes this secret key.
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_SIZE] {
+        self.0
+    }
+
+    /// Deserializes a secret key, returning `None` if `bytes` is not the canonical
+    /// encoding of a scalar.
+    pub fn from_bytes(bytes: &[u8; SECRET_KEY_SIZE]) -> Option<Self> {
+        Option::<Scalar>::from(Scalar::from_bytes(bytes)).map(SecretKey::from)
+    }
+}
+
+impl From<Scalar> for SecretKey {
+    fn from(scalar: Scalar) -> Self {
+        SecretKey(scalar.to_bytes())
+    }
+}
+
+impl ConstantTimeEq for SecretKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SecretKey {}
+
+impl Zeroize for SecretKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretKey {}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+// PUBLIC KEY
+// ================================================================================================
+
+/// A public key verifying Schnorr signatures.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey(AffinePoint);
+
+impl PublicKey {
+    /// Returns the point of this public key.
+    pub fn to_point(self) -> AffinePoint {
+        self.0
+    }
+
+    /// Returns the affine coordinates of this public key, as expected at the start of
+    /// signed messages.
+    pub fn to_elements(self) -> [BaseElement; AFFINE_POINT_WIDTH] {
+        let mut elements = [BaseElement::default(); AFFINE_POINT_WIDTH];
+        elements[..POINT_COORDINATE_WIDTH].copy_from_slice(&self.0.get_x());
+        elements[POINT_COORDINATE_WIDTH..].copy_from_slice(&self.0.get_y());
+
+        elements
+    }
+
+    /// Serializes this public key as a compressed point.
+    pub fn to_bytes(self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.0.to_compressed()
+    }
+
+    /// Deserializes a public key from a compressed point, returning `None` if `bytes` does
+    /// not encode a point of the curve other than the identity.
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_SIZE]) -> Option<Self> {
+        let point: Option<AffinePoint> = AffinePoint::from_compressed(bytes).into();
+
+        point
+            .filter(|point| !bool::from(point.is_identity()))
+            .map(PublicKey)
+    }
+}
+
+impl From<PublicKey> for AffinePoint {
+    fn from(key: PublicKey) -> Self {
+        key.0
+    }
+}
+
+// EXTENDED SECRET KEY
+// ================================================================================================
+
+/// A secret key along with the chain code deriving its child keys.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedSecretKey {
+    secret_key: SecretKey,
+    chain_code: Hash,
+}
+
+impl ExtendedSecretKey {
+    /// Derives a master key from `seed`. The same seed always yields the same key.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let mut sponge = RescueSponge::new_with_domain(KEY_DERIVATION_DOMAIN);
+        sponge.absorb(&bytes_to_elements(seed));
+        let secret_key = SecretKey::from(scalar_from_elements(&sponge.squeeze().to_elements()));
+        let chain_code = sponge.squeeze();
+
+        ExtendedSecretKey {
+            secret_key,
+            chain_code,
+        }
+    }
+
+    /// Returns the secret key of this extended key.
+    pub fn secret_key(&self) -> SecretKey {
+        self.secret_key.clone()
+    }
+
+    /// Returns the chain code of this extended key.
+    pub fn chain_code(&self) -> Hash {
+        self.chain_code
+    }
+
+    /// Returns the extended public key associated to this extended key, deriving the
+    /// public keys of its non-hardened children.
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            public_key: self.secret_key.public_key(),
+            chain_code: self.chain_code,
+        }
+    }
+
+    /// Derives the child key of this extended key at position `index`, which is hardened
+    /// if `index` is at least `HARDENED_OFFSET`.
+    pub fn derive_child(&self, index: u32) -> Self {
+        let (tweak, chain_code) = if index >= HARDENED_OFFSET {
+            derive_tweak(
+                &self.chain_code,
+                &bytes_to_elements(&self.secret_key.to_bytes()),
+                index,
+            )
+        } else {
+            derive_tweak(
+                &self.chain_code,
+                &self.secret_key.public_key().to_elements(),
+                index,
+            )
+        };
+
+        ExtendedSecretKey {
+            secret_key: SecretKey::from(self.secret_key.to_scalar() + tweak),
+            chain_code,
+        }
+    }
+
+    /// Derives the descendant key of this extended key reached by successively deriving
+    /// the children at the positions of `path`.
+    pub fn derive_path(&self, path: &[u32]) -> Self {
+        path.iter()
+            .fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+
+    /// Serializes this extended key as its secret key followed by its chain code.
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_SIZE + CHAIN_CODE_SIZE] {
+        let mut bytes = [0u8; SECRET_KEY_SIZE + CHAIN_CODE_SIZE];
+        bytes[..SECRET_KEY_SIZE].copy_from_slice(&self.secret_key.to_bytes());
+        bytes[SECRET_KEY_SIZE..].copy_from_slice(&chain_code_to_bytes(&self.chain_code));
+
+        bytes
+    }
+
+    /// Deserializes an extended key, returning `None` if `bytes` is not the canonical
+    /// encoding of a secret key followed by a chain code.
+    pub fn from_bytes(bytes: &[u8; SECRET_KEY_SIZE + CHAIN_CODE_SIZE]) -> Option<Self> {
+        let mut secret_bytes = [0u8; SECRET_KEY_SIZE];
+        secret_bytes.copy_from_slice(&bytes[..SECRET_KEY_SIZE]);
+
+        Some(ExtendedSecretKey {
+            secret_key: SecretKey::from_bytes(&secret_bytes)?,
+            chain_code: chain_code_from_bytes(&bytes[SECRET_KEY_SIZE..])?,
+        })
+    }
+}
+
+impl fmt::Debug for ExtendedSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ExtendedSecretKey(..)")
+    }
+}
+
+// EXTENDED PUBLIC KEY
+// ================================================================================================
+
+/// A public key along with the chain code deriving its non-hardened child keys.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    public_key: PublicKey,
+    chain_code: Hash,
+}
+
+impl ExtendedPublicKey {
+    /// Returns the public key of this extended key.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// Returns the chain code of this extended key.
+    pub fn chain_code(&self) -> Hash {
+        self.chain_code
+    }
+
+    /// Derives the public child key of this extended key at position `index`, returning
+    /// `None` if `index` is hardened, i.e. at least `HARDENED_OFFSET`.
+    pub fn derive_child(&self, index: u32) -> Option<Self> {
+        if index >= HARDENED_OFFSET {
+            return None;
+        }
+        let (tweak, chain_code) =
+            derive_tweak(&self.chain_code, &self.public_key.to_elements(), index);
+        let point = AffinePoint::from(AffinePoint::generator() * tweak + self.public_key.0);
+
+        Some(ExtendedPublicKey {
+            public_key: PublicKey(point),
+            chain_code,
+        })
+    }
+
+    /// Serializes this extended key as its compressed public key followed by its chain code.
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE + CHAIN_CODE_SIZE] {
+        let mut bytes = [0u8; PUBLIC_KEY_SIZE + CHAIN_CODE_SIZE];
+        bytes[..PUBLIC_KEY_SIZE].copy_from_slice(&self.public_key.to_bytes());
+        bytes[PUBLIC_KEY_SIZE..].copy_from_slice(&chain_code_to_bytes(&self.chain_code));
+
+        bytes
+    }
+
+    /// Deserializes an extended key, returning `None` if `bytes` is not the canonical
+    /// encoding of a public key followed by a chain code.
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_SIZE + CHAIN_CODE_SIZE]) -> Option<Self> {
+        let mut public_bytes = [0u8; PUBLIC_KEY_SIZE];
+        public_bytes.copy_from_slice(&bytes[..PUBLIC_KEY_SIZE]);
+
+        Some(ExtendedPublicKey {
+            public_key: PublicKey::from_bytes(&public_bytes)?,
+            chain_code: chain_code_from_bytes(&bytes[PUBLIC_KEY_SIZE..])?,
+        })
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the scalar added to the parent secret key, and the chain code of the child key
+/// at position `index`, given the chain code of the parent and its derivation key, i.e.
+/// its secret key for hardened children or its public key otherwise.
+fn derive_tweak(chain_code: &Hash, key: &[BaseElement], index: u32) -> (Scalar, Hash) {
+    let mut sponge = RescueSponge::new_with_domain(KEY_DERIVATION_DOMAIN);
+    sponge.absorb(&chain_code.to_elements());
+    sponge.absorb(key);
+    sponge.absorb(&[BaseElement::new(index as u64)]);
+    let tweak = scalar_from_elements(&sponge.squeeze().to_elements());
+
+    (tweak, sponge.squeeze())
+}
+
+/// Splits `bytes` into chunks of 7 bytes, each of them fitting in a field element.
+fn bytes_to_elements(bytes: &[u8; 32]) -> [BaseElement; 5] {
+    let mut elements = [BaseElement::default(); 5];
+    for (element, chunk) in elements.iter_mut().zip(bytes.chunks(7)) {
+        let mut word = [0u8; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        *element = BaseElement::new(u64::from_le_bytes(word));
+    }
+
+    elements
+}
+
+fn chain_code_to_bytes(chain_code: &Hash) -> [u8; CHAIN_CODE_SIZE] {
+    let mut bytes = [0u8; CHAIN_CODE_SIZE];
+    for (chunk, element) in bytes.chunks_mut(8).zip(chain_code.to_elements().iter()) {
+        chunk.copy_from_slice(&element.to_bytes());
+    }
+
+    bytes
+}
+
+fn chain_code_from_bytes(bytes: &[u8]) -> Option<Hash> {
+    let mut elements = [BaseElement::default(); 7];
+    for (element, chunk) in elements.iter_mut().zip(bytes.chunks(8)) {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        *element = BaseElement::from_bytes(&word)?;
+    }
+
+    Some(Hash::new(
+        elements[0],
+        elements[1],
+        elements[2],
+        elements[3],
+        elements[4],
+        elements[5],
+        elements[6],
+    ))
+}
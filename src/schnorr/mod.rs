@@ -7,15 +7,15 @@
 // except according to those terms.
 
 use bitvec::{order::Lsb0, view::AsBits};
-use rand_core::OsRng;
+use rand_core::{CryptoRng, OsRng, RngCore};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
 use winterfell::{
-    crypto::Hasher,
     math::{
-        curves::curve_f63::{AffinePoint, Scalar},
+        curves::curve_f63::{AffinePoint, ProjectivePoint, Scalar},
         fields::f63::BaseElement,
         FieldElement,
     },
-    FieldExtension, HashFunction, ProofOptions, Prover, StarkProof, VerifierError,
+    ProofOptions, Prover, StarkProof, VerifierError,
 };
 
 #[cfg(not(feature = "std"))]
@@ -29,20 +29,29 @@ use std::time::Instant;
 use winterfell::{math::log2, Trace};
 
 use super::utils::{
+    bits,
     ecc::{self, AFFINE_POINT_WIDTH, POINT_COORDINATE_WIDTH},
-    field,
-    rescue::{self, Rescue63, RATE_WIDTH as HASH_RATE_WIDTH},
+    rescue::{self, RescueSponge, RATE_WIDTH as HASH_RATE_WIDTH},
 };
+use super::ProofProfile;
 
 pub(crate) mod constants;
+pub use constants::MESSAGE_LENGTH;
+pub mod keys;
+pub mod musig;
+pub mod threshold;
+use keys::SecretKey;
 mod trace;
 pub(crate) use trace::{
     build_sig_info, init_sig_verification_state, update_sig_verification_state,
 };
 
 mod air;
-pub(crate) use air::{evaluate_constraints, periodic_columns, transition_constraint_degrees};
-use air::{PublicInputs, SchnorrAir};
+pub use air::PublicInputs;
+use air::SchnorrAir;
+pub(crate) use air::{
+    evaluate_constraints, periodic_columns, transition_constraint_degrees, HASH_HOLD_FLAG_INDEX,
+};
 
 mod prover;
 pub(crate) use prover::SchnorrProver;
@@ -53,21 +62,16 @@ mod tests;
 // SCHNORR SIGNATURE EXAMPLE
 // ================================================================================================
 
-/// Outputs a new `SchnorrExample` with `num_signatures` signatures on random messages.
+/// Outputs a new `SchnorrExample` with `num_signatures` signatures on random messages,
+/// using the `Balanced` proof profile.
 pub fn get_example(num_signatures: usize) -> SchnorrExample {
-    SchnorrExample::new(
-        // TODO: make it customizable
-        ProofOptions::new(
-            42,
-            8,
-            0,
-            HashFunction::Blake3_256,
-            FieldExtension::None,
-            4,
-            256,
-        ),
-        num_signatures,
-    )
+    get_example_with_options(ProofProfile::Balanced.to_proof_options(), num_signatures)
+}
+
+/// Outputs a new `SchnorrExample` with `num_signatures` signatures on random messages,
+/// using the provided proof options.
+pub fn get_example_with_options(options: ProofOptions, num_signatures: usize) -> SchnorrExample {
+    SchnorrExample::new(options, num_signatures)
 }
 
 /// A struct to perform Schnorr signature valid
@@ -75,7 +79,7 @@ pub fn get_example(num_signatures: usize) -> SchnorrExample {
 #[derive(Clone, Debug)]
 pub struct SchnorrExample {
     options: ProofOptions,
-    messages: Vec<[BaseElement; AFFINE_POINT_WIDTH * 2 + 4]>,
+    messages: Vec<[BaseElement; MESSAGE_LENGTH]>,
     signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
 }
 
@@ -88,12 +92,10 @@ impl SchnorrExample {
         let mut signatures = Vec::with_capacity(num_signatures);
 
         for _ in 0..num_signatures {
-            let skey = Scalar::random(&mut rng);
-            let pkey = AffinePoint::from(AffinePoint::generator() * skey);
+            let skey = keys::SecretKey::random(&mut rng);
 
-            let mut message = [BaseElement::ZERO; AFFINE_POINT_WIDTH * 2 + 4];
-            message[0..POINT_COORDINATE_WIDTH].copy_from_slice(&pkey.get_x());
-            message[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&pkey.get_y());
+            let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
+            message[..AFFINE_POINT_WIDTH].copy_from_slice(&skey.public_key().to_elements());
             for msg in message.iter_mut().skip(AFFINE_POINT_WIDTH) {
                 *msg = BaseElement::random(&mut rng);
             }
@@ -107,7 +109,7 @@ impl SchnorrExample {
         let now = Instant::now();
 
         for i in 0..num_signatures {
-            signatures.push(sign(messages[i], skeys[i]));
+            signatures.push(skeys[i].sign(messages[i]));
         }
 
         #[cfg(feature = "std")]
@@ -141,34 +143,11 @@ impl SchnorrExample {
 
     /// Proves the validity of a sequence of Schnorr signatures
     pub fn prove(&self) -> StarkProof {
-        // generate the execution trace
-        #[cfg(feature = "std")]
-        debug!(
-            "Generating proof for verifying {} Schnorr signatures\n\
-            ---------------------",
-            self.messages.len(),
-        );
-
-        let prover = SchnorrProver::new(
+        prove(
             self.options.clone(),
             self.messages.clone(),
             self.signatures.clone(),
-        );
-
-        // generate the execution trace
-        #[cfg(feature = "std")]
-        let now = Instant::now();
-        let trace = prover.build_trace();
-        #[cfg(feature = "std")]
-        debug!(
-            "Generated execution trace of {} registers and 2^{} steps in {} ms",
-            trace.width(),
-            log2(trace.length()),
-            now.elapsed().as_millis()
-        );
-
-        // generate the proof
-        prover.prove(trace).unwrap()
+        )
     }
 
     /// Verifies the validity of a proof of correct Schnorr signature verification
@@ -177,7 +156,7 @@ impl SchnorrExample {
             messages: self.messages.clone(),
             signatures: self.signatures.clone(),
         };
-        winterfell::verify::<SchnorrAir>(proof, pub_inputs)
+        verify_proof(proof, pub_inputs)
     }
 
     #[cfg(test)]
@@ -190,35 +169,103 @@ impl SchnorrExample {
     }
 }
 
+// SCHNORR SIGNATURE PROOFS
+// ================================================================================================
+
+/// Proves the validity of the provided Schnorr signatures over their respective messages,
+/// the first `AFFINE_POINT_WIDTH` elements of each message being the affine coordinates of
+/// the signer's public key.
+///
+/// # Panics
+/// Panics if `messages` and `signatures` are not of the same length, if their number is not
+/// a power of two, or if any of the signatures is invalid.
+pub fn prove(
+    options: ProofOptions,
+    messages: Vec<[BaseElement; MESSAGE_LENGTH]>,
+    signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
+) -> StarkProof {
+    assert_eq!(
+        messages.len(),
+        signatures.len(),
+        "number of messages and signatures must match"
+    );
+    assert!(
+        messages.len().is_power_of_two(),
+        "number of signatures must be a power of two"
+    );
+
+    #[cfg(feature = "std")]
+    debug!(
+        "Generating proof for verifying {} Schnorr signatures\n\
+        ---------------------",
+        messages.len(),
+    );
+
+    let prover = SchnorrProver::new(options, messages, signatures);
+
+    // generate the execution trace
+    #[cfg(feature = "std")]
+    let now = Instant::now();
+    let trace = prover.build_trace();
+    #[cfg(feature = "std")]
+    debug!(
+        "Generated execution trace of {} registers and 2^{} steps in {} ms",
+        trace.width(),
+        log2(trace.length()),
+        now.elapsed().as_millis()
+    );
+
+    // generate the proof
+    prover.prove(trace).unwrap()
+}
+
+/// Verifies a proof of validity of the Schnorr signatures and messages contained in
+/// the provided public inputs.
+pub fn verify_proof(proof: StarkProof, pub_inputs: PublicInputs) -> Result<(), VerifierError> {
+    winterfell::verify::<SchnorrAir>(proof, pub_inputs)
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
-/// Computes a Schnorr signature
-pub(crate) fn sign(
-    message: [BaseElement; AFFINE_POINT_WIDTH * 2 + 4],
-    skey: Scalar,
+/// Computes a Schnorr signature of `message` with the secret key `skey`. The first
+/// `AFFINE_POINT_WIDTH` elements of the message are expected to be the affine coordinates
+/// of the associated public key.
+pub fn sign(
+    message: [BaseElement; MESSAGE_LENGTH],
+    skey: &SecretKey,
 ) -> ([BaseElement; POINT_COORDINATE_WIDTH], Scalar) {
-    let mut rng = OsRng;
-    let r = Scalar::random(&mut rng);
-    let r_point = AffinePoint::from(AffinePoint::generator() * r);
+    sign_with_rng(message, skey, &mut OsRng)
+}
 
-    let h = hash_message(r_point.get_x(), message);
-    let mut h_bytes = [0u8; 32];
-    for (i, h_word) in h.iter().enumerate().take(4) {
-        h_bytes[8 * i..8 * i + 8].copy_from_slice(&h_word.to_bytes());
-    }
-    let h_bits = h_bytes.as_bits::<Lsb0>();
+/// Computes a Schnorr signature of `message` with the secret key `skey`, sampling the
+/// nonce from the provided `rng`. The first `AFFINE_POINT_WIDTH` elements of the message
+/// are expected to be the affine coordinates of the associated public key.
+///
+/// The secret key and the nonce are only involved in constant-time operations.
+pub fn sign_with_rng<R: RngCore + CryptoRng>(
+    message: [BaseElement; MESSAGE_LENGTH],
+    skey: &SecretKey,
+    rng: &mut R,
+) -> ([BaseElement; POINT_COORDINATE_WIDTH], Scalar) {
+    let r = Scalar::random(rng);
+    let r_point = AffinePoint::from(AffinePoint::generator() * r);
+    // Commit to the nonce point recovered from its x-coordinate alone, so that the
+    // signature can be batch verified. This leaves the signed x-coordinate unchanged.
+    let negate = !lift_x_ct(&r_point.get_x()).ct_eq(&CtOption::new(r_point, Choice::from(1)));
+    let r = Scalar::conditional_select(&r, &-r, negate);
+    let r_point = AffinePoint::conditional_select(&r_point, &-r_point, negate);
 
-    // Reconstruct a scalar from the binary sequence of h
-    let h_scalar = Scalar::from_bits(h_bits);
+    let h_scalar = compute_challenge(r_point.get_x(), message);
 
-    let s = r - skey * h_scalar;
+    let s = r - skey.to_scalar() * h_scalar;
     (r_point.get_x(), s)
 }
 
-/// Verifies a Schnorr signature
-pub(crate) fn verify_signature(
-    message: [BaseElement; AFFINE_POINT_WIDTH * 2 + 4],
+/// Verifies a Schnorr signature of `message`, signed with the public key whose affine
+/// coordinates are the first `AFFINE_POINT_WIDTH` elements of the message.
+pub fn verify_signature(
+    message: [BaseElement; MESSAGE_LENGTH],
     signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
 ) -> bool {
     let s_point = AffinePoint::generator() * signature.1;
@@ -227,15 +274,7 @@ pub(crate) fn verify_signature(
     let pkey = AffinePoint::from_raw_coordinates(pkey_coords);
     assert!(pkey.is_on_curve());
 
-    let h = hash_message(signature.0, message);
-    let mut h_bytes = [0u8; 32];
-    for (i, h_word) in h.iter().enumerate().take(4) {
-        h_bytes[8 * i..8 * i + 8].copy_from_slice(&h_word.to_bytes());
-    }
-    let h_bits = h_bytes.as_bits::<Lsb0>();
-
-    // Reconstruct a scalar from the binary sequence of h
-    let h_scalar = Scalar::from_bits(h_bits);
+    let h_scalar = compute_challenge(signature.0, message);
 
     let h_pubkey_point = pkey * h_scalar;
 
@@ -244,45 +283,193 @@ pub(crate) fn verify_signature(
     r_point.get_x() == signature.0
 }
 
+/// Verifies a Schnorr signature of `message` carrying the full nonce point `R` rather than
+/// its x-coordinate alone, signed with the public key whose affine coordinates are the first
+/// `AFFINE_POINT_WIDTH` elements of the message.
+///
+/// The challenge is computed from the x-coordinate of `R` only, and the signature is valid
+/// if and only if `s.G + h.P = R`, both coordinates included.
+pub fn verify_signature_with_point(
+    message: [BaseElement; MESSAGE_LENGTH],
+    signature: (AffinePoint, Scalar),
+) -> bool {
+    let (r_point, s) = signature;
+    if !r_point.is_on_curve() || bool::from(r_point.is_identity()) {
+        return false;
+    }
+    let mut pkey_coords = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    pkey_coords[..AFFINE_POINT_WIDTH].clone_from_slice(&message[..AFFINE_POINT_WIDTH]);
+    let pkey = AffinePoint::from_raw_coordinates(pkey_coords);
+    assert!(pkey.is_on_curve());
+
+    let h_scalar = compute_challenge(r_point.get_x(), message);
+
+    AffinePoint::from(AffinePoint::generator() * s + pkey * h_scalar) == r_point
+}
+
+/// Converts a signature carrying the full nonce point `R` into the encoding carrying its
+/// x-coordinate alone, as used by `verify_signature` and proven by the AIR programs.
+///
+/// Returns `None` if `R` is not a valid point of the curve. Any signature accepted by
+/// `verify_signature_with_point` is accepted once converted, as the x-coordinate of
+/// `s.G + h.P` matching the one of `R` only leaves its y-coordinate unchecked.
+pub fn signature_from_point(
+    r_point: &AffinePoint,
+    s: Scalar,
+) -> Option<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)> {
+    if !r_point.is_on_curve() || bool::from(r_point.is_identity()) {
+        return None;
+    }
+
+    Some((r_point.get_x(), s))
+}
+
+/// Converts a signature carrying the compressed nonce point `R`, i.e. its x-coordinate
+/// along with the sign of its y-coordinate, into the encoding carrying its x-coordinate alone.
+///
+/// The y-coordinate is reconstructed from the x-coordinate and the sign bit, and `None` is
+/// returned if the encoding does not correspond to a valid point of the curve.
+pub fn signature_from_compressed(
+    r_bytes: &[u8; 49],
+    s: Scalar,
+) -> Option<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)> {
+    let r_point: Option<AffinePoint> = AffinePoint::from_compressed(r_bytes).into();
+
+    r_point.and_then(|r_point| signature_from_point(&r_point, s))
+}
+
+/// Converts a signature carrying the x-coordinate of its nonce point `R` into the encoding
+/// carrying `R` compressed, as expected by external signers and verifiers.
+///
+/// As `R` is recovered from its x-coordinate alone, the resulting signature passes
+/// `verify_signature_with_point` only if it commits to the nonce point recovered this way,
+/// as produced by this crate. Returns `None` if no point of the curve has this x-coordinate.
+pub fn signature_to_compressed(
+    signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
+) -> Option<([u8; 49], Scalar)> {
+    lift_x(&signature.0).map(|r_point| (r_point.to_compressed(), signature.1))
+}
+
+/// Verifies a batch of Schnorr signatures, `pkeys[i]` being the public key of the
+/// signer of `messages[i]`, and returns `true` if and only if all of them are valid.
+///
+/// The verification equations `s.G + h.P = R` of all signatures are combined with random
+/// coefficients, and checked at once with a single multi-scalar multiplication, which is
+/// much faster than verifying the signatures one by one. As the nonce points `R` are
+/// recovered from their x-coordinates, only signatures committing to the nonce point
+/// recovered this way, as produced by this crate, pass batch verification. Other valid
+/// signatures can still be checked individually.
+///
+/// # Panics
+/// Panics if `messages`, `signatures` and `pkeys` are not of the same length.
+pub fn verify_batch(
+    messages: &[[BaseElement; MESSAGE_LENGTH]],
+    signatures: &[([BaseElement; POINT_COORDINATE_WIDTH], Scalar)],
+    pkeys: &[AffinePoint],
+) -> bool {
+    assert_eq!(
+        messages.len(),
+        signatures.len(),
+        "number of messages and signatures must match"
+    );
+    assert_eq!(
+        messages.len(),
+        pkeys.len(),
+        "number of messages and public keys must match"
+    );
+
+    let mut rng = OsRng;
+    let mut s_sum = Scalar::zero();
+    let mut terms = Vec::with_capacity(2 * messages.len() + 1);
+    for ((message, signature), pkey) in messages.iter().zip(signatures).zip(pkeys) {
+        if !pkey.is_on_curve() {
+            return false;
+        }
+        let r_point = match lift_x(&signature.0) {
+            Some(point) => point,
+            None => return false,
+        };
+        let h_scalar = compute_challenge(signature.0, *message);
+
+        // Sample a 128-bit random coefficient for this signature
+        let mut a_bytes = [0u8; 32];
+        rng.fill_bytes(&mut a_bytes[..16]);
+        let a = Scalar::from_bytes(&a_bytes).unwrap();
+
+        s_sum += a * signature.1;
+        terms.push((*pkey, a * h_scalar));
+        terms.push((-r_point, a));
+    }
+    terms.push((AffinePoint::generator(), s_sum));
+
+    bool::from(AffinePoint::from(multi_scalar_mul(&terms)).is_identity())
+}
+
+/// Returns the point whose x-coordinate is `x` and whose y-coordinate is the
+/// lexicographically smallest of the two candidates, if any.
+fn lift_x(x: &[BaseElement; POINT_COORDINATE_WIDTH]) -> Option<AffinePoint> {
+    lift_x_ct(x).into()
+}
+
+/// Returns the point whose x-coordinate is `x` and whose y-coordinate is the
+/// lexicographically smallest of the two candidates, if any, in constant time.
+fn lift_x_ct(x: &[BaseElement; POINT_COORDINATE_WIDTH]) -> CtOption<AffinePoint> {
+    let mut bytes = [0u8; 49];
+    for (chunk, coordinate) in bytes[..48].chunks_mut(8).zip(x.iter()) {
+        chunk.copy_from_slice(&coordinate.to_bytes());
+    }
+    // Set the compression flag only
+    bytes[48] = 1 << 7;
+
+    AffinePoint::from_compressed(&bytes)
+}
+
+/// Computes the sum of the provided points multiplied by their associated scalars,
+/// sharing the point doublings among all of them.
+fn multi_scalar_mul(terms: &[(AffinePoint, Scalar)]) -> ProjectivePoint {
+    let scalars: Vec<[u8; 32]> = terms.iter().map(|(_, scalar)| scalar.to_bytes()).collect();
+
+    let mut result = ProjectivePoint::identity();
+    for bit in (0..256).rev() {
+        result += result;
+        for ((point, _), scalar) in terms.iter().zip(scalars.iter()) {
+            if (scalar[bit / 8] >> (bit % 8)) & 1 == 1 {
+                result = result + *point;
+            }
+        }
+    }
+
+    result
+}
+
+/// Computes the challenge scalar of a signature from the x-coordinate of its nonce point
+/// and the signed message.
+fn compute_challenge(
+    r_x: [BaseElement; POINT_COORDINATE_WIDTH],
+    message: [BaseElement; MESSAGE_LENGTH],
+) -> Scalar {
+    let h = hash_message(r_x, message);
+
+    scalar_from_elements(&h)
+}
+
+/// Reconstructs a scalar from the binary sequence of the first four provided elements.
+fn scalar_from_elements(elements: &[BaseElement]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    for (i, word) in elements.iter().enumerate().take(4) {
+        bytes[8 * i..8 * i + 8].copy_from_slice(&word.to_bytes());
+    }
+
+    Scalar::from_bits(bytes.as_bits::<Lsb0>())
+}
+
 fn hash_message(
     input: [BaseElement; POINT_COORDINATE_WIDTH],
-    message: [BaseElement; AFFINE_POINT_WIDTH * 2 + 4],
+    message: [BaseElement; MESSAGE_LENGTH],
 ) -> [BaseElement; HASH_RATE_WIDTH] {
-    let mut h = Rescue63::digest(&input);
-    let mut message_chunk = rescue::Hash::new(
-        message[0], message[1], message[2], message[3], message[4], message[5], message[6],
-    );
-    h = Rescue63::merge(&[h, message_chunk]);
-    message_chunk = rescue::Hash::new(
-        message[7],
-        message[8],
-        message[9],
-        message[10],
-        message[11],
-        message[12],
-        message[13],
-    );
-    h = Rescue63::merge(&[h, message_chunk]);
-    message_chunk = rescue::Hash::new(
-        message[14],
-        message[15],
-        message[16],
-        message[17],
-        message[18],
-        message[19],
-        message[20],
-    );
-    h = Rescue63::merge(&[h, message_chunk]);
-    message_chunk = rescue::Hash::new(
-        message[21],
-        message[22],
-        message[23],
-        message[24],
-        message[25],
-        message[26],
-        message[27],
-    );
-    h = Rescue63::merge(&[h, message_chunk]);
+    let mut sponge = RescueSponge::new_with_domain(constants::CHALLENGE_DOMAIN);
+    sponge.absorb(&input);
+    sponge.absorb(&message);
 
-    h.to_elements()
+    sponge.squeeze().to_elements()
 }
@@ -0,0 +1,216 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Two-of-two aggregation of Schnorr keys and signatures, in the fashion of MuSig.
+//!
+//! Two signers holding the keys `X_1` and `X_2` control together the aggregated key
+//! `X = a_1.X_1 + a_2.X_2`, each coefficient `a_i` being hashed from both keys and from `X_i`,
+//! so that neither signer can choose its key to cancel out the other one. They sign in three
+//! rounds: each of them samples a `SecretNonce` and sends the commitment to its public nonce,
+//! then sends the public nonce itself once it received the other commitment, and finally its
+//! partial signature. Partial signatures add up to a Schnorr signature of the aggregated key,
+//! which is verified as any other signature, including by the AIR programs.
+
+use super::constants::{KEY_AGGREGATION_DOMAIN, NONCE_COMMITMENT_DOMAIN, POINT_COORDINATE_WIDTH};
+use super::keys::SecretKey;
+use super::{
+    compute_challenge, lift_x_ct, scalar_from_elements, AFFINE_POINT_WIDTH, MESSAGE_LENGTH,
+};
+use crate::utils::rescue::{Hash, RescueSponge};
+use core::fmt;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
+use winterfell::math::{
+    curves::curve_f63::{AffinePoint, ProjectivePoint, Scalar},
+    fields::f63::BaseElement,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Number of signers controlling an aggregated key
+pub const NUM_SIGNERS: usize = 2;
+
+// KEY AGGREGATION
+// ================================================================================================
+
+/// Returns the coefficient of the key of the signer at position `index` in the aggregate of
+/// `keys`.
+///
+/// # Panics
+/// Panics if `index` is not less than `NUM_SIGNERS`.
+pub fn key_coefficient(keys: &[AffinePoint; NUM_SIGNERS], index: usize) -> Scalar {
+    let mut sponge = RescueSponge::new_with_domain(KEY_AGGREGATION_DOMAIN);
+    for key in keys.iter() {
+        sponge.absorb(&point_elements(key));
+    }
+    sponge.absorb(&point_elements(&keys[index]));
+
+    scalar_from_elements(&sponge.squeeze().to_elements())
+}
+
+/// Returns the key aggregated from `keys`, i.e. the sum of the keys weighted by their
+/// coefficients, which is stored in the leaf of the account they control together.
+pub fn aggregate_keys(keys: &[AffinePoint; NUM_SIGNERS]) -> AffinePoint {
+    let mut aggregate = ProjectivePoint::identity();
+    for (index, key) in keys.iter().enumerate() {
+        aggregate += *key * key_coefficient(keys, index);
+    }
+
+    AffinePoint::from(aggregate)
+}
+
+// SECRET NONCE
+// ================================================================================================
+
+/// The secret nonce of a signer for a single aggregated signature.
+///
+/// A nonce cannot be cloned, and is consumed by `sign_partial`, as signing two messages with
+/// the same nonce would reveal the secret key of its signer. It is zeroized once dropped.
+pub struct SecretNonce([u8; 32]);
+
+impl SecretNonce {
+    /// Samples a new secret nonce from the provided `rng`.
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        SecretNonce(Scalar::random(rng).to_bytes())
+    }
+
+    /// Returns the public nonce associated to this secret nonce, to be sent to the other
+    /// signer once its commitment has been received.
+    pub fn public_nonce(&self) -> AffinePoint {
+        AffinePoint::from(AffinePoint::generator() * self.to_scalar())
+    }
+
+    fn to_scalar(&self) -> Scalar {
+        Scalar::from_bytes(&self.0).unwrap()
+    }
+}
+
+impl Zeroize for SecretNonce {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretNonce {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretNonce {}
+
+impl fmt::Debug for SecretNonce {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretNonce(..)")
+    }
+}
+
+/// Returns the commitment to `public_nonce`, sent to the other signer before the nonce
+/// itself. Without this first round, a signer could choose its nonce after seeing the other
+/// one, and forge signatures from concurrent signing sessions.
+pub fn commit_nonce(public_nonce: &AffinePoint) -> Hash {
+    let mut sponge = RescueSponge::new_with_domain(NONCE_COMMITMENT_DOMAIN);
+    sponge.absorb(&point_elements(public_nonce));
+
+    sponge.squeeze()
+}
+
+// SIGNING
+// ================================================================================================
+
+/// Computes the partial signature of `message` by the signer at position `index` of `keys`,
+/// holding `skey` and `nonce`, given the public nonces of both signers. The first
+/// `AFFINE_POINT_WIDTH` elements of the message are expected to be the affine coordinates of
+/// the key aggregated from `keys`.
+///
+/// The secret key and the nonce are only involved in constant-time operations.
+///
+/// # Panics
+/// Panics if `index` is not less than `NUM_SIGNERS`.
+pub fn sign_partial(
+    message: [BaseElement; MESSAGE_LENGTH],
+    skey: &SecretKey,
+    nonce: SecretNonce,
+    keys: &[AffinePoint; NUM_SIGNERS],
+    index: usize,
+    public_nonces: &[AffinePoint; NUM_SIGNERS],
+) -> Scalar {
+    let (r_point, negate) = aggregate_nonces(public_nonces);
+    let r = nonce.to_scalar();
+    let r = Scalar::conditional_select(&r, &-r, negate);
+
+    let h_scalar = compute_challenge(r_point.get_x(), message);
+
+    r - skey.to_scalar() * key_coefficient(keys, index) * h_scalar
+}
+
+/// Verifies the partial signature of `message` by the signer at position `index` of `keys`,
+/// so that a signer sending an invalid one can be identified before aggregation.
+///
+/// # Panics
+/// Panics if `index` is not less than `NUM_SIGNERS`.
+pub fn verify_partial(
+    message: [BaseElement; MESSAGE_LENGTH],
+    partial_signature: Scalar,
+    keys: &[AffinePoint; NUM_SIGNERS],
+    index: usize,
+    public_nonces: &[AffinePoint; NUM_SIGNERS],
+) -> bool {
+    let (r_point, negate) = aggregate_nonces(public_nonces);
+    let nonce =
+        AffinePoint::conditional_select(&public_nonces[index], &-public_nonces[index], negate);
+
+    let h_scalar = compute_challenge(r_point.get_x(), message);
+    let weighted_key = keys[index] * (key_coefficient(keys, index) * h_scalar);
+
+    AffinePoint::from(AffinePoint::generator() * partial_signature + weighted_key) == nonce
+}
+
+/// Combines the partial signatures of both signers into a Schnorr signature of the key
+/// aggregated from their keys, as verified by `verify_signature` and proven by the AIR
+/// programs.
+pub fn aggregate_signatures(
+    public_nonces: &[AffinePoint; NUM_SIGNERS],
+    partial_signatures: &[Scalar; NUM_SIGNERS],
+) -> ([BaseElement; POINT_COORDINATE_WIDTH], Scalar) {
+    let (r_point, _) = aggregate_nonces(public_nonces);
+    let s = partial_signatures
+        .iter()
+        .fold(Scalar::zero(), |sum, partial| sum + *partial);
+
+    (r_point.get_x(), s)
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the nonce point aggregated from `public_nonces`, recovered from its x-coordinate
+/// alone as for single signatures, and whether each signer must negate its nonce to match it.
+fn aggregate_nonces(public_nonces: &[AffinePoint; NUM_SIGNERS]) -> (AffinePoint, Choice) {
+    let r_point = AffinePoint::from(
+        public_nonces
+            .iter()
+            .fold(ProjectivePoint::identity(), |sum, nonce| sum + *nonce),
+    );
+    let negate = !lift_x_ct(&r_point.get_x()).ct_eq(&CtOption::new(r_point, Choice::from(1)));
+
+    (
+        AffinePoint::conditional_select(&r_point, &-r_point, negate),
+        negate,
+    )
+}
+
+fn point_elements(point: &AffinePoint) -> [BaseElement; AFFINE_POINT_WIDTH] {
+    let mut elements = [BaseElement::default(); AFFINE_POINT_WIDTH];
+    elements[..POINT_COORDINATE_WIDTH].copy_from_slice(&point.get_x());
+    elements[POINT_COORDINATE_WIDTH..].copy_from_slice(&point.get_y());
+
+    elements
+}
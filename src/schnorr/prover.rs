@@ -17,14 +17,14 @@ use alloc::vec::Vec;
 
 pub struct SchnorrProver {
     options: ProofOptions,
-    messages: Vec<[BaseElement; AFFINE_POINT_WIDTH * 2 + 4]>,
+    messages: Vec<[BaseElement; MESSAGE_LENGTH]>,
     signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
 }
 
 impl SchnorrProver {
     pub fn new(
         options: ProofOptions,
-        messages: Vec<[BaseElement; AFFINE_POINT_WIDTH * 2 + 4]>,
+        messages: Vec<[BaseElement; MESSAGE_LENGTH]>,
         signatures: Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
     ) -> Self {
         Self {
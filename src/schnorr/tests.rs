@@ -6,7 +6,17 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use winterfell::{FieldExtension, HashFunction, ProofOptions};
+use super::keys::SecretKey;
+use super::{AFFINE_POINT_WIDTH, MESSAGE_LENGTH, POINT_COORDINATE_WIDTH};
+use rand_core::OsRng;
+use winterfell::{
+    math::{
+        curves::curve_f63::{AffinePoint, Scalar},
+        fields::f63::BaseElement,
+        FieldElement,
+    },
+    FieldExtension, HashFunction, ProofOptions,
+};
 
 #[test]
 fn schnorr_test_basic_proof_verification() {
@@ -37,6 +47,456 @@ fn schnorr_test_basic_proof_verification_fail() {
     assert!(verified.is_err());
 }
 
+#[test]
+fn schnorr_test_standalone_proof() {
+    let (messages, signatures, _) = build_signed_messages(2);
+    let proof = super::prove(build_options(1), messages.clone(), signatures.clone());
+    let pub_inputs = super::PublicInputs {
+        messages: messages.clone(),
+        signatures: signatures.clone(),
+    };
+    assert!(super::verify_proof(proof.clone(), pub_inputs).is_ok());
+
+    let pub_inputs = super::PublicInputs {
+        messages: vec![messages[0]; 2],
+        signatures,
+    };
+    assert!(super::verify_proof(proof, pub_inputs).is_err());
+}
+
+#[test]
+fn schnorr_test_batch_verification() {
+    let (messages, mut signatures, mut pkeys) = build_signed_messages(4);
+    assert!(super::verify_batch(&messages, &signatures, &pkeys));
+
+    // A single invalid signature invalidates the whole batch
+    signatures[2].1 += Scalar::one();
+    assert!(!super::verify_batch(&messages, &signatures, &pkeys));
+    signatures[2].1 -= Scalar::one();
+    pkeys.swap(0, 1);
+    assert!(!super::verify_batch(&messages, &signatures, &pkeys));
+}
+
+#[test]
+fn schnorr_test_signature_encodings() {
+    let (messages, signatures, _) = build_signed_messages(2);
+    for (message, signature) in messages.iter().zip(signatures) {
+        // Signatures produced by this crate recover the nonce point from its x-coordinate
+        let (r_bytes, s) = super::signature_to_compressed(signature).unwrap();
+        let r_point = AffinePoint::from_compressed(&r_bytes).unwrap();
+        assert!(super::verify_signature_with_point(*message, (r_point, s)));
+        assert!(!super::verify_signature_with_point(*message, (-r_point, s)));
+
+        assert_eq!(super::signature_from_point(&r_point, s), Some(signature));
+        assert_eq!(
+            super::signature_from_compressed(&r_bytes, s),
+            Some(signature)
+        );
+        assert!(super::verify_signature(*message, signature));
+    }
+
+    // Invalid encodings of the nonce point are rejected
+    let mut r_bytes = [0xffu8; 49];
+    r_bytes[48] = 1 << 7;
+    assert_eq!(
+        super::signature_from_compressed(&r_bytes, Scalar::one()),
+        None
+    );
+}
+
+#[test]
+fn schnorr_test_key_derivation() {
+    use super::keys::{
+        ExtendedPublicKey, ExtendedSecretKey, PublicKey, SecretKey, HARDENED_OFFSET,
+    };
+
+    // Keys are derived deterministically from their seed
+    let master = ExtendedSecretKey::from_seed(&[7u8; 32]);
+    assert_eq!(master, ExtendedSecretKey::from_seed(&[7u8; 32]));
+    assert_ne!(master, ExtendedSecretKey::from_seed(&[8u8; 32]));
+    assert_eq!(master.secret_key(), SecretKey::from_seed(&[7u8; 32]));
+
+    // Non-hardened children can be derived from the parent public key alone
+    let child = master.derive_path(&[0, 1]);
+    let public_child = master.public_key().derive_child(0).unwrap().derive_child(1);
+    assert_eq!(Some(child.public_key()), public_child);
+    assert!(master.public_key().derive_child(HARDENED_OFFSET).is_none());
+    assert_ne!(
+        master.derive_child(HARDENED_OFFSET).secret_key(),
+        master.derive_child(0).secret_key()
+    );
+
+    // Keys can be persisted and restored
+    assert_eq!(
+        ExtendedSecretKey::from_bytes(&child.to_bytes()),
+        Some(child.clone())
+    );
+    let public_child = child.public_key();
+    assert_eq!(
+        ExtendedPublicKey::from_bytes(&public_child.to_bytes()),
+        Some(public_child)
+    );
+    let skey = child.secret_key();
+    assert_eq!(SecretKey::from_bytes(&skey.to_bytes()), Some(skey.clone()));
+    assert_eq!(
+        PublicKey::from_bytes(&skey.public_key().to_bytes()),
+        Some(skey.public_key())
+    );
+
+    // Derived keys sign messages as any other key
+    let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
+    message[..AFFINE_POINT_WIDTH].copy_from_slice(&skey.public_key().to_elements());
+    assert!(super::verify_signature(message, skey.sign(message)));
+}
+
+#[test]
+fn schnorr_test_secret_key_zeroize() {
+    use zeroize::Zeroize;
+
+    let mut skey = SecretKey::random(&mut OsRng);
+    let copy = skey.clone();
+    assert_eq!(skey, copy);
+    assert_eq!(format!("{:?}", copy), "SecretKey(..)");
+
+    skey.zeroize();
+    assert_eq!(skey.to_bytes(), [0u8; 32]);
+    assert_ne!(skey, copy);
+}
+
+#[test]
+fn schnorr_test_musig() {
+    use super::musig::{
+        aggregate_keys, aggregate_signatures, commit_nonce, sign_partial, verify_partial,
+        SecretNonce,
+    };
+    use winterfell::math::curves::curve_f63::ProjectivePoint;
+
+    let skeys = [SecretKey::random(&mut OsRng), SecretKey::random(&mut OsRng)];
+    let keys = [
+        skeys[0].public_key().to_point(),
+        skeys[1].public_key().to_point(),
+    ];
+    let aggregated_key = aggregate_keys(&keys);
+    assert_ne!(
+        aggregated_key,
+        AffinePoint::from(ProjectivePoint::from(keys[0]) + keys[1])
+    );
+
+    let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
+    message[0..POINT_COORDINATE_WIDTH].copy_from_slice(&aggregated_key.get_x());
+    message[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&aggregated_key.get_y());
+    for msg in message.iter_mut().skip(AFFINE_POINT_WIDTH) {
+        *msg = BaseElement::random(&mut OsRng);
+    }
+
+    // Both signers commit to their nonces before revealing them
+    let nonces = [
+        SecretNonce::random(&mut OsRng),
+        SecretNonce::random(&mut OsRng),
+    ];
+    let public_nonces = [nonces[0].public_nonce(), nonces[1].public_nonce()];
+    assert_ne!(
+        commit_nonce(&public_nonces[0]),
+        commit_nonce(&public_nonces[1])
+    );
+
+    let mut partial_signatures = [Scalar::zero(); 2];
+    for (index, (skey, nonce)) in skeys.iter().zip(nonces).enumerate() {
+        partial_signatures[index] =
+            sign_partial(message, skey, nonce, &keys, index, &public_nonces);
+        assert!(verify_partial(
+            message,
+            partial_signatures[index],
+            &keys,
+            index,
+            &public_nonces
+        ));
+    }
+    assert!(!verify_partial(
+        message,
+        partial_signatures[0],
+        &keys,
+        1,
+        &public_nonces
+    ));
+
+    // The aggregated signature is verified as any other one against the aggregated key
+    let signature = aggregate_signatures(&public_nonces, &partial_signatures);
+    assert!(super::verify_signature(message, signature));
+    assert!(super::verify_batch(
+        &[message],
+        &[signature],
+        &[aggregated_key]
+    ));
+
+    // Neither signer can sign alone for the aggregated key
+    partial_signatures[1] = Scalar::zero();
+    let signature = aggregate_signatures(&public_nonces, &partial_signatures);
+    assert!(!super::verify_signature(message, signature));
+}
+
+#[test]
+fn schnorr_test_threshold_signatures() {
+    use super::threshold::{signer_message, verify_threshold, KeyTree};
+    use crate::merkle::verify_path;
+
+    let skeys: Vec<_> = (0..3).map(|_| SecretKey::random(&mut OsRng)).collect();
+    let keys: Vec<_> = skeys
+        .iter()
+        .map(|skey| skey.public_key().to_point())
+        .collect();
+    let tree = KeyTree::new(&keys);
+    assert_eq!(tree.depth(), 2);
+    for index in 0..3 {
+        let path = tree.open(index);
+        assert!(verify_path(path[0], index, &path, tree.root()));
+    }
+
+    let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
+    for msg in message.iter_mut().skip(AFFINE_POINT_WIDTH) {
+        *msg = BaseElement::random(&mut OsRng);
+    }
+    let shares: Vec<_> = [0, 2]
+        .iter()
+        .map(|&index| {
+            let signature = super::sign(signer_message(message, &keys[index]), &skeys[index]);
+            tree.share(index, signature)
+        })
+        .collect();
+    assert!(verify_threshold(message, tree.root(), 2, &shares));
+    assert!(!verify_threshold(message, tree.root(), 3, &shares));
+
+    // A member cannot be counted twice
+    let duplicated = vec![shares[0].clone(), shares[0].clone()];
+    assert!(!verify_threshold(message, tree.root(), 2, &duplicated));
+
+    // Keys outside of the committee are rejected, even with a valid signature
+    let outsider = SecretKey::random(&mut OsRng);
+    let mut forged = shares.clone();
+    forged[1].key = outsider.public_key().to_point();
+    forged[1].signature = super::sign(signer_message(message, &forged[1].key), &outsider);
+    assert!(!verify_threshold(message, tree.root(), 2, &forged));
+
+    // Signatures are bound to the message
+    message[AFFINE_POINT_WIDTH] += BaseElement::ONE;
+    assert!(!verify_threshold(message, tree.root(), 2, &shares));
+}
+
+#[test]
+fn schnorr_test_joint_scalar_multiplication() {
+    use crate::utils::ecc::{self, JOINT_STEP_WIDTH, PROJECTIVE_POINT_WIDTH};
+
+    // Double-and-add of a point in affine coordinates by a scalar given MSB first, followed
+    // by the bit register of the conditional additions
+    let multiply = |point: &[BaseElement], bits: &[u8]| {
+        let mut state = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1];
+        state[POINT_COORDINATE_WIDTH] = BaseElement::ONE;
+        for &bit in bits {
+            ecc::apply_point_doubling(&mut state);
+            state[PROJECTIVE_POINT_WIDTH] = BaseElement::from(bit);
+            ecc::apply_point_addition_mixed(&mut state, point);
+        }
+        state
+    };
+    let mut point = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    point.copy_from_slice(&ecc::projective_to_affine(&multiply(
+        &ecc::GENERATOR,
+        &[1, 1],
+    )));
+
+    // All four pairs of bits select their point, and each step satisfies the constraints
+    let s_bits = [0, 1, 0, 1, 1, 0, 1];
+    let h_bits = [0, 0, 1, 1, 0, 1, 1];
+    let mut current = [BaseElement::ZERO; JOINT_STEP_WIDTH];
+    current[POINT_COORDINATE_WIDTH] = BaseElement::ONE;
+    for (&s_bit, &h_bit) in s_bits.iter().zip(h_bits.iter()) {
+        let mut next = current;
+        ecc::apply_point_doubling(&mut next);
+        ecc::apply_joint_selection(
+            &mut next,
+            BaseElement::from(s_bit),
+            BaseElement::from(h_bit),
+            &point,
+        );
+        let mut result = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1];
+        ecc::enforce_point_doubling(&mut result, &current, &next, BaseElement::ONE);
+        assert_eq!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1]);
+
+        current = next;
+        ecc::apply_joint_addition(&mut next);
+        let mut result = [BaseElement::ZERO; JOINT_STEP_WIDTH];
+        ecc::enforce_joint_addition(&mut result, &current, &next, &point, BaseElement::ONE);
+        assert_eq!(result, [BaseElement::ZERO; JOINT_STEP_WIDTH]);
+
+        // Another point than the selected one is rejected, unless nothing is added
+        let mut forged = current;
+        forged[JOINT_STEP_WIDTH - 1] += BaseElement::ONE;
+        ecc::enforce_joint_addition(&mut result, &forged, &next, &point, BaseElement::ONE);
+        assert_eq!(
+            result == [BaseElement::ZERO; JOINT_STEP_WIDTH],
+            s_bit + h_bit == 0
+        );
+        current = next;
+    }
+
+    // The joint scalar multiplication matches the sum of both scalar multiplications
+    let mut expected = multiply(&ecc::GENERATOR, &s_bits);
+    let mut h_point = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH];
+    h_point.copy_from_slice(&multiply(&point, &h_bits)[..PROJECTIVE_POINT_WIDTH]);
+    expected[PROJECTIVE_POINT_WIDTH] = BaseElement::ONE;
+    ecc::apply_point_addition(&mut expected, &h_point);
+    assert_eq!(
+        ecc::projective_to_affine(&current[..PROJECTIVE_POINT_WIDTH]),
+        ecc::projective_to_affine(&expected[..PROJECTIVE_POINT_WIDTH])
+    );
+
+    // Its result is reduced to its affine x-coordinate
+    let mut next = current;
+    ecc::apply_x_reduction(&mut next);
+    assert_eq!(
+        next[..POINT_COORDINATE_WIDTH],
+        ecc::projective_to_affine(&expected[..PROJECTIVE_POINT_WIDTH])[..POINT_COORDINATE_WIDTH]
+    );
+    let mut result = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH];
+    ecc::enforce_x_reduction(&mut result, &current, &next, BaseElement::ONE);
+    assert_eq!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH]);
+}
+
+#[test]
+fn schnorr_test_ecc_gadgets() {
+    use crate::utils::ecc::{self, PROJECTIVE_POINT_WIDTH};
+
+    let mut current = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1];
+    for (i, register) in current.iter_mut().enumerate() {
+        *register = BaseElement::from(i as u64 * 7919 + 13);
+    }
+    current[PROJECTIVE_POINT_WIDTH] = BaseElement::ONE;
+    let mut point = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    point.copy_from_slice(&ecc::GENERATOR);
+
+    // Each trace builder satisfies its constraint counterpart when its flag is set,
+    // and the constraints reject a different next step
+    let mut next = current;
+    ecc::apply_point_doubling(&mut next);
+    let mut result = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1];
+    ecc::enforce_point_doubling(&mut result, &current, &next, BaseElement::ONE);
+    assert_eq!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1]);
+
+    let mut next = current;
+    ecc::apply_point_addition_mixed(&mut next, &point);
+    let mut result = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1];
+    ecc::enforce_point_addition_mixed(&mut result, &current, &next, &point, BaseElement::ONE);
+    assert_eq!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1]);
+
+    let mut projective_point = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH];
+    projective_point.copy_from_slice(&current[..PROJECTIVE_POINT_WIDTH]);
+    let mut next = current;
+    ecc::apply_point_addition(&mut next, &projective_point);
+    let mut result = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1];
+    ecc::enforce_point_addition(
+        &mut result,
+        &current,
+        &next,
+        &projective_point,
+        BaseElement::ONE,
+    );
+    assert_eq!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1]);
+
+    ecc::enforce_point_addition(
+        &mut result,
+        &current,
+        &current,
+        &projective_point,
+        BaseElement::ONE,
+    );
+    assert_ne!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH + 1]);
+
+    // Field operations in Fp6
+    let a = &current[..POINT_COORDINATE_WIDTH];
+    assert_eq!(
+        ecc::mul_fp6(a, &ecc::invert_fp6(a)),
+        [
+            BaseElement::ONE,
+            BaseElement::ZERO,
+            BaseElement::ZERO,
+            BaseElement::ZERO,
+            BaseElement::ZERO,
+            BaseElement::ZERO
+        ]
+    );
+    assert_eq!(ecc::square_fp6(a), ecc::mul_fp6(a, a));
+}
+
+#[test]
+fn schnorr_test_point_normalization() {
+    use crate::utils::ecc::{self, PROJECTIVE_POINT_WIDTH};
+
+    let mut current = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH];
+    for (i, register) in current.iter_mut().enumerate() {
+        *register = BaseElement::from(i as u64 * 7919 + 13);
+    }
+    let mut next = current;
+    ecc::apply_point_normalization(&mut next);
+    assert_eq!(
+        next[..AFFINE_POINT_WIDTH],
+        ecc::projective_to_affine(&current)
+    );
+    assert_eq!(next[AFFINE_POINT_WIDTH], BaseElement::ONE);
+
+    let mut result = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH];
+    ecc::enforce_point_normalization(&mut result, &current, &next, BaseElement::ONE);
+    assert_eq!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH]);
+
+    // A normalized point is left unchanged by a second normalization
+    let mut normalized = next;
+    ecc::apply_point_normalization(&mut normalized);
+    assert_eq!(normalized, next);
+
+    next[0] += BaseElement::ONE;
+    ecc::enforce_point_normalization(&mut result, &current, &next, BaseElement::ONE);
+    assert_ne!(result, [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH]);
+}
+
+#[test]
+fn schnorr_test_message_hash_sponge() {
+    use super::constants::CHALLENGE_DOMAIN;
+    use crate::utils::rescue::{apply_permutation, Hash, Rescue63, RescueSponge, STATE_WIDTH};
+    use winterfell::crypto::Hasher;
+
+    let input = [BaseElement::from(3u8); POINT_COORDINATE_WIDTH];
+    let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
+    for (i, element) in message.iter_mut().enumerate() {
+        *element = BaseElement::from(i as u8);
+    }
+
+    // The message hash starts from the nonce point and the domain of signature challenges,
+    // and iterates over chunks of the message, as computed within the AIR
+    let mut state = [BaseElement::ZERO; STATE_WIDTH];
+    state[..POINT_COORDINATE_WIDTH].copy_from_slice(&input);
+    state[STATE_WIDTH - 1] = CHALLENGE_DOMAIN;
+    apply_permutation(&mut state);
+    let mut digest = Hash::new(
+        state[0], state[1], state[2], state[3], state[4], state[5], state[6],
+    );
+    assert_ne!(digest, Rescue63::digest(&input));
+    for chunk in message.chunks(7) {
+        let chunk = Hash::new(
+            chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6],
+        );
+        digest = Rescue63::merge(&[digest, chunk]);
+    }
+    assert_eq!(super::hash_message(input, message), digest.to_elements());
+
+    let mut sponge = RescueSponge::new_with_domain(CHALLENGE_DOMAIN);
+    sponge.absorb(&input);
+    sponge.absorb(&message);
+    assert_eq!(sponge.squeeze(), digest);
+    assert_eq!(
+        sponge.squeeze(),
+        Rescue63::merge(&[digest, Hash::default()])
+    );
+}
+
 fn build_options(extension: u8) -> ProofOptions {
     ProofOptions::new(
         42,
@@ -52,3 +512,32 @@ fn build_options(extension: u8) -> ProofOptions {
         256,
     )
 }
+
+#[allow(clippy::type_complexity)]
+fn build_signed_messages(
+    num_signatures: usize,
+) -> (
+    Vec<[BaseElement; MESSAGE_LENGTH]>,
+    Vec<([BaseElement; POINT_COORDINATE_WIDTH], Scalar)>,
+    Vec<AffinePoint>,
+) {
+    let mut messages = Vec::with_capacity(num_signatures);
+    let mut signatures = Vec::with_capacity(num_signatures);
+    let mut pkeys = Vec::with_capacity(num_signatures);
+    for _ in 0..num_signatures {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        let mut message = [BaseElement::ZERO; MESSAGE_LENGTH];
+        message[0..POINT_COORDINATE_WIDTH].copy_from_slice(&pkey.get_x());
+        message[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&pkey.get_y());
+        for msg in message.iter_mut().skip(AFFINE_POINT_WIDTH) {
+            *msg = BaseElement::random(&mut OsRng);
+        }
+
+        signatures.push(super::sign(message, &skey));
+        messages.push(message);
+        pkeys.push(pkey);
+    }
+
+    (messages, signatures, pkeys)
+}
@@ -0,0 +1,212 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Threshold signatures of committees, by `t` out of `n` keys committed in a key tree.
+//!
+//! The keys of a committee are committed in a `KeyTree`, whose root identifies the committee.
+//! A message is signed by the committee once `t` distinct members signed it, each of them with
+//! its own key in place of the signer's key at the start of the message, and proved its
+//! membership with the authentication path of its key in the tree.
+//!
+//! Key leaves are hashed as account leaves holding the key along with a zero balance and nonce,
+//! and key trees are hashed with Rescue63 as the tree of accounts. Within the transaction AIR
+//! program, the account of a committee holds a pseudo-key made of the root of its key tree and
+//! its threshold, as returned by `AccountState::new_committee`, and a committee transfer from
+//! it is proven by `t` consecutive transactions: the transfer itself, signed by a first member,
+//! followed by `t - 1` approvals, each of them verifying one more signature. Key trees must
+//! then have the depth of the tree of accounts, as built by `KeyTree::with_depth`.
+
+use super::constants::POINT_COORDINATE_WIDTH;
+use super::{verify_signature, AFFINE_POINT_WIDTH, MESSAGE_LENGTH};
+use crate::merkle::{hash_leaf, verify_path, TreeHasher};
+use crate::utils::rescue::{Hash, Rescue63};
+use winterfell::math::{
+    curves::curve_f63::{AffinePoint, Scalar},
+    fields::f63::BaseElement,
+    FieldElement,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// KEY TREE
+// ================================================================================================
+
+/// A Merkle tree committing to the keys of the members of a committee.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyTree {
+    keys: Vec<AffinePoint>,
+    /// The nodes of the tree, level by level from the leaves up to the root
+    levels: Vec<Vec<Hash>>,
+}
+
+impl KeyTree {
+    /// Returns the tree committing to `keys`, the key of rank `i` being stored at the leaf of
+    /// index `i`. Leaves past the last key are left empty, i.e. hold a zero hash.
+    ///
+    /// # Panics
+    /// Panics if `keys` is empty.
+    pub fn new(keys: &[AffinePoint]) -> Self {
+        Self::with_depth(
+            keys,
+            keys.len().next_power_of_two().trailing_zeros() as usize,
+        )
+    }
+
+    /// Returns the tree committing to `keys` as `KeyTree::new`, with `2^depth` leaves, e.g.
+    /// to match the depth of the tree of accounts for committee transfers.
+    ///
+    /// # Panics
+    /// Panics if `keys` is empty or does not fit in `2^depth` leaves.
+    pub fn with_depth(keys: &[AffinePoint], depth: usize) -> Self {
+        assert!(!keys.is_empty(), "a key tree must hold at least one key");
+        assert!(
+            keys.len() <= 1 << depth,
+            "a key tree of depth {} holds at most {} keys",
+            depth,
+            1usize << depth
+        );
+        let mut leaves = vec![Hash::default(); 1 << depth];
+        for (leaf, key) in leaves.iter_mut().zip(keys) {
+            *leaf = hash_key(key);
+        }
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| Rescue63::merge(&[pair[0], pair[1]]))
+                .collect();
+            levels.push(level);
+        }
+
+        KeyTree {
+            keys: keys.to_vec(),
+            levels,
+        }
+    }
+
+    /// Returns the root of this tree, identifying the committee.
+    pub fn root(&self) -> Hash {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Returns the depth of this tree.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Returns the committed keys, in the order of their leaves.
+    pub fn keys(&self) -> &[AffinePoint] {
+        &self.keys
+    }
+
+    /// Returns the authentication path of the key at position `index`, starting with its leaf
+    /// followed by the siblings from the bottom of the tree up to its root, as returned by
+    /// `StateTree::open` for accounts.
+    ///
+    /// # Panics
+    /// Panics if no key is stored at position `index`.
+    pub fn open(&self, index: usize) -> Vec<Hash> {
+        assert!(index < self.keys.len(), "no key at position {}", index);
+        let mut path = Vec::with_capacity(self.levels.len());
+        path.push(self.levels[0][index]);
+        for (depth, level) in self.levels[..self.depth()].iter().enumerate() {
+            path.push(level[(index >> depth) ^ 1]);
+        }
+
+        path
+    }
+
+    /// Returns the share of the member at position `index` in a threshold signature, given its
+    /// signature of `signer_message(message, key)`.
+    ///
+    /// # Panics
+    /// Panics if no key is stored at position `index`.
+    pub fn share(
+        &self,
+        index: usize,
+        signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
+    ) -> SignatureShare {
+        SignatureShare {
+            index,
+            key: self.keys[index],
+            path: self.open(index),
+            signature,
+        }
+    }
+}
+
+// THRESHOLD SIGNATURES
+// ================================================================================================
+
+/// The signature of a member of a committee, along with the proof of its membership.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureShare {
+    /// The position of the member's key in the key tree
+    pub index: usize,
+    /// The member's key
+    pub key: AffinePoint,
+    /// The authentication path of the member's key, as returned by `KeyTree::open`
+    pub path: Vec<Hash>,
+    /// The member's signature of the message, starting with its own key
+    pub signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
+}
+
+/// Returns the message signed by the member holding `key` on behalf of its committee, i.e.
+/// `message` whose first `AFFINE_POINT_WIDTH` elements are replaced with the coordinates of
+/// `key`.
+pub fn signer_message(
+    message: [BaseElement; MESSAGE_LENGTH],
+    key: &AffinePoint,
+) -> [BaseElement; MESSAGE_LENGTH] {
+    let mut signed_message = message;
+    signed_message[..POINT_COORDINATE_WIDTH].copy_from_slice(&key.get_x());
+    signed_message[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&key.get_y());
+
+    signed_message
+}
+
+/// Verifies that `message` is signed by at least `threshold` distinct members of the
+/// committee whose key tree has root `root`.
+///
+/// Shares must be sorted by strictly increasing positions, so that no member is counted
+/// twice, and each of them must authenticate its key along `root` and carry a valid signature
+/// of `signer_message(message, key)`.
+pub fn verify_threshold(
+    message: [BaseElement; MESSAGE_LENGTH],
+    root: Hash,
+    threshold: usize,
+    shares: &[SignatureShare],
+) -> bool {
+    if threshold == 0 || shares.len() < threshold {
+        return false;
+    }
+    if shares.windows(2).any(|pair| pair[0].index >= pair[1].index) {
+        return false;
+    }
+
+    shares.iter().all(|share| {
+        share.key.is_on_curve()
+            && verify_path(hash_key(&share.key), share.index, &share.path, root)
+            && verify_signature(signer_message(message, &share.key), share.signature)
+    })
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the leaf of `key` in a key tree, hashed as the leaf of an account holding `key`
+/// with a zero balance and nonce.
+pub(crate) fn hash_key(key: &AffinePoint) -> Hash {
+    let mut elements = [BaseElement::ZERO; AFFINE_POINT_WIDTH + 2];
+    elements[..POINT_COORDINATE_WIDTH].copy_from_slice(&key.get_x());
+    elements[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH].copy_from_slice(&key.get_y());
+
+    hash_leaf(&elements)
+}
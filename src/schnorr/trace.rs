@@ -7,7 +7,7 @@
 // except according to those terms.
 
 use super::constants::*;
-use super::{ecc, field, rescue};
+use super::{bits, ecc, rescue};
 use bitvec::{order::Lsb0, slice::BitSlice};
 use core::cmp::Ordering;
 use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
@@ -24,12 +24,11 @@ pub(crate) fn init_sig_verification_state(
 ) {
     // initialize first state of the computation
     state[0..TRACE_WIDTH].copy_from_slice(&[BaseElement::ZERO; TRACE_WIDTH]);
-    state[POINT_COORDINATE_WIDTH] = BaseElement::ONE; // y(S)
+    state[POINT_COORDINATE_WIDTH] = BaseElement::ONE; // y(s.G + h.P)
 
-    state[PROJECTIVE_POINT_WIDTH + POINT_COORDINATE_WIDTH + 1] = BaseElement::ONE; // y(h.P)
-
-    state[PROJECTIVE_POINT_WIDTH * 2 + 6..PROJECTIVE_POINT_WIDTH * 2 + POINT_COORDINATE_WIDTH + 6]
+    state[HASH_STATE_POS..HASH_STATE_POS + POINT_COORDINATE_WIDTH]
         .copy_from_slice(&signature.0[..]); // x(R)
+    state[HASH_STATE_POS + STATE_WIDTH - 1] = CHALLENGE_DOMAIN;
 }
 
 // TRANSITION FUNCTION
@@ -37,7 +36,7 @@ pub(crate) fn init_sig_verification_state(
 
 pub(crate) fn update_sig_verification_state(
     step: usize,
-    message: [BaseElement; AFFINE_POINT_WIDTH * 2 + 4],
+    message: [BaseElement; MESSAGE_LENGTH],
     pkey_point: [BaseElement; AFFINE_POINT_WIDTH],
     s_bits: &BitSlice<Lsb0, u8>,
     h_bits: &BitSlice<Lsb0, u8>,
@@ -47,29 +46,23 @@ pub(crate) fn update_sig_verification_state(
     let rescue_flag = step < TOTAL_HASH_LENGTH;
     let rescue_step = step % HASH_CYCLE_LENGTH;
 
-    // enforcing the three kind of rescue operations
+    // enforcing the rescue rounds and the absorption of the message chunks. Once the message
+    // is hashed, the hash state is held as is until the end of the scalar multiplication,
+    // which the AIR program enforces with its own selector rather than relying on the
+    // registers being copied from the previous step
     if rescue_flag && (rescue_step < NUM_HASH_ROUNDS) {
         // for the first NUM_HASH_ROUNDS steps in every cycle, compute a single round of Rescue hash
-        rescue::apply_round(&mut state[PROJECTIVE_POINT_WIDTH * 2 + 6..], step);
+        rescue::apply_round(&mut state[HASH_STATE_POS..], step);
     } else if rescue_flag && (step < (NUM_HASH_ITER - 1) * HASH_CYCLE_LENGTH) {
         // for the next step, insert message chunks in the state registers
         let index = step / HASH_CYCLE_LENGTH;
         for i in 0..rescue::RATE_WIDTH {
-            state[PROJECTIVE_POINT_WIDTH * 2 + rescue::RATE_WIDTH + 6 + i] =
+            state[HASH_STATE_POS + rescue::RATE_WIDTH + i] =
                 message[rescue::RATE_WIDTH * index + i];
         }
-    } else if rescue_flag {
-        // Register cells are by default copied from the previous state if no operation
-        // is specified. This would conflict for here, as the "periodic" values for the
-        // enforce_hash_copy() internal inputs are set to 0 at almost every step.
-        // Hence we manually set them to zero for the final hash iteration, and this will
-        // carry over until the end of the trace
-        for i in 0..rescue::RATE_WIDTH {
-            state[PROJECTIVE_POINT_WIDTH * 2 + rescue::RATE_WIDTH + 6 + i] = BaseElement::ZERO;
-        }
     }
 
-    // enforcing scalar multiplications
+    // enforcing the joint scalar multiplication
     match step.cmp(&SCALAR_MUL_LENGTH) {
         Ordering::Less => {
             let real_step = step / 2;
@@ -79,46 +72,25 @@ pub(crate) fn update_sig_verification_state(
             } else {
                 (real_step - 63) / 64 + 1
             };
-            state[PROJECTIVE_POINT_WIDTH] =
-                BaseElement::from(s_bits[bit_length - 1 - real_step] as u8);
-            state[2 * PROJECTIVE_POINT_WIDTH + 1] =
-                BaseElement::from(h_bits[bit_length - 1 - real_step] as u8);
 
+            // The accumulator is doubled, while the bits of s and h are inserted along with
+            // the point they select, to be added at the next step
             if is_doubling_step {
-                ecc::apply_point_doubling(&mut state[0..PROJECTIVE_POINT_WIDTH + 1]);
-                ecc::apply_point_doubling(
-                    &mut state[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
-                );
-                field::apply_double_and_add_step(
-                    &mut state[2 * PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 6],
-                    4 - chunk,
-                    0,
-                );
-            } else {
-                ecc::apply_point_addition_mixed(
-                    &mut state[0..PROJECTIVE_POINT_WIDTH + 1],
-                    &GENERATOR,
-                );
-                ecc::apply_point_addition_mixed(
-                    &mut state[PROJECTIVE_POINT_WIDTH + 1..2 * PROJECTIVE_POINT_WIDTH + 2],
+                ecc::apply_point_doubling(&mut state[..PROJECTIVE_POINT_WIDTH]);
+                ecc::apply_joint_selection(
+                    &mut state[..JOINT_STEP_WIDTH],
+                    bits::msb_first(s_bits, bit_length, real_step),
+                    bits::msb_first(h_bits, bit_length, real_step),
                     &pkey_point,
                 );
+                bits::apply_double_and_add_step(state, H_LIMBS_POS + 3 - chunk, H_BIT_POS);
+            } else {
+                ecc::apply_joint_addition(&mut state[..JOINT_STEP_WIDTH]);
             }
         }
         Ordering::Equal => {
-            let mut hp_point = [BaseElement::ZERO; PROJECTIVE_POINT_WIDTH];
-            hp_point.copy_from_slice(
-                &state[PROJECTIVE_POINT_WIDTH + 1..PROJECTIVE_POINT_WIDTH * 2 + 1],
-            );
-            state[PROJECTIVE_POINT_WIDTH] = BaseElement::ONE;
-            ecc::apply_point_addition(&mut state[..PROJECTIVE_POINT_WIDTH + 1], &hp_point);
             // Affine coordinates, hence do X/Z
-            let mut x = [BaseElement::ZERO; POINT_COORDINATE_WIDTH];
-            x.copy_from_slice(&state[0..POINT_COORDINATE_WIDTH]);
-            let mut z = [BaseElement::ZERO; POINT_COORDINATE_WIDTH];
-            z.copy_from_slice(&state[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH]);
-            state[0..POINT_COORDINATE_WIDTH]
-                .copy_from_slice(&ecc::mul_fp6(&x, &ecc::invert_fp6(&z)));
+            ecc::apply_x_reduction(&mut state[..PROJECTIVE_POINT_WIDTH]);
         }
         _ => {}
     }
@@ -127,8 +99,12 @@ pub(crate) fn update_sig_verification_state(
 // HELPER FUNCTIONS
 // ================================================================================================
 
+/// Returns the public key point of the signer of `message`, and the bytes of the scalar of
+/// the signature and of its challenge. The challenge spans the first four 63-bit limbs of
+/// the message hash, all of which are decomposed into bits within the AIR programs and
+/// matched against the hash output.
 pub(crate) fn build_sig_info(
-    message: &[BaseElement; AFFINE_POINT_WIDTH * 2 + 4],
+    message: &[BaseElement; MESSAGE_LENGTH],
     signature: &([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
 ) -> ([BaseElement; AFFINE_POINT_WIDTH], [u8; 32], [u8; 32]) {
     let mut pkey_point = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
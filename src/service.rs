@@ -0,0 +1,348 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A prover service, proving batches of transactions submitted from asynchronous code.
+//!
+//! Proof generation is CPU-bound and takes seconds to minutes, so that it must not run on the
+//! threads of an async executor. A [`ProverService`] instead pushes every batch onto a work
+//! queue, drained by a fixed pool of dedicated worker threads, and hands back a future
+//! resolving once the proof is generated. The futures do not depend on any particular
+//! executor, and progress events can be streamed to any number of subscribers, e.g. to
+//! report the status of the queue to the clients of a JSON-RPC server.
+//!
+//! ```no_run
+//! # async fn example(batch: certificate_stark::TransactionMetadata) {
+//! use certificate_stark::{service::ProverService, ProofProfile, TransactionProver};
+//!
+//! let prover = TransactionProver::new(ProofProfile::Balanced.to_proof_options());
+//! let service = ProverService::new(prover, 2);
+//! let events = service.subscribe();
+//!
+//! let (proof, report) = service.prove_batch(batch).await.unwrap();
+//! for event in events.try_iter() {
+//!     println!("{:?}", event);
+//! }
+//! # }
+//! ```
+
+use super::{CancellationToken, Error, ProofReport, TransactionMetadata, TransactionProver};
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+use winterfell::StarkProof;
+
+/// Result of a proving job, holding the payload of the panic of its worker if any
+type JobResult = thread::Result<Result<(StarkProof, ProofReport), Error>>;
+
+// PROGRESS EVENTS
+// ================================================================================================
+
+/// A progress event of a proving job of a [`ProverService`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// The job was pushed onto the work queue.
+    Queued {
+        /// Identifier of the job
+        job_id: u64,
+        /// Number of transactions of the batch
+        num_transactions: usize,
+    },
+    /// A worker started proving the batch of the job.
+    Started {
+        /// Identifier of the job
+        job_id: u64,
+    },
+    /// The proof of the job was generated.
+    Proved {
+        /// Identifier of the job
+        job_id: u64,
+        /// Report on the generation of the proof
+        report: ProofReport,
+    },
+    /// The job failed, the batch being invalid or the proof generation having failed, been
+    /// cancelled or panicked. The error is returned by the future of the job.
+    Failed {
+        /// Identifier of the job
+        job_id: u64,
+    },
+}
+
+impl ProgressEvent {
+    /// Returns the identifier of the job this event relates to.
+    pub fn job_id(&self) -> u64 {
+        match self {
+            Self::Queued { job_id, .. }
+            | Self::Started { job_id }
+            | Self::Proved { job_id, .. }
+            | Self::Failed { job_id } => *job_id,
+        }
+    }
+}
+
+// PROVER SERVICE
+// ================================================================================================
+
+/// A service proving batches of transactions on a pool of worker threads, in the order in
+/// which they were submitted.
+///
+/// Dropping the service waits for the jobs still in the queue to complete.
+pub struct ProverService {
+    queue: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    subscribers: Arc<Mutex<Vec<Sender<ProgressEvent>>>>,
+    next_job_id: AtomicU64,
+}
+
+impl ProverService {
+    /// Returns a new service proving batches with `prover` on `num_workers` worker threads.
+    ///
+    /// Each worker proves one batch at a time, over the threads set by the `ProverConfig`
+    /// of `prover` when the `concurrent` feature is enabled.
+    ///
+    /// # Panics
+    /// Panics if `num_workers` is zero.
+    pub fn new(prover: TransactionProver, num_workers: usize) -> Self {
+        assert!(num_workers > 0, "number of workers must be positive");
+        let (queue, jobs) = mpsc::channel::<Job>();
+        let jobs = Arc::new(Mutex::new(jobs));
+        let prover = Arc::new(prover);
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+
+        let workers = (0..num_workers)
+            .map(|i| {
+                let jobs = Arc::clone(&jobs);
+                let prover = Arc::clone(&prover);
+                let subscribers = Arc::clone(&subscribers);
+                thread::Builder::new()
+                    .name(format!("prover-worker-{}", i))
+                    .spawn(move || run_worker(&prover, &jobs, &subscribers))
+                    .expect("failed to spawn prover worker")
+            })
+            .collect();
+
+        Self {
+            queue: Some(queue),
+            workers,
+            subscribers,
+            next_job_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns a receiver of the progress events of all the jobs submitted from now on.
+    pub fn subscribe(&self) -> Receiver<ProgressEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .expect("subscribers lock poisoned")
+            .push(sender);
+        receiver
+    }
+
+    /// Pushes the provided batch onto the work queue, and returns a future resolving to its
+    /// proof and a report on its generation.
+    ///
+    /// The batch is proven whether or not the future is polled, unless the job is cancelled
+    /// through `ProofFuture::cancel`. If the proof generation panics, the panic is resumed
+    /// when polling the future.
+    pub fn submit(&self, tx_metadata: TransactionMetadata) -> ProofFuture {
+        let job_id = self.next_job_id.fetch_add(1, Ordering::Relaxed);
+        let slot = Arc::new(Mutex::new(Slot::default()));
+        let token = CancellationToken::new();
+        broadcast(
+            &self.subscribers,
+            ProgressEvent::Queued {
+                job_id,
+                num_transactions: tx_metadata.num_transactions(),
+            },
+        );
+        self.queue
+            .as_ref()
+            .expect("queue is open while the service is alive")
+            .send(Job {
+                job_id,
+                tx_metadata,
+                token: token.clone(),
+                slot: Arc::clone(&slot),
+            })
+            .expect("prover workers stopped");
+
+        ProofFuture {
+            job_id,
+            token,
+            slot,
+        }
+    }
+
+    /// Proves the state-transition of the provided transactions on a worker thread.
+    ///
+    /// Returns an error if the transactions are invalid, or if the proof generation fails.
+    pub async fn prove_batch(
+        &self,
+        tx_metadata: TransactionMetadata,
+    ) -> Result<(StarkProof, ProofReport), Error> {
+        self.submit(tx_metadata).await
+    }
+
+    /// Returns the number of worker threads of this service.
+    pub fn num_workers(&self) -> usize {
+        self.workers.len()
+    }
+}
+
+impl fmt::Debug for ProverService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProverService")
+            .field("num_workers", &self.workers.len())
+            .field("next_job_id", &self.next_job_id)
+            .finish()
+    }
+}
+
+impl Drop for ProverService {
+    fn drop(&mut self) {
+        // closing the queue stops the workers once it is drained
+        self.queue.take();
+        for worker in self.workers.drain(..) {
+            // panics of the proof generation are caught, and returned to the futures
+            let _ = worker.join();
+        }
+    }
+}
+
+// PROOF FUTURE
+// ================================================================================================
+
+/// A future resolving to the proof of a batch submitted to a [`ProverService`], along with
+/// a report on its generation.
+#[derive(Debug)]
+pub struct ProofFuture {
+    job_id: u64,
+    token: CancellationToken,
+    slot: Arc<Mutex<Slot>>,
+}
+
+impl ProofFuture {
+    /// Returns the identifier of the job, as carried by its progress events.
+    pub fn job_id(&self) -> u64 {
+        self.job_id
+    }
+
+    /// Aborts the job, its future then resolving to `Error::Cancelled`.
+    ///
+    /// A queued job is skipped by the workers, while an in-flight one stops at the next
+    /// transaction of its trace, or before its proof generation. Once winterfell has started
+    /// generating the proof, it completes regardless.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+impl Future for ProofFuture {
+    type Output = Result<(StarkProof, ProofReport), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().expect("job lock poisoned");
+        match slot.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// HELPER TYPES AND FUNCTIONS
+// ================================================================================================
+
+/// A batch waiting in the work queue, with the slot receiving its result
+struct Job {
+    job_id: u64,
+    tx_metadata: TransactionMetadata,
+    token: CancellationToken,
+    slot: Arc<Mutex<Slot>>,
+}
+
+/// The result of a job, shared between its worker and its future
+#[derive(Default)]
+struct Slot {
+    result: Option<JobResult>,
+    waker: Option<Waker>,
+}
+
+impl fmt::Debug for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slot")
+            .field("done", &self.result.is_some())
+            .finish()
+    }
+}
+
+/// Proves the batches of the work queue until it is closed.
+fn run_worker(
+    prover: &TransactionProver,
+    jobs: &Mutex<Receiver<Job>>,
+    subscribers: &Mutex<Vec<Sender<ProgressEvent>>>,
+) {
+    loop {
+        // the lock is released as soon as a job is received, so that other workers can pick
+        // the next one while this one is being proven
+        let job = match jobs.lock().expect("queue lock poisoned").recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let Job {
+            job_id,
+            tx_metadata,
+            token,
+            slot,
+        } = job;
+        let result: JobResult = if token.is_cancelled() {
+            Ok(Err(Error::Cancelled))
+        } else {
+            broadcast(subscribers, ProgressEvent::Started { job_id });
+            let prover = prover.clone().with_cancellation_token(token);
+            panic::catch_unwind(AssertUnwindSafe(|| prover.prove_with_report(&tx_metadata)))
+        };
+        let event = match &result {
+            Ok(Ok((_, report))) => ProgressEvent::Proved {
+                job_id,
+                report: report.clone(),
+            },
+            _ => ProgressEvent::Failed { job_id },
+        };
+        broadcast(subscribers, event);
+
+        let mut slot = slot.lock().expect("job lock poisoned");
+        slot.result = Some(result);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Sends the provided event to all subscribers, forgetting the ones which went away.
+fn broadcast(subscribers: &Mutex<Vec<Sender<ProgressEvent>>>, event: ProgressEvent) {
+    subscribers
+        .lock()
+        .expect("subscribers lock poisoned")
+        .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+}
@@ -0,0 +1,299 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generators of deliberately invalid transactions, to check that integrations reject them.
+//!
+//! Each [`Tampering`] alters a single transaction of an otherwise valid batch, in a way that
+//! a dishonest batch producer could attempt. The prover refuses to prove such batches, with
+//! the error returned by [`Tampering::expected_error`], so that downstream services can assert
+//! that they surface these errors instead of publishing a proof.
+//!
+//! ```no_run
+//! use certificate_stark::testing::{build_invalid, Tampering};
+//! use certificate_stark::{Error, ProofProfile, TransactionProver};
+//!
+//! let prover = TransactionProver::new(ProofProfile::Fast.to_proof_options());
+//! for &tampering in Tampering::ALL.iter() {
+//!     let tx_metadata = build_invalid(tampering, 4, 3);
+//!     assert_eq!(
+//!         prover.prove(&tx_metadata).unwrap_err(),
+//!         Error::InvalidMetadata(tampering.expected_error(Tampering::TX_INDEX)),
+//!     );
+//! }
+//! ```
+//!
+//! Execution traces can likewise be altered with a [`TraceMutation`], each one targeting a
+//! family of transition constraints, as a malicious prover bypassing the trace builder would.
+
+use super::constants::merkle_const::{
+    SENDER_BIT_POS, SENDER_INITIAL_POS, SENDER_UPDATED_POS,
+    TRANSACTION_CYCLE_LENGTH as MERKLE_UPDATE_LENGTH,
+};
+use super::constants::schnorr_const::{AFFINE_POINT_WIDTH, S_BIT_POS};
+use super::constants::*;
+use super::{Error, MetadataError, TransactionMetadata, TransactionProver};
+use winterfell::{
+    math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement},
+    Trace, TraceTable,
+};
+
+// TAMPERING
+// ================================================================================================
+
+/// A way of tampering with the witness of a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tampering {
+    /// The signature of the transaction no longer matches its message.
+    WrongSignature,
+    /// The fee collector's account and Merkle path are the ones of the previous transaction,
+    /// i.e. before the previous fee was credited, as if replayed from an outdated tree.
+    StalePath,
+    /// The amount sent exceeds the sender's balance once the fee is deducted.
+    BalanceUnderflow,
+    /// The amount sent is increased by one after the tree was updated and the transaction
+    /// signed, so that the updated leaves no longer match it.
+    TamperedDelta,
+}
+
+impl Tampering {
+    /// All the ways of tampering with a transaction.
+    pub const ALL: [Tampering; 4] = [
+        Tampering::WrongSignature,
+        Tampering::StalePath,
+        Tampering::BalanceUnderflow,
+        Tampering::TamperedDelta,
+    ];
+
+    /// Position of the transaction tampered with by `build_invalid`.
+    pub const TX_INDEX: usize = 1;
+
+    /// Returns a copy of the provided transactions, with the one at `tx_index` tampered with.
+    ///
+    /// The error raised by the tampering is only guaranteed for transfers of random batches,
+    /// e.g. built with `TransactionMetadata::build_random`, whose senders pay less than their
+    /// balance.
+    ///
+    /// # Panics
+    /// Panics if `tx_index` is out of bounds, or if it is zero for a `StalePath`, which requires
+    /// a previous transaction.
+    pub fn apply(self, tx_metadata: &TransactionMetadata, tx_index: usize) -> TransactionMetadata {
+        assert!(
+            tx_index < tx_metadata.num_transactions(),
+            "transaction index out of bounds"
+        );
+        let mut tx_metadata = tx_metadata.clone();
+        match self {
+            Tampering::WrongSignature => {
+                tx_metadata.signatures[tx_index].1 += Scalar::one();
+            }
+            Tampering::StalePath => {
+                assert!(tx_index > 0, "a stale path requires a previous transaction");
+                tx_metadata.c_old_values[tx_index] = tx_metadata.c_old_values[tx_index - 1];
+                tx_metadata.c_paths[tx_index] = tx_metadata.c_paths[tx_index - 1].clone();
+            }
+            Tampering::BalanceUnderflow => {
+                tx_metadata.deltas[tx_index] = tx_metadata.s_old_values[tx_index].balance
+                    - tx_metadata.fees[tx_index]
+                    + BaseElement::ONE;
+            }
+            Tampering::TamperedDelta => {
+                tx_metadata.deltas[tx_index] += BaseElement::ONE;
+            }
+        }
+
+        tx_metadata
+    }
+
+    /// Returns the error raised when validating transactions whose one at `tx_index` was
+    /// tampered with.
+    pub fn expected_error(self, tx_index: usize) -> MetadataError {
+        match self {
+            Tampering::WrongSignature => MetadataError::InvalidSignature(tx_index),
+            Tampering::StalePath => MetadataError::InvalidCollectorPath(tx_index),
+            Tampering::BalanceUnderflow => MetadataError::InsufficientBalance(tx_index),
+            // the sender's updated leaf no longer leads to the root the receiver's path
+            // authenticates against
+            Tampering::TamperedDelta => MetadataError::InvalidReceiverPath(tx_index),
+        }
+    }
+}
+
+// GENERATORS
+// ================================================================================================
+
+/// Builds `num_transactions` random transfers in a Merkle tree of depth `tree_depth`, the one
+/// at `Tampering::TX_INDEX` being tampered with.
+///
+/// # Panics
+/// Panics if `num_transactions` is smaller than 2.
+pub fn build_invalid(
+    tampering: Tampering,
+    num_transactions: usize,
+    tree_depth: usize,
+) -> TransactionMetadata {
+    assert!(
+        num_transactions > Tampering::TX_INDEX,
+        "invalid batches hold at least {} transactions",
+        Tampering::TX_INDEX + 1
+    );
+    tampering.apply(
+        &TransactionMetadata::build_random_with_tree_depth(num_transactions, tree_depth),
+        Tampering::TX_INDEX,
+    )
+}
+
+// TRACE MUTATIONS
+// ================================================================================================
+
+/// A way of altering the execution trace of a transaction, each one violating a different
+/// family of transition constraints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceMutation {
+    /// A register of the hash state of the sender's initial leaf is altered in the middle of a
+    /// Rescue round.
+    LeafHashState,
+    /// The sender's index bit inserting the first node of its Merkle path is not binary.
+    PathBitNonBinary,
+    /// The sender's updated balance is not debited the amount and fee.
+    BalanceNotDebited,
+    /// The sender's updated nonce is not incremented.
+    NonceNotIncremented,
+    /// The copy of the receiver's key, signed by the sender, is substituted along the whole
+    /// transaction.
+    ReceiverKeySubstituted,
+    /// The copy of delta changes in the middle of the transaction.
+    DeltaNotCopied,
+    /// A bit of the range proof of delta is not binary.
+    RangeBitNonBinary,
+    /// A bit of the scalar `s` of the signature is not binary.
+    ScalarBitNonBinary,
+    /// The no-op flag changes in the middle of the transaction.
+    NoOpFlagNotCopied,
+    /// The running digest of transactions changes in the middle of the transaction.
+    DigestNotCarried,
+    /// The commitment to nullifiers changes in the middle of the transaction.
+    NullifiersNotCarried,
+    /// The volume of transactions changes in the middle of the transaction.
+    VolumeNotCarried,
+    /// The sender's index, signed by the sender, changes in the middle of the transaction.
+    SenderIndexNotCarried,
+    /// The copy of the sender's key, verifying the signature, is substituted during the scalar
+    /// multiplication, once the signed message is hashed.
+    SigningKeySubstituted,
+}
+
+impl TraceMutation {
+    /// All the ways of altering the execution trace of a transaction.
+    pub const ALL: [TraceMutation; 14] = [
+        TraceMutation::LeafHashState,
+        TraceMutation::PathBitNonBinary,
+        TraceMutation::BalanceNotDebited,
+        TraceMutation::NonceNotIncremented,
+        TraceMutation::ReceiverKeySubstituted,
+        TraceMutation::DeltaNotCopied,
+        TraceMutation::RangeBitNonBinary,
+        TraceMutation::ScalarBitNonBinary,
+        TraceMutation::NoOpFlagNotCopied,
+        TraceMutation::DigestNotCarried,
+        TraceMutation::NullifiersNotCarried,
+        TraceMutation::VolumeNotCarried,
+        TraceMutation::SenderIndexNotCarried,
+        TraceMutation::SigningKeySubstituted,
+    ];
+
+    /// Returns the first step of the trace altered by this mutation of the transaction at
+    /// `tx_index`. The trace is rejected at this step or at the previous one.
+    pub fn step(self, tx_index: usize) -> usize {
+        let offset = match self {
+            TraceMutation::BalanceNotDebited | TraceMutation::NonceNotIncremented => 0,
+            TraceMutation::ReceiverKeySubstituted => 1,
+            TraceMutation::LeafHashState | TraceMutation::PathBitNonBinary => 3,
+            TraceMutation::DeltaNotCopied
+            | TraceMutation::NoOpFlagNotCopied
+            | TraceMutation::DigestNotCarried
+            | TraceMutation::NullifiersNotCarried
+            | TraceMutation::VolumeNotCarried
+            | TraceMutation::SenderIndexNotCarried => 100,
+            TraceMutation::RangeBitNonBinary => MERKLE_UPDATE_LENGTH + 10,
+            TraceMutation::ScalarBitNonBinary => MERKLE_UPDATE_LENGTH + 101,
+            TraceMutation::SigningKeySubstituted => MERKLE_UPDATE_LENGTH + 300,
+        };
+
+        tx_index * TRANSACTION_CYCLE_LENGTH + offset
+    }
+
+    /// Alters the provided execution trace at the transaction at `tx_index`.
+    ///
+    /// # Panics
+    /// Panics if the trace does not hold the transaction at `tx_index`.
+    pub fn apply(self, trace: &mut TraceTable<BaseElement>, tx_index: usize) {
+        assert!(
+            (tx_index + 1) * TRANSACTION_CYCLE_LENGTH <= trace.length(),
+            "transaction index out of the trace"
+        );
+        let step = self.step(tx_index);
+        let mut add_one = |register: usize, step: usize| {
+            let value = trace.get(register, step);
+            trace.set(register, step, value + BaseElement::ONE);
+        };
+
+        match self {
+            TraceMutation::LeafHashState => add_one(SENDER_INITIAL_POS, step),
+            TraceMutation::BalanceNotDebited => {
+                add_one(SENDER_UPDATED_POS + AFFINE_POINT_WIDTH, step)
+            }
+            TraceMutation::NonceNotIncremented => {
+                let nonce = trace.get(SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 1, step);
+                trace.set(
+                    SENDER_UPDATED_POS + AFFINE_POINT_WIDTH + 1,
+                    step,
+                    nonce - BaseElement::ONE,
+                );
+            }
+            TraceMutation::ReceiverKeySubstituted => {
+                for step in step..(tx_index + 1) * TRANSACTION_CYCLE_LENGTH {
+                    add_one(RECEIVER_KEY_POINT_POS, step);
+                }
+            }
+            TraceMutation::DeltaNotCopied => add_one(DELTA_COPY_POS, step),
+            TraceMutation::NoOpFlagNotCopied => add_one(NOOP_FLAG_POS, step),
+            TraceMutation::DigestNotCarried => add_one(TX_DIGEST_POS, step),
+            TraceMutation::NullifiersNotCarried => add_one(NULLIFIERS_POS, step),
+            TraceMutation::VolumeNotCarried => add_one(TX_VOLUME_POS, step),
+            TraceMutation::SenderIndexNotCarried => add_one(SENDER_INDEX_POS, step),
+            TraceMutation::SigningKeySubstituted => {
+                for step in step..(tx_index + 1) * TRANSACTION_CYCLE_LENGTH {
+                    add_one(SENDER_KEY_POINT_POS, step);
+                }
+            }
+            TraceMutation::PathBitNonBinary => {
+                trace.set(SENDER_BIT_POS, step, BaseElement::from(2u8))
+            }
+            TraceMutation::RangeBitNonBinary => {
+                trace.set(DELTA_BIT_POS, step, BaseElement::from(2u8))
+            }
+            TraceMutation::ScalarBitNonBinary => trace.set(S_BIT_POS, step, BaseElement::from(2u8)),
+        }
+    }
+}
+
+/// Builds the execution trace of the provided transactions, alters it with `mutation` at the
+/// transaction at `tx_index`, and checks its transition constraints as the prover does before
+/// proving with `TransactionProver::with_constraint_checks`.
+///
+/// Returns `Error::UnsatisfiedConstraint` for any mutation of a valid batch.
+pub fn check_mutated_trace(
+    prover: &TransactionProver,
+    tx_metadata: &TransactionMetadata,
+    mutation: TraceMutation,
+    tx_index: usize,
+) -> Result<(), Error> {
+    let mut trace = prover.build_trace(tx_metadata);
+    mutation.apply(&mut trace, tx_index);
+    prover.check_constraints(tx_metadata, &trace)
+}
@@ -11,38 +11,2400 @@ use winterfell::{FieldExtension, HashFunction, ProofOptions};
 #[test]
 fn transaction_test_basic_proof_verification() {
     let transaction = Box::new(super::TransactionExample::new(build_options(1), 2));
-    let proof = transaction.prove();
+    let proof = transaction.prove().unwrap();
     assert!(transaction.verify(proof).is_ok());
 }
 
 #[test]
 fn transaction_test_basic_proof_verification_quadratic_extension() {
     let transaction = Box::new(super::TransactionExample::new(build_options(2), 2));
-    let proof = transaction.prove();
+    let proof = transaction.prove().unwrap();
     assert!(transaction.verify(proof).is_ok());
 }
 
 #[test]
 fn transaction_test_basic_proof_verification_cubic_extension() {
     let transaction = Box::new(super::TransactionExample::new(build_options(3), 2));
-    let proof = transaction.prove();
+    let proof = transaction.prove().unwrap();
     assert!(transaction.verify(proof).is_ok());
 }
 
+#[test]
+fn transaction_test_field_extensions() {
+    use super::{ProofOptionsBuilder, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+
+    // All optional public inputs are exposed, so that every constraint is evaluated
+    // over the extension field
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    for &field_extension in [FieldExtension::Quadratic, FieldExtension::Cubic].iter() {
+        let options = ProofOptionsBuilder::fast()
+            .field_extension(field_extension)
+            .build();
+        let prover = TransactionProver::new(options)
+            .with_intermediate_roots()
+            .with_transactions_digest()
+            .with_nullifiers()
+            .with_batch_statistics();
+        let proof = prover.prove(&tx_metadata).unwrap();
+        assert_eq!(proof.options().field_extension(), field_extension);
+
+        let pub_inputs = prover.public_inputs(&tx_metadata);
+        assert!(super::verify_with_hasher::<Rescue63>(proof, pub_inputs).is_ok());
+    }
+}
+
+#[test]
+fn transaction_test_hash_functions() {
+    use super::{ProofOptionsBuilder, TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    for &hash_fn in [HashFunction::Blake3_256, HashFunction::Sha3_256].iter() {
+        let prover = TransactionProver::new(ProofOptionsBuilder::fast().hash_fn(hash_fn).build());
+        let proof = prover.prove(&tx_metadata).unwrap();
+        assert_eq!(proof.options().hash_fn(), hash_fn);
+
+        let pub_inputs = prover.public_inputs(&tx_metadata);
+        assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+    }
+}
+
 #[test]
 fn transaction_test_basic_proof_verification_fail() {
     let transaction = Box::new(super::TransactionExample::new(build_options(1), 2));
-    let proof = transaction.prove();
+    let proof = transaction.prove().unwrap();
     let verified = transaction.verify_with_wrong_inputs(proof);
     assert!(verified.is_err());
 }
 
+#[test]
+fn transaction_test_prover() {
+    use super::{TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+}
+
+#[test]
+fn transaction_test_proof_report() {
+    use super::{constants::TRACE_WIDTH, TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let (proof, report) = prover.prove_with_report(&tx_metadata).unwrap();
+    assert_eq!(report.num_transactions, 2);
+    assert_eq!(report.trace_width, TRACE_WIDTH);
+    assert_eq!(report.trace_length, proof.trace_length());
+    assert_eq!(report.proof_size, proof.to_bytes().len());
+    assert_eq!(report.security_level, proof.security_level(true));
+
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+}
+
+#[test]
+fn transaction_test_min_security() {
+    use super::{Error, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    let security_level = proof.security_level(true);
+    assert!(
+        super::verify_with_min_security(proof.clone(), pub_inputs.clone(), security_level).is_ok()
+    );
+    assert!(super::verify_with_hasher_and_min_security::<Rescue63>(
+        proof.clone(),
+        pub_inputs.clone(),
+        security_level
+    )
+    .is_ok());
+
+    // Proofs below the required security level are rejected, even if valid
+    assert_eq!(
+        super::verify_with_min_security(proof, pub_inputs, security_level + 1),
+        Err(Error::InsufficientSecurity {
+            security_level,
+            min_security: security_level + 1,
+        })
+    );
+}
+
+#[test]
+fn transaction_test_prover_config() {
+    use super::{ProverConfig, TransactionMetadata, TransactionProver};
+
+    let config = ProverConfig::new();
+    assert_eq!(config.is_concurrent(), cfg!(feature = "concurrent"));
+    let config = config.with_num_threads(2);
+    if config.is_concurrent() {
+        assert_eq!(config.num_threads(), 2);
+    } else {
+        assert_eq!(config.num_threads(), 1);
+    }
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1)).with_config(config.clone());
+    assert_eq!(prover.config(), &config);
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+}
+
+#[test]
+fn transaction_test_prover_observer() {
+    use super::{constants::TRACE_WIDTH, ProverObserver, TransactionMetadata, TransactionProver};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+    use winterfell::Trace;
+
+    #[derive(Debug, Default)]
+    struct Progress {
+        num_filled: AtomicUsize,
+        milestones: Mutex<Vec<String>>,
+    }
+
+    impl ProverObserver for Progress {
+        fn on_trace_started(&self, num_transactions: usize, trace_length: usize) {
+            let milestone = format!("started {} {}", num_transactions, trace_length);
+            self.milestones.lock().unwrap().push(milestone);
+        }
+
+        fn on_transaction_filled(&self, _tx_index: usize) {
+            self.num_filled.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_trace_built(&self, trace_width: usize, trace_length: usize) {
+            let milestone = format!("built {} {}", trace_width, trace_length);
+            self.milestones.lock().unwrap().push(milestone);
+        }
+
+        fn on_proving_started(&self) {
+            self.milestones.lock().unwrap().push("proving".into());
+        }
+
+        fn on_proof_generated(&self, proof_size: usize) {
+            let milestone = format!("proved {}", proof_size);
+            self.milestones.lock().unwrap().push(milestone);
+        }
+    }
+
+    // three transactions are padded to four
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let progress = Arc::new(Progress::default());
+    let prover = TransactionProver::new(build_options(1)).with_observer(progress.clone());
+
+    let trace = prover.build_trace(&tx_metadata);
+    let trace_length = trace.length();
+    assert_eq!(progress.num_filled.load(Ordering::Relaxed), 4);
+    assert_eq!(
+        *progress.milestones.lock().unwrap(),
+        [
+            format!("started 4 {}", trace_length),
+            format!("built {} {}", TRACE_WIDTH, trace_length),
+        ]
+    );
+
+    progress.milestones.lock().unwrap().clear();
+    let proof = prover.prove(&tx_metadata).unwrap();
+    assert_eq!(
+        progress.milestones.lock().unwrap()[2..],
+        [
+            "proving".to_string(),
+            format!("proved {}", proof.to_bytes().len()),
+        ]
+    );
+}
+
+#[test]
+fn transaction_test_cancellation() {
+    use super::{CancellationToken, Error, TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let token = CancellationToken::new();
+    let prover = TransactionProver::new(build_options(1)).with_cancellation_token(token.clone());
+    assert!(!token.is_cancelled());
+    assert!(prover.try_build_trace(&tx_metadata).is_ok());
+
+    // cancelling any clone of the token aborts the proof generation
+    token.clone().cancel();
+    assert!(token.is_cancelled());
+    assert_eq!(
+        prover.try_build_trace(&tx_metadata).unwrap_err(),
+        Error::Cancelled
+    );
+    assert_eq!(prover.prove(&tx_metadata).unwrap_err(), Error::Cancelled);
+    assert!(matches!(
+        prover.prove_with_report(&tx_metadata),
+        Err(Error::Cancelled)
+    ));
+}
+
+#[test]
+fn transaction_test_memory_cap() {
+    use super::{
+        constants::{TRACE_WIDTH, TRANSACTION_CYCLE_LENGTH},
+        Error, ProverConfig, TransactionMetadata, TransactionProver,
+    };
+
+    let prover = TransactionProver::new(build_options(1));
+    let tx_size = TRACE_WIDTH * TRANSACTION_CYCLE_LENGTH * 8;
+    assert_eq!(prover.estimate_trace_memory(1), tx_size);
+    // padding transactions are accounted for
+    assert_eq!(prover.estimate_trace_memory(3), 4 * tx_size);
+    assert_eq!(prover.estimate_trace_memory(4), 4 * tx_size);
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let config = ProverConfig::new().with_max_trace_memory(4 * tx_size - 1);
+    assert_eq!(config.max_trace_memory(), Some(4 * tx_size - 1));
+    let prover = prover.with_config(config);
+    assert_eq!(
+        prover.try_build_trace(&tx_metadata).unwrap_err(),
+        Error::MemoryLimitExceeded {
+            required: 4 * tx_size,
+            limit: 4 * tx_size - 1
+        }
+    );
+
+    let config = ProverConfig::new().with_max_trace_memory(4 * tx_size);
+    let prover = prover.with_config(config);
+    assert!(prover.try_build_trace(&tx_metadata).is_ok());
+}
+
+#[test]
+fn transaction_test_transactions_cap() {
+    use super::{Error, ProverConfig, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+
+    // Any number of transactions up to the cap is proven by the same AIR program, the count
+    // being exposed along with the batch statistics
+    let config = ProverConfig::new().with_max_transactions(3);
+    assert_eq!(config.max_transactions(), Some(3));
+    let prover = TransactionProver::new(build_options(1))
+        .with_config(config)
+        .with_batch_statistics();
+    for num_transactions in 1..=3 {
+        let tx_metadata = TransactionMetadata::build_random_with_tree_depth(num_transactions, 4);
+        let proof = prover.prove(&tx_metadata).unwrap();
+        let pub_inputs = prover.public_inputs(&tx_metadata);
+        assert_eq!(
+            pub_inputs.batch_statistics.unwrap().num_transactions,
+            num_transactions
+        );
+        assert!(super::verify_with_hasher::<Rescue63>(proof, pub_inputs).is_ok());
+    }
+
+    // Padding transactions are not accounted for
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(4, 4);
+    assert_eq!(
+        prover.try_build_trace(&tx_metadata).unwrap_err(),
+        Error::TooManyTransactions {
+            num_transactions: 4,
+            limit: 3
+        }
+    );
+}
+
+#[test]
+fn transaction_test_constraint_checks() {
+    use super::{TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1)).with_constraint_checks();
+    let trace = prover.build_trace(&tx_metadata);
+    assert!(prover.check_constraints(&tx_metadata, &trace).is_ok());
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+}
+
+#[test]
+fn transaction_trace_segments() {
+    use super::constants::{TRACE_WIDTH, TRANSACTION_CYCLE_LENGTH};
+    use super::{Error, ProverConfig, TransactionMetadata, TransactionProver};
+    use winterfell::Trace;
+
+    // Three transactions are padded with a no-op, the segments matching the rows of the
+    // whole trace
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let trace = prover.build_trace(&tx_metadata);
+    let segments = prover.trace_segments(&tx_metadata).unwrap();
+    assert_eq!(segments.len(), 4);
+    for (index, segment) in segments.enumerate() {
+        assert_eq!(segment.index(), index);
+        assert_eq!(segment.first_step(), index * TRANSACTION_CYCLE_LENGTH);
+        assert_eq!(segment.length(), TRANSACTION_CYCLE_LENGTH);
+        for step in 0..segment.length() {
+            for register in 0..TRACE_WIDTH {
+                assert_eq!(
+                    segment.get(register, step),
+                    trace.get(register, segment.first_step() + step)
+                );
+            }
+        }
+    }
+
+    // the number of transactions is capped, but not the memory of the whole trace
+    let config = ProverConfig::new()
+        .with_max_transactions(2)
+        .with_max_trace_memory(1);
+    let prover = TransactionProver::new(build_options(1)).with_config(config);
+    assert!(matches!(
+        prover.trace_segments(&tx_metadata),
+        Err(Error::TooManyTransactions { .. })
+    ));
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    assert_eq!(prover.trace_segments(&tx_metadata).unwrap().count(), 2);
+}
+
+#[test]
+fn transaction_constraint_degrees() {
+    use super::{TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let trace = prover.build_trace(&tx_metadata);
+    let degrees = prover.constraint_degrees(&tx_metadata, &trace);
+    assert_eq!(
+        degrees.len(),
+        super::air::transition_constraint_degrees(false).len()
+    );
+    let mismatches: Vec<String> = degrees
+        .iter()
+        .filter(|degree| !degree.is_consistent())
+        .map(|degree| degree.to_string())
+        .collect();
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+
+    // as do the constraints binding the indices of accounts to their keys, appended when
+    // required by the public inputs
+    let prover = prover.with_derived_indices();
+    let degrees = prover.constraint_degrees(&tx_metadata, &trace);
+    assert_eq!(
+        degrees.len(),
+        super::air::transition_constraint_degrees(true).len()
+    );
+    let mismatches: Vec<String> = degrees
+        .iter()
+        .filter(|degree| !degree.is_consistent())
+        .map(|degree| degree.to_string())
+        .collect();
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+}
+
+#[test]
+fn constraint_registry() {
+    use super::constants::schnorr_const::AFFINE_POINT_WIDTH;
+    use super::constants::{SENDER_KEY_POINT_RES, TRACE_WIDTH, TRANSACTION_CYCLE_LENGTH};
+    use super::diagnostics::{check_transition_constraints, constraint_names, ConstraintFailure};
+    use super::utils::rescue::Rescue63;
+    use super::{PublicInputs, TransactionAir};
+    use winterfell::{
+        math::{fields::f63::BaseElement, FieldElement},
+        Air, TraceInfo, TraceTable,
+    };
+
+    let root = Rescue63::digest(&[BaseElement::ZERO]);
+    let pub_inputs = PublicInputs::new(root, root, 3, 0, vec![], vec![]).with_derived_indices();
+    let trace_length = TRANSACTION_CYCLE_LENGTH * 2;
+    let air = TransactionAir::<Rescue63>::new(
+        TraceInfo::new(TRACE_WIDTH, trace_length),
+        pub_inputs,
+        build_options(1),
+    );
+
+    // every constraint is named
+    let num_constraints = air.context().num_transition_constraints();
+    for slot in 0..num_constraints {
+        assert!(!constraint_names(slot).is_empty(), "constraint {}", slot);
+    }
+    assert!(constraint_names(num_constraints).is_empty());
+
+    // the binding of the signing key to the sender's leaf shares its slots with no other
+    // constraint, so that an altered coordinate cannot be offset by another register
+    for offset in 0..AFFINE_POINT_WIDTH {
+        assert_eq!(
+            constraint_names(SENDER_KEY_POINT_RES + offset),
+            vec!["Schnorr: signing key from the sender's leaf"]
+        );
+    }
+
+    // the first register of an empty trace does not follow the Rescue rounds of the Merkle
+    // hash, which share their constraints with the scalar multiplication of Schnorr
+    let trace = TraceTable::new(TRACE_WIDTH, trace_length);
+    assert_eq!(
+        check_transition_constraints(&air, &trace),
+        Err(ConstraintFailure {
+            constraint: 0,
+            names: vec![
+                "Merkle: sender's initial leaf hash",
+                "Schnorr: s.G + h.P accumulator"
+            ],
+            step: 0,
+            registers: vec![0],
+        })
+    );
+}
+
+#[test]
+fn transaction_test_signature_pre_validation() {
+    use super::{TransactionKind, TransactionMetadata};
+
+    let kinds = [
+        TransactionKind::Transfer,
+        TransactionKind::Withdrawal {
+            recipient: [0xab; 20],
+        },
+        TransactionKind::Deposit,
+    ];
+    let tx_metadata = TransactionMetadata::build_random_with_kinds(&kinds, 4);
+    assert!(tx_metadata.verify_signatures());
+}
+
+#[test]
+fn transaction_test_range_log() {
+    use super::{TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert_eq!(pub_inputs.range_log, super::range::RANGE_LOG);
+
+    // The proof must be verified against the range log it was generated for
+    let other_pub_inputs = pub_inputs.with_range_log(32);
+    assert!(super::verify_batch(vec![proof], vec![other_pub_inputs])[0].is_err());
+}
+
+#[test]
+fn transaction_test_range_log_too_small() {
+    use super::{Error, MetadataError, TransactionMetadata, TransactionProver};
+
+    // Random balances span 64 bits, and cannot be range-checked over 16 bits
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new_with_range_log(build_options(1), 16);
+    assert!(matches!(
+        prover.prove(&tx_metadata),
+        Err(Error::InvalidMetadata(MetadataError::OutOfRange(0)))
+    ));
+}
+
+#[test]
+fn transaction_test_receiver_debit_rejected() {
+    use super::constants::DELTA_SIGN_RES;
+    use super::diagnostics::constraint_names;
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::utils::rescue::Rescue63;
+    use super::{
+        AccountState, Error, MetadataError, Transaction, TransactionBatchBuilder, TransactionError,
+        TransactionKind, TransactionMetadata, TransactionProver,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+
+    // Without signed deltas, a transfer of a negative amount, which would take 10 from the
+    // receiver, is out of range, and refunds authorized by the receiver are rejected
+    let mut debit = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: -BaseElement::from(10u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2).with_range_log(32);
+    debit.signature = sign(builder.message(&debit).unwrap(), &secret_keys[0]);
+    assert_eq!(
+        builder.add_transaction(debit),
+        Err(TransactionError::OutOfRange(0))
+    );
+    let mut refund = debit;
+    refund.fee = BaseElement::ZERO;
+    refund.kind = TransactionKind::Refund;
+    refund.signature = sign(builder.message(&refund).unwrap(), &secret_keys[1]);
+    assert_eq!(
+        builder.add_transaction(refund),
+        Err(TransactionError::InvalidKind(0))
+    );
+    assert_eq!(builder.num_transactions(), 0);
+
+    // Metadata applying the refund anyway is rejected by the validation and by the constraints
+    let witness = state.apply_transaction(&refund, 2);
+    assert_eq!(
+        state.get_account(1).unwrap().balance,
+        BaseElement::from(90u8)
+    );
+    let tx_metadata =
+        TransactionMetadata::from_witnesses(&[refund], vec![witness], state.root(), 2);
+    assert_eq!(
+        tx_metadata.validate_with_hasher::<Rescue63>(32),
+        Err(MetadataError::InvalidKind(0))
+    );
+    let prover = TransactionProver::new(build_options(1));
+    let trace = prover.build_trace(&tx_metadata);
+    match prover.check_constraints(&tx_metadata, &trace) {
+        Err(Error::UnsatisfiedConstraint(failure)) => {
+            assert_eq!(failure.names, constraint_names(DELTA_SIGN_RES))
+        }
+        result => panic!("refund without signed deltas not rejected: {:?}", result),
+    }
+}
+
+#[test]
+fn transaction_test_signed_deltas() {
+    use super::constants::{
+        DELTA_SIGN_RES, SENDER_KEY_POINT_POS, SENDER_KEY_POINT_RES, TRANSACTION_CYCLE_LENGTH,
+    };
+    use super::diagnostics::constraint_names;
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::utils::rescue::Rescue63;
+    use super::{
+        AccountState, Error, MetadataError, Transaction, TransactionBatchBuilder, TransactionError,
+        TransactionKind, TransactionMetadata, TransactionProver,
+    };
+    use rand_core::OsRng;
+    use winterfell::{
+        math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement},
+        Serializable,
+    };
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+    let refund = |amount: u8, fee: u8| Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: -BaseElement::from(amount),
+        fee: BaseElement::from(fee),
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Refund,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2)
+        .with_range_log(32)
+        .with_signed_deltas();
+
+    // refunds must be signed by their receiver, pay no fee and fit in the receiver's balance,
+    // and other transactions cannot transfer negative amounts
+    let mut transaction = refund(10, 0);
+    let sender_signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    transaction.signature = sender_signature;
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::InvalidSignature(0))
+    );
+    let mut transaction = refund(10, 1);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[1]);
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::InvalidKind(0))
+    );
+    let mut transaction = refund(200, 0);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[1]);
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::OutOfRange(0))
+    );
+    let mut transaction = refund(10, 0);
+    transaction.kind = TransactionKind::Transfer;
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::OutOfRange(0))
+    );
+
+    // a refund signed by its receiver credits the sender, and increments the receiver's nonce
+    let mut transaction = refund(10, 0);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[1]);
+    assert!(builder.add_transaction(transaction).is_ok());
+    let tx_metadata = builder.build().unwrap();
+    let sender = state.get_account(0).unwrap();
+    let receiver = state.get_account(1).unwrap();
+    assert_eq!(sender.balance, BaseElement::from(110u8));
+    assert_eq!(sender.nonce, BaseElement::ZERO);
+    assert_eq!(receiver.balance, BaseElement::from(90u8));
+    assert_eq!(receiver.nonce, BaseElement::ONE);
+
+    assert!(tx_metadata
+        .validate_signed_with_hasher::<Rescue63>(32)
+        .is_ok());
+    let deserialized = TransactionMetadata::from_bytes(&tx_metadata.to_bytes()).unwrap();
+    assert_eq!(deserialized.to_bytes(), tx_metadata.to_bytes());
+    assert_eq!(
+        tx_metadata.validate_with_hasher::<Rescue63>(32),
+        Err(MetadataError::InvalidKind(0))
+    );
+    let prover = TransactionProver::new_with_range_log(build_options(1), 32).with_signed_deltas();
+    assert!(prover.public_inputs(&tx_metadata).signed_deltas);
+    let mut trace = prover.build_trace(&tx_metadata);
+    assert!(prover.check_constraints(&tx_metadata, &trace).is_ok());
+
+    // the refund does not satisfy the constraints of a batch without signed deltas
+    let unsigned_prover = TransactionProver::new(build_options(1));
+    let unsigned_trace = unsigned_prover.build_trace(&tx_metadata);
+    match unsigned_prover.check_constraints(&tx_metadata, &unsigned_trace) {
+        Err(Error::UnsatisfiedConstraint(failure)) => {
+            assert_eq!(failure.names, constraint_names(DELTA_SIGN_RES))
+        }
+        result => panic!("refund without signed deltas not rejected: {:?}", result),
+    }
+
+    // the refund signed by its sender instead is rejected, as the signature is verified
+    // against the key of the receiver's leaf
+    let mut forged = tx_metadata.clone();
+    forged.signatures[0] = sender_signature;
+    assert_eq!(
+        forged.validate_signed_with_hasher::<Rescue63>(32),
+        Err(MetadataError::InvalidSignature(0))
+    );
+    let sender_key = state.get_account(0).unwrap().pub_key_elements();
+    for step in 0..TRANSACTION_CYCLE_LENGTH {
+        for (offset, &element) in sender_key.iter().enumerate() {
+            trace.set(SENDER_KEY_POINT_POS + offset, step, element);
+        }
+    }
+    match prover.check_constraints(&tx_metadata, &trace) {
+        Err(Error::UnsatisfiedConstraint(failure)) => {
+            assert_eq!(failure.names, constraint_names(SENDER_KEY_POINT_RES))
+        }
+        result => panic!("refund verified against the sender's key: {:?}", result),
+    }
+}
+
+#[test]
+fn transaction_test_intermediate_roots() {
+    use super::{PublicInputs, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(4, 4);
+    let prover = TransactionProver::new(build_options(1)).with_intermediate_roots();
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    let intermediate_roots: Vec<_> = tx_metadata.initial_roots[1..]
+        .iter()
+        .map(|root| root.to_elements())
+        .collect();
+    assert_eq!(pub_inputs.intermediate_roots, Some(intermediate_roots));
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof binds every intermediate root
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.intermediate_roots.as_mut().unwrap()[1] = pub_inputs.initial_root;
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+
+    // The proof must be verified against the intermediate roots it exposes
+    let other_pub_inputs = PublicInputs {
+        intermediate_roots: None,
+        ..pub_inputs
+    };
+    assert!(super::verify_with_hasher::<Rescue63>(proof, other_pub_inputs).is_err());
+}
+
+#[test]
+fn transaction_test_transactions_digest() {
+    use super::{PublicInputs, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+
+    // Three transactions are padded with a no-op, which leaves the digest unchanged
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let prover = TransactionProver::new(build_options(1)).with_transactions_digest();
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    let transactions_digest = tx_metadata.transactions_digest();
+    assert_eq!(
+        pub_inputs.transactions_digest,
+        Some(transactions_digest.to_elements())
+    );
+    assert_eq!(
+        tx_metadata
+            .padded_with_hasher::<Rescue63>()
+            .transactions_digest(),
+        transactions_digest
+    );
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof binds the transfers it covers, in order
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.transactions_digest = Some(
+        tx_metadata.sub_batches(2)[0]
+            .transactions_digest()
+            .to_elements(),
+    );
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+
+    // The proof must be verified against the digest it exposes
+    let other_pub_inputs = PublicInputs {
+        transactions_digest: None,
+        ..pub_inputs
+    };
+    assert!(super::verify_with_hasher::<Rescue63>(proof, other_pub_inputs).is_err());
+}
+
+#[test]
+fn transaction_test_inclusion_proof() {
+    use super::{InclusionError, InclusionProof, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+    use winterfell::{
+        math::{fields::f63::BaseElement, FieldElement},
+        Deserializable, Serializable, SliceReader,
+    };
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let prover = TransactionProver::new(build_options(1))
+        .with_transactions_digest()
+        .with_batch_statistics();
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_with_hasher::<Rescue63>(proof, pub_inputs.clone()).is_ok());
+
+    for tx_index in 0..3 {
+        let inclusion_proof = tx_metadata.inclusion_proof(tx_index).unwrap();
+        assert_eq!(inclusion_proof.position(), tx_index);
+        assert_eq!(inclusion_proof.amount(), tx_metadata.deltas[tx_index]);
+        assert_eq!(inclusion_proof.digest(), tx_metadata.transactions_digest());
+        assert!(inclusion_proof.verify(&pub_inputs).is_ok());
+
+        let bytes = inclusion_proof.to_bytes();
+        let mut source = SliceReader::new(&bytes);
+        assert_eq!(
+            InclusionProof::read_from(&mut source).unwrap(),
+            inclusion_proof
+        );
+    }
+    // Padding no-ops are not part of the digest
+    let padded_metadata = tx_metadata.padded_with_hasher::<Rescue63>();
+    assert!(padded_metadata.inclusion_proof(3).is_none());
+
+    // A transfer of another amount, or at another position, is rejected
+    let mut tampered_metadata = tx_metadata.clone();
+    tampered_metadata.deltas[1] += BaseElement::ONE;
+    assert_eq!(
+        tampered_metadata
+            .inclusion_proof(1)
+            .unwrap()
+            .verify(&pub_inputs),
+        Err(InclusionError::DigestMismatch)
+    );
+    let mut bytes = tx_metadata.inclusion_proof(2).unwrap().to_bytes();
+    bytes[0] = 0;
+    let mut source = SliceReader::new(&bytes);
+    assert_eq!(
+        InclusionProof::read_from(&mut source)
+            .unwrap()
+            .verify(&pub_inputs),
+        Err(InclusionError::PositionMismatch)
+    );
+
+    // Inclusion proofs require the digest to be exposed
+    let pub_inputs = TransactionProver::new(build_options(1)).public_inputs(&tx_metadata);
+    assert_eq!(
+        tx_metadata.inclusion_proof(0).unwrap().verify(&pub_inputs),
+        Err(InclusionError::DigestNotExposed)
+    );
+}
+
+#[test]
+fn transaction_test_nullifiers() {
+    use super::{PublicInputs, TransactionKind, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+
+    // Deposits and the padding no-op consume no nonce, and have no nullifier
+    let kinds = [
+        TransactionKind::Transfer,
+        TransactionKind::Withdrawal {
+            recipient: [0xab; 20],
+        },
+        TransactionKind::Deposit,
+    ];
+    let tx_metadata = TransactionMetadata::build_random_with_kinds(&kinds, 4);
+    let nullifiers = tx_metadata.nullifiers();
+    assert_eq!(
+        nullifiers,
+        vec![
+            tx_metadata.s_old_values[0].nullifier(),
+            tx_metadata.s_old_values[1].nullifier()
+        ]
+    );
+    let prover = TransactionProver::new(build_options(1)).with_nullifiers();
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof binds the nullifiers it covers, in order
+    let wrong_pub_inputs = pub_inputs
+        .clone()
+        .with_nullifiers(&[nullifiers[1], nullifiers[0]]);
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+
+    // The proof must be verified against the commitment it exposes
+    let other_pub_inputs = PublicInputs {
+        nullifiers_commitment: None,
+        ..pub_inputs
+    };
+    assert!(super::verify_with_hasher::<Rescue63>(proof, other_pub_inputs).is_err());
+}
+
+#[test]
+fn transaction_test_batch_statistics() {
+    use super::{BatchStatistics, PublicInputs, TransactionMetadata, TransactionProver};
+    use crate::utils::rescue::Rescue63;
+    use winterfell::math::{fields::f63::BaseElement, FieldElement, StarkField};
+
+    // Three transactions are padded with a no-op, which is not counted
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let volume = tx_metadata
+        .deltas
+        .iter()
+        .fold(BaseElement::ZERO, |volume, &delta| volume + delta);
+    let statistics = tx_metadata.batch_statistics();
+    assert_eq!(statistics, BatchStatistics::new(3, volume.to_repr()));
+    assert_eq!(
+        tx_metadata
+            .padded_with_hasher::<Rescue63>()
+            .batch_statistics(),
+        statistics
+    );
+
+    let prover = TransactionProver::new(build_options(1)).with_batch_statistics();
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert_eq!(pub_inputs.batch_statistics, Some(statistics));
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof binds both the number of transactions and their volume
+    let wrong_pub_inputs = pub_inputs
+        .clone()
+        .with_batch_statistics(BatchStatistics::new(2, statistics.volume));
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+    let wrong_pub_inputs = pub_inputs
+        .clone()
+        .with_batch_statistics(BatchStatistics::new(3, statistics.volume + 1));
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+
+    // The proof must be verified against the statistics it exposes
+    let other_pub_inputs = PublicInputs {
+        batch_statistics: None,
+        ..pub_inputs
+    };
+    assert!(super::verify_with_hasher::<Rescue63>(proof, other_pub_inputs).is_err());
+}
+
+#[test]
+fn public_inputs_intermediate_roots_commitment() {
+    use super::PublicInputs;
+    use crate::utils::rescue::Rescue63;
+    use winterfell::math::fields::f63::BaseElement;
+
+    let roots: Vec<_> = (0..4u64)
+        .map(|i| Rescue63::digest(&[BaseElement::new(i)]))
+        .collect();
+    let pub_inputs = PublicInputs::new(roots[0], roots[3], 4, 0, Vec::new(), Vec::new());
+    assert_eq!(pub_inputs.intermediate_roots_commitment(), None);
+
+    let commitment = pub_inputs
+        .clone()
+        .with_intermediate_roots(&roots[1..3])
+        .intermediate_roots_commitment();
+    assert!(commitment.is_some());
+    let swapped_commitment = pub_inputs
+        .with_intermediate_roots(&[roots[2], roots[1]])
+        .intermediate_roots_commitment();
+    assert_ne!(commitment, swapped_commitment);
+}
+
+#[test]
+fn transaction_test_tree_hasher() {
+    use super::{TransactionKind, TransactionMetadata, TransactionProver};
+    use crate::merkle::TreeHasher;
+    use crate::utils::rescue::Rescue63;
+
+    let kinds = [TransactionKind::Transfer; 2];
+    let tx_metadata = TransactionMetadata::build_random_with_hasher::<Rescue63>(&kinds, 4);
+    let prover =
+        TransactionProver::<Rescue63>::new_with_hasher(build_options(1), super::range::RANGE_LOG);
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert_eq!(pub_inputs.hash_id, Rescue63::HASH_ID);
+    assert!(super::verify_with_hasher::<Rescue63>(proof, pub_inputs).is_ok());
+}
+
+#[test]
+#[should_panic]
+fn transaction_test_tree_hasher_mismatch() {
+    use super::{PublicInputs, TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let proof = prover.prove(&tx_metadata).unwrap();
+    // The public inputs must identify the hash function the proof was generated with
+    let pub_inputs = PublicInputs {
+        hash_id: 0xff,
+        ..prover.public_inputs(&tx_metadata)
+    };
+    let _ = super::verify_with_hasher::<crate::utils::rescue::Rescue63>(proof, pub_inputs);
+}
+
+#[test]
+fn transaction_test_stateless_verification() {
+    let transaction = Box::new(super::TransactionExample::new(build_options(1), 2));
+    let proof = transaction.prove().unwrap();
+    let initial_root = transaction.tx_metadata.initial_roots[0];
+    let final_root = transaction.tx_metadata.final_root;
+    let tree_depth = transaction.tx_metadata.tree_depth();
+    let fee_collector = transaction.tx_metadata.fee_collector();
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        tree_depth,
+        fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_ok());
+    assert!(super::verify_transaction_proof(
+        proof,
+        final_root,
+        initial_root,
+        tree_depth,
+        fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn transaction_test_custom_tree_depth() {
+    let transaction = Box::new(super::TransactionExample::new_with_tree_depth(
+        build_options(1),
+        2,
+        5,
+    ));
+    let proof = transaction.prove().unwrap();
+    let initial_root = transaction.tx_metadata.initial_roots[0];
+    let final_root = transaction.tx_metadata.final_root;
+    let fee_collector = transaction.tx_metadata.fee_collector();
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        5,
+        fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_ok());
+    assert!(super::verify_transaction_proof(
+        proof,
+        initial_root,
+        final_root,
+        4,
+        fee_collector % (1 << 4),
+        Vec::new(),
+        Vec::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn transaction_test_fee_collector() {
+    let transaction = Box::new(super::TransactionExample::new(build_options(1), 2));
+    let proof = transaction.prove().unwrap();
+    let initial_root = transaction.tx_metadata.initial_roots[0];
+    let final_root = transaction.tx_metadata.final_root;
+    let tree_depth = transaction.tx_metadata.tree_depth();
+    let fee_collector = transaction.tx_metadata.fee_collector();
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        tree_depth,
+        fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_ok());
+
+    // The fees must have been credited to the designated leaf
+    let other_fee_collector = fee_collector ^ 1;
+    assert!(super::verify_transaction_proof(
+        proof,
+        initial_root,
+        final_root,
+        tree_depth,
+        other_fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn transaction_test_withdrawal() {
+    use super::{TransactionKind, TransactionMetadata};
+
+    let recipient = [0xab; 20];
+    let kinds = [
+        TransactionKind::Transfer,
+        TransactionKind::Withdrawal { recipient },
+    ];
+    let transaction = super::TransactionExample {
+        options: build_options(1),
+        tx_metadata: TransactionMetadata::build_random_with_kinds(&kinds, 4),
+    };
+    let proof = transaction.prove().unwrap();
+    let initial_root = transaction.tx_metadata.initial_roots[0];
+    let final_root = transaction.tx_metadata.final_root;
+    let fee_collector = transaction.tx_metadata.fee_collector();
+    let withdrawals = transaction.tx_metadata.withdrawals();
+    assert_eq!(withdrawals.len(), 1);
+    assert_eq!(withdrawals[0].tx_index, 1);
+    assert_eq!(withdrawals[0].recipient, recipient);
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        withdrawals.clone(),
+        Vec::new()
+    )
+    .is_ok());
+
+    // The withdrawals must be exposed exactly as performed
+    let mut other_withdrawals = withdrawals.clone();
+    other_withdrawals[0].recipient[0] ^= 1;
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        other_withdrawals,
+        Vec::new()
+    )
+    .is_err());
+    let mut other_withdrawals = withdrawals;
+    other_withdrawals[0].amount += 1;
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        other_withdrawals,
+        Vec::new()
+    )
+    .is_err());
+    assert!(super::verify_transaction_proof(
+        proof,
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn transaction_test_deposit() {
+    use super::{TransactionKind, TransactionMetadata};
+
+    let kinds = [TransactionKind::Deposit, TransactionKind::Transfer];
+    let transaction = super::TransactionExample {
+        options: build_options(1),
+        tx_metadata: TransactionMetadata::build_random_with_kinds(&kinds, 4),
+    };
+    let proof = transaction.prove().unwrap();
+    let initial_root = transaction.tx_metadata.initial_roots[0];
+    let final_root = transaction.tx_metadata.final_root;
+    let fee_collector = transaction.tx_metadata.fee_collector();
+    let deposits = transaction.tx_metadata.deposits();
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0].tx_index, 0);
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        Vec::new(),
+        deposits.clone()
+    )
+    .is_ok());
+
+    // The deposits must be exposed exactly as performed
+    let mut other_deposits = deposits.clone();
+    other_deposits[0].receiver ^= 1;
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        Vec::new(),
+        other_deposits
+    )
+    .is_err());
+    let mut other_deposits = deposits;
+    other_deposits[0].amount += 1;
+    assert!(super::verify_transaction_proof(
+        proof.clone(),
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        Vec::new(),
+        other_deposits
+    )
+    .is_err());
+    assert!(super::verify_transaction_proof(
+        proof,
+        initial_root,
+        final_root,
+        4,
+        fee_collector,
+        Vec::new(),
+        Vec::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn transaction_test_noop() {
+    use super::merkle::StateTree;
+    use super::{
+        AccountState, TransactionBatchBuilder, TransactionError, TransactionKind,
+        TransactionMetadata,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{
+        curves::curve_f63::{AffinePoint, Scalar},
+        fields::f63::BaseElement,
+        FieldElement,
+    };
+
+    let kinds = [
+        TransactionKind::NoOp,
+        TransactionKind::Transfer,
+        TransactionKind::Deposit,
+        TransactionKind::NoOp,
+    ];
+    let transaction = super::TransactionExample {
+        options: build_options(1),
+        tx_metadata: TransactionMetadata::build_random_with_kinds(&kinds, 4),
+    };
+    assert!(transaction.tx_metadata.validate().is_ok());
+    assert_eq!(transaction.tx_metadata.deposits().len(), 1);
+    let proof = transaction.prove().unwrap();
+    assert!(transaction.verify(proof).is_ok());
+
+    // No-ops require no signature from the account's owner, and leave the state untouched
+    let mut state = StateTree::new(4);
+    for index in 0..2 {
+        let pkey = AffinePoint::from(AffinePoint::generator() * Scalar::random(&mut OsRng));
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ONE),
+        );
+    }
+    let initial_root = state.root();
+    let mut builder = TransactionBatchBuilder::new(&mut state, 1);
+    assert_eq!(
+        builder.add_noop(1),
+        Err(TransactionError::FeeCollectorAccount(0))
+    );
+    assert_eq!(
+        builder.add_noop(2),
+        Err(TransactionError::UnknownAccount(0))
+    );
+    assert!(builder.add_noop(0).is_ok());
+    assert!(builder.add_noop(0).is_ok());
+    assert_eq!(builder.state().root(), initial_root);
+
+    let tx_metadata = builder.build().unwrap();
+    assert!(tx_metadata.validate().is_ok());
+    assert_eq!(tx_metadata.final_root, initial_root);
+}
+
+#[test]
+fn transaction_test_rekey() {
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        AccountState, Transaction, TransactionBatchBuilder, TransactionError, TransactionKind,
+        TransactionMetadata, TransactionProver,
+    };
+    use rand_core::OsRng;
+    use winterfell::{
+        math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement},
+        Serializable,
+    };
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+
+    // The owner of account 0 replaces its key, signing with the current one
+    let new_key = SecretKey::random(&mut OsRng).public_key().to_point();
+    let mut rekey = Transaction {
+        sender: 0,
+        receiver: 0,
+        amount: BaseElement::ZERO,
+        fee: BaseElement::ZERO,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Rekey { new_key },
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2);
+
+    // Rekeyings neither move funds nor pay fees, and only concern the sender's account
+    for (amount, fee, receiver) in [
+        (BaseElement::ONE, BaseElement::ZERO, 0),
+        (BaseElement::ZERO, BaseElement::ONE, 0),
+        (BaseElement::ZERO, BaseElement::ZERO, 1),
+    ] {
+        let mut invalid = rekey;
+        invalid.amount = amount;
+        invalid.fee = fee;
+        invalid.receiver = receiver;
+        invalid.signature = sign(builder.message(&invalid).unwrap(), &secret_keys[0]);
+        assert_eq!(
+            builder.add_transaction(invalid),
+            Err(TransactionError::InvalidKind(0))
+        );
+    }
+
+    rekey.signature = sign(builder.message(&rekey).unwrap(), &secret_keys[0]);
+    assert!(builder.add_transaction(rekey).is_ok());
+    let account = *builder.state().get_account(0).unwrap();
+    assert_eq!(account.pub_key, new_key);
+    assert_eq!(account.balance, BaseElement::from(100u8));
+    assert_eq!(account.nonce, BaseElement::ONE);
+
+    let tx_metadata = builder.build().unwrap();
+    assert!(tx_metadata.validate().is_ok());
+    let deserialized = TransactionMetadata::from_bytes(&tx_metadata.to_bytes()).unwrap();
+    assert_eq!(deserialized.to_bytes(), tx_metadata.to_bytes());
+
+    let prover = TransactionProver::new(build_options(1));
+    let trace = prover.build_trace(&tx_metadata);
+    assert!(prover.check_constraints(&tx_metadata, &trace).is_ok());
+}
+
+#[test]
+fn transaction_test_padding() {
+    use super::{constants::TRANSACTION_CYCLE_LENGTH, TransactionMetadata, TransactionProver};
+
+    // Three transactions are padded with a no-op to fill a trace of four segments
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let prover = TransactionProver::new(build_options(1)).with_intermediate_roots();
+    let (proof, report) = prover.prove_with_report(&tx_metadata).unwrap();
+    assert_eq!(report.num_transactions, 3);
+    assert_eq!(report.trace_length, 4 * TRANSACTION_CYCLE_LENGTH);
+
+    // The padding leaves the final root unchanged
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    let intermediate_roots = pub_inputs.intermediate_roots.clone().unwrap();
+    assert_eq!(intermediate_roots.len(), 3);
+    assert_eq!(intermediate_roots[2], tx_metadata.final_root.to_elements());
+    assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+}
+
+#[test]
+fn transaction_test_nonce_replay() {
+    use super::schnorr::{keys::SecretKey, sign, verify_signature};
+    use super::{AccountState, TransactionKind};
+    use rand_core::OsRng;
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let skey = SecretKey::random(&mut OsRng);
+    let pkey = skey.public_key().to_point();
+    let nonce = BaseElement::from(41u8);
+    let account = AccountState::new(pkey, BaseElement::from(100u8), nonce);
+    let s_addr = account.pub_key_elements();
+    let r_addr = s_addr;
+    let delta = BaseElement::from(10u8);
+    let fee = BaseElement::from(1u8);
+
+    let message = super::build_tx_message(
+        &s_addr,
+        &r_addr,
+        0,
+        1,
+        delta,
+        nonce,
+        fee,
+        TransactionKind::Transfer,
+        None,
+        BaseElement::ZERO,
+        0,
+    );
+    let signature = sign(message, &skey);
+    assert!(verify_signature(message, signature));
+
+    // Once applied, the sender's nonce is incremented and the signature cannot be replayed
+    let replayed_message = super::build_tx_message(
+        &s_addr,
+        &r_addr,
+        0,
+        1,
+        delta,
+        nonce + BaseElement::ONE,
+        fee,
+        TransactionKind::Transfer,
+        None,
+        BaseElement::ZERO,
+        0,
+    );
+    assert!(!verify_signature(replayed_message, signature));
+}
+
+#[test]
+fn transaction_message_indices() {
+    use super::constants::{RECEIVER_INDEX_MESSAGE_INDEX, SENDER_INDEX_MESSAGE_INDEX};
+    use super::{SplitOutputs, TransactionKind, AFFINE_POINT_WIDTH};
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let s_addr = [BaseElement::ONE; AFFINE_POINT_WIDTH];
+    let r_addr = [BaseElement::from(2u8); AFFINE_POINT_WIDTH];
+    let message = |s_index, r_index, nonce: u8, kind| {
+        super::build_tx_message(
+            &s_addr,
+            &r_addr,
+            s_index,
+            r_index,
+            BaseElement::from(10u8),
+            BaseElement::from(nonce),
+            BaseElement::ONE,
+            kind,
+            None,
+            BaseElement::ZERO,
+            0,
+        )
+    };
+
+    // the same transfer between other leaves is another message
+    let transfer = message(3, 5, 0, TransactionKind::Transfer);
+    assert_eq!(transfer[SENDER_INDEX_MESSAGE_INDEX], BaseElement::from(3u8));
+    assert_eq!(
+        transfer[RECEIVER_INDEX_MESSAGE_INDEX],
+        BaseElement::from(5u8)
+    );
+    assert_ne!(transfer, message(4, 5, 0, TransactionKind::Transfer));
+    assert_ne!(transfer, message(3, 4, 0, TransactionKind::Transfer));
+
+    // the outputs of a split transfer share their message, which signs their receivers instead
+    let outputs = SplitOutputs::new(&[(5, BaseElement::from(4u8)), (6, BaseElement::from(6u8))]);
+    let first = message(
+        3,
+        5,
+        0,
+        TransactionKind::Split {
+            outputs,
+            position: 0,
+        },
+    );
+    assert_eq!(first[RECEIVER_INDEX_MESSAGE_INDEX], BaseElement::ZERO);
+    assert_eq!(
+        first,
+        message(
+            3,
+            6,
+            1,
+            TransactionKind::Split {
+                outputs,
+                position: 1
+            }
+        )
+    );
+}
+
+#[test]
+fn account_state_elements() {
+    use super::merkle::hash_leaf;
+    use super::utils::rescue::Rescue63;
+    use super::{AccountState, AFFINE_POINT_WIDTH, POINT_COORDINATE_WIDTH};
+    use rand_core::OsRng;
+    use winterfell::{
+        math::{
+            curves::curve_f63::{AffinePoint, Scalar},
+            fields::f63::BaseElement,
+        },
+        Deserializable, Serializable, SliceReader,
+    };
+
+    let pkey = AffinePoint::from(AffinePoint::generator() * Scalar::random(&mut OsRng));
+    let account = AccountState::new(pkey, BaseElement::from(1000u64), BaseElement::from(3u8));
+
+    let elements = account.to_elements();
+    assert_eq!(elements[0..POINT_COORDINATE_WIDTH], pkey.get_x());
+    assert_eq!(
+        elements[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH],
+        pkey.get_y()
+    );
+    assert_eq!(elements[AFFINE_POINT_WIDTH], account.balance);
+    assert_eq!(elements[AFFINE_POINT_WIDTH + 1], account.nonce);
+    assert_eq!(AccountState::from_elements(&elements), account);
+    assert_eq!(account.to_leaf::<Rescue63>(), hash_leaf(&elements));
+
+    let bytes = account.to_bytes();
+    let mut source = SliceReader::new(&bytes);
+    assert_eq!(AccountState::read_from(&mut source).unwrap(), account);
+}
+
+#[test]
+fn account_state_multisig() {
+    use super::merkle::StateTree;
+    use super::schnorr::{
+        keys::SecretKey,
+        musig::{aggregate_keys, aggregate_signatures, sign_partial, SecretNonce},
+    };
+    use super::{
+        AccountKind, AccountState, Transaction, TransactionBatchBuilder, TransactionKind,
+        MULTISIG_NONCE_FLAG,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let skeys = [SecretKey::random(&mut OsRng), SecretKey::random(&mut OsRng)];
+    let keys = [
+        skeys[0].public_key().to_point(),
+        skeys[1].public_key().to_point(),
+    ];
+    let multisig = AccountState::new_multisig(&keys, BaseElement::from(100u8));
+    assert_eq!(multisig.pub_key, aggregate_keys(&keys));
+    assert_eq!(multisig.kind(), AccountKind::Multisig);
+    assert_eq!(multisig.nonce, BaseElement::new(MULTISIG_NONCE_FLAG));
+
+    let single_key = SecretKey::random(&mut OsRng).public_key().to_point();
+    let single = AccountState::new(single_key, BaseElement::ZERO, BaseElement::ZERO);
+    assert_eq!(single.kind(), AccountKind::Single);
+
+    let mut state = StateTree::new(4);
+    state.set_account(0, multisig);
+    state.set_account(1, single);
+    state.set_account(2, single);
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2);
+
+    // Both owners sign the transfer together, as a single signature of their aggregated key
+    let mut transaction = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::from(10u8),
+        fee: BaseElement::ZERO,
+        nonce: multisig.nonce,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    let message = builder.message(&transaction).unwrap();
+    let nonces = [
+        SecretNonce::random(&mut OsRng),
+        SecretNonce::random(&mut OsRng),
+    ];
+    let public_nonces = [nonces[0].public_nonce(), nonces[1].public_nonce()];
+    let mut partial_signatures = [Scalar::zero(); 2];
+    for (index, (skey, nonce)) in skeys.iter().zip(nonces).enumerate() {
+        partial_signatures[index] =
+            sign_partial(message, skey, nonce, &keys, index, &public_nonces);
+    }
+    transaction.signature = aggregate_signatures(&public_nonces, &partial_signatures);
+    assert!(builder.add_transaction(transaction).is_ok());
+
+    // The flag survives the increment of the nonce
+    let account = *builder.state().get_account(0).unwrap();
+    assert_eq!(account.kind(), AccountKind::Multisig);
+    assert_eq!(account.nonce, multisig.nonce + BaseElement::ONE);
+    assert!(builder.build().unwrap().validate().is_ok());
+}
+
+#[test]
+fn account_state_nonce_limit() {
+    use super::merkle::StateTree;
+    use super::{
+        AccountKind, AccountState, Transaction, TransactionBatchBuilder, TransactionError,
+        TransactionKind, MULTISIG_NONCE_FLAG,
+    };
+    use winterfell::math::{
+        curves::curve_f63::{AffinePoint, Scalar},
+        fields::f63::BaseElement,
+        FieldElement,
+    };
+
+    // the last nonce below the flag cannot be incremented, as it would flag the account as
+    // multisig
+    let nonce = BaseElement::new(MULTISIG_NONCE_FLAG - 1);
+    let account = AccountState::new(AffinePoint::generator(), BaseElement::ONE, nonce);
+    assert_eq!(account.kind(), AccountKind::Single);
+    assert!(!account.can_increment_nonce());
+    assert!(AccountState::new(
+        AffinePoint::generator(),
+        BaseElement::ONE,
+        BaseElement::ZERO
+    )
+    .can_increment_nonce());
+
+    let mut state = StateTree::new(4);
+    state.set_account(0, account);
+    state.set_account(1, account);
+    state.set_account(2, account);
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2);
+    let transaction = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::ONE,
+        fee: BaseElement::ZERO,
+        nonce,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::InvalidNonce(0))
+    );
+}
+
+#[test]
+#[should_panic(expected = "nonce must be below the multisig flag")]
+fn account_state_flagged_nonce() {
+    use super::{AccountState, MULTISIG_NONCE_FLAG};
+    use winterfell::math::{
+        curves::curve_f63::AffinePoint, fields::f63::BaseElement, FieldElement,
+    };
+
+    AccountState::new(
+        AffinePoint::generator(),
+        BaseElement::ZERO,
+        BaseElement::new(MULTISIG_NONCE_FLAG),
+    );
+}
+
+#[test]
+fn account_state_multisig_rekey() {
+    use super::constants::schnorr_const::AFFINE_POINT_WIDTH;
+    use super::merkle::StateTree;
+    use super::{AccountKind, AccountState, Transaction, TransactionKind, MULTISIG_NONCE_FLAG};
+    use winterfell::math::{
+        curves::curve_f63::{AffinePoint, Scalar},
+        fields::f63::BaseElement,
+        FieldElement,
+    };
+
+    let mut elements = [BaseElement::ONE; AFFINE_POINT_WIDTH + 2];
+    elements[AFFINE_POINT_WIDTH] = BaseElement::from(100u8);
+    elements[AFFINE_POINT_WIDTH + 1] = BaseElement::new(MULTISIG_NONCE_FLAG);
+    let multisig = AccountState::from_elements(&elements);
+    assert_eq!(multisig.kind(), AccountKind::Multisig);
+
+    let mut state = StateTree::new(4);
+    state.set_account(0, multisig);
+    state.set_account(
+        1,
+        AccountState::new(
+            AffinePoint::generator(),
+            BaseElement::ZERO,
+            BaseElement::ZERO,
+        ),
+    );
+
+    // the flag is carried over by the incremented nonce, whatever the new key
+    let rekey = Transaction {
+        sender: 0,
+        receiver: 0,
+        amount: BaseElement::ZERO,
+        fee: BaseElement::ZERO,
+        nonce: multisig.nonce,
+        kind: TransactionKind::Rekey {
+            new_key: AffinePoint::generator(),
+        },
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    state.apply_transaction(&rekey, 1);
+    let rekeyed = state.get_account(0).unwrap();
+    assert_eq!(rekeyed.pub_key, AffinePoint::generator());
+    assert_eq!(rekeyed.nonce, multisig.nonce + BaseElement::ONE);
+    assert_eq!(rekeyed.kind(), AccountKind::Multisig);
+}
+
+#[test]
+fn transaction_test_batch_builder() {
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        AccountState, Transaction, TransactionBatchBuilder, TransactionError, TransactionKind,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+    let builder = TransactionBatchBuilder::new(&mut state, 2);
+    assert_eq!(builder.build().unwrap_err(), TransactionError::EmptyBatch);
+
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2);
+    let mut transaction = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::from(10u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::InvalidSignature(0))
+    );
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    assert!(builder.add_transaction(transaction).is_ok());
+    assert_eq!(
+        builder.state().get_account(0).unwrap().balance,
+        BaseElement::from(89u8)
+    );
+    assert_eq!(
+        builder.state().get_account(0).unwrap().nonce,
+        BaseElement::ONE
+    );
+    assert_eq!(
+        builder.state().get_account(1).unwrap().balance,
+        BaseElement::from(110u8)
+    );
+    assert_eq!(
+        builder.state().get_account(2).unwrap().balance,
+        BaseElement::from(101u8)
+    );
+
+    // The transaction cannot be replayed, nor overdraw the sender's balance
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::InvalidNonce(1))
+    );
+    let mut transaction = Transaction {
+        sender: 1,
+        receiver: 0,
+        amount: BaseElement::from(110u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::InsufficientBalance(1))
+    );
+    transaction.amount = BaseElement::from(50u8);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[1]);
+    assert!(builder.add_transaction(transaction).is_ok());
+
+    let tx_metadata = builder.build().unwrap();
+    assert_eq!(tx_metadata.num_transactions(), 2);
+    assert!(tx_metadata.verify_signatures());
+
+    let transaction = super::TransactionExample {
+        options: build_options(1),
+        tx_metadata,
+    };
+    let proof = transaction.prove().unwrap();
+    assert!(transaction.verify(proof).is_ok());
+
+    // The state is maintained for subsequent batches
+    assert_eq!(transaction.tx_metadata.final_root, state.root());
+    assert_eq!(state.get_account(1).unwrap().nonce, BaseElement::ONE);
+}
+
+#[test]
+fn transaction_metadata_push_transaction() {
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        AccountState, Transaction, TransactionBatchBuilder, TransactionError, TransactionKind,
+        TransactionMetadata,
+    };
+    use rand_core::OsRng;
+    use winterfell::{
+        math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement},
+        Serializable,
+    };
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+
+    // Sign the transactions against the successive states, as a builder would
+    let mut builder_state = state.clone();
+    let mut builder = TransactionBatchBuilder::new(&mut builder_state, 2);
+    let mut transactions = Vec::new();
+    for &(sender, receiver) in [(0, 1), (1, 0)].iter() {
+        let mut transaction = Transaction {
+            sender,
+            receiver,
+            amount: BaseElement::from(10u8),
+            fee: BaseElement::ONE,
+            nonce: BaseElement::ZERO,
+            kind: TransactionKind::Transfer,
+            signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+            memo: Some([sender as u8; 32]),
+        };
+        transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[sender]);
+        builder.add_transaction(transaction).unwrap();
+        transactions.push(transaction);
+    }
+    let expected_metadata = builder.build().unwrap();
+
+    // Pushing them one at a time results in the same metadata and state
+    let mut tx_metadata = TransactionMetadata::empty(&state, 2);
+    assert_eq!(tx_metadata.num_transactions(), 0);
+    assert_eq!(tx_metadata.final_root(), state.root());
+    for transaction in transactions.iter() {
+        assert!(tx_metadata
+            .push_transaction(*transaction, &mut state)
+            .is_ok());
+    }
+    assert_eq!(tx_metadata.to_bytes(), expected_metadata.to_bytes());
+    assert_eq!(state.root(), builder_state.root());
+
+    // Transactions are validated, and must be applied to the state they continue
+    let root = state.root();
+    assert_eq!(
+        tx_metadata.push_transaction(transactions[1], &mut state),
+        Err(TransactionError::InvalidNonce(2))
+    );
+    assert_eq!(state.root(), root);
+    let mut other_state = StateTree::new(4);
+    assert_eq!(
+        tx_metadata.push_transaction(transactions[1], &mut other_state),
+        Err(TransactionError::StateMismatch(2))
+    );
+}
+
+#[test]
+fn transaction_test_split_transfer() {
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        AccountState, MetadataError, SplitOutputs, SplitTransfer, TransactionBatchBuilder,
+        TransactionError,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{
+        curves::curve_f63::{AffinePoint, Scalar},
+        fields::f63::BaseElement,
+        FieldElement,
+    };
+
+    let mut state = StateTree::new(4);
+    let skey = SecretKey::random(&mut OsRng);
+    for index in 0..5 {
+        let pkey = AffinePoint::from(AffinePoint::generator() * Scalar::random(&mut OsRng));
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+    let pkey = skey.public_key().to_point();
+    state.set_account(
+        0,
+        AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+    );
+    let initial_root = state.root();
+
+    let mut builder = TransactionBatchBuilder::new(&mut state, 4);
+    let mut transfer = SplitTransfer {
+        sender: 0,
+        outputs: SplitOutputs::new(&[
+            (1, BaseElement::from(10u8)),
+            (2, BaseElement::from(20u8)),
+            (3, BaseElement::from(80u8)),
+        ]),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    transfer.signature = sign(builder.message(&transfer.transaction(0)).unwrap(), &skey);
+
+    // Outputs cannot be added on their own, and the last one overdraws the sender's balance
+    assert_eq!(
+        builder.add_transaction(transfer.transaction(0)),
+        Err(TransactionError::SplitOutput(0))
+    );
+    assert_eq!(
+        builder.add_split_transfer(transfer),
+        Err(TransactionError::InsufficientBalance(2))
+    );
+    assert_eq!(builder.num_transactions(), 0);
+    assert_eq!(builder.state().root(), initial_root);
+
+    transfer.outputs = SplitOutputs::new(&[
+        (1, BaseElement::from(10u8)),
+        (2, BaseElement::from(20u8)),
+        (3, BaseElement::from(30u8)),
+    ]);
+    transfer.signature = sign(builder.message(&transfer.transaction(0)).unwrap(), &skey);
+    assert!(builder.add_split_transfer(transfer).is_ok());
+    assert_eq!(builder.num_transactions(), 3);
+    assert_eq!(
+        builder.state().get_account(0).unwrap().balance,
+        BaseElement::from(37u8)
+    );
+    assert_eq!(
+        builder.state().get_account(0).unwrap().nonce,
+        BaseElement::from(3u8)
+    );
+    assert_eq!(
+        builder.state().get_account(3).unwrap().balance,
+        BaseElement::from(130u8)
+    );
+
+    let tx_metadata = builder.build().unwrap();
+    assert!(tx_metadata.validate().is_ok());
+
+    // Each output must match its transaction, and continue the previous outputs
+    let mut reordered = tx_metadata.clone();
+    reordered.kinds.swap(0, 1);
+    assert_eq!(reordered.validate(), Err(MetadataError::InvalidKind(0)));
+    let mut unchained = tx_metadata.clone();
+    unchained.kinds[1] = SplitTransfer {
+        outputs: SplitOutputs::new(&[(1, BaseElement::from(10u8)), (2, BaseElement::from(20u8))]),
+        ..transfer
+    }
+    .transaction(1)
+    .kind;
+    assert_eq!(unchained.validate(), Err(MetadataError::InvalidSplit(1)));
+
+    let transaction = super::TransactionExample {
+        options: build_options(1),
+        tx_metadata,
+    };
+    let proof = transaction.prove().unwrap();
+    assert!(transaction.verify(proof).is_ok());
+}
+
+#[test]
+fn transaction_test_committee_transfer() {
+    use super::constants::MEMBER_GAP_RES;
+    use super::diagnostics::constraint_names;
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign, threshold};
+    use super::{
+        AccountState, CommitteeTransfer, Error, MetadataError, Transaction,
+        TransactionBatchBuilder, TransactionError, TransactionKind, TransactionProver,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let skeys: Vec<_> = (0..3).map(|_| SecretKey::random(&mut OsRng)).collect();
+    let keys: Vec<_> = skeys
+        .iter()
+        .map(|skey| skey.public_key().to_point())
+        .collect();
+    let tree = threshold::KeyTree::with_depth(&keys, 4);
+
+    let mut state = StateTree::new(4);
+    state.set_account(
+        0,
+        AccountState::new_committee(&tree, 2, BaseElement::from(100u8)),
+    );
+    for index in 1..3 {
+        let pkey = SecretKey::random(&mut OsRng).public_key().to_point();
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2);
+    let message = builder
+        .message(&Transaction {
+            sender: 0,
+            receiver: 1,
+            amount: BaseElement::from(10u8),
+            fee: BaseElement::ONE,
+            nonce: BaseElement::ZERO,
+            kind: TransactionKind::Committee,
+            signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+            memo: None,
+        })
+        .unwrap();
+    let shares: Vec<_> = [0, 2]
+        .iter()
+        .map(|&index| {
+            let signature = sign(
+                threshold::signer_message(message, &keys[index]),
+                &skeys[index],
+            );
+            tree.share(index, signature)
+        })
+        .collect();
+    let transfer = CommitteeTransfer {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::from(10u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        shares,
+        memo: None,
+    };
+
+    // Committee transfers cannot be added on their own, nor below the threshold
+    assert_eq!(
+        builder.add_transaction(transfer.transaction(0)),
+        Err(TransactionError::CommitteeTransaction(0))
+    );
+    let below_threshold = CommitteeTransfer {
+        shares: transfer.shares[..1].to_vec(),
+        ..transfer.clone()
+    };
+    assert_eq!(
+        builder.add_committee_transfer(below_threshold),
+        Err(TransactionError::InvalidSignature(0))
+    );
+    assert!(builder.add_committee_transfer(transfer).is_ok());
+    assert_eq!(builder.num_transactions(), 2);
+    assert_eq!(
+        builder.state().get_account(0).unwrap().balance,
+        BaseElement::from(89u8)
+    );
+    assert_eq!(
+        builder.state().get_account(1).unwrap().balance,
+        BaseElement::from(110u8)
+    );
+
+    let tx_metadata = builder.build().unwrap();
+    assert!(tx_metadata.validate().is_ok());
+    let prover = TransactionProver::new(build_options(1));
+    let trace = prover.build_trace(&tx_metadata);
+    assert!(prover.check_constraints(&tx_metadata, &trace).is_ok());
+
+    // Members must sign by increasing positions in the key tree, so that none is counted twice
+    let mut reordered = tx_metadata.clone();
+    reordered.shares.swap(0, 1);
+    reordered.signatures.swap(0, 1);
+    assert_eq!(
+        reordered.validate(),
+        Err(MetadataError::InvalidCommittee(1))
+    );
+    let trace = prover.build_trace(&reordered);
+    match prover.check_constraints(&reordered, &trace) {
+        Err(Error::UnsatisfiedConstraint(failure)) => {
+            assert_eq!(failure.names, constraint_names(MEMBER_GAP_RES + 2))
+        }
+        result => panic!("reordered members not rejected: {:?}", result),
+    }
+
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_batch(vec![proof], vec![pub_inputs])[0].is_ok());
+}
+
+#[test]
+fn transaction_test_memos() {
+    use super::air::{decode_memo, encode_memo};
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{AccountState, Memo, Transaction, TransactionBatchBuilder, TransactionKind};
+    use crate::utils::rescue::Rescue63;
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let mut invoice = [0u8; 32];
+    invoice[..12].copy_from_slice(b"invoice-4217");
+    invoice[31] = 0xff;
+    assert_eq!(decode_memo(&encode_memo(&invoice)), invoice);
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+
+    let mut builder = TransactionBatchBuilder::new(&mut state, 2);
+    let mut transaction = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::from(10u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: Some(invoice),
+    };
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+
+    // The memo is signed along with the transaction
+    let mut tampered = transaction;
+    tampered.memo = Some([0x01; 32]);
+    assert!(builder.add_transaction(tampered).is_err());
+    assert!(builder.add_transaction(transaction).is_ok());
+    let mut transaction = Transaction {
+        sender: 1,
+        receiver: 0,
+        amount: BaseElement::from(5u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[1]);
+    assert!(builder.add_transaction(transaction).is_ok());
+
+    let tx_metadata = builder.build().unwrap();
+    assert_eq!(tx_metadata.memos(), vec![Memo::new(0, invoice)]);
+
+    let transaction = super::TransactionExample {
+        options: build_options(1),
+        tx_metadata,
+    };
+    let proof = transaction.prove().unwrap();
+    let pub_inputs = transaction.tx_metadata.public_inputs();
+    assert_eq!(pub_inputs.memos, vec![Memo::new(0, invoice)]);
+    assert!(transaction.verify(proof.clone()).is_ok());
+
+    // The proof binds the memo of every transaction, including transactions without memo
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.memos[0].data[0] ^= 1;
+    assert_ne!(wrong_pub_inputs.memos_digest(), pub_inputs.memos_digest());
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+    let wrong_pub_inputs = pub_inputs.clone().with_memos(vec![Memo::new(1, invoice)]);
+    assert!(super::verify_with_hasher::<Rescue63>(proof, wrong_pub_inputs).is_err());
+}
+
+#[test]
+fn transaction_test_subnet_id() {
+    use super::aggregation::{AggregatedProof, AggregationError};
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        AccountState, MetadataError, Transaction, TransactionBatchBuilder, TransactionKind,
+        TransactionProver,
+    };
+    use crate::utils::rescue::Rescue63;
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let subnet_id = BaseElement::from(7u8);
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+    let initial_root = state.root();
+
+    let mut transaction = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::from(10u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    let builder = TransactionBatchBuilder::new(&mut state, 2);
+    let replayed_signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+
+    // A signature produced for the default subnet cannot be replayed on another subnet
+    let mut builder = builder.with_subnet_id(subnet_id);
+    transaction.signature = replayed_signature;
+    assert!(builder.add_transaction(transaction).is_err());
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    assert!(builder.add_transaction(transaction).is_ok());
+
+    let tx_metadata = builder.build().unwrap();
+    assert_eq!(tx_metadata.subnet_id(), subnet_id);
+    assert_eq!(
+        tx_metadata
+            .clone()
+            .with_subnet_id(BaseElement::ZERO)
+            .validate(),
+        Err(MetadataError::InvalidSignature(0))
+    );
+
+    let prover = TransactionProver::new(build_options(1));
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert_eq!(pub_inputs.subnet_id, subnet_id);
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof is bound to the subnet of its transactions
+    let wrong_pub_inputs = pub_inputs.clone().with_subnet_id(BaseElement::ZERO);
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+
+    let mut aggregated_proof = AggregatedProof::new(initial_root, 4, 2);
+    assert_eq!(
+        aggregated_proof.push(proof.clone(), &pub_inputs),
+        Err(AggregationError::SubnetMismatch(0))
+    );
+    let mut aggregated_proof = aggregated_proof.with_subnet_id(subnet_id);
+    assert!(aggregated_proof.push(proof, &pub_inputs).is_ok());
+    assert!(aggregated_proof
+        .verify(initial_root, tx_metadata.final_root)
+        .is_ok());
+}
+
+#[test]
+fn transaction_test_epoch() {
+    use super::aggregation::{AggregatedProof, AggregationError};
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        AccountState, MetadataError, Transaction, TransactionBatchBuilder, TransactionKind,
+        TransactionProver,
+    };
+    use crate::utils::rescue::Rescue63;
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let mut secret_keys = Vec::new();
+    let mut state = StateTree::new(4);
+    for index in 0..3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let pkey = skey.public_key().to_point();
+        secret_keys.push(skey);
+        state.set_account(
+            index,
+            AccountState::new(pkey, BaseElement::from(100u8), BaseElement::ZERO),
+        );
+    }
+    let initial_root = state.root();
+    let prover = TransactionProver::new(build_options(1));
+
+    // Builds a batch of a single transfer from `sender`, signed for `epoch`
+    let mut prove_transfer = |sender: usize, epoch: u32| {
+        let mut builder = TransactionBatchBuilder::new(&mut state, 2).with_epoch(epoch);
+        let mut transaction = Transaction {
+            sender,
+            receiver: 1 - sender,
+            amount: BaseElement::from(10u8),
+            fee: BaseElement::ONE,
+            nonce: BaseElement::ZERO,
+            kind: TransactionKind::Transfer,
+            signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+            memo: None,
+        };
+        transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[sender]);
+        builder.add_transaction(transaction).unwrap();
+        let tx_metadata = builder.build().unwrap();
+        let proof = prover.prove(&tx_metadata).unwrap();
+        (tx_metadata, proof)
+    };
+
+    let (tx_metadata, proof) = prove_transfer(0, 5);
+    assert_eq!(tx_metadata.epoch(), 5);
+    assert_eq!(
+        tx_metadata.clone().with_epoch(6).validate(),
+        Err(MetadataError::InvalidSignature(0))
+    );
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert_eq!(pub_inputs.epoch, 5);
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), pub_inputs.clone()).is_ok());
+
+    // The proof is bound to the epoch signed by its transactions
+    let wrong_pub_inputs = pub_inputs.clone().with_epoch(6);
+    assert!(super::verify_with_hasher::<Rescue63>(proof.clone(), wrong_pub_inputs).is_err());
+
+    // Chained proofs cannot go back in time
+    let mut aggregated_proof = AggregatedProof::new(initial_root, 4, 2);
+    assert!(aggregated_proof.push(proof, &pub_inputs).is_ok());
+    let (tx_metadata, proof) = prove_transfer(1, 4);
+    assert_eq!(
+        aggregated_proof.push(proof, &prover.public_inputs(&tx_metadata)),
+        Err(AggregationError::EpochRegression(1))
+    );
+}
+
+#[test]
+fn transaction_test_batch_verification() {
+    let transactions = [
+        super::TransactionExample::new(build_options(1), 2),
+        super::TransactionExample::new(build_options(1), 2),
+    ];
+    let proofs: Vec<_> = transactions.iter().map(|tx| tx.prove().unwrap()).collect();
+    let mut pub_inputs: Vec<_> = transactions
+        .iter()
+        .map(|tx| {
+            super::PublicInputs::new(
+                tx.tx_metadata.initial_roots[0],
+                tx.tx_metadata.final_root,
+                tx.tx_metadata.tree_depth(),
+                tx.tx_metadata.fee_collector(),
+                Vec::new(),
+                Vec::new(),
+            )
+        })
+        .collect();
+    assert!(super::verify_batch(proofs.clone(), pub_inputs.clone())
+        .iter()
+        .all(|result| result.is_ok()));
+
+    pub_inputs[1].final_root = pub_inputs[1].initial_root;
+    let results = super::verify_batch(proofs, pub_inputs);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn transaction_test_aggregated_proof() {
+    use super::{AggregatedProof, AggregationError};
+
+    let transaction = super::TransactionExample::new(build_options(1), 2);
+    let other_transaction = super::TransactionExample::new(build_options(1), 2);
+    let initial_root = transaction.tx_metadata.initial_roots[0];
+    let final_root = transaction.tx_metadata.final_root;
+    let tree_depth = transaction.tx_metadata.tree_depth();
+    let fee_collector = transaction.tx_metadata.fee_collector();
+
+    let mut aggregated_proof = AggregatedProof::new(initial_root, tree_depth, fee_collector);
+    aggregated_proof
+        .push(
+            transaction.prove().unwrap(),
+            &super::PublicInputs::new(
+                initial_root,
+                final_root,
+                tree_depth,
+                fee_collector,
+                Vec::new(),
+                Vec::new(),
+            ),
+        )
+        .unwrap();
+
+    // The other transaction batch does not start from the current final root
+    let other_pub_inputs = super::PublicInputs::new(
+        other_transaction.tx_metadata.initial_roots[0],
+        other_transaction.tx_metadata.final_root,
+        tree_depth,
+        fee_collector,
+        Vec::new(),
+        Vec::new(),
+    );
+    assert_eq!(
+        aggregated_proof.push(other_transaction.prove().unwrap(), &other_pub_inputs),
+        Err(AggregationError::RootMismatch(1))
+    );
+
+    assert_eq!(aggregated_proof.num_proofs(), 1);
+    assert!(aggregated_proof.verify(initial_root, final_root).is_ok());
+    assert_eq!(
+        aggregated_proof.verify(final_root, initial_root),
+        Err(AggregationError::UnexpectedRoots)
+    );
+}
+
+#[test]
+fn transaction_test_proof_chain() {
+    use super::{AggregationError, TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(5, 4);
+    let sub_batches = tx_metadata.sub_batches(2);
+    assert_eq!(sub_batches.len(), 3);
+    assert_eq!(
+        sub_batches[1].initial_roots[0],
+        tx_metadata.initial_roots[2]
+    );
+    assert_eq!(sub_batches[2].num_transactions(), 1);
+    assert_eq!(sub_batches[2].final_root, tx_metadata.final_root);
+    assert!(sub_batches
+        .iter()
+        .all(|sub_batch| sub_batch.validate().is_ok()));
+
+    let prover = TransactionProver::new(build_options(1));
+    let proof_chain = prover.prove_chain(&tx_metadata, 2).unwrap();
+    assert_eq!(proof_chain.num_proofs(), 3);
+    let (initial_root, final_root) = (tx_metadata.initial_roots[0], tx_metadata.final_root);
+    assert!(proof_chain.verify(initial_root, final_root).is_ok());
+    assert_eq!(
+        proof_chain.verify(initial_root, sub_batches[1].final_root),
+        Err(AggregationError::UnexpectedRoots)
+    );
+}
+
 fn build_options(extension: u8) -> ProofOptions {
     ProofOptions::new(
         42,
         8,
         0,
-        HashFunction::Blake3_256,
+        super::options::DEFAULT_HASH_FN,
         match extension {
             2 => FieldExtension::Quadratic,
             3 => FieldExtension::Cubic,
@@ -52,3 +2414,1203 @@ fn build_options(extension: u8) -> ProofOptions {
         256,
     )
 }
+
+#[test]
+fn proof_options_profiles() {
+    use super::{ProofOptionsBuilder, ProofProfile};
+
+    assert_eq!(ProofProfile::Balanced.to_proof_options(), build_options(1));
+    assert_eq!(ProofOptionsBuilder::new().build(), build_options(1));
+
+    let options = ProofOptionsBuilder::from_profile(ProofProfile::Fast)
+        .blowup_factor(16)
+        .field_extension(FieldExtension::Cubic)
+        .build();
+    assert_eq!(options.blowup_factor(), 16);
+    assert_eq!(options.field_extension(), FieldExtension::Cubic);
+
+    let options = ProofProfile::HighSecurity.to_proof_options();
+    assert_eq!(options.field_extension(), FieldExtension::Quadratic);
+    assert_eq!(options.grinding_factor(), 16);
+}
+
+#[test]
+fn proof_options_validation() {
+    use super::{OptionsError, ProofOptionsBuilder};
+
+    let options = ProofOptionsBuilder::new()
+        .fri_folding_factor(16)
+        .fri_max_remainder_size(64)
+        .try_build()
+        .unwrap();
+    assert_eq!(options.to_fri_options().folding_factor(), 16);
+    assert_eq!(options.to_fri_options().max_remainder_size(), 64);
+
+    assert_eq!(
+        ProofOptionsBuilder::new().fri_folding_factor(2).try_build(),
+        Err(OptionsError::InvalidFriFoldingFactor(2))
+    );
+    assert_eq!(
+        ProofOptionsBuilder::new()
+            .fri_folding_factor(12)
+            .try_build(),
+        Err(OptionsError::InvalidFriFoldingFactor(12))
+    );
+    assert_eq!(
+        ProofOptionsBuilder::new()
+            .fri_max_remainder_size(16)
+            .try_build(),
+        Err(OptionsError::InvalidFriMaxRemainderSize(16))
+    );
+    assert_eq!(
+        ProofOptionsBuilder::new()
+            .fri_max_remainder_size(300)
+            .try_build(),
+        Err(OptionsError::InvalidFriMaxRemainderSize(300))
+    );
+    assert_eq!(
+        ProofOptionsBuilder::new().num_queries(0).try_build(),
+        Err(OptionsError::InvalidNumQueries(0))
+    );
+    assert_eq!(
+        ProofOptionsBuilder::new().blowup_factor(12).try_build(),
+        Err(OptionsError::InvalidBlowupFactor(12))
+    );
+    assert_eq!(
+        ProofOptionsBuilder::new().grinding_factor(40).try_build(),
+        Err(OptionsError::InvalidGrindingFactor(40))
+    );
+}
+
+#[test]
+fn transaction_metadata_serialization() {
+    use super::{TransactionKind, TransactionMetadata};
+    use winterfell::{math::fields::f63::BaseElement, Serializable};
+
+    let kinds = [
+        TransactionKind::Transfer,
+        TransactionKind::Withdrawal {
+            recipient: [0x42; 20],
+        },
+        TransactionKind::Deposit,
+        TransactionKind::Transfer,
+    ];
+    let tx_metadata = TransactionMetadata::build_random_with_kinds(&kinds, 4)
+        .with_subnet_id(BaseElement::from(42u8))
+        .with_epoch(17);
+    let bytes = tx_metadata.to_bytes();
+    let deserialized = TransactionMetadata::from_bytes(&bytes).unwrap();
+    assert_eq!(bytes, deserialized.to_bytes());
+    assert_eq!(deserialized.subnet_id(), tx_metadata.subnet_id());
+    assert_eq!(deserialized.epoch(), tx_metadata.epoch());
+
+    assert!(TransactionMetadata::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn transaction_metadata_truncated_huge_count() {
+    use super::utils::rescue::Hash;
+    use super::TransactionMetadata;
+    use winterfell::{
+        math::{fields::f63::BaseElement, FieldElement},
+        DeserializationError, Serializable,
+    };
+
+    let mut bytes = u32::MAX.to_le_bytes().to_vec();
+    bytes.push(4);
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    Hash::default().write_into(&mut bytes);
+    BaseElement::ZERO.write_into(&mut bytes);
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+
+    assert!(matches!(
+        TransactionMetadata::from_bytes(&bytes),
+        Err(DeserializationError::UnexpectedEOF)
+    ));
+}
+
+#[test]
+fn state_tree_snapshots() {
+    use super::constants::schnorr_const::AFFINE_POINT_WIDTH;
+    use super::merkle::StateTree;
+    use super::AccountState;
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let account = |balance: u64| {
+        let mut elements = [BaseElement::ONE; AFFINE_POINT_WIDTH + 2];
+        elements[AFFINE_POINT_WIDTH] = BaseElement::new(balance);
+        AccountState::from_elements(&elements)
+    };
+    let mut state = StateTree::new(4);
+    state.set_account(0, account(10));
+    state.set_account(1, account(20));
+
+    let snapshot = state.snapshot();
+    assert_eq!(snapshot.root(), state.root());
+    assert!(state.diff(&snapshot).changes.is_empty());
+
+    state.set_account(0, account(5));
+    state.set_account(0, account(7));
+    state.set_account(2, account(30));
+    let inner_snapshot = state.snapshot();
+    state.set_account(1, account(25));
+    state.set_account(1, account(20));
+
+    // accounts changed back to their state at the snapshot are omitted
+    let diff = state.diff(&snapshot);
+    assert_eq!(diff.previous_root, snapshot.root());
+    assert_eq!(diff.root, state.root());
+    assert_eq!(diff.changes.len(), 2);
+    assert_eq!(diff.changes[&0], (Some(account(10)), account(7)));
+    assert_eq!(diff.changes[&2], (None, account(30)));
+    assert!(state.diff(&inner_snapshot).changes.is_empty());
+
+    // rolling back restores the root, and removes the accounts created since
+    state.rollback(&snapshot);
+    assert_eq!(state.root(), snapshot.root());
+    assert_eq!(state.get_account(0), Some(&account(10)));
+    assert_eq!(state.get_account(2), None);
+    assert!(state.diff(&snapshot).changes.is_empty());
+
+    // the snapshot remains valid, while the inner one is invalidated
+    state.set_account(3, account(40));
+    state.set_account(3, account(41));
+    assert_eq!(state.diff(&snapshot).changes.len(), 1);
+    let result = std::panic::catch_unwind(|| state.diff(&inner_snapshot));
+    assert!(result.is_err());
+
+    let mut other_state = StateTree::new(4);
+    other_state.set_account(0, account(10));
+    other_state.set_account(1, account(20));
+    other_state.set_account(3, account(41));
+    assert_eq!(state.root(), other_state.root());
+
+    state.release_snapshots();
+    let result = std::panic::catch_unwind(|| state.diff(&snapshot));
+    assert!(result.is_err());
+}
+
+#[test]
+fn batch_replay() {
+    use super::constants::schnorr_const::{AFFINE_POINT_WIDTH, POINT_COORDINATE_WIDTH};
+    use super::merkle::StateTree;
+    use super::replay::{apply_batch, check_public_inputs, ReplayError};
+    use super::{AccountState, PublicInputs, Transaction, TransactionError, TransactionKind};
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let account = |balance: u64| {
+        let mut elements = [BaseElement::ONE; AFFINE_POINT_WIDTH + 2];
+        elements[AFFINE_POINT_WIDTH] = BaseElement::new(balance);
+        AccountState::from_elements(&elements)
+    };
+    let transaction = |sender, receiver, amount: u64| Transaction {
+        sender,
+        receiver,
+        amount: BaseElement::new(amount),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; POINT_COORDINATE_WIDTH], Scalar::zero()),
+        memo: None,
+    };
+    let mut state = StateTree::new(4);
+    state.set_account(0, account(100));
+    state.set_account(1, account(50));
+    state.set_account(2, account(0));
+    let transactions = [transaction(0, 1, 10), transaction(1, 0, 20)];
+
+    let (final_root, intermediate_roots) = apply_batch(&state, 2, &transactions).unwrap();
+    let mut expected_state = state.clone();
+    expected_state.apply_transaction(&transactions[0], 2);
+    assert_eq!(intermediate_roots, vec![expected_state.root()]);
+    expected_state.apply_transaction(&transactions[1], 2);
+    assert_eq!(final_root, expected_state.root());
+    assert_eq!(expected_state.get_account(2), Some(&account(2)));
+    // the replayed tree is left untouched
+    assert_ne!(state.root(), final_root);
+
+    assert_eq!(
+        apply_batch(&state, 2, &[transactions[0], transaction(0, 3, 10)]),
+        Err(TransactionError::UnknownAccount(1))
+    );
+    assert_eq!(
+        apply_batch(&state, 16, &transactions),
+        Err(TransactionError::InvalidIndex(0))
+    );
+    assert_eq!(
+        apply_batch(&state, 2, &[transactions[0], transaction(2, 1, 0)]),
+        Err(TransactionError::FeeCollectorAccount(1))
+    );
+    assert_eq!(
+        apply_batch(&state, 2, &[transaction(0, 2, 10)]),
+        Err(TransactionError::FeeCollectorAccount(0))
+    );
+
+    let mut pub_inputs = PublicInputs::new(state.root(), final_root, 4, 2, vec![], vec![]);
+    assert_eq!(
+        check_public_inputs(&pub_inputs, &state, &transactions),
+        Ok(())
+    );
+    pub_inputs.intermediate_roots = Some(vec![intermediate_roots[0].to_elements()]);
+    assert_eq!(
+        check_public_inputs(&pub_inputs, &state, &transactions),
+        Ok(())
+    );
+    pub_inputs.intermediate_roots = Some(vec![final_root.to_elements()]);
+    assert_eq!(
+        check_public_inputs(&pub_inputs, &state, &transactions),
+        Err(ReplayError::IntermediateRootsMismatch)
+    );
+    pub_inputs.intermediate_roots = None;
+    assert_eq!(
+        check_public_inputs(&pub_inputs, &state, &transactions[..1]),
+        Err(ReplayError::FinalRootMismatch)
+    );
+    pub_inputs.fee_collector = 1;
+    assert_eq!(
+        check_public_inputs(&pub_inputs, &state, &transactions),
+        Err(ReplayError::Transaction(
+            TransactionError::FeeCollectorAccount(0)
+        ))
+    );
+    pub_inputs.fee_collector = 3;
+    assert_eq!(
+        check_public_inputs(&pub_inputs, &state, &transactions),
+        Err(ReplayError::Transaction(TransactionError::UnknownAccount(
+            0
+        )))
+    );
+}
+
+#[test]
+fn derived_account_indices() {
+    use super::account_index;
+    use super::constants::schnorr_const::AFFINE_POINT_WIDTH;
+    use super::merkle::StateTree;
+    use super::AccountState;
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let account = |seed: u64, balance: u64| {
+        let mut elements = [BaseElement::new(seed); AFFINE_POINT_WIDTH + 2];
+        elements[AFFINE_POINT_WIDTH] = BaseElement::new(balance);
+        elements[AFFINE_POINT_WIDTH + 1] = BaseElement::ZERO;
+        AccountState::from_elements(&elements)
+    };
+
+    // indices are the low bits of a single hash of the key
+    let key = account(1, 0).pub_key;
+    let index = account_index(&key, 16);
+    assert!(index < 1 << 16);
+    assert_eq!(account_index(&key, 4), index & 0b1111);
+    assert_eq!(account_index(&key, 0), 0);
+    assert_ne!(account_index(&account(2, 0).pub_key, 16), index);
+
+    let mut state = StateTree::new(4);
+    assert_eq!(state.insert_account(account(1, 10)), Some(index & 0b1111));
+    assert_eq!(state.get_account(index & 0b1111), Some(&account(1, 10)));
+    // the account of the same key can be updated in place
+    assert_eq!(state.insert_account(account(1, 20)), Some(index & 0b1111));
+    assert_eq!(state.get_account(index & 0b1111), Some(&account(1, 20)));
+
+    // another key deriving the same leaf is rejected
+    let colliding = (2..)
+        .map(|seed| account(seed, 30))
+        .find(|value| value.derived_index(4) == index & 0b1111)
+        .unwrap();
+    let root = state.root();
+    assert_eq!(state.insert_account(colliding), None);
+    assert_eq!(state.root(), root);
+}
+
+#[test]
+fn transaction_test_derived_indices() {
+    use super::diagnostics::constraint_names;
+    use super::merkle::StateTree;
+    use super::schnorr::{keys::SecretKey, sign};
+    use super::{
+        account_index, AccountState, Error, MetadataError, Transaction, TransactionBatchBuilder,
+        TransactionError, TransactionKind, TransactionProver,
+    };
+    use rand_core::OsRng;
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    // three accounts are stored at the leaves derived from their keys, and a fourth one
+    // elsewhere
+    let mut secret_keys = Vec::new();
+    let mut leaves = Vec::new();
+    let mut state = StateTree::new(8);
+    while leaves.len() < 3 {
+        let skey = SecretKey::random(&mut OsRng);
+        let account = AccountState::new(
+            skey.public_key().to_point(),
+            BaseElement::from(100u8),
+            BaseElement::ZERO,
+        );
+        if let Some(leaf) = state.insert_account(account) {
+            if !leaves.contains(&leaf) {
+                secret_keys.push(skey);
+                leaves.push(leaf);
+            }
+        }
+    }
+    let underived = (0..1 << 8)
+        .find(|leaf| state.get_account(*leaf).is_none())
+        .unwrap();
+    let stray_key = SecretKey::random(&mut OsRng);
+    let stray_pkey = stray_key.public_key().to_point();
+    assert_ne!(account_index(&stray_pkey, 8), underived);
+    state.set_account(
+        underived,
+        AccountState::new(stray_pkey, BaseElement::from(100u8), BaseElement::ZERO),
+    );
+
+    let transfer = |sender: usize, receiver: usize| Transaction {
+        sender: leaves[sender],
+        receiver,
+        amount: BaseElement::from(10u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::ZERO,
+        kind: TransactionKind::Transfer,
+        signature: ([BaseElement::ZERO; 6], Scalar::zero()),
+        memo: None,
+    };
+    let mut rejected_state = state.clone();
+    let mut builder =
+        TransactionBatchBuilder::new(&mut rejected_state, leaves[2]).with_derived_indices();
+
+    // transactions involving the account stored elsewhere are rejected, as are rekeyings to
+    // a key deriving another leaf
+    let mut transaction = transfer(0, underived);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    assert_eq!(
+        builder.add_transaction(transaction),
+        Err(TransactionError::UnderivedIndex(0))
+    );
+    let new_key = (0..)
+        .map(|_| SecretKey::random(&mut OsRng).public_key().to_point())
+        .find(|key| account_index(key, 8) != leaves[0])
+        .unwrap();
+    let mut rekey = transfer(0, leaves[0]);
+    rekey.amount = BaseElement::ZERO;
+    rekey.fee = BaseElement::ZERO;
+    rekey.kind = TransactionKind::Rekey { new_key };
+    rekey.signature = sign(builder.message(&rekey).unwrap(), &secret_keys[0]);
+    assert_eq!(
+        builder.add_transaction(rekey),
+        Err(TransactionError::UnderivedIndex(0))
+    );
+
+    // transfers between accounts at their derived leaves are bound to their keys
+    let mut transaction = transfer(0, leaves[1]);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    assert!(builder.add_transaction(transaction).is_ok());
+    let mut transaction = transfer(1, leaves[0]);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[1]);
+    assert!(builder.add_transaction(transaction).is_ok());
+    let tx_metadata = builder.build().unwrap();
+
+    let prover = TransactionProver::new(build_options(1)).with_derived_indices();
+    assert!(prover.public_inputs(&tx_metadata).derived_indices);
+    let trace = prover.build_trace(&tx_metadata);
+    assert!(prover.check_constraints(&tx_metadata, &trace).is_ok());
+
+    // a batch sending to the account stored elsewhere is refused by the prover, and its
+    // execution trace does not satisfy the constraints binding the receiver's index
+    let mut builder = TransactionBatchBuilder::new(&mut state, leaves[2]);
+    let mut transaction = transfer(0, underived);
+    transaction.signature = sign(builder.message(&transaction).unwrap(), &secret_keys[0]);
+    assert!(builder.add_transaction(transaction).is_ok());
+    let tx_metadata = builder.build().unwrap();
+    assert_eq!(
+        prover.prove(&tx_metadata).unwrap_err(),
+        Error::InvalidMetadata(MetadataError::UnderivedIndex(0))
+    );
+    let trace = prover.build_trace(&tx_metadata);
+    match prover.check_constraints(&tx_metadata, &trace) {
+        Err(Error::UnsatisfiedConstraint(failure)) => assert_eq!(
+            failure.names,
+            constraint_names(super::constants::RECEIVER_KEY_INDEX_RES + 3)
+        ),
+        result => panic!("underived receiver not rejected: {:?}", result),
+    }
+}
+
+#[test]
+fn transaction_metadata_shared_paths() {
+    use super::merkle::sparse::SparseMerkleTree;
+    use super::shared_path_length;
+    use crate::utils::rescue::Rescue63;
+    use winterfell::math::fields::f63::BaseElement;
+
+    // the leaves of the sender and receiver share their ancestors from height 4 onwards
+    let (sender, receiver) = (0b0010_1101, 0b0010_0110);
+    let mut tree = SparseMerkleTree::<Rescue63>::new(8);
+    for (i, &index) in [sender, receiver, 0b1100_0000].iter().enumerate() {
+        tree.update_leaf(index, Rescue63::digest(&[BaseElement::new(i as u64)]));
+    }
+
+    // the receiver's path is taken once the sender's leaf is updated, as when proving
+    let s_path = tree.prove(sender);
+    tree.update_leaf(sender, Rescue63::digest(&[BaseElement::new(42)]));
+    let r_path = tree.prove(receiver);
+    assert_eq!(shared_path_length(&s_path, &r_path), 4);
+    assert_eq!(s_path[5..], r_path[5..]);
+
+    assert_eq!(shared_path_length(&s_path, &s_path), 8);
+    assert_eq!(shared_path_length(&s_path, &tree.prove(0b1100_0000)), 0);
+}
+
+#[test]
+fn transaction_test_shared_path_flag() {
+    use super::constants::{SHARED_PATH_FLAG_POS, SHARED_PATH_RES, TRANSACTION_CYCLE_LENGTH};
+    use super::utils::rescue::HASH_CYCLE_LENGTH;
+    use super::{Error, TransactionMetadata, TransactionProver};
+    use winterfell::{
+        math::{fields::f63::BaseElement, FieldElement},
+        Trace,
+    };
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(4, 8);
+    let prover = TransactionProver::new(build_options(1));
+    let mut trace = prover.build_trace(&tx_metadata);
+
+    // the flag is set from the level hashing both children of the lowest common ancestor of
+    // the sender's and receiver's leaves
+    let (tx_index, shared_level) = (0..4)
+        .map(|i| {
+            let indices = tx_metadata.s_indices[i] ^ tx_metadata.r_indices[i];
+            (i, (usize::BITS - indices.leading_zeros()) as usize)
+        })
+        .find(|&(_, level)| level >= 2)
+        .unwrap();
+    let shared_step = tx_index * TRANSACTION_CYCLE_LENGTH + shared_level * HASH_CYCLE_LENGTH;
+    assert_eq!(
+        trace.get(SHARED_PATH_FLAG_POS, shared_step - 1),
+        BaseElement::ZERO
+    );
+    assert_eq!(
+        trace.get(SHARED_PATH_FLAG_POS, shared_step),
+        BaseElement::ONE
+    );
+
+    // setting it one level lower ties the hash states of distinct nodes
+    for step in shared_step - HASH_CYCLE_LENGTH..shared_step {
+        trace.set(SHARED_PATH_FLAG_POS, step, BaseElement::ONE);
+    }
+    match prover.check_constraints(&tx_metadata, &trace) {
+        Err(Error::UnsatisfiedConstraint(failure)) => {
+            assert!(failure.constraint > SHARED_PATH_RES, "{}", failure);
+            assert_eq!(failure.step, shared_step - HASH_CYCLE_LENGTH - 1);
+        }
+        result => panic!("shared path flag not rejected: {:?}", result),
+    }
+}
+
+#[test]
+fn public_inputs_serialization() {
+    use super::{BatchStatistics, Deposit, Memo, PublicInputs, Withdrawal};
+    use crate::utils::rescue::Rescue63;
+    use winterfell::math::fields::f63::BaseElement;
+
+    let roots: Vec<_> = (0..4u64)
+        .map(|i| Rescue63::digest(&[BaseElement::new(i)]))
+        .collect();
+    let pub_inputs = PublicInputs::new(
+        roots[0],
+        roots[3],
+        4,
+        3,
+        vec![Withdrawal::new(0, 12, [0x42; 20])],
+        vec![Deposit::new(2, 7, 9)],
+    )
+    .with_memos(vec![Memo::new(1, [0x17; 32])])
+    .with_range_log(32)
+    .with_subnet_id(BaseElement::new(42))
+    .with_epoch(1_700_000_000);
+    let bytes = pub_inputs.to_bytes();
+    assert_eq!(PublicInputs::from_bytes(&bytes).unwrap(), pub_inputs);
+    assert!(PublicInputs::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    let pub_inputs = pub_inputs
+        .with_intermediate_roots(&roots[1..3])
+        .with_transactions_digest(roots[2])
+        .with_nullifiers(&roots[..2])
+        .with_batch_statistics(BatchStatistics::new(3, 1_000))
+        .with_derived_indices()
+        .with_signed_deltas();
+    let bytes = pub_inputs.to_bytes();
+    assert_eq!(PublicInputs::from_bytes(&bytes).unwrap(), pub_inputs);
+
+    // signed deltas cannot be range-checked over more than 61 bits
+    let wide_bytes = pub_inputs.clone().with_range_log(64).to_bytes();
+    assert!(PublicInputs::from_bytes(&wide_bytes).is_err());
+
+    let hex_string = pub_inputs.to_hex();
+    assert_eq!(hex_string, hex::encode(&bytes));
+    assert_eq!(PublicInputs::from_hex(&hex_string).unwrap(), pub_inputs);
+    assert_eq!(
+        PublicInputs::from_hex(&hex_string.to_uppercase()).unwrap(),
+        pub_inputs
+    );
+    assert!(PublicInputs::from_hex(&hex_string[1..]).is_err());
+    assert!(PublicInputs::from_hex(&hex_string[..hex_string.len() - 2]).is_err());
+}
+
+#[test]
+fn public_inputs_transaction_count() {
+    use super::air::check_num_transactions;
+    use super::{BatchStatistics, Deposit, Memo, PublicInputs, Withdrawal};
+    use crate::utils::rescue::Rescue63;
+    use winterfell::{math::fields::f63::BaseElement, VerifierError};
+
+    let pub_inputs = PublicInputs::new(
+        Rescue63::digest(&[BaseElement::new(0)]),
+        Rescue63::digest(&[BaseElement::new(1)]),
+        4,
+        3,
+        vec![Withdrawal::new(1, 12, [0x42; 20])],
+        vec![],
+    );
+    assert_eq!(check_num_transactions(&pub_inputs, 2), Ok(()));
+    // the trace of a proof may hold fewer transactions than referred to, or none at all
+    assert!(matches!(
+        check_num_transactions(&pub_inputs, 1),
+        Err(VerifierError::ProofDeserializationError(_))
+    ));
+    assert!(matches!(
+        check_num_transactions(
+            &PublicInputs {
+                withdrawals: vec![],
+                ..pub_inputs.clone()
+            },
+            0
+        ),
+        Err(VerifierError::ProofDeserializationError(_))
+    ));
+
+    let unsorted_inputs = PublicInputs {
+        withdrawals: vec![
+            Withdrawal::new(1, 12, [0x42; 20]),
+            Withdrawal::new(1, 5, [0x17; 20]),
+        ],
+        ..pub_inputs.clone()
+    };
+    assert!(check_num_transactions(&unsorted_inputs, 4).is_err());
+
+    let deposit_inputs = |deposits| PublicInputs {
+        deposits,
+        ..pub_inputs.clone()
+    };
+    assert_eq!(
+        check_num_transactions(&deposit_inputs(vec![Deposit::new(0, 7, 9)]), 2),
+        Ok(())
+    );
+    assert!(check_num_transactions(&deposit_inputs(vec![Deposit::new(2, 7, 9)]), 2).is_err());
+    assert!(check_num_transactions(&deposit_inputs(vec![Deposit::new(0, 7, 16)]), 2).is_err());
+    // the second transaction is already a withdrawal
+    assert!(check_num_transactions(&deposit_inputs(vec![Deposit::new(1, 7, 9)]), 2).is_err());
+
+    let memo_inputs = |memos| pub_inputs.clone().with_memos(memos);
+    assert_eq!(
+        check_num_transactions(&memo_inputs(vec![Memo::new(1, [0x17; 32])]), 2),
+        Ok(())
+    );
+    assert!(check_num_transactions(&memo_inputs(vec![Memo::new(2, [0x17; 32])]), 2).is_err());
+    assert!(check_num_transactions(
+        &memo_inputs(vec![Memo::new(1, [0x17; 32]), Memo::new(0, [0x42; 32])]),
+        2
+    )
+    .is_err());
+
+    let statistics_inputs = pub_inputs
+        .clone()
+        .with_batch_statistics(BatchStatistics::new(2, 1_000));
+    assert_eq!(check_num_transactions(&statistics_inputs, 2), Ok(()));
+    assert!(check_num_transactions(&statistics_inputs, 1).is_err());
+    let statistics_inputs =
+        statistics_inputs.with_batch_statistics(BatchStatistics::new(2, u64::MAX));
+    assert!(check_num_transactions(&statistics_inputs, 2).is_err());
+
+    let roots_inputs =
+        pub_inputs.with_intermediate_roots(&[Rescue63::digest(&[BaseElement::new(2)])]);
+    assert_eq!(check_num_transactions(&roots_inputs, 2), Ok(()));
+    assert!(check_num_transactions(&roots_inputs, 4).is_err());
+}
+
+#[test]
+fn public_inputs_truncated_huge_counts() {
+    use super::PublicInputs;
+    use crate::utils::rescue::Rescue63;
+    use winterfell::{math::fields::f63::BaseElement, DeserializationError};
+
+    let pub_inputs = PublicInputs::new(
+        Rescue63::digest(&[BaseElement::new(0)]),
+        Rescue63::digest(&[BaseElement::new(1)]),
+        4,
+        3,
+        vec![],
+        vec![],
+    );
+    let bytes = pub_inputs.to_bytes();
+    // empty withdrawals, deposits and memos, followed by four absent optional fields
+    assert_eq!(bytes[bytes.len() - 16..], [0; 16]);
+    let prefix = &bytes[..bytes.len() - 16];
+
+    let huge_count = u32::MAX.to_le_bytes();
+    let empty_count = 0u32.to_le_bytes();
+    let cases: [&[&[u8]]; 4] = [
+        &[&huge_count],
+        &[&empty_count, &huge_count],
+        &[&empty_count, &empty_count, &huge_count],
+        &[&empty_count, &empty_count, &empty_count, &[1], &huge_count],
+    ];
+    for case in cases.iter() {
+        let mut truncated_bytes = prefix.to_vec();
+        for part in case.iter() {
+            truncated_bytes.extend_from_slice(part);
+        }
+        assert!(matches!(
+            PublicInputs::from_bytes(&truncated_bytes),
+            Err(DeserializationError::UnexpectedEOF)
+        ));
+    }
+}
+
+#[test]
+fn public_inputs_evm_encoding() {
+    use super::encoding::evm::{
+        decode_public_inputs, encode_public_inputs, PUBLIC_INPUTS_HEAD_LENGTH, WORD_SIZE,
+    };
+    use super::{BatchStatistics, Deposit, Memo, PublicInputs, Withdrawal};
+    use crate::utils::rescue::Rescue63;
+    use winterfell::math::fields::f63::BaseElement;
+
+    let roots: Vec<_> = (0..4u64)
+        .map(|i| Rescue63::digest(&[BaseElement::new(i)]))
+        .collect();
+    let pub_inputs = PublicInputs::new(
+        roots[0],
+        roots[3],
+        4,
+        3,
+        vec![Withdrawal::new(0, 12, [0x42; 20])],
+        vec![Deposit::new(2, 7, 9)],
+    )
+    .with_memos(vec![Memo::new(1, [0x17; 32])]);
+    let bytes = encode_public_inputs(&pub_inputs);
+    assert_eq!(
+        bytes.len(),
+        (PUBLIC_INPUTS_HEAD_LENGTH + 4 + 3 + 3 + 2) * WORD_SIZE
+    );
+    assert_eq!(decode_public_inputs(&bytes).unwrap(), pub_inputs);
+
+    // The tree depth and the recipient of the first withdrawal are right-aligned in their words
+    assert_eq!(
+        bytes[14 * WORD_SIZE..15 * WORD_SIZE - 1],
+        [0u8; WORD_SIZE - 1]
+    );
+    assert_eq!(bytes[15 * WORD_SIZE - 1], 4);
+    let recipient_end = (PUBLIC_INPUTS_HEAD_LENGTH + 4) * WORD_SIZE;
+    assert_eq!(bytes[recipient_end - 20..recipient_end], [0x42; 20]);
+
+    // Truncated, extended or non-canonical encodings are rejected
+    assert!(decode_public_inputs(&bytes[..bytes.len() - WORD_SIZE]).is_err());
+    let mut extended_bytes = bytes.clone();
+    extended_bytes.extend_from_slice(&[0u8; WORD_SIZE]);
+    assert!(decode_public_inputs(&extended_bytes).is_err());
+    let mut non_canonical_bytes = bytes;
+    non_canonical_bytes[WORD_SIZE - 8..WORD_SIZE].copy_from_slice(&u64::MAX.to_be_bytes());
+    assert!(decode_public_inputs(&non_canonical_bytes).is_err());
+
+    let pub_inputs = pub_inputs
+        .with_intermediate_roots(&roots[1..3])
+        .with_transactions_digest(roots[2])
+        .with_nullifiers(&roots[..2])
+        .with_batch_statistics(BatchStatistics::new(3, 200))
+        .with_subnet_id(BaseElement::new(42))
+        .with_epoch(17)
+        .with_derived_indices()
+        .with_range_log(32)
+        .with_signed_deltas();
+    let bytes = encode_public_inputs(&pub_inputs);
+    assert_eq!(decode_public_inputs(&bytes).unwrap(), pub_inputs);
+    assert_eq!(bytes[17 * WORD_SIZE - 1], 32);
+    assert_eq!(bytes[19 * WORD_SIZE - 1], 63);
+    assert_eq!(bytes[34 * WORD_SIZE - 1], 42);
+    assert_eq!(bytes[35 * WORD_SIZE - 1], 17);
+    assert_eq!(bytes[36 * WORD_SIZE - 1], 3);
+    assert_eq!(bytes[37 * WORD_SIZE - 1], 200);
+
+    // signed deltas cannot be range-checked over more than 61 bits
+    let wide_bytes = encode_public_inputs(&pub_inputs.with_range_log(64));
+    assert!(decode_public_inputs(&wide_bytes).is_err());
+}
+
+#[test]
+fn transaction_test_evm_proof_encoding() {
+    use super::encoding::evm::{decode_proof, encode_proof, WORD_SIZE};
+    use super::{TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let proof = prover.prove(&tx_metadata).unwrap();
+    let bytes = encode_proof(&proof);
+    assert_eq!(bytes.len() % WORD_SIZE, 0);
+    assert_eq!(
+        bytes[WORD_SIZE - 8..WORD_SIZE],
+        (proof.trace_length() as u64).to_be_bytes()
+    );
+
+    let decoded_proof = decode_proof(&bytes).unwrap();
+    assert_eq!(decoded_proof.to_bytes(), proof.to_bytes());
+    let pub_inputs = prover.public_inputs(&tx_metadata);
+    assert!(super::verify_batch(vec![decoded_proof], vec![pub_inputs])[0].is_ok());
+
+    // The head must match the options of the proof
+    let mut bytes = bytes;
+    bytes[2 * WORD_SIZE - 1] ^= 1;
+    assert!(decode_proof(&bytes).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn public_inputs_serde() {
+    use super::{BatchStatistics, Deposit, Memo, PublicInputs, Withdrawal};
+    use crate::utils::rescue::Rescue63;
+    use winterfell::math::{fields::f63::BaseElement, StarkField};
+
+    let roots: Vec<_> = (0..4u64)
+        .map(|i| Rescue63::digest(&[BaseElement::new(i)]))
+        .collect();
+    let pub_inputs = PublicInputs::new(
+        roots[0],
+        roots[3],
+        4,
+        3,
+        vec![Withdrawal::new(0, 12, [0x42; 20])],
+        vec![Deposit::new(2, 7, 9)],
+    )
+    .with_memos(vec![Memo::new(1, [0x17; 32])]);
+    let json = serde_json::to_value(&pub_inputs).unwrap();
+    assert_eq!(
+        json["initial_root"][0],
+        pub_inputs.initial_root[0].to_repr().to_string()
+    );
+    assert_eq!(json["withdrawals"][0]["recipient"], "42".repeat(20));
+    assert!(json["intermediate_roots"].is_null());
+    assert_eq!(
+        serde_json::from_value::<PublicInputs>(json).unwrap(),
+        pub_inputs
+    );
+
+    let pub_inputs = pub_inputs
+        .with_intermediate_roots(&roots[1..3])
+        .with_transactions_digest(roots[2])
+        .with_nullifiers(&roots[..2])
+        .with_batch_statistics(BatchStatistics::new(3, 1_000));
+    let json = serde_json::to_string(&pub_inputs).unwrap();
+    assert_eq!(
+        serde_json::from_str::<PublicInputs>(&json).unwrap(),
+        pub_inputs
+    );
+
+    // Non-canonical field elements and roots of the wrong length are rejected
+    let mut json = serde_json::to_value(&pub_inputs).unwrap();
+    json["final_root"][0] = BaseElement::MODULUS.to_string().into();
+    assert!(serde_json::from_value::<PublicInputs>(json).is_err());
+    let mut json = serde_json::to_value(&pub_inputs).unwrap();
+    json["final_root"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<PublicInputs>(json).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn transaction_serde() {
+    use super::{SplitOutputs, Transaction, TransactionKind};
+    use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement};
+
+    let outputs = SplitOutputs::new(&[(1, BaseElement::from(3u8)), (2, BaseElement::from(4u8))]);
+    let transaction = Transaction {
+        sender: 0,
+        receiver: 1,
+        amount: BaseElement::from(3u8),
+        fee: BaseElement::ONE,
+        nonce: BaseElement::from(5u8),
+        kind: TransactionKind::Split {
+            outputs,
+            position: 0,
+        },
+        signature: ([BaseElement::from(6u8); 6], Scalar::zero()),
+        memo: Some([0x17; 32]),
+    };
+    let json = serde_json::to_value(transaction).unwrap();
+    assert_eq!(json["amount"], "3");
+    assert_eq!(json["kind"]["Split"]["outputs"][1][0], 2);
+    let deserialized: Transaction = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(deserialized.kind, transaction.kind);
+    assert_eq!(deserialized.signature.0, transaction.signature.0);
+    assert_eq!(deserialized.memo, transaction.memo);
+    assert_eq!(serde_json::to_value(deserialized).unwrap(), json);
+
+    let mut json = json;
+    json["kind"]["Split"]["outputs"] = serde_json::Value::Array(Vec::new());
+    assert!(serde_json::from_value::<Transaction>(json).is_err());
+}
+
+#[test]
+fn transaction_metadata_seeded_generation() {
+    use super::TransactionMetadata;
+    use winterfell::Serializable;
+
+    let tx_metadata = TransactionMetadata::build_random_with_seed(2, [7; 32]);
+    let same_metadata = TransactionMetadata::build_random_with_seed(2, [7; 32]);
+    let other_metadata = TransactionMetadata::build_random_with_seed(2, [8; 32]);
+    assert!(tx_metadata.verify_signatures());
+    assert_eq!(tx_metadata.to_bytes(), same_metadata.to_bytes());
+    assert_ne!(tx_metadata.to_bytes(), other_metadata.to_bytes());
+}
+
+#[test]
+fn transaction_metadata_validation() {
+    use super::{MetadataError, TransactionMetadata};
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let tx_metadata = TransactionMetadata::build_random(4);
+    assert_eq!(tx_metadata.validate(), Ok(()));
+
+    let mut invalid_metadata = tx_metadata.clone();
+    invalid_metadata.final_root = tx_metadata.initial_roots[0];
+    assert_eq!(
+        invalid_metadata.validate(),
+        Err(MetadataError::InvalidFinalRoot)
+    );
+
+    let mut invalid_metadata = tx_metadata.clone();
+    invalid_metadata.s_paths[1] = tx_metadata.r_paths[1].clone();
+    assert_eq!(
+        invalid_metadata.validate(),
+        Err(MetadataError::InvalidSenderPath(1))
+    );
+
+    let mut invalid_metadata = tx_metadata.clone();
+    invalid_metadata.deltas[2] += BaseElement::ONE;
+    assert_eq!(
+        invalid_metadata.validate(),
+        Err(MetadataError::InvalidReceiverPath(2))
+    );
+
+    let mut invalid_metadata = tx_metadata;
+    invalid_metadata.s_old_values[3].balance = BaseElement::ZERO;
+    assert_eq!(
+        invalid_metadata.validate(),
+        Err(MetadataError::InsufficientBalance(3))
+    );
+}
+
+#[test]
+fn transaction_metadata_tampering() {
+    use super::testing::{build_invalid, Tampering};
+    use super::{Error, TransactionProver};
+
+    let prover = TransactionProver::new(build_options(1));
+    for &tampering in Tampering::ALL.iter() {
+        let tx_metadata = build_invalid(tampering, 3, 4);
+        assert_eq!(
+            tx_metadata.validate(),
+            Err(tampering.expected_error(Tampering::TX_INDEX))
+        );
+        assert_eq!(
+            prover.prove(&tx_metadata).unwrap_err(),
+            Error::InvalidMetadata(tampering.expected_error(Tampering::TX_INDEX))
+        );
+    }
+}
+
+#[test]
+fn transaction_test_trace_mutations() {
+    use super::testing::{check_mutated_trace, TraceMutation};
+    use super::{Error, TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(3, 4);
+    let prover = TransactionProver::new(build_options(1));
+    for &mutation in TraceMutation::ALL.iter() {
+        for tx_index in [0, 2].iter() {
+            let step = mutation.step(*tx_index);
+            match check_mutated_trace(&prover, &tx_metadata, mutation, *tx_index) {
+                Err(Error::UnsatisfiedConstraint(failure)) => assert!(
+                    failure.step + 1 >= step && failure.step <= step,
+                    "{:?} of transaction {} rejected by {}",
+                    mutation,
+                    tx_index,
+                    failure
+                ),
+                result => panic!(
+                    "{:?} of transaction {} not rejected: {:?}",
+                    mutation, tx_index, result
+                ),
+            }
+        }
+    }
+}
+
+#[test]
+fn transaction_metadata_path_verification() {
+    use super::{MetadataError, TransactionMetadata};
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let tx_metadata = TransactionMetadata::build_random(4);
+    assert_eq!(tx_metadata.verify_paths(), Ok(()));
+
+    let mut invalid_metadata = tx_metadata.clone();
+    invalid_metadata.c_paths[0] = tx_metadata.s_paths[0].clone();
+    assert_eq!(
+        invalid_metadata.verify_paths(),
+        Err(MetadataError::InvalidCollectorPath(0))
+    );
+
+    let mut invalid_metadata = tx_metadata.clone();
+    invalid_metadata.initial_roots[2] = tx_metadata.initial_roots[1];
+    assert_eq!(
+        invalid_metadata.verify_paths(),
+        Err(MetadataError::RootMismatch(1))
+    );
+
+    // An altered account no longer matches its path, whether its balance suffices or not
+    let mut unbalanced_metadata = tx_metadata;
+    unbalanced_metadata.s_old_values[3].balance = BaseElement::ZERO;
+    assert_eq!(
+        unbalanced_metadata.verify_paths(),
+        Err(MetadataError::InvalidSenderPath(3))
+    );
+}
+
+#[test]
+fn transaction_metadata_getters() {
+    use super::{TransactionMetadata, TransactionProver};
+
+    let tx_metadata = TransactionMetadata::build_random(4);
+    assert_eq!(tx_metadata.initial_roots().len(), 4);
+    assert_eq!(tx_metadata.s_paths()[0].len(), tx_metadata.tree_depth() + 1);
+    assert_eq!(tx_metadata.signatures().len(), 4);
+
+    let pub_inputs = tx_metadata.public_inputs();
+    assert_eq!(
+        pub_inputs.initial_root,
+        tx_metadata.initial_roots()[0].to_elements()
+    );
+    assert_eq!(
+        pub_inputs.final_root,
+        tx_metadata.final_root().to_elements()
+    );
+    assert_eq!(
+        pub_inputs.to_bytes(),
+        TransactionProver::new(build_options(1))
+            .public_inputs(&tx_metadata)
+            .to_bytes()
+    );
+}
+
+#[cfg(feature = "trace-export")]
+#[test]
+fn trace_export_labels() {
+    use super::constants::TRACE_WIDTH;
+    use super::export::register_labels;
+    use std::collections::BTreeSet;
+
+    // every register is labelled, once
+    let labels = register_labels();
+    assert_eq!(labels.len(), TRACE_WIDTH);
+    assert!(labels.iter().all(|label| !label.is_empty()));
+    assert_eq!(labels.iter().collect::<BTreeSet<_>>().len(), TRACE_WIDTH);
+    assert_eq!(labels[0], "merkle.sender.initial.0/schnorr.acc.x0");
+}
+
+#[cfg(feature = "trace-export")]
+#[test]
+fn transaction_test_trace_export() {
+    use super::constants::{TRACE_WIDTH, TRANSACTION_CYCLE_LENGTH};
+    use super::export::{register_labels, write_csv, write_csv_segments, write_csv_steps};
+    use super::{TransactionMetadata, TransactionProver};
+
+    let labels = register_labels();
+    let tx_metadata = TransactionMetadata::build_random_with_tree_depth(2, 4);
+    let prover = TransactionProver::new(build_options(1));
+    let trace = prover.build_trace(&tx_metadata);
+    let mut csv = Vec::new();
+    write_csv_steps(&trace, 0..TRANSACTION_CYCLE_LENGTH, &mut csv).unwrap();
+
+    let csv = String::from_utf8(csv).unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|row| row.split(',').collect()).collect();
+    assert_eq!(rows.len(), TRANSACTION_CYCLE_LENGTH + 1);
+    assert_eq!(rows[0][0], "step");
+    assert_eq!(
+        rows[0][1..],
+        labels
+            .iter()
+            .map(|label| label.as_str())
+            .collect::<Vec<_>>()[..]
+    );
+    for (step, row) in rows[1..].iter().enumerate() {
+        assert_eq!(row.len(), TRACE_WIDTH + 1);
+        assert_eq!(row[0], step.to_string());
+    }
+
+    // exporting the trace segment by segment yields the same rows as the whole trace
+    let mut whole_csv = Vec::new();
+    write_csv(&trace, &mut whole_csv).unwrap();
+    let mut segments_csv = Vec::new();
+    let segments = prover.trace_segments(&tx_metadata).unwrap();
+    write_csv_segments(segments, &mut segments_csv).unwrap();
+    assert_eq!(segments_csv, whole_csv);
+}
+
+#[cfg(feature = "service")]
+#[test]
+fn transaction_test_prover_service() {
+    use super::service::{ProgressEvent, ProverService};
+    use super::{Error, TransactionMetadata, TransactionProver};
+    use std::{
+        future::Future,
+        sync::Arc,
+        task::{Context, Poll, Wake},
+        thread::{self, Thread},
+    };
+
+    // minimal executor parking the current thread until the future is woken
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    let prover = TransactionProver::new(build_options(1));
+    let service = ProverService::new(prover.clone(), 2);
+    assert_eq!(service.num_workers(), 2);
+    let events = service.subscribe();
+
+    let batches: Vec<_> = (0..3)
+        .map(|_| TransactionMetadata::build_random_with_tree_depth(2, 4))
+        .collect();
+    let futures: Vec<_> = batches
+        .iter()
+        .map(|batch| service.submit(batch.clone()))
+        .collect();
+    let job_ids: Vec<_> = futures.iter().map(|future| future.job_id()).collect();
+    assert_eq!(job_ids, [0, 1, 2]);
+
+    for (future, batch) in futures.into_iter().zip(batches.iter()) {
+        let (proof, report) = block_on(future).unwrap();
+        assert_eq!(report.num_transactions, 2);
+        assert!(super::verify_batch(vec![proof], vec![prover.public_inputs(batch)])[0].is_ok());
+    }
+    let (proof, _) = block_on(service.prove_batch(batches[0].clone())).unwrap();
+
+    // a job cancelled before being proven resolves to an error
+    let future = service.submit(batches[1].clone());
+    future.cancel();
+    assert_eq!(block_on(future).unwrap_err(), Error::Cancelled);
+    assert!(super::verify_batch(vec![proof], vec![prover.public_inputs(&batches[0])])[0].is_ok());
+    drop(service);
+
+    // every job is queued, started and proven, in that order
+    let events: Vec<_> = events.try_iter().collect();
+    for job_id in 0..4 {
+        let job_events: Vec<_> = events
+            .iter()
+            .filter(|event| event.job_id() == job_id)
+            .collect();
+        assert_eq!(
+            job_events[0],
+            &ProgressEvent::Queued {
+                job_id,
+                num_transactions: 2
+            }
+        );
+        assert_eq!(job_events[1], &ProgressEvent::Started { job_id });
+        assert!(matches!(job_events[2], ProgressEvent::Proved { .. }));
+    }
+
+    // the cancelled job may have been started before being cancelled
+    let job_events: Vec<_> = events.iter().filter(|event| event.job_id() == 4).collect();
+    assert!(matches!(job_events[0], ProgressEvent::Queued { .. }));
+    assert_eq!(
+        job_events.last().unwrap(),
+        &&ProgressEvent::Failed { job_id: 4 }
+    );
+}
+
+#[test]
+fn transaction_periodic_columns_segments() {
+    use super::air::periodic_columns;
+    use super::constants::{
+        merkle_const::TRANSACTION_CYCLE_LENGTH as MERKLE_UPDATE_LENGTH,
+        schnorr_const::{SCALAR_MUL_LENGTH, TOTAL_HASH_LENGTH},
+        HASH_INTERNAL_INPUT_MASKS_INDEX, RANGE_PROOF_FINISH_MASK_INDEX,
+        RANGE_PROOF_STEP_MASK_INDEX, SCHNORR_HASH_HOLD_MASK_INDEX, SCHNORR_HASH_MASK_INDEX,
+        SCHNORR_MASK_INDEX, SETUP_MASK_INDEX, TRANSACTION_CYCLE_LENGTH,
+    };
+    use super::utils::rescue::Rescue63;
+    use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+    let range_log = 32;
+    let columns = periodic_columns::<Rescue63>(4, range_log);
+    for column in columns.iter() {
+        assert!(column.len().is_power_of_two() && column.len() <= TRANSACTION_CYCLE_LENGTH);
+    }
+
+    // the setup runs at the first step only, and the Schnorr and range proof components over
+    // the second half of the cycle
+    let is_set = |index: usize, step: usize| {
+        columns[index][step % columns[index].len()] != BaseElement::ZERO
+    };
+    for step in 0..TRANSACTION_CYCLE_LENGTH {
+        assert_eq!(is_set(SETUP_MASK_INDEX, step), step == 0);
+        if step < MERKLE_UPDATE_LENGTH {
+            assert!(!is_set(SCHNORR_MASK_INDEX, step));
+        }
+        let range_step = step.wrapping_sub(MERKLE_UPDATE_LENGTH);
+        assert_eq!(
+            is_set(RANGE_PROOF_STEP_MASK_INDEX, step),
+            range_step < range_log
+        );
+        assert_eq!(
+            is_set(RANGE_PROOF_FINISH_MASK_INDEX, step),
+            range_step == range_log - 1
+        );
+    }
+    assert_eq!(
+        columns[RANGE_PROOF_STEP_MASK_INDEX][MERKLE_UPDATE_LENGTH],
+        BaseElement::ONE
+    );
+
+    // each step of the Schnorr hash state, until its digest is read, is either a Rescue round,
+    // the absorption of a chunk of the message, or the hold of the hash state
+    for schnorr_step in 0..=SCALAR_MUL_LENGTH {
+        let step = MERKLE_UPDATE_LENGTH + schnorr_step;
+        let input = (HASH_INTERNAL_INPUT_MASKS_INDEX..RANGE_PROOF_STEP_MASK_INDEX)
+            .any(|index| is_set(index, step));
+        let hold = is_set(SCHNORR_HASH_HOLD_MASK_INDEX, step);
+        assert_eq!(
+            [is_set(SCHNORR_HASH_MASK_INDEX, step), input, hold]
+                .iter()
+                .filter(|&&flag| flag)
+                .count(),
+            1
+        );
+        assert_eq!(hold, schnorr_step >= TOTAL_HASH_LENGTH - 1);
+    }
+}
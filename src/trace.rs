@@ -6,33 +6,48 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use super::air::{encode_memo, encode_recipient};
 use super::constants::*;
-use super::merkle;
+use super::merkle::{self, TreeHasher};
 use super::range;
 use super::schnorr;
-use super::utils::rescue;
+use super::utils::{bits, rescue, rescue::Rescue63};
+use super::{AccountState, TransactionKind};
 use bitvec::{order::Lsb0, slice::BitSlice};
-use winterfell::math::{curves::curve_f63::Scalar, fields::f63::BaseElement};
+use winterfell::math::{
+    curves::curve_f63::Scalar, fields::f63::BaseElement, FieldElement, StarkField,
+};
 
 #[cfg(feature = "concurrent")]
 use winterfell::iterators::*;
 
-#[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
-
 use merkle_const::TRANSACTION_CYCLE_LENGTH as MERKLE_UPDATE_LENGTH;
+use range_const::MAX_RANGE_LOG;
 use schnorr_const::{
-    AFFINE_POINT_WIDTH, POINT_COORDINATE_WIDTH, SIG_CYCLE_LENGTH as SCHNORR_LENGTH,
+    AFFINE_POINT_WIDTH, MESSAGE_LENGTH, POINT_COORDINATE_WIDTH, SIG_CYCLE_LENGTH as SCHNORR_LENGTH,
 };
 
 // TRACE INITIALIZATION
 // ================================================================================================
 
+#[allow(clippy::too_many_arguments)]
 pub fn init_transaction_state(
     initial_root: rescue::Hash,
-    s_old_value: [BaseElement; AFFINE_POINT_WIDTH + 2],
-    r_old_value: [BaseElement; AFFINE_POINT_WIDTH + 2],
+    s_old_value: AccountState,
+    r_old_value: AccountState,
+    c_old_value: AccountState,
     delta: BaseElement,
+    nonce: BaseElement,
+    fee: BaseElement,
+    kind: TransactionKind,
+    memo: Option<[u8; 32]>,
+    member_key: [BaseElement; AFFINE_POINT_WIDTH],
+    previous_split_values: &[BaseElement; SPLIT_REGISTER_WIDTH],
+    previous_indices: [usize; 2],
+    previous_digest: rescue::Hash,
+    previous_nullifiers: rescue::Hash,
+    previous_statistics: [BaseElement; 2],
+    previous_committee_values: &[BaseElement; COMMITTEE_REGISTER_WIDTH],
     state: &mut [BaseElement],
 ) {
     // Initialize leaf values prior to hashing
@@ -40,38 +55,171 @@ pub fn init_transaction_state(
         initial_root,
         s_old_value,
         r_old_value,
+        c_old_value,
         delta,
+        fee,
+        kind,
         &mut state[..merkle_const::TRACE_WIDTH],
     );
 
-    // Copy public keys, delta, sigma = balance_sender - delta, and nonce
+    // Copy public keys (or the deposit key and the recipient address for deposits and
+    // withdrawals, the receiver's key in place of the sender's for refunds, and the member's
+    // key for committee transfers and approvals), delta, sigma = updated sender balance, the
+    // signer's nonce, fee, flags and the updated receiver balance
     let start_copy_index = merkle_const::TRACE_WIDTH;
-    state[start_copy_index..start_copy_index + AFFINE_POINT_WIDTH]
-        .copy_from_slice(&s_old_value[0..AFFINE_POINT_WIDTH]);
-    state[start_copy_index + AFFINE_POINT_WIDTH..start_copy_index + AFFINE_POINT_WIDTH * 2]
-        .copy_from_slice(&r_old_value[0..AFFINE_POINT_WIDTH]);
+    let sender_key_copy = &mut state[start_copy_index..start_copy_index + AFFINE_POINT_WIDTH];
+    match kind {
+        TransactionKind::Deposit | TransactionKind::NoOp => {
+            sender_key_copy.copy_from_slice(&super::deposit_key())
+        }
+        TransactionKind::Refund => sender_key_copy.copy_from_slice(&r_old_value.pub_key_elements()),
+        TransactionKind::Committee | TransactionKind::Approval => {
+            sender_key_copy.copy_from_slice(&member_key)
+        }
+        _ => sender_key_copy.copy_from_slice(&s_old_value.pub_key_elements()),
+    }
+    let receiver_key_copy = &mut state
+        [start_copy_index + AFFINE_POINT_WIDTH..start_copy_index + AFFINE_POINT_WIDTH * 2];
+    match kind {
+        TransactionKind::Withdrawal { recipient } => {
+            receiver_key_copy.copy_from_slice(&encode_recipient(&recipient))
+        }
+        TransactionKind::Split { outputs, .. } => {
+            receiver_key_copy.copy_from_slice(&outputs.to_elements())
+        }
+        _ => receiver_key_copy.copy_from_slice(&r_old_value.pub_key_elements()),
+    }
     state[start_copy_index + AFFINE_POINT_WIDTH * 2] = delta;
-    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 1] = s_old_value[AFFINE_POINT_WIDTH] - delta;
-    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 2] = s_old_value[AFFINE_POINT_WIDTH + 1];
+    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 1] =
+        state[merkle_const::SENDER_UPDATED_POS + AFFINE_POINT_WIDTH];
+    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 2] = nonce;
+    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 3] = fee;
+    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 4] = if kind.is_withdrawal() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 5] = if kind.is_deposit() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    state[start_copy_index + AFFINE_POINT_WIDTH * 2 + 6] =
+        state[merkle_const::RECEIVER_UPDATED_POS + AFFINE_POINT_WIDTH];
+
+    state[NOOP_FLAG_POS] = if kind.is_noop() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    state[REKEY_FLAG_POS] = if kind.is_rekey() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    state[SHARED_PATH_FLAG_POS] = BaseElement::ZERO;
+    state[DELTA_SIGN_POS] = if kind.is_refund() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    state[COMMITTEE_FLAG_POS] = if kind.is_committee() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    state[APPROVAL_FLAG_POS] = if kind.is_approval() {
+        BaseElement::ONE
+    } else {
+        BaseElement::ZERO
+    };
+    if let Some(memo) = memo {
+        state[MEMO_POS..MEMO_POS + MEMO_WIDTH].copy_from_slice(&encode_memo(&memo));
+    }
+
+    // Carry over the split registers and the receiver's and sender's indices of the previous
+    // transaction
+    state[SPLIT_FLAG_POS..SPLIT_FLAG_POS + SPLIT_REGISTER_WIDTH]
+        .copy_from_slice(previous_split_values);
+    state[RECEIVER_INDEX_POS] = BaseElement::new(previous_indices[0] as u64);
+    state[SENDER_INDEX_POS] = BaseElement::new(previous_indices[1] as u64);
+
+    // Carry over the running digest of the previous transactions and the commitment to their
+    // nullifiers
+    state[TX_DIGEST_POS..TX_DIGEST_POS + merkle_const::HASH_RATE_WIDTH]
+        .copy_from_slice(&previous_digest.to_elements());
+    state[NULLIFIERS_POS..NULLIFIERS_POS + merkle_const::HASH_RATE_WIDTH]
+        .copy_from_slice(&previous_nullifiers.to_elements());
+
+    // Carry over the number and volume of the previous transactions
+    state[TX_COUNT_POS] = previous_statistics[0];
+    state[TX_VOLUME_POS] = previous_statistics[1];
+
+    // Carry over the root of the key tree, the number of approvals left and the member's
+    // index of the previous transaction, and start the authentication path of the member's
+    // key from its leaf, hashed as the one of an account with a zero balance and nonce
+    state[COMMITTEE_ROOT_POS..COMMITTEE_ROOT_POS + COMMITTEE_REGISTER_WIDTH]
+        .copy_from_slice(previous_committee_values);
+    state[KEY_PATH_POS..KEY_PATH_POS + AFFINE_POINT_WIDTH].copy_from_slice(&member_key);
+    state[KEY_PATH_POS + AFFINE_POINT_WIDTH] = BaseElement::ZERO;
+    state[KEY_PATH_POS + AFFINE_POINT_WIDTH + 1] = merkle_const::LEAF_DOMAIN;
+    state[KEY_PATH_POS + rescue::STATE_WIDTH] = BaseElement::ZERO;
+
+    // The last coordinate of the key verifying the signature is never zero
+    state[SIGNING_KEY_INVERSE_POS] = state[SENDER_KEY_POINT_POS + AFFINE_POINT_WIDTH - 1].inv();
+}
+
+/// Returns the values of the split registers for a transaction of the provided kind with
+/// amount `delta`, i.e. the split flag, the position flags, the signed amount, and the amounts
+/// sent and remaining to be sent.
+pub fn split_register_values(
+    kind: TransactionKind,
+    delta: BaseElement,
+) -> [BaseElement; SPLIT_REGISTER_WIDTH] {
+    let mut values = [BaseElement::ZERO; SPLIT_REGISTER_WIDTH];
+    let (position, amount, sent) = match kind {
+        TransactionKind::Split { outputs, position } => {
+            values[0] = BaseElement::ONE;
+            (position, outputs.total(), outputs.sent(position))
+        }
+        _ => (0, delta, delta),
+    };
+    values[1 + position] = BaseElement::ONE;
+    values[SPLIT_AMOUNT_POS - SPLIT_FLAG_POS] = amount;
+    values[SPLIT_SENT_POS - SPLIT_FLAG_POS] = sent;
+    values[SPLIT_REMAINING_POS - SPLIT_FLAG_POS] = amount - sent;
+
+    values
 }
 
 // TRANSITION FUNCTION
 // ================================================================================================
 
 #[allow(clippy::too_many_arguments)]
-pub fn update_transaction_state(
+pub fn update_transaction_state<H: TreeHasher>(
     step: usize,
+    tree_depth: usize,
+    range_log: usize,
+    delta_sign_weight: BaseElement,
     s_index: usize,
     r_index: usize,
-    s_branch: Vec<rescue::Hash>,
-    r_branch: Vec<rescue::Hash>,
+    c_index: usize,
+    s_branch: &[rescue::Hash],
+    r_branch: &[rescue::Hash],
+    c_branch: &[rescue::Hash],
     delta_bits: &BitSlice<Lsb0, u8>,
     sigma_bits: &BitSlice<Lsb0, u8>,
+    fee_bits: &BitSlice<Lsb0, u8>,
+    receiver_balance_bits: &BitSlice<Lsb0, u8>,
     signature: ([BaseElement; POINT_COORDINATE_WIDTH], Scalar),
     sig_bits: &BitSlice<Lsb0, u8>,
     sig_hash_bits: &BitSlice<Lsb0, u8>,
-    message: [BaseElement; AFFINE_POINT_WIDTH * 2 + 4],
+    message: [BaseElement; MESSAGE_LENGTH],
     pkey_point: [BaseElement; AFFINE_POINT_WIDTH],
+    split_values: &[BaseElement; SPLIT_REGISTER_WIDTH],
+    member_index: usize,
+    key_branch: &[rescue::Hash],
+    committee_values: &[BaseElement; COMMITTEE_REGISTER_WIDTH],
     state: &mut [BaseElement],
 ) {
     let merkle_update_flag = step < MERKLE_UPDATE_LENGTH - 1;
@@ -80,27 +228,110 @@ pub fn update_transaction_state(
 
     if merkle_update_flag {
         // Proceed to Merkle authentication paths verification
-        merkle::update::update_merkle_update_state(
+        merkle::update::update_merkle_update_state::<H>(
             step,
+            tree_depth,
             s_index,
             r_index,
+            c_index,
             s_branch,
             r_branch,
+            c_branch,
             &mut state[..merkle_const::TRACE_WIDTH],
         );
+        // Authenticate the member's key in the key tree of the committee alongside
+        if step < merkle_const::transaction_hash_length(tree_depth) {
+            merkle::update::update_merkle_path_state::<Rescue63>(
+                step,
+                member_index,
+                key_branch,
+                &mut state[KEY_PATH_POS..SIGNING_KEY_INVERSE_POS],
+            );
+        }
+
+        // Flag the levels from the one hashing both children of the lowest common ancestor of
+        // the sender's and receiver's leaves, above which their paths share their nodes
+        let shared_level = (usize::BITS - (s_index ^ r_index).leading_zeros()).max(1) as usize;
+        state[SHARED_PATH_FLAG_POS] =
+            if (step + 1) / rescue_const::HASH_CYCLE_LENGTH >= shared_level {
+                BaseElement::ONE
+            } else {
+                BaseElement::ZERO
+            };
+
+        // Set the split and committee registers of this transaction, and accumulate the
+        // receiver's, sender's and member's indices from the bits of their authentication
+        // paths. Approvals carry the receiver's and sender's indices of the transfer they
+        // approve instead
+        let approval = state[APPROVAL_FLAG_POS] == BaseElement::ONE;
+        if step == 0 {
+            state[SPLIT_FLAG_POS..SPLIT_FLAG_POS + SPLIT_REGISTER_WIDTH]
+                .copy_from_slice(split_values);
+            if !approval {
+                state[RECEIVER_INDEX_POS] = BaseElement::ZERO;
+                state[SENDER_INDEX_POS] = BaseElement::ZERO;
+            }
+            state[COMMITTEE_ROOT_POS..MEMBER_INDEX_POS]
+                .copy_from_slice(&committee_values[..MEMBER_INDEX_POS - COMMITTEE_ROOT_POS]);
+            state[PREVIOUS_MEMBER_INDEX_POS] = state[MEMBER_INDEX_POS];
+            state[MEMBER_INDEX_POS] = BaseElement::ZERO;
+        } else if step % rescue_const::HASH_CYCLE_LENGTH == rescue_const::HASH_CYCLE_LENGTH - 1 {
+            let level = step / rescue_const::HASH_CYCLE_LENGTH;
+            if level < tree_depth {
+                let weight = BaseElement::new(1u64 << level);
+                if !approval {
+                    bits::apply_weighted_step(
+                        state,
+                        RECEIVER_INDEX_POS,
+                        merkle_const::RECEIVER_BIT_POS,
+                        weight,
+                    );
+                    bits::apply_weighted_step(
+                        state,
+                        SENDER_INDEX_POS,
+                        merkle_const::SENDER_BIT_POS,
+                        weight,
+                    );
+                }
+                bits::apply_weighted_step(
+                    state,
+                    MEMBER_INDEX_POS,
+                    KEY_PATH_POS + rescue::STATE_WIDTH,
+                    weight,
+                );
+            }
+        }
     // Initialize Schnorr signature verification state
     } else if schnorr_init_flag {
         schnorr::init_sig_verification_state(signature, &mut state[..schnorr_const::TRACE_WIDTH]);
-        // We set the 4 registers next to the Schnorr signature sub-trace to zero, for computing
-        // the range proofs on delta and sigma = sender_balance - delta
+        // We set the 4 registers next to the Schnorr signature sub-trace and the 4 registers
+        // after the hash state of the digest of transactions to zero, for computing the range
+        // proofs on delta, the fee, sigma = sender_balance - delta - fee and the updated
+        // receiver balance, or after the copied values in the legacy layout
         let start_delta_range_index = schnorr_const::TRACE_WIDTH;
-        let start_sigma_range_index = NONCE_COPY_POS + 1;
+        let start_fee_range_index = FEE_BIT_POS;
+        let start_sigma_range_index = SIGMA_BIT_POS;
+        let start_receiver_balance_range_index = RECEIVER_BALANCE_BIT_POS;
         range::init_range_verification_state(
             &mut state[start_delta_range_index..start_delta_range_index + 2],
         );
+        range::init_range_verification_state(
+            &mut state[start_fee_range_index..start_fee_range_index + 2],
+        );
         range::init_range_verification_state(
             &mut state[start_sigma_range_index..start_sigma_range_index + 2],
         );
+        range::init_range_verification_state(
+            &mut state[start_receiver_balance_range_index..start_receiver_balance_range_index + 2],
+        );
+        // The hash state of the running digest of transactions starts from the digest of the
+        // previous transactions, followed by the first chunk of the tuple of this transaction
+        for i in 0..rescue::RATE_WIDTH {
+            state[TX_DIGEST_HASH_POS + i] = state[TX_DIGEST_POS + i];
+            state[TX_DIGEST_HASH_POS + rescue::RATE_WIDTH + i] = state[SENDER_KEY_POINT_POS + i];
+        }
+        // The hash state of the receiver's key starts alongside
+        init_key_index_hash_state(state);
     } else if schnorr_update_flag {
         // Proceed to Schnorr signature verification
         let schnorr_step = step - MERKLE_UPDATE_LENGTH;
@@ -112,34 +343,241 @@ pub fn update_transaction_state(
             sig_hash_bits,
             &mut state[..schnorr_const::TRACE_WIDTH],
         );
+        update_tx_digest_state(schnorr_step, state);
+        update_key_index_hash_state(schnorr_step, state);
 
-        if schnorr_step < range::RANGE_LOG {
-            // Compute the range proof on delta and sigma
+        if schnorr_step < range_log {
+            // Compute the range proof on delta, the fee, sigma and the updated receiver balance
             let start_delta_range_index = schnorr_const::TRACE_WIDTH;
-            let start_sigma_range_index = NONCE_COPY_POS + 1;
+            let start_fee_range_index = FEE_BIT_POS;
+            let start_sigma_range_index = SIGMA_BIT_POS;
+            let start_receiver_balance_range_index = RECEIVER_BALANCE_BIT_POS;
             range::update_range_verification_state(
                 schnorr_step,
-                range_const::RANGE_LOG,
+                range_log,
                 delta_bits,
                 &mut state[start_delta_range_index..start_delta_range_index + 2],
             );
             range::update_range_verification_state(
                 schnorr_step,
-                range_const::RANGE_LOG,
+                range_log,
+                fee_bits,
+                &mut state[start_fee_range_index..start_fee_range_index + 2],
+            );
+            range::update_range_verification_state(
+                schnorr_step,
+                range_log,
                 sigma_bits,
                 &mut state[start_sigma_range_index..start_sigma_range_index + 2],
             );
-        } else {
+            range::update_range_verification_state(
+                schnorr_step,
+                range_log,
+                receiver_balance_bits,
+                &mut state
+                    [start_receiver_balance_range_index..start_receiver_balance_range_index + 2],
+            );
+        } else if schnorr_step == range_log {
             debug_assert_eq!(
-                state[DELTA_ACCUMULATE_POS], state[DELTA_COPY_POS],
+                state[DELTA_ACCUMULATE_POS] - delta_sign_weight * state[DELTA_SIGN_POS],
+                state[DELTA_COPY_POS],
                 "expected accumulated value for delta of {}, found {}",
-                state[DELTA_COPY_POS], state[DELTA_ACCUMULATE_POS],
+                state[DELTA_COPY_POS],
+                state[DELTA_ACCUMULATE_POS],
+            );
+            debug_assert_eq!(
+                state[FEE_ACCUMULATE_POS], state[FEE_COPY_POS],
+                "expected accumulated value for the fee of {}, found {}",
+                state[FEE_COPY_POS], state[FEE_ACCUMULATE_POS],
             );
             debug_assert_eq!(
                 state[SIGMA_ACCUMULATE_POS], state[SIGMA_COPY_POS],
                 "expected accumulated value for sigma of {}, found {}",
                 state[SIGMA_COPY_POS], state[SIGMA_ACCUMULATE_POS],
             );
+            debug_assert_eq!(
+                state[RECEIVER_BALANCE_ACCUMULATE_POS], state[RECEIVER_BALANCE_COPY_POS],
+                "expected accumulated value for the receiver's balance of {}, found {}",
+                state[RECEIVER_BALANCE_COPY_POS], state[RECEIVER_BALANCE_ACCUMULATE_POS],
+            );
+        }
+
+        // Decompose the hashes of the keys and the gap between the indices of consecutive
+        // members once the range proofs are over
+        update_key_index_state(schnorr_step, state);
+        update_member_gap_state(schnorr_step, state);
+    }
+
+    debug_assert!(
+        step + 1 < TRANSACTION_CYCLE_LENGTH - 1
+            || state[MEMBER_INDEX_POS] == committee_values[MEMBER_INDEX_POS - COMMITTEE_ROOT_POS],
+        "expected member index of {}, found {}",
+        committee_values[MEMBER_INDEX_POS - COMMITTEE_ROOT_POS],
+        state[MEMBER_INDEX_POS],
+    );
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Hashes the running digest of transactions along with the (sender, receiver, delta) tuple of
+/// the transaction, alongside the iterated hash of the signed message, and updates the digest
+/// with the result unless for no-ops and approvals. The hash state then computes the nullifier
+/// of the transaction and merges it with the commitment to nullifiers, updated with the result
+/// unless for deposits, no-ops and approvals. The hash state finally hashes the sender's key
+/// into the hash whose low bits are the index derived from it.
+fn update_tx_digest_state(step: usize, state: &mut [BaseElement]) {
+    if step >= schnorr_const::TOTAL_HASH_LENGTH {
+        return;
+    }
+
+    let rescue_step = step % rescue_const::HASH_CYCLE_LENGTH;
+    let iteration = step / rescue_const::HASH_CYCLE_LENGTH;
+    let hash_state = TX_DIGEST_HASH_POS..TX_DIGEST_HASH_POS + rescue::STATE_WIDTH;
+    if rescue_step < rescue::NUM_HASH_ROUNDS {
+        rescue::apply_round(&mut state[hash_state], step);
+    } else if iteration < TX_TUPLE_NUM_CHUNKS - 1 {
+        // insert the next chunk of the tuple, padded with zeros
+        for i in 0..rescue::RATE_WIDTH {
+            let tuple_index = (iteration + 1) * rescue::RATE_WIDTH + i;
+            state[TX_DIGEST_HASH_POS + rescue::RATE_WIDTH + i] = if tuple_index < TX_TUPLE_WIDTH {
+                state[SENDER_KEY_POINT_POS + tuple_index]
+            } else {
+                BaseElement::ZERO
+            };
+        }
+    } else if iteration == TX_TUPLE_NUM_CHUNKS - 1 {
+        // update the digest and the number and volume of transactions, unless for no-ops and
+        // approvals, and reset the hash state to the sender's key, nonce and domain
+        if state[NOOP_FLAG_POS] == BaseElement::ZERO
+            && state[APPROVAL_FLAG_POS] == BaseElement::ZERO
+        {
+            for i in 0..rescue::RATE_WIDTH {
+                state[TX_DIGEST_POS + i] = state[TX_DIGEST_HASH_POS + i];
+            }
+            state[TX_COUNT_POS] += BaseElement::ONE;
+            state[TX_VOLUME_POS] += state[DELTA_COPY_POS];
+        }
+        for i in 0..AFFINE_POINT_WIDTH {
+            state[TX_DIGEST_HASH_POS + i] = state[SENDER_KEY_POINT_POS + i];
+        }
+        state[TX_DIGEST_HASH_POS + AFFINE_POINT_WIDTH] = state[NONCE_COPY_POS];
+        state[hash_state.end - 1] = NULLIFIER_DOMAIN;
+    } else if iteration == TX_TUPLE_NUM_CHUNKS {
+        // merge the nullifier with the commitment to the previous nullifiers
+        for i in 0..rescue::RATE_WIDTH {
+            state[TX_DIGEST_HASH_POS + rescue::RATE_WIDTH + i] = state[NULLIFIERS_POS + i];
         }
+    } else if iteration == TX_TUPLE_NUM_CHUNKS + 1 {
+        // update the commitment from the first registers, and reset the hash state to the
+        // sender's key, a zero and domain, after which the hash state is held
+        if state[DEPOSIT_FLAG_POS] == BaseElement::ZERO
+            && state[NOOP_FLAG_POS] == BaseElement::ZERO
+            && state[APPROVAL_FLAG_POS] == BaseElement::ZERO
+        {
+            for i in 0..rescue::RATE_WIDTH {
+                state[NULLIFIERS_POS + i] = state[TX_DIGEST_HASH_POS + i];
+            }
+        }
+        for i in 0..AFFINE_POINT_WIDTH {
+            state[TX_DIGEST_HASH_POS + i] = state[SENDER_KEY_POINT_POS + i];
+        }
+        state[TX_DIGEST_HASH_POS + AFFINE_POINT_WIDTH] = BaseElement::ZERO;
+        state[hash_state.end - 1] = ACCOUNT_INDEX_DOMAIN;
+    }
+}
+
+/// Resets the hash state of the receiver's key to the key, followed by a zero and the domain
+/// of the hash into the index derived from it.
+fn init_key_index_hash_state(state: &mut [BaseElement]) {
+    for i in 0..AFFINE_POINT_WIDTH {
+        state[KEY_INDEX_HASH_POS + i] = state[RECEIVER_KEY_POINT_POS + i];
     }
+    state[KEY_INDEX_HASH_POS + AFFINE_POINT_WIDTH] = BaseElement::ZERO;
+    state[KEY_INDEX_HASH_POS + rescue::STATE_WIDTH - 1] = ACCOUNT_INDEX_DOMAIN;
+}
+
+/// Hashes the receiver's key alongside the iterated hash of the signed message, the hash state
+/// being reset at every iteration but the last one, after which it is held.
+fn update_key_index_hash_state(step: usize, state: &mut [BaseElement]) {
+    if step >= schnorr_const::TOTAL_HASH_LENGTH {
+        return;
+    }
+
+    if step % rescue_const::HASH_CYCLE_LENGTH < rescue::NUM_HASH_ROUNDS {
+        rescue::apply_round(
+            &mut state[KEY_INDEX_HASH_POS..KEY_INDEX_HASH_POS + rescue::STATE_WIDTH],
+            step,
+        );
+    } else if step < schnorr_const::TOTAL_HASH_LENGTH - 1 {
+        init_key_index_hash_state(state);
+    }
+}
+
+/// Decomposes the hashes of the sender's and receiver's keys into their bits, least
+/// significant first, once the longest range proofs are over. Along with the accumulated
+/// value, a bound register tells whether the bits inserted so far are at most the ones of the
+/// field modulus minus one.
+fn update_key_index_state(step: usize, state: &mut [BaseElement]) {
+    if !(MAX_RANGE_LOG..=MAX_RANGE_LOG + KEY_INDEX_NUM_BITS).contains(&step) {
+        return;
+    }
+
+    for (bit_pos, accumulate_pos, bound_pos, hash_pos) in [
+        (
+            SENDER_KEY_INDEX_BIT_POS,
+            SENDER_KEY_INDEX_ACCUMULATE_POS,
+            SENDER_KEY_INDEX_BOUND_POS,
+            TX_DIGEST_HASH_POS,
+        ),
+        (
+            RECEIVER_KEY_INDEX_BIT_POS,
+            RECEIVER_KEY_INDEX_ACCUMULATE_POS,
+            RECEIVER_KEY_INDEX_BOUND_POS,
+            KEY_INDEX_HASH_POS,
+        ),
+    ] {
+        if step == MAX_RANGE_LOG {
+            state[bit_pos] = BaseElement::ZERO;
+            state[accumulate_pos] = BaseElement::ZERO;
+            state[bound_pos] = BaseElement::ONE;
+            continue;
+        }
+
+        let index = step - MAX_RANGE_LOG - 1;
+        let bit = state[hash_pos].to_repr() >> index & 1;
+        let bound_bit = (BaseElement::MODULUS - 1) >> index & 1;
+        state[bit_pos] = BaseElement::new(bit);
+        state[accumulate_pos] += BaseElement::new(bit << index);
+        state[bound_pos] = match (bit, bound_bit) {
+            (0, 1) => BaseElement::ONE,
+            (1, 0) => BaseElement::ZERO,
+            _ => state[bound_pos],
+        };
+    }
+}
+
+/// Decomposes the gap between the indices of the previous and current members, minus one, into
+/// its bits, least significant first, along the decompositions of the hashes of the keys. The
+/// gap is only decomposed for approvals, whose member follows the previous one, and is zero
+/// otherwise.
+fn update_member_gap_state(step: usize, state: &mut [BaseElement]) {
+    if !(MAX_RANGE_LOG..=MAX_RANGE_LOG + KEY_INDEX_NUM_BITS).contains(&step) {
+        return;
+    }
+    if step == MAX_RANGE_LOG {
+        state[MEMBER_GAP_BIT_POS] = BaseElement::ZERO;
+        state[MEMBER_GAP_ACCUMULATE_POS] = BaseElement::ZERO;
+        return;
+    }
+
+    let gap = if state[APPROVAL_FLAG_POS] == BaseElement::ONE {
+        (state[MEMBER_INDEX_POS] - state[PREVIOUS_MEMBER_INDEX_POS] - BaseElement::ONE).to_repr()
+    } else {
+        0
+    };
+    let index = step - MAX_RANGE_LOG - 1;
+    let bit = gap >> index & 1;
+    state[MEMBER_GAP_BIT_POS] = BaseElement::new(bit);
+    state[MEMBER_GAP_ACCUMULATE_POS] += BaseElement::new(bit << index);
 }
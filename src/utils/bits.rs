@@ -0,0 +1,270 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Binary decomposition of values over consecutive steps of an execution trace.
+//!
+//! A decomposition spans two registers: a bit register, and a register accumulating the
+//! value from its bits. Bits can be inserted most significant first, the accumulated value
+//! being doubled before each bit is added, as done by the range proofs and the scalar
+//! multiplications of the Schnorr signature verification. They can also be inserted least
+//! significant first at any pace, each bit being added with a weight read from a periodic
+//! column, as done for the indices of the leaves along Merkle authentication paths.
+
+use super::{are_equal, is_binary, EvaluationResult};
+use crate::periodic::{PeriodicColumnSet, Segment};
+use bitvec::{order::Lsb0, slice::BitSlice};
+use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+// TRACE
+// ================================================================================================
+
+/// Returns the bit of `bits` inserted at the `step`-th step of the decomposition of their
+/// `num_bits` low bits, most significant first.
+pub(crate) fn msb_first(bits: &BitSlice<Lsb0, u8>, num_bits: usize, step: usize) -> BaseElement {
+    BaseElement::from(bits[num_bits - 1 - step] as u8)
+}
+
+/// Apply a step of double-and-add in the field when filling up the execution trace.
+pub(crate) fn apply_double_and_add_step(
+    state: &mut [BaseElement],
+    value_position: usize,
+    bit_position: usize,
+) {
+    state[value_position] = state[value_position].double() + state[bit_position];
+}
+
+/// Inserts the bit of `bits` read at the `step`-th step of the decomposition of their
+/// `num_bits` low bits, most significant first, and applies a step of double-and-add.
+pub(crate) fn apply_decomposition_step(
+    state: &mut [BaseElement],
+    value_position: usize,
+    bit_position: usize,
+    bits: &BitSlice<Lsb0, u8>,
+    num_bits: usize,
+    step: usize,
+) {
+    state[bit_position] = msb_first(bits, num_bits, step);
+    apply_double_and_add_step(state, value_position, bit_position);
+}
+
+/// Adds the bit register, weighted by `weight`, to the accumulated value, for values whose
+/// bits are inserted least significant first.
+pub(crate) fn apply_weighted_step(
+    state: &mut [BaseElement],
+    value_position: usize,
+    bit_position: usize,
+    weight: BaseElement,
+) {
+    state[value_position] += state[bit_position] * weight;
+}
+
+// CONSTRAINTS
+// ================================================================================================
+
+/// Enforce a step of double-and-add in the field, given two registers:
+///  - an accumulated value, starting from zero
+///  - a binary value, indicating whether we add after doubling or not
+///
+/// and enforce binary constraint on the bit decomposition register
+pub(crate) fn enforce_double_and_add_step<E: FieldElement>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    value_position: usize,
+    bit_position: usize,
+    flag: E,
+) {
+    enforce_double_and_add_step_constrained(
+        result,
+        current,
+        next,
+        value_position,
+        bit_position,
+        flag,
+    );
+
+    // enforce that the binary input is indeed, binary
+    result.agg_constraint(bit_position, flag, is_binary(next[bit_position]));
+}
+
+/// Enforce a step of double-and-add in the field, with an
+/// already constrained binary input.
+pub(crate) fn enforce_double_and_add_step_constrained<E: FieldElement>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    value_position: usize,
+    bit_position: usize,
+    flag: E,
+) {
+    let mut step1 = current[value_position];
+    let step2 = next[value_position];
+
+    // We can directly add next[bit_position], as its value is either E::ZERO or E::ONE
+    step1 = step1.double() + next[bit_position];
+
+    // make sure that the results are equal
+    result.agg_constraint(value_position, flag, are_equal(step2, step1));
+}
+
+/// Enforce, at constraint `index`, the addition of the next bit weighted by `weight` to the
+/// accumulated value, which is carried over from the current step under `carry_flag` and
+/// starts over from zero otherwise. The bit register is assumed to be already constrained to
+/// be binary.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn enforce_weighted_step<E: FieldElement>(
+    result: &mut [E],
+    index: usize,
+    current: &[E],
+    next: &[E],
+    value_position: usize,
+    bit_position: usize,
+    carry_flag: E,
+    weight: E,
+) {
+    result.agg_constraint(
+        index,
+        E::ONE,
+        are_equal(
+            next[value_position],
+            carry_flag * current[value_position] + weight * next[bit_position],
+        ),
+    );
+}
+
+// PERIODIC MASKS
+// ================================================================================================
+
+/// Sets column `index` of `layout` to one over the `num_bits` steps, from the start of
+/// `segment`, inserting the bits of a value most significant first.
+pub(crate) fn place_step_mask(
+    layout: &mut PeriodicColumnSet,
+    index: usize,
+    segment: Segment,
+    num_bits: usize,
+) {
+    layout.mask(
+        index,
+        Segment::new(segment.start, num_bits),
+        BaseElement::ONE,
+    );
+}
+
+/// Sets column `index` of `layout` to one at the step of `segment` inserting the last of
+/// `num_bits` bits, most significant first, after which the value is fully accumulated.
+pub(crate) fn place_finish_mask(
+    layout: &mut PeriodicColumnSet,
+    index: usize,
+    segment: Segment,
+    num_bits: usize,
+) {
+    layout.set(index, segment.step(num_bits - 1), BaseElement::ONE);
+}
+
+/// Sets column `index` of `layout` to the weights of `num_bits` bits inserted least
+/// significant first, one every `period` steps of `segment`, at the steps preceding their
+/// insertion.
+pub(crate) fn place_weights(
+    layout: &mut PeriodicColumnSet,
+    index: usize,
+    segment: Segment,
+    num_bits: usize,
+    period: usize,
+) {
+    for bit in 0..num_bits {
+        layout.set(
+            index,
+            segment.step((bit + 1) * period - 1),
+            BaseElement::from(1u64 << bit),
+        );
+    }
+}
+
+// TESTS
+// ================================================================================================
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn bit_decomposition_gadget() {
+        use crate::periodic::{PeriodicColumnSet, Segment};
+        use crate::utils::bits;
+        use bitvec::{order::Lsb0, view::AsBits};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        // bits inserted most significant first over 8 steps, starting at step 4 of a 16-step cycle
+        let value_bytes = 0b1011_0110u64.to_le_bytes();
+        let value_bits = value_bytes.as_bits::<Lsb0>();
+        let segment = Segment::new(4, 8);
+        let mut layout = PeriodicColumnSet::new(3, 16);
+        bits::place_step_mask(&mut layout, 0, segment, 8);
+        bits::place_finish_mask(&mut layout, 1, segment, 8);
+        // bits inserted least significant first, one every 4 steps
+        bits::place_weights(&mut layout, 2, Segment::new(0, 16), 3, 4);
+        let columns = layout.into_columns();
+
+        let mut trace = vec![[BaseElement::ZERO; 4]; 16];
+        for step in 0..15 {
+            let mut state = trace[step];
+            if step >= segment.start && step < segment.end() {
+                bits::apply_decomposition_step(
+                    &mut state,
+                    0,
+                    1,
+                    value_bits,
+                    8,
+                    step - segment.start,
+                );
+            }
+            if columns[2][step] != BaseElement::ZERO {
+                state[3] = BaseElement::from((0b101u8 >> (step / 4)) & 1);
+                bits::apply_weighted_step(&mut state, 2, 3, columns[2][step]);
+            } else {
+                state[3] = BaseElement::ZERO;
+            }
+            trace[step + 1] = state;
+        }
+        assert_eq!(trace[segment.end()][0], BaseElement::new(0b1011_0110));
+        assert_eq!(trace[15][2], BaseElement::new(0b101));
+
+        let evaluate = |trace: &[[BaseElement; 4]]| {
+            (0..15).all(|step| {
+                let mut result = [BaseElement::ZERO; 3];
+                let (current, next) = (&trace[step], &trace[step + 1]);
+                bits::enforce_double_and_add_step(
+                    &mut result,
+                    current,
+                    next,
+                    0,
+                    1,
+                    columns[0][step],
+                );
+                bits::enforce_weighted_step(
+                    &mut result,
+                    2,
+                    current,
+                    next,
+                    2,
+                    3,
+                    BaseElement::ONE,
+                    columns[2][step],
+                );
+                result.iter().all(|&value| value == BaseElement::ZERO)
+                    && (columns[1][step] == BaseElement::ZERO
+                        || next[0] == BaseElement::new(0b1011_0110))
+            })
+        };
+        assert!(evaluate(&trace));
+
+        // a bit of two is rejected, even when accumulated as such
+        let mut altered = trace.clone();
+        altered[5][1] = BaseElement::from(2u8);
+        altered[5][0] = altered[4][0].double() + altered[5][1];
+        assert!(!evaluate(&altered));
+    }
+}
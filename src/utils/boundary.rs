@@ -0,0 +1,132 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Boundary assertions binding cells of an execution trace to public inputs.
+//!
+//! Most public inputs span several consecutive registers, e.g. the elements of a root or of a
+//! digest, or are spread over the cycles of the trace, one value per cycle, e.g. the bits of a
+//! leaf index along a Merkle path or the memos of transactions. These helpers build the
+//! assertions binding them, so that AIR programs only name the first register and step of
+//! each input, instead of offsetting registers and steps by hand.
+
+use winterfell::{
+    math::{fields::f63::BaseElement, FieldElement},
+    Assertion,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// SINGLE STEP
+// ================================================================================================
+
+/// Returns the assertions binding `values` to consecutive registers from `first_register`,
+/// at `step`, e.g. the elements of a root or of a digest.
+pub(crate) fn values_at(
+    first_register: usize,
+    step: usize,
+    values: &[BaseElement],
+) -> Vec<Assertion<BaseElement>> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(offset, &value)| Assertion::single(first_register + offset, step, value))
+        .collect()
+}
+
+/// Returns the assertions binding `width` consecutive registers from `first_register` to
+/// zero at `step`, e.g. the initial state of a running digest.
+pub(crate) fn zeros_at(
+    first_register: usize,
+    step: usize,
+    width: usize,
+) -> Vec<Assertion<BaseElement>> {
+    (0..width)
+        .map(|offset| Assertion::single(first_register + offset, step, BaseElement::ZERO))
+        .collect()
+}
+
+/// Returns the assertion binding `register` to `count` at `step`, e.g. a number of
+/// transactions, an amount, or a flag.
+pub(crate) fn count_at(register: usize, step: usize, count: u64) -> Assertion<BaseElement> {
+    Assertion::single(register, step, BaseElement::from(count))
+}
+
+/// Returns the assertions binding `register` to the `num_bits` low bits of `value`, least
+/// significant first, the bit of rank `i` being asserted at step `first_step + i * stride`.
+pub(crate) fn bits_at(
+    register: usize,
+    first_step: usize,
+    stride: usize,
+    value: usize,
+    num_bits: usize,
+) -> Vec<Assertion<BaseElement>> {
+    (0..num_bits)
+        .map(|i| Assertion::single(register, first_step + i * stride, bit(value, i)))
+        .collect()
+}
+
+// PERIODIC
+// ================================================================================================
+
+/// Returns the assertions binding `values` to consecutive registers from `first_register`,
+/// at `first_step` and every `period` steps after it.
+pub(crate) fn periodic_values_at(
+    first_register: usize,
+    first_step: usize,
+    period: usize,
+    values: &[BaseElement],
+) -> Vec<Assertion<BaseElement>> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(offset, &value)| {
+            Assertion::periodic(first_register + offset, first_step, period, value)
+        })
+        .collect()
+}
+
+/// Same as `bits_at`, the bits being asserted at the same steps of every `period` steps,
+/// e.g. the bits of a leaf index shared by every transaction of a batch.
+pub(crate) fn periodic_bits_at(
+    register: usize,
+    first_step: usize,
+    stride: usize,
+    period: usize,
+    value: usize,
+    num_bits: usize,
+) -> Vec<Assertion<BaseElement>> {
+    (0..num_bits)
+        .map(|i| Assertion::periodic(register, first_step + i * stride, period, bit(value, i)))
+        .collect()
+}
+
+/// Returns the assertions binding each of `columns` to consecutive registers from
+/// `first_register`, the `i`-th value of a column being asserted at step
+/// `first_step + i * period`, e.g. one value per transaction of a batch.
+pub(crate) fn sequences_at(
+    first_register: usize,
+    first_step: usize,
+    period: usize,
+    columns: Vec<Vec<BaseElement>>,
+) -> Vec<Assertion<BaseElement>> {
+    columns
+        .into_iter()
+        .enumerate()
+        .map(|(offset, values)| {
+            Assertion::sequence(first_register + offset, first_step, period, values)
+        })
+        .collect()
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn bit(value: usize, rank: usize) -> BaseElement {
+    BaseElement::from(((value >> rank) & 1) as u64)
+}
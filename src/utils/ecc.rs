@@ -6,7 +6,25 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use super::{are_equal, is_binary, not, EvaluationResult};
+//! Gadgets for the arithmetic of the cheetah curve, defined over the sextic extension Fp6 of
+//! the f63 field, to be embedded in AIR programs.
+//!
+//! Each gadget comes as a pair of a trace builder (`apply_*`) updating registers in place, and
+//! of a constraint evaluator (`enforce_*`) enforcing the same operation between two consecutive
+//! rows of the trace, whenever a given flag is set to one. Extension field elements span
+//! `POINT_COORDINATE_WIDTH` registers, and points are laid out as follows:
+//! - points in affine coordinates span `AFFINE_POINT_WIDTH` registers as [x, y],
+//! - points in projective coordinates span `PROJECTIVE_POINT_WIDTH` registers as [X, Y, Z],
+//!   and are followed in the state registers by a binary register, set to one when the
+//!   conditional additions must be performed, as in the double-and-add steps of a scalar
+//!   multiplication.
+//!
+//! The joint scalar multiplication s.G + h.P, computed with Shamir's trick along a single chain
+//! of doublings, spans `JOINT_STEP_WIDTH` registers: the accumulated point in projective
+//! coordinates, followed by the flag of the addition to come, the bits of s and h, and the
+//! point to be added in affine coordinates, i.e. G, P or G + P depending on both bits.
+
+use super::{are_equal, is_binary, is_zero, not, EvaluationResult};
 use winterfell::math::{fields::f63::BaseElement, FieldElement};
 
 // CONSTANTS
@@ -18,6 +36,8 @@ pub const POINT_COORDINATE_WIDTH: usize = 6;
 pub const AFFINE_POINT_WIDTH: usize = POINT_COORDINATE_WIDTH * 2;
 /// The length of a ProjectivePoint
 pub const PROJECTIVE_POINT_WIDTH: usize = POINT_COORDINATE_WIDTH * 3;
+/// The length of the registers of a joint scalar multiplication step
+pub const JOINT_STEP_WIDTH: usize = PROJECTIVE_POINT_WIDTH + 3 + AFFINE_POINT_WIDTH;
 
 /// Specifies the affine coordinates of the curve generator G
 pub const GENERATOR: [BaseElement; AFFINE_POINT_WIDTH] = [
@@ -35,6 +55,8 @@ pub const GENERATOR: [BaseElement; AFFINE_POINT_WIDTH] = [
     BaseElement::from_raw_unchecked(0xe13dca26b2ac6ab),
 ];
 
+/// Specifies the coefficient 3.B of the curve equation y^2 = x^3 + x + B, used by the
+/// complete addition formulas
 pub const B3: [BaseElement; POINT_COORDINATE_WIDTH] = [
     BaseElement::new(4580716109223965136),
     BaseElement::new(2805468717395796313),
@@ -47,30 +69,104 @@ pub const B3: [BaseElement; POINT_COORDINATE_WIDTH] = [
 // TRACE
 // ================================================================================================
 
-/// Apply a point doubling.
-pub(crate) fn apply_point_doubling(state: &mut [BaseElement]) {
+/// Apply a point doubling to the projective point stored in the `state` registers.
+pub fn apply_point_doubling(state: &mut [BaseElement]) {
     compute_double(state);
 }
 
-/// Apply a point addition between the current `state` registers with a given point.
-pub(crate) fn apply_point_addition(state: &mut [BaseElement], point: &[BaseElement]) {
+/// Apply a point addition between the current `state` registers with a given point in
+/// projective coordinates, if the register following the current point is set to one.
+pub fn apply_point_addition(state: &mut [BaseElement], point: &[BaseElement]) {
     if state[PROJECTIVE_POINT_WIDTH] == BaseElement::ONE {
         compute_add(state, point)
     };
 }
 
-/// Apply a point mixed addition between the current `state` registers with a given point.
-pub(crate) fn apply_point_addition_mixed(state: &mut [BaseElement], point: &[BaseElement]) {
+/// Apply a point mixed addition between the current `state` registers with a given point in
+/// affine coordinates, if the register following the current point is set to one.
+pub fn apply_point_addition_mixed(state: &mut [BaseElement], point: &[BaseElement]) {
     if state[PROJECTIVE_POINT_WIDTH] == BaseElement::ONE {
         compute_add_mixed(state, point)
     };
 }
 
+/// Apply a normalization to the projective point stored in the `state` registers, so that
+/// they hold its affine coordinates followed by a unit z coordinate, i.e. [x, y, 1].
+///
+/// # Panics
+/// Panics if `state` holds the point at infinity, which has no affine coordinates.
+pub fn apply_point_normalization(state: &mut [BaseElement]) {
+    assert!(
+        state[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH]
+            .iter()
+            .any(|&coord| coord != BaseElement::ZERO),
+        "the point at infinity cannot be normalized"
+    );
+    let affine_point = projective_to_affine(&state[..PROJECTIVE_POINT_WIDTH]);
+    state[..AFFINE_POINT_WIDTH].copy_from_slice(&affine_point);
+    state[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH]
+        .copy_from_slice(&[BaseElement::ZERO; POINT_COORDINATE_WIDTH]);
+    state[AFFINE_POINT_WIDTH] = BaseElement::ONE;
+}
+
+/// Returns the affine coordinates [x, y] = [X/Z, Y/Z] of the projective point [X, Y, Z].
+pub fn projective_to_affine(point: &[BaseElement]) -> [BaseElement; AFFINE_POINT_WIDTH] {
+    let z_inv = invert_fp6(&point[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH]);
+    let mut affine_point = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    affine_point[..POINT_COORDINATE_WIDTH]
+        .copy_from_slice(&mul_fp6(&point[..POINT_COORDINATE_WIDTH], &z_inv));
+    affine_point[POINT_COORDINATE_WIDTH..].copy_from_slice(&mul_fp6(
+        &point[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH],
+        &z_inv,
+    ));
+
+    affine_point
+}
+
+/// Apply the selection step of a joint scalar multiplication s.G + h.P, setting the registers
+/// following the accumulated point to the flag of the addition to come, i.e. s OR h, to the
+/// bits of s and h, and to the affine point to be added: G, P or G + P. The point is left to
+/// zero when both bits are zero, as nothing is added then.
+pub fn apply_joint_selection(
+    state: &mut [BaseElement],
+    s_bit: BaseElement,
+    h_bit: BaseElement,
+    point: &[BaseElement],
+) {
+    state[PROJECTIVE_POINT_WIDTH] = s_bit + h_bit - s_bit * h_bit;
+    state[PROJECTIVE_POINT_WIDTH + 1] = s_bit;
+    state[PROJECTIVE_POINT_WIDTH + 2] = h_bit;
+
+    let addend = &mut state[PROJECTIVE_POINT_WIDTH + 3..JOINT_STEP_WIDTH];
+    match (s_bit == BaseElement::ONE, h_bit == BaseElement::ONE) {
+        (true, false) => addend.copy_from_slice(&GENERATOR),
+        (false, true) => addend.copy_from_slice(&point[..AFFINE_POINT_WIDTH]),
+        (true, true) => addend.copy_from_slice(&projective_to_affine(&compute_joint_sum(point))),
+        (false, false) => addend.copy_from_slice(&[BaseElement::ZERO; AFFINE_POINT_WIDTH]),
+    }
+}
+
+/// Apply the addition step of a joint scalar multiplication, adding the point selected by
+/// `apply_joint_selection` to the accumulated point if any of both bits is set.
+pub fn apply_joint_addition(state: &mut [BaseElement]) {
+    let mut addend = [BaseElement::ZERO; AFFINE_POINT_WIDTH];
+    addend.copy_from_slice(&state[PROJECTIVE_POINT_WIDTH + 3..JOINT_STEP_WIDTH]);
+    apply_point_addition_mixed(state, &addend);
+}
+
+/// Apply a reduction of the projective point stored in the `state` registers, replacing its
+/// X coordinate by the affine x = X/Z, the other registers being left as is.
+pub fn apply_x_reduction(state: &mut [BaseElement]) {
+    let affine_point = projective_to_affine(&state[..PROJECTIVE_POINT_WIDTH]);
+    state[..POINT_COORDINATE_WIDTH].copy_from_slice(&affine_point[..POINT_COORDINATE_WIDTH]);
+}
+
 // CONSTRAINTS
 // ================================================================================================
 
-/// When flag = 1, enforces constraints for performing a point doubling.
-pub(crate) fn enforce_point_doubling<E: FieldElement + From<BaseElement>>(
+/// When flag = 1, enforces constraints for performing a point doubling, and that the register
+/// following the current point is binary.
+pub fn enforce_point_doubling<E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
@@ -97,9 +193,10 @@ pub(crate) fn enforce_point_doubling<E: FieldElement + From<BaseElement>>(
     );
 }
 
-/// When flag = 1, enforces constraints for performing a mixed point addition
-/// between current and point.
-pub(crate) fn enforce_point_addition_mixed<E: FieldElement + From<BaseElement>>(
+/// When flag = 1, enforces constraints for performing a point addition
+/// between current and point in projective coordinates, conditioned by the register
+/// following the current point, which is copied to the next step.
+pub fn enforce_point_addition<E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
@@ -112,7 +209,7 @@ pub(crate) fn enforce_point_addition_mixed<E: FieldElement + From<BaseElement>>(
     let mut step2 = [E::ZERO; PROJECTIVE_POINT_WIDTH];
     step2.copy_from_slice(&next[0..PROJECTIVE_POINT_WIDTH]);
 
-    compute_add_mixed(&mut step1, point);
+    compute_add(&mut step1, point);
     let adding_bit = current[PROJECTIVE_POINT_WIDTH];
 
     for i in 0..PROJECTIVE_POINT_WIDTH {
@@ -137,13 +234,10 @@ pub(crate) fn enforce_point_addition_mixed<E: FieldElement + From<BaseElement>>(
     );
 }
 
-/// When flag = 1, enforces constraints for performing a point addition
-/// between current and point in projective coordinates.
-///
-/// In the current implementation, this is being used only once, at the final step,
-/// so we add a division of register 0 by register 2 to obtain the final affine
-/// x coordinate (computations are being done internally in projective coordinates)
-pub(crate) fn enforce_point_addition_reduce_x<E: FieldElement + From<BaseElement>>(
+/// When flag = 1, enforces constraints for performing a mixed point addition
+/// between current and point, conditioned by the register following the current point,
+/// which is copied to the next step.
+pub fn enforce_point_addition_mixed<E: FieldElement + From<BaseElement>>(
     result: &mut [E],
     current: &[E],
     next: &[E],
@@ -156,24 +250,187 @@ pub(crate) fn enforce_point_addition_reduce_x<E: FieldElement + From<BaseElement
     let mut step2 = [E::ZERO; PROJECTIVE_POINT_WIDTH];
     step2.copy_from_slice(&next[0..PROJECTIVE_POINT_WIDTH]);
 
-    compute_add(&mut step1, point);
+    compute_add_mixed(&mut step1, point);
+    let adding_bit = current[PROJECTIVE_POINT_WIDTH];
+
+    for i in 0..PROJECTIVE_POINT_WIDTH {
+        result.agg_constraint(
+            i,
+            flag,
+            are_equal(
+                step2[i],
+                adding_bit * step1[i] + not(adding_bit) * current[i],
+            ),
+        );
+    }
+
+    // Ensure proper duplication of the binary decomposition
+    result.agg_constraint(
+        PROJECTIVE_POINT_WIDTH,
+        flag,
+        are_equal(
+            current[PROJECTIVE_POINT_WIDTH],
+            next[PROJECTIVE_POINT_WIDTH],
+        ),
+    );
+}
+
+/// When flag = 1, enforces constraints for a step of joint scalar multiplication s.G + h.P on
+/// the current registers, laid out as by `apply_joint_selection`:
+/// - the bits of s and h are binary, and the flag of the addition is set to s OR h,
+/// - the selected point is G if only s is set, `point` if only h is set, and G + `point` if
+///   both are set, the latter being checked against the projective sum of both points,
+/// - the next accumulated point is the sum of the current one and of the selected point when
+///   the flag of the addition is set, and a copy of the current one otherwise.
+///
+/// These constraints are of degree 5 when `point` is read from registers. The bits and the
+/// addition flag are left unconstrained on the next step. When `point` is the opposite of G,
+/// their sum is the point at infinity and any selected point satisfies the constraints, which
+/// does not weaken anything as the secret key q - 1 of this point is public.
+pub fn enforce_joint_addition<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    point: &[E],
+    flag: E,
+) {
+    let adding_flag = current[PROJECTIVE_POINT_WIDTH];
+    let s_bit = current[PROJECTIVE_POINT_WIDTH + 1];
+    let h_bit = current[PROJECTIVE_POINT_WIDTH + 2];
+    let addend = &current[PROJECTIVE_POINT_WIDTH + 3..JOINT_STEP_WIDTH];
+
+    // Enforce the bits to be binary, and the addition flag to be set when any of them is
+    result.agg_constraint(
+        PROJECTIVE_POINT_WIDTH,
+        flag,
+        are_equal(adding_flag, s_bit + h_bit - s_bit * h_bit),
+    );
+    result.agg_constraint(PROJECTIVE_POINT_WIDTH + 1, flag, is_binary(s_bit));
+    result.agg_constraint(PROJECTIVE_POINT_WIDTH + 2, flag, is_binary(h_bit));
+
+    // Enforce the selected point to be G, P or G + P, the latter in affine coordinates
+    let sum = compute_joint_sum(point);
+    let sum_z = &sum[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH];
+    let x_z = mul_fp6(&addend[..POINT_COORDINATE_WIDTH], sum_z);
+    let y_z = mul_fp6(&addend[POINT_COORDINATE_WIDTH..], sum_z);
+    let s_only = s_bit * not(h_bit);
+    let h_only = not(s_bit) * h_bit;
+    let both = s_bit * h_bit;
+    for i in 0..AFFINE_POINT_WIDTH {
+        let scaled_sum = if i < POINT_COORDINATE_WIDTH {
+            x_z[i]
+        } else {
+            y_z[i - POINT_COORDINATE_WIDTH]
+        };
+        result.agg_constraint(
+            PROJECTIVE_POINT_WIDTH + 3 + i,
+            flag,
+            s_only * are_equal(addend[i], E::from(GENERATOR[i]))
+                + h_only * are_equal(addend[i], point[i])
+                + both * are_equal(scaled_sum, sum[i]),
+        );
+    }
 
+    // Enforce the conditional addition of the selected point
+    let mut step = [E::ZERO; PROJECTIVE_POINT_WIDTH];
+    step.copy_from_slice(&current[..PROJECTIVE_POINT_WIDTH]);
+    compute_add_mixed(&mut step, addend);
+    for i in 0..PROJECTIVE_POINT_WIDTH {
+        result.agg_constraint(
+            i,
+            flag,
+            are_equal(
+                next[i],
+                adding_flag * step[i] + not(adding_flag) * current[i],
+            ),
+        );
+    }
+}
+
+/// When flag = 1, enforces the x-coordinate of the next step to be the affine x-coordinate
+/// X/Z of the projective point of the current step, through constraints of degree 2 on the
+/// `POINT_COORDINATE_WIDTH` first registers. The point at infinity, whose z coordinate is zero,
+/// satisfies them whatever the next step.
+pub fn enforce_x_reduction<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    flag: E,
+) {
     let x_z = mul_fp6(
-        &step2[0..POINT_COORDINATE_WIDTH],
-        &step1[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH],
+        &next[..POINT_COORDINATE_WIDTH],
+        &current[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH],
     );
+    for i in 0..POINT_COORDINATE_WIDTH {
+        result.agg_constraint(i, flag, are_equal(x_z[i], current[i]));
+    }
+}
 
+/// When flag = 1, enforces constraints for normalizing the projective point of the current
+/// step, i.e. that the next step holds its affine coordinates followed by a unit z coordinate,
+/// as [x, y, 1].
+///
+/// This spans `PROJECTIVE_POINT_WIDTH` registers of both steps and as many constraints, of
+/// degree 2 in addition to the flag. The point at infinity, whose z coordinate is zero,
+/// satisfies these constraints whatever the next step, and must hence be excluded by the
+/// calling AIR program.
+pub fn enforce_point_normalization<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    flag: E,
+) {
+    let z = &current[AFFINE_POINT_WIDTH..PROJECTIVE_POINT_WIDTH];
+
+    // Make sure that x.Z = X and y.Z = Y
+    let x_z = mul_fp6(&next[..POINT_COORDINATE_WIDTH], z);
+    let y_z = mul_fp6(&next[POINT_COORDINATE_WIDTH..AFFINE_POINT_WIDTH], z);
     for i in 0..POINT_COORDINATE_WIDTH {
-        result.agg_constraint(i, flag, are_equal(x_z[i], step1[i]));
+        result.agg_constraint(i, flag, are_equal(x_z[i], current[i]));
+        result.agg_constraint(
+            POINT_COORDINATE_WIDTH + i,
+            flag,
+            are_equal(y_z[i], current[POINT_COORDINATE_WIDTH + i]),
+        );
     }
-    for i in POINT_COORDINATE_WIDTH..PROJECTIVE_POINT_WIDTH {
-        result.agg_constraint(i, flag, are_equal(step2[i], step1[i]));
+
+    // Enforce that the next z coordinate is one
+    result.agg_constraint(
+        AFFINE_POINT_WIDTH,
+        flag,
+        are_equal(next[AFFINE_POINT_WIDTH], E::ONE),
+    );
+    for (i, &coord) in next
+        .iter()
+        .enumerate()
+        .take(PROJECTIVE_POINT_WIDTH)
+        .skip(AFFINE_POINT_WIDTH + 1)
+    {
+        result.agg_constraint(i, flag, is_zero(coord));
     }
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
 
+/// Returns the sum G + P of the generator and of the provided point in affine coordinates,
+/// as a point in projective coordinates whose coordinates are of degree 2 in the ones of P.
+#[inline(always)]
+fn compute_joint_sum<E: FieldElement + From<BaseElement>>(
+    point: &[E],
+) -> [E; PROJECTIVE_POINT_WIDTH] {
+    let mut sum = [E::ZERO; PROJECTIVE_POINT_WIDTH];
+    sum[..AFFINE_POINT_WIDTH].copy_from_slice(&point[..AFFINE_POINT_WIDTH]);
+    sum[AFFINE_POINT_WIDTH] = E::ONE;
+    let mut generator = [E::ZERO; AFFINE_POINT_WIDTH];
+    for (coord, &value) in generator.iter_mut().zip(GENERATOR.iter()) {
+        *coord = E::from(value);
+    }
+    compute_add_mixed(&mut sum, &generator);
+
+    sum
+}
+
 /// Compute the double of the current point, stored as [X,Y,Z].
 /// Doubling is computed as:
 ///
@@ -465,10 +722,9 @@ pub(crate) fn neg_fp2<E: FieldElement + From<BaseElement>>(a: &[E]) -> [E; 2] {
     [a[0].neg(), a[1].neg()]
 }
 
+/// Returns the square of `a` in Fp6.
 #[inline(always)]
-pub(crate) fn square_fp6<E: FieldElement + From<BaseElement>>(
-    a: &[E],
-) -> [E; POINT_COORDINATE_WIDTH] {
+pub fn square_fp6<E: FieldElement + From<BaseElement>>(a: &[E]) -> [E; POINT_COORDINATE_WIDTH] {
     let self_c0 = &a[0..2];
     let self_c1 = &a[2..4];
     let self_c2 = &a[4..POINT_COORDINATE_WIDTH];
@@ -502,8 +758,9 @@ pub(crate) fn square_fp6<E: FieldElement + From<BaseElement>>(
     [c0[0], c0[1], c1[0], c1[1], c2[0], c2[1]]
 }
 
+/// Returns the product of `a` and `b` in Fp6.
 #[inline(always)]
-pub(crate) fn mul_fp6<E: FieldElement + From<BaseElement>>(
+pub fn mul_fp6<E: FieldElement + From<BaseElement>>(
     a: &[E],
     b: &[E],
 ) -> [E; POINT_COORDINATE_WIDTH] {
@@ -547,8 +804,9 @@ pub(crate) fn mul_fp6<E: FieldElement + From<BaseElement>>(
     [c0[0], c0[1], c1[0], c1[1], c2[0], c2[1]]
 }
 
+/// Returns the inverse of `a` in Fp6, or zero if `a` is zero.
 #[inline(always)]
-pub(crate) fn invert_fp6(a: &[BaseElement]) -> [BaseElement; POINT_COORDINATE_WIDTH] {
+pub fn invert_fp6(a: &[BaseElement]) -> [BaseElement; POINT_COORDINATE_WIDTH] {
     let self_c0 = &a[0..2];
     let self_c1 = &a[2..4];
     let self_c2 = &a[4..POINT_COORDINATE_WIDTH];
@@ -590,8 +848,9 @@ pub(crate) fn invert_fp6(a: &[BaseElement]) -> [BaseElement; POINT_COORDINATE_WI
     [c0[0], c0[1], c1[0], c1[1], c2[0], c2[1]]
 }
 
+/// Returns the sum of `a` and `b` in Fp6.
 #[inline(always)]
-pub(crate) fn add_fp6<E: FieldElement + From<BaseElement>>(
+pub fn add_fp6<E: FieldElement + From<BaseElement>>(
     a: &[E],
     b: &[E],
 ) -> [E; POINT_COORDINATE_WIDTH] {
@@ -605,10 +864,9 @@ pub(crate) fn add_fp6<E: FieldElement + From<BaseElement>>(
     ]
 }
 
+/// Returns the double of `a` in Fp6.
 #[inline(always)]
-pub(crate) fn double_fp6<E: FieldElement + From<BaseElement>>(
-    a: &[E],
-) -> [E; POINT_COORDINATE_WIDTH] {
+pub fn double_fp6<E: FieldElement + From<BaseElement>>(a: &[E]) -> [E; POINT_COORDINATE_WIDTH] {
     [
         a[0].double(),
         a[1].double(),
@@ -619,8 +877,9 @@ pub(crate) fn double_fp6<E: FieldElement + From<BaseElement>>(
     ]
 }
 
+/// Returns the difference of `a` and `b` in Fp6.
 #[inline(always)]
-pub(crate) fn sub_fp6<E: FieldElement + From<BaseElement>>(
+pub fn sub_fp6<E: FieldElement + From<BaseElement>>(
     a: &[E],
     b: &[E],
 ) -> [E; POINT_COORDINATE_WIDTH] {
@@ -636,7 +895,8 @@ pub(crate) fn sub_fp6<E: FieldElement + From<BaseElement>>(
 
 #[inline(always)]
 #[allow(unused)]
-pub(crate) fn neg_fp6<E: FieldElement + From<BaseElement>>(a: &[E]) -> [E; POINT_COORDINATE_WIDTH] {
+/// Returns the negation of `a` in Fp6.
+pub fn neg_fp6<E: FieldElement + From<BaseElement>>(a: &[E]) -> [E; POINT_COORDINATE_WIDTH] {
     [
         a[0].neg(),
         a[1].neg(),
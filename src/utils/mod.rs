@@ -11,12 +11,15 @@ use winterfell::math::FieldElement;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-/// An elliptic curve group operation utility module
-pub(crate) mod ecc;
-/// A field operation utility module
-pub(crate) mod field;
-/// A periodic values utility module
-pub(crate) mod periodic_columns;
+/// A binary decomposition gadget module
+pub(crate) mod bits;
+/// A boundary assertion helper module
+pub(crate) mod boundary;
+/// An elliptic curve group operation gadget module
+// Public for reuse of the curve arithmetic by other AIR programs
+pub mod ecc;
+/// The Poseidon utility module
+pub mod poseidon;
 /// The Rescue-Prime utility module
 // Public for benchmarking purposes
 pub mod rescue;
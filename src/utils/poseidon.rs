@@ -0,0 +1,1256 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Poseidon hash function over F63, with α = 3.
+//!
+//! Contrary to Rescue, Poseidon does not rely on the inverse S-box, and hence cannot enforce
+//! two half rounds per step. Each step of its AIR round function enforces a single round,
+//! so that a permutation spans `NUM_HASH_ROUNDS` steps over a cycle of `HASH_CYCLE_LENGTH`
+//! steps. The Merkle and Schnorr sub-AIR programs are laid out around the 8-step cycle of
+//! Rescue, and Poseidon can therefore not be plugged in as a `merkle::TreeHasher` until
+//! their hash cycle length is made configurable.
+
+use super::rescue::Hash;
+use super::{are_equal, EvaluationResult};
+use winterfell::{
+    crypto::Hasher,
+    math::{fields::f63::BaseElement, FieldElement},
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Function state is set to 14 field elements or 112 bytes; 7 elements are reserved for rate
+/// and 7 elements are reserved for capacity.
+pub const STATE_WIDTH: usize = 14;
+/// The rate of the Poseidon hash.
+pub const RATE_WIDTH: usize = 7;
+
+/// The number of full rounds, half of which are applied before the partial rounds, and
+/// the other half after them.
+pub const NUM_FULL_ROUNDS: usize = 8;
+/// The number of partial rounds, which apply the S-box to the first state element only.
+pub const NUM_PARTIAL_ROUNDS: usize = 41;
+/// The number of rounds provides 128-bit security, including the security margin of two full
+/// rounds and 7.5% partial rounds recommended in <https://eprint.iacr.org/2019/458.pdf>
+pub const NUM_HASH_ROUNDS: usize = NUM_FULL_ROUNDS + NUM_PARTIAL_ROUNDS;
+
+/// Minimum cycle length required to describe Poseidon permutation.
+pub const HASH_CYCLE_LENGTH: usize = 64;
+
+/// Specifies steps on which Poseidon transition function is applied.
+pub const HASH_CYCLE_MASK: [BaseElement; HASH_CYCLE_LENGTH] = build_cycle_mask();
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Implementation of Poseidon over F63, whose digests are represented as Rescue63 ones
+#[derive(Clone, Debug)]
+pub struct Poseidon63 {
+    state: [BaseElement; STATE_WIDTH],
+    idx: usize,
+}
+
+// Poseidon63 IMPLEMENTATION
+// ================================================================================================
+
+impl Poseidon63 {
+    /// Returns a new hasher with the state initialized to all zeros.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Poseidon63 {
+            state: [BaseElement::ZERO; STATE_WIDTH],
+            idx: 0,
+        }
+    }
+
+    /// Absorbs data into the hasher state.
+    pub fn update(&mut self, data: &[BaseElement]) {
+        for &element in data {
+            self.state[self.idx] += element;
+            self.idx += 1;
+            if self.idx == RATE_WIDTH {
+                apply_permutation(&mut self.state);
+                self.idx = 0;
+            }
+        }
+    }
+
+    /// Returns hash of the data absorbed into the hasher.
+    pub fn finalize(mut self) -> Hash {
+        // Data is absorbed as with Rescue63, without padding
+        if self.idx > 0 {
+            apply_permutation(&mut self.state);
+        }
+        Hash::new(
+            self.state[0],
+            self.state[1],
+            self.state[2],
+            self.state[3],
+            self.state[4],
+            self.state[5],
+            self.state[6],
+        )
+    }
+
+    /// Returns hash of the provided data.
+    pub fn digest(data: &[BaseElement]) -> Hash {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+// HASHER IMPLEMENTATION
+// ================================================================================================
+
+impl Hasher for Poseidon63 {
+    type Digest = Hash;
+
+    fn hash(_bytes: &[u8]) -> Self::Digest {
+        unimplemented!("not implemented")
+    }
+
+    fn merge(values: &[Self::Digest; 2]) -> Self::Digest {
+        let mut state = [BaseElement::ZERO; STATE_WIDTH];
+        state[..STATE_WIDTH - RATE_WIDTH].copy_from_slice(&values[0].to_elements());
+        state[STATE_WIDTH - RATE_WIDTH..STATE_WIDTH].copy_from_slice(&values[1].to_elements());
+        apply_permutation(&mut state);
+
+        Hash::new(
+            state[0], state[1], state[2], state[3], state[4], state[5], state[6],
+        )
+    }
+
+    fn merge_with_int(_seed: Self::Digest, _value: u64) -> Self::Digest {
+        unimplemented!("not implemented")
+    }
+}
+
+// POSEIDON PERMUTATION
+// ================================================================================================
+
+/// Applies Poseidon permutation to the provided state.
+pub fn apply_permutation(state: &mut [BaseElement; STATE_WIDTH]) {
+    for i in 0..NUM_HASH_ROUNDS {
+        apply_round(state, i);
+    }
+}
+
+/// Poseidon round function, applied at the provided step of the hash cycle, which must be
+/// one of its first `NUM_HASH_ROUNDS` steps.
+#[inline(always)]
+pub fn apply_round(state: &mut [BaseElement], step: usize) {
+    let round = step % HASH_CYCLE_LENGTH;
+    let ark = ARK[round];
+
+    for i in 0..STATE_WIDTH {
+        state[i] += ark[i];
+    }
+    if is_full_round(round) {
+        apply_sbox(state);
+    } else {
+        state[0] *= state[0].square();
+    }
+    apply_mds(state);
+}
+
+// CONSTRAINTS
+// ================================================================================================
+
+/// when flag = 1, enforces constraints for a single round of Poseidon hash functions, `ark`
+/// holding the round constants followed by the flags of the state elements going through
+/// the S-box, as output by `get_round_constants`
+pub fn enforce_round<E: FieldElement + From<BaseElement>>(
+    result: &mut [E],
+    current: &[E],
+    next: &[E],
+    ark: &[E],
+    flag: E,
+) {
+    // compute the state that should result from applying the round to the current state
+    // of the computation, the S-box being skipped on elements whose flag is zero
+    let mut step = [E::ZERO; STATE_WIDTH];
+    for i in 0..STATE_WIDTH {
+        let element = current[i] + ark[i];
+        step[i] = element + ark[STATE_WIDTH + i] * (element * element.square() - element);
+    }
+    apply_mds(&mut step);
+
+    // make sure that the results are equal
+    for i in 0..STATE_WIDTH {
+        result.agg_constraint(i, flag, are_equal(next[i], step[i]));
+    }
+}
+
+// ROUND CONSTANTS
+// ================================================================================================
+
+/// Returns Poseidon round constants arranged in column-major form, followed by the columns
+/// flagging the state elements going through the S-box at each round.
+pub fn get_round_constants() -> Vec<Vec<BaseElement>> {
+    let mut constants = Vec::new();
+    for _ in 0..(STATE_WIDTH * 2) {
+        constants.push(vec![BaseElement::ZERO; HASH_CYCLE_LENGTH]);
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    for i in 0..NUM_HASH_ROUNDS {
+        for j in 0..STATE_WIDTH {
+            constants[j][i] = ARK[i][j];
+            if j == 0 || is_full_round(i) {
+                constants[STATE_WIDTH + j][i] = BaseElement::ONE;
+            }
+        }
+    }
+
+    constants
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns true if the round at the provided index applies the S-box to the whole state.
+#[inline(always)]
+fn is_full_round(round: usize) -> bool {
+    !(NUM_FULL_ROUNDS / 2..NUM_FULL_ROUNDS / 2 + NUM_PARTIAL_ROUNDS).contains(&round)
+}
+
+const fn build_cycle_mask() -> [BaseElement; HASH_CYCLE_LENGTH] {
+    let mut mask = [BaseElement::ZERO; HASH_CYCLE_LENGTH];
+    let mut i = 0;
+    while i < NUM_HASH_ROUNDS {
+        mask[i] = BaseElement::ONE;
+        i += 1;
+    }
+
+    mask
+}
+
+#[inline(always)]
+#[allow(clippy::needless_range_loop)]
+fn apply_sbox<E: FieldElement>(state: &mut [E]) {
+    for i in 0..STATE_WIDTH {
+        // ALPHA = 3 hence rely on faster exponentiation
+        // through squaring and multiplication
+        state[i] *= state[i].square();
+    }
+}
+
+#[inline(always)]
+#[allow(clippy::needless_range_loop)]
+fn apply_mds<E: FieldElement + From<BaseElement>>(state: &mut [E]) {
+    let mut result = [E::ZERO; STATE_WIDTH];
+    let mut temp = [E::ZERO; STATE_WIDTH];
+    for i in 0..STATE_WIDTH {
+        for j in 0..STATE_WIDTH {
+            temp[j] = E::from(MDS[i * STATE_WIDTH + j]) * state[j];
+        }
+
+        for j in 0..STATE_WIDTH {
+            result[i] += temp[j];
+        }
+    }
+    state.copy_from_slice(&result);
+}
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum distance separable matrix, defined as the Cauchy matrix (1 / (x_i + y_j)) with
+/// x_i = i and y_j = STATE_WIDTH + j
+const MDS: [BaseElement; STATE_WIDTH * STATE_WIDTH] = [
+    BaseElement::new(0x1764924924924925),
+    BaseElement::new(0x45dddddddddddde),
+    BaseElement::new(0x3d68000000000001),
+    BaseElement::new(0x2e3c3c3c3c3c3c3d),
+    BaseElement::new(0x2f4e38e38e38e38f),
+    BaseElement::new(0x2cd0d79435e50d7a),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x45dddddddddddde),
+    BaseElement::new(0x3d68000000000001),
+    BaseElement::new(0x2e3c3c3c3c3c3c3d),
+    BaseElement::new(0x2f4e38e38e38e38f),
+    BaseElement::new(0x2cd0d79435e50d7a),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0x3d68000000000001),
+    BaseElement::new(0x2e3c3c3c3c3c3c3d),
+    BaseElement::new(0x2f4e38e38e38e38f),
+    BaseElement::new(0x2cd0d79435e50d7a),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x2e3c3c3c3c3c3c3d),
+    BaseElement::new(0x2f4e38e38e38e38f),
+    BaseElement::new(0x2cd0d79435e50d7a),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x2f4e38e38e38e38f),
+    BaseElement::new(0x2cd0d79435e50d7a),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x2cd0d79435e50d7a),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x2406666666666667),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x3b430c30c30c30c4),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0xee2e8ba2e8ba2e9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0x30a83a83a83a83a9),
+    BaseElement::new(0xb642c8590b21643),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0x30a83a83a83a83a9),
+    BaseElement::new(0x38671c71c71c71c8),
+    BaseElement::new(0x131aaaaaaaaaaaab),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0x30a83a83a83a83a9),
+    BaseElement::new(0x38671c71c71c71c8),
+    BaseElement::new(0x21a2983759f22984),
+    BaseElement::new(0x1cd1eb851eb851ec),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0x30a83a83a83a83a9),
+    BaseElement::new(0x38671c71c71c71c8),
+    BaseElement::new(0x21a2983759f22984),
+    BaseElement::new(0x16686bca1af286bd),
+    BaseElement::new(0x284ec4ec4ec4ec5),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0x30a83a83a83a83a9),
+    BaseElement::new(0x38671c71c71c71c8),
+    BaseElement::new(0x21a2983759f22984),
+    BaseElement::new(0x16686bca1af286bd),
+    BaseElement::new(0x1adf2df2df2df2e),
+    BaseElement::new(0x9b425ed097b425f),
+    BaseElement::new(0x2c72492492492493),
+    BaseElement::new(0xb4b08d3dcb08d3e),
+    BaseElement::new(0x22eeeeeeeeeeeef),
+    BaseElement::new(0x390c6318c6318c64),
+    BaseElement::new(0x3f74000000000001),
+    BaseElement::new(0x1fc1f07c1f07c1f1),
+    BaseElement::new(0x37de1e1e1e1e1e1f),
+    BaseElement::new(0x30a83a83a83a83a9),
+    BaseElement::new(0x38671c71c71c71c8),
+    BaseElement::new(0x21a2983759f22984),
+    BaseElement::new(0x16686bca1af286bd),
+    BaseElement::new(0x1adf2df2df2df2e),
+    BaseElement::new(0x32c3333333333334),
+];
+
+/// The additive round constants (ARK), generated with the Grain LFSR as specified in
+/// <https://eprint.iacr.org/2019/458.pdf>
+pub const ARK: [[BaseElement; STATE_WIDTH]; NUM_HASH_ROUNDS] = [
+    [
+        BaseElement::new(0x195b7de7bb383fc1),
+        BaseElement::new(0xc3fe932b33f7335),
+        BaseElement::new(0x29fec78c02493be8),
+        BaseElement::new(0x362f9ac381b7e703),
+        BaseElement::new(0x2e2cb3cf737e725f),
+        BaseElement::new(0xa93e3c17ff83ec6),
+        BaseElement::new(0xe84b30d025fc543),
+        BaseElement::new(0xc40c35cc1ddb055),
+        BaseElement::new(0x3783d10b0f7b163),
+        BaseElement::new(0x2e04b9164060a309),
+        BaseElement::new(0x39850731faf266b),
+        BaseElement::new(0x1ece87c856ccd8f),
+        BaseElement::new(0xd340cc4561a8c65),
+        BaseElement::new(0x7d232d79380cd5f),
+    ],
+    [
+        BaseElement::new(0x16f3c4b6adf81795),
+        BaseElement::new(0xca62709ec793661),
+        BaseElement::new(0x3085def935bd29c1),
+        BaseElement::new(0x1494d34601567468),
+        BaseElement::new(0x14839ea04063f094),
+        BaseElement::new(0x358bb2c82a10a8b3),
+        BaseElement::new(0x35f013003138038a),
+        BaseElement::new(0x2ea13875d03c08f1),
+        BaseElement::new(0x28824245743bff18),
+        BaseElement::new(0x310442252e7222ef),
+        BaseElement::new(0x231825af4200909e),
+        BaseElement::new(0x13deb8266f2f2138),
+        BaseElement::new(0x2b0927c491c0e676),
+        BaseElement::new(0x361cd58d02308d77),
+    ],
+    [
+        BaseElement::new(0x2915d0e60b208dc1),
+        BaseElement::new(0x333fe9efd91eb5),
+        BaseElement::new(0x2271dd889363983d),
+        BaseElement::new(0xd7706a0d4272da1),
+        BaseElement::new(0x412b9d58f4c5d645),
+        BaseElement::new(0x38fad5f68d93428b),
+        BaseElement::new(0x178f6d59141f3ad3),
+        BaseElement::new(0xa89516dee618076),
+        BaseElement::new(0x23a078651e4ed385),
+        BaseElement::new(0x3ff633c4f694b5d1),
+        BaseElement::new(0x91428fd70b7e9bd),
+        BaseElement::new(0x3a62d324d91ab7cc),
+        BaseElement::new(0xbfdf935c91b5368),
+        BaseElement::new(0xbcb442e49b09304),
+    ],
+    [
+        BaseElement::new(0x9ed32f93ec93e51),
+        BaseElement::new(0x95fccffb4865e97),
+        BaseElement::new(0x305c2295bc238f42),
+        BaseElement::new(0x8c9d2d9ff5ea2ce),
+        BaseElement::new(0xdbc358fe66e8d28),
+        BaseElement::new(0xff081af5f33a61b),
+        BaseElement::new(0x244a5138b22e81e),
+        BaseElement::new(0xb3099007eea2ac6),
+        BaseElement::new(0x153536827a614b68),
+        BaseElement::new(0x3274cbb34bf80fe6),
+        BaseElement::new(0x20fc553f52db5c4f),
+        BaseElement::new(0x1153a4935703df77),
+        BaseElement::new(0x1fdb242622cb2933),
+        BaseElement::new(0x2fa2db92c5c20a99),
+    ],
+    [
+        BaseElement::new(0x21b50a8f9dcc05cc),
+        BaseElement::new(0x56ca335a5e741fb),
+        BaseElement::new(0x15c5fe23bb65c327),
+        BaseElement::new(0x1df5344fed95063d),
+        BaseElement::new(0xa175ca69f5f2500),
+        BaseElement::new(0x3feb33da99e5b067),
+        BaseElement::new(0x2aa5d6150dbeac46),
+        BaseElement::new(0x1fd7f50c1260d4ef),
+        BaseElement::new(0x2fd24e03bdc91c9),
+        BaseElement::new(0x1caa52fd55ffc4d1),
+        BaseElement::new(0x32ec4506e1ffcb7e),
+        BaseElement::new(0xb18ca395ed9723b),
+        BaseElement::new(0xc0237757b3347dc),
+        BaseElement::new(0x9c5a6e309530fc8),
+    ],
+    [
+        BaseElement::new(0x1648716c01f0f906),
+        BaseElement::new(0x1c99410ff6048e0f),
+        BaseElement::new(0x26cb4ebfb52f81cd),
+        BaseElement::new(0x8dc863020cb9b90),
+        BaseElement::new(0x303ac3c4af52b03e),
+        BaseElement::new(0x3cb94156d1c97d85),
+        BaseElement::new(0x180aa8d72cfcce1d),
+        BaseElement::new(0x33a1170140841097),
+        BaseElement::new(0x39b3b80e11277f14),
+        BaseElement::new(0x1196c15691a57558),
+        BaseElement::new(0x3ee076f9f2060b94),
+        BaseElement::new(0x28eb5c2c0402a0ac),
+        BaseElement::new(0x1fd30eff26a36abb),
+        BaseElement::new(0x14ae7b183e512a60),
+    ],
+    [
+        BaseElement::new(0x13538d6f6b2b510e),
+        BaseElement::new(0xc9cc199dc7c31e4),
+        BaseElement::new(0x16f56212bc5de649),
+        BaseElement::new(0xd092bebc8322eb0),
+        BaseElement::new(0x3fb24f8929a0c8c),
+        BaseElement::new(0x1cdf50f71ada5bc2),
+        BaseElement::new(0x97606863c8ab477),
+        BaseElement::new(0x2f8cdd291fd15b52),
+        BaseElement::new(0x135c73e81e96df66),
+        BaseElement::new(0x1b6ef848a678b83c),
+        BaseElement::new(0x3481c701d49a3199),
+        BaseElement::new(0xf12ef8966dbad6e),
+        BaseElement::new(0x2a80e5fce1a7e59f),
+        BaseElement::new(0x3a80316ee94f67cc),
+    ],
+    [
+        BaseElement::new(0x1a788d0663d0b5c8),
+        BaseElement::new(0x15278aa05b0d1162),
+        BaseElement::new(0x180714519d7cd773),
+        BaseElement::new(0x98422402657637a),
+        BaseElement::new(0x20a4c124ac7419e2),
+        BaseElement::new(0x2f307ac3cce1051b),
+        BaseElement::new(0x24405dbfece0d3ce),
+        BaseElement::new(0x27ab221c81246b8a),
+        BaseElement::new(0x2f18af294c3fe5c6),
+        BaseElement::new(0x364cd3806f245971),
+        BaseElement::new(0x1893cba249c95c73),
+        BaseElement::new(0x3b12d8f2c98a6db0),
+        BaseElement::new(0x1ab4e7f84fc4bbd6),
+        BaseElement::new(0x79ed50e77c7c41d),
+    ],
+    [
+        BaseElement::new(0x391a7d497ae84ddf),
+        BaseElement::new(0x1adb4a149a2cc4d5),
+        BaseElement::new(0x1a25c03646d37b6a),
+        BaseElement::new(0x9ebaff8d9dedd53),
+        BaseElement::new(0x30f90b22f26d830),
+        BaseElement::new(0x1f14b7c2cce490de),
+        BaseElement::new(0x1f134c9fa9200af1),
+        BaseElement::new(0x36192776f376d60a),
+        BaseElement::new(0x27563e0a75cbe7a4),
+        BaseElement::new(0xc6371f7c2e253b5),
+        BaseElement::new(0x8d82b38a9eb352),
+        BaseElement::new(0x3cfee1f0def9f360),
+        BaseElement::new(0x2b02ff8fa418ba3c),
+        BaseElement::new(0x12f3d0a42dcac018),
+    ],
+    [
+        BaseElement::new(0x1f8b36cbef13a563),
+        BaseElement::new(0x13ac9b0bf5192da2),
+        BaseElement::new(0x369d5b8eaaf096b2),
+        BaseElement::new(0xdb5033e2314f943),
+        BaseElement::new(0x146f3905c200ba95),
+        BaseElement::new(0x18fd36db72290795),
+        BaseElement::new(0xe8369da119f425f),
+        BaseElement::new(0x30a708b4e227e006),
+        BaseElement::new(0x3654fba465d0abe1),
+        BaseElement::new(0x1a3b3c98b9918980),
+        BaseElement::new(0x1e70f91f15330c6c),
+        BaseElement::new(0x134c4207952e8d2e),
+        BaseElement::new(0xc66fa9663bff433),
+        BaseElement::new(0x27ccb5f5b1fed419),
+    ],
+    [
+        BaseElement::new(0xd4386c679045bf9),
+        BaseElement::new(0x13543a45a570d802),
+        BaseElement::new(0x22f11f737983430c),
+        BaseElement::new(0x32f1e0b53e077d3c),
+        BaseElement::new(0x3f00d5280fba163d),
+        BaseElement::new(0xd16c4e70c2b15e4),
+        BaseElement::new(0x36c19ad550c77145),
+        BaseElement::new(0x46bbc82a8bb6b55),
+        BaseElement::new(0x958cac6de133a99),
+        BaseElement::new(0x2cb14b8c5c8ef6f9),
+        BaseElement::new(0x14ae394cace31050),
+        BaseElement::new(0x30f8b667d8e78ba),
+        BaseElement::new(0x1c9ff5f345a52fd8),
+        BaseElement::new(0x2ed683c40da686e3),
+    ],
+    [
+        BaseElement::new(0x2090070e5bb89fa5),
+        BaseElement::new(0x24a469236686ee71),
+        BaseElement::new(0x1eb2b69dc4489b2f),
+        BaseElement::new(0x1245015f87a214a0),
+        BaseElement::new(0x3d7a1b4338cfae4b),
+        BaseElement::new(0x37eb8c8637ffb9af),
+        BaseElement::new(0x828e8222d90c7d6),
+        BaseElement::new(0x394e107d37522412),
+        BaseElement::new(0x3dac22d4357ac18a),
+        BaseElement::new(0x3b0c7e8a5eddef68),
+        BaseElement::new(0x28dc53eb1d2f3b76),
+        BaseElement::new(0x35955abe26291211),
+        BaseElement::new(0x6a61f9707aadbfb),
+        BaseElement::new(0x3f01bc82db536bc1),
+    ],
+    [
+        BaseElement::new(0x10d758510ffdc7c4),
+        BaseElement::new(0x204b37f770de9e65),
+        BaseElement::new(0x28e844820d9c19f2),
+        BaseElement::new(0x18bf88c29174e875),
+        BaseElement::new(0x21424dacbf342005),
+        BaseElement::new(0x1b00f69237f5a04f),
+        BaseElement::new(0x45aee6294792813),
+        BaseElement::new(0x275b6efa5153c720),
+        BaseElement::new(0x27ec6182fa63e3a2),
+        BaseElement::new(0x770f26eb5cae7a8),
+        BaseElement::new(0x1db4280e358e842a),
+        BaseElement::new(0x7187b0fd8898733),
+        BaseElement::new(0x20b2a5989fb789a7),
+        BaseElement::new(0x36d76aad6348d6ba),
+    ],
+    [
+        BaseElement::new(0x36f28e5957a4dc60),
+        BaseElement::new(0xc1bc8507261d841),
+        BaseElement::new(0x1964dcec77a4b2f6),
+        BaseElement::new(0x23a0968bdc552f14),
+        BaseElement::new(0x15fb59e2b05982d1),
+        BaseElement::new(0x54d3515650b9878),
+        BaseElement::new(0x30384ff99aad0520),
+        BaseElement::new(0x178a2c20c7d9c0),
+        BaseElement::new(0xd8922dd802a1b1),
+        BaseElement::new(0x24d002cc0dd9c3f9),
+        BaseElement::new(0x91e0cfc19fe6c2a),
+        BaseElement::new(0x2f7a748e80dac713),
+        BaseElement::new(0x204b64f10e893f6d),
+        BaseElement::new(0x322078f9047a6dd2),
+    ],
+    [
+        BaseElement::new(0x7db760ded284b1d),
+        BaseElement::new(0x323c4ad8dfe559fe),
+        BaseElement::new(0x5826a08df92e1d6),
+        BaseElement::new(0x1bea064e6fa7a9b1),
+        BaseElement::new(0x3f6f4d1b131f4af0),
+        BaseElement::new(0x37ef6c0b249998c4),
+        BaseElement::new(0x225df90e44a093bf),
+        BaseElement::new(0x26bb12592c8a6d7f),
+        BaseElement::new(0x375ca5b98eee06bd),
+        BaseElement::new(0x4b357fab00e9fd0),
+        BaseElement::new(0x1d0c33a0dfe3ae15),
+        BaseElement::new(0x413e7fff8bcf10d9),
+        BaseElement::new(0x3c6b0618e6c46564),
+        BaseElement::new(0x2120cb2658fac97),
+    ],
+    [
+        BaseElement::new(0x3d9abde513d7010),
+        BaseElement::new(0x2b461f424341cf40),
+        BaseElement::new(0x238c63ff39ea65fd),
+        BaseElement::new(0x18dc03ca0f8a945),
+        BaseElement::new(0x367d1f965fa6e39),
+        BaseElement::new(0x2dbcf22b8ab9d824),
+        BaseElement::new(0xf58b450b3c0d021),
+        BaseElement::new(0x20244d1f94a7a9),
+        BaseElement::new(0x3dd7f7e051abcf7a),
+        BaseElement::new(0x66f2b513a6d87b0),
+        BaseElement::new(0x1a9d7772d1a54246),
+        BaseElement::new(0xc73e1810187430f),
+        BaseElement::new(0x233f9236ce519840),
+        BaseElement::new(0x3ce06697c386f277),
+    ],
+    [
+        BaseElement::new(0x211e5b0d253bdfe7),
+        BaseElement::new(0x1232587f7028abba),
+        BaseElement::new(0x39ca9681296f951e),
+        BaseElement::new(0x14b48e2f79b10567),
+        BaseElement::new(0x2a3bbd2ea913c316),
+        BaseElement::new(0x7dbb1ca12827c7a),
+        BaseElement::new(0x189d87ebc3216d3a),
+        BaseElement::new(0x4049d7d0889ab9f5),
+        BaseElement::new(0x30943e10cb99c1a1),
+        BaseElement::new(0x331dc50b77ce80f),
+        BaseElement::new(0x224c52ad2f5c790f),
+        BaseElement::new(0x17deb1922d4f1afd),
+        BaseElement::new(0x2c1b9b7f19076026),
+        BaseElement::new(0x1affd27ef70242cd),
+    ],
+    [
+        BaseElement::new(0x202deedd99e1150e),
+        BaseElement::new(0x40227ad9e618aa9d),
+        BaseElement::new(0x4675642e0c4fbef),
+        BaseElement::new(0x1e6a94fbb89cd0ec),
+        BaseElement::new(0x27a45a28c8a94478),
+        BaseElement::new(0x2ca65faf2299026f),
+        BaseElement::new(0x116f2568cf4a9d4e),
+        BaseElement::new(0x1c16b9361e2b2060),
+        BaseElement::new(0x312d97a677149712),
+        BaseElement::new(0xcdbb535679a3f7a),
+        BaseElement::new(0xbabf38c8898e7f7),
+        BaseElement::new(0x2e3195c12fb92488),
+        BaseElement::new(0x7422d0b3549b6ec),
+        BaseElement::new(0xb7e31d77da6a752),
+    ],
+    [
+        BaseElement::new(0x282f0da195fa7986),
+        BaseElement::new(0xf2e2acd1a6fa39d),
+        BaseElement::new(0x16f7e6693be561dc),
+        BaseElement::new(0x2aaf44a50b1d97e4),
+        BaseElement::new(0xca68dbb8d67faaf),
+        BaseElement::new(0x3d9663fb3490485b),
+        BaseElement::new(0x194d94b12f7ed142),
+        BaseElement::new(0x3073c564df336879),
+        BaseElement::new(0x2af5539ebe59b2e8),
+        BaseElement::new(0x1ce2bc47a372c0d),
+        BaseElement::new(0x18ed921005e40f1b),
+        BaseElement::new(0x33c9a3ce14c9916),
+        BaseElement::new(0x1d5ac22fbec3bf1c),
+        BaseElement::new(0x30d035357aa2c5a0),
+    ],
+    [
+        BaseElement::new(0x1dcf573def2dc0aa),
+        BaseElement::new(0x33869a30a7b0895c),
+        BaseElement::new(0xcfe4d7dd9a27f5d),
+        BaseElement::new(0x310d76549f941134),
+        BaseElement::new(0x1161bf6420726fc7),
+        BaseElement::new(0x253cb98a38d38b1a),
+        BaseElement::new(0x1da06e4c772f9a47),
+        BaseElement::new(0x44834a50d9c62ac),
+        BaseElement::new(0x23f3ee0fda46a22),
+        BaseElement::new(0x1444cf5682f2aba2),
+        BaseElement::new(0x21869590feebb156),
+        BaseElement::new(0x264eecf919ca3bae),
+        BaseElement::new(0x95f9b1d42207b33),
+        BaseElement::new(0x348135e084b8906d),
+    ],
+    [
+        BaseElement::new(0x128c5bd3e16a6cb4),
+        BaseElement::new(0x18300ca38062607b),
+        BaseElement::new(0x1d354773c1e356c),
+        BaseElement::new(0x3c551d2116bb697c),
+        BaseElement::new(0x53d20dceb67afa7),
+        BaseElement::new(0x18a93be8aea803fc),
+        BaseElement::new(0x8d322ec939cf19e),
+        BaseElement::new(0x20c7759fd13c5654),
+        BaseElement::new(0xd810276fbe23ef2),
+        BaseElement::new(0x2e4d9710ab5d0435),
+        BaseElement::new(0x3c017fa48bfcb768),
+        BaseElement::new(0xd6ac8df475883bb),
+        BaseElement::new(0x3702d9f0e35b0516),
+        BaseElement::new(0x3239798cd70f0425),
+    ],
+    [
+        BaseElement::new(0x4014f2423ca26674),
+        BaseElement::new(0x3bdde975068c52ef),
+        BaseElement::new(0x40deb6f5acacefdc),
+        BaseElement::new(0x3dcd3c016b680ee6),
+        BaseElement::new(0x382fba62f4a09cfb),
+        BaseElement::new(0x109051ae6be74450),
+        BaseElement::new(0x3f8faf2dfc31f431),
+        BaseElement::new(0x191345b6cc371fb9),
+        BaseElement::new(0x277bb9f19a2438f1),
+        BaseElement::new(0xe79dee530ea76d3),
+        BaseElement::new(0x1b1c4f1f524e54a5),
+        BaseElement::new(0xc4a8d6016daf084),
+        BaseElement::new(0x313ef1cf764d4667),
+        BaseElement::new(0xb225232547edfb0),
+    ],
+    [
+        BaseElement::new(0x1f6db06ff35e6caf),
+        BaseElement::new(0x1b8f3bf57792c984),
+        BaseElement::new(0x2245e0cdb87a7769),
+        BaseElement::new(0x36efa12639d76866),
+        BaseElement::new(0x2914f0e788005994),
+        BaseElement::new(0x12a17e35a46729b3),
+        BaseElement::new(0xe9dde08cc29829b),
+        BaseElement::new(0xfc2917356af87a3),
+        BaseElement::new(0x121c25b850ae7594),
+        BaseElement::new(0x25c52321db9fdedf),
+        BaseElement::new(0x350cb605f135c354),
+        BaseElement::new(0x5a5f8dc91f17c48),
+        BaseElement::new(0x3d4f0cb8a90d652),
+        BaseElement::new(0x133d709d119610e0),
+    ],
+    [
+        BaseElement::new(0x79657b78e1b6fa5),
+        BaseElement::new(0x31ad8f74ff964207),
+        BaseElement::new(0x285a2af7104fb226),
+        BaseElement::new(0xde8448f2afa96af),
+        BaseElement::new(0x3559874a743da060),
+        BaseElement::new(0xf56296bee2efbc0),
+        BaseElement::new(0x30693759c27eb06a),
+        BaseElement::new(0x3e7f95c84b065ab),
+        BaseElement::new(0x5b0001017f0ea96),
+        BaseElement::new(0xa1e90a15bfd2c9),
+        BaseElement::new(0x27c506699a0201d3),
+        BaseElement::new(0x3658a455f87edb63),
+        BaseElement::new(0x360d433e9ab009f),
+        BaseElement::new(0x19e18fef92bbb4e4),
+    ],
+    [
+        BaseElement::new(0xe14b02d1507526d),
+        BaseElement::new(0x27ff2df882569984),
+        BaseElement::new(0x18b3802f4c49f169),
+        BaseElement::new(0x29f37fd10477c552),
+        BaseElement::new(0x24247f78df560459),
+        BaseElement::new(0x1a5ff1e2a6e1098b),
+        BaseElement::new(0x1491360c80788691),
+        BaseElement::new(0x15094d8e9e091db5),
+        BaseElement::new(0xe3ed2be75643d81),
+        BaseElement::new(0xe10f968cd474815),
+        BaseElement::new(0x1d21402dfeb66d18),
+        BaseElement::new(0x3d4256059b6d3dd2),
+        BaseElement::new(0x2cacc22abb851737),
+        BaseElement::new(0x9dd35040f066a66),
+    ],
+    [
+        BaseElement::new(0x270d5e4ca61475a8),
+        BaseElement::new(0x13b00094977d4674),
+        BaseElement::new(0x340a82a1a776932),
+        BaseElement::new(0xbdc033b1bc68c4a),
+        BaseElement::new(0x3c007fd58870d096),
+        BaseElement::new(0x109d40117d53e8ac),
+        BaseElement::new(0x3c5dd3be290816f4),
+        BaseElement::new(0x1ff39d258657a0b9),
+        BaseElement::new(0x3997339819c7d0de),
+        BaseElement::new(0x4a56e4b1bb01705),
+        BaseElement::new(0x564e75e23f2cd4),
+        BaseElement::new(0x2d576781dd8cb373),
+        BaseElement::new(0x3a132437f2088fc0),
+        BaseElement::new(0x2824c71daec70d63),
+    ],
+    [
+        BaseElement::new(0x2b707e4e22c3b8f5),
+        BaseElement::new(0x25696e055a1bf38f),
+        BaseElement::new(0x79616251972f0a4),
+        BaseElement::new(0x220e44a493f47699),
+        BaseElement::new(0x3650c5c1dde52ccd),
+        BaseElement::new(0xced2f9c2e274079),
+        BaseElement::new(0x2f9f51feeea4dfa2),
+        BaseElement::new(0x11b17bb101dca9d1),
+        BaseElement::new(0x5ca8ab2c97ec863),
+        BaseElement::new(0x22f21c3436d101aa),
+        BaseElement::new(0x112f6ef93c8c3899),
+        BaseElement::new(0x1363a71051824ab4),
+        BaseElement::new(0x184711efeeeab32e),
+        BaseElement::new(0x1fa675b9c3965a8d),
+    ],
+    [
+        BaseElement::new(0x25f98a0bdf7d2e26),
+        BaseElement::new(0x417a3a49004fb6a5),
+        BaseElement::new(0x1772ab5b28ed3029),
+        BaseElement::new(0x3f51b80563327ae7),
+        BaseElement::new(0x1588c3aac2fd6235),
+        BaseElement::new(0x2a5ccde994944e49),
+        BaseElement::new(0x32807f15b53eb214),
+        BaseElement::new(0x5a7e2d13e7b513d),
+        BaseElement::new(0xe081129afb5a7b6),
+        BaseElement::new(0xdd2157fe30807a1),
+        BaseElement::new(0x6b4cfa5b95612e1),
+        BaseElement::new(0x20b477a821bec630),
+        BaseElement::new(0x1de7af9b4a33fcba),
+        BaseElement::new(0x2e84179dff0a3dbb),
+    ],
+    [
+        BaseElement::new(0x39b7cc8b1d59e1ba),
+        BaseElement::new(0xc4dd69589227f7c),
+        BaseElement::new(0x2093637690c9c968),
+        BaseElement::new(0x1f130d5354ab7cfb),
+        BaseElement::new(0x1b63c6fdab8e3f98),
+        BaseElement::new(0x3f7e3427a4d9504a),
+        BaseElement::new(0x894f8d257a94c88),
+        BaseElement::new(0x1feb919afecea6ca),
+        BaseElement::new(0x32885b50691d6c1b),
+        BaseElement::new(0xa2c36462d8c9256),
+        BaseElement::new(0x20aea268638aa69b),
+        BaseElement::new(0x1cc187b7ad8730e9),
+        BaseElement::new(0x1ed0dcd69f3772cc),
+        BaseElement::new(0x372eb86d4a047202),
+    ],
+    [
+        BaseElement::new(0x9dc4498156a2ba7),
+        BaseElement::new(0x2dca3330608315c4),
+        BaseElement::new(0x84072262b589b6c),
+        BaseElement::new(0x17c813afd14cf003),
+        BaseElement::new(0x26e891c357ac0889),
+        BaseElement::new(0x298618c2f0084f0b),
+        BaseElement::new(0x26c66dd881b1d5f7),
+        BaseElement::new(0x36fa42101c3a2342),
+        BaseElement::new(0x32ec12670f403688),
+        BaseElement::new(0x2320d4199a9c8a63),
+        BaseElement::new(0x88915e3bd7c6ea2),
+        BaseElement::new(0x180cb63cb04b3333),
+        BaseElement::new(0x3dbb705f6544521e),
+        BaseElement::new(0x34fcb493306267d5),
+    ],
+    [
+        BaseElement::new(0x2d87d9222ccfb892),
+        BaseElement::new(0x2581bb85b3c00738),
+        BaseElement::new(0x229d49bf1a441ef5),
+        BaseElement::new(0x32b6ad4959b0c78a),
+        BaseElement::new(0x12053a84eaae2062),
+        BaseElement::new(0x333910c10de15d35),
+        BaseElement::new(0x1250b568129c0372),
+        BaseElement::new(0x1e095521e41790de),
+        BaseElement::new(0xf3d8e9d4748a0de),
+        BaseElement::new(0x3e9560228a3aaa9c),
+        BaseElement::new(0x2d29460e9a9b34ad),
+        BaseElement::new(0x616727ca7a33cad),
+        BaseElement::new(0x31f62b3192083501),
+        BaseElement::new(0x11e79e7d72e8de0a),
+    ],
+    [
+        BaseElement::new(0x67d00d58bdc3047),
+        BaseElement::new(0xe4e6f062896408f),
+        BaseElement::new(0xea094dc5fb94fa7),
+        BaseElement::new(0x2b525e0087530659),
+        BaseElement::new(0x21603221ff37cb9f),
+        BaseElement::new(0x32cef9568bf3f5ff),
+        BaseElement::new(0x327eb2c65fd00cb6),
+        BaseElement::new(0x13d08f78c28d0e21),
+        BaseElement::new(0x2a60765d3beca4f5),
+        BaseElement::new(0x405b1d98287e1610),
+        BaseElement::new(0x3b7c84a9be233f57),
+        BaseElement::new(0x5195f607dc1a7bc),
+        BaseElement::new(0x1cd774766f234497),
+        BaseElement::new(0x3043bc402486fbcf),
+    ],
+    [
+        BaseElement::new(0x3049947e5a3b0731),
+        BaseElement::new(0x130875112d33128a),
+        BaseElement::new(0x20b48d92e17a2b55),
+        BaseElement::new(0x2122b8ca212829c1),
+        BaseElement::new(0x89dc9637e7ab2c8),
+        BaseElement::new(0x3e939476966becc2),
+        BaseElement::new(0x3e35c7fcfe52600e),
+        BaseElement::new(0x32d7ac7b35ef57f9),
+        BaseElement::new(0x3ba33aeb12b373af),
+        BaseElement::new(0x39b5c3d9443059b2),
+        BaseElement::new(0x89d35bebe0cc8b),
+        BaseElement::new(0x27bd263a48e49970),
+        BaseElement::new(0x110d18a9570238e),
+        BaseElement::new(0x307ef46146153377),
+    ],
+    [
+        BaseElement::new(0xa92d1697f3b5a02),
+        BaseElement::new(0xbde302b3449ed44),
+        BaseElement::new(0x2640ed7e1581ee54),
+        BaseElement::new(0x15256ded879ce2d6),
+        BaseElement::new(0x3d370f1b415f7538),
+        BaseElement::new(0x6334cb5a49067f2),
+        BaseElement::new(0x1d8527a873867c2c),
+        BaseElement::new(0x28096f1f1d8d6b94),
+        BaseElement::new(0x27cf501fa34ff5e6),
+        BaseElement::new(0x374316f8be989162),
+        BaseElement::new(0x3d093754129b3c53),
+        BaseElement::new(0x1826517fc0788b4e),
+        BaseElement::new(0x12f1d0b143c5c398),
+        BaseElement::new(0xff15a7381ef6427),
+    ],
+    [
+        BaseElement::new(0x33ccea73a6c36df0),
+        BaseElement::new(0x27e511d8e22c388f),
+        BaseElement::new(0x37d06e3deecf2e4b),
+        BaseElement::new(0x9d1ddff5d073469),
+        BaseElement::new(0x7ec63365778dfbe),
+        BaseElement::new(0x16782487cc9b0811),
+        BaseElement::new(0xd88425c14fc44bc),
+        BaseElement::new(0x2a9b5068d957d4b5),
+        BaseElement::new(0x34e4567588ecb14b),
+        BaseElement::new(0x119ca9d215c5d824),
+        BaseElement::new(0xdf13dda7a17c75b),
+        BaseElement::new(0x174c9cf2315b1feb),
+        BaseElement::new(0x22b8c2b4e6a6d44a),
+        BaseElement::new(0x34a396c97451121c),
+    ],
+    [
+        BaseElement::new(0x3ccc5dbfc4facdea),
+        BaseElement::new(0xf686bcb7ceabe73),
+        BaseElement::new(0x3aee9d5b074dbe9f),
+        BaseElement::new(0x27a05c0de55ab328),
+        BaseElement::new(0x279f07d9e34bd55f),
+        BaseElement::new(0x20e271c08e5b710d),
+        BaseElement::new(0x105d5661e4ed6a7c),
+        BaseElement::new(0x1a29e500b19988d8),
+        BaseElement::new(0x24cf86bf83dae052),
+        BaseElement::new(0x3186ad87adf7004),
+        BaseElement::new(0x91885c902431446),
+        BaseElement::new(0x37783ea58f8a11de),
+        BaseElement::new(0xba2d25217724d54),
+        BaseElement::new(0x20e925457360b339),
+    ],
+    [
+        BaseElement::new(0x18d1bd111ea671ae),
+        BaseElement::new(0x27cb6e16ea2bc524),
+        BaseElement::new(0x1b48e000cfbed86b),
+        BaseElement::new(0x163f74bdf81e8210),
+        BaseElement::new(0x3421d76097947367),
+        BaseElement::new(0x220e387216633e38),
+        BaseElement::new(0x31dbb10be01cd9b6),
+        BaseElement::new(0x1c464171eb6f4e61),
+        BaseElement::new(0x24ea159ebc885a1),
+        BaseElement::new(0x61eba710177fba8),
+        BaseElement::new(0x1e33c843811928ca),
+        BaseElement::new(0x21824a22e438e7a6),
+        BaseElement::new(0xd5dbb4f651023bb),
+        BaseElement::new(0x3aced716cc8c828e),
+    ],
+    [
+        BaseElement::new(0x18531aadb7c6a8d6),
+        BaseElement::new(0x342b75ed395ac033),
+        BaseElement::new(0x3e6f67af38963925),
+        BaseElement::new(0x12a259c08f8f4395),
+        BaseElement::new(0x329f2e61925db6c0),
+        BaseElement::new(0x1a33d51c87b5ce16),
+        BaseElement::new(0x393e7e5c474fe918),
+        BaseElement::new(0x304e0a6e8f189f35),
+        BaseElement::new(0x2118148aeadc51ee),
+        BaseElement::new(0x280784ef6bb6c093),
+        BaseElement::new(0x3feadb27de71cdb1),
+        BaseElement::new(0x120af12c9cc4e858),
+        BaseElement::new(0x27400f9c2399a611),
+        BaseElement::new(0x179563ba0530e12d),
+    ],
+    [
+        BaseElement::new(0x1ec87782410cf849),
+        BaseElement::new(0xd397b0b28259ff2),
+        BaseElement::new(0xe68a00a7e83737b),
+        BaseElement::new(0x3d8b36a1fbcd39a6),
+        BaseElement::new(0x35f2ea6eb94f4d7e),
+        BaseElement::new(0x27f5b3396fa335d4),
+        BaseElement::new(0x352edac0d778a49a),
+        BaseElement::new(0x3279cd1e2b615472),
+        BaseElement::new(0x2e593d3bd4af5bad),
+        BaseElement::new(0x137e29f04b09a904),
+        BaseElement::new(0x9c3a008a060bc41),
+        BaseElement::new(0xf551286caf9add8),
+        BaseElement::new(0x3c67ff876c31bcf6),
+        BaseElement::new(0x3790db4dbc3a9d65),
+    ],
+    [
+        BaseElement::new(0x3e573c257cdcbca5),
+        BaseElement::new(0x25616a97515054da),
+        BaseElement::new(0x36c4aadcf54c7b46),
+        BaseElement::new(0x22dea419d2e8094d),
+        BaseElement::new(0x3a262db47e1d0438),
+        BaseElement::new(0x3c6cfb9c3bfabf52),
+        BaseElement::new(0x59cea1b1ba08968),
+        BaseElement::new(0x12a7ad7ec702561f),
+        BaseElement::new(0x3cf486961c3f8659),
+        BaseElement::new(0x3e6857e3a58bd655),
+        BaseElement::new(0x3e1fc76ab6b61f8d),
+        BaseElement::new(0x1e335e1340768c1d),
+        BaseElement::new(0xf1f61a671dc864c),
+        BaseElement::new(0x210ca2514368b43c),
+    ],
+    [
+        BaseElement::new(0x3356334a06f68a18),
+        BaseElement::new(0x2562c95f093531e7),
+        BaseElement::new(0x35d7bba166a170b0),
+        BaseElement::new(0xe54d0d86353423c),
+        BaseElement::new(0x21c520f5a989ed89),
+        BaseElement::new(0x3f6ae00b70e845bf),
+        BaseElement::new(0x33fc79b69aea7f27),
+        BaseElement::new(0x396433afa271464e),
+        BaseElement::new(0x2c35754efa6928ab),
+        BaseElement::new(0x2951ca17fc88ff41),
+        BaseElement::new(0xeafde6ad9336457),
+        BaseElement::new(0x3f2a7b5ff8f025e9),
+        BaseElement::new(0x41323e22ad549666),
+        BaseElement::new(0x3ae2710359ed6e8),
+    ],
+    [
+        BaseElement::new(0x28792144ba303bb6),
+        BaseElement::new(0x347e2b995dfa1f08),
+        BaseElement::new(0x1d5734bfdd2b1b9a),
+        BaseElement::new(0x35e81c81d7ec68a2),
+        BaseElement::new(0x207fc65a2c609227),
+        BaseElement::new(0x301be1e624703446),
+        BaseElement::new(0x2ac2dd3d7212a16e),
+        BaseElement::new(0xdcc344ae03311b6),
+        BaseElement::new(0x18a09bdf8d313d97),
+        BaseElement::new(0x359a4b6d04548814),
+        BaseElement::new(0x82b47090b77f82c),
+        BaseElement::new(0x2ebce7ecd987fec2),
+        BaseElement::new(0x1e0bd772da255a20),
+        BaseElement::new(0x13e5fe9c4dc933d4),
+    ],
+    [
+        BaseElement::new(0x40aed7c7bc051c93),
+        BaseElement::new(0x166a23314021b6c7),
+        BaseElement::new(0x157cfa292a153e9f),
+        BaseElement::new(0x172a3d305ff744ae),
+        BaseElement::new(0x37f76e9b377c8246),
+        BaseElement::new(0x14b1d54a6afa3113),
+        BaseElement::new(0x9e550a4b2af1734),
+        BaseElement::new(0xf84c001755807af),
+        BaseElement::new(0x34e42ef8ee17a45d),
+        BaseElement::new(0x90c616a866b6e5c),
+        BaseElement::new(0x1b3a5d5bc4bb0c88),
+        BaseElement::new(0x1c71a188758eed17),
+        BaseElement::new(0x38e980fef9768913),
+        BaseElement::new(0x183223091fac4f9b),
+    ],
+    [
+        BaseElement::new(0x3e6045734b05d9d9),
+        BaseElement::new(0x2ea556fb1f0990c5),
+        BaseElement::new(0x217a76561765e9e3),
+        BaseElement::new(0x256549140a4adac),
+        BaseElement::new(0x3284d990fc07fe8b),
+        BaseElement::new(0xb409a2cf56e2f05),
+        BaseElement::new(0x2d5b053c1f18dffd),
+        BaseElement::new(0x1122b88441248c9e),
+        BaseElement::new(0x1f4f7f3ffc4c7f53),
+        BaseElement::new(0x27cf2439f359dc4a),
+        BaseElement::new(0x2fdd4e6dc1e60ba),
+        BaseElement::new(0x2a8eb0d8fcc33a10),
+        BaseElement::new(0x304ae6a4f0a0e973),
+        BaseElement::new(0x1d24194e815dbdb0),
+    ],
+    [
+        BaseElement::new(0xf402074c90e059),
+        BaseElement::new(0x43e6d8069916a1f),
+        BaseElement::new(0xbc5893a0fa73040),
+        BaseElement::new(0x391d96edd38a2d28),
+        BaseElement::new(0x30ee9249844a5bad),
+        BaseElement::new(0x12f384d560c75b7d),
+        BaseElement::new(0x21032eab3df56129),
+        BaseElement::new(0x18f2536508b66cb3),
+        BaseElement::new(0x10fe2ca7ea39b89c),
+        BaseElement::new(0xb9c686f9b7c9ea1),
+        BaseElement::new(0x18fb567ccf34a9b9),
+        BaseElement::new(0x3ce28add80a6cb06),
+        BaseElement::new(0x1363abea15a5eb6c),
+        BaseElement::new(0x12b4c0a1f59cb781),
+    ],
+    [
+        BaseElement::new(0x255b7f1f06a8821a),
+        BaseElement::new(0x31994038da7f7a0f),
+        BaseElement::new(0xdb61c5fd3af1346),
+        BaseElement::new(0x3fd4d73d0eff1f62),
+        BaseElement::new(0xe0fa3c08b5cf0e9),
+        BaseElement::new(0x333acad592c3f801),
+        BaseElement::new(0xcab4a15184c3a94),
+        BaseElement::new(0xed35020f20c6a53),
+        BaseElement::new(0x232aca112611b5d),
+        BaseElement::new(0x299931d0a3251fb5),
+        BaseElement::new(0x13893425688f4d9f),
+        BaseElement::new(0x17d493e6a27c2e80),
+        BaseElement::new(0x17c18db6061f762d),
+        BaseElement::new(0x58506b0a0a251b1),
+    ],
+    [
+        BaseElement::new(0x1c449d889faf425a),
+        BaseElement::new(0x27e54a14c25f264b),
+        BaseElement::new(0x1eac6ff33d3e75a),
+        BaseElement::new(0x40ee2e254bb9cf32),
+        BaseElement::new(0x10005bf7b42a4c7c),
+        BaseElement::new(0x17404f8d5447080d),
+        BaseElement::new(0x12f6de3b155cba27),
+        BaseElement::new(0x2ce18fd90371483b),
+        BaseElement::new(0x29fc4ae7884d2a49),
+        BaseElement::new(0x2fe470ce444d76e7),
+        BaseElement::new(0x2d59bb2ddb5ebae),
+        BaseElement::new(0x1551d7d1df945ea0),
+        BaseElement::new(0xd0c5825c5067e90),
+        BaseElement::new(0x3e064c9ef2e76cca),
+    ],
+    [
+        BaseElement::new(0x3f135b244759f8ba),
+        BaseElement::new(0x411794d6fbac35ae),
+        BaseElement::new(0x1e4d8fb001971e9d),
+        BaseElement::new(0x7234b7528228832),
+        BaseElement::new(0x2e0a69f9bb06aea0),
+        BaseElement::new(0x3d8d395704f22ffd),
+        BaseElement::new(0x16cca7efa843cb01),
+        BaseElement::new(0x9dfc26031fc492c),
+        BaseElement::new(0x3049fc7138617c8e),
+        BaseElement::new(0x3294c312a49fcc49),
+        BaseElement::new(0x5bc215072162da4),
+        BaseElement::new(0x17d81ef8bcdd2952),
+        BaseElement::new(0x1151fde9129e5187),
+        BaseElement::new(0x1e60fd4f5c7ec7e),
+    ],
+    [
+        BaseElement::new(0x26f988d05b52f120),
+        BaseElement::new(0x208f42cd00734b48),
+        BaseElement::new(0x2c3773de9ba434f2),
+        BaseElement::new(0xa032513263a58c8),
+        BaseElement::new(0x1bd075ce5fb0293a),
+        BaseElement::new(0x11468a743d0d0758),
+        BaseElement::new(0x232a40aaea01a629),
+        BaseElement::new(0x19d9886401e51513),
+        BaseElement::new(0x296716303c689fe1),
+        BaseElement::new(0x2cad194e73cdca4f),
+        BaseElement::new(0x155b8bd7060a8daf),
+        BaseElement::new(0x22fe7ae484eba194),
+        BaseElement::new(0x701aa6118af23fc),
+        BaseElement::new(0x1e01a16bc524ecef),
+    ],
+];
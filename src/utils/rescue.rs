@@ -8,9 +8,11 @@
 
 use super::{are_equal, EvaluationResult};
 use core::slice;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
 use winterfell::{
     crypto::{Digest, Hasher},
-    math::{fields::f63::BaseElement, FieldElement},
+    math::{fields::f63::BaseElement, FieldElement, StarkField},
     ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable,
 };
 
@@ -36,6 +38,10 @@ pub const NUM_HASH_ROUNDS: usize = 7;
 /// Minimum cycle length required to describe Rescue permutation.
 pub const HASH_CYCLE_LENGTH: usize = 8;
 
+/// Round constants of the Rescue permutation over a hash cycle, with `2 * STATE_WIDTH`
+/// constants per step, the last step of the cycle holding zeros.
+pub type RoundConstants = [[BaseElement; STATE_WIDTH * 2]; HASH_CYCLE_LENGTH];
+
 /// Specifies steps on which Rescue transition function is applied.
 pub const HASH_CYCLE_MASK: [BaseElement; HASH_CYCLE_LENGTH] = [
     BaseElement::ONE,
@@ -62,6 +68,18 @@ pub struct Rescue63 {
 /// A hash object
 pub struct Hash([BaseElement; DIGEST_SIZE]);
 
+/// A sponge over the Rescue permutation, computing the same iterated hash as the AIR
+/// programs: the first chunk of `RATE_WIDTH` elements fills the first half of a zero state,
+/// whose last element holds the domain of the hash, which is then permuted, and every
+/// following chunk replaces the second half of the state before permuting it again, the
+/// digest being the first half of the state.
+#[derive(Clone, Debug, Default)]
+pub struct RescueSponge {
+    domain: BaseElement,
+    digest: Option<Hash>,
+    squeezed: bool,
+}
+
 // Rescue63 IMPLEMENTATION
 // ================================================================================================
 
@@ -130,6 +148,73 @@ impl Rescue63 {
     }
 }
 
+// RESCUE SPONGE IMPLEMENTATION
+// ================================================================================================
+
+impl RescueSponge {
+    /// Returns a new sponge, having absorbed no element.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new sponge, having absorbed no element, whose digests are separated from
+    /// the ones of other domains by initializing the last element of the state to `domain`.
+    pub fn new_with_domain(domain: BaseElement) -> Self {
+        RescueSponge {
+            domain,
+            ..Self::default()
+        }
+    }
+
+    /// Absorbs `data` into the sponge, in chunks of `RATE_WIDTH` elements, the last chunk
+    /// being padded with zeros.
+    ///
+    /// As padding is not injective, inputs of variable length should be absorbed along
+    /// with their length.
+    pub fn absorb(&mut self, data: &[BaseElement]) {
+        for chunk in data.chunks(RATE_WIDTH) {
+            let mut elements = [BaseElement::ZERO; DIGEST_SIZE];
+            elements[..chunk.len()].copy_from_slice(chunk);
+            self.absorb_chunk(Hash(elements));
+        }
+        self.squeezed = false;
+    }
+
+    /// Returns the digest of the elements absorbed so far.
+    ///
+    /// Squeezing again without absorbing any element in between absorbs a chunk of zeros
+    /// first, similarly to the AIR programs resetting the second half of the state, so that
+    /// each call returns a new digest.
+    pub fn squeeze(&mut self) -> Hash {
+        if self.squeezed || self.digest.is_none() {
+            self.absorb_chunk(Hash::default());
+        }
+        self.squeezed = true;
+
+        self.digest.expect("a chunk was absorbed")
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Absorbs a single chunk of `RATE_WIDTH` elements.
+    fn absorb_chunk(&mut self, chunk: Hash) {
+        self.digest = Some(match self.digest {
+            None => {
+                let mut state = [BaseElement::ZERO; STATE_WIDTH];
+                state[..RATE_WIDTH].copy_from_slice(&chunk.0);
+                state[STATE_WIDTH - 1] = self.domain;
+                apply_permutation(&mut state);
+
+                Hash([
+                    state[0], state[1], state[2], state[3], state[4], state[5], state[6],
+                ])
+            }
+            Some(digest) => Rescue63::merge(&[digest, chunk]),
+        });
+    }
+}
+
 // HASHER IMPLEMENTATION
 // ================================================================================================
 
@@ -161,7 +246,7 @@ impl Hasher for Rescue63 {
 
 impl Hash {
     /// Instantiates a new hasher state
-    pub fn new(
+    pub const fn new(
         v1: BaseElement,
         v2: BaseElement,
         v3: BaseElement,
@@ -229,14 +314,126 @@ impl Deserializable for Hash {
     }
 }
 
+// CROSS-IMPLEMENTATION API
+// ================================================================================================
+
+/// Returns the digest of `data` computed as the AIR programs do, i.e. with a `RescueSponge`
+/// of domain zero absorbing `data` before being squeezed once.
+///
+/// Implementations of the hash in other languages can check their compatibility with this
+/// function on the known-answer vectors `HASH_TEST_VECTORS`.
+pub fn hash_elements(data: &[BaseElement]) -> Hash {
+    let mut sponge = RescueSponge::new();
+    sponge.absorb(data);
+    sponge.squeeze()
+}
+
+/// Known-answer vectors of `hash_elements`, as pairs of an input and its digest, with
+/// elements given by their canonical integer representation.
+pub const HASH_TEST_VECTORS: [(&[u64], [u64; DIGEST_SIZE]); 5] = [
+    (
+        &[0],
+        [
+            0x22ad90dc29cf25eb,
+            0x368c2fad196a6d65,
+            0x266c7415f3e97ed,
+            0x4174c2942efb4628,
+            0x2fe7271cc73174dc,
+            0x30b47ae0fb08fa60,
+            0x1e490f1a492ef1e5,
+        ],
+    ),
+    (
+        &[0, 1, 2, 3, 4, 5, 6],
+        [
+            0x2cb2a5e7353f81d3,
+            0x25345599c405b070,
+            0x1584af70ba6da377,
+            0xea3cfaa08f5cc7c,
+            0x1f95aa4d02119e66,
+            0x3a3a9631cc207b24,
+            0x8e1cc3ecd62a26a,
+        ],
+    ),
+    (
+        &[0, 1, 2, 3, 4, 5, 6, 7],
+        [
+            0x2fa8545521282414,
+            0x12e3d4d444060c2e,
+            0x3312c0f80fbc1b1a,
+            0x3f19c95e0e8d6156,
+            0x81f8697e921ffe,
+            0x27c3700615420e94,
+            0xafd33eef41bd332,
+        ],
+    ),
+    (
+        &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+        [
+            0x725a397ac40a297,
+            0x1e6d50cbc8a536c4,
+            0xb07430632df461a,
+            0x24a0e34bef1d3231,
+            0x33dc79753c918aab,
+            0x354507f09ee52e8f,
+            0x3f352d2d0fecb19b,
+        ],
+    ),
+    (
+        &[
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+        ],
+        [
+            0xa026d2ed305f44c,
+            0xa63a259bbb4c0a1,
+            0x16781323b42d9439,
+            0x33c24ee927eef3c0,
+            0x204a3b183c9b8444,
+            0x19c94b58c140991c,
+            0x2b394b06d916f70a,
+        ],
+    ),
+];
+
+/// Known-answer vector of the Rescue permutation, as a state holding the integers zero to
+/// thirteen and its permutation by `apply_permutation`.
+pub const PERMUTATION_TEST_VECTOR: ([u64; STATE_WIDTH], [u64; STATE_WIDTH]) = (
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+    [
+        0x1e66ec9ee7478f92,
+        0x1a2adddd8b019bb2,
+        0x39878b0058738b20,
+        0x13a87bb2d59fbd14,
+        0x1aca0cc032230315,
+        0x13cef960e8cac398,
+        0x3c1b620cc44ebe5,
+        0xbe28a7c83be961a,
+        0x40e3fa17cd66680e,
+        0x40873ec0119ad1f2,
+        0x1d32ca186eb4d426,
+        0x36544799e021bd12,
+        0x30f4df45c241b1b9,
+        0x1426f153bb2d1810,
+    ],
+);
+
 // RESCUE PERMUTATION
 // ================================================================================================
 
 /// Applies Rescue-XLIX permutation to the provided state.
 pub fn apply_permutation(state: &mut [BaseElement; STATE_WIDTH]) {
+    apply_permutation_with_constants(state, &ARK)
+}
+
+/// Applies Rescue-XLIX permutation to the provided state, with the round constants `ark`
+/// instead of the default ones.
+pub fn apply_permutation_with_constants(
+    state: &mut [BaseElement; STATE_WIDTH],
+    ark: &RoundConstants,
+) {
     // apply round function 7 times; this provides 128-bit security with 40% security margin
     for i in 0..NUM_HASH_ROUNDS {
-        apply_round(state, i);
+        apply_round_with_constants(state, i, ark);
     }
 }
 
@@ -244,8 +441,14 @@ pub fn apply_permutation(state: &mut [BaseElement; STATE_WIDTH]) {
 /// implementation based on algorithm 3 from <https://eprint.iacr.org/2020/1143.pdf>
 #[inline(always)]
 pub fn apply_round(state: &mut [BaseElement], step: usize) {
+    apply_round_with_constants(state, step, &ARK)
+}
+
+/// Rescue-XLIX round function, with the round constants `ark` instead of the default ones.
+#[inline(always)]
+pub fn apply_round_with_constants(state: &mut [BaseElement], step: usize, ark: &RoundConstants) {
     // determine which round constants to use
-    let ark = ARK[step % HASH_CYCLE_LENGTH];
+    let ark = ark[step % HASH_CYCLE_LENGTH];
 
     // apply first half of Rescue round
     apply_sbox(state);
@@ -304,6 +507,12 @@ pub fn enforce_round<E: FieldElement + From<BaseElement>>(
 
 /// Returns Rescue round constants arranged in column-major form.
 pub fn get_round_constants() -> Vec<Vec<BaseElement>> {
+    round_constants_columns(&ARK)
+}
+
+/// Returns the provided round constants arranged in column-major form, as periodic columns
+/// of the AIR programs.
+pub fn round_constants_columns(ark: &RoundConstants) -> Vec<Vec<BaseElement>> {
     let mut constants = Vec::new();
     for _ in 0..(STATE_WIDTH * 2) {
         constants.push(vec![BaseElement::ZERO; HASH_CYCLE_LENGTH]);
@@ -312,31 +521,212 @@ pub fn get_round_constants() -> Vec<Vec<BaseElement>> {
     #[allow(clippy::needless_range_loop)]
     for i in 0..HASH_CYCLE_LENGTH {
         for j in 0..(STATE_WIDTH * 2) {
-            constants[j][i] = ARK[i][j];
+            constants[j][i] = ark[i][j];
         }
     }
 
     constants
 }
 
+/// Derives round constants from the provided seed string, so that deployments can use their
+/// own instance of the permutation without having to trust its constants.
+///
+/// The seed is hashed with the default permutation, its length being absorbed last, and the
+/// first 32 bytes of the digest key a ChaCha20 stream. The constants of the rounds are then
+/// read from this stream as 64-bit little-endian integers, rejecting the ones not below the
+/// field modulus, while the idle step of the hash cycle keeps zero constants.
+pub fn generate_round_constants(seed: &str) -> RoundConstants {
+    let mut rng = seeded_rng(seed);
+    let mut ark = [[BaseElement::ZERO; STATE_WIDTH * 2]; HASH_CYCLE_LENGTH];
+    for round in ark[..NUM_HASH_ROUNDS].iter_mut() {
+        for constant in round.iter_mut() {
+            *constant = next_element(&mut rng);
+        }
+    }
+
+    ark
+}
+
+// GENERIC RESCUE INSTANCES
+// ================================================================================================
+
+/// A Rescue-XLIX permutation over a state of `WIDTH` elements, absorbing `RATE` elements at
+/// once, so that AIR programs hashing short messages (e.g. nullifiers) can use a narrower
+/// state than the default one of `STATE_WIDTH` elements, and hence fewer constraints.
+///
+/// Instances apply `NUM_HASH_ROUNDS` rounds over a cycle of `HASH_CYCLE_LENGTH` steps like
+/// the default permutation, and their digests are the first `RATE` elements of the state,
+/// which must hold at least twice as many elements. The number of rounds was chosen for the
+/// default width only, and should be reassessed before using narrower instances in
+/// production.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RescueInstance<const WIDTH: usize, const RATE: usize> {
+    mds: [[BaseElement; WIDTH]; WIDTH],
+    inv_mds: [[BaseElement; WIDTH]; WIDTH],
+    ark: [[[BaseElement; WIDTH]; 2]; HASH_CYCLE_LENGTH],
+}
+
+impl<const WIDTH: usize, const RATE: usize> RescueInstance<WIDTH, RATE> {
+    /// Fails to compile for instances whose state cannot hold a digest and a chunk of data.
+    const VALID_WIDTH: () = assert!(RATE > 0 && 2 * RATE <= WIDTH);
+
+    /// Returns a new instance whose MDS matrix is the Cauchy matrix of the points `0..WIDTH`
+    /// and `WIDTH..2 * WIDTH`, and whose round constants are derived from the provided seed
+    /// string as done by `generate_round_constants`.
+    pub fn from_seed(seed: &str) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_WIDTH;
+
+        let mut mds = [[BaseElement::ZERO; WIDTH]; WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, element) in row.iter_mut().enumerate() {
+                *element =
+                    (BaseElement::new(i as u64) - BaseElement::new((WIDTH + j) as u64)).inv();
+            }
+        }
+        let inv_mds = invert_matrix(&mds);
+
+        let mut rng = seeded_rng(seed);
+        let mut ark = [[[BaseElement::ZERO; WIDTH]; 2]; HASH_CYCLE_LENGTH];
+        for round in ark[..NUM_HASH_ROUNDS].iter_mut() {
+            for constant in round.iter_mut().flatten() {
+                *constant = next_element(&mut rng);
+            }
+        }
+
+        RescueInstance { mds, inv_mds, ark }
+    }
+
+    /// Applies a round of this permutation to the provided state, with the constants of the
+    /// provided step of the hash cycle.
+    pub fn apply_round(&self, state: &mut [BaseElement; WIDTH], step: usize) {
+        let ark = &self.ark[step % HASH_CYCLE_LENGTH];
+
+        apply_sbox(state);
+        apply_matrix(state, &self.mds);
+        add_constants(state, &ark[0]);
+
+        apply_inv_sbox(state);
+        apply_matrix(state, &self.mds);
+        add_constants(state, &ark[1]);
+    }
+
+    /// Applies this permutation to the provided state.
+    pub fn apply_permutation(&self, state: &mut [BaseElement; WIDTH]) {
+        for i in 0..NUM_HASH_ROUNDS {
+            self.apply_round(state, i);
+        }
+    }
+
+    /// Returns the digest of `data` computed as `hash_elements` does with the default
+    /// permutation: chunks of `RATE` elements are absorbed one after the other, the last one
+    /// being padded with zeros, the first one filling a zero state and every other one being
+    /// appended to the digest of the previous ones.
+    pub fn hash_elements(&self, data: &[BaseElement]) -> [BaseElement; RATE] {
+        let mut digest: Option<[BaseElement; RATE]> = None;
+        let mut absorb_chunk = |chunk: &[BaseElement]| {
+            let mut state = [BaseElement::ZERO; WIDTH];
+            match digest {
+                None => state[..chunk.len()].copy_from_slice(chunk),
+                Some(digest) => {
+                    state[..RATE].copy_from_slice(&digest);
+                    state[RATE..RATE + chunk.len()].copy_from_slice(chunk);
+                }
+            }
+            self.apply_permutation(&mut state);
+            let mut result = [BaseElement::ZERO; RATE];
+            result.copy_from_slice(&state[..RATE]);
+            digest = Some(result);
+        };
+
+        if data.is_empty() {
+            absorb_chunk(&[BaseElement::ZERO]);
+        }
+        for chunk in data.chunks(RATE) {
+            absorb_chunk(chunk);
+        }
+
+        digest.expect("a chunk was absorbed")
+    }
+
+    /// Returns the round constants of this instance arranged in column-major form, as
+    /// periodic columns of the AIR programs, similarly to `round_constants_columns`.
+    pub fn round_constants_columns(&self) -> Vec<Vec<BaseElement>> {
+        let mut constants = vec![vec![BaseElement::ZERO; HASH_CYCLE_LENGTH]; 2 * WIDTH];
+        for (i, step) in self.ark.iter().enumerate() {
+            for (column, &constant) in constants.iter_mut().zip(step.iter().flatten()) {
+                column[i] = constant;
+            }
+        }
+
+        constants
+    }
+
+    /// When flag = 1, enforces constraints for a single round of this permutation, with the
+    /// round constants `ark` read from the columns of `round_constants_columns`.
+    pub fn enforce_round<E: FieldElement + From<BaseElement>>(
+        &self,
+        result: &mut [E],
+        current: &[E],
+        next: &[E],
+        ark: &[E],
+        flag: E,
+    ) {
+        let mut step1 = [E::ZERO; WIDTH];
+        step1.copy_from_slice(&current[..WIDTH]);
+        apply_sbox(&mut step1);
+        apply_matrix(&mut step1, &self.mds);
+        add_constants(&mut step1, &ark[..WIDTH]);
+
+        let mut step2 = [E::ZERO; WIDTH];
+        step2.copy_from_slice(&next[..WIDTH]);
+        for (element, &constant) in step2.iter_mut().zip(ark[WIDTH..2 * WIDTH].iter()) {
+            *element -= constant;
+        }
+        apply_matrix(&mut step2, &self.inv_mds);
+        apply_sbox(&mut step2);
+
+        for i in 0..WIDTH {
+            result.agg_constraint(i, flag, are_equal(step2[i], step1[i]));
+        }
+    }
+}
+
+impl RescueInstance<STATE_WIDTH, RATE_WIDTH> {
+    /// Returns the default permutation, as applied by `apply_permutation`, as an instance.
+    pub fn default_instance() -> Self {
+        let mut mds = [[BaseElement::ZERO; STATE_WIDTH]; STATE_WIDTH];
+        let mut inv_mds = [[BaseElement::ZERO; STATE_WIDTH]; STATE_WIDTH];
+        for i in 0..STATE_WIDTH {
+            mds[i].copy_from_slice(&MDS[i * STATE_WIDTH..(i + 1) * STATE_WIDTH]);
+            inv_mds[i].copy_from_slice(&INV_MDS[i * STATE_WIDTH..(i + 1) * STATE_WIDTH]);
+        }
+        let mut ark = [[[BaseElement::ZERO; STATE_WIDTH]; 2]; HASH_CYCLE_LENGTH];
+        for (step, constants) in ark.iter_mut().zip(ARK.iter()) {
+            step[0].copy_from_slice(&constants[..STATE_WIDTH]);
+            step[1].copy_from_slice(&constants[STATE_WIDTH..]);
+        }
+
+        RescueInstance { mds, inv_mds, ark }
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
 #[inline(always)]
-#[allow(clippy::needless_range_loop)]
 fn apply_sbox<E: FieldElement>(state: &mut [E]) {
-    for i in 0..STATE_WIDTH {
+    for element in state.iter_mut() {
         // ALPHA = 3 hence rely on faster exponentiation
         // through squaring and multiplication
-        state[i] *= state[i].square();
+        *element *= element.square();
     }
 }
 
 #[inline(always)]
-#[allow(clippy::needless_range_loop)]
 fn apply_inv_sbox(state: &mut [BaseElement]) {
-    for i in 0..STATE_WIDTH {
-        state[i] = state[i].exp(INV_ALPHA);
+    for element in state.iter_mut() {
+        *element = element.exp(INV_ALPHA);
     }
 }
 
@@ -374,6 +764,105 @@ fn apply_inv_mds<E: FieldElement + From<BaseElement>>(state: &mut [E]) {
     state.copy_from_slice(&result);
 }
 
+/// Multiplies the provided state by the square matrix `matrix`.
+#[inline(always)]
+fn apply_matrix<E: FieldElement + From<BaseElement>, const WIDTH: usize>(
+    state: &mut [E; WIDTH],
+    matrix: &[[BaseElement; WIDTH]; WIDTH],
+) {
+    let mut result = [E::ZERO; WIDTH];
+    for (element, row) in result.iter_mut().zip(matrix.iter()) {
+        for (&coefficient, &value) in row.iter().zip(state.iter()) {
+            *element += E::from(coefficient) * value;
+        }
+    }
+    *state = result;
+}
+
+#[inline(always)]
+fn add_constants<E: FieldElement + From<BaseElement>>(state: &mut [E], constants: &[E]) {
+    for (element, &constant) in state.iter_mut().zip(constants.iter()) {
+        *element += constant;
+    }
+}
+
+/// Inverts the provided matrix by Gauss-Jordan elimination.
+///
+/// # Panics
+/// Panics if the matrix is singular.
+fn invert_matrix<const WIDTH: usize>(
+    matrix: &[[BaseElement; WIDTH]; WIDTH],
+) -> [[BaseElement; WIDTH]; WIDTH] {
+    let mut matrix = *matrix;
+    let mut inverse = [[BaseElement::ZERO; WIDTH]; WIDTH];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        row[i] = BaseElement::ONE;
+    }
+
+    for column in 0..WIDTH {
+        let pivot = (column..WIDTH)
+            .find(|&row| matrix[row][column] != BaseElement::ZERO)
+            .expect("matrix must be invertible");
+        matrix.swap(column, pivot);
+        inverse.swap(column, pivot);
+
+        let factor = matrix[column][column].inv();
+        for j in 0..WIDTH {
+            matrix[column][j] *= factor;
+            inverse[column][j] *= factor;
+        }
+        for row in 0..WIDTH {
+            if row != column && matrix[row][column] != BaseElement::ZERO {
+                let factor = matrix[row][column];
+                for j in 0..WIDTH {
+                    let (pivot_value, pivot_inverse) = (matrix[column][j], inverse[column][j]);
+                    matrix[row][j] -= factor * pivot_value;
+                    inverse[row][j] -= factor * pivot_inverse;
+                }
+            }
+        }
+    }
+
+    inverse
+}
+
+/// Returns a ChaCha20 stream keyed by the first 32 bytes of the digest of the provided seed
+/// string, as described in `generate_round_constants`.
+fn seeded_rng(seed: &str) -> ChaCha20Rng {
+    // pack the seed into elements of 7 bytes, which are always canonical
+    let mut elements = seed
+        .as_bytes()
+        .chunks(7)
+        .map(|chunk| {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            BaseElement::new(u64::from_le_bytes(bytes))
+        })
+        .collect::<Vec<_>>();
+    elements.push(BaseElement::new(seed.len() as u64));
+
+    let mut key = [0u8; 32];
+    for (chunk, element) in key
+        .chunks_mut(8)
+        .zip(Rescue63::digest(&elements).to_elements().iter())
+    {
+        chunk.copy_from_slice(&element.to_repr().to_le_bytes());
+    }
+
+    ChaCha20Rng::from_seed(key)
+}
+
+/// Reads the next element from the provided stream, rejecting 64-bit integers which are not
+/// below the field modulus.
+fn next_element(rng: &mut ChaCha20Rng) -> BaseElement {
+    loop {
+        let value = rng.next_u64();
+        if value < BaseElement::MODULUS {
+            break BaseElement::new(value);
+        }
+    }
+}
+
 // CONSTANTS
 // ================================================================================================
 
@@ -781,7 +1270,7 @@ const INV_MDS: [BaseElement; STATE_WIDTH * STATE_WIDTH] = [
 ];
 
 /// The additive round constants (ARK)
-pub const ARK: [[BaseElement; STATE_WIDTH * 2]; HASH_CYCLE_LENGTH] = [
+pub const ARK: RoundConstants = [
     [
         BaseElement::new(0x1f0a24c4ed42b7df),
         BaseElement::new(0x23966eb7b343720e),
@@ -994,3 +1483,183 @@ pub const ARK: [[BaseElement; STATE_WIDTH * 2]; HASH_CYCLE_LENGTH] = [
     ],
     [BaseElement::ZERO; STATE_WIDTH * 2],
 ];
+
+// TESTS
+// ================================================================================================
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn rescue_round_constants_from_seed() {
+        use super::{self as rescue, Hash, Rescue63, RoundConstants};
+        use crate::merkle::{RoundConstantSet, SeededRescue63, TreeHasher};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement, StarkField};
+
+        // The derivation is deterministic, and frozen by a few known answers
+        let ark = rescue::generate_round_constants("certificate-stark");
+        assert_eq!(ark, rescue::generate_round_constants("certificate-stark"));
+        assert_eq!(ark[0][0], BaseElement::new(0xe2be4d123b8bd3c));
+        assert_eq!(ark[0][1], BaseElement::new(0x1f2cb9b76fc88ba7));
+        assert_eq!(ark[6][27], BaseElement::new(0x35cc993a790a3da2));
+        assert_ne!(ark, rescue::generate_round_constants("certificate-stark "));
+        assert_ne!(ark, rescue::ARK);
+        assert!(ark[..rescue::NUM_HASH_ROUNDS]
+            .iter()
+            .flatten()
+            .all(|constant| constant.to_repr() < BaseElement::MODULUS
+                && *constant != BaseElement::ZERO));
+        assert_eq!(
+            ark[rescue::HASH_CYCLE_LENGTH - 1],
+            [BaseElement::ZERO; rescue::STATE_WIDTH * 2]
+        );
+
+        // The default constants are not derived from a seed, and hashing with them as a constant
+        // set matches Rescue63
+        #[derive(Clone, Debug)]
+        struct DefaultConstants;
+        impl RoundConstantSet for DefaultConstants {
+            const HASH_ID: u8 = 0x80;
+            const SEED: &'static str = "certificate-stark";
+            const ARK: RoundConstants = rescue::ARK;
+        }
+        assert!(!DefaultConstants::is_derived_from_seed());
+        let nodes = [
+            Hash::new(
+                BaseElement::ONE,
+                BaseElement::ZERO,
+                BaseElement::ZERO,
+                BaseElement::ZERO,
+                BaseElement::ZERO,
+                BaseElement::ZERO,
+                BaseElement::ZERO,
+            ),
+            Hash::default(),
+        ];
+        assert_eq!(
+            SeededRescue63::<DefaultConstants>::merge(&nodes),
+            <Rescue63 as TreeHasher>::merge(&nodes)
+        );
+        assert_eq!(
+            SeededRescue63::<DefaultConstants>::get_round_constants(),
+            rescue::get_round_constants()
+        );
+
+        // Rounds applied natively satisfy the round constraints evaluated with the periodic
+        // columns of the derived constants
+        let columns = rescue::round_constants_columns(&ark);
+        let mut state = [BaseElement::ONE; rescue::STATE_WIDTH];
+        for step in 0..rescue::NUM_HASH_ROUNDS {
+            let current = state;
+            rescue::apply_round_with_constants(&mut state, step, &ark);
+            let step_ark = columns
+                .iter()
+                .map(|column| column[step])
+                .collect::<Vec<_>>();
+            let mut result = [BaseElement::ZERO; rescue::STATE_WIDTH];
+            rescue::enforce_round(&mut result, &current, &state, &step_ark, BaseElement::ONE);
+            assert_eq!(result, [BaseElement::ZERO; rescue::STATE_WIDTH]);
+        }
+    }
+
+    #[test]
+    fn rescue_known_answers() {
+        use super::{self as rescue, Hash, RescueSponge};
+        use winterfell::math::fields::f63::BaseElement;
+
+        let to_elements = |integers: &[u64]| -> Vec<BaseElement> {
+            integers.iter().map(|&i| BaseElement::new(i)).collect()
+        };
+
+        for (input, digest) in rescue::HASH_TEST_VECTORS.iter() {
+            let input = to_elements(input);
+            let digest = to_elements(digest);
+            assert_eq!(
+                &rescue::hash_elements(&input).to_elements()[..],
+                &digest[..]
+            );
+
+            let mut sponge = RescueSponge::new();
+            for chunk in input.chunks(rescue::RATE_WIDTH) {
+                sponge.absorb(chunk);
+            }
+            assert_eq!(&sponge.squeeze().to_elements()[..], &digest[..]);
+        }
+        // inputs are padded with zeros, so that the empty input hashes as a zero element
+        assert_eq!(
+            rescue::hash_elements(&[]),
+            rescue::hash_elements(&to_elements(&[0]))
+        );
+        assert_ne!(rescue::hash_elements(&[]), Hash::default());
+
+        let (input, output) = rescue::PERMUTATION_TEST_VECTOR;
+        let mut state = [BaseElement::new(0); rescue::STATE_WIDTH];
+        for (element, &integer) in state.iter_mut().zip(input.iter()) {
+            *element = BaseElement::new(integer);
+        }
+        rescue::apply_permutation(&mut state);
+        assert_eq!(&state[..], &to_elements(&output)[..]);
+    }
+
+    #[test]
+    fn rescue_generic_instances() {
+        use super::{self as rescue, RescueInstance, HASH_CYCLE_LENGTH, NUM_HASH_ROUNDS};
+        use winterfell::math::{fields::f63::BaseElement, FieldElement};
+
+        // the default instance is the default permutation
+        let instance = RescueInstance::default_instance();
+        let mut state = [BaseElement::ZERO; rescue::STATE_WIDTH];
+        for (i, element) in state.iter_mut().enumerate() {
+            *element = BaseElement::new(i as u64 + 1);
+        }
+        let mut expected_state = state;
+        rescue::apply_permutation(&mut expected_state);
+        instance.apply_permutation(&mut state);
+        assert_eq!(state, expected_state);
+        assert_eq!(
+            instance.round_constants_columns(),
+            rescue::get_round_constants()
+        );
+        for (input, _) in rescue::HASH_TEST_VECTORS.iter() {
+            let input = input
+                .iter()
+                .map(|&i| BaseElement::new(i))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                instance.hash_elements(&input),
+                rescue::hash_elements(&input).to_elements()
+            );
+        }
+
+        // a narrower instance enforces its own rounds
+        let instance = RescueInstance::<8, 4>::from_seed("nullifiers");
+        assert_eq!(instance, RescueInstance::<8, 4>::from_seed("nullifiers"));
+        assert_ne!(instance, RescueInstance::<8, 4>::from_seed("nullifiers "));
+        let columns = instance.round_constants_columns();
+        let mut current = [BaseElement::new(42); 8];
+        for step in 0..NUM_HASH_ROUNDS {
+            let mut next = current;
+            instance.apply_round(&mut next, step);
+            let ark = columns
+                .iter()
+                .map(|column| column[step % HASH_CYCLE_LENGTH])
+                .collect::<Vec<_>>();
+            let mut result = [BaseElement::ZERO; 8];
+            instance.enforce_round(&mut result, &current, &next, &ark, BaseElement::ONE);
+            assert_eq!(result, [BaseElement::ZERO; 8]);
+
+            next[0] += BaseElement::ONE;
+            instance.enforce_round(&mut result, &current, &next, &ark, BaseElement::ONE);
+            assert_ne!(result, [BaseElement::ZERO; 8]);
+            next[0] -= BaseElement::ONE;
+            current = next;
+        }
+
+        let data = (0..9).map(BaseElement::new).collect::<Vec<_>>();
+        let digest = instance.hash_elements(&data);
+        assert_ne!(digest, instance.hash_elements(&data[..8]));
+        assert_eq!(
+            instance.hash_elements(&[]),
+            instance.hash_elements(&data[..1])
+        );
+    }
+}
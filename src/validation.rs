@@ -0,0 +1,563 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::merkle::{compute_root, verify_path, verify_path_with_hasher, TreeHasher};
+use super::range::{self, MAX_RANGE_LOG, MAX_SIGNED_RANGE_LOG, RANGE_LOG};
+use super::schnorr::threshold;
+use super::utils::rescue::Rescue63;
+use super::{build_tx_message, schnorr, TransactionKind, TransactionMetadata};
+use core::fmt;
+use winterfell::math::{fields::f63::BaseElement, FieldElement, StarkField};
+
+// METADATA ERROR
+// ================================================================================================
+
+/// Errors raised when validating a `TransactionMetadata` prior to proving its state-transition
+#[derive(Debug, PartialEq)]
+pub enum MetadataError {
+    /// The transaction at the given position refers to a leaf outside of the tree
+    InvalidIndex(usize),
+    /// The transaction at the given position sends from or to the fee collector
+    FeeCollectorAccount(usize),
+    /// The transaction at the given position does not match the constraints of its kind:
+    /// transfers and refunds must have distinct sender and receiver, withdrawals, deposits,
+    /// no-ops and rekeyings must have identical ones, deposits and refunds cannot pay any fee,
+    /// no-ops and rekeyings transfer nothing, refunds transfer a negative amount and
+    /// require signed deltas, and committee transfers must be sent from the account of a
+    /// committee to a distinct receiver
+    InvalidKind(usize),
+    /// The sender's Merkle path of the transaction at the given position does not lead to
+    /// the root of the tree prior to this transaction
+    InvalidSenderPath(usize),
+    /// The receiver's Merkle path of the transaction at the given position does not lead to
+    /// the root of the tree once the sender is updated
+    InvalidReceiverPath(usize),
+    /// The fee collector's Merkle path of the transaction at the given position does not
+    /// lead to the root of the tree once the receiver is updated
+    InvalidCollectorPath(usize),
+    /// The tree root resulting from the transaction at the given position is not the one
+    /// prior to the next transaction
+    RootMismatch(usize),
+    /// The tree root resulting from the last transaction is not the final root
+    InvalidFinalRoot,
+    /// The sender of the transaction at the given position cannot pay its amount and fee
+    InsufficientBalance(usize),
+    /// The transaction at the given position involves an amount, fee or balance which
+    /// does not fit in the range-checked bits
+    OutOfRange(usize),
+    /// The signature of the transaction at the given position is invalid
+    InvalidSignature(usize),
+    /// The transaction at the given position does not continue the split transfer performed
+    /// by the previous one, or leaves it incomplete
+    InvalidSplit(usize),
+    /// The transaction at the given position is an approval which does not continue the
+    /// committee transfer approved by the previous ones, or is signed by a member whose key is
+    /// not in the committee's key tree or whose index does not follow the one of the previous
+    /// member, or is a committee transfer not followed by one approval per further member
+    /// required by its threshold
+    InvalidCommittee(usize),
+    /// The sender or receiver of the transaction at the given position is not stored at the
+    /// leaf derived from its public key
+    UnderivedIndex(usize),
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataError::InvalidIndex(index) => {
+                write!(
+                    f,
+                    "transaction {} refers to a leaf outside of the tree",
+                    index
+                )
+            }
+            MetadataError::FeeCollectorAccount(index) => {
+                write!(f, "transaction {} involves the fee collector", index)
+            }
+            MetadataError::InvalidKind(index) => {
+                write!(f, "transaction {} does not match its kind", index)
+            }
+            MetadataError::InvalidSenderPath(index) => {
+                write!(
+                    f,
+                    "sender path of transaction {} does not match its initial root",
+                    index
+                )
+            }
+            MetadataError::InvalidReceiverPath(index) => {
+                write!(
+                    f,
+                    "receiver path of transaction {} does not match the tree updated by its sender",
+                    index
+                )
+            }
+            MetadataError::InvalidCollectorPath(index) => {
+                write!(
+                    f,
+                    "fee collector path of transaction {} does not match the tree updated by its receiver",
+                    index
+                )
+            }
+            MetadataError::RootMismatch(index) => {
+                write!(
+                    f,
+                    "transaction {} does not result in the initial root of the next one",
+                    index
+                )
+            }
+            MetadataError::InvalidFinalRoot => {
+                write!(f, "transactions do not result in the final root")
+            }
+            MetadataError::InsufficientBalance(index) => {
+                write!(
+                    f,
+                    "sender of transaction {} has an insufficient balance",
+                    index
+                )
+            }
+            MetadataError::OutOfRange(index) => {
+                write!(f, "transaction {} exceeds the range-checked values", index)
+            }
+            MetadataError::InvalidSignature(index) => {
+                write!(f, "transaction {} has an invalid signature", index)
+            }
+            MetadataError::InvalidSplit(index) => {
+                write!(f, "transaction {} breaks a split transfer", index)
+            }
+            MetadataError::InvalidCommittee(index) => {
+                write!(f, "transaction {} breaks a committee transfer", index)
+            }
+            MetadataError::UnderivedIndex(index) => {
+                write!(
+                    f,
+                    "transaction {} refers to an account outside of its derived leaf",
+                    index
+                )
+            }
+        }
+    }
+}
+
+// METADATA VALIDATION
+// ================================================================================================
+
+impl TransactionMetadata {
+    /// Checks that these transactions can be proven with the default range log and a
+    /// Merkle tree hashed with Rescue63.
+    ///
+    /// This replays all transactions, and returns the first inconsistency found between
+    /// the roots, Merkle paths, account states, amounts and signatures, so that invalid
+    /// batches are rejected before spending time proving their state-transition.
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        self.validate_with_hasher::<Rescue63>(RANGE_LOG)
+    }
+
+    /// Checks that these transactions can be proven with amounts, fees and balances
+    /// range-checked over `range_log` bits, and a Merkle tree hashed with `H`.
+    ///
+    /// # Panics
+    /// Panics if `range_log` is not between 1 and `MAX_RANGE_LOG`.
+    pub fn validate_with_hasher<H: TreeHasher>(
+        &self,
+        range_log: usize,
+    ) -> Result<(), MetadataError> {
+        assert!(
+            range_log > 0 && range_log <= MAX_RANGE_LOG,
+            "range log must be between 1 and {}",
+            MAX_RANGE_LOG
+        );
+        self.validate_transactions::<H>(range_log, false)
+    }
+
+    /// Checks that these transactions can be proven with amounts range-checked as signed
+    /// integers within [-2^range_log, 2^range_log), fees and balances over `range_log` bits,
+    /// and a Merkle tree hashed with `H`.
+    ///
+    /// Only refunds may have a negative amount, which is then taken from the receiver's
+    /// balance and credited to the sender's one. Refunds must be signed by the receiver over
+    /// its own nonce, and transfer no fee.
+    ///
+    /// # Panics
+    /// Panics if `range_log` is not between 1 and `MAX_SIGNED_RANGE_LOG`.
+    pub fn validate_signed_with_hasher<H: TreeHasher>(
+        &self,
+        range_log: usize,
+    ) -> Result<(), MetadataError> {
+        assert!(
+            range_log > 0 && range_log <= MAX_SIGNED_RANGE_LOG,
+            "range log of signed deltas must be between 1 and {}",
+            MAX_SIGNED_RANGE_LOG
+        );
+        self.validate_transactions::<H>(range_log, true)
+    }
+
+    /// Checks these transactions as `validate_with_hasher`, the amounts being read as signed
+    /// integers if `signed_deltas`.
+    fn validate_transactions<H: TreeHasher>(
+        &self,
+        range_log: usize,
+        signed_deltas: bool,
+    ) -> Result<(), MetadataError> {
+        let tree_size = 1 << self.tree_depth;
+
+        for i in 0..self.num_transactions() {
+            let (s_index, r_index) = (self.s_indices[i], self.r_indices[i]);
+            let (amount, fee, kind) = (self.deltas[i], self.fees[i], self.kinds[i]);
+            let (s_old_value, r_old_value, c_old_value) = (
+                self.s_old_values[i],
+                self.r_old_values[i],
+                self.c_old_values[i],
+            );
+
+            if s_index >= tree_size || r_index >= tree_size {
+                return Err(MetadataError::InvalidIndex(i));
+            }
+            if s_index == self.fee_collector || r_index == self.fee_collector {
+                return Err(MetadataError::FeeCollectorAccount(i));
+            }
+            let kind_matches = match kind {
+                TransactionKind::Transfer => s_index != r_index,
+                TransactionKind::Withdrawal { .. } => s_index == r_index,
+                TransactionKind::Deposit => s_index == r_index && fee == BaseElement::ZERO,
+                TransactionKind::NoOp | TransactionKind::Rekey { .. } => {
+                    s_index == r_index && amount == BaseElement::ZERO && fee == BaseElement::ZERO
+                }
+                TransactionKind::Split { outputs, position } => {
+                    s_index != r_index
+                        && outputs.receiver(position) == r_index
+                        && outputs.amount(position) == amount
+                }
+                TransactionKind::Refund => {
+                    signed_deltas
+                        && s_index != r_index
+                        && range::is_negative(amount)
+                        && fee == BaseElement::ZERO
+                }
+                TransactionKind::Committee => {
+                    s_index != r_index && s_old_value.committee().is_some()
+                }
+                TransactionKind::Approval => s_index != r_index,
+            };
+            if !kind_matches {
+                return Err(MetadataError::InvalidKind(i));
+            }
+            let previous_kind = if i == 0 {
+                None
+            } else {
+                Some(self.kinds[i - 1])
+            };
+            let is_last = i + 1 == self.num_transactions();
+            if !continues_split(previous_kind, kind) || (is_last && !completes_split(kind)) {
+                return Err(MetadataError::InvalidSplit(i));
+            }
+            if !self.continues_committee(i) {
+                return Err(MetadataError::InvalidCommittee(i));
+            }
+
+            check_balances(
+                range_log,
+                kind,
+                amount,
+                fee,
+                [
+                    s_old_value.balance,
+                    r_old_value.balance,
+                    c_old_value.balance,
+                ],
+            )
+            .map_err(|err| match err {
+                BalanceError::InsufficientBalance => MetadataError::InsufficientBalance(i),
+                BalanceError::OutOfRange => MetadataError::OutOfRange(i),
+            })?;
+
+            self.replay_paths::<H>(i)?;
+
+            let message = build_tx_message(
+                &self.signer_key_elements(i),
+                &r_old_value.pub_key_elements(),
+                s_index,
+                r_index,
+                amount,
+                self.nonce_account(i).nonce,
+                fee,
+                kind,
+                self.memos[i],
+                self.subnet_id,
+                self.epoch,
+            );
+            if !schnorr::verify_signature(message, self.signatures[i]) {
+                return Err(MetadataError::InvalidSignature(i));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the transaction at position `i` is consistent with the committee
+    /// transfers around it, as enforced by the AIR program: a committee transfer is followed
+    /// by one approval per further member required by its threshold, an approval carries the
+    /// leaves, amount, fee and memo of the previous transaction, and each of them is signed by
+    /// a member of the committee, authenticated in its key tree, with an index greater than
+    /// the one of the previous member.
+    fn continues_committee(&self, i: usize) -> bool {
+        let kind = self.kinds[i];
+        let next_is_approval = matches!(self.kinds.get(i + 1), Some(TransactionKind::Approval));
+        if !kind.is_committee() {
+            return !next_is_approval;
+        }
+        let share = match &self.shares[i] {
+            Some(share) => share,
+            None => return false,
+        };
+
+        let (transfer_index, carried) = if kind.is_approval() {
+            if i == 0 || !self.kinds[i - 1].is_committee() {
+                return false;
+            }
+            let previous_index = self.shares[i - 1].as_ref().map(|share| share.index);
+            let carried = self.s_indices[i] == self.s_indices[i - 1]
+                && self.r_indices[i] == self.r_indices[i - 1]
+                && self.deltas[i] == self.deltas[i - 1]
+                && self.fees[i] == self.fees[i - 1]
+                && self.memos[i] == self.memos[i - 1]
+                && matches!(previous_index, Some(index) if index < share.index);
+            (self.committee_transfer_index(i), carried)
+        } else {
+            (i, true)
+        };
+        let (root, threshold) = match self.s_old_values[transfer_index].committee() {
+            Some(committee) => committee,
+            None => return false,
+        };
+        let num_approvals = i - transfer_index + 1;
+
+        carried
+            && share.index < 1 << self.tree_depth
+            && verify_path(
+                threshold::hash_key(&share.key),
+                share.index,
+                &share.path,
+                root,
+            )
+            && num_approvals <= threshold
+            && next_is_approval == (num_approvals < threshold)
+    }
+
+    /// Checks the Merkle paths of these transactions against their roots, in a tree hashed
+    /// with Rescue63.
+    ///
+    /// This is the batch variant of `merkle::verify_path`, allowing batch builders to reject
+    /// stale paths before proving, without validating the rest of the transactions.
+    pub fn verify_paths(&self) -> Result<(), MetadataError> {
+        self.verify_paths_with_hasher::<Rescue63>()
+    }
+
+    /// Checks the Merkle paths of these transactions against their roots, in a tree hashed
+    /// with `H`.
+    ///
+    /// As within the AIR program, the sender's path must lead to the root prior to the
+    /// transaction, the receiver's one to the root once the sender is updated, and the fee
+    /// collector's one to the root once the receiver is updated. The resulting root must be
+    /// the one prior to the next transaction, or the final root for the last one.
+    pub fn verify_paths_with_hasher<H: TreeHasher>(&self) -> Result<(), MetadataError> {
+        let tree_size = 1 << self.tree_depth;
+        for i in 0..self.num_transactions() {
+            if self.s_indices[i] >= tree_size || self.r_indices[i] >= tree_size {
+                return Err(MetadataError::InvalidIndex(i));
+            }
+            self.replay_paths::<H>(i)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the sender and receiver of every transaction are stored at the leaf
+    /// derived from their public key by `account_index`. For rekeyings, the receiver's value
+    /// holds the new key, which must hence derive the same leaf as the former one.
+    ///
+    /// State-transition proofs only enforce this addressing scheme when generated by a
+    /// `TransactionProver::with_derived_indices`, which runs this check first, and leave the
+    /// receivers of split transfers, the senders of refunds and the leaves of no-ops unbound.
+    pub fn verify_derived_indices(&self) -> Result<(), MetadataError> {
+        for i in 0..self.num_transactions() {
+            if self.s_old_values[i].derived_index(self.tree_depth) != self.s_indices[i]
+                || self.r_old_values[i].derived_index(self.tree_depth) != self.r_indices[i]
+            {
+                return Err(MetadataError::UnderivedIndex(i));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replays the sender, receiver and fee collector updates of the transaction at position
+    /// `i`, each path being checked against the tree resulting from the previous update, and
+    /// checks the resulting root against the one prior to the next transaction.
+    fn replay_paths<H: TreeHasher>(&self, i: usize) -> Result<(), MetadataError> {
+        let (s_index, r_index) = (self.s_indices[i], self.r_indices[i]);
+        let (amount, fee, kind) = (self.deltas[i], self.fees[i], self.kinds[i]);
+        let (s_old_value, r_old_value, c_old_value) = (
+            self.s_old_values[i],
+            self.r_old_values[i],
+            self.c_old_values[i],
+        );
+
+        let mut s_value = s_old_value;
+        if !kind.is_deposit() && !kind.is_noop() && !kind.is_approval() {
+            s_value.balance -= amount + fee;
+        }
+        if !kind.is_deposit() && !kind.is_noop() && !kind.is_refund() && !kind.is_approval() {
+            s_value.nonce += BaseElement::ONE;
+        }
+        if let TransactionKind::Rekey { new_key } = kind {
+            s_value.pub_key = new_key;
+        }
+        if !verify_path_with_hasher::<H>(
+            s_old_value.to_leaf::<H>(),
+            s_index,
+            &self.s_paths[i],
+            self.initial_roots[i],
+        ) {
+            return Err(MetadataError::InvalidSenderPath(i));
+        }
+        let root = compute_root::<H>(s_index, s_value.to_leaf::<H>(), &self.s_paths[i]);
+
+        let mut r_value = r_old_value;
+        if !kind.is_withdrawal() && !kind.is_approval() {
+            r_value.balance += amount;
+        }
+        if kind.is_refund() {
+            r_value.nonce += BaseElement::ONE;
+        }
+        if !verify_path_with_hasher::<H>(
+            r_old_value.to_leaf::<H>(),
+            r_index,
+            &self.r_paths[i],
+            root,
+        ) {
+            return Err(MetadataError::InvalidReceiverPath(i));
+        }
+        let root = compute_root::<H>(r_index, r_value.to_leaf::<H>(), &self.r_paths[i]);
+
+        let mut c_value = c_old_value;
+        if !kind.is_approval() {
+            c_value.balance += fee;
+        }
+        if !verify_path_with_hasher::<H>(
+            c_old_value.to_leaf::<H>(),
+            self.fee_collector,
+            &self.c_paths[i],
+            root,
+        ) {
+            return Err(MetadataError::InvalidCollectorPath(i));
+        }
+        let root = compute_root::<H>(self.fee_collector, c_value.to_leaf::<H>(), &self.c_paths[i]);
+
+        match self.initial_roots.get(i + 1) {
+            Some(next_root) if *next_root != root => Err(MetadataError::RootMismatch(i)),
+            None if root != self.final_root => Err(MetadataError::InvalidFinalRoot),
+            _ => Ok(()),
+        }
+    }
+}
+
+// BALANCE CHECKS
+// ================================================================================================
+
+/// Reasons for the amount, fee and balances of a transaction not to be provable
+#[derive(Debug, PartialEq)]
+pub(crate) enum BalanceError {
+    /// The sender cannot pay the amount and fee
+    InsufficientBalance,
+    /// The amount, the fee or an updated balance does not fit in the range-checked bits
+    OutOfRange,
+}
+
+/// Checks the `amount` and `fee` of a transaction of kind `kind` against the `balances` of
+/// its sender, receiver and fee collector prior to it, with amounts, fees and balances
+/// range-checked over `range_log` bits.
+///
+/// The amounts of refunds are negative, and are range-checked within [-2^range_log, 0), their
+/// receiver paying the amount back to the sender.
+pub(crate) fn check_balances(
+    range_log: usize,
+    kind: TransactionKind,
+    amount: BaseElement,
+    fee: BaseElement,
+    balances: [BaseElement; 3],
+) -> Result<(), BalanceError> {
+    // Balances are checked over the integers, as they would otherwise silently wrap
+    // around the field modulus
+    let bound = 1i128 << range_log;
+    let in_range =
+        |value: i128| value >= 0 && value < bound && value < BaseElement::MODULUS as i128;
+    let amount_value = if kind.is_refund() {
+        amount.to_repr() as i128 - BaseElement::MODULUS as i128
+    } else {
+        amount.to_repr() as i128
+    };
+    let fee_value = fee.to_repr() as i128;
+    let [s_balance, r_balance, c_balance] = balances.map(|balance| balance.to_repr() as i128);
+
+    let amount_in_range = if kind.is_refund() {
+        amount_value < 0 && amount_value >= -bound
+    } else {
+        in_range(amount_value)
+    };
+    if !amount_in_range || !in_range(fee_value) {
+        return Err(BalanceError::OutOfRange);
+    }
+    // Approvals carry the amount and fee of the committee transfer they approve, and leave
+    // all balances untouched
+    if kind.is_approval() {
+        return Ok(());
+    }
+    if !kind.is_deposit() && s_balance < amount_value + fee_value {
+        return Err(BalanceError::InsufficientBalance);
+    }
+    // A negative amount credits the sender, and debits the receiver
+    if (amount_value < 0 && !in_range(s_balance - amount_value - fee_value))
+        || (!kind.is_withdrawal() && !in_range(r_balance + amount_value))
+        || !in_range(c_balance + fee_value)
+    {
+        return Err(BalanceError::OutOfRange);
+    }
+
+    Ok(())
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns whether a transaction of kind `kind` can follow one of kind `previous_kind`, if
+/// any: outputs of a split transfer follow the previous output of a split transfer with the
+/// same signed amount, and any other transaction follows a complete split transfer.
+fn continues_split(previous_kind: Option<TransactionKind>, kind: TransactionKind) -> bool {
+    match (previous_kind, kind) {
+        (
+            Some(TransactionKind::Split {
+                outputs: previous_outputs,
+                position: previous_position,
+            }),
+            TransactionKind::Split { outputs, position },
+        ) if position > 0 => {
+            previous_position + 1 == position && previous_outputs.total() == outputs.total()
+        }
+        (_, TransactionKind::Split { position, .. }) if position > 0 => false,
+        (Some(previous_kind), _) => completes_split(previous_kind),
+        (None, _) => true,
+    }
+}
+
+/// Returns whether a transaction of kind `kind` leaves no amount of a split transfer unsent.
+fn completes_split(kind: TransactionKind) -> bool {
+    match kind {
+        TransactionKind::Split { outputs, position } => outputs.sent(position) == outputs.total(),
+        _ => true,
+    }
+}
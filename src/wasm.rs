@@ -0,0 +1,78 @@
+// Copyright (c) 2021-2022 Toposware, Inc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::merkle::TreeHasher;
+use super::utils::rescue::Rescue63;
+use super::{ProofProfile, PublicInputs, TransactionMetadata, TransactionProver};
+use core::fmt;
+use wasm_bindgen::prelude::*;
+use winterfell::StarkProof;
+
+/// Maximum number of transactions of a batch proven through `proveBatch`, as proving larger
+/// batches would exceed the memory usually available to a browser.
+pub const MAX_PROVING_BATCH_SIZE: usize = 16;
+
+// WASM BINDINGS
+// ================================================================================================
+
+/// Verifies a serialized state-transition proof against public inputs serialized with
+/// `PublicInputs::to_bytes`.
+///
+/// Returns an error describing why the inputs could not be read or why the proof is invalid.
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(proof: &[u8], pub_inputs: &[u8]) -> Result<(), JsValue> {
+    let proof = StarkProof::from_bytes(proof).map_err(to_js_error)?;
+    let pub_inputs = PublicInputs::from_bytes(pub_inputs).map_err(to_js_error)?;
+    if pub_inputs.hash_id != Rescue63::HASH_ID {
+        return Err(JsValue::from_str("unsupported tree hash function"));
+    }
+
+    super::verify_with_hasher::<Rescue63>(proof, pub_inputs).map_err(to_js_error)
+}
+
+/// Returns the public inputs of a state-transition proof for the provided transactions,
+/// serialized with `Serializable::to_bytes`. The public inputs are serialized with
+/// `PublicInputs::to_bytes`.
+#[wasm_bindgen(js_name = publicInputs)]
+pub fn public_inputs(tx_metadata: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let tx_metadata = TransactionMetadata::from_bytes(tx_metadata).map_err(to_js_error)?;
+    let prover = TransactionProver::new(ProofProfile::default().to_proof_options());
+
+    Ok(prover.public_inputs(&tx_metadata).to_bytes())
+}
+
+/// Proves the state-transition of the provided transactions, serialized with
+/// `Serializable::to_bytes`, with the `Balanced` proof options, and returns the serialized
+/// proof.
+///
+/// Returns an error if the batch holds more than `MAX_PROVING_BATCH_SIZE` transactions, if
+/// any of its signatures is invalid, or if the proof generation fails.
+#[wasm_bindgen(js_name = proveBatch)]
+pub fn prove_batch(tx_metadata: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let tx_metadata = TransactionMetadata::from_bytes(tx_metadata).map_err(to_js_error)?;
+    if tx_metadata.num_transactions() > MAX_PROVING_BATCH_SIZE {
+        return Err(JsValue::from_str(&format!(
+            "batch must hold at most {} transactions",
+            MAX_PROVING_BATCH_SIZE
+        )));
+    }
+    if !tx_metadata.verify_signatures() {
+        return Err(JsValue::from_str("batch contains invalid signatures"));
+    }
+    let prover = TransactionProver::new(ProofProfile::default().to_proof_options());
+
+    Ok(prover.prove(&tx_metadata).map_err(to_js_error)?.to_bytes())
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Converts an error into a JavaScript string value.
+fn to_js_error<E: fmt::Display>(err: E) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}